@@ -0,0 +1,167 @@
+//! Board support for the CanMV-K230.
+//!
+//! Names the pads wired to on-board parts and headers, and sets up the
+//! default console, so applications do not repeat pad numbers:
+//!
+//! ```ignore
+//! #![no_std]
+//! #![no_main]
+//! use canmv_k230::{Board, Clocks, Peripherals, entry};
+//! use kendryte_hal::delay::DelayNs;
+//! use kendryte_hal::gpio::StatefulOutputPin;
+//!
+//! #[entry]
+//! fn main(p: Peripherals, c: Clocks) -> ! {
+//!     let mut board = Board::take(p, c);
+//!     let mut delay = c.delay();
+//!     loop {
+//!         board.led.toggle().ok();
+//!         delay.delay_ms(500);
+//!     }
+//! }
+//! ```
+#![no_std]
+
+use kendryte_hal::gpio::{DriveStrength, Input, Output, PinState};
+use kendryte_hal::iomux::ops::Pull;
+use kendryte_hal::uart::{BlockingUart, Config};
+use kendryte_rt::soc::k230::{
+    GPIO0, GPIO1, I2C0, I2C1, I2C2, I2C3, I2C4, LSADC, PWM0, Pad, SPI0, SPI1, SPI2, UART1, UART2,
+    UART3, UART4, WDT0, WDT1,
+};
+
+pub use kendryte_rt::{Clocks, Peripherals, entry};
+
+/// Pad driving the user LED, active high.
+pub type LedPad = Pad<19>;
+/// Pad connected to the user key, pressed high.
+pub type KeyPad = Pad<20>;
+/// Pad driving the buzzer; PWM0 channel 1 output.
+pub type BuzzerPad = Pad<43>;
+/// Console UART0 transmit pad.
+pub type ConsoleTxPad = Pad<38>;
+/// Console UART0 receive pad.
+pub type ConsoleRxPad = Pad<39>;
+
+/// User LED.
+pub type Led = Output<'static, 'static>;
+/// User key.
+pub type Key = Input<'static, 'static>;
+/// Console UART on the debug USB serial port.
+pub type Console = BlockingUart<'static, 'static, 'static>;
+
+/// Pads of the Grove connector.
+///
+/// The connector carries UART3, so its signal pins are named after it; both
+/// pads can also be used as GPIO1 pins 18 and 19.
+pub struct Grove {
+    /// UART3 transmit, GPIO1 pin 18.
+    pub tx: Pad<50>,
+    /// UART3 receive, GPIO1 pin 19.
+    pub rx: Pad<51>,
+}
+
+/// GPIO0 token shared by the LED, the key and the application.
+// SAFETY: `Board::take` consumes the only other GPIO0 token in exchange.
+static GPIO0_SHARED: GPIO0 = unsafe { GPIO0::steal() };
+
+/// Peripherals of the CanMV-K230, with on-board parts configured.
+pub struct Board {
+    /// User LED, initially off.
+    pub led: Led,
+    /// User key, with pull-down.
+    pub key: Key,
+    /// Console UART0 at 115200 8N1.
+    pub console: Console,
+    /// Buzzer pad, to be turned into a PWM output.
+    pub buzzer: BuzzerPad,
+    /// Grove connector pads.
+    pub grove: Grove,
+    /// General Purpose Input/Output 0, shared with the LED and key, for
+    /// their other pins.
+    pub gpio0: &'static GPIO0,
+    /// General Purpose Input/Output 1.
+    pub gpio1: GPIO1,
+    /// Universal Asynchronous Receiver Transmitter 1.
+    pub uart1: UART1,
+    /// Universal Asynchronous Receiver Transmitter 2.
+    pub uart2: UART2,
+    /// Universal Asynchronous Receiver Transmitter 3.
+    pub uart3: UART3,
+    /// Universal Asynchronous Receiver Transmitter 4.
+    pub uart4: UART4,
+    /// Inter-Integrated Circuit 0.
+    pub i2c0: I2C0,
+    /// Inter-Integrated Circuit 1.
+    pub i2c1: I2C1,
+    /// Inter-Integrated Circuit 2.
+    pub i2c2: I2C2,
+    /// Inter-Integrated Circuit 3.
+    pub i2c3: I2C3,
+    /// Inter-Integrated Circuit 4.
+    pub i2c4: I2C4,
+    /// Low Speed Analog to Digital Converter.
+    pub lsadc: LSADC,
+    /// Serial Peripheral Interface 0.
+    pub spi0: SPI0,
+    /// Serial Peripheral Interface 1.
+    pub spi1: SPI1,
+    /// Serial Peripheral Interface 2.
+    pub spi2: SPI2,
+    /// Pulse Width Modulation 0.
+    pub pwm0: PWM0,
+    /// Watchdog Timer 0.
+    pub wdt0: WDT0,
+    /// Watchdog Timer 1.
+    pub wdt1: WDT1,
+    /// Clock configuration.
+    pub clocks: Clocks,
+}
+
+impl Board {
+    /// Takes the peripherals passed to the `#[entry]` function, configures
+    /// the on-board parts and hands out the remaining peripherals.
+    pub fn take(p: Peripherals, c: Clocks) -> Self {
+        let iomux = p.iomux;
+        // The GPIO0 token in `p` is dropped with it, leaving the shared one.
+        let gpio0 = &GPIO0_SHARED;
+        let led = Output::new(gpio0, iomux.io19, PinState::Low, DriveStrength::Medium);
+        let key = Input::new(gpio0, iomux.io20, Pull::Down);
+        let console = BlockingUart::new(
+            p.uart0,
+            Some(iomux.io38),
+            Some(iomux.io39),
+            Config::new(),
+            c,
+        );
+        Board {
+            led,
+            key,
+            console,
+            buzzer: iomux.io43,
+            grove: Grove {
+                tx: iomux.io50,
+                rx: iomux.io51,
+            },
+            gpio0,
+            gpio1: p.gpio1,
+            uart1: p.uart1,
+            uart2: p.uart2,
+            uart3: p.uart3,
+            uart4: p.uart4,
+            i2c0: p.i2c0,
+            i2c1: p.i2c1,
+            i2c2: p.i2c2,
+            i2c3: p.i2c3,
+            i2c4: p.i2c4,
+            lsadc: p.lsadc,
+            spi0: p.spi0,
+            spi1: p.spi1,
+            spi2: p.spi2,
+            pwm0: p.pwm0,
+            wdt0: p.wdt0,
+            wdt1: p.wdt1,
+            clocks: c,
+        }
+    }
+}
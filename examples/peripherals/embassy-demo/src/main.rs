@@ -0,0 +1,73 @@
+#![no_std]
+#![no_main]
+
+use embassy_time::Timer;
+use embedded_io_async::{Read, Write};
+use kendryte_hal::gpio::{DriveStrength, Output, PinState, StatefulOutputPin};
+use kendryte_hal::instance::{Instance, Steal};
+use kendryte_hal::uart::{AsyncUart, BlockingUart, Config, UartState};
+use kendryte_rt::embassy::InterruptExecutor;
+use kendryte_rt::soc::k230::UART0;
+use kendryte_rt::{Clocks, Peripherals, entry, interrupt};
+use panic_halt as _;
+
+static EXECUTOR: InterruptExecutor = InterruptExecutor::new();
+static UART0_STATE: UartState = UartState::new();
+
+#[entry]
+fn main(p: Peripherals, c: Clocks) -> ! {
+    let led = Output::new(p.gpio0, p.iomux.io19, PinState::Low, DriveStrength::Medium);
+    let serial = BlockingUart::new(
+        p.uart0,
+        Some(p.iomux.io38),
+        Some(p.iomux.io39),
+        Config::new(),
+        c,
+    );
+    let serial = AsyncUart::new(serial, &UART0_STATE);
+
+    interrupt::register_peripheral::<UART0>(on_uart0);
+    interrupt::enable_external();
+    let spawner = EXECUTOR.start();
+    spawner.spawn(echo(serial)).unwrap();
+    spawner.spawn(blink(led)).unwrap();
+    interrupt::enable();
+
+    loop {
+        kendryte_rt::idle();
+    }
+}
+
+fn on_uart0() {
+    // SAFETY: the handler only touches the interrupt enable register, which
+    // the driver expects it to.
+    let mut regs = unsafe { UART0::steal() }.inner();
+    UART0_STATE.on_interrupt(&mut regs);
+}
+
+#[interrupt]
+fn MachineSoft() {
+    unsafe { EXECUTOR.on_interrupt() };
+}
+
+#[embassy_executor::task]
+async fn echo(mut serial: AsyncUart<'static, 'static, 'static>) {
+    serial
+        .write_all(b"Type something, it comes back\r\n")
+        .await
+        .ok();
+    let mut buf = [0; 32];
+    loop {
+        if let Ok(count) = serial.read(&mut buf).await {
+            serial.write_all(&buf[..count]).await.ok();
+        }
+    }
+}
+
+#[embassy_executor::task]
+async fn blink(mut led: Output<'static, 'static>) {
+    loop {
+        led.toggle().ok();
+        Timer::after_millis(500).await;
+    }
+}
@@ -0,0 +1,50 @@
+#![no_std]
+#![no_main]
+
+use embedded_io::Write;
+use kendryte_hal::delay::DelayNs;
+use kendryte_hal::gpio::{DriveStrength, Output, PinState, StatefulOutputPin};
+use kendryte_hal::soc::TIMER_FREQUENCY;
+use kendryte_hal::time::now;
+use kendryte_hal::uart::*;
+use kendryte_rt::{Clocks, Peripherals, entry};
+use panic_halt as _;
+
+const CYCLES: u64 = 1_000_000;
+
+/// Full high-low cycles per second for `ticks` machine timer ticks.
+fn frequency(ticks: u64) -> u64 {
+    CYCLES * TIMER_FREQUENCY as u64 / ticks.max(1)
+}
+
+#[entry]
+fn main(p: Peripherals, c: Clocks) -> ! {
+    let mut serial = BlockingUart::new(
+        p.uart0,
+        Some(p.iomux.io38),
+        Some(p.iomux.io39),
+        Config::new(),
+        c,
+    );
+    let mut delay = c.delay();
+    let mut pin = Output::new(p.gpio0, p.iomux.io19, PinState::Low, DriveStrength::Maximum);
+    loop {
+        let mut fast_pin = pin.fast();
+        let start = now();
+        for _ in 0..CYCLES {
+            fast_pin.set_high();
+            fast_pin.set_low();
+        }
+        let fast = now() - start;
+
+        let start = now();
+        for _ in 0..2 * CYCLES {
+            pin.toggle().ok();
+        }
+        let toggle = now() - start;
+
+        writeln!(serial, "FastOutput: {} Hz", frequency(fast)).ok();
+        writeln!(serial, "toggle: {} Hz", frequency(toggle)).ok();
+        delay.delay_ms(1000);
+    }
+}
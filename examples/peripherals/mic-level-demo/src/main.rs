@@ -0,0 +1,65 @@
+#![no_std]
+#![no_main]
+
+use embedded_io::Write;
+use kendryte_hal::lsadc::{ChannelSelect, FrameQueue, Microphone};
+use kendryte_hal::pwm::Pwm;
+use kendryte_hal::uart::{BlockingUart, Config};
+use kendryte_rt::{Clocks, Peripherals, entry};
+use panic_halt as _;
+
+/// PWM clock the timer period is derived from.
+const PWM_CLK_HZ: u32 = 100_000_000;
+const SAMPLE_RATE: u32 = 16_000;
+/// 32 ms of audio per frame.
+const FRAME_LEN: usize = 512;
+
+static FRAMES: FrameQueue<FRAME_LEN, 4> = FrameQueue::new();
+
+#[entry]
+fn main(p: Peripherals, c: Clocks) -> ! {
+    // One short line per frame fits in the empty TX FIFO, so printing does
+    // not wait for the UART and hold up the sampling loop.
+    let config = Config::new().set_fifo(true);
+    let mut serial = BlockingUart::new(p.uart0, Some(p.iomux.io38), Some(p.iomux.io39), config, c);
+    let mut mic = Microphone::<FRAME_LEN>::new(p.lsadc, ChannelSelect::AdcIn0).unwrap();
+    let mut timer = Pwm::new(p.pwm0).into_timer(0, (PWM_CLK_HZ / SAMPLE_RATE - 1) as u16);
+    let (mut producer, mut frames) = FRAMES.split().unwrap();
+    timer.start();
+    writeln!(
+        serial,
+        "mic-level-demo: {SAMPLE_RATE} Hz, {FRAME_LEN} samples per frame"
+    )
+    .ok();
+
+    loop {
+        // Polling keeps the demo free of interrupt setup; a trigger must
+        // come once per timer period, or the sample rate drops.
+        if timer.wait().is_ok() {
+            mic.trigger(&mut producer);
+        }
+        if let Some(frame) = frames.pop() {
+            let sum: u64 = frame
+                .samples
+                .iter()
+                .map(|&s| (s as i64 * s as i64) as u64)
+                .sum();
+            let rms = isqrt(sum / FRAME_LEN as u64);
+            writeln!(serial, "rms {rms:5} missed {}", frame.missed).ok();
+        }
+    }
+}
+
+/// Integer square root by Newton's method.
+fn isqrt(n: u64) -> u64 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n;
+    let mut y = (x + 1) / 2;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
@@ -4,18 +4,15 @@
 use core::arch::{asm, naked_asm};
 use core::sync::atomic::{AtomicU32, Ordering};
 use embedded_io::Write;
+use kendryte_hal::delay::DelayNs;
 use kendryte_hal::uart::*;
 use kendryte_rt::{Clocks, Peripherals, entry};
 use panic_halt as _;
-use riscv::asm::delay; // for slowing down print rate
 
 // Hart1 reset vector & control register (simplified single-attempt bring-up).
 const CPU1_RSTVEC: usize = 0x9110_2104; // cpu1_hart_rstvec
 const CPU_CTRL: usize = 0x9110_100c; // control: done/reset bits
 
-// Approximate core frequency hint for debug delays (adjust to actual clock if known).
-// Used only for coarse 5s startup delay observation.
-const APPROX_CYCLES_PER_SEC: u32 = 50_000_000; // adjust if output cadence is off
 const STARTUP_DELAY_SECS: u32 = 5; // user requested ~5s observation window
 
 // Provide a small separate stack for the 2nd hart.
@@ -102,6 +99,7 @@ fn main(p: Peripherals, c: Clocks) -> ! {
         Config::new(),
         c,
     );
+    let mut delay = c.delay();
     writeln!(uart0, "=== multicore-demo (K230) ===").ok();
     writeln!(uart0, "hart0: starting bring-up sequence").ok();
     // Pre-launch diagnostics: read current mailbox state (may be uninitialized random value).
@@ -121,7 +119,7 @@ fn main(p: Peripherals, c: Clocks) -> ! {
     .ok();
     const BAR_WIDTH: usize = 20;
     for sec in 1..=STARTUP_DELAY_SECS {
-        riscv::asm::delay(APPROX_CYCLES_PER_SEC);
+        delay.delay_ms(1000);
         let filled = (sec as usize * BAR_WIDTH + (STARTUP_DELAY_SECS as usize - 1))
             / STARTUP_DELAY_SECS as usize;
         let mut bar = [b'.'; BAR_WIDTH];
@@ -143,11 +141,7 @@ fn main(p: Peripherals, c: Clocks) -> ! {
     let entry = hart1_reset_trap as usize;
     writeln!(uart0, "launching hart1 rstvec=0x{:08x}", entry as u32).ok();
     start_hart1(entry);
-    for _ in 0..300_000 {
-        unsafe {
-            asm!("nop");
-        }
-    }
+    delay.delay_ms(10);
     let first_flag = HART1_FLAG.load(Ordering::Acquire);
     writeln!(uart0, "hart1 initial flag=0x{:08x}", first_flag).ok();
 
@@ -170,8 +164,8 @@ fn main(p: Peripherals, c: Clocks) -> ! {
             .ok();
             last_flag = flag_now;
             last_ticks = ticks_now;
-            // Insert a busy wait delay (~tunable) to further slow down output.
-            delay(5_000_000);
+            // Insert a busy wait delay to further slow down output.
+            delay.delay_ms(50);
         }
         unsafe {
             asm!("nop");
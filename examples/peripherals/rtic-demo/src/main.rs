@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+use panic_halt as _;
+
+#[rtic::app(device = kendryte_rt::rtic, backend = H0, dispatchers = [SoftLow])]
+mod app {
+    use embedded_io::{Read, Write};
+    use kendryte_hal::gpio::{DriveStrength, Output, OutputPin, PinState, StatefulOutputPin};
+    use kendryte_hal::uart::{BlockingUart, Config};
+    use kendryte_rt::rtic::{Interrupt, Mtime, Priority, unmask};
+    use kendryte_rt::{Clocks, Peripherals};
+    use rtic_time::Monotonic;
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {
+        serial: BlockingUart<'static, 'static, 'static>,
+        led: Output<'static, 'static>,
+    }
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        let p = Peripherals::take();
+        let c = Clocks;
+        Mtime::start();
+
+        let led = Output::new(p.gpio0, p.iomux.io19, PinState::Low, DriveStrength::Medium);
+        let mut serial = BlockingUart::new(
+            p.uart0,
+            Some(p.iomux.io38),
+            Some(p.iomux.io39),
+            Config::new(),
+            c,
+        );
+        serial.listen_rx();
+        unmask(Interrupt::UART0, Priority::P2);
+        writeln!(serial, "Blinking the LED; type to echo").ok();
+        blink::spawn().ok();
+
+        (Shared {}, Local { serial, led })
+    }
+
+    #[task(priority = 1, local = [led])]
+    async fn blink(cx: blink::Context) {
+        loop {
+            cx.local.led.toggle().ok();
+            Mtime::delay(<Mtime as Monotonic>::Duration::millis(500)).await;
+        }
+    }
+
+    #[task(binds = UART0, priority = 2, local = [serial])]
+    fn on_uart0(cx: on_uart0::Context) {
+        let mut buf = [0; 16];
+        let count = cx.local.serial.read(&mut buf).unwrap_or(0);
+        cx.local.serial.write_all(&buf[..count]).ok();
+    }
+}
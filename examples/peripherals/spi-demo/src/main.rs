@@ -2,6 +2,7 @@
 #![no_main]
 use embedded_hal::spi::{SpiBus, MODE_0};
 use embedded_io::Write as _;
+use kendryte_hal::delay::DelayNs;
 use kendryte_hal::spi::{Config as SpiConfig, Spi};
 use kendryte_hal::uart::{BlockingUart, Config as UartConfig};
 use kendryte_rt::{entry, Clocks, Peripherals};
@@ -27,6 +28,7 @@ fn main(p: Peripherals, c: Clocks) -> ! {
             mode: MODE_0,
             data_bits: 8,
             ss_index: 0,
+            ..Default::default()
         },
         c,
     );
@@ -38,7 +40,8 @@ fn main(p: Peripherals, c: Clocks) -> ! {
     let id = &buf[1..4];
     writeln!(uart, "JEDEC ID: {:02X} {:02X} {:02X}", id[0], id[1], id[2]).ok();
 
+    let mut delay = c.delay();
     loop {
-        riscv::asm::delay(50_000_000);
+        delay.delay_ms(1000);
     }
 }
@@ -0,0 +1,70 @@
+#![no_std]
+#![no_main]
+use embedded_hal::spi::MODE_0;
+use embedded_io::Write as _;
+use kendryte_hal::delay::DelayNs;
+use kendryte_hal::spi::{Config as SpiConfig, Loopback, Spi};
+use kendryte_hal::uart::{BlockingUart, Config as UartConfig};
+use kendryte_rt::{entry, Clocks, Peripherals};
+use panic_halt as _;
+
+#[entry]
+fn main(p: Peripherals, c: Clocks) -> ! {
+    // UART for printing
+    let mut uart = BlockingUart::new(
+        p.uart3,
+        Some(p.iomux.io50),
+        Some(p.iomux.io51),
+        UartConfig::new(),
+        c,
+    );
+
+    // Pad numbers are subject to board routing; adjust as needed.
+    let mut spi = Spi::with_pads(
+        p.spi0,
+        (p.iomux.io40, p.iomux.io41, p.iomux.io39, p.iomux.io38), // SCLK, MOSI, MISO, CS
+        SpiConfig {
+            frequency: 1_000_000,
+            mode: MODE_0,
+            data_bits: 8,
+            ..Default::default()
+        },
+        c,
+    );
+
+    writeln!(
+        uart,
+        "SPI0 loopback, highest clock {} Hz",
+        Spi::max_achievable_frequency::<0>(c)
+    )
+    .ok();
+
+    let mut buf = [0u8; 4096];
+    for (name, loopback) in [
+        ("internal", Loopback::Internal),
+        ("external", Loopback::External),
+    ] {
+        writeln!(uart, "{name}").ok();
+        writeln!(uart, "   frequency  errors   throughput").ok();
+        let best = spi.loopback_sweep(loopback, &mut buf, |report| {
+            let kbps = report.throughput().unwrap_or(0) / 1000;
+            writeln!(
+                uart,
+                "{:>9} Hz {:>6} {:>6} kbit/s",
+                report.frequency, report.errors, kbps
+            )
+            .ok();
+        });
+        match best {
+            Ok(Some(frequency)) => writeln!(uart, "{name}: passes up to {frequency} Hz"),
+            Ok(None) => writeln!(uart, "{name}: fails at every rate"),
+            Err(e) => writeln!(uart, "{name}: {e:?}"),
+        }
+        .ok();
+    }
+
+    let mut delay = c.delay();
+    loop {
+        delay.delay_ms(1000);
+    }
+}
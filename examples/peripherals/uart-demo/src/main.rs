@@ -1,6 +1,7 @@
 #![no_std]
 #![no_main]
 use embedded_io::Write;
+use kendryte_hal::delay::DelayNs;
 use kendryte_hal::uart::*;
 use kendryte_rt::{Clocks, Peripherals, entry};
 use panic_halt as _;
@@ -21,9 +22,10 @@ fn main(p: Peripherals, c: Clocks) -> ! {
         Config::new(),
         c,
     );
+    let mut delay = c.delay();
     loop {
         writeln!(serial0, "Welcome to use kendryte-hal🦀!").ok();
         writeln!(serial3, "Welcome to use kendryte-hal🦀!").ok();
-        riscv::asm::delay(10_000_000);
+        delay.delay_ms(1000);
     }
 }
@@ -0,0 +1,439 @@
+//! Event and crash log kept in NOR flash across resets.
+//!
+//! [`Blackbox`] appends fixed-size records to a reserved flash region used
+//! as a ring of erase sectors: once the region is full, the sector holding
+//! the oldest records is erased for the next ones. Records carry a sequence
+//! number and a CRC, so the log is found again after a reset and a record
+//! torn by power loss is skipped. With the `panic-blackbox` feature of
+//! kendryte-rt the panic message is added as a [`Kind::Panic`] record, and
+//! the log can be printed over a UART at the next boot:
+//!
+//! ```ignore
+//! let mut log = Blackbox::open(flash, blackbox::Region { offset: 0x7E_0000, size: 0x1_0000 })?;
+//! log.dump(&mut uart)?;
+//! log.log(Kind::Boot, b"v1.2.0")?;
+//! ```
+//!
+//! Record layout, little endian, [`RECORD_LEN`] bytes:
+//!
+//! | Offset | Size | Field                                              |
+//! |--------|------|----------------------------------------------------|
+//! | 0      | 4    | sequence number                                    |
+//! | 4      | 8    | machine timer value when the record was written    |
+//! | 12     | 1    | kind, see [`Kind`]                                 |
+//! | 13     | 1    | payload length                                     |
+//! | 14     | 2    | reserved, `0xFF`                                   |
+//! | 16     | 44   | payload, padded with `0xFF`                        |
+//! | 60     | 4    | CRC-32 of bytes 0..60                              |
+
+use crate::crc::CRC32_IEEE;
+use crate::time::now;
+use core::fmt;
+use embedded_io::Error as _;
+use embedded_storage::nor_flash::NorFlash;
+
+/// Length of a record in bytes.
+pub const RECORD_LEN: usize = 64;
+/// Longest payload, in bytes; longer payloads are truncated.
+pub const MAX_PAYLOAD_LEN: usize = 44;
+
+/// Offset of the payload in a record.
+const PAYLOAD_OFFSET: usize = 16;
+/// Offset of the CRC in a record.
+const CRC_OFFSET: usize = PAYLOAD_OFFSET + MAX_PAYLOAD_LEN;
+
+/// Flash region reserved for the log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    /// Flash offset of the region, a multiple of the erase size.
+    pub offset: u32,
+    /// Size of the region, at least two erase sectors.
+    pub size: u32,
+}
+
+/// What a record is about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// Firmware start, usually with the version as payload.
+    Boot,
+    /// Something worth keeping happened.
+    Event,
+    /// Something unexpected happened but was handled.
+    Warning,
+    /// An operation failed.
+    Error,
+    /// The panic message.
+    Panic,
+    /// Application defined kind, from 16 to 254; lower values are reserved.
+    User(u8),
+}
+
+impl Kind {
+    const fn to_u8(self) -> u8 {
+        match self {
+            Kind::Boot => 0,
+            Kind::Event => 1,
+            Kind::Warning => 2,
+            Kind::Error => 3,
+            Kind::Panic => 4,
+            Kind::User(kind) => kind,
+        }
+    }
+
+    const fn from_u8(kind: u8) -> Self {
+        match kind {
+            0 => Kind::Boot,
+            1 => Kind::Event,
+            2 => Kind::Warning,
+            3 => Kind::Error,
+            4 => Kind::Panic,
+            kind => Kind::User(kind),
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::Boot => f.write_str("BOOT"),
+            Kind::Event => f.write_str("EVENT"),
+            Kind::Warning => f.write_str("WARN"),
+            Kind::Error => f.write_str("ERROR"),
+            Kind::Panic => f.write_str("PANIC"),
+            Kind::User(kind) => write!(f, "USER{kind}"),
+        }
+    }
+}
+
+/// Decoded log record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Record {
+    /// Sequence number; higher is newer.
+    pub sequence: u32,
+    /// Machine timer value when the record was written. The timer restarts
+    /// at every reset, so only records of the same boot compare.
+    pub timestamp: u64,
+    /// Kind of the record.
+    pub kind: Kind,
+    len: u8,
+    payload: [u8; MAX_PAYLOAD_LEN],
+}
+
+impl Record {
+    /// Returns the payload.
+    #[inline]
+    pub fn payload(&self) -> &[u8] {
+        &self.payload[..self.len as usize]
+    }
+
+    /// Decodes a record, returning `None` for erased or damaged flash.
+    pub fn decode(bytes: &[u8; RECORD_LEN]) -> Option<Self> {
+        let crc = u32::from_le_bytes(bytes[CRC_OFFSET..].try_into().unwrap());
+        let len = bytes[13];
+        if crc != CRC32_IEEE.checksum(&bytes[..CRC_OFFSET]) || len as usize > MAX_PAYLOAD_LEN {
+            return None;
+        }
+        Some(Self {
+            sequence: u32::from_le_bytes(bytes[0..4].try_into().unwrap()),
+            timestamp: u64::from_le_bytes(bytes[4..12].try_into().unwrap()),
+            kind: Kind::from_u8(bytes[12]),
+            len,
+            payload: bytes[PAYLOAD_OFFSET..CRC_OFFSET].try_into().unwrap(),
+        })
+    }
+
+    /// Encodes the record.
+    pub fn encode(&self) -> [u8; RECORD_LEN] {
+        let mut bytes = [0xFF; RECORD_LEN];
+        bytes[0..4].copy_from_slice(&self.sequence.to_le_bytes());
+        bytes[4..12].copy_from_slice(&self.timestamp.to_le_bytes());
+        bytes[12] = self.kind.to_u8();
+        bytes[13] = self.len;
+        bytes[PAYLOAD_OFFSET..PAYLOAD_OFFSET + self.len as usize].copy_from_slice(self.payload());
+        let crc = CRC32_IEEE.checksum(&bytes[..CRC_OFFSET]);
+        bytes[CRC_OFFSET..].copy_from_slice(&crc.to_le_bytes());
+        bytes
+    }
+}
+
+impl fmt::Display for Record {
+    /// One line: sequence number, timestamp, kind, and the payload as text,
+    /// or in hex if it is not UTF-8.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{} @{} {}: ", self.sequence, self.timestamp, self.kind)?;
+        match core::str::from_utf8(self.payload()) {
+            Ok(text) => f.write_str(text),
+            Err(_) => self.payload().iter().try_for_each(|b| write!(f, "{b:02x}")),
+        }
+    }
+}
+
+/// Log error types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlackboxError<E> {
+    /// Underlying flash error.
+    Flash(E),
+    /// The writer passed to [`Blackbox::dump`] failed.
+    Output(embedded_io::ErrorKind),
+}
+
+/// Object-safe view of a [`Blackbox`], so that runtime code such as a panic
+/// handler can log without knowing the flash type.
+pub trait Log {
+    /// Appends a record, ignoring flash errors.
+    fn log(&mut self, kind: Kind, payload: &[u8]);
+
+    /// Appends a record with formatted text, truncated to
+    /// [`MAX_PAYLOAD_LEN`] bytes, ignoring flash errors.
+    fn log_fmt(&mut self, kind: Kind, args: fmt::Arguments);
+}
+
+/// Record log in a flash region.
+pub struct Blackbox<F> {
+    flash: F,
+    region: Region,
+    /// Offset of the next slot to write, relative to the region.
+    next: u32,
+    sequence: u32,
+}
+
+impl<F: NorFlash> Blackbox<F> {
+    /// Opens the log, continuing after the newest record in the region.
+    pub fn open(flash: F, region: Region) -> Result<Self, BlackboxError<F::Error>> {
+        assert!(
+            RECORD_LEN.is_multiple_of(F::WRITE_SIZE) && F::ERASE_SIZE.is_multiple_of(RECORD_LEN),
+            "flash write and erase sizes must fit whole records"
+        );
+        assert!(
+            region.size as usize >= 2 * F::ERASE_SIZE,
+            "the log needs at least two erase sectors"
+        );
+        let mut log = Self {
+            flash,
+            region,
+            next: 0,
+            sequence: 0,
+        };
+        let mut newest: Option<(u32, u32)> = None;
+        for slot in (0..region.size).step_by(RECORD_LEN) {
+            if let Some(record) = log.read_slot(slot)?
+                && newest.is_none_or(|(sequence, _)| record.sequence > sequence)
+            {
+                newest = Some((record.sequence, slot));
+            }
+        }
+        if let Some((sequence, slot)) = newest {
+            log.sequence = sequence.wrapping_add(1);
+            log.next = (slot + RECORD_LEN as u32) % region.size;
+        }
+        Ok(log)
+    }
+
+    /// Release the underlying flash.
+    #[inline]
+    pub fn free(self) -> F {
+        self.flash
+    }
+
+    /// Appends a record, truncating the payload to [`MAX_PAYLOAD_LEN`].
+    pub fn log(&mut self, kind: Kind, payload: &[u8]) -> Result<(), BlackboxError<F::Error>> {
+        let len = payload.len().min(MAX_PAYLOAD_LEN);
+        let mut record = Record {
+            sequence: self.sequence,
+            timestamp: now(),
+            kind,
+            len: len as u8,
+            payload: [0xFF; MAX_PAYLOAD_LEN],
+        };
+        record.payload[..len].copy_from_slice(&payload[..len]);
+        self.append(&record.encode())
+    }
+
+    /// Appends a record with formatted text, truncated to
+    /// [`MAX_PAYLOAD_LEN`] bytes.
+    pub fn log_fmt(
+        &mut self,
+        kind: Kind,
+        args: fmt::Arguments,
+    ) -> Result<(), BlackboxError<F::Error>> {
+        struct Truncate {
+            buf: [u8; MAX_PAYLOAD_LEN],
+            len: usize,
+        }
+
+        impl fmt::Write for Truncate {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                let n = s.len().min(MAX_PAYLOAD_LEN - self.len);
+                self.buf[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+                self.len += n;
+                Ok(())
+            }
+        }
+
+        let mut text = Truncate {
+            buf: [0; MAX_PAYLOAD_LEN],
+            len: 0,
+        };
+        let _ = fmt::Write::write_fmt(&mut text, args);
+        self.log(kind, &text.buf[..text.len])
+    }
+
+    /// Calls `f` on every record, oldest first.
+    pub fn for_each(&mut self, mut f: impl FnMut(&Record)) -> Result<(), BlackboxError<F::Error>> {
+        let mut slot = self.next;
+        loop {
+            if let Some(record) = self.read_slot(slot)? {
+                f(&record);
+            }
+            slot = (slot + RECORD_LEN as u32) % self.region.size;
+            if slot == self.next {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Writes every record to `w`, oldest first, one line each.
+    pub fn dump<W: embedded_io::Write>(
+        &mut self,
+        w: &mut W,
+    ) -> Result<(), BlackboxError<F::Error>> {
+        let mut result = Ok(());
+        self.for_each(|record| {
+            if result.is_ok() {
+                result = write!(w, "{record}\r\n");
+            }
+        })?;
+        result.map_err(|error| match error {
+            embedded_io::WriteFmtError::Other(error) => BlackboxError::Output(error.kind()),
+            _ => BlackboxError::Output(embedded_io::ErrorKind::Other),
+        })
+    }
+
+    /// Erases the whole region.
+    pub fn clear(&mut self) -> Result<(), BlackboxError<F::Error>> {
+        let Region { offset, size } = self.region;
+        self.flash
+            .erase(offset, offset + size)
+            .map_err(BlackboxError::Flash)?;
+        self.next = 0;
+        Ok(())
+    }
+
+    /// Writes a record to the next free slot, erasing the sector of the
+    /// oldest records when reaching it.
+    fn append(&mut self, record: &[u8; RECORD_LEN]) -> Result<(), BlackboxError<F::Error>> {
+        let sector = F::ERASE_SIZE as u32;
+        loop {
+            let address = self.region.offset + self.next;
+            if self.next.is_multiple_of(sector) {
+                self.flash
+                    .erase(address, address + sector)
+                    .map_err(BlackboxError::Flash)?;
+            } else if !self.is_erased(address)? {
+                // A record torn by power loss; leave it behind.
+                self.next = (self.next + RECORD_LEN as u32) % self.region.size;
+                continue;
+            }
+            self.flash
+                .write(address, record)
+                .map_err(BlackboxError::Flash)?;
+            self.next = (self.next + RECORD_LEN as u32) % self.region.size;
+            self.sequence = self.sequence.wrapping_add(1);
+            return Ok(());
+        }
+    }
+
+    fn read_slot(&mut self, slot: u32) -> Result<Option<Record>, BlackboxError<F::Error>> {
+        let mut bytes = [0; RECORD_LEN];
+        self.flash
+            .read(self.region.offset + slot, &mut bytes)
+            .map_err(BlackboxError::Flash)?;
+        Ok(Record::decode(&bytes))
+    }
+
+    fn is_erased(&mut self, address: u32) -> Result<bool, BlackboxError<F::Error>> {
+        let mut bytes = [0; RECORD_LEN];
+        self.flash
+            .read(address, &mut bytes)
+            .map_err(BlackboxError::Flash)?;
+        Ok(bytes.iter().all(|&b| b == 0xFF))
+    }
+}
+
+impl<F: NorFlash> Log for Blackbox<F> {
+    #[inline]
+    fn log(&mut self, kind: Kind, payload: &[u8]) {
+        let _ = Blackbox::log(self, kind, payload);
+    }
+
+    #[inline]
+    fn log_fmt(&mut self, kind: Kind, args: fmt::Arguments) {
+        let _ = Blackbox::log_fmt(self, kind, args);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::mock::{FLASH_SECTOR as SECTOR, RamFlash};
+    use std::string::String;
+    use std::vec::Vec;
+
+    const REGION: Region = Region {
+        offset: SECTOR as u32,
+        size: 2 * SECTOR as u32,
+    };
+
+    fn sequences(log: &mut Blackbox<RamFlash>) -> Vec<u32> {
+        let mut sequences = Vec::new();
+        log.for_each(|record| sequences.push(record.sequence))
+            .unwrap();
+        sequences
+    }
+
+    #[test]
+    fn record_round_trip() {
+        let mut log = Blackbox::open(RamFlash::new(4, 0), REGION).unwrap();
+        log.log(Kind::Boot, b"v1").unwrap();
+        log.log_fmt(Kind::Panic, format_args!("{:>50}", "x"))
+            .unwrap();
+        log.log(Kind::User(0x20), &[0xC0, 0xFF]).unwrap();
+
+        let mut records = Vec::new();
+        log.for_each(|record| records.push(*record)).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].kind, Kind::Boot);
+        assert_eq!(records[0].payload(), b"v1");
+        assert_eq!(records[1].payload().len(), MAX_PAYLOAD_LEN);
+        assert_eq!(Record::decode(&records[2].encode()), Some(records[2]));
+
+        let mut out = String::new();
+        for record in &records {
+            out += &std::format!("{record}\n");
+        }
+        assert!(out.starts_with("#0 @0 BOOT: v1\n#1 @0 PANIC:    "));
+        assert!(out.ends_with("#2 @0 USER32: c0ff\n"));
+    }
+
+    #[test]
+    fn wraps_around_sectors() {
+        let mut log = Blackbox::open(RamFlash::new(4, 0xFF), REGION).unwrap();
+        for i in 0..10_u32 {
+            log.log(Kind::Event, &i.to_le_bytes()).unwrap();
+        }
+        // Eight slots; writing the ninth record erased the first sector.
+        assert_eq!(sequences(&mut log), [4, 5, 6, 7, 8, 9]);
+
+        // Reopening continues after the newest record, skipping a torn one.
+        let mut flash = log.free();
+        let torn = REGION.offset as usize + 2 * RECORD_LEN;
+        flash.data[torn] = 0;
+        let mut log = Blackbox::open(flash, REGION).unwrap();
+        log.log(Kind::Event, b"after reset").unwrap();
+        assert_eq!(sequences(&mut log), [4, 5, 6, 7, 8, 9, 10]);
+        log.clear().unwrap();
+        assert_eq!(sequences(&mut log), []);
+    }
+}
@@ -1,11 +1,304 @@
+//! Clock tree frequencies, clock output and clock measurement.
+//!
+//! This crate does not program the dedicated clock output pads some of the
+//! chips have, such as the K230 camera sensor clocks. Instead, a
+//! [`ClockOutput`] divides the PWM clock onto the pads of a PWM block's
+//! channels, where a scope can check it, and [`measure_pwm_clock`] times
+//! the PWM counter against the machine timer to read the clock back without
+//! one.
+//!
+//! The CPU clock can be changed at runtime. Code that reprograms it records
+//! the new frequency with [`Clocks::set_cpu_frequency`], or measures it with
+//! [`Clocks::calibrate_cpu`], so cycle based delays keep their length.
+
+use crate::delay::{McycleDelay, cycles};
+use crate::pwm::{Enable, Pwm, PwmError};
+use crate::soc::TIMER_FREQUENCY;
+use crate::time::now;
+use core::sync::atomic::{AtomicU32, Ordering};
 use embedded_time::rate::{Extensions, Hertz};
 
+crate::trace::traced_registers!($ "pwm", crate::pwm::RegisterBlock);
+
+/// Maximum polling iterations of a measurement, bounding it where the
+/// machine timer does not advance.
+const MAX_ITERATIONS: u32 = 10_000_000;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Clocks;
 
+/// A clock feeding a peripheral instance.
+///
+/// Runtime crates attach these to peripheral tokens through
+/// [`PeripheralInfo::CLOCK`](crate::instance::PeripheralInfo::CLOCK), so a
+/// driver can look up its input clock without knowing the instance number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockId {
+    /// Serial clock of UART `n`.
+    UartSclk(u8),
+    /// Serial clock of I2C `n`.
+    I2cSclk(u8),
+    /// Serial clock of SPI `n`.
+    SpiSclk(u8),
+    /// CPU core clock, which drives the `mcycle` counter.
+    Cpu,
+}
+
+/// Current CPU clock frequency in Hz.
+static CPU_FREQUENCY: AtomicU32 = AtomicU32::new(crate::soc::CPU_FREQUENCY);
+
 impl Clocks {
     pub fn uart_sclk<const N: usize>(&self) -> Hertz {
-        assert!(N <= 4, "N must be less than or equal to 4");
-        50_000_000.Hz()
+        self.frequency(ClockId::UartSclk(N as u8))
+    }
+
+    pub fn i2c_sclk<const N: usize>(&self) -> Hertz {
+        self.frequency(ClockId::I2cSclk(N as u8))
+    }
+
+    /// Frequency of `clock`.
+    pub fn frequency(&self, clock: ClockId) -> Hertz {
+        match clock {
+            ClockId::UartSclk(n) => {
+                assert!(n <= 4, "N must be less than or equal to 4");
+                crate::soc::UART_SCLK_FREQUENCY.Hz()
+            }
+            ClockId::I2cSclk(n) => {
+                assert!(n <= 4, "N must be less than or equal to 4");
+                crate::soc::I2C_SCLK_FREQUENCY.Hz()
+            }
+            ClockId::SpiSclk(n) => {
+                assert!(n <= 3, "N must be less than or equal to 3");
+                crate::soc::SPI_SCLK_FREQUENCY.Hz()
+            }
+            ClockId::Cpu => CPU_FREQUENCY.load(Ordering::Relaxed).Hz(),
+        }
+    }
+
+    /// Frequency of the CPU clock.
+    ///
+    /// Starts at the frequency the boot ROM leaves the core at, and follows
+    /// [`set_cpu_frequency`](Self::set_cpu_frequency) and
+    /// [`calibrate_cpu`](Self::calibrate_cpu).
+    #[inline]
+    pub fn cpu(&self) -> Hertz {
+        self.frequency(ClockId::Cpu)
+    }
+
+    /// Record a new CPU clock frequency.
+    ///
+    /// Call this after reprogramming the CPU PLL or divider; delays created
+    /// with [`delay`](Self::delay) pick up the new value on their next call.
+    #[inline]
+    pub fn set_cpu_frequency(&self, frequency: Hertz) {
+        CPU_FREQUENCY.store(frequency.0, Ordering::Relaxed);
+    }
+
+    /// Measure the CPU clock against the machine timer and record it.
+    ///
+    /// Counts `mcycle` for `gate_us` microseconds of machine timer time. The
+    /// error is about one timer tick per gate time, so a gate of 1000 µs
+    /// gives the frequency to within a few hundred parts per million.
+    pub fn calibrate_cpu(&self, gate_us: u32) -> Hertz {
+        let gate = (gate_us as u64 * TIMER_FREQUENCY as u64 / 1_000_000).max(1);
+        let start = now();
+        let start_cycles = cycles();
+        let mut elapsed = 0;
+        while elapsed < gate {
+            elapsed = now().wrapping_sub(start);
+        }
+        let counted = cycles().wrapping_sub(start_cycles);
+        let hz = counted * TIMER_FREQUENCY as u64 / elapsed;
+        let frequency = (hz.min(u32::MAX as u64) as u32).Hz();
+        self.set_cpu_frequency(frequency);
+        frequency
+    }
+
+    /// Busy-wait delay timed by the CPU cycle counter.
+    #[inline]
+    pub fn delay(&self) -> McycleDelay {
+        McycleDelay::new(*self)
+    }
+}
+
+/// The PWM clock, divided down, on the channel outputs of a PWM block.
+///
+/// All three channels carry the same square wave, so any pad muxed to one
+/// of them shows it. The driver does not own the pads; mux them to the PWM
+/// function as for [`Pwm`].
+pub struct ClockOutput<'i> {
+    pwm: Pwm<'i>,
+    divider: u32,
+}
+
+impl<'i> ClockOutput<'i> {
+    /// Starts emitting the PWM clock divided by `divider`.
+    ///
+    /// The divider must be at least 2 and the product of a power of two up
+    /// to 2^15 and a count of at most 65536; other values fail with
+    /// [`PwmError::FrequencyOutOfRange`]. Even counts give a 50% duty
+    /// cycle, odd ones are high for one count less than low.
+    pub fn new(mut pwm: Pwm<'i>, divider: u32) -> Result<Self, PwmError> {
+        let (scale, top) = output_divider(divider).ok_or(PwmError::FrequencyOutOfRange)?;
+        pwm.stop();
+        pwm.reset_config();
+        pwm.set_scale(scale);
+        pwm.set_period(top);
+        // High from the threshold to the end of the period.
+        let threshold = (top as u32 + 1).div_ceil(2) as u16;
+        let (mut ch1, mut ch2, mut ch3) = pwm.split();
+        ch1.set_compare(threshold);
+        ch2.set_compare(threshold);
+        ch3.set_compare(threshold);
+        pwm.start();
+        Ok(Self { pwm, divider })
+    }
+
+    /// Ratio of the PWM clock to the output frequency.
+    #[inline]
+    pub fn divider(&self) -> u32 {
+        self.divider
+    }
+
+    /// Stops the output and returns the PWM block.
+    pub fn free(mut self) -> Pwm<'i> {
+        self.pwm.stop();
+        self.pwm.reset_config();
+        self.pwm
+    }
+}
+
+/// Prescaler and period dividing the PWM clock by `divider`.
+fn output_divider(divider: u32) -> Option<(u8, u16)> {
+    if divider < 2 {
+        return None;
+    }
+    (0..16).find_map(|scale| {
+        let counts = divider >> scale;
+        let exact = counts << scale == divider;
+        (exact && (1..=65_536).contains(&counts)).then(|| (scale as u8, (counts - 1) as u16))
+    })
+}
+
+/// Measures the clock feeding `pwm` against the machine timer.
+///
+/// Lets the PWM counter run free at the undivided clock for `gate_us`
+/// microseconds and leaves the block stopped in its reset configuration.
+/// The error is about one timer tick per gate time. Returns 0 Hz if the
+/// machine timer did not advance, as on a host without one.
+pub fn measure_pwm_clock(pwm: &mut Pwm<'_>, gate_us: u32) -> Hertz {
+    pwm.stop();
+    pwm.reset_config();
+    let regs = pwm.regs();
+    unsafe {
+        modify_reg!(regs, pwm_cfg, |r| r.with_pwm_zero_cmp(Enable::Disabled));
+        modify_reg!(regs, pwm_count, |r| r
+            .with_counter(arbitrary_int::u31::new(0)));
+    }
+    pwm.start();
+    let gate = gate_us as u64 * TIMER_FREQUENCY as u64 / 1_000_000;
+    let (counted, elapsed) = count_against_timer(
+        now,
+        || read_reg!(regs, pwm_count).counter().value() as u64,
+        (1 << 31) - 1,
+        gate,
+    );
+    pwm.stop();
+    pwm.reset_config();
+    ratio_hz(counted, elapsed)
+}
+
+/// Samples the counter read by `count`, which wraps at `mask`, until `gate`
+/// ticks of `timer` have passed or [`MAX_ITERATIONS`] polls were made.
+///
+/// Returns the counts and timer ticks that passed between the first and
+/// last sample.
+fn count_against_timer(
+    mut timer: impl FnMut() -> u64,
+    mut count: impl FnMut() -> u64,
+    mask: u64,
+    gate: u64,
+) -> (u64, u64) {
+    let start = timer();
+    let start_count = count();
+    let mut counted = 0;
+    let mut last = start_count;
+    let mut elapsed = 0;
+    for _ in 0..MAX_ITERATIONS {
+        elapsed = timer().wrapping_sub(start);
+        let value = count();
+        counted += value.wrapping_sub(last) & mask;
+        last = value;
+        if elapsed >= gate {
+            break;
+        }
+    }
+    (counted, elapsed)
+}
+
+/// Frequency of `counted` events over `elapsed` machine timer ticks.
+fn ratio_hz(counted: u64, elapsed: u64) -> Hertz {
+    if elapsed == 0 {
+        return 0.Hz();
+    }
+    let hz = counted as u128 * TIMER_FREQUENCY as u128 / elapsed as u128;
+    (hz.min(u32::MAX as u128) as u32).Hz()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+    use crate::pwm::RegisterBlock;
+    use core::cell::Cell;
+
+    #[test]
+    fn output_divider_splits_into_scale_and_period() {
+        assert_eq!(output_divider(2), Some((0, 1)));
+        assert_eq!(output_divider(1_000), Some((0, 999)));
+        assert_eq!(output_divider(65_536), Some((0, 65_535)));
+        assert_eq!(output_divider(1 << 20), Some((4, 65_535)));
+        assert_eq!(output_divider(3 << 16), Some((2, 49_151)));
+        assert_eq!(output_divider(65_537), None);
+        assert_eq!(output_divider(1), None);
+        assert_eq!(output_divider(0), None);
+    }
+
+    #[test]
+    fn clock_output_programs_square_wave() {
+        let regs = unsafe { &*mock::block::<RegisterBlock>() };
+        let pwm = unsafe { Pwm::from_raw(regs) };
+        let output = ClockOutput::new(pwm, 8).unwrap();
+        assert_eq!(output.divider(), 8);
+        assert_eq!(regs.pwm_cfg.read().pwm_scale().value(), 0);
+        assert_eq!(regs.pwm_cfg.read().pwm_en_always(), Enable::Enabled);
+        assert_eq!(regs.pwm_cmpn[0].read().pwm_cpmn().value(), 7);
+        for cmp in &regs.pwm_cmpn[1..] {
+            assert_eq!(cmp.read().pwm_cpmn().value(), 4);
+        }
+        output.free();
+        assert_eq!(regs.pwm_cfg.read().pwm_en_always(), Enable::Disabled);
+    }
+
+    #[test]
+    fn counter_is_sampled_until_gate_passes() {
+        // The counter runs 3 counts per timer tick and wraps at 16.
+        let time = Cell::new(0_u64);
+        let timer = || {
+            time.set(time.get() + 1);
+            time.get()
+        };
+        let count = || (time.get() * 3) % 16;
+        let (counted, elapsed) = count_against_timer(timer, count, 15, 10);
+        assert_eq!(elapsed, 10);
+        assert_eq!(counted, 30);
+        assert_eq!(ratio_hz(counted, elapsed), (3 * TIMER_FREQUENCY).Hz());
+    }
+
+    #[test]
+    fn stopped_timer_ends_measurement() {
+        let (counted, elapsed) = count_against_timer(|| 0, || 0, u64::MAX, 100);
+        assert_eq!((counted, elapsed), (0, 0));
+        assert_eq!(ratio_hz(counted, elapsed), 0.Hz());
     }
 }
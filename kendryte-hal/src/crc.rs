@@ -0,0 +1,174 @@
+//! Table-driven CRC-16 and CRC-32.
+//!
+//! The K230 has no CRC accelerator, so checksums used by the firmware
+//! trailer, OTA headers and serial protocols are computed in software, one
+//! table lookup per byte. An algorithm is described by the usual Rocksoft
+//! parameters and builds its table at compile time; place it in a `static`
+//! so the table exists once:
+//!
+//! ```
+//! use kendryte_hal::crc::{CRC16_XMODEM, Crc32};
+//!
+//! assert_eq!(CRC16_XMODEM.checksum(b"123456789"), 0x31C3);
+//!
+//! // CRC-32C (Castagnoli).
+//! static CRC32C: Crc32 = Crc32::new(0x1EDC_6F41, 0xFFFF_FFFF, true, 0xFFFF_FFFF);
+//! let mut digest = CRC32C.digest();
+//! digest.update(b"1234");
+//! digest.update(b"56789");
+//! assert_eq!(digest.finalize(), 0xE306_9283);
+//! ```
+
+macro_rules! impl_crc {
+    ($(#[$doc:meta])* $Crc:ident, $(#[$digest_doc:meta])* $Digest:ident, $W:ty) => {
+        $(#[$doc])*
+        #[derive(Clone, Debug)]
+        pub struct $Crc {
+            table: [$W; 256],
+            init: $W,
+            reflected: bool,
+            xorout: $W,
+        }
+
+        impl $Crc {
+            /// Creates an algorithm from its parameters.
+            ///
+            /// `poly` and `init` are given unreflected, as in CRC catalogues;
+            /// `reflected` selects least significant bit first processing of
+            /// both input and output.
+            pub const fn new(poly: $W, init: $W, reflected: bool, xorout: $W) -> Self {
+                const TOP: $W = 1 << (<$W>::BITS - 1);
+                let mut table = [0; 256];
+                let mut i = 0;
+                while i < 256 {
+                    let mut crc: $W;
+                    let mut bit = 0;
+                    if reflected {
+                        crc = i as $W;
+                        while bit < 8 {
+                            crc = if crc & 1 != 0 {
+                                (crc >> 1) ^ poly.reverse_bits()
+                            } else {
+                                crc >> 1
+                            };
+                            bit += 1;
+                        }
+                    } else {
+                        crc = (i as $W) << (<$W>::BITS - 8);
+                        while bit < 8 {
+                            crc = if crc & TOP != 0 {
+                                (crc << 1) ^ poly
+                            } else {
+                                crc << 1
+                            };
+                            bit += 1;
+                        }
+                    }
+                    table[i] = crc;
+                    i += 1;
+                }
+                Self {
+                    table,
+                    init: if reflected { init.reverse_bits() } else { init },
+                    reflected,
+                    xorout,
+                }
+            }
+
+            /// CRC of `data`.
+            pub fn checksum(&self, data: &[u8]) -> $W {
+                let mut digest = self.digest();
+                digest.update(data);
+                digest.finalize()
+            }
+
+            /// Starts a CRC computed over several pieces of data.
+            pub fn digest(&self) -> $Digest<'_> {
+                $Digest {
+                    crc: self,
+                    state: self.init,
+                }
+            }
+        }
+
+        $(#[$digest_doc])*
+        #[derive(Clone, Debug)]
+        pub struct $Digest<'a> {
+            crc: &'a $Crc,
+            state: $W,
+        }
+
+        impl $Digest<'_> {
+            /// Feeds `data` into the CRC.
+            pub fn update(&mut self, data: &[u8]) {
+                let table = &self.crc.table;
+                let mut state = self.state;
+                if self.crc.reflected {
+                    for &byte in data {
+                        state = (state >> 8) ^ table[((state as u8) ^ byte) as usize];
+                    }
+                } else {
+                    for &byte in data {
+                        let index = ((state >> (<$W>::BITS - 8)) as u8) ^ byte;
+                        state = (state << 8) ^ table[index as usize];
+                    }
+                }
+                self.state = state;
+            }
+
+            /// CRC of the data fed so far.
+            pub fn finalize(&self) -> $W {
+                self.state ^ self.crc.xorout
+            }
+        }
+    };
+}
+
+impl_crc!(
+    /// A 16-bit CRC algorithm.
+    Crc16,
+    /// Running CRC-16 computation, created by [`Crc16::digest`].
+    Digest16,
+    u16
+);
+
+impl_crc!(
+    /// A 32-bit CRC algorithm.
+    Crc32,
+    /// Running CRC-32 computation, created by [`Crc32::digest`].
+    Digest32,
+    u32
+);
+
+/// CRC-16/XMODEM, used by XMODEM-CRC and YMODEM.
+pub static CRC16_XMODEM: Crc16 = Crc16::new(0x1021, 0x0000, false, 0x0000);
+
+/// CRC-16/CCITT-FALSE, also known as CRC-16/IBM-3740.
+pub static CRC16_CCITT_FALSE: Crc16 = Crc16::new(0x1021, 0xFFFF, false, 0x0000);
+
+/// CRC-16/MODBUS, sent least significant byte first after a Modbus RTU frame.
+pub static CRC16_MODBUS: Crc16 = Crc16::new(0x8005, 0xFFFF, true, 0x0000);
+
+/// CRC-32 (IEEE 802.3), as used by zlib, Ethernet and the image trailer.
+pub static CRC32_IEEE: Crc32 = Crc32::new(0x04C1_1DB7, 0xFFFF_FFFF, true, 0xFFFF_FFFF);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_values() {
+        const CHECK: &[u8] = b"123456789";
+        assert_eq!(CRC16_XMODEM.checksum(CHECK), 0x31C3);
+        assert_eq!(CRC16_CCITT_FALSE.checksum(CHECK), 0x29B1);
+        assert_eq!(CRC16_MODBUS.checksum(CHECK), 0x4B37);
+        assert_eq!(CRC32_IEEE.checksum(CHECK), 0xCBF4_3926);
+
+        let mut digest = CRC32_IEEE.digest();
+        for chunk in CHECK.chunks(2) {
+            digest.update(chunk);
+        }
+        assert_eq!(digest.finalize(), 0xCBF4_3926);
+        assert_eq!(CRC32_IEEE.checksum(&[]), 0);
+    }
+}
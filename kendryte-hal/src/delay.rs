@@ -0,0 +1,101 @@
+//! Busy-wait delays timed by the CPU cycle counter.
+//!
+//! [`McycleDelay`] converts a duration into CPU cycles with the frequency
+//! reported by [`Clocks::cpu`], instead of a cycle count fixed at build
+//! time, so the same delay holds on every chip and after the CPU clock is
+//! changed at runtime:
+//!
+//! ```no_run
+//! use kendryte_hal::clocks::Clocks;
+//! use kendryte_hal::delay::DelayNs;
+//!
+//! let mut delay = Clocks.delay();
+//! delay.delay_ms(500);
+//! ```
+
+use crate::clocks::Clocks;
+
+pub use embedded_hal::delay::DelayNs;
+
+/// Returns the number of CPU cycles since reset, read from `mcycle`.
+#[inline]
+pub fn cycles() -> u64 {
+    #[cfg(target_arch = "riscv64")]
+    {
+        let cycles: u64;
+        unsafe { core::arch::asm!("csrr {}, mcycle", out(reg) cycles, options(nomem, nostack)) };
+        cycles
+    }
+    #[cfg(not(target_arch = "riscv64"))]
+    0
+}
+
+/// Delay provider counting CPU cycles, created by [`Clocks::delay`].
+///
+/// The CPU frequency is looked up on every call, so a delay object created
+/// before [`Clocks::set_cpu_frequency`] stays accurate. Interrupt handlers
+/// running during a delay lengthen it; they never shorten it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct McycleDelay {
+    clocks: Clocks,
+}
+
+impl McycleDelay {
+    /// Creates a delay provider using the CPU frequency of `clocks`.
+    #[inline]
+    pub const fn new(clocks: Clocks) -> Self {
+        Self { clocks }
+    }
+
+    /// Spin for `amount` units of `1 / per_second` seconds.
+    #[inline]
+    fn wait(&self, amount: u32, per_second: u64) {
+        let count = to_cycles(self.clocks.cpu().0, amount, per_second);
+        let start = cycles();
+        while cycles().wrapping_sub(start) < count {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+impl DelayNs for McycleDelay {
+    #[inline]
+    fn delay_ns(&mut self, ns: u32) {
+        self.wait(ns, 1_000_000_000);
+    }
+
+    #[inline]
+    fn delay_us(&mut self, us: u32) {
+        self.wait(us, 1_000_000);
+    }
+
+    #[inline]
+    fn delay_ms(&mut self, ms: u32) {
+        self.wait(ms, 1_000);
+    }
+}
+
+/// Cycles at `frequency` Hz in `amount / per_second` seconds, rounded up so
+/// a delay is never shorter than asked.
+#[inline]
+pub(crate) const fn to_cycles(frequency: u32, amount: u32, per_second: u64) -> u64 {
+    (amount as u64 * frequency as u64).div_ceil(per_second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_cycles() {
+        assert_eq!(to_cycles(800_000_000, 1, 1_000_000_000), 1);
+        assert_eq!(to_cycles(800_000_000, 10, 1_000_000_000), 8);
+        assert_eq!(to_cycles(800_000_000, 3, 1_000_000), 2_400);
+        assert_eq!(to_cycles(390_000_000, 1, 1_000_000_000), 1);
+        assert_eq!(
+            to_cycles(1_600_000_000, u32::MAX, 1_000),
+            6_871_947_672_000_000
+        );
+        assert_eq!(to_cycles(800_000_000, 0, 1_000), 0);
+    }
+}
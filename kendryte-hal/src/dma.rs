@@ -0,0 +1,250 @@
+//! Buffers shared with DMA engines and cache maintenance.
+//!
+//! The DMA engines of the Kendryte chips are not coherent with the CPU data
+//! caches. Before a device reads a buffer, the lines the CPU wrote must be
+//! cleaned to memory; after a device wrote a buffer, the stale lines must be
+//! invalidated before the CPU reads it. Invalidating also throws away any
+//! other data sharing a line with the buffer, so DMA buffers must start and
+//! end on cache line boundaries.
+//!
+//! [`DmaBuffer`] takes care of the layout: it is aligned to, and padded to a
+//! multiple of, the largest cache line of the supported chips. Its
+//! [`prepare_for_device`](DmaBuffer::prepare_for_device) and
+//! [`complete_from_device`](DmaBuffer::complete_from_device) do the cache
+//! maintenance around a transfer:
+//!
+//! ```ignore
+//! let buf = kendryte_hal::dma_buffer!(4096).unwrap();
+//! buf[..4].copy_from_slice(b"data");
+//! buf.prepare_for_device();
+//! // ... start the transfer from `buf.as_ptr()` and wait for it ...
+//! buf.complete_from_device();
+//! ```
+//!
+//! The K210 cores have no cache maintenance instructions. Buffers there are
+//! accessed through the uncached alias of main memory, see [`uncached`], and
+//! the functions in this module only order memory accesses.
+
+use crate::soc::CACHE_LINE_SIZE;
+use core::ops::{Deref, DerefMut};
+
+/// Alignment of [`DmaBuffer`], at least the cache line size of every chip.
+pub const DMA_ALIGN: usize = 64;
+
+const _: () = assert!(DMA_ALIGN >= CACHE_LINE_SIZE && DMA_ALIGN % CACHE_LINE_SIZE == 0);
+
+/// Byte buffer occupying whole cache lines, for use with DMA.
+///
+/// The buffer holds `N` bytes; its storage is rounded up to a multiple of
+/// [`DMA_ALIGN`] so no other object shares its last cache line.
+#[repr(C, align(64))]
+pub struct DmaBuffer<const N: usize> {
+    data: [u8; N],
+}
+
+impl<const N: usize> DmaBuffer<N> {
+    /// Creates a zeroed buffer.
+    pub const fn new() -> Self {
+        Self { data: [0; N] }
+    }
+
+    /// Write back the CPU's writes so a device reads the current contents.
+    #[inline]
+    pub fn prepare_for_device(&self) {
+        clean(&self.data);
+    }
+
+    /// Discard cached contents so the CPU reads what a device wrote.
+    #[inline]
+    pub fn complete_from_device(&mut self) {
+        // SAFETY: the buffer owns every line it touches.
+        unsafe { invalidate(self.data.as_ptr() as usize, N) };
+    }
+}
+
+impl<const N: usize> Default for DmaBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Deref for DmaBuffer<N> {
+    type Target = [u8; N];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.data
+    }
+}
+
+impl<const N: usize> DerefMut for DmaBuffer<N> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.data
+    }
+}
+
+/// Allocate a static [`DmaBuffer`] of the given size.
+///
+/// Returns `Some(&'static mut DmaBuffer<SIZE>)` the first time the
+/// expansion is evaluated and `None` afterwards, so every expansion site
+/// owns exactly one buffer.
+#[macro_export]
+macro_rules! dma_buffer {
+    ($size:expr) => {{
+        static TAKEN: ::core::sync::atomic::AtomicBool =
+            ::core::sync::atomic::AtomicBool::new(false);
+        static mut BUFFER: $crate::dma::DmaBuffer<{ $size }> = $crate::dma::DmaBuffer::new();
+        if TAKEN.swap(true, ::core::sync::atomic::Ordering::AcqRel) {
+            None
+        } else {
+            // SAFETY: the flag hands out the buffer only once.
+            Some(unsafe { &mut *::core::ptr::addr_of_mut!(BUFFER) })
+        }
+    }};
+}
+
+/// Returns true if `buf` starts and ends on cache line boundaries, so it
+/// can be invalidated without touching other data.
+#[inline]
+pub fn is_cache_aligned(buf: &[u8]) -> bool {
+    let start = buf.as_ptr() as usize;
+    start % CACHE_LINE_SIZE == 0 && buf.len() % CACHE_LINE_SIZE == 0
+}
+
+/// Write back the cache lines covering `buf` to memory.
+///
+/// Call before a device reads `buf`. Lines partly covered by `buf` are
+/// written back whole, which is harmless.
+pub fn clean(buf: &[u8]) {
+    for_each_line(buf.as_ptr() as usize, buf.len(), Op::Clean);
+}
+
+/// Write back and invalidate the cache lines covering `buf`.
+///
+/// Call before a device both reads and writes `buf`, then use
+/// [`invalidate`] once it is done.
+pub fn flush(buf: &[u8]) {
+    for_each_line(buf.as_ptr() as usize, buf.len(), Op::Flush);
+}
+
+/// Invalidate the cache lines covering `len` bytes at `addr`.
+///
+/// Call after a device wrote the range and before the CPU reads it.
+///
+/// # Safety
+///
+/// Writes by the CPU to any byte of the lines covering the range that were
+/// not written back are lost. The range should be cache aligned, see
+/// [`is_cache_aligned`]; otherwise nothing else may be written in its
+/// first and last lines while the device owns the range.
+pub unsafe fn invalidate(addr: usize, len: usize) {
+    for_each_line(addr, len, Op::Invalidate);
+}
+
+/// Address of the uncached alias of `addr`, on chips that have one.
+///
+/// The K210 maps main memory twice: cached at `0x8000_0000` and uncached at
+/// `0x4000_0000`. Accesses through the alias bypass the cache, so buffers
+/// used through it need no maintenance. Returns `None` on other chips or if
+/// `addr` is not in cached main memory.
+pub fn uncached(addr: usize) -> Option<usize> {
+    #[cfg(feature = "k210")]
+    if (0x8000_0000..0x8060_0000).contains(&addr) {
+        return Some(addr - 0x4000_0000);
+    }
+    let _ = addr;
+    None
+}
+
+#[derive(Clone, Copy)]
+enum Op {
+    Clean,
+    Invalidate,
+    Flush,
+}
+
+/// Apply `op` to every line covering `len` bytes at `addr`, then wait for
+/// the operations to finish.
+fn for_each_line(addr: usize, len: usize, op: Op) {
+    if len == 0 {
+        return;
+    }
+    let start = addr & !(CACHE_LINE_SIZE - 1);
+    let end = addr + len;
+    for line in (start..end).step_by(CACHE_LINE_SIZE) {
+        line_op(line, op);
+    }
+    #[cfg(all(target_arch = "riscv64", not(any(feature = "k510", feature = "k210"))))]
+    unsafe {
+        // th.sync.s
+        core::arch::asm!(".long 0x0190000b", options(nostack))
+    };
+    #[cfg(target_arch = "riscv64")]
+    unsafe {
+        core::arch::asm!("fence iorw, iorw", options(nostack))
+    };
+}
+
+#[inline(always)]
+fn line_op(line: usize, op: Op) {
+    #[cfg(all(target_arch = "riscv64", not(any(feature = "k510", feature = "k210"))))]
+    unsafe {
+        // T-Head cache operations on the line at the virtual address in a0.
+        match op {
+            // th.dcache.cva a0
+            Op::Clean => core::arch::asm!(".long 0x0255000b", in("a0") line, options(nostack)),
+            // th.dcache.iva a0
+            Op::Invalidate => core::arch::asm!(".long 0x0265000b", in("a0") line, options(nostack)),
+            // th.dcache.civa a0
+            Op::Flush => core::arch::asm!(".long 0x0275000b", in("a0") line, options(nostack)),
+        }
+    }
+    #[cfg(all(target_arch = "riscv64", feature = "k510"))]
+    unsafe {
+        // Andes CCTL: address in mcctlbeginaddr, command in mcctlcommand.
+        let command: usize = match op {
+            Op::Invalidate => 0,
+            Op::Clean => 1,
+            Op::Flush => 2,
+        };
+        core::arch::asm!(
+            "csrw   0x7cb, {line}",
+            "csrw   0x7cc, {command}",
+            line = in(reg) line,
+            command = in(reg) command,
+            options(nostack),
+        )
+    }
+    #[cfg(any(not(target_arch = "riscv64"), feature = "k210"))]
+    let _ = (line, op);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffer_layout() {
+        assert_eq!(core::mem::align_of::<DmaBuffer<1>>(), DMA_ALIGN);
+        assert_eq!(core::mem::size_of::<DmaBuffer<1>>(), DMA_ALIGN);
+        assert_eq!(core::mem::size_of::<DmaBuffer<100>>(), 2 * DMA_ALIGN);
+        let buf = DmaBuffer::<128>::new();
+        assert!(is_cache_aligned(&buf[..]));
+        assert!(!is_cache_aligned(&buf[1..65]));
+        assert!(!is_cache_aligned(&buf[..100]));
+    }
+
+    #[test]
+    fn static_buffer_taken_once() {
+        fn take() -> Option<&'static mut DmaBuffer<256>> {
+            crate::dma_buffer!(256)
+        }
+        let buf = take().unwrap();
+        buf[0] = 1;
+        buf.prepare_for_device();
+        buf.complete_from_device();
+        assert_eq!(buf[0], 1);
+        assert!(take().is_none());
+    }
+}
@@ -0,0 +1,235 @@
+//! Crate-wide error model.
+//!
+//! Each driver keeps its own error enum, which describes exactly what can go
+//! wrong with that peripheral. [`Error`] wraps any of them so application code
+//! can propagate driver errors with `?` into one type, and [`ErrorKind`]
+//! classifies them the same way across drivers.
+//!
+//! The embedded-hal and embedded-io `kind()` implementations of the driver
+//! errors are all derived from [`ErrorKind`], so a timeout is reported as a
+//! timeout whichever trait it is seen through.
+
+use crate::gpio::GpioError;
+use crate::i2c::I2cError;
+#[cfg(feature = "k210")]
+use crate::kpu::KpuError;
+use crate::lsadc::LsadcError;
+use crate::onewire::OneWireError;
+use crate::pwm::PwmError;
+use crate::spi::SpiError;
+use crate::uart::UartError;
+use core::fmt;
+
+/// Error from any driver in this crate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    Uart(UartError),
+    Spi(SpiError),
+    I2c(I2cError),
+    Gpio(GpioError),
+    Pwm(PwmError),
+    OneWire(OneWireError),
+    Lsadc(LsadcError),
+    #[cfg(feature = "k210")]
+    Kpu(KpuError),
+}
+
+/// Classification of driver errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A wait for the peripheral did not finish in time.
+    Timeout,
+    /// Received data was lost because a FIFO was full.
+    Overrun,
+    /// A FIFO was read while empty or ran dry during a transfer.
+    Underrun,
+    /// A received frame had an invalid stop bit.
+    Framing,
+    /// A received frame had a parity error.
+    Parity,
+    /// The addressed device did not acknowledge.
+    NoAcknowledge,
+    /// Another bus master won arbitration.
+    ArbitrationLoss,
+    /// A bus line is stuck or another bus level error occurred.
+    Bus,
+    /// The driver was created without a pin the operation needs.
+    MissingPin,
+    /// The peripheral instance or chip does not support the operation.
+    NotSupported,
+    /// A configuration value is out of range or inconsistent.
+    InvalidConfig,
+    /// The driver is not in a state that allows the operation.
+    InvalidState,
+    /// The register block is not the expected peripheral; see [`crate::ident`].
+    WrongPeripheral,
+    /// Any other error.
+    Other,
+}
+
+impl Error {
+    /// Returns the kind of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::Uart(e) => match e {
+                UartError::Framing => ErrorKind::Framing,
+                UartError::Parity => ErrorKind::Parity,
+                UartError::Overrun => ErrorKind::Overrun,
+                UartError::NotFoundTx | UartError::NotFoundRx => ErrorKind::MissingPin,
+                UartError::NotSupported => ErrorKind::NotSupported,
+                UartError::Timeout => ErrorKind::Timeout,
+                UartError::BufferFull => ErrorKind::Other,
+                UartError::IdMismatch(_) => ErrorKind::WrongPeripheral,
+            },
+            Error::Spi(e) => match e {
+                SpiError::BusyTimeout => ErrorKind::Timeout,
+                SpiError::FifoOverflow => ErrorKind::Overrun,
+                SpiError::FifoUnderflow => ErrorKind::Underrun,
+                SpiError::InvalidWordSize => ErrorKind::InvalidConfig,
+                SpiError::NotSupported => ErrorKind::NotSupported,
+                SpiError::TransferInProgress => ErrorKind::InvalidState,
+                SpiError::WrongFrameFormat => ErrorKind::InvalidState,
+                SpiError::ChipSelect => ErrorKind::Bus,
+                SpiError::IdMismatch(_) => ErrorKind::WrongPeripheral,
+            },
+            Error::I2c(e) => match e {
+                I2cError::Timeout => ErrorKind::Timeout,
+                I2cError::NoAcknowledge(_) => ErrorKind::NoAcknowledge,
+                I2cError::ArbitrationLoss => ErrorKind::ArbitrationLoss,
+                I2cError::SdaStuckLow | I2cError::SclStuckLow => ErrorKind::Bus,
+                I2cError::Aborted => ErrorKind::Other,
+            },
+            Error::Gpio(e) => match e {
+                GpioError::ConfigurationFailed => ErrorKind::InvalidConfig,
+                GpioError::HardwareError => ErrorKind::Other,
+                GpioError::IncompatibleMode => ErrorKind::InvalidState,
+                GpioError::Timeout => ErrorKind::Timeout,
+                GpioError::InvalidDriveStrength => ErrorKind::InvalidConfig,
+                GpioError::IdMismatch(_) => ErrorKind::WrongPeripheral,
+            },
+            Error::Pwm(e) => match e {
+                PwmError::PeriodNotSet => ErrorKind::InvalidState,
+                PwmError::FrequencyOutOfRange => ErrorKind::InvalidConfig,
+            },
+            Error::OneWire(e) => match e {
+                OneWireError::NoPresence => ErrorKind::NoAcknowledge,
+                OneWireError::BusShorted => ErrorKind::Bus,
+                OneWireError::CrcMismatch => ErrorKind::Other,
+            },
+            Error::Lsadc(e) => match e {
+                LsadcError::CalibrationTimeout => ErrorKind::Timeout,
+            },
+            #[cfg(feature = "k210")]
+            Error::Kpu(e) => match e {
+                KpuError::InvalidModel | KpuError::OutOfRange => ErrorKind::InvalidConfig,
+                KpuError::Timeout => ErrorKind::Timeout,
+            },
+        }
+    }
+}
+
+macro_rules! impl_from {
+    ($($(#[$meta:meta])* $variant:ident($ty:ty)),+ $(,)?) => {
+        $(
+            $(#[$meta])*
+            impl From<$ty> for Error {
+                #[inline]
+                fn from(e: $ty) -> Self {
+                    Error::$variant(e)
+                }
+            }
+        )+
+    };
+}
+
+impl_from!(
+    Uart(UartError),
+    Spi(SpiError),
+    I2c(I2cError),
+    Gpio(GpioError),
+    Pwm(PwmError),
+    OneWire(OneWireError),
+    Lsadc(LsadcError),
+    #[cfg(feature = "k210")]
+    Kpu(KpuError),
+);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Uart(e) => write!(f, "UART error: {e:?}"),
+            Error::Spi(e) => write!(f, "SPI error: {e:?}"),
+            Error::I2c(e) => write!(f, "I2C error: {e:?}"),
+            Error::Gpio(e) => write!(f, "GPIO error: {e}"),
+            Error::Pwm(e) => write!(f, "PWM error: {e:?}"),
+            Error::OneWire(e) => write!(f, "1-Wire error: {e:?}"),
+            Error::Lsadc(e) => write!(f, "LSADC error: {e:?}"),
+            #[cfg(feature = "k210")]
+            Error::Kpu(e) => write!(f, "KPU error: {e:?}"),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+impl From<ErrorKind> for embedded_io::ErrorKind {
+    fn from(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::Timeout => embedded_io::ErrorKind::TimedOut,
+            ErrorKind::Overrun | ErrorKind::Framing | ErrorKind::Parity => {
+                embedded_io::ErrorKind::InvalidData
+            }
+            ErrorKind::MissingPin => embedded_io::ErrorKind::NotConnected,
+            ErrorKind::NotSupported => embedded_io::ErrorKind::Unsupported,
+            ErrorKind::InvalidConfig => embedded_io::ErrorKind::InvalidInput,
+            _ => embedded_io::ErrorKind::Other,
+        }
+    }
+}
+
+impl From<ErrorKind> for embedded_hal_nb::serial::ErrorKind {
+    fn from(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::Overrun => Self::Overrun,
+            ErrorKind::Framing => Self::FrameFormat,
+            ErrorKind::Parity => Self::Parity,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl From<ErrorKind> for embedded_hal::spi::ErrorKind {
+    fn from(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::Overrun => Self::Overrun,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl From<ErrorKind> for embedded_hal::i2c::ErrorKind {
+    fn from(kind: ErrorKind) -> Self {
+        use embedded_hal::i2c::NoAcknowledgeSource;
+        match kind {
+            ErrorKind::Overrun => Self::Overrun,
+            ErrorKind::NoAcknowledge => Self::NoAcknowledge(NoAcknowledgeSource::Unknown),
+            ErrorKind::ArbitrationLoss => Self::ArbitrationLoss,
+            ErrorKind::Bus => Self::Bus,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl From<ErrorKind> for embedded_hal::digital::ErrorKind {
+    fn from(_: ErrorKind) -> Self {
+        Self::Other
+    }
+}
+
+impl From<ErrorKind> for embedded_hal::pwm::ErrorKind {
+    fn from(_: ErrorKind) -> Self {
+        Self::Other
+    }
+}
@@ -0,0 +1,188 @@
+//! Firmware self-identification.
+//!
+//! kendryte-rt reserves [`TRAILER_LEN`] bytes at the end of the loaded image,
+//! in the `.trailer` section placed after `.data` and before `.bss`, holding
+//! [`RESERVED_TRAILER`]. `cargo xtask gen-image --fw-version <VERSION>` fills
+//! them with a metadata trailer before the payload is packed into an image.
+//! The trailer is loaded together with the firmware, so the running program
+//! can report its version, build time and git commit, and check that the
+//! payload is intact; `kendryte_rt::firmware::info` finds it through the
+//! linker symbols of the section.
+//!
+//! Trailer layout, little endian, [`TRAILER_LEN`] bytes at the end of the payload:
+//!
+//! | Offset | Size | Field                                           |
+//! |--------|------|-------------------------------------------------|
+//! | 0      | 4    | magic, `b"KFWI"`                                |
+//! | 4      | 2    | trailer format version, 1                       |
+//! | 6      | 2    | trailer length, 96                              |
+//! | 8      | 6    | firmware version: major, minor, patch as `u16`  |
+//! | 14     | 2    | reserved                                        |
+//! | 16     | 8    | build time, seconds since the Unix epoch        |
+//! | 24     | 20   | git commit hash, zero if unknown                |
+//! | 44     | 4    | CRC-32 of the payload before the trailer        |
+//! | 48     | 32   | SHA-256 of the payload and trailer bytes 0..48  |
+//! | 80     | 16   | reserved                                        |
+
+use crate::crc::CRC32_IEEE;
+use core::fmt;
+
+/// Magic bytes at the start of the trailer.
+pub const TRAILER_MAGIC: [u8; 4] = *b"KFWI";
+/// Trailer format version understood by [`FirmwareInfo::parse`].
+pub const TRAILER_VERSION: u16 = 1;
+/// Length of the trailer in bytes.
+pub const TRAILER_LEN: usize = 96;
+
+/// Contents of the trailer space before it is filled in: the magic and
+/// length with format version 0, which [`FirmwareInfo::parse`] rejects.
+pub const RESERVED_TRAILER: [u8; TRAILER_LEN] = {
+    let mut bytes = [0; TRAILER_LEN];
+    let mut i = 0;
+    while i < TRAILER_MAGIC.len() {
+        bytes[i] = TRAILER_MAGIC[i];
+        i += 1;
+    }
+    bytes[6] = TRAILER_LEN as u8;
+    bytes
+};
+
+/// Semantic version of the firmware.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Metadata read from the firmware trailer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FirmwareInfo {
+    /// Firmware version given at image generation time.
+    pub version: Version,
+    /// Build time in seconds since the Unix epoch.
+    pub build_time: u64,
+    /// Git commit hash of the build, zero if unknown.
+    pub git_hash: [u8; 20],
+    /// CRC-32 (IEEE) of the payload.
+    pub payload_crc32: u32,
+    /// SHA-256 of the payload and the trailer fields before it.
+    pub sha256: [u8; 32],
+    payload_len: usize,
+}
+
+impl FirmwareInfo {
+    /// Reads the trailer at the end of `image`.
+    ///
+    /// `image` must end exactly where the loaded payload ends. Returns `None`
+    /// if no trailer of a known format is found.
+    pub fn parse(image: &[u8]) -> Option<Self> {
+        let payload_len = image.len().checked_sub(TRAILER_LEN)?;
+        let t = &image[payload_len..];
+        let u16_at = |at: usize| u16::from_le_bytes([t[at], t[at + 1]]);
+        if t[..4] != TRAILER_MAGIC
+            || u16_at(4) != TRAILER_VERSION
+            || u16_at(6) as usize != TRAILER_LEN
+        {
+            return None;
+        }
+        Some(Self {
+            version: Version {
+                major: u16_at(8),
+                minor: u16_at(10),
+                patch: u16_at(12),
+            },
+            build_time: u64::from_le_bytes(t[16..24].try_into().unwrap()),
+            git_hash: t[24..44].try_into().unwrap(),
+            payload_crc32: u32::from_le_bytes(t[44..48].try_into().unwrap()),
+            sha256: t[48..80].try_into().unwrap(),
+            payload_len,
+        })
+    }
+
+    /// Returns the payload part of `image`, without the trailer.
+    pub fn payload<'a>(&self, image: &'a [u8]) -> &'a [u8] {
+        &image[..self.payload_len]
+    }
+
+    /// Checks the payload of `image` against the recorded CRC-32.
+    ///
+    /// The SHA-256 is meant for host tools; checking the CRC is cheap enough
+    /// to do on every boot.
+    pub fn verify(&self, image: &[u8]) -> bool {
+        image.len() == self.payload_len + TRAILER_LEN
+            && CRC32_IEEE.checksum(self.payload(image)) == self.payload_crc32
+    }
+
+    /// Returns an object that displays the git hash as 40 hex digits.
+    pub fn git_hash_hex(&self) -> impl fmt::Display + '_ {
+        struct Hex<'a>(&'a [u8]);
+        impl fmt::Display for Hex<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+            }
+        }
+        Hex(&self.git_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::vec::Vec;
+
+    /// Appends a trailer the way `cargo xtask gen-image` fills it in, minus
+    /// the SHA-256 which is not checked on the target.
+    fn with_trailer(payload: &[u8]) -> Vec<u8> {
+        let mut image = payload.to_vec();
+        image.extend(TRAILER_MAGIC);
+        image.extend(TRAILER_VERSION.to_le_bytes());
+        image.extend((TRAILER_LEN as u16).to_le_bytes());
+        image.extend([1, 0, 2, 0, 3, 0, 0, 0]);
+        image.extend(1_700_000_000u64.to_le_bytes());
+        image.extend([0xAB; 20]);
+        image.extend(CRC32_IEEE.checksum(payload).to_le_bytes());
+        image.resize(payload.len() + TRAILER_LEN, 0);
+        image
+    }
+
+    #[test]
+    fn parse_and_verify() {
+        let mut image = with_trailer(b"123456789");
+        let info = FirmwareInfo::parse(&image).unwrap();
+        assert_eq!(
+            info.version,
+            Version {
+                major: 1,
+                minor: 2,
+                patch: 3
+            }
+        );
+        assert_eq!(info.build_time, 1_700_000_000);
+        assert_eq!(info.payload_crc32, 0xCBF4_3926);
+        assert_eq!(info.payload(&image), b"123456789");
+        assert_eq!(std::format!("{}", info.git_hash_hex()), "ab".repeat(20));
+        assert!(info.verify(&image));
+
+        image[0] ^= 1;
+        assert!(!info.verify(&image));
+        assert!(!info.verify(&image[1..]));
+    }
+
+    #[test]
+    fn reserved_trailer_is_not_parsed() {
+        let mut image = b"payload".to_vec();
+        image.extend(RESERVED_TRAILER);
+        assert_eq!(&RESERVED_TRAILER[..8], b"KFWI\0\0\x60\0");
+        assert_eq!(FirmwareInfo::parse(&image), None);
+        assert_eq!(FirmwareInfo::parse(&image[..TRAILER_LEN - 1]), None);
+    }
+}
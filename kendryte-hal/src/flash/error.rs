@@ -0,0 +1,28 @@
+use embedded_storage::nor_flash::{NorFlashError, NorFlashErrorKind};
+
+/// Serial NOR flash error types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlashError<E> {
+    /// Underlying SPI bus error.
+    Spi(E),
+    /// The device has no valid SFDP header or basic parameter table.
+    NoSfdp,
+    /// The device does not support an operation this driver requires.
+    Unsupported,
+    /// Offset or length is not aligned to the required boundary.
+    NotAligned,
+    /// Offset or length is outside the flash capacity.
+    OutOfBounds,
+    /// The device stayed busy longer than the configured poll limit.
+    Timeout,
+}
+
+impl<E: core::fmt::Debug> NorFlashError for FlashError<E> {
+    fn kind(&self) -> NorFlashErrorKind {
+        match self {
+            FlashError::NotAligned => NorFlashErrorKind::NotAligned,
+            FlashError::OutOfBounds => NorFlashErrorKind::OutOfBounds,
+            _ => NorFlashErrorKind::Other,
+        }
+    }
+}
@@ -0,0 +1,12 @@
+//! Serial NOR flash support on top of any embedded-hal `SpiDevice`.
+//!
+//! Geometry is discovered through JEDEC SFDP, so most SPI NOR parts found on
+//! K230 boards work without a per-chip table.
+
+mod error;
+mod nor;
+mod sfdp;
+
+pub use error::FlashError;
+pub use nor::SpiNor;
+pub use sfdp::{EraseType, FlashInfo};
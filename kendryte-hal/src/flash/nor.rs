@@ -0,0 +1,264 @@
+use super::error::FlashError;
+use super::sfdp::{self, FlashInfo};
+use embedded_hal::spi::{Operation, SpiDevice};
+use embedded_storage::nor_flash::{ErrorType, MultiwriteNorFlash, NorFlash, ReadNorFlash};
+
+const CMD_WRITE_ENABLE: u8 = 0x06;
+const CMD_READ_STATUS: u8 = 0x05;
+const CMD_READ_JEDEC_ID: u8 = 0x9F;
+const CMD_READ_SFDP: u8 = 0x5A;
+const CMD_READ: u8 = 0x03;
+const CMD_READ_4B: u8 = 0x13;
+const CMD_PAGE_PROGRAM: u8 = 0x02;
+const CMD_PAGE_PROGRAM_4B: u8 = 0x12;
+const CMD_CHIP_ERASE: u8 = 0xC7;
+
+/// Write-in-progress bit of status register 1.
+const STATUS_BUSY: u8 = 1 << 0;
+
+/// Sector size exposed through [`NorFlash::ERASE_SIZE`].
+const SECTOR_SIZE: u32 = 4096;
+
+/// Default number of status register polls before [`FlashError::Timeout`].
+///
+/// Large enough to cover a 64 KiB block erase at the SPI clocks used on K230 boards.
+const DEFAULT_MAX_POLLS: u32 = 10_000_000;
+
+/// Serial NOR flash driver.
+///
+/// Works with any `SpiDevice`. Commands are sent as multi-operation
+/// transactions, so use [`CsDevice`](crate::spi::CsDevice) to keep chip
+/// select asserted across each of them. The device is probed through JEDEC SFDP on creation; erases use the largest supported
+/// erase type for each aligned region.
+pub struct SpiNor<SPI> {
+    spi: SPI,
+    info: FlashInfo,
+    max_polls: u32,
+}
+
+impl<SPI: SpiDevice> SpiNor<SPI> {
+    /// Probe the device through SFDP and create a new driver.
+    ///
+    /// Fails with [`FlashError::Unsupported`] if the device has no 4 KiB erase.
+    pub fn new(mut spi: SPI) -> Result<Self, FlashError<SPI::Error>> {
+        let info = probe(&mut spi)?;
+        Self::with_info(spi, info)
+    }
+
+    /// Create a driver from known geometry, skipping SFDP probing.
+    ///
+    /// Useful for old parts without SFDP.
+    pub fn with_info(spi: SPI, info: FlashInfo) -> Result<Self, FlashError<SPI::Error>> {
+        if info.min_erase_size() != Some(SECTOR_SIZE) {
+            return Err(FlashError::Unsupported);
+        }
+        Ok(SpiNor {
+            spi,
+            info,
+            max_polls: DEFAULT_MAX_POLLS,
+        })
+    }
+
+    /// Returns the geometry of the flash.
+    #[inline]
+    pub fn info(&self) -> &FlashInfo {
+        &self.info
+    }
+
+    /// Set the number of status register polls before an operation times out.
+    #[inline]
+    pub fn set_max_polls(&mut self, max_polls: u32) {
+        self.max_polls = max_polls;
+    }
+
+    /// Release the underlying SPI device.
+    #[inline]
+    pub fn free(self) -> SPI {
+        self.spi
+    }
+
+    /// Read the manufacturer ID and the two device ID bytes.
+    pub fn read_jedec_id(&mut self) -> Result<[u8; 3], FlashError<SPI::Error>> {
+        let mut id = [0; 3];
+        self.spi
+            .transaction(&mut [
+                Operation::Write(&[CMD_READ_JEDEC_ID]),
+                Operation::Read(&mut id),
+            ])
+            .map_err(FlashError::Spi)?;
+        Ok(id)
+    }
+
+    /// Read status register 1.
+    pub fn read_status(&mut self) -> Result<u8, FlashError<SPI::Error>> {
+        let mut status = [0];
+        self.spi
+            .transaction(&mut [
+                Operation::Write(&[CMD_READ_STATUS]),
+                Operation::Read(&mut status),
+            ])
+            .map_err(FlashError::Spi)?;
+        Ok(status[0])
+    }
+
+    /// Erase the whole device.
+    pub fn erase_chip(&mut self) -> Result<(), FlashError<SPI::Error>> {
+        self.write_enable()?;
+        self.spi.write(&[CMD_CHIP_ERASE]).map_err(FlashError::Spi)?;
+        self.wait_ready()
+    }
+
+    fn write_enable(&mut self) -> Result<(), FlashError<SPI::Error>> {
+        self.spi.write(&[CMD_WRITE_ENABLE]).map_err(FlashError::Spi)
+    }
+
+    fn wait_ready(&mut self) -> Result<(), FlashError<SPI::Error>> {
+        for _ in 0..self.max_polls {
+            if self.read_status()? & STATUS_BUSY == 0 {
+                return Ok(());
+            }
+        }
+        Err(FlashError::Timeout)
+    }
+
+    /// Encode an instruction followed by its address; returns the used length.
+    fn command(&self, opcode: u8, address: u32, buf: &mut [u8; 5]) -> usize {
+        let address = address.to_be_bytes();
+        buf[0] = opcode;
+        if self.info.address_bytes == 4 {
+            buf[1..5].copy_from_slice(&address);
+            5
+        } else {
+            buf[1..4].copy_from_slice(&address[1..]);
+            4
+        }
+    }
+
+    fn check_range(&self, offset: u32, len: usize) -> Result<(), FlashError<SPI::Error>> {
+        match offset.checked_add(len as u32) {
+            Some(end) if len <= u32::MAX as usize && end <= self.info.capacity => Ok(()),
+            _ => Err(FlashError::OutOfBounds),
+        }
+    }
+}
+
+impl<SPI: SpiDevice> ErrorType for SpiNor<SPI> {
+    type Error = FlashError<SPI::Error>;
+}
+
+impl<SPI: SpiDevice> ReadNorFlash for SpiNor<SPI> {
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        self.check_range(offset, bytes.len())?;
+        let opcode = if self.info.address_bytes == 4 {
+            CMD_READ_4B
+        } else {
+            CMD_READ
+        };
+        let mut cmd = [0; 5];
+        let len = self.command(opcode, offset, &mut cmd);
+        self.spi
+            .transaction(&mut [Operation::Write(&cmd[..len]), Operation::Read(bytes)])
+            .map_err(FlashError::Spi)
+    }
+
+    fn capacity(&self) -> usize {
+        self.info.capacity as usize
+    }
+}
+
+impl<SPI: SpiDevice> NorFlash for SpiNor<SPI> {
+    const WRITE_SIZE: usize = 1;
+    const ERASE_SIZE: usize = SECTOR_SIZE as usize;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        if from > to || to > self.info.capacity {
+            return Err(FlashError::OutOfBounds);
+        }
+        if from % SECTOR_SIZE != 0 || to % SECTOR_SIZE != 0 {
+            return Err(FlashError::NotAligned);
+        }
+        let mut offset = from;
+        while offset < to {
+            // The 4 KiB erase type is always present, so this never fails.
+            let erase = self
+                .info
+                .erase_type_for(offset, to)
+                .ok_or(FlashError::NotAligned)?;
+            let mut cmd = [0; 5];
+            let len = self.command(erase.opcode, offset, &mut cmd);
+            self.write_enable()?;
+            self.spi.write(&cmd[..len]).map_err(FlashError::Spi)?;
+            self.wait_ready()?;
+            offset += erase.size;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.check_range(offset, bytes.len())?;
+        let opcode = if self.info.address_bytes == 4 {
+            CMD_PAGE_PROGRAM_4B
+        } else {
+            CMD_PAGE_PROGRAM
+        };
+        let mut address = offset;
+        let mut remaining = bytes;
+        while !remaining.is_empty() {
+            // A page program wraps around inside the page, so never cross a page boundary.
+            let page_left = self.info.page_size - address % self.info.page_size;
+            let (chunk, rest) = remaining.split_at(remaining.len().min(page_left as usize));
+            let mut cmd = [0; 5];
+            let len = self.command(opcode, address, &mut cmd);
+            self.write_enable()?;
+            self.spi
+                .transaction(&mut [Operation::Write(&cmd[..len]), Operation::Write(chunk)])
+                .map_err(FlashError::Spi)?;
+            self.wait_ready()?;
+            address += chunk.len() as u32;
+            remaining = rest;
+        }
+        Ok(())
+    }
+}
+
+// Page program only clears bits, so programming an already written location
+// ANDs the new data into it.
+impl<SPI: SpiDevice> MultiwriteNorFlash for SpiNor<SPI> {}
+
+/// Read and decode the SFDP Basic Flash Parameter Table.
+fn probe<SPI: SpiDevice>(spi: &mut SPI) -> Result<FlashInfo, FlashError<SPI::Error>> {
+    let mut header = [0; sfdp::HEADER_LEN];
+    let mut param = [0; sfdp::HEADER_LEN];
+    read_sfdp(spi, 0, &mut header)?;
+    read_sfdp(spi, sfdp::HEADER_LEN as u32, &mut param)?;
+    let (ptr, len) = FlashInfo::find_bfpt(&header, &param).ok_or(FlashError::NoSfdp)?;
+    // JESD216 requires at least 9 double words in the BFPT.
+    if len < 9 {
+        return Err(FlashError::NoSfdp);
+    }
+
+    let len = len.min(sfdp::BFPT_DWORDS);
+    let mut raw = [0; sfdp::BFPT_DWORDS * 4];
+    read_sfdp(spi, ptr, &mut raw[..len * 4])?;
+    let mut dwords = [0; sfdp::BFPT_DWORDS];
+    for (dword, bytes) in dwords.iter_mut().zip(raw.chunks_exact(4)) {
+        *dword = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    FlashInfo::from_bfpt(&dwords, len).ok_or(FlashError::NoSfdp)
+}
+
+/// Read SFDP data; the instruction always uses a 3-byte address and 8 dummy clocks.
+fn read_sfdp<SPI: SpiDevice>(
+    spi: &mut SPI,
+    address: u32,
+    buf: &mut [u8],
+) -> Result<(), FlashError<SPI::Error>> {
+    let [_, a2, a1, a0] = address.to_be_bytes();
+    spi.transaction(&mut [
+        Operation::Write(&[CMD_READ_SFDP, a2, a1, a0, 0]),
+        Operation::Read(buf),
+    ])
+    .map_err(FlashError::Spi)
+}
@@ -0,0 +1,252 @@
+//! JEDEC JESD216 Serial Flash Discoverable Parameters decoding.
+
+/// "SFDP" signature, read as a little-endian word.
+pub(crate) const SIGNATURE: u32 = 0x5044_4653;
+/// Parameter ID of the JEDEC Basic Flash Parameter Table.
+const BFPT_ID: u16 = 0xFF00;
+/// Size of the SFDP header and of each parameter header in bytes.
+pub(crate) const HEADER_LEN: usize = 8;
+/// Number of BFPT double words decoded by [`FlashInfo::from_bfpt`].
+pub(crate) const BFPT_DWORDS: usize = 11;
+
+/// One erase granularity supported by the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EraseType {
+    /// Erase size in bytes.
+    pub size: u32,
+    /// Erase instruction opcode using the device's address width.
+    pub opcode: u8,
+}
+
+/// Flash geometry decoded from SFDP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlashInfo {
+    /// Total capacity in bytes.
+    pub capacity: u32,
+    /// Program page size in bytes.
+    pub page_size: u32,
+    /// Number of address bytes used by read, program and erase instructions.
+    pub address_bytes: u8,
+    /// Supported erase types, sorted from smallest to largest.
+    pub erase_types: [Option<EraseType>; 4],
+}
+
+impl FlashInfo {
+    /// Locate the Basic Flash Parameter Table from the SFDP header and parameter headers.
+    ///
+    /// Returns the BFPT byte address and its length in double words.
+    pub(crate) fn find_bfpt(
+        header: &[u8; HEADER_LEN],
+        param: &[u8; HEADER_LEN],
+    ) -> Option<(u32, usize)> {
+        if u32::from_le_bytes([header[0], header[1], header[2], header[3]]) != SIGNATURE {
+            return None;
+        }
+        // JESD216 requires the first parameter header to describe the BFPT.
+        let id = u16::from_le_bytes([param[0], param[7]]);
+        if id != BFPT_ID {
+            return None;
+        }
+        let len = param[3] as usize;
+        let ptr = u32::from_le_bytes([param[4], param[5], param[6], 0]);
+        Some((ptr, len))
+    }
+
+    /// Decode the Basic Flash Parameter Table.
+    ///
+    /// `dwords` holds the first `len` double words of the table; missing
+    /// double words of older JESD216 revisions are left as zero.
+    pub(crate) fn from_bfpt(dwords: &[u32; BFPT_DWORDS], len: usize) -> Option<Self> {
+        let dw1 = dwords[0];
+        let dw2 = dwords[1];
+
+        let address_bytes = match (dw1 >> 17) & 0b11 {
+            0b10 => 4,
+            _ => 3,
+        };
+
+        let bits: u64 = if dw2 & (1 << 31) == 0 {
+            (dw2 as u64) + 1
+        } else {
+            1u64.checked_shl(dw2 & 0x7FFF_FFFF)?
+        };
+        let capacity = u32::try_from(bits / 8).ok()?;
+        // Anything above 16 MiB needs 4-byte addressing.
+        let address_bytes = if capacity > 1 << 24 { 4 } else { address_bytes };
+
+        let mut erase_types = [None; 4];
+        if len >= 9 {
+            for (i, erase) in erase_types.iter_mut().enumerate() {
+                let word = dwords[7 + i / 2] >> (16 * (i % 2));
+                let exponent = word & 0xFF;
+                let opcode = (word >> 8) as u8;
+                if exponent != 0 && exponent < 32 {
+                    *erase = Some(EraseType {
+                        size: 1 << exponent,
+                        opcode: erase_opcode(opcode, address_bytes),
+                    });
+                }
+            }
+        }
+        // JESD216 revision A and earlier only describe the 4 KiB erase in DWORD1.
+        if erase_types.iter().all(Option::is_none) && dw1 & 0b11 == 0b01 {
+            erase_types[0] = Some(EraseType {
+                size: 4096,
+                opcode: erase_opcode((dw1 >> 8) as u8, address_bytes),
+            });
+        }
+        erase_types.sort_unstable_by_key(|e| e.map_or(u32::MAX, |e| e.size));
+
+        let page_size = if len >= 11 {
+            1 << ((dwords[10] >> 4) & 0xF)
+        } else {
+            256
+        };
+
+        Some(FlashInfo {
+            capacity,
+            page_size,
+            address_bytes,
+            erase_types,
+        })
+    }
+
+    /// Smallest supported erase size in bytes.
+    #[inline]
+    pub fn min_erase_size(&self) -> Option<u32> {
+        self.erase_types.iter().flatten().map(|e| e.size).next()
+    }
+
+    /// Largest erase type that fits at `offset` and does not go past `end`.
+    pub(crate) fn erase_type_for(&self, offset: u32, end: u32) -> Option<EraseType> {
+        self.erase_types
+            .iter()
+            .rev()
+            .flatten()
+            .find(|e| offset % e.size == 0 && end - offset >= e.size)
+            .copied()
+    }
+}
+
+/// Map a 3-byte address erase opcode to its 4-byte address variant.
+fn erase_opcode(opcode: u8, address_bytes: u8) -> u8 {
+    if address_bytes != 4 {
+        return opcode;
+    }
+    match opcode {
+        0x20 => 0x21,
+        0x52 => 0x5C,
+        0xD8 => 0xDC,
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// BFPT of a 128 Mbit device with 4, 32 and 64 KiB erase types.
+    fn bfpt_128mbit() -> [u32; BFPT_DWORDS] {
+        let mut dwords = [0; BFPT_DWORDS];
+        dwords[0] = 0xFFF9_20E5;
+        dwords[1] = 0x07FF_FFFF;
+        dwords[7] = 0x520F_200C;
+        dwords[8] = 0xFF00_D810;
+        dwords[10] = 0x0000_0082;
+        dwords
+    }
+
+    #[test]
+    fn find_bfpt_header() {
+        let header = *b"SFDP\x06\x01\x00\xFF";
+        let param = [0x00, 0x06, 0x01, 0x10, 0x80, 0x00, 0x00, 0xFF];
+        assert_eq!(FlashInfo::find_bfpt(&header, &param), Some((0x80, 16)));
+
+        let bad_signature = *b"SFDQ\x06\x01\x00\xFF";
+        assert_eq!(FlashInfo::find_bfpt(&bad_signature, &param), None);
+        let vendor_table = [0x84, 0x01, 0x01, 0x02, 0x30, 0x00, 0x00, 0xFF];
+        assert_eq!(FlashInfo::find_bfpt(&header, &vendor_table), None);
+    }
+
+    #[test]
+    fn decode_bfpt() {
+        let info = FlashInfo::from_bfpt(&bfpt_128mbit(), 16).unwrap();
+        assert_eq!(info.capacity, 16 << 20);
+        assert_eq!(info.page_size, 256);
+        assert_eq!(info.address_bytes, 3);
+        assert_eq!(
+            info.erase_types,
+            [
+                Some(EraseType {
+                    size: 4 << 10,
+                    opcode: 0x20
+                }),
+                Some(EraseType {
+                    size: 32 << 10,
+                    opcode: 0x52
+                }),
+                Some(EraseType {
+                    size: 64 << 10,
+                    opcode: 0xD8
+                }),
+                None,
+            ]
+        );
+        assert_eq!(info.min_erase_size(), Some(4 << 10));
+    }
+
+    #[test]
+    fn decode_bfpt_4_byte_addresses() {
+        let mut dwords = bfpt_128mbit();
+        // 2^32 bits, too large for 3-byte addresses.
+        dwords[1] = 0x8000_0020;
+        let info = FlashInfo::from_bfpt(&dwords, 16).unwrap();
+        assert_eq!(info.capacity, 512 << 20);
+        assert_eq!(info.address_bytes, 4);
+        let opcodes = info.erase_types.map(|e| e.map(|e| e.opcode));
+        assert_eq!(opcodes, [Some(0x21), Some(0x5C), Some(0xDC), None]);
+    }
+
+    #[test]
+    fn decode_legacy_bfpt() {
+        // JESD216 revision A tables stop after DWORD9 and only describe the
+        // 4 KiB erase in DWORD1.
+        let mut dwords = bfpt_128mbit();
+        dwords[7] = 0;
+        dwords[8] = 0;
+        let info = FlashInfo::from_bfpt(&dwords, 9).unwrap();
+        assert_eq!(info.page_size, 256);
+        assert_eq!(
+            info.erase_types,
+            [
+                Some(EraseType {
+                    size: 4 << 10,
+                    opcode: 0x20
+                }),
+                None,
+                None,
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_oversized_density() {
+        let mut dwords = bfpt_128mbit();
+        // 2^35 bits does not fit the 32-bit capacity.
+        dwords[1] = 0x8000_0023;
+        assert_eq!(FlashInfo::from_bfpt(&dwords, 16), None);
+        dwords[1] = 0x8000_0040;
+        assert_eq!(FlashInfo::from_bfpt(&dwords, 16), None);
+    }
+
+    #[test]
+    fn erase_type_selection() {
+        let info = FlashInfo::from_bfpt(&bfpt_128mbit(), 16).unwrap();
+        let size = |offset, end| info.erase_type_for(offset, end).map(|e| e.size);
+        assert_eq!(size(0, 0x2_0000), Some(64 << 10));
+        assert_eq!(size(0x8000, 0x2_0000), Some(32 << 10));
+        assert_eq!(size(0x1_0000, 0x1_1000), Some(4 << 10));
+        assert_eq!(size(0x1_0000, 0x1_0800), None);
+    }
+}
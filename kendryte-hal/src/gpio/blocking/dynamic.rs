@@ -8,6 +8,8 @@ use crate::gpio::blocking::unconfigured::Unconfigured;
 use crate::gpio::blocking::{PinCommon, PinInfo};
 use crate::gpio::config::Pull;
 use crate::gpio::{DriveStrength, GpioError, GpioPort, IntoGpio};
+use crate::iomux::pad::Strength;
+use crate::iomux::{DriveCurrent, FlexPad};
 use embedded_hal::digital::{ErrorType, InputPin, OutputPin, PinState, StatefulOutputPin};
 
 /// GPIO pin mode enumeration.
@@ -215,6 +217,36 @@ impl<'i, 'p> Dynamic<'i, 'p> {
         self.common.drive_strength()
     }
 
+    /// Set a raw drive strength code, validated against the pad's voltage domain.
+    pub fn set_drive_strength_code(&mut self, code: Strength) -> Result<(), GpioError> {
+        self.common.set_drive_strength_code(code)
+    }
+
+    /// Approximate source and sink current of the current drive strength.
+    pub fn drive_current(&self) -> Option<DriveCurrent> {
+        self.common.drive_current()
+    }
+
+    /// Enable or disable hardware debounce.
+    ///
+    /// The setting only affects the pin while it is in input mode.
+    pub fn set_debounce(&mut self, enable: bool) -> Result<(), GpioError> {
+        self.common.set_debounce(enable)
+    }
+
+    /// Check if hardware debounce is enabled.
+    pub fn is_debounce_enabled(&self) -> bool {
+        self.common.is_debounce_enabled()
+    }
+
+    /// Release the pin.
+    ///
+    /// Stops driving the line and returns the pad with input and output disabled,
+    /// ready to be configured for another function.
+    pub fn free(self) -> FlexPad<'p> {
+        self.common.release()
+    }
+
     /// Convert to dedicated input pin.
     ///
     /// Returns a type-safe input pin that cannot be reconfigured.
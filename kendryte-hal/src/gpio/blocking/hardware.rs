@@ -0,0 +1,70 @@
+//! Hardware controlled GPIO pin implementation
+//!
+//! This module provides the [`HardwareControlled`] type for GPIO pins whose
+//! data and direction come from the auxiliary hardware interface of the GPIO
+//! block instead of the software data and direction registers.
+//!
+//! The DesignWare GPIO only offers hardware control on ports synthesized with
+//! it, reported by the `hw_port*_enable` bits of `config_reg1`, and only for
+//! the signals the SoC connects to the auxiliary interface. Which peripheral
+//! drives which pin is decided by the SoC integration and cannot be read from
+//! the GPIO registers, so check the chip reference manual for the pin in
+//! question before relying on this mode, for example for hardware flow
+//! control or debug signals. Pins whose auxiliary inputs are unconnected are
+//! left undriven while in hardware mode.
+
+use crate::gpio::blocking::unconfigured::Unconfigured;
+use crate::gpio::blocking::{PinCommon, PinInfo};
+use crate::gpio::{ControlMode, GpioPort};
+use crate::iomux::FlexPad;
+use crate::iomux::ops::PadOps;
+use embedded_hal::digital::PinState;
+
+/// GPIO pin driven by hardware.
+///
+/// While in this mode, writes to the software data and direction registers
+/// have no effect on the pin. The pin can still be read.
+pub struct HardwareControlled<'i, 'p> {
+    pub(crate) common: PinCommon<'i, 'p>,
+}
+
+/// Implement PinInfo trait for hardware controlled pins.
+impl<'i, 'p> PinInfo for HardwareControlled<'i, 'p> {
+    fn port(&self) -> GpioPort {
+        self.common.port()
+    }
+
+    fn pin_number(&self) -> usize {
+        self.common.pin_number()
+    }
+
+    fn instance_number(&self) -> usize {
+        self.common.instance_number()
+    }
+}
+
+impl<'i, 'p> HardwareControlled<'i, 'p> {
+    /// Read the current pin state.
+    pub fn state(&self) -> PinState {
+        self.common.read_input_state()
+    }
+
+    /// Return the pin to software control.
+    ///
+    /// The pin is left configured as an input.
+    pub fn into_software(mut self) -> Unconfigured<'i, 'p> {
+        self.common.configure_as_input();
+        self.common.set_control_mode(ControlMode::SoftWare);
+        Unconfigured {
+            common: self.common,
+        }
+    }
+
+    /// Release the pin.
+    ///
+    /// Returns the pin to software control and returns the pad with input and
+    /// output disabled, ready to be configured for another function.
+    pub fn free(self) -> FlexPad<'p> {
+        self.into_software().free()
+    }
+}
@@ -7,6 +7,7 @@ use crate::gpio::blocking::unconfigured::Unconfigured;
 use crate::gpio::blocking::{PinCommon, PinInfo};
 use crate::gpio::{config::*, error::*, pad::*};
 use crate::instance::Numbered;
+use crate::iomux::FlexPad;
 use embedded_hal::digital::{ErrorType, InputPin, PinState};
 
 /// GPIO input pin.
@@ -54,6 +55,16 @@ impl<'i, 'p> Input<'i, 'p> {
         self.common.read_input_state()
     }
 
+    /// Raise the GPIO interrupt on `edge`; see [`PinCommon::listen`].
+    pub fn listen(&mut self, edge: Edge) -> Result<(), GpioError> {
+        self.common.listen(edge)
+    }
+
+    /// Stop raising the GPIO interrupt for this pin.
+    pub fn unlisten(&mut self) {
+        self.common.unlisten();
+    }
+
     /// Configure pull resistor.
     ///
     /// Changes the pull resistor configuration for this input pin.
@@ -68,6 +79,42 @@ impl<'i, 'p> Input<'i, 'p> {
         self.common.pull()
     }
 
+    /// Enable or disable the Schmitt trigger.
+    ///
+    /// Recommended for buttons, encoders and other slow or noisy signals on
+    /// long wires, which may otherwise toggle several times per edge.
+    pub fn set_schmitt_trigger(&mut self, enable: bool) {
+        self.common.set_schmitt_trigger(enable);
+    }
+
+    /// Check if the Schmitt trigger is enabled.
+    pub fn is_schmitt_trigger_enabled(&self) -> bool {
+        self.common.is_schmitt_trigger_enabled()
+    }
+
+    /// Enable or disable hardware debounce.
+    ///
+    /// Filters out glitches shorter than two debounce clock cycles, so mechanical
+    /// buttons can be read without software filtering loops. The length of a
+    /// cycle is set with
+    /// [`Sysctl::set_gpio_debounce_divider`](crate::sysctl::Sysctl::set_gpio_debounce_divider).
+    pub fn set_debounce(&mut self, enable: bool) -> Result<(), GpioError> {
+        self.common.set_debounce(enable)
+    }
+
+    /// Check if hardware debounce is enabled.
+    pub fn is_debounce_enabled(&self) -> bool {
+        self.common.is_debounce_enabled()
+    }
+
+    /// Release the pin.
+    ///
+    /// Stops driving the line and returns the pad with input and output disabled,
+    /// ready to be configured for another function.
+    pub fn free(self) -> FlexPad<'p> {
+        self.common.release()
+    }
+
     /// Convert to output pin.
     ///
     /// Reconfigures this pin as an output with the specified initial state and drive strength.
@@ -128,6 +175,19 @@ impl<'i, 'p> Input<'i, 'p> {
         Self::new(instance, pad, Pull::Down)
     }
 
+    /// Convenience constructor: create input pin with Schmitt trigger.
+    ///
+    /// Creates an input pin with the given pull resistor and the Schmitt trigger enabled.
+    pub fn new_schmitt<const N: usize, P: IntoGpio<'p, N>>(
+        instance: impl Numbered<'i, N, R = crate::gpio::MmioRegisterBlock<'static>>,
+        pad: P,
+        pull: Pull,
+    ) -> Self {
+        let mut pin = Self::new(instance, pad, pull);
+        pin.set_schmitt_trigger(true);
+        pin
+    }
+
     /// Block until pin goes high.
     ///
     /// Continuously polls the pin state until it reads as High.
@@ -8,28 +8,35 @@
 //! - [`Output`] - Output pins with configurable drive strength.
 //! - [`Dynamic`] - Pins that can switch between input and output modes.
 //! - [`Unconfigured`] - Unconfigured pins that can be converted to any mode.
+//! - [`HardwareControlled`] - Pins driven by the GPIO auxiliary hardware interface.
 //!
 //! # Common Functionality
 //! All pin types share common functionality through the [`PinCommon`] structure
 //! and [`PinInfo`] trait, including drive strength control and pin state reading.
 
 mod dynamic;
+mod hardware;
 mod input;
 mod output;
 mod unconfigured;
 
 use core::marker::PhantomData;
 pub use dynamic::{Dynamic, PinMode};
+pub use hardware::HardwareControlled;
 pub use input::Input;
-pub use output::Output;
+pub use output::{FastOutput, Output};
 pub use unconfigured::Unconfigured;
 // Re-export embedded-hal traits for convenience
 pub use embedded_hal::digital::{ErrorType, InputPin, OutputPin, PinState, StatefulOutputPin};
 
-use crate::gpio::config::Pull;
-use crate::gpio::{Direction, DriveStrength, GpioError, GpioPort, MmioRegisterBlock};
-use crate::iomux::FlexPad;
+use crate::gpio::config::{Edge, Pull, SlewRate};
+use crate::gpio::{
+    ControlMode, Direction, Dr, DriveStrength, Eoi, GpioError, GpioPort, MmioRegisterBlock,
+    Polarity, TriggerType,
+};
 use crate::iomux::ops::PadOps;
+use crate::iomux::pad::Strength;
+use crate::iomux::{DriveCurrent, FlexPad, drive};
 
 /// Common pin information trait.
 ///
@@ -88,14 +95,10 @@ impl<'i, 'p> PinCommon<'i, 'p> {
     /// regardless of whether it's configured as input or output.
     pub fn read_input_state(&self) -> PinState {
         match self.port {
-            GpioPort::A => self
-                .inner
-                .read_ext_porta()
+            GpioPort::A => read_reg!(self.inner, ext_porta, read_ext_porta)
                 .external_pin_state(self.pin_num)
                 .into(),
-            GpioPort::B => self
-                .inner
-                .read_ext_portb()
+            GpioPort::B => read_reg!(self.inner, ext_portb, read_ext_portb)
                 .external_pin_state(self.pin_num)
                 .into(),
         }
@@ -107,8 +110,12 @@ impl<'i, 'p> PinCommon<'i, 'p> {
     /// from the actual pin state if the pin is not configured as output.
     pub fn output_state(&self) -> PinState {
         match self.port {
-            GpioPort::A => self.inner.read_swporta_dr().pin_state(self.pin_num).into(),
-            GpioPort::B => self.inner.read_swportb_dr().pin_state(self.pin_num).into(),
+            GpioPort::A => read_reg!(self.inner, swporta_dr, read_swporta_dr)
+                .pin_state(self.pin_num)
+                .into(),
+            GpioPort::B => read_reg!(self.inner, swportb_dr, read_swportb_dr)
+                .pin_state(self.pin_num)
+                .into(),
         }
     }
 
@@ -119,16 +126,39 @@ impl<'i, 'p> PinCommon<'i, 'p> {
     pub fn set_output_state(&mut self, state: PinState) {
         match self.port {
             GpioPort::A => unsafe {
-                self.inner
-                    .modify_swporta_dr(|r| r.with_pin_state(self.pin_num, state.into()));
+                modify_reg!(self.inner, swporta_dr, modify_swporta_dr, |r| r
+                    .with_pin_state(self.pin_num, state.into()));
             },
             GpioPort::B => unsafe {
-                self.inner
-                    .modify_swportb_dr(|r| r.with_pin_state(self.pin_num, state.into()));
+                modify_reg!(self.inner, swportb_dr, modify_swportb_dr, |r| r
+                    .with_pin_state(self.pin_num, state.into()));
             },
         }
     }
 
+    /// Raw value of the output data register of this pin's port.
+    #[inline(always)]
+    pub(crate) fn port_output(&self) -> u32 {
+        match self.port {
+            GpioPort::A => read_reg!(self.inner, swporta_dr, read_swporta_dr).raw_value(),
+            GpioPort::B => read_reg!(self.inner, swportb_dr, read_swportb_dr).raw_value(),
+        }
+    }
+
+    /// Write the output data register of this pin's port without reading it.
+    ///
+    /// Every pin of the port takes its state from `value`.
+    #[inline(always)]
+    pub(crate) fn write_port_output(&mut self, value: u32) {
+        let value = Dr::new_with_raw_value(value);
+        unsafe {
+            match self.port {
+                GpioPort::A => write_reg!(self.inner, swporta_dr, write_swporta_dr, value),
+                GpioPort::B => write_reg!(self.inner, swportb_dr, write_swportb_dr, value),
+            }
+        }
+    }
+
     /// Configure pull resistor setting.
     ///
     /// Sets the internal pull resistor configuration for this pin.
@@ -143,11 +173,44 @@ impl<'i, 'p> PinCommon<'i, 'p> {
         self.pad.pull().ok_or(GpioError::HardwareError)
     }
 
+    /// Pad number of this pin, as used by the IOMUX.
+    pub fn pad_number(&self) -> usize {
+        self.numbered * 32 + self.pin_num
+    }
+
     /// Set output drive strength.
     ///
     /// Configures the output drive strength, affecting current capability and switching speed.
+    /// Levels above what the pad's voltage domain supports are clamped to its strongest code.
     pub fn set_drive_strength(&mut self, drive_strength: DriveStrength) {
-        self.pad.set_drive_strength(drive_strength.into());
+        let max = drive::max_strength(self.pad_number());
+        let code: Strength = drive_strength.into();
+        let code = if code as u8 > max as u8 { max } else { code };
+        self.pad.set_drive_strength(code);
+    }
+
+    /// Set a raw drive strength code.
+    ///
+    /// Fails with [`GpioError::InvalidDriveStrength`] if the pad's voltage
+    /// domain does not support the code.
+    pub fn set_drive_strength_code(&mut self, code: Strength) -> Result<(), GpioError> {
+        if !drive::is_valid_strength(self.pad_number(), code) {
+            return Err(GpioError::InvalidDriveStrength);
+        }
+        self.pad.set_drive_strength(code);
+        Ok(())
+    }
+
+    /// Get the raw drive strength code.
+    pub fn drive_strength_code(&self) -> Strength {
+        self.pad.drive_strength()
+    }
+
+    /// Approximate source and sink current of the current drive strength.
+    ///
+    /// Returns `None` if the pad holds a code its voltage domain does not support.
+    pub fn drive_current(&self) -> Option<DriveCurrent> {
+        drive::drive_current(self.pad_number(), self.pad.drive_strength())
     }
 
     /// Get current drive strength setting.
@@ -157,18 +220,163 @@ impl<'i, 'p> PinCommon<'i, 'p> {
         self.pad.drive_strength().into()
     }
 
+    /// Enable or disable the input Schmitt trigger.
+    ///
+    /// Adds hysteresis to the input buffer, so slow or noisy edges on long
+    /// wires are seen as a single transition.
+    pub fn set_schmitt_trigger(&mut self, enable: bool) {
+        self.pad.set_schmitt_trigger(enable);
+    }
+
+    /// Check if the input Schmitt trigger is enabled.
+    pub fn is_schmitt_trigger_enabled(&self) -> bool {
+        self.pad.is_schmitt_trigger_enabled()
+    }
+
+    /// Set output slew rate.
+    ///
+    /// A slow slew rate reduces ringing and EMI on long traces at the cost of
+    /// slower edges.
+    pub fn set_slew_rate(&mut self, slew_rate: SlewRate) {
+        self.pad.set_slew_rate(slew_rate);
+    }
+
+    /// Get current output slew rate setting.
+    pub fn slew_rate(&self) -> SlewRate {
+        self.pad.slew_rate()
+    }
+
+    /// Raise the GPIO interrupt on `edge`.
+    ///
+    /// Any interrupt still pending from an earlier configuration is cleared.
+    /// Interrupts are only implemented for port A; pins on port B return
+    /// [`GpioError::IncompatibleMode`].
+    pub fn listen(&mut self, edge: Edge) -> Result<(), GpioError> {
+        if self.port != GpioPort::A {
+            return Err(GpioError::IncompatibleMode);
+        }
+        let pin = self.pin_num;
+        let polarity = match edge {
+            Edge::Falling => Polarity::ActiveLow,
+            Edge::Rising | Edge::Both => Polarity::ActiveHigh,
+        };
+        unsafe {
+            modify_reg!(self.inner, inten, modify_inten, |r| r
+                .with_interrupt_enable(pin, false));
+            modify_reg!(self.inner, inttype_level, modify_inttype_level, |r| r
+                .with_trigger_type(pin, TriggerType::Edge));
+            modify_reg!(self.inner, int_polarity, modify_int_polarity, |r| r
+                .with_interrupt_polarity(pin, polarity));
+            modify_reg!(self.inner, int_both_edge, modify_int_both_edge, |r| r
+                .with_both_edge_enable(pin, edge == Edge::Both));
+            modify_reg!(self.inner, intmask, modify_intmask, |r| r
+                .with_interrupt_mask(pin, false));
+            write_reg!(
+                self.inner,
+                porta_eoi,
+                write_porta_eoi,
+                Eoi::new_with_raw_value(1 << pin)
+            );
+            modify_reg!(self.inner, inten, modify_inten, |r| r
+                .with_interrupt_enable(pin, true));
+        }
+        Ok(())
+    }
+
+    /// Stop raising the GPIO interrupt for this pin.
+    pub fn unlisten(&mut self) {
+        if self.port == GpioPort::A {
+            unsafe {
+                modify_reg!(self.inner, inten, modify_inten, |r| r
+                    .with_interrupt_enable(self.pin_num, false))
+            };
+        }
+    }
+
+    /// Enable or disable the hardware debounce filter.
+    ///
+    /// When enabled, the input is sampled on the debounce clock and must be stable
+    /// for two debounce clock cycles before a change is seen by software.
+    /// The debounce clock is divided in the system controller, see
+    /// [`Sysctl::set_gpio_debounce_divider`](crate::sysctl::Sysctl::set_gpio_debounce_divider).
+    /// Debounce is only implemented for port A; pins on port B return
+    /// [`GpioError::IncompatibleMode`].
+    pub fn set_debounce(&mut self, enable: bool) -> Result<(), GpioError> {
+        match self.port {
+            GpioPort::A => {
+                unsafe {
+                    modify_reg!(self.inner, debounce, modify_debounce, |r| r
+                        .with_debounce_enable(self.pin_num, enable));
+                }
+                Ok(())
+            }
+            GpioPort::B => Err(GpioError::IncompatibleMode),
+        }
+    }
+
+    /// Check if the hardware debounce filter is enabled.
+    pub fn is_debounce_enabled(&self) -> bool {
+        match self.port {
+            GpioPort::A => {
+                read_reg!(self.inner, debounce, read_debounce).debounce_enable(self.pin_num)
+            }
+            GpioPort::B => false,
+        }
+    }
+
+    /// Get the control mode of the pin.
+    pub fn control_mode(&self) -> ControlMode {
+        match self.port {
+            GpioPort::A => {
+                read_reg!(self.inner, swporta_ctl, read_swporta_ctl).control_mode(self.pin_num)
+            }
+            GpioPort::B => {
+                read_reg!(self.inner, swportb_ctl, read_swportb_ctl).control_mode(self.pin_num)
+            }
+        }
+    }
+
+    /// Check if the port of this pin was synthesized with hardware control.
+    pub fn supports_hardware_control(&self) -> bool {
+        let config = read_reg!(self.inner, config_reg1, read_config_reg1);
+        match self.port {
+            GpioPort::A => config.hw_porta_enable(),
+            GpioPort::B => config.hw_portb_enable(),
+        }
+    }
+
+    /// Internal method: select software or hardware control of the pin.
+    pub(crate) fn set_control_mode(&mut self, mode: ControlMode) {
+        unsafe {
+            match self.port {
+                GpioPort::A => modify_reg!(self.inner, swporta_ctl, modify_swporta_ctl, |r| r
+                    .with_control_mode(self.pin_num, mode)),
+                GpioPort::B => modify_reg!(self.inner, swportb_ctl, modify_swportb_ctl, |r| r
+                    .with_control_mode(self.pin_num, mode)),
+            }
+        }
+    }
+
+    /// Internal method: deconfigure the pin and return its pad.
+    ///
+    /// Switches the pin back to input so it no longer drives the line,
+    /// then disables input and output on the pad.
+    pub(crate) fn release(mut self) -> FlexPad<'p> {
+        self.configure_as_input();
+        self.pad.set_disabled();
+        self.pad
+    }
+
     /// Internal method: configure pin as input.
     ///
     /// Sets the data direction register to configure this pin as an input.
     pub(crate) fn configure_as_input(&mut self) {
         unsafe {
             match self.port {
-                GpioPort::A => self
-                    .inner
-                    .modify_swporta_ddr(|r| r.with_direction(self.pin_num, Direction::Input)),
-                GpioPort::B => self
-                    .inner
-                    .modify_swportb_ddr(|r| r.with_direction(self.pin_num, Direction::Input)),
+                GpioPort::A => modify_reg!(self.inner, swporta_ddr, modify_swporta_ddr, |r| r
+                    .with_direction(self.pin_num, Direction::Input)),
+                GpioPort::B => modify_reg!(self.inner, swportb_ddr, modify_swportb_ddr, |r| r
+                    .with_direction(self.pin_num, Direction::Input)),
             }
         }
     }
@@ -180,16 +388,16 @@ impl<'i, 'p> PinCommon<'i, 'p> {
         unsafe {
             match self.port {
                 GpioPort::A => {
-                    self.inner
-                        .modify_swporta_ddr(|r| r.with_direction(self.pin_num, Direction::Output));
-                    self.inner
-                        .modify_swporta_dr(|r| r.with_pin_state(self.pin_num, pin_state.into()))
+                    modify_reg!(self.inner, swporta_ddr, modify_swporta_ddr, |r| r
+                        .with_direction(self.pin_num, Direction::Output));
+                    modify_reg!(self.inner, swporta_dr, modify_swporta_dr, |r| r
+                        .with_pin_state(self.pin_num, pin_state.into()))
                 }
                 GpioPort::B => {
-                    self.inner
-                        .modify_swportb_ddr(|r| r.with_direction(self.pin_num, Direction::Output));
-                    self.inner
-                        .modify_swportb_dr(|r| r.with_pin_state(self.pin_num, pin_state.into()))
+                    modify_reg!(self.inner, swportb_ddr, modify_swportb_ddr, |r| r
+                        .with_direction(self.pin_num, Direction::Output));
+                    modify_reg!(self.inner, swportb_dr, modify_swportb_dr, |r| r
+                        .with_pin_state(self.pin_num, pin_state.into()))
                 }
             }
         }
@@ -7,6 +7,8 @@ use crate::gpio::blocking::unconfigured::Unconfigured;
 use crate::gpio::blocking::{PinCommon, PinInfo};
 use crate::gpio::{MmioRegisterBlock, config::*, error::*, pad::*};
 use crate::instance::Numbered;
+use crate::iomux::pad::Strength;
+use crate::iomux::{DriveCurrent, FlexPad};
 use embedded_hal::digital::{ErrorType, OutputPin, PinState, StatefulOutputPin};
 
 /// GPIO output pin.
@@ -17,6 +19,42 @@ pub struct Output<'i, 'p> {
     pub(crate) common: PinCommon<'i, 'p>,
 }
 
+/// Output pin that drives its level with a single register write.
+///
+/// Created by [`Output::fast`]. The state of the port's other pins is taken
+/// when it is created and written back unchanged with every edge, so they
+/// must not be changed, from this hart, an interrupt handler or another hart,
+/// while it exists; such a change is undone by the next edge.
+pub struct FastOutput<'a, 'i, 'p> {
+    pin: &'a mut Output<'i, 'p>,
+    /// Data register value last written.
+    value: u32,
+    mask: u32,
+}
+
+impl FastOutput<'_, '_, '_> {
+    /// Drive the pin high.
+    #[inline(always)]
+    pub fn set_high(&mut self) {
+        self.value |= self.mask;
+        self.pin.common.write_port_output(self.value);
+    }
+
+    /// Drive the pin low.
+    #[inline(always)]
+    pub fn set_low(&mut self) {
+        self.value &= !self.mask;
+        self.pin.common.write_port_output(self.value);
+    }
+
+    /// Invert the pin.
+    #[inline(always)]
+    pub fn toggle(&mut self) {
+        self.value ^= self.mask;
+        self.pin.common.write_port_output(self.value);
+    }
+}
+
 /// Implement PinInfo trait for Output pins.
 impl<'i, 'p> PinInfo for Output<'i, 'p> {
     fn port(&self) -> GpioPort {
@@ -60,6 +98,22 @@ impl<'i, 'p> Output<'i, 'p> {
         Ok(())
     }
 
+    /// Borrow the pin for toggling with a single register write per edge.
+    ///
+    /// Intended for bit-banged protocols. The GPIO block has no set or clear
+    /// registers, so [`set_state`](Self::set_state) has to read the port
+    /// data register before writing it, and the uncached read is the slower
+    /// half. [`FastOutput`] reads the register once here and then only
+    /// writes it. See its documentation for what this costs.
+    #[inline]
+    pub fn fast(&mut self) -> FastOutput<'_, 'i, 'p> {
+        FastOutput {
+            mask: 1 << self.common.pin_num,
+            value: self.common.port_output(),
+            pin: self,
+        }
+    }
+
     /// Read the current output register state.
     ///
     /// Returns the state stored in the output data register.
@@ -81,6 +135,37 @@ impl<'i, 'p> Output<'i, 'p> {
         self.common.drive_strength()
     }
 
+    /// Set a raw drive strength code, validated against the pad's voltage domain.
+    pub fn set_drive_strength_code(&mut self, code: Strength) -> Result<(), GpioError> {
+        self.common.set_drive_strength_code(code)
+    }
+
+    /// Approximate source and sink current of the current drive strength.
+    pub fn drive_current(&self) -> Option<DriveCurrent> {
+        self.common.drive_current()
+    }
+
+    /// Set output slew rate.
+    ///
+    /// Use [`SlewRate::Slow`] for long lines such as LED strips, where fast
+    /// edges cause ringing and EMI.
+    pub fn set_slew_rate(&mut self, slew_rate: SlewRate) {
+        self.common.set_slew_rate(slew_rate);
+    }
+
+    /// Get current output slew rate setting.
+    pub fn slew_rate(&self) -> SlewRate {
+        self.common.slew_rate()
+    }
+
+    /// Release the pin.
+    ///
+    /// Stops driving the line and returns the pad with input and output disabled,
+    /// ready to be configured for another function.
+    pub fn free(self) -> FlexPad<'p> {
+        self.common.release()
+    }
+
     /// Convert to input pin.
     ///
     /// Reconfigures this pin as an input with the specified pull resistor setting.
@@ -107,6 +192,22 @@ impl<'i, 'p> Output<'i, 'p> {
         }
     }
 
+    /// Create a new output pin with the given slew rate.
+    ///
+    /// The slew rate is set before the pin starts driving, so the first edge
+    /// already uses it.
+    pub fn new_with_slew_rate<const N: usize, P: IntoGpio<'p, N>>(
+        instance: impl Numbered<'i, N, R = MmioRegisterBlock<'static>>,
+        pad: P,
+        state: PinState,
+        drive_strength: DriveStrength,
+        slew_rate: SlewRate,
+    ) -> Self {
+        let mut pin = Unconfigured::new(instance, pad);
+        pin.common.set_slew_rate(slew_rate);
+        pin.into_output(state, drive_strength)
+    }
+
     /// Convenience constructor: create high output pin.
     ///
     /// Creates an output pin with initial High state and default drive strength.
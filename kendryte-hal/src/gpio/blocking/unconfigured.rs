@@ -5,8 +5,13 @@
 
 use crate::gpio::blocking::{PinCommon, PinInfo};
 use crate::gpio::config::Pull;
-use crate::gpio::{DriveStrength, Dynamic, GpioPort, IntoGpio, MmioRegisterBlock};
+use crate::gpio::{
+    ControlMode, DriveStrength, Dynamic, GpioError, GpioPort, HardwareControlled, IntoGpio,
+    MmioRegisterBlock,
+};
 use crate::instance::{Instance, Numbered};
+use crate::iomux::FlexPad;
+use crate::iomux::ops::PadOps;
 use core::marker::PhantomData;
 use embedded_hal::digital::PinState;
 
@@ -62,6 +67,37 @@ impl<'i, 'p> Unconfigured<'i, 'p> {
         Self { common }
     }
 
+    /// Like [`Unconfigured::new`], but first checks that the instance is a
+    /// GPIO controller; see [`crate::ident`].
+    ///
+    /// The pad is left untouched if the check fails.
+    pub fn try_new<const N: usize, P: IntoGpio<'p, N>>(
+        instance: impl Numbered<'i, N, R = MmioRegisterBlock<'static>>,
+        pad: P,
+    ) -> Result<Self, GpioError> {
+        let inner = instance.inner();
+        crate::ident::gpio(&inner)?;
+
+        let common = PinCommon {
+            inner,
+            pad: pad.into_gpio(),
+            numbered: N,
+            port: P::PORT,
+            pin_num: P::PIN_NUM,
+            _marker: PhantomData,
+        };
+
+        Ok(Self { common })
+    }
+
+    /// Release the pin.
+    ///
+    /// Stops driving the line and returns the pad with input and output disabled,
+    /// ready to be configured for another function.
+    pub fn free(self) -> FlexPad<'p> {
+        self.common.release()
+    }
+
     /// Convert to input pin.
     ///
     /// Configures the pin for input operations with the specified pull resistor.
@@ -105,4 +141,25 @@ impl<'i, 'p> Unconfigured<'i, 'p> {
             mode: super::dynamic::PinMode::Unconfigured,
         }
     }
+
+    /// Hand the pin over to hardware control.
+    ///
+    /// The pad is set up for both directions and the pin's data and direction
+    /// are then taken from the GPIO auxiliary hardware interface. Returns the
+    /// pin unchanged if its port was not synthesized with hardware control.
+    /// See [`HardwareControlled`] for which signals can drive the pin.
+    pub fn into_hardware_controlled(
+        mut self,
+        drive_strength: DriveStrength,
+    ) -> Result<HardwareControlled<'i, 'p>, Self> {
+        if !self.common.supports_hardware_control() {
+            return Err(self);
+        }
+        self.common.set_drive_strength(drive_strength);
+        self.common.pad.set_bidirectional();
+        self.common.set_control_mode(ControlMode::Hardware);
+        Ok(HardwareControlled {
+            common: self.common,
+        })
+    }
 }
@@ -1,9 +1,10 @@
 //! GPIO configuration types and enums.
 //!
 //! This module defines configuration options for GPIO pins, including
-//! drive strength levels and pull resistor settings.
+//! drive strength levels, pull resistor and slew rate settings.
 
 pub use crate::iomux::ops::Pull;
+pub use crate::iomux::pad::SlewRate;
 
 use crate::iomux::pad::Strength;
 
@@ -53,3 +54,14 @@ impl From<Strength> for DriveStrength {
         }
     }
 }
+
+/// Input transition that raises a GPIO interrupt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    /// Low to high transition.
+    Rising,
+    /// High to low transition.
+    Falling,
+    /// Either transition.
+    Both,
+}
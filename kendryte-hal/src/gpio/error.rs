@@ -7,6 +7,7 @@
 ///
 /// These errors can occur during GPIO pin configuration and operation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum GpioError {
     /// Pin configuration failed during setup.
     ConfigurationFailed,
@@ -16,6 +17,10 @@ pub enum GpioError {
     IncompatibleMode,
     /// Operation timed out waiting for condition.
     Timeout,
+    /// Drive strength code not supported by the pad's voltage domain.
+    InvalidDriveStrength,
+    /// The identification registers read back this value instead of a GPIO controller's.
+    IdMismatch(u32),
 }
 
 impl core::fmt::Display for GpioError {
@@ -25,14 +30,14 @@ impl core::fmt::Display for GpioError {
             Self::HardwareError => write!(f, "Hardware access error"),
             Self::IncompatibleMode => write!(f, "Pin mode not compatible with operation"),
             Self::Timeout => write!(f, "Operation timeout"),
+            Self::InvalidDriveStrength => write!(f, "Drive strength not supported by pad"),
+            Self::IdMismatch(id) => write!(f, "Not a GPIO controller (read {id:#010x})"),
         }
     }
 }
 
 impl embedded_hal::digital::Error for GpioError {
     fn kind(&self) -> embedded_hal::digital::ErrorKind {
-        match self {
-            _ => embedded_hal::digital::ErrorKind::Other,
-        }
+        crate::Error::from(*self).kind().into()
     }
 }
@@ -0,0 +1,119 @@
+//! Interrupt fed queue of GPIO edge events.
+//!
+//! The GPIO interrupt handler records every edge with the machine timer
+//! value at which it was serviced, and application code drains the events
+//! whenever it gets around to it. Button presses and encoder steps are not
+//! lost while the main loop is busy, only delayed.
+//!
+//! # Example
+//! ```ignore
+//! static EVENTS: EventQueue<32> = EventQueue::new();
+//! static GPIO0: Mutex<Option<(MmioRegisterBlock<'static>, Producer<'static, Event, 32>)>> =
+//!     Mutex::new(None);
+//!
+//! // In main:
+//! let (producer, mut events) = EVENTS.split().unwrap();
+//! GPIO0.lock(|g| *g = Some((gpio0, producer)));
+//!
+//! // In the GPIO0 interrupt handler:
+//! GPIO0.lock(|g| g.as_mut().map(|(gpio0, producer)| on_interrupt(producer, 0, gpio0)));
+//!
+//! // In the main loop:
+//! while let Some(event) = events.pop() {
+//!     writeln!(uart, "pad {} {:?} at {}", event.pin, event.edge, event.timestamp).ok();
+//! }
+//! ```
+
+use crate::gpio::config::Edge;
+use crate::gpio::{Eoi, MmioRegisterBlock, Polarity};
+use crate::sync::{Producer, Queue};
+
+/// An edge seen on a GPIO pin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event {
+    /// Pad number of the pin, `instance * 32 + pin`.
+    pub pin: u8,
+    /// Direction of the transition; never [`Edge::Both`].
+    pub edge: Edge,
+    /// Machine timer value when the interrupt was serviced.
+    pub timestamp: u64,
+}
+
+/// Queue of [`Event`]s, split into the handles fed by [`on_interrupt`] and
+/// drained by the main loop.
+pub type EventQueue<const N: usize> = Queue<Event, N>;
+
+/// Handles a GPIO interrupt of instance `instance`.
+///
+/// Pushes an event for every pending pin to `events` and clears it. Pins
+/// listening on both edges are classified by their level when serviced, so a
+/// pulse shorter than the interrupt latency is reported with the wrong edge.
+#[cfg_attr(
+    feature = "ramfunc",
+    unsafe(link_section = ".ramfunc.gpio_on_interrupt")
+)]
+pub fn on_interrupt<const N: usize>(
+    events: &mut Producer<'_, Event, N>,
+    instance: usize,
+    inner: &mut MmioRegisterBlock,
+) {
+    let status = read_reg!(inner, intstatus, read_intstatus).raw_value();
+    if status == 0 {
+        return;
+    }
+    let timestamp = crate::time::now();
+    let level = read_reg!(inner, ext_porta, read_ext_porta);
+    let polarity = read_reg!(inner, int_polarity, read_int_polarity);
+    let both_edge = read_reg!(inner, int_both_edge, read_int_both_edge);
+    for pin in (0..32).filter(|pin| status & (1 << pin) != 0) {
+        let high = if both_edge.both_edge_enable(pin) {
+            level.external_pin_state(pin)
+        } else {
+            polarity.interrupt_polarity(pin) == Polarity::ActiveHigh
+        };
+        events.push(Event {
+            pin: (instance * 32 + pin) as u8,
+            edge: if high { Edge::Rising } else { Edge::Falling },
+            timestamp,
+        });
+    }
+    unsafe {
+        write_reg!(
+            inner,
+            porta_eoi,
+            write_porta_eoi,
+            Eoi::new_with_raw_value(status)
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpio::{IntBothEdge, IntPolarity, IntStatus, RegisterBlock};
+    use crate::mock;
+
+    #[test]
+    fn edges_from_pending_pins() {
+        let block = mock::block::<RegisterBlock>();
+        let mut gpio = unsafe { RegisterBlock::new_mmio(block) };
+        unsafe {
+            (*block).intstatus = IntStatus::new_with_raw_value(0b101);
+            gpio.write_int_polarity(IntPolarity::new_with_raw_value(0b001));
+            gpio.write_int_both_edge(IntBothEdge::new_with_raw_value(0b100));
+        }
+        let queue = EventQueue::<4>::new();
+        let (mut producer, mut events) = queue.split().unwrap();
+
+        on_interrupt(&mut producer, 1, &mut gpio);
+        let event = |pin, edge| Event {
+            pin,
+            edge,
+            timestamp: 0,
+        };
+        assert_eq!(events.pop(), Some(event(32, Edge::Rising)));
+        assert_eq!(events.pop(), Some(event(34, Edge::Falling)));
+        assert_eq!(events.pop(), None);
+        assert_eq!(unsafe { (*block).porta_eoi.raw_value() }, 0b101);
+    }
+}
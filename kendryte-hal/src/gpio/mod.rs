@@ -7,7 +7,11 @@
 //! - Input pins with configurable pull-up/pull-down resistors.
 //! - Output pins with configurable drive strength.
 //! - Dynamic pins that can switch between input and output modes.
+//! - Type-state pins whose mode is part of their type.
 //! - Blocking operations for edge detection and state changes.
+//! - An interrupt fed queue of timestamped edge events.
+//! - Hand-over of pins to the GPIO auxiliary hardware interface.
+//! - Snapshot and restore of the controller configuration.
 //! - Full embedded-hal compatibility.
 //!
 //! # Example
@@ -28,18 +32,49 @@
 //! }
 //! ```
 
+crate::trace::traced_registers!($ "gpio", crate::gpio::RegisterBlock);
+
 pub mod blocking;
 pub mod config;
 pub mod error;
+pub mod event;
 pub mod pad;
 pub mod register;
+pub mod snapshot;
+pub mod typestate;
 
 // Re-export core types for convenient access
-pub use blocking::{Dynamic, Input, Output, PinCommon, PinInfo, PinMode, Unconfigured};
-pub use config::DriveStrength;
+pub use blocking::{
+    Dynamic, FastOutput, HardwareControlled, Input, Output, PinCommon, PinInfo, PinMode,
+    Unconfigured,
+};
+pub use config::{DriveStrength, Edge, SlewRate};
 pub use error::GpioError;
+pub use event::{Event, EventQueue};
 pub use pad::{GpioPort, IntoGpio};
 pub use register::*;
+pub use snapshot::GpioSnapshot;
 
 // Re-export embedded-hal traits for convenience
 pub use embedded_hal::digital::*;
+
+crate::trace::impl_raw!(
+    Dr,
+    Ddr,
+    Ctl,
+    IntEn,
+    IntMask,
+    IntTypeLevel,
+    IntPolarity,
+    IntStatus,
+    RawIntStatus,
+    Debounce,
+    Eoi,
+    Ext,
+    LsSync,
+    IdCode,
+    IntBothEdge,
+    VerIdCode,
+    ConfigReg2,
+    ConfigReg1,
+);
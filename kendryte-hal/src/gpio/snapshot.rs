@@ -0,0 +1,188 @@
+//! GPIO controller snapshot and restore.
+//!
+//! Captures the data, direction and interrupt configuration of a GPIO
+//! controller and writes it back later, for firmware that must return the
+//! hardware to a known state before handing over to another image. Use it
+//! together with [`IomuxSnapshot`](crate::iomux::IomuxSnapshot), which
+//! covers the pad multiplexing:
+//!
+//! ```ignore
+//! let gpio0 = GpioSnapshot::capture(&mut gpio0_regs);
+//! let pads = IomuxSnapshot::capture(&mut iomux);
+//! // ... load the next image ...
+//! gpio0.restore(&mut gpio0_regs);
+//! pads.restore(&mut iomux);
+//! ```
+
+use crate::gpio::register::*;
+
+/// Raw register values of one GPIO controller.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct GpioSnapshot {
+    /// Port A output data.
+    pub porta_dr: u32,
+    /// Port A direction.
+    pub porta_ddr: u32,
+    /// Port A data source.
+    pub porta_ctl: u32,
+    /// Port B output data.
+    pub portb_dr: u32,
+    /// Port B direction.
+    pub portb_ddr: u32,
+    /// Port B data source.
+    pub portb_ctl: u32,
+    /// Interrupt enable.
+    pub inten: u32,
+    /// Interrupt mask.
+    pub intmask: u32,
+    /// Level or edge triggering.
+    pub inttype_level: u32,
+    /// Interrupt polarity.
+    pub int_polarity: u32,
+    /// Both edge triggering.
+    pub int_both_edge: u32,
+    /// Debounce enable.
+    pub debounce: u32,
+    /// Level interrupt synchronization.
+    pub ls_sync: u32,
+}
+
+impl GpioSnapshot {
+    /// Reads the configuration of a GPIO controller.
+    pub fn capture(gpio: &mut MmioRegisterBlock<'static>) -> Self {
+        Self {
+            porta_dr: read_reg!(gpio, swporta_dr, read_swporta_dr).raw_value(),
+            porta_ddr: read_reg!(gpio, swporta_ddr, read_swporta_ddr).raw_value(),
+            porta_ctl: read_reg!(gpio, swporta_ctl, read_swporta_ctl).raw_value(),
+            portb_dr: read_reg!(gpio, swportb_dr, read_swportb_dr).raw_value(),
+            portb_ddr: read_reg!(gpio, swportb_ddr, read_swportb_ddr).raw_value(),
+            portb_ctl: read_reg!(gpio, swportb_ctl, read_swportb_ctl).raw_value(),
+            inten: read_reg!(gpio, inten, read_inten).raw_value(),
+            intmask: read_reg!(gpio, intmask, read_intmask).raw_value(),
+            inttype_level: read_reg!(gpio, inttype_level, read_inttype_level).raw_value(),
+            int_polarity: read_reg!(gpio, int_polarity, read_int_polarity).raw_value(),
+            int_both_edge: read_reg!(gpio, int_both_edge, read_int_both_edge).raw_value(),
+            debounce: read_reg!(gpio, debounce, read_debounce).raw_value(),
+            ls_sync: read_reg!(gpio, ls_sync, read_ls_sync).raw_value(),
+        }
+    }
+
+    /// Writes the configuration back to a GPIO controller.
+    ///
+    /// Interrupts are disabled while the controller is reprogrammed. Output
+    /// data is written before the direction, so pins turning into outputs
+    /// start at their saved level, and edges latched in between are cleared
+    /// before interrupts are enabled again.
+    pub fn restore(&self, gpio: &mut MmioRegisterBlock<'static>) {
+        unsafe {
+            write_reg!(gpio, inten, write_inten, IntEn::new_with_raw_value(0));
+            write_reg!(
+                gpio,
+                swporta_dr,
+                write_swporta_dr,
+                Dr::new_with_raw_value(self.porta_dr)
+            );
+            write_reg!(
+                gpio,
+                swporta_ddr,
+                write_swporta_ddr,
+                Ddr::new_with_raw_value(self.porta_ddr)
+            );
+            write_reg!(
+                gpio,
+                swporta_ctl,
+                write_swporta_ctl,
+                Ctl::new_with_raw_value(self.porta_ctl)
+            );
+            write_reg!(
+                gpio,
+                swportb_dr,
+                write_swportb_dr,
+                Dr::new_with_raw_value(self.portb_dr)
+            );
+            write_reg!(
+                gpio,
+                swportb_ddr,
+                write_swportb_ddr,
+                Ddr::new_with_raw_value(self.portb_ddr)
+            );
+            write_reg!(
+                gpio,
+                swportb_ctl,
+                write_swportb_ctl,
+                Ctl::new_with_raw_value(self.portb_ctl)
+            );
+            write_reg!(
+                gpio,
+                inttype_level,
+                write_inttype_level,
+                IntTypeLevel::new_with_raw_value(self.inttype_level)
+            );
+            write_reg!(
+                gpio,
+                int_polarity,
+                write_int_polarity,
+                IntPolarity::new_with_raw_value(self.int_polarity)
+            );
+            write_reg!(
+                gpio,
+                int_both_edge,
+                write_int_both_edge,
+                IntBothEdge::new_with_raw_value(self.int_both_edge)
+            );
+            write_reg!(
+                gpio,
+                debounce,
+                write_debounce,
+                Debounce::new_with_raw_value(self.debounce)
+            );
+            write_reg!(
+                gpio,
+                ls_sync,
+                write_ls_sync,
+                LsSync::new_with_raw_value(self.ls_sync)
+            );
+            write_reg!(
+                gpio,
+                porta_eoi,
+                write_porta_eoi,
+                Eoi::new_with_raw_value(u32::MAX)
+            );
+            write_reg!(
+                gpio,
+                intmask,
+                write_intmask,
+                IntMask::new_with_raw_value(self.intmask)
+            );
+            write_reg!(
+                gpio,
+                inten,
+                write_inten,
+                IntEn::new_with_raw_value(self.inten)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+
+    #[test]
+    fn round_trip() {
+        let snapshot = GpioSnapshot {
+            porta_dr: 0x0000_00F0,
+            porta_ddr: 0x0000_00FF,
+            inten: 0x0000_0003,
+            intmask: 0x0000_0002,
+            int_polarity: 0x0000_0001,
+            debounce: 0x0000_0001,
+            ..GpioSnapshot::default()
+        };
+        let mut gpio = unsafe { RegisterBlock::new_mmio(mock::block::<RegisterBlock>()) };
+        snapshot.restore(&mut gpio);
+        assert_eq!(GpioSnapshot::capture(&mut gpio), snapshot);
+    }
+}
@@ -0,0 +1,222 @@
+//! Type-state GPIO pins.
+//!
+//! [`Pin`] carries its GPIO instance, pin number and mode in its type, like
+//! the pins of bouffalo-hal and other RustSBI HALs, so drivers written
+//! against those APIs port over with few changes. Mode conversions consume
+//! the pin and only program the registers of the new mode; there is no
+//! mode check at runtime, and a pin in the wrong mode does not compile.
+//!
+//! ```ignore
+//! use kendryte_hal::gpio::typestate::Pin;
+//!
+//! let led = Pin::<0, 5>::new(p.gpio0, p.iomux.io5).into_push_pull_output();
+//! let button = Pin::<0, 4>::new(p.gpio0, p.iomux.io4).into_pull_up_input();
+//! ```
+//!
+//! The runtime-mode types in [`blocking`](super::blocking) stay available;
+//! [`Pin::into_dynamic`] hands a pin over to them.
+
+use crate::gpio::blocking::{Dynamic, PinCommon, PinInfo, Unconfigured};
+use crate::gpio::config::{DriveStrength, Pull, SlewRate};
+use crate::gpio::{GpioError, GpioPort, IntoGpio, MmioRegisterBlock};
+use crate::instance::Numbered;
+use crate::iomux::FlexPad;
+use core::marker::PhantomData;
+use embedded_hal::digital::{ErrorType, InputPin, OutputPin, PinState, StatefulOutputPin};
+
+/// Pin mode: the pad is routed to GPIO but neither pull nor output is set up.
+pub struct Disabled;
+
+/// Pin mode: input with pull resistor configuration `PULL`.
+pub struct Input<PULL> {
+    _pull: PhantomData<PULL>,
+}
+
+/// Pin mode: output with drive configuration `MODE`.
+pub struct Output<MODE> {
+    _mode: PhantomData<MODE>,
+}
+
+/// Input without pull resistor.
+pub struct Floating;
+
+/// Input with pull-up resistor.
+pub struct PullUp;
+
+/// Input with pull-down resistor.
+pub struct PullDown;
+
+/// Output driving both levels.
+pub struct PushPull;
+
+/// GPIO pin `N` of GPIO instance `I` in mode `M`.
+pub struct Pin<'i, 'p, const I: usize, const N: usize, M = Disabled> {
+    common: PinCommon<'i, 'p>,
+    _mode: PhantomData<M>,
+}
+
+impl<'i, 'p, const I: usize, const N: usize> Pin<'i, 'p, I, N, Disabled> {
+    /// Route `pad` to GPIO as pin `N` of `instance`.
+    ///
+    /// Fails to compile if `pad` is not pin `N` of GPIO instance `I`.
+    pub fn new<P: IntoGpio<'p, I>>(
+        instance: impl Numbered<'i, I, R = MmioRegisterBlock<'static>>,
+        pad: P,
+    ) -> Self {
+        const { assert!(P::PIN_NUM == N, "pad is not this GPIO pin") };
+        Self::from_common(Unconfigured::new(instance, pad).common)
+    }
+}
+
+impl<'i, 'p, const I: usize, const N: usize, M> Pin<'i, 'p, I, N, M> {
+    #[inline]
+    fn from_common(common: PinCommon<'i, 'p>) -> Self {
+        Pin {
+            common,
+            _mode: PhantomData,
+        }
+    }
+
+    #[inline]
+    fn into_mode<M2>(self) -> Pin<'i, 'p, I, N, M2> {
+        Pin::from_common(self.common)
+    }
+
+    /// Configure the pin as an input without pull resistor.
+    pub fn into_floating_input(self) -> Pin<'i, 'p, I, N, Input<Floating>> {
+        self.into_input(Pull::None)
+    }
+
+    /// Configure the pin as an input with pull-up resistor.
+    pub fn into_pull_up_input(self) -> Pin<'i, 'p, I, N, Input<PullUp>> {
+        self.into_input(Pull::Up)
+    }
+
+    /// Configure the pin as an input with pull-down resistor.
+    pub fn into_pull_down_input(self) -> Pin<'i, 'p, I, N, Input<PullDown>> {
+        self.into_input(Pull::Down)
+    }
+
+    /// Configure the pin as a push-pull output driving low.
+    pub fn into_push_pull_output(self) -> Pin<'i, 'p, I, N, Output<PushPull>> {
+        self.into_push_pull_output_in_state(PinState::Low)
+    }
+
+    /// Configure the pin as a push-pull output driving `state`.
+    ///
+    /// The output register is written before the pin starts driving, so the
+    /// line never glitches to the other level.
+    pub fn into_push_pull_output_in_state(
+        mut self,
+        state: PinState,
+    ) -> Pin<'i, 'p, I, N, Output<PushPull>> {
+        self.common
+            .set_drive_strength(crate::gpio::Output::DEFAULT_DRIVE_STRENGTH);
+        self.common.configure_as_output(state);
+        self.into_mode()
+    }
+
+    fn into_input<PULL>(mut self, pull: Pull) -> Pin<'i, 'p, I, N, Input<PULL>> {
+        self.common.configure_as_input();
+        self.common.set_pull(pull);
+        self.into_mode()
+    }
+
+    /// Hand the pin over to the runtime-mode [`Dynamic`] API.
+    pub fn into_dynamic(self) -> Dynamic<'i, 'p> {
+        Unconfigured {
+            common: self.common,
+        }
+        .into_dynamic()
+    }
+
+    /// Release the pin.
+    ///
+    /// Stops driving the line and returns the pad with input and output
+    /// disabled, ready to be configured for another function.
+    pub fn free(self) -> FlexPad<'p> {
+        self.common.release()
+    }
+
+    /// Pad number of this pin, as used by the IOMUX.
+    #[inline]
+    pub fn pad_number(&self) -> usize {
+        self.common.pad_number()
+    }
+}
+
+impl<'i, 'p, const I: usize, const N: usize, PULL> Pin<'i, 'p, I, N, Input<PULL>> {
+    /// Enable or disable the input Schmitt trigger.
+    pub fn set_schmitt_trigger(&mut self, enable: bool) {
+        self.common.set_schmitt_trigger(enable);
+    }
+
+    /// Enable or disable the hardware debounce filter; port A only.
+    pub fn set_debounce(&mut self, enable: bool) -> Result<(), GpioError> {
+        self.common.set_debounce(enable)
+    }
+}
+
+impl<'i, 'p, const I: usize, const N: usize, MODE> Pin<'i, 'p, I, N, Output<MODE>> {
+    /// Set output drive strength.
+    pub fn set_drive_strength(&mut self, strength: DriveStrength) {
+        self.common.set_drive_strength(strength);
+    }
+
+    /// Set output slew rate.
+    pub fn set_slew_rate(&mut self, slew_rate: SlewRate) {
+        self.common.set_slew_rate(slew_rate);
+    }
+}
+
+impl<'i, 'p, const I: usize, const N: usize, M> PinInfo for Pin<'i, 'p, I, N, M> {
+    fn port(&self) -> GpioPort {
+        self.common.port()
+    }
+
+    fn pin_number(&self) -> usize {
+        N
+    }
+
+    fn instance_number(&self) -> usize {
+        I
+    }
+}
+
+impl<'i, 'p, const I: usize, const N: usize, M> ErrorType for Pin<'i, 'p, I, N, M> {
+    type Error = GpioError;
+}
+
+impl<'i, 'p, const I: usize, const N: usize, PULL> InputPin for Pin<'i, 'p, I, N, Input<PULL>> {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.common.read_input_state() == PinState::High)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.common.read_input_state() == PinState::Low)
+    }
+}
+
+impl<'i, 'p, const I: usize, const N: usize, MODE> OutputPin for Pin<'i, 'p, I, N, Output<MODE>> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.common.set_output_state(PinState::Low);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.common.set_output_state(PinState::High);
+        Ok(())
+    }
+}
+
+impl<'i, 'p, const I: usize, const N: usize, MODE> StatefulOutputPin
+    for Pin<'i, 'p, I, N, Output<MODE>>
+{
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.common.output_state() == PinState::High)
+    }
+
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.common.output_state() == PinState::Low)
+    }
+}
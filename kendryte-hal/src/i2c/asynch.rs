@@ -0,0 +1,315 @@
+use crate::i2c::driver::*;
+use crate::i2c::register::{Command, DataCmd, Interrupts, MmioRegisterBlock};
+use atomic_waker::AtomicWaker;
+use core::future::{Future, poll_fn};
+use core::pin::pin;
+use core::task::Poll;
+use embedded_hal::i2c::{Operation, SevenBitAddress, TenBitAddress};
+use embedded_hal_async::delay::DelayNs;
+
+/// Interrupt state shared between an [`AsyncI2c`] and its interrupt handler.
+///
+/// Place one in a `static` per I2C instance and call [`I2cState::on_interrupt`]
+/// from the instance's interrupt handler.
+pub struct I2cState {
+    waker: AtomicWaker,
+}
+
+impl I2cState {
+    /// Creates a new state with no registered waker.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            waker: AtomicWaker::new(),
+        }
+    }
+
+    /// Handles an I2C interrupt.
+    ///
+    /// Masks all interrupt sources and wakes the pending future, which
+    /// re-enables the sources it waits for on its next poll.
+    #[inline]
+    pub fn on_interrupt(&self, inner: &mut MmioRegisterBlock) {
+        unsafe { write_reg!(inner, intr_mask, write_intr_mask, Interrupts::DEFAULT) };
+        self.waker.wake();
+    }
+}
+
+impl Default for I2cState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Interrupt driven I2C master implementing embedded-hal-async `I2c` for 7-bit and 10-bit addresses.
+///
+/// Every wait is bounded by the [timeout](I2c::set_timeout) of the wrapped
+/// driver, measured with `delay`, so a bus that stops raising interrupts
+/// fails the transaction with [`I2cError::Timeout`] instead of hanging it.
+pub struct AsyncI2c<'i, D> {
+    i2c: I2c<'i>,
+    state: &'static I2cState,
+    delay: D,
+}
+
+impl<'i, D: DelayNs> AsyncI2c<'i, D> {
+    /// Wraps a configured blocking driver.
+    ///
+    /// The I2C interrupt must be routed to a handler calling
+    /// [`I2cState::on_interrupt`] with the same `state`. `delay` is usually
+    /// the async delay of the executor's time driver.
+    #[inline]
+    pub fn new(i2c: I2c<'i>, state: &'static I2cState, delay: D) -> Self {
+        Self { i2c, state, delay }
+    }
+
+    /// Returns the underlying blocking driver.
+    #[inline]
+    pub fn into_blocking(mut self) -> I2c<'i> {
+        unsafe {
+            write_reg!(
+                self.i2c.inner,
+                intr_mask,
+                write_intr_mask,
+                Interrupts::DEFAULT
+            )
+        };
+        self.i2c
+    }
+
+    /// Waits until `ready` returns true, sleeping on `interrupts` in between.
+    ///
+    /// Transfer aborts and SCL stuck detection always wake the future and
+    /// complete it with the corresponding error. Gives up with
+    /// [`I2cError::Timeout`] once the driver's timeout has passed.
+    async fn wait_for(
+        &mut self,
+        interrupts: Interrupts,
+        mut ready: impl FnMut(&MmioRegisterBlock) -> bool,
+    ) -> Result<(), I2cError> {
+        let Self { i2c, state, delay } = self;
+        let mut expired = pin!(delay.delay_us(i2c.timeout));
+        let result = poll_fn(|cx| {
+            state.waker.register(cx.waker());
+            if let Err(e) = i2c.check_errors() {
+                return Poll::Ready(Err(e));
+            }
+            if ready(&i2c.inner) {
+                return Poll::Ready(Ok(()));
+            }
+            if expired.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Err(I2cError::Timeout));
+            }
+            unsafe {
+                write_reg!(
+                    i2c.inner,
+                    intr_mask,
+                    write_intr_mask,
+                    interrupts.with_tx_abrt(true).with_scl_stuck_at_low(true)
+                )
+            };
+            Poll::Pending
+        })
+        .await;
+        unsafe { write_reg!(i2c.inner, intr_mask, write_intr_mask, Interrupts::DEFAULT) };
+        result
+    }
+
+    async fn push_command(&mut self, command: DataCmd) -> Result<(), I2cError> {
+        self.wait_for(Interrupts::DEFAULT.with_tx_empty(true), |r| {
+            read_reg!(r, status, read_status).transmit_fifo_not_full()
+        })
+        .await?;
+        unsafe { write_reg!(self.i2c.inner, data_cmd, write_data_cmd, command) };
+        Ok(())
+    }
+
+    async fn pop_data(&mut self) -> Result<u8, I2cError> {
+        self.wait_for(Interrupts::DEFAULT.with_rx_full(true), |r| {
+            read_reg!(r, status, read_status).receive_fifo_not_empty()
+        })
+        .await?;
+        Ok(read_reg!(self.i2c.inner, data_cmd, read_data_cmd).data())
+    }
+
+    /// Send `bytes` to the general call address; see [`I2c::general_call`].
+    pub async fn general_call(&mut self, bytes: &[u8]) -> Result<(), I2cError> {
+        self.run(Target::GeneralCall, &mut [Operation::Write(bytes)])
+            .await
+    }
+
+    /// Execute `operations` and clean up the bus after a failure.
+    async fn run(
+        &mut self,
+        target: Target,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), I2cError> {
+        let result = self.execute(target, operations).await;
+        match result {
+            Err(I2cError::Timeout) | Err(I2cError::SclStuckLow) => self.i2c.abort(),
+            Err(I2cError::SdaStuckLow) => {
+                let _ = self.i2c.recover_bus();
+            }
+            _ => {}
+        }
+        result
+    }
+
+    async fn execute(
+        &mut self,
+        target: Target,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), I2cError> {
+        self.i2c.set_address(target)?;
+        self.i2c.ensure_enabled();
+        let _ = read_reg!(self.i2c.inner, clr_tx_abrt, read_clr_tx_abrt);
+        let _ = read_reg!(self.i2c.inner, clr_stop_det, read_clr_stop_det);
+
+        let Some(stop_at) = stop_operation(operations) else {
+            // The controller only sends the address along with a data
+            // command, so an address probe reads one byte and drops it.
+            let command = DataCmd::DEFAULT.with_command(Command::Read).with_stop(true);
+            self.push_command(command).await?;
+            self.pop_data().await?;
+            return self.finish().await;
+        };
+
+        let mut last_read = None;
+        for (index, operation) in operations.iter_mut().enumerate() {
+            if operation_len(operation) == 0 {
+                continue;
+            }
+            let last_operation = index == stop_at;
+            let is_read = matches!(operation, Operation::Read(_));
+            // Consecutive operations of the same type are merged; a direction
+            // change needs a repeated START.
+            let restart = last_read.is_some_and(|r| r != is_read);
+            last_read = Some(is_read);
+            match operation {
+                Operation::Read(buffer) => {
+                    let len = buffer.len();
+                    for (i, byte) in buffer.iter_mut().enumerate() {
+                        let command = DataCmd::DEFAULT
+                            .with_command(Command::Read)
+                            .with_restart(i == 0 && restart)
+                            .with_stop(last_operation && i + 1 == len);
+                        self.push_command(command).await?;
+                        *byte = self.pop_data().await?;
+                    }
+                }
+                Operation::Write(buffer) => {
+                    let len = buffer.len();
+                    for (i, byte) in buffer.iter().enumerate() {
+                        let command = DataCmd::DEFAULT
+                            .with_data(*byte)
+                            .with_restart(i == 0 && restart)
+                            .with_stop(last_operation && i + 1 == len);
+                        self.push_command(command).await?;
+                    }
+                }
+            }
+        }
+
+        self.finish().await
+    }
+
+    /// Wait for the STOP queued by the last command.
+    async fn finish(&mut self) -> Result<(), I2cError> {
+        self.wait_for(Interrupts::DEFAULT.with_stop_det(true), |r| {
+            read_reg!(r, raw_intr_stat, read_raw_intr_stat).stop_det()
+        })
+        .await?;
+        let _ = read_reg!(self.i2c.inner, clr_stop_det, read_clr_stop_det);
+        Ok(())
+    }
+}
+
+impl<D> embedded_hal::i2c::ErrorType for AsyncI2c<'_, D> {
+    type Error = I2cError;
+}
+
+impl<D: DelayNs> embedded_hal_async::i2c::I2c<SevenBitAddress> for AsyncI2c<'_, D> {
+    async fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.run(Target::SevenBit(address), operations).await
+    }
+}
+
+impl<D: DelayNs> embedded_hal_async::i2c::I2c<TenBitAddress> for AsyncI2c<'_, D> {
+    async fn transaction(
+        &mut self,
+        address: TenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.run(Target::TenBit(address), operations).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clocks::Clocks;
+    use crate::i2c::register::RegisterBlock;
+    use crate::instance::{Instance, Numbered};
+    use crate::mock;
+    use core::task::{Context, Waker};
+    use embedded_hal_async::i2c::I2c as _;
+
+    struct MockI2c;
+
+    impl Instance<'static> for MockI2c {
+        type R = MmioRegisterBlock<'static>;
+        fn inner(self) -> Self::R {
+            unsafe { RegisterBlock::new_mmio(mock::block::<RegisterBlock>()) }
+        }
+    }
+
+    impl Numbered<'static, 0> for MockI2c {}
+
+    /// A delay that has always already elapsed, or never does.
+    struct Elapsed(bool);
+
+    impl DelayNs for Elapsed {
+        async fn delay_ns(&mut self, _ns: u32) {
+            if !self.0 {
+                core::future::pending::<()>().await;
+            }
+        }
+    }
+
+    fn write_once(elapsed: bool) -> (Poll<Result<(), I2cError>>, Interrupts) {
+        static STATE: I2cState = I2cState::new();
+        // The host has no machine timer, so blocking waits only end at once
+        // with a zero timeout.
+        let config = Config {
+            timeout: 0,
+            ..Config::default()
+        };
+        let i2c = I2c::new::<0>(MockI2c, config, Clocks);
+        let mut i2c = AsyncI2c::new(i2c, &STATE, Elapsed(elapsed));
+        let mut cx = Context::from_waker(Waker::noop());
+        let poll = {
+            let mut transaction = pin!(i2c.write(0x21u8, &[0x55]));
+            transaction.as_mut().poll(&mut cx)
+        };
+        let mask = read_reg!(i2c.i2c.inner, intr_mask, read_intr_mask);
+        (poll, mask)
+    }
+
+    #[test]
+    fn idle_bus_sleeps_on_interrupts() {
+        let (poll, mask) = write_once(false);
+        assert!(poll.is_pending());
+        assert!(mask.tx_empty());
+        assert!(mask.tx_abrt());
+    }
+
+    #[test]
+    fn idle_bus_times_out() {
+        let (poll, _) = write_once(true);
+        assert_eq!(poll, Poll::Ready(Err(I2cError::Timeout)));
+    }
+}
@@ -0,0 +1,367 @@
+//! Bit-banged I2C master over two GPIO pins.
+//!
+//! A fallback for pads that are not routed to a hardware I2C controller, and
+//! a way to talk to a bus whose controller has been freed, e.g. to recover a
+//! slave stuck in the middle of a byte. The K230 GPIO has no open-drain
+//! output, so a line is driven low by switching its pin to output with a low
+//! level and released by switching it back to input. The internal pad
+//! pull-ups are enabled, but they are too weak for anything but a short bus
+//! at low speed; fit external pull-up resistors as for a hardware bus.
+//!
+//! SCL is read back after every release, so a slave may stretch the clock
+//! for up to [`BitBangConfig::timeout`] microseconds. Every bit is timed
+//! against the machine timer; interrupts are left enabled, since I2C slaves
+//! follow the master clock and only see a longer bit. The achievable rate is
+//! bounded by GPIO register access and stays well below fast mode.
+
+use crate::gpio::blocking::Dynamic;
+use crate::gpio::config::Pull;
+use crate::i2c::I2cError;
+use crate::i2c::driver::Target;
+use crate::soc::TIMER_FREQUENCY;
+use crate::time::{Timeout, now};
+use embedded_hal::digital::PinState;
+use embedded_hal::i2c::{NoAcknowledgeSource, Operation, SevenBitAddress, TenBitAddress};
+
+/// Settings of a bit-banged I2C master.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitBangConfig {
+    /// SCL frequency in Hz.
+    pub frequency: u32,
+    /// Longest a slave may hold SCL low, in microseconds, before
+    /// [`I2cError::Timeout`].
+    pub timeout: u32,
+}
+
+impl Default for BitBangConfig {
+    fn default() -> Self {
+        Self {
+            frequency: 100_000,
+            timeout: 10_000,
+        }
+    }
+}
+
+/// I2C master on two GPIO pins, implementing embedded-hal 1.0 `I2c` for
+/// 7-bit and 10-bit addresses.
+pub struct I2cBitBang<'i, 'p> {
+    scl: Dynamic<'i, 'p>,
+    sda: Dynamic<'i, 'p>,
+    half_period: u64,
+    timeout: u32,
+}
+
+impl<'i, 'p> I2cBitBang<'i, 'p> {
+    /// Creates a master on `scl` and `sda` and releases both lines.
+    pub fn new(mut scl: Dynamic<'i, 'p>, mut sda: Dynamic<'i, 'p>, config: BitBangConfig) -> Self {
+        scl.configure_as_input(Pull::Up);
+        sda.configure_as_input(Pull::Up);
+        Self {
+            scl,
+            sda,
+            half_period: half_period(config.frequency),
+            timeout: config.timeout,
+        }
+    }
+
+    /// Releases both lines and returns the SCL and SDA pins.
+    pub fn free(mut self) -> (Dynamic<'i, 'p>, Dynamic<'i, 'p>) {
+        self.release_bus();
+        (self.scl, self.sda)
+    }
+
+    /// Change the SCL frequency, in Hz.
+    #[inline]
+    pub fn set_frequency(&mut self, frequency: u32) {
+        self.half_period = half_period(frequency);
+    }
+
+    /// Change the clock stretching timeout, in microseconds.
+    #[inline]
+    pub fn set_timeout(&mut self, timeout: u32) {
+        self.timeout = timeout;
+    }
+
+    /// Recover a bus whose SDA line is held low by a slave.
+    ///
+    /// Clocks SCL up to nine times until the slave releases SDA, then
+    /// generates a STOP condition.
+    pub fn recover_bus(&mut self) -> Result<(), I2cError> {
+        self.release(Line::Sda);
+        for _ in 0..9 {
+            if self.is_high(Line::Sda) {
+                break;
+            }
+            self.drive_low(Line::Scl);
+            self.delay();
+            self.release_scl()?;
+            self.delay();
+        }
+        self.stop()?;
+        if self.is_high(Line::Sda) {
+            Ok(())
+        } else {
+            Err(I2cError::SdaStuckLow)
+        }
+    }
+
+    /// Send a general call with `bytes` as payload.
+    ///
+    /// Fails with [`I2cError::NoAcknowledge`] if no slave acknowledges the
+    /// call.
+    pub fn general_call(&mut self, bytes: &[u8]) -> Result<(), I2cError> {
+        self.run(Target::GeneralCall, &mut [Operation::Write(bytes)])
+    }
+
+    /// Execute `operations` and leave the bus idle after a failure.
+    fn run(&mut self, target: Target, operations: &mut [Operation<'_>]) -> Result<(), I2cError> {
+        let result = self.execute(target, operations);
+        match result {
+            Err(I2cError::NoAcknowledge(_)) => {
+                let _ = self.stop();
+            }
+            // Another master or a stretching slave owns the bus; get out of
+            // its way without a STOP.
+            Err(_) => self.release_bus(),
+            Ok(()) => {}
+        }
+        result
+    }
+
+    fn execute(
+        &mut self,
+        target: Target,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), I2cError> {
+        if operations.is_empty() {
+            return Ok(());
+        }
+        if !self.is_high(Line::Sda) {
+            return Err(I2cError::SdaStuckLow);
+        }
+        let mut last_read = None;
+        let mut operations = operations.iter_mut().peekable();
+        while let Some(operation) = operations.next() {
+            // Consecutive reads are merged, so only the last byte before a
+            // direction change or the STOP is not acknowledged.
+            let next_read = matches!(operations.peek(), Some(Operation::Read(_)));
+            let is_read = matches!(operation, Operation::Read(_));
+            // A direction change needs a repeated START.
+            if last_read != Some(is_read) {
+                self.start()?;
+                self.address(target, is_read)?;
+            }
+            last_read = Some(is_read);
+            match operation {
+                Operation::Read(buffer) => {
+                    let len = buffer.len();
+                    for (i, byte) in buffer.iter_mut().enumerate() {
+                        *byte = self.read_byte(next_read || i + 1 < len)?;
+                    }
+                }
+                Operation::Write(buffer) => {
+                    for &byte in buffer.iter() {
+                        if !self.write_byte(byte)? {
+                            return Err(I2cError::NoAcknowledge(NoAcknowledgeSource::Data));
+                        }
+                    }
+                }
+            }
+        }
+        self.stop()
+    }
+
+    /// Send the address phase for a transfer in direction `read`.
+    fn address(&mut self, target: Target, read: bool) -> Result<(), I2cError> {
+        let acked = match target {
+            Target::SevenBit(address) => self.write_byte(((address & 0x7F) << 1) | read as u8)?,
+            Target::TenBit(address) => {
+                let high = 0xF0 | ((address >> 7) & 0x06) as u8;
+                let mut acked = self.write_byte(high)? && self.write_byte(address as u8)?;
+                // A 10-bit read repeats the first address byte with the read
+                // bit set after a repeated START.
+                if acked && read {
+                    self.start()?;
+                    acked = self.write_byte(high | 1)?;
+                }
+                acked
+            }
+            Target::GeneralCall => self.write_byte(0)?,
+        };
+        if acked {
+            Ok(())
+        } else {
+            Err(I2cError::NoAcknowledge(NoAcknowledgeSource::Address))
+        }
+    }
+
+    /// Generate a START, or a repeated START if SCL is low.
+    fn start(&mut self) -> Result<(), I2cError> {
+        self.release(Line::Sda);
+        self.delay();
+        self.release_scl()?;
+        self.delay();
+        if !self.is_high(Line::Sda) {
+            return Err(I2cError::ArbitrationLoss);
+        }
+        self.drive_low(Line::Sda);
+        self.delay();
+        self.drive_low(Line::Scl);
+        Ok(())
+    }
+
+    /// Generate a STOP, leaving both lines released.
+    fn stop(&mut self) -> Result<(), I2cError> {
+        self.drive_low(Line::Sda);
+        self.delay();
+        self.release_scl()?;
+        self.delay();
+        self.release(Line::Sda);
+        self.delay();
+        Ok(())
+    }
+
+    /// Write one byte, most significant bit first, and return whether it was
+    /// acknowledged.
+    fn write_byte(&mut self, byte: u8) -> Result<bool, I2cError> {
+        for i in (0..8).rev() {
+            self.write_bit(byte & (1 << i) != 0)?;
+        }
+        Ok(!self.read_bit()?)
+    }
+
+    /// Read one byte, most significant bit first, and acknowledge it if `ack`.
+    fn read_byte(&mut self, ack: bool) -> Result<u8, I2cError> {
+        let mut byte = 0;
+        for _ in 0..8 {
+            byte = (byte << 1) | self.read_bit()? as u8;
+        }
+        self.write_bit(!ack)?;
+        Ok(byte)
+    }
+
+    /// Clock out one bit; SCL is low on entry and on return.
+    fn write_bit(&mut self, bit: bool) -> Result<(), I2cError> {
+        if bit {
+            self.release(Line::Sda);
+        } else {
+            self.drive_low(Line::Sda);
+        }
+        self.delay();
+        self.release_scl()?;
+        // A released SDA read back low means another master drives a zero.
+        if bit && !self.is_high(Line::Sda) {
+            return Err(I2cError::ArbitrationLoss);
+        }
+        self.delay();
+        self.drive_low(Line::Scl);
+        Ok(())
+    }
+
+    /// Clock in one bit; SCL is low on entry and on return.
+    fn read_bit(&mut self) -> Result<bool, I2cError> {
+        self.release(Line::Sda);
+        self.delay();
+        self.release_scl()?;
+        self.delay();
+        let bit = self.is_high(Line::Sda);
+        self.drive_low(Line::Scl);
+        Ok(bit)
+    }
+
+    /// Release SCL and wait for a stretching slave to let it rise.
+    fn release_scl(&mut self) -> Result<(), I2cError> {
+        self.release(Line::Scl);
+        let scl = &self.scl;
+        Timeout::from_micros(self.timeout).wait(I2cError::Timeout, || {
+            scl.common.read_input_state() == PinState::High
+        })
+    }
+
+    fn release_bus(&mut self) {
+        self.release(Line::Scl);
+        self.release(Line::Sda);
+    }
+
+    #[inline(always)]
+    fn pin(&mut self, line: Line) -> &mut Dynamic<'i, 'p> {
+        match line {
+            Line::Scl => &mut self.scl,
+            Line::Sda => &mut self.sda,
+        }
+    }
+
+    #[inline(always)]
+    fn drive_low(&mut self, line: Line) {
+        self.pin(line).common.configure_as_output(PinState::Low);
+    }
+
+    #[inline(always)]
+    fn release(&mut self, line: Line) {
+        self.pin(line).common.configure_as_input();
+    }
+
+    #[inline(always)]
+    fn is_high(&mut self, line: Line) -> bool {
+        self.pin(line).common.read_input_state() == PinState::High
+    }
+
+    #[inline(always)]
+    fn delay(&self) {
+        let deadline = now() + self.half_period;
+        while now() < deadline {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Line {
+    Scl,
+    Sda,
+}
+
+impl embedded_hal::i2c::ErrorType for I2cBitBang<'_, '_> {
+    type Error = I2cError;
+}
+
+impl embedded_hal::i2c::I2c<SevenBitAddress> for I2cBitBang<'_, '_> {
+    fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.run(Target::SevenBit(address), operations)
+    }
+}
+
+impl embedded_hal::i2c::I2c<TenBitAddress> for I2cBitBang<'_, '_> {
+    fn transaction(
+        &mut self,
+        address: TenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.run(Target::TenBit(address), operations)
+    }
+}
+
+/// Machine timer ticks in half an SCL period at `frequency` Hz, rounded up
+/// so the bus never runs faster than asked.
+const fn half_period(frequency: u32) -> u64 {
+    let frequency = if frequency == 0 { 1 } else { frequency as u64 };
+    (TIMER_FREQUENCY as u64).div_ceil(2 * frequency)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_half_period() {
+        let ticks = half_period(100_000);
+        assert!(TIMER_FREQUENCY as u64 / (2 * ticks) <= 100_000);
+        assert!(TIMER_FREQUENCY as u64 / (2 * (ticks - 1)) > 100_000);
+        assert_eq!(half_period(TIMER_FREQUENCY), 1);
+        assert_eq!(half_period(0), (TIMER_FREQUENCY as u64).div_ceil(2));
+    }
+}
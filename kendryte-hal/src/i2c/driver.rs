@@ -0,0 +1,793 @@
+use crate::clocks::{ClockId, Clocks};
+use crate::i2c::pad::{I2cPads, IntoI2cScl, IntoI2cSda};
+use crate::i2c::register::*;
+use crate::instance::Numbered;
+use crate::sysctl::Sysctl;
+use crate::time::Timeout;
+use arbitrary_int::u10;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{InputPin, OutputPin};
+use embedded_hal::i2c::{NoAcknowledgeSource, Operation, SevenBitAddress, TenBitAddress};
+
+/// Error type for I2C operations.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum I2cError {
+    /// An operation did not complete within the configured timeout.
+    Timeout,
+    /// The addressed device did not acknowledge.
+    NoAcknowledge(NoAcknowledgeSource),
+    /// Another master won arbitration.
+    ///
+    /// The transfer was abandoned without a STOP; the other master owns the
+    /// bus until it releases it, after which the transfer can be retried.
+    ArbitrationLoss,
+    /// SDA is held low by a device and could not be released by bus recovery.
+    SdaStuckLow,
+    /// SCL is held low longer than the configured stuck timeout.
+    SclStuckLow,
+    /// The transfer was aborted for another reason.
+    Aborted,
+}
+
+impl embedded_hal::i2c::Error for I2cError {
+    fn kind(&self) -> embedded_hal::i2c::ErrorKind {
+        match self {
+            // Keep the source, which the crate-wide kind does not carry.
+            I2cError::NoAcknowledge(source) => embedded_hal::i2c::ErrorKind::NoAcknowledge(*source),
+            _ => crate::Error::from(*self).kind().into(),
+        }
+    }
+}
+
+/// Configuration for I2C.
+#[derive(Clone, Copy, Debug)]
+pub struct Config {
+    /// SCL frequency in Hz. Up to 100 kHz selects standard mode, above that fast mode.
+    pub frequency: u32,
+    /// Bound on a single FIFO or bus wait in microseconds before [`I2cError::Timeout`].
+    ///
+    /// This is a breaking change from earlier versions, which counted polling
+    /// iterations here with a default of 100_000. Waits are now timed by the
+    /// machine timer, and the default is 10_000 µs.
+    pub timeout: u32,
+    /// SCL and SDA stuck-at-low detection timeout, in I2C controller clock cycles.
+    /// A value of 0 disables stuck detection.
+    pub stuck_timeout: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            frequency: 100_000,
+            timeout: 10_000,
+            // 10 ms at 100 MHz.
+            stuck_timeout: 1_000_000,
+        }
+    }
+}
+
+/// Address phase of a master transfer, as programmed into IC_TAR.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Target {
+    /// 7-bit slave address.
+    SevenBit(SevenBitAddress),
+    /// 10-bit slave address.
+    TenBit(TenBitAddress),
+    /// General call address 0, addressing every slave that acknowledges it.
+    GeneralCall,
+}
+
+impl Target {
+    fn tar(self) -> Tar {
+        match self {
+            Target::SevenBit(address) => Tar::DEFAULT.with_address(u10::new(address as u16 & 0x7F)),
+            Target::TenBit(address) => Tar::DEFAULT
+                .with_address(u10::new(address & 0x3FF))
+                .with_addr_10bit_master(true),
+            Target::GeneralCall => Tar::DEFAULT.with_special(true),
+        }
+    }
+}
+
+/// Blocking I2C master implementing embedded-hal 1.0 `I2c` for 7-bit and 10-bit addresses.
+pub struct I2c<'i> {
+    pub(super) inner: MmioRegisterBlock<'static>,
+    pads: Option<I2cPads<'i>>,
+    pub(super) timeout: u32,
+    clock: ClockId,
+    context: Option<Context>,
+}
+
+impl<'i> I2c<'i> {
+    /// Create and configure an I2C master for numbered instance N.
+    pub fn new<const N: usize>(
+        instance: impl Numbered<'i, N, R = MmioRegisterBlock<'static>>,
+        config: Config,
+        clocks: Clocks,
+    ) -> Self {
+        let mut inner = instance.inner();
+        Self::configure::<N>(&mut inner, config, clocks);
+        I2c {
+            inner,
+            pads: None,
+            timeout: config.timeout,
+            clock: ClockId::I2cSclk(N as u8),
+            context: None,
+        }
+    }
+
+    /// Create and configure an I2C master with SCL and SDA pads.
+    #[inline]
+    pub fn with_pads<const N: usize>(
+        instance: impl Numbered<'i, N, R = MmioRegisterBlock<'static>>,
+        scl: impl IntoI2cScl<'i, N>,
+        sda: impl IntoI2cSda<'i, N>,
+        config: Config,
+        clocks: Clocks,
+    ) -> Self {
+        let mut i2c = Self::new(instance, config, clocks);
+        i2c.pads = Some(I2cPads {
+            scl: scl.into_i2c_scl(),
+            sda: sda.into_i2c_sda(),
+        });
+        i2c
+    }
+
+    fn configure<const N: usize>(inner: &mut MmioRegisterBlock, config: Config, clocks: Clocks) {
+        let _ = disable(inner, config.timeout);
+
+        let ic_clk = clocks.i2c_sclk::<N>().0;
+        let speed = if config.frequency <= 100_000 {
+            Speed::Standard
+        } else {
+            Speed::Fast
+        };
+        // Split the SCL period roughly evenly; the controller adds spike
+        // suppression and synchronisation cycles on top of the counts.
+        let period = ic_clk / config.frequency.max(1);
+        let hcnt = (period / 2).saturating_sub(7).max(6);
+        let lcnt = (period - period / 2).saturating_sub(1).max(8);
+        unsafe {
+            write_reg!(
+                inner,
+                con,
+                write_con,
+                Con::DEFAULT
+                    .with_master_mode(true)
+                    .with_speed(speed)
+                    .with_restart_enable(true)
+                    .with_slave_disable(true)
+                    .with_bus_clear_feature_ctrl(true)
+            );
+            if speed == Speed::Standard {
+                write_reg!(
+                    inner,
+                    ss_scl_hcnt_ufm_scl_hcnt,
+                    write_ss_scl_hcnt_ufm_scl_hcnt,
+                    hcnt
+                );
+                write_reg!(
+                    inner,
+                    ss_scl_lcnt_ufm_scl_lcnt,
+                    write_ss_scl_lcnt_ufm_scl_lcnt,
+                    lcnt
+                );
+            } else {
+                write_reg!(
+                    inner,
+                    fs_scl_hcnt_ufm_tbuf_cnt,
+                    write_fs_scl_hcnt_ufm_tbuf_cnt,
+                    hcnt
+                );
+                write_reg!(inner, fs_scl_lcnt, write_fs_scl_lcnt, lcnt);
+            }
+            let stuck_timeout = match config.stuck_timeout {
+                0 => u32::MAX,
+                n => n,
+            };
+            write_reg!(
+                inner,
+                scl_stuck_at_low_timeout,
+                write_scl_stuck_at_low_timeout,
+                stuck_timeout
+            );
+            write_reg!(
+                inner,
+                sda_stuck_at_low_timeout,
+                write_sda_stuck_at_low_timeout,
+                stuck_timeout
+            );
+            write_reg!(inner, rx_tl, write_rx_tl, 0);
+            write_reg!(inner, tx_tl, write_tx_tl, 0);
+            // All interrupts are polled through IC_RAW_INTR_STAT.
+            write_reg!(inner, intr_mask, write_intr_mask, Interrupts::DEFAULT);
+        }
+        let _ = read_reg!(inner, clr_intr, read_clr_intr);
+    }
+
+    /// Disable the controller and release the pads it was created with.
+    ///
+    /// Returned pads have input and output disabled, ready to be configured
+    /// for another function, e.g. as GPIO for [`recover_bus_gpio`].
+    pub fn free(mut self) -> Option<I2cPads<'i>> {
+        let _ = disable(&mut self.inner, self.timeout);
+        let mut pads = self.pads;
+        if let Some(pads) = pads.as_mut() {
+            pads.disable();
+        }
+        pads
+    }
+
+    /// Change the per-operation timeout, in microseconds.
+    #[inline]
+    pub fn set_timeout(&mut self, timeout: u32) {
+        self.timeout = timeout;
+    }
+
+    /// Saves the controller configuration and gates its clock.
+    ///
+    /// The controller is disabled first, which fails with
+    /// [`I2cError::Timeout`] while a transfer is still on the bus. The driver
+    /// must not be used until [`resume`](Self::resume). Suspending a
+    /// suspended controller does nothing.
+    pub fn suspend(&mut self, sysctl: &mut Sysctl<'_>) -> Result<(), I2cError> {
+        if self.context.is_some() {
+            return Ok(());
+        }
+        let enabled = read_reg!(self.inner, enable, read_enable).enable();
+        disable(&mut self.inner, self.timeout)?;
+        self.context = Some(Context::save(&mut self.inner, enabled));
+        sysctl.disable_clock(self.clock);
+        Ok(())
+    }
+
+    /// Enables the clock and restores the configuration saved by
+    /// [`suspend`](Self::suspend).
+    ///
+    /// The registers are rewritten even if they kept their values, so the
+    /// controller also comes back after its power domain was switched off.
+    /// Does nothing if the controller is not suspended.
+    pub fn resume(&mut self, sysctl: &mut Sysctl<'_>) {
+        if let Some(context) = self.context.take() {
+            sysctl.enable_clock(self.clock);
+            context.restore(&mut self.inner);
+        }
+    }
+
+    /// Returns true between [`suspend`](Self::suspend) and [`resume`](Self::resume).
+    #[inline]
+    pub fn is_suspended(&self) -> bool {
+        self.context.is_some()
+    }
+
+    /// Recover a bus whose SDA line is held low by a slave.
+    ///
+    /// Uses the controller's SDA stuck recovery, which clocks SCL up to nine
+    /// times until the slave releases SDA and then issues a STOP.
+    pub fn recover_bus(&mut self) -> Result<(), I2cError> {
+        self.ensure_enabled();
+        unsafe {
+            modify_reg!(self.inner, enable, modify_enable, |r| r
+                .with_sda_stuck_recovery_enable(true))
+        };
+        let timeout = self.timeout;
+        wait(timeout, || {
+            !read_reg!(self.inner, enable, read_enable).sda_stuck_recovery_enable()
+        })?;
+        let _ = read_reg!(self.inner, clr_tx_abrt, read_clr_tx_abrt);
+        if read_reg!(self.inner, status, read_status).sda_stuck_not_recovered() {
+            return Err(I2cError::SdaStuckLow);
+        }
+        Ok(())
+    }
+
+    /// Abort the current transfer, flushing the transmit FIFO.
+    pub(super) fn abort(&mut self) {
+        unsafe { modify_reg!(self.inner, enable, modify_enable, |r| r.with_abort(true)) };
+        let timeout = self.timeout;
+        let _ = wait(timeout, || {
+            !read_reg!(self.inner, enable, read_enable).abort()
+        });
+        let _ = read_reg!(self.inner, clr_tx_abrt, read_clr_tx_abrt);
+        let _ = read_reg!(self.inner, clr_stop_det, read_clr_stop_det);
+    }
+
+    /// Check for a transfer abort or a stuck bus.
+    pub(super) fn check_errors(&mut self) -> Result<(), I2cError> {
+        let raw = read_reg!(self.inner, raw_intr_stat, read_raw_intr_stat);
+        if raw.scl_stuck_at_low() {
+            let _ = read_reg!(self.inner, clr_scl_stuck_det, read_clr_scl_stuck_det);
+            return Err(I2cError::SclStuckLow);
+        }
+        if raw.tx_abrt() {
+            let source = read_reg!(self.inner, tx_abrt_source, read_tx_abrt_source);
+            // Reading IC_CLR_TX_ABRT also releases the transmit FIFO.
+            let _ = read_reg!(self.inner, clr_tx_abrt, read_clr_tx_abrt);
+            return Err(abort_reason(source));
+        }
+        Ok(())
+    }
+
+    /// Poll `f` until it returns true, checking for errors on every iteration.
+    fn poll(&mut self, mut f: impl FnMut(&mut Self) -> bool) -> Result<(), I2cError> {
+        let timeout = Timeout::from_micros(self.timeout);
+        loop {
+            let expired = timeout.is_expired();
+            self.check_errors()?;
+            if f(self) {
+                return Ok(());
+            }
+            if expired {
+                return Err(I2cError::Timeout);
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    fn push_command(&mut self, command: DataCmd) -> Result<(), I2cError> {
+        self.poll(|i2c| read_reg!(i2c.inner, status, read_status).transmit_fifo_not_full())?;
+        unsafe { write_reg!(self.inner, data_cmd, write_data_cmd, command) };
+        Ok(())
+    }
+
+    fn pop_data(&mut self) -> Result<u8, I2cError> {
+        self.poll(|i2c| read_reg!(i2c.inner, status, read_status).receive_fifo_not_empty())?;
+        Ok(read_reg!(self.inner, data_cmd, read_data_cmd).data())
+    }
+
+    /// Enable the controller if it is disabled.
+    #[inline]
+    pub(super) fn ensure_enabled(&mut self) {
+        if !read_reg!(self.inner, enable, read_enable).enable() {
+            unsafe {
+                write_reg!(
+                    self.inner,
+                    enable,
+                    write_enable,
+                    Enable::DEFAULT.with_enable(true)
+                )
+            };
+        }
+    }
+
+    pub(super) fn set_address(&mut self, target: Target) -> Result<(), I2cError> {
+        let tar = target.tar();
+        if read_reg!(self.inner, tar, read_tar) == tar {
+            return Ok(());
+        }
+        // IC_TAR can only be written while the controller is disabled.
+        disable(&mut self.inner, self.timeout)?;
+        unsafe {
+            // Without dynamic IC_TAR updates the addressing mode is taken
+            // from IC_CON instead of IC_TAR, so keep both in step.
+            modify_reg!(self.inner, con, modify_con, |r| r
+                .with_addr_10bit_master(tar.addr_10bit_master()));
+            write_reg!(self.inner, tar, write_tar, tar);
+            write_reg!(
+                self.inner,
+                enable,
+                write_enable,
+                Enable::DEFAULT.with_enable(true)
+            );
+        }
+        Ok(())
+    }
+
+    /// Send `bytes` to the general call address.
+    ///
+    /// Every slave that acknowledges general calls receives the bytes; the
+    /// first one usually selects the command, e.g. 0x06 for a reset and
+    /// address reload. Fails with [`I2cError::NoAcknowledge`] if no slave
+    /// acknowledges the call.
+    pub fn general_call(&mut self, bytes: &[u8]) -> Result<(), I2cError> {
+        self.run(Target::GeneralCall, &mut [Operation::Write(bytes)])
+    }
+
+    /// Execute `operations` and clean up the bus after a failure.
+    fn run(&mut self, target: Target, operations: &mut [Operation<'_>]) -> Result<(), I2cError> {
+        let result = self.execute(target, operations);
+        match result {
+            Err(I2cError::Timeout) | Err(I2cError::SclStuckLow) => self.abort(),
+            Err(I2cError::SdaStuckLow) => {
+                // Let the controller try to free the bus so the next
+                // transaction has a chance to succeed.
+                let _ = self.recover_bus();
+            }
+            _ => {}
+        }
+        result
+    }
+
+    fn execute(
+        &mut self,
+        target: Target,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), I2cError> {
+        self.set_address(target)?;
+        self.ensure_enabled();
+        let _ = read_reg!(self.inner, clr_tx_abrt, read_clr_tx_abrt);
+        let _ = read_reg!(self.inner, clr_stop_det, read_clr_stop_det);
+
+        let Some(stop_at) = stop_operation(operations) else {
+            // The controller only sends the address along with a data
+            // command, so an address probe reads one byte and drops it.
+            let command = DataCmd::DEFAULT.with_command(Command::Read).with_stop(true);
+            self.push_command(command)?;
+            self.pop_data()?;
+            return self.finish();
+        };
+
+        let mut last_read = None;
+        for (index, operation) in operations.iter_mut().enumerate() {
+            if operation_len(operation) == 0 {
+                continue;
+            }
+            let last_operation = index == stop_at;
+            let is_read = matches!(operation, Operation::Read(_));
+            // Consecutive operations of the same type are merged; a direction
+            // change needs a repeated START.
+            let restart = last_read.is_some_and(|r| r != is_read);
+            last_read = Some(is_read);
+            match operation {
+                Operation::Read(buffer) => {
+                    let len = buffer.len();
+                    for (i, byte) in buffer.iter_mut().enumerate() {
+                        let command = DataCmd::DEFAULT
+                            .with_command(Command::Read)
+                            .with_restart(i == 0 && restart)
+                            .with_stop(last_operation && i + 1 == len);
+                        self.push_command(command)?;
+                        *byte = self.pop_data()?;
+                    }
+                }
+                Operation::Write(buffer) => {
+                    let len = buffer.len();
+                    for (i, byte) in buffer.iter().enumerate() {
+                        let command = DataCmd::DEFAULT
+                            .with_data(*byte)
+                            .with_restart(i == 0 && restart)
+                            .with_stop(last_operation && i + 1 == len);
+                        self.push_command(command)?;
+                    }
+                }
+            }
+        }
+
+        self.finish()
+    }
+
+    /// Wait for the STOP queued by the last command.
+    fn finish(&mut self) -> Result<(), I2cError> {
+        self.poll(|i2c| read_reg!(i2c.inner, raw_intr_stat, read_raw_intr_stat).stop_det())?;
+        let _ = read_reg!(self.inner, clr_stop_det, read_clr_stop_det);
+        Ok(())
+    }
+}
+
+/// Number of bytes `operation` transfers.
+#[inline]
+pub(super) fn operation_len(operation: &Operation<'_>) -> usize {
+    match operation {
+        Operation::Read(buffer) => buffer.len(),
+        Operation::Write(buffer) => buffer.len(),
+    }
+}
+
+/// Index of the last operation that transfers data, whose last byte carries
+/// the STOP; `None` if the transaction only probes the address.
+///
+/// Empty operations queue no command, so a STOP placed on one would never
+/// be sent.
+pub(super) fn stop_operation(operations: &[Operation<'_>]) -> Option<usize> {
+    operations.iter().rposition(|op| operation_len(op) != 0)
+}
+
+impl embedded_hal::i2c::ErrorType for I2c<'_> {
+    type Error = I2cError;
+}
+
+impl embedded_hal::i2c::I2c<SevenBitAddress> for I2c<'_> {
+    fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.run(Target::SevenBit(address), operations)
+    }
+}
+
+impl embedded_hal::i2c::I2c<TenBitAddress> for I2c<'_> {
+    fn transaction(
+        &mut self,
+        address: TenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.run(Target::TenBit(address), operations)
+    }
+}
+
+/// Recover a stuck bus by bit-banging SCL with GPIO pins.
+///
+/// Clocks SCL up to nine times until the slave releases SDA, then generates a
+/// STOP condition. Use this when the controller's own recovery is not enough,
+/// e.g. with pads temporarily configured as GPIO after [`I2c::free`].
+/// `half_period_ns` sets the SCL half period, 5000 for 100 kHz.
+pub fn recover_bus_gpio<SCL, SDA>(
+    scl: &mut SCL,
+    sda: &mut SDA,
+    delay: &mut impl DelayNs,
+    half_period_ns: u32,
+) -> Result<(), I2cError>
+where
+    SCL: OutputPin,
+    SDA: InputPin + OutputPin,
+{
+    // Leave SDA released (high) and clock the slave through the rest of its byte.
+    let _ = sda.set_high();
+    for _ in 0..9 {
+        if sda.is_high().unwrap_or(false) {
+            break;
+        }
+        let _ = scl.set_low();
+        delay.delay_ns(half_period_ns);
+        let _ = scl.set_high();
+        delay.delay_ns(half_period_ns);
+    }
+    // STOP: SDA rises while SCL is high.
+    let _ = scl.set_low();
+    let _ = sda.set_low();
+    delay.delay_ns(half_period_ns);
+    let _ = scl.set_high();
+    delay.delay_ns(half_period_ns);
+    let _ = sda.set_high();
+    delay.delay_ns(half_period_ns);
+
+    if sda.is_high().unwrap_or(false) {
+        Ok(())
+    } else {
+        Err(I2cError::SdaStuckLow)
+    }
+}
+
+/// Controller registers kept across a suspend.
+#[derive(Clone, Copy)]
+struct Context {
+    con: Con,
+    tar: Tar,
+    ss_hcnt: u32,
+    ss_lcnt: u32,
+    fs_hcnt: u32,
+    fs_lcnt: u32,
+    sda_hold: u32,
+    sda_setup: u32,
+    spklen: u32,
+    scl_stuck_timeout: u32,
+    sda_stuck_timeout: u32,
+    rx_tl: u32,
+    tx_tl: u32,
+    intr_mask: Interrupts,
+    enabled: bool,
+}
+
+impl Context {
+    fn save(inner: &mut MmioRegisterBlock, enabled: bool) -> Self {
+        Context {
+            con: read_reg!(inner, con, read_con),
+            tar: read_reg!(inner, tar, read_tar),
+            ss_hcnt: read_reg!(
+                inner,
+                ss_scl_hcnt_ufm_scl_hcnt,
+                read_ss_scl_hcnt_ufm_scl_hcnt
+            ),
+            ss_lcnt: read_reg!(
+                inner,
+                ss_scl_lcnt_ufm_scl_lcnt,
+                read_ss_scl_lcnt_ufm_scl_lcnt
+            ),
+            fs_hcnt: read_reg!(
+                inner,
+                fs_scl_hcnt_ufm_tbuf_cnt,
+                read_fs_scl_hcnt_ufm_tbuf_cnt
+            ),
+            fs_lcnt: read_reg!(inner, fs_scl_lcnt, read_fs_scl_lcnt),
+            sda_hold: read_reg!(inner, sda_hold, read_sda_hold),
+            sda_setup: read_reg!(inner, sda_setup, read_sda_setup),
+            spklen: read_reg!(inner, fs_spklen_ufm_spklen, read_fs_spklen_ufm_spklen),
+            scl_stuck_timeout: read_reg!(
+                inner,
+                scl_stuck_at_low_timeout,
+                read_scl_stuck_at_low_timeout
+            ),
+            sda_stuck_timeout: read_reg!(
+                inner,
+                sda_stuck_at_low_timeout,
+                read_sda_stuck_at_low_timeout
+            ),
+            rx_tl: read_reg!(inner, rx_tl, read_rx_tl),
+            tx_tl: read_reg!(inner, tx_tl, read_tx_tl),
+            intr_mask: read_reg!(inner, intr_mask, read_intr_mask),
+            enabled,
+        }
+    }
+
+    /// Write the saved registers back, with the controller disabled as most
+    /// of them require, and re-enable it last if it was enabled.
+    fn restore(&self, inner: &mut MmioRegisterBlock) {
+        unsafe {
+            write_reg!(inner, enable, write_enable, Enable::DEFAULT);
+            write_reg!(inner, con, write_con, self.con);
+            write_reg!(inner, tar, write_tar, self.tar);
+            write_reg!(
+                inner,
+                ss_scl_hcnt_ufm_scl_hcnt,
+                write_ss_scl_hcnt_ufm_scl_hcnt,
+                self.ss_hcnt
+            );
+            write_reg!(
+                inner,
+                ss_scl_lcnt_ufm_scl_lcnt,
+                write_ss_scl_lcnt_ufm_scl_lcnt,
+                self.ss_lcnt
+            );
+            write_reg!(
+                inner,
+                fs_scl_hcnt_ufm_tbuf_cnt,
+                write_fs_scl_hcnt_ufm_tbuf_cnt,
+                self.fs_hcnt
+            );
+            write_reg!(inner, fs_scl_lcnt, write_fs_scl_lcnt, self.fs_lcnt);
+            write_reg!(inner, sda_hold, write_sda_hold, self.sda_hold);
+            write_reg!(inner, sda_setup, write_sda_setup, self.sda_setup);
+            write_reg!(
+                inner,
+                fs_spklen_ufm_spklen,
+                write_fs_spklen_ufm_spklen,
+                self.spklen
+            );
+            write_reg!(
+                inner,
+                scl_stuck_at_low_timeout,
+                write_scl_stuck_at_low_timeout,
+                self.scl_stuck_timeout
+            );
+            write_reg!(
+                inner,
+                sda_stuck_at_low_timeout,
+                write_sda_stuck_at_low_timeout,
+                self.sda_stuck_timeout
+            );
+            write_reg!(inner, rx_tl, write_rx_tl, self.rx_tl);
+            write_reg!(inner, tx_tl, write_tx_tl, self.tx_tl);
+            write_reg!(inner, intr_mask, write_intr_mask, self.intr_mask);
+            if self.enabled {
+                write_reg!(
+                    inner,
+                    enable,
+                    write_enable,
+                    Enable::DEFAULT.with_enable(true)
+                );
+            }
+        }
+        let _ = read_reg!(inner, clr_intr, read_clr_intr);
+    }
+}
+
+/// Disable the controller and wait until it reports being disabled.
+fn disable(inner: &mut MmioRegisterBlock, timeout: u32) -> Result<(), I2cError> {
+    unsafe { write_reg!(inner, enable, write_enable, Enable::DEFAULT) };
+    wait(timeout, || {
+        !read_reg!(inner, enable_status, read_enable_status).enabled()
+    })
+}
+
+/// Poll `f` until it returns true or `timeout` microseconds have elapsed.
+#[inline]
+fn wait(timeout: u32, f: impl FnMut() -> bool) -> Result<(), I2cError> {
+    Timeout::from_micros(timeout).wait(I2cError::Timeout, f)
+}
+
+/// Decode IC_TX_ABRT_SOURCE into an error.
+fn abort_reason(source: TxAbrtSource) -> I2cError {
+    if source.sda_stuck_at_low() {
+        I2cError::SdaStuckLow
+    } else if source.arbitration_lost() || source.slave_arbitration_lost() {
+        I2cError::ArbitrationLoss
+    } else if source.addr_7bit_noack()
+        || source.addr_10bit_1_noack()
+        || source.addr_10bit_2_noack()
+        || source.general_call_noack()
+    {
+        I2cError::NoAcknowledge(NoAcknowledgeSource::Address)
+    } else if source.tx_data_noack() {
+        I2cError::NoAcknowledge(NoAcknowledgeSource::Data)
+    } else {
+        I2cError::Aborted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instance::Instance;
+    use crate::mock;
+
+    struct MockI2c;
+
+    impl Instance<'static> for MockI2c {
+        type R = MmioRegisterBlock<'static>;
+        fn inner(self) -> Self::R {
+            unsafe { RegisterBlock::new_mmio(mock::block::<RegisterBlock>()) }
+        }
+    }
+
+    impl Numbered<'static, 0> for MockI2c {}
+
+    #[test]
+    fn target_address_phase() {
+        assert_eq!(Target::SevenBit(0x50).tar().raw_value(), 0x50);
+        // Only the low 7 bits of a 7-bit address are sent.
+        assert_eq!(Target::SevenBit(0xD0).tar().raw_value(), 0x50);
+        let ten_bit = Target::TenBit(0x3A5).tar();
+        assert_eq!(ten_bit.address(), u10::new(0x3A5));
+        assert!(ten_bit.addr_10bit_master());
+        assert!(!ten_bit.special());
+        let general_call = Target::GeneralCall.tar();
+        assert!(general_call.special());
+        assert!(!general_call.gc_or_start());
+        assert_eq!(general_call.address(), u10::new(0));
+    }
+
+    #[test]
+    fn set_address_keeps_con_in_step() {
+        // The host has no machine timer, so blocking waits only end at once
+        // with a zero timeout.
+        let config = Config {
+            timeout: 0,
+            ..Config::default()
+        };
+        let mut i2c = I2c::new::<0>(MockI2c, config, Clocks);
+        i2c.set_address(Target::TenBit(0x3A5)).unwrap();
+        assert_eq!(
+            read_reg!(i2c.inner, tar, read_tar),
+            Target::TenBit(0x3A5).tar()
+        );
+        assert!(read_reg!(i2c.inner, con, read_con).addr_10bit_master());
+        i2c.set_address(Target::SevenBit(0x50)).unwrap();
+        assert!(!read_reg!(i2c.inner, con, read_con).addr_10bit_master());
+    }
+
+    #[test]
+    fn abort_reason_decoding() {
+        let abort = |raw: u32| abort_reason(TxAbrtSource::new_with_raw_value(raw));
+        let address = I2cError::NoAcknowledge(NoAcknowledgeSource::Address);
+        assert_eq!(abort(1 << 0), address);
+        assert_eq!(abort(1 << 1), address);
+        assert_eq!(abort(1 << 2), address);
+        assert_eq!(abort(1 << 4), address);
+        assert_eq!(
+            abort(1 << 3),
+            I2cError::NoAcknowledge(NoAcknowledgeSource::Data)
+        );
+        assert_eq!(abort(1 << 12), I2cError::ArbitrationLoss);
+        assert_eq!(abort(1 << 14), I2cError::ArbitrationLoss);
+        assert_eq!(abort(1 << 16), I2cError::Aborted);
+        // A stuck bus explains every other reason, and a lost arbitration
+        // explains the missing acknowledge.
+        assert_eq!(abort((1 << 17) | (1 << 12) | 1), I2cError::SdaStuckLow);
+        assert_eq!(abort((1 << 12) | (1 << 3)), I2cError::ArbitrationLoss);
+    }
+
+    #[test]
+    fn stop_goes_on_last_operation_with_data() {
+        let mut buffer = [0u8; 2];
+        let operations = [
+            Operation::Write(&[0x10]),
+            Operation::Read(&mut buffer),
+            Operation::Write(&[]),
+        ];
+        assert_eq!(stop_operation(&operations), Some(1));
+        assert_eq!(stop_operation(&[Operation::Write(&[])]), None);
+        assert_eq!(stop_operation(&[]), None);
+    }
+}
@@ -1,2 +1,27 @@
+crate::trace::traced_registers!($ "i2c", crate::i2c::RegisterBlock);
+
 mod register;
 pub use register::*;
+
+mod driver;
+pub use driver::*;
+
+mod asynch;
+pub use asynch::{AsyncI2c, I2cState};
+
+pub mod pad;
+pub use pad::{I2cPads, IntoI2cScl, IntoI2cSda};
+
+pub mod bitbang;
+pub use bitbang::{BitBangConfig, I2cBitBang};
+
+crate::trace::impl_raw!(
+    Con,
+    Tar,
+    DataCmd,
+    Interrupts,
+    Enable,
+    Status,
+    TxAbrtSource,
+    EnableStatus
+);
@@ -0,0 +1,30 @@
+use crate::iomux::FlexPad;
+use crate::iomux::ops::PadOps;
+
+/// Pad that can be configured into I2C serial clock alternate function.
+pub trait IntoI2cScl<'a, const I: usize> {
+    /// Configure this pad into I2C SCL signal.
+    fn into_i2c_scl(self) -> FlexPad<'a>;
+}
+
+/// Pad that can be configured into I2C serial data alternate function.
+pub trait IntoI2cSda<'a, const I: usize> {
+    /// Configure this pad into I2C SDA signal.
+    fn into_i2c_sda(self) -> FlexPad<'a>;
+}
+
+/// Pads held by an I2C driver, returned by [`I2c::free`](crate::i2c::I2c::free).
+pub struct I2cPads<'a> {
+    /// Serial clock pad.
+    pub scl: FlexPad<'a>,
+    /// Serial data pad.
+    pub sda: FlexPad<'a>,
+}
+
+impl<'a> I2cPads<'a> {
+    /// Disable input and output on both pads so they can be reassigned.
+    pub(crate) fn disable(&mut self) {
+        self.scl.set_disabled();
+        self.sda.set_disabled();
+    }
+}
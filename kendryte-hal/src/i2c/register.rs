@@ -1,3 +1,5 @@
+use arbitrary_int::{u9, u10};
+use bitbybit::{bitenum, bitfield};
 use derive_mmio::Mmio;
 
 /// I2C Register Block.
@@ -10,10 +12,10 @@ pub struct RegisterBlock {
     /// I2C Control Register.
     /// This register can be written only when the I2C controller is disabled, which corresponds to the IC_ENABLE\[0\] register being set to 0.
     /// Writes at other times have no effect.
-    pub con: u32,
+    pub con: Con,
     /// I2C Target Address Register.
     /// This register stores the target I2C address for master mode operations.
-    pub tar: u32,
+    pub tar: Tar,
     /// I2C Slave Address Register.
     /// This register holds the slave address when operating in slave mode.
     pub sar: u32,
@@ -22,7 +24,7 @@ pub struct RegisterBlock {
     pub hs_maddr: u32,
     /// I2C Rx/Tx Data Buffer and Command Register.
     /// This is the register the CPU writes to when filling the TX FIFO and reads from when retrieving bytes from RX FIFO.
-    pub data_cmd: u32,
+    pub data_cmd: DataCmd,
     /// Standard Speed I2C Clock SCL High Count Register.
     /// This register controls the SCL clock high time for standard speed mode.
     /// Ultra-Fast Speed I2C Clock SCL High Count Register.
@@ -51,14 +53,16 @@ pub struct RegisterBlock {
     /// Each bit in this register has a corresponding mask bit in the IC_INTR_MASK register.
     /// These bits are cleared by reading the matching interrupt clear register.
     /// The unmasked raw versions of these bits are available in the IC_RAW_INTR_STAT register.
-    pub intr_stat: u32,
+    #[mmio(PureRead)]
+    pub intr_stat: Interrupts,
     /// I2C Interrupt Mask Register.
     /// These bits mask their corresponding interrupt status bits.
     /// This register is active low; a value of 0 masks the interrupt, whereas a value of 1 unmasks the interrupt.
-    pub intr_mask: u32,
+    pub intr_mask: Interrupts,
     /// I2C Raw Interrupt Status Register.
     /// Unlike the IC_INTR_STAT register, these bits are not masked so they always show the true status of the I2C controller.
-    pub raw_intr_stat: u32,
+    #[mmio(PureRead)]
+    pub raw_intr_stat: Interrupts,
     /// I2C Receive FIFO Threshold Register.
     /// This register controls the threshold level for receive FIFO operations.
     pub rx_tl: u32,
@@ -100,12 +104,13 @@ pub struct RegisterBlock {
     pub clr_gen_call: u32,
     /// I2C Enable Register.
     /// This register enables or disables the I2C controller.
-    pub enable: u32,
+    pub enable: Enable,
     /// I2C Status Register.
     /// This is a read-only register used to indicate the current transfer status and FIFO status.
     /// The status register may be read at any time.
     /// None of the bits in this register request an interrupt.
-    pub status: u32,
+    #[mmio(PureRead)]
+    pub status: Status,
     /// I2C Transmit FIFO Level Register.
     /// This register contains the number of valid data entries in the transmit FIFO buffer.
     pub txflr: u32,
@@ -117,7 +122,8 @@ pub struct RegisterBlock {
     pub sda_hold: u32,
     /// I2C Transmit Abort Source Register.
     /// This register indicates the source of a transmission abort.
-    pub tx_abrt_source: u32,
+    #[mmio(PureRead)]
+    pub tx_abrt_source: TxAbrtSource,
     /// Generate Slave Data NACK Register.
     /// The register is used to generate a NACK for the data part of a transfer when I2C controller is acting as a slave-receiver.
     pub slv_data_nack_only: u32,
@@ -139,7 +145,8 @@ pub struct RegisterBlock {
     /// I2C Enable Status Register.
     /// The register is used to report the I2C controller hardware status when the IC_ENABLE\[0\] register is set from 1 to 0;
     /// that is, when I2C controller is disabled.
-    pub enable_status: u32,
+    #[mmio(PureRead)]
+    pub enable_status: EnableStatus,
     /// I2C SS, FS or FM+ spike suppression limit Register.
     /// This register controls spike suppression in various speed modes.
     /// I2C UFM spike suppression limit Register.
@@ -202,6 +209,295 @@ pub struct RegisterBlock {
     pub comp_type: u32,
 }
 
+/// Bus speed selected in IC_CON.
+#[bitenum(u2, exhaustive = false)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum Speed {
+    /// Standard mode, up to 100 kbit/s.
+    Standard = 0b01,
+    /// Fast mode or fast mode plus, up to 1 Mbit/s.
+    Fast = 0b10,
+    /// High speed mode, up to 3.4 Mbit/s.
+    High = 0b11,
+}
+
+/// I2C Control Register (IC_CON).
+#[bitfield(u32, default = 0x0)]
+#[derive(Debug, PartialEq, Eq)]
+pub struct Con {
+    /// Enable master mode (MASTER_MODE).
+    #[bit(0, rw)]
+    pub master_mode: bool,
+    /// Bus speed (SPEED).
+    #[bits(1..=2, rw)]
+    pub speed: Option<Speed>,
+    /// Respond to 10-bit addresses in slave mode (IC_10BITADDR_SLAVE).
+    #[bit(3, rw)]
+    pub addr_10bit_slave: bool,
+    /// Start transfers with 10-bit addressing in master mode (IC_10BITADDR_MASTER).
+    #[bit(4, rw)]
+    pub addr_10bit_master: bool,
+    /// Allow RESTART conditions in master mode (IC_RESTART_EN).
+    #[bit(5, rw)]
+    pub restart_enable: bool,
+    /// Disable the slave (IC_SLAVE_DISABLE).
+    #[bit(6, rw)]
+    pub slave_disable: bool,
+    /// Issue STOP_DET in slave mode only when addressed (STOP_DET_IFADDRESSED).
+    #[bit(7, rw)]
+    pub stop_det_if_addressed: bool,
+    /// Raise TX_EMPTY only once the current command has been shifted out (TX_EMPTY_CTRL).
+    #[bit(8, rw)]
+    pub tx_empty_ctrl: bool,
+    /// Hold the bus instead of overflowing when the receive FIFO is full (RX_FIFO_FULL_HLD_CTRL).
+    #[bit(9, rw)]
+    pub rx_fifo_full_hold_ctrl: bool,
+    /// Issue STOP_DET in master mode only when the master is active (STOP_DET_IF_MASTER_ACTIVE).
+    #[bit(10, rw)]
+    pub stop_det_if_master_active: bool,
+    /// Enable SDA and SCL stuck-at-low detection and recovery (BUS_CLEAR_FEATURE_CTRL).
+    #[bit(11, rw)]
+    pub bus_clear_feature_ctrl: bool,
+}
+
+/// I2C Target Address Register (IC_TAR).
+#[bitfield(u32, default = 0x0)]
+#[derive(Debug, PartialEq, Eq)]
+pub struct Tar {
+    /// Target address for master transfers (IC_TAR).
+    #[bits(0..=9, rw)]
+    pub address: u10,
+    /// Send a START byte instead of a general call when `special` is set (GC_OR_START).
+    #[bit(10, rw)]
+    pub gc_or_start: bool,
+    /// Perform a general call or START byte instead of a normal transfer (SPECIAL).
+    #[bit(11, rw)]
+    pub special: bool,
+    /// Use 10-bit addressing for master transfers (IC_10BITADDR_MASTER).
+    #[bit(12, rw)]
+    pub addr_10bit_master: bool,
+}
+
+/// Direction of a command pushed into the transmit FIFO.
+#[bitenum(u1, exhaustive = true)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Write the data byte.
+    Write = 0,
+    /// Read a byte into the receive FIFO.
+    Read = 1,
+}
+
+/// I2C Rx/Tx Data Buffer and Command Register (IC_DATA_CMD).
+#[bitfield(u32, default = 0x0)]
+#[derive(Debug, PartialEq, Eq)]
+pub struct DataCmd {
+    /// Byte to transmit, or received byte when read (DAT).
+    #[bits(0..=7, rw)]
+    pub data: u8,
+    /// Read or write command (CMD).
+    #[bit(8, w)]
+    pub command: Command,
+    /// Issue a STOP after this byte (STOP).
+    #[bit(9, w)]
+    pub stop: bool,
+    /// Issue a RESTART before this byte (RESTART).
+    #[bit(10, w)]
+    pub restart: bool,
+    /// The received byte is the first data byte after the address phase (FIRST_DATA_BYTE).
+    #[bit(11, r)]
+    pub first_data_byte: bool,
+}
+
+/// Interrupt bits shared by IC_INTR_STAT, IC_INTR_MASK and IC_RAW_INTR_STAT.
+#[bitfield(u32, default = 0x0)]
+#[derive(Debug, PartialEq, Eq)]
+pub struct Interrupts {
+    /// Receive FIFO read while empty (RX_UNDER).
+    #[bit(0, rw)]
+    pub rx_under: bool,
+    /// Byte received while the receive FIFO was full (RX_OVER).
+    #[bit(1, rw)]
+    pub rx_over: bool,
+    /// Receive FIFO above the IC_RX_TL threshold (RX_FULL).
+    #[bit(2, rw)]
+    pub rx_full: bool,
+    /// Transmit FIFO written while full (TX_OVER).
+    #[bit(3, rw)]
+    pub tx_over: bool,
+    /// Transmit FIFO at or below the IC_TX_TL threshold (TX_EMPTY).
+    #[bit(4, rw)]
+    pub tx_empty: bool,
+    /// A master is reading from this slave (RD_REQ).
+    #[bit(5, rw)]
+    pub rd_req: bool,
+    /// The transfer was aborted; see IC_TX_ABRT_SOURCE (TX_ABRT).
+    #[bit(6, rw)]
+    pub tx_abrt: bool,
+    /// Master did not acknowledge a byte sent as slave transmitter (RX_DONE).
+    #[bit(7, rw)]
+    pub rx_done: bool,
+    /// Bus activity (ACTIVITY).
+    #[bit(8, rw)]
+    pub activity: bool,
+    /// STOP condition detected (STOP_DET).
+    #[bit(9, rw)]
+    pub stop_det: bool,
+    /// START or RESTART condition detected (START_DET).
+    #[bit(10, rw)]
+    pub start_det: bool,
+    /// General call address received (GEN_CALL).
+    #[bit(11, rw)]
+    pub gen_call: bool,
+    /// RESTART condition detected while addressed as slave (RESTART_DET).
+    #[bit(12, rw)]
+    pub restart_det: bool,
+    /// Master holds the bus with an empty transmit FIFO (MST_ON_HOLD).
+    #[bit(13, rw)]
+    pub master_on_hold: bool,
+    /// SCL held low longer than IC_SCL_STUCK_AT_LOW_TIMEOUT (SCL_STUCK_AT_LOW).
+    #[bit(14, rw)]
+    pub scl_stuck_at_low: bool,
+}
+
+/// I2C Enable Register (IC_ENABLE).
+#[bitfield(u32, default = 0x0)]
+#[derive(Debug, PartialEq, Eq)]
+pub struct Enable {
+    /// Enable the controller (ENABLE).
+    #[bit(0, rw)]
+    pub enable: bool,
+    /// Abort the current transfer; cleared by hardware when done (ABORT).
+    #[bit(1, rw)]
+    pub abort: bool,
+    /// Block transmission of commands in the transmit FIFO (TX_CMD_BLOCK).
+    #[bit(2, rw)]
+    pub tx_cmd_block: bool,
+    /// Start SDA stuck-at-low recovery; cleared by hardware when done (SDA_STUCK_RECOVERY_ENABLE).
+    #[bit(3, rw)]
+    pub sda_stuck_recovery_enable: bool,
+}
+
+/// I2C Status Register (IC_STATUS).
+#[bitfield(u32)]
+#[derive(Debug, PartialEq, Eq)]
+pub struct Status {
+    /// Controller activity (ACTIVITY).
+    #[bit(0, r)]
+    pub activity: bool,
+    /// Transmit FIFO not full (TFNF).
+    #[bit(1, r)]
+    pub transmit_fifo_not_full: bool,
+    /// Transmit FIFO empty (TFE).
+    #[bit(2, r)]
+    pub transmit_fifo_empty: bool,
+    /// Receive FIFO not empty (RFNE).
+    #[bit(3, r)]
+    pub receive_fifo_not_empty: bool,
+    /// Receive FIFO full (RFF).
+    #[bit(4, r)]
+    pub receive_fifo_full: bool,
+    /// Master state machine active (MST_ACTIVITY).
+    #[bit(5, r)]
+    pub master_activity: bool,
+    /// Slave state machine active (SLV_ACTIVITY).
+    #[bit(6, r)]
+    pub slave_activity: bool,
+    /// Master holds the bus because the transmit FIFO is empty (MST_HOLD_TX_FIFO_EMPTY).
+    #[bit(7, r)]
+    pub master_hold_tx_fifo_empty: bool,
+    /// Master holds the bus because the receive FIFO is full (MST_HOLD_RX_FIFO_FULL).
+    #[bit(8, r)]
+    pub master_hold_rx_fifo_full: bool,
+    /// Slave holds the bus because the transmit FIFO is empty (SLV_HOLD_TX_FIFO_EMPTY).
+    #[bit(9, r)]
+    pub slave_hold_tx_fifo_empty: bool,
+    /// Slave holds the bus because the receive FIFO is full (SLV_HOLD_RX_FIFO_FULL).
+    #[bit(10, r)]
+    pub slave_hold_rx_fifo_full: bool,
+    /// SDA stuck-at-low recovery did not free the bus (SDA_STUCK_NOT_RECOVERED).
+    #[bit(11, r)]
+    pub sda_stuck_not_recovered: bool,
+}
+
+/// I2C Transmit Abort Source Register (IC_TX_ABRT_SOURCE).
+#[bitfield(u32)]
+#[derive(Debug, PartialEq, Eq)]
+pub struct TxAbrtSource {
+    /// 7-bit address not acknowledged (ABRT_7B_ADDR_NOACK).
+    #[bit(0, r)]
+    pub addr_7bit_noack: bool,
+    /// First byte of a 10-bit address not acknowledged (ABRT_10ADDR1_NOACK).
+    #[bit(1, r)]
+    pub addr_10bit_1_noack: bool,
+    /// Second byte of a 10-bit address not acknowledged (ABRT_10ADDR2_NOACK).
+    #[bit(2, r)]
+    pub addr_10bit_2_noack: bool,
+    /// Data byte not acknowledged (ABRT_TXDATA_NOACK).
+    #[bit(3, r)]
+    pub tx_data_noack: bool,
+    /// General call not acknowledged (ABRT_GCALL_NOACK).
+    #[bit(4, r)]
+    pub general_call_noack: bool,
+    /// Read command issued after a general call (ABRT_GCALL_READ).
+    #[bit(5, r)]
+    pub general_call_read: bool,
+    /// High speed master code acknowledged (ABRT_HS_ACKDET).
+    #[bit(6, r)]
+    pub hs_ack_detected: bool,
+    /// START byte acknowledged (ABRT_SBYTE_ACKDET).
+    #[bit(7, r)]
+    pub start_byte_ack_detected: bool,
+    /// High speed transfer with RESTART disabled (ABRT_HS_NORSTRT).
+    #[bit(8, r)]
+    pub hs_no_restart: bool,
+    /// START byte with RESTART disabled (ABRT_SBYTE_NORSTRT).
+    #[bit(9, r)]
+    pub start_byte_no_restart: bool,
+    /// 10-bit read with RESTART disabled (ABRT_10B_RD_NORSTRT).
+    #[bit(10, r)]
+    pub read_10bit_no_restart: bool,
+    /// Master operation with master mode disabled (ABRT_MASTER_DIS).
+    #[bit(11, r)]
+    pub master_disabled: bool,
+    /// Master lost arbitration (ARB_LOST).
+    #[bit(12, r)]
+    pub arbitration_lost: bool,
+    /// Slave flushed the transmit FIFO on a read request (ABRT_SLVFLUSH_TXFIFO).
+    #[bit(13, r)]
+    pub slave_flush_tx_fifo: bool,
+    /// Slave lost arbitration while transmitting (ABRT_SLV_ARBLOST).
+    #[bit(14, r)]
+    pub slave_arbitration_lost: bool,
+    /// Slave read command written to IC_DATA_CMD (ABRT_SLVRD_INTX).
+    #[bit(15, r)]
+    pub slave_read_in_tx: bool,
+    /// Transfer aborted through IC_ENABLE.ABORT (ABRT_USER_ABRT).
+    #[bit(16, r)]
+    pub user_abort: bool,
+    /// SDA stuck at low (ABRT_SDA_STUCK_AT_LOW).
+    #[bit(17, r)]
+    pub sda_stuck_at_low: bool,
+    /// Number of commands flushed from the transmit FIFO (TX_FLUSH_CNT).
+    #[bits(23..=31, r)]
+    pub tx_flush_count: u9,
+}
+
+/// I2C Enable Status Register (IC_ENABLE_STATUS).
+#[bitfield(u32)]
+#[derive(Debug, PartialEq, Eq)]
+pub struct EnableStatus {
+    /// Controller is enabled (IC_EN).
+    #[bit(0, r)]
+    pub enabled: bool,
+    /// Slave was disabled while a transfer was in progress (SLV_DISABLED_WHILE_BUSY).
+    #[bit(1, r)]
+    pub slave_disabled_while_busy: bool,
+    /// Slave receive data was lost when disabled (SLV_RX_DATA_LOST).
+    #[bit(2, r)]
+    pub slave_rx_data_lost: bool,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -269,4 +565,57 @@ mod tests {
         assert_eq!(offset_of!(RegisterBlock, comp_version), 0xF8);
         assert_eq!(offset_of!(RegisterBlock, comp_type), 0xFC);
     }
+
+    #[test]
+    fn con_fields() {
+        let con = Con::DEFAULT
+            .with_master_mode(true)
+            .with_speed(Speed::Fast)
+            .with_restart_enable(true)
+            .with_slave_disable(true);
+        assert_eq!(con.raw_value(), 0x65);
+        assert_eq!(con.speed(), Ok(Speed::Fast));
+        // Speed 0 is reserved.
+        assert!(Con::new_with_raw_value(0).speed().is_err());
+        assert!(Con::new_with_raw_value(1 << 11).bus_clear_feature_ctrl());
+    }
+
+    #[test]
+    fn tar_fields() {
+        let tar = Tar::DEFAULT
+            .with_address(u10::new(0x2A5))
+            .with_addr_10bit_master(true);
+        assert_eq!(tar.raw_value(), 0x12A5);
+        let start_byte = Tar::DEFAULT.with_special(true).with_gc_or_start(true);
+        assert_eq!(start_byte.raw_value(), 0xC00);
+    }
+
+    #[test]
+    fn data_cmd_fields() {
+        let command = DataCmd::DEFAULT
+            .with_data(0xA5)
+            .with_command(Command::Read)
+            .with_stop(true)
+            .with_restart(true);
+        assert_eq!(command.raw_value(), 0x7A5);
+        let received = DataCmd::new_with_raw_value(0x85A);
+        assert_eq!(received.data(), 0x5A);
+        assert!(received.first_data_byte());
+    }
+
+    #[test]
+    fn interrupt_bits() {
+        let interrupts = Interrupts::DEFAULT
+            .with_rx_full(true)
+            .with_tx_abrt(true)
+            .with_stop_det(true)
+            .with_scl_stuck_at_low(true);
+        assert_eq!(
+            interrupts.raw_value(),
+            (1 << 2) | (1 << 6) | (1 << 9) | (1 << 14)
+        );
+        let raw = Interrupts::new_with_raw_value(1 << 4);
+        assert!(raw.tx_empty());
+        assert!(!raw.rx_full());
+    }
 }
@@ -0,0 +1,124 @@
+//! Identification of the DesignWare peripheral blocks.
+//!
+//! The SSI, UART and GPIO controllers carry read-only registers describing
+//! the IP block and its version. Reading them before a driver is configured
+//! catches a wrong base address early, which is the usual mistake when the
+//! peripheral table of a new chip is written: instead of programming some
+//! other block, or hanging on an unmapped bus, the `try_new` constructors
+//! fail with an `IdMismatch` error carrying the value that was read.
+//!
+//! The checks only rely on values fixed by the DesignWare IP, so they hold
+//! on every Kendryte chip:
+//!
+//! - UART: the component type register reads [`UART_COMPONENT_TYPE`] and the
+//!   component version register holds an ASCII version.
+//! - SPI: the `SSI_VERSION_ID` register holds an ASCII version.
+//! - GPIO: the `VER_ID_CODE` register holds an ASCII version.
+
+use crate::gpio::{self, GpioError};
+use crate::spi::{self, SpiError};
+use crate::uart::{self, UartError};
+use core::fmt;
+
+/// Component type register value of a DesignWare APB UART (`DW` and `0x0110`).
+pub const UART_COMPONENT_TYPE: u32 = 0x4457_0110;
+
+/// Version of a DesignWare IP block.
+///
+/// Version registers hold the version as ASCII digits followed by `*`, so
+/// version 1.03 reads `0x3130_332A`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ComponentVersion {
+    /// Major version, the digit before the point.
+    pub major: u8,
+    /// Minor version, the two digits after the point.
+    pub minor: u8,
+}
+
+impl ComponentVersion {
+    /// Decodes a version register value.
+    ///
+    /// Returns `None` if `raw` is not three ASCII digits followed by `*`.
+    pub const fn parse(raw: u32) -> Option<Self> {
+        let [major, tens, ones, star] = raw.to_be_bytes();
+        if star != b'*'
+            || !major.is_ascii_digit()
+            || !tens.is_ascii_digit()
+            || !ones.is_ascii_digit()
+        {
+            return None;
+        }
+        Some(Self {
+            major: major - b'0',
+            minor: (tens - b'0') * 10 + (ones - b'0'),
+        })
+    }
+}
+
+impl fmt::Display for ComponentVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:02}", self.major, self.minor)
+    }
+}
+
+/// Checks that `regs` is an SPI controller and returns its version.
+pub fn spi(regs: &spi::RegisterBlock) -> Result<ComponentVersion, SpiError> {
+    let raw = regs.ssi_version_id.read().component_version();
+    ComponentVersion::parse(raw).ok_or(SpiError::IdMismatch(raw))
+}
+
+/// Checks that `regs` is a UART controller and returns its version.
+pub fn uart(regs: &uart::MmioRegisterBlock) -> Result<ComponentVersion, UartError> {
+    let ctr = regs.read_ctr();
+    if ctr != UART_COMPONENT_TYPE {
+        return Err(UartError::IdMismatch(ctr));
+    }
+    let raw = regs.read_ucv();
+    ComponentVersion::parse(raw).ok_or(UartError::IdMismatch(raw))
+}
+
+/// Checks that `regs` is a GPIO controller and returns its version.
+pub fn gpio(regs: &gpio::MmioRegisterBlock) -> Result<ComponentVersion, GpioError> {
+    let raw = regs.read_ver_id_code().version_id();
+    ComponentVersion::parse(raw).ok_or(GpioError::IdMismatch(raw))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+
+    #[test]
+    fn parse_version() {
+        assert_eq!(
+            ComponentVersion::parse(0x3130_332A),
+            Some(ComponentVersion { major: 1, minor: 3 })
+        );
+        assert_eq!(
+            ComponentVersion::parse(0x3430_322A),
+            Some(ComponentVersion { major: 4, minor: 2 })
+        );
+        assert_eq!(ComponentVersion::parse(0), None);
+        assert_eq!(ComponentVersion::parse(0xFFFF_FFFF), None);
+        assert_eq!(ComponentVersion::parse(0x3130_3330), None);
+    }
+
+    #[test]
+    fn display_version() {
+        extern crate std;
+        use std::string::ToString;
+        let version = ComponentVersion { major: 1, minor: 3 };
+        assert_eq!(version.to_string(), "1.03");
+    }
+
+    #[test]
+    fn identify_spi() {
+        let regs = unsafe { &*mock::block::<spi::RegisterBlock>() };
+        assert_eq!(spi(regs), Err(SpiError::IdMismatch(0)));
+        unsafe {
+            regs.ssi_version_id
+                .write(spi::ComponentVersionReg::new_with_raw_value(0x3130_332A));
+        }
+        assert_eq!(spi(regs), Ok(ComponentVersion { major: 1, minor: 3 }));
+    }
+}
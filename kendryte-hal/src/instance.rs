@@ -1,6 +1,47 @@
-pub trait Instance<'i> {
+//! Peripheral ownership tokens.
+//!
+//! The runtime crate represents every peripheral as a zero-sized token which
+//! can only be moved, never copied. Drivers take a token, or a mutable borrow
+//! of one, and turn it into the register block with [`Instance::inner`].
+//! Tokens are `Send`, so a peripheral can be handed to another hart or task
+//! before a driver is created from it.
+//!
+//! Tokens also carry static [`PeripheralInfo`] about their instance, such as
+//! the interrupt source, so drivers and runtime code can enable clocks and
+//! register handlers without per-instance tables of their own.
+
+use crate::clocks::ClockId;
+
+/// A peripheral instance that drivers can be created from.
+pub trait Instance<'i>: Send {
+    /// Register block handle used by the driver.
     type R;
+    /// Consumes the token and returns the register block.
     fn inner(self) -> Self::R;
 }
 
+/// An [`Instance`] that is peripheral number `N` of its kind.
+///
+/// Drivers use `N` to select the matching clock and pads at compile time.
 pub trait Numbered<'i, const N: usize>: Instance<'i> {}
+
+/// A peripheral token that can be created without taking it from the peripherals struct.
+pub trait Steal: Sized {
+    /// Steals this peripheral instance.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that no driver created from another token of
+    /// the same peripheral is still in use.
+    unsafe fn steal() -> Self;
+}
+
+/// Static information about a peripheral instance.
+pub trait PeripheralInfo {
+    /// Address of the register block.
+    const BASE: usize;
+    /// Interrupt source on the platform interrupt controller, if known.
+    const IRQ: Option<usize>;
+    /// Clock feeding the peripheral, if known.
+    const CLOCK: Option<ClockId>;
+}
@@ -0,0 +1,152 @@
+//! Runtime detection of pads claimed by two peripherals.
+//!
+//! With the `pad-claims` feature every function assignment through
+//! [`PadOps::set_function_select`](crate::iomux::ops::PadOps::set_function_select)
+//! is recorded, and assigning a different function to a pad that is still
+//! claimed panics with both functions. This catches the classic mistake of
+//! initializing SPI on the pads the console UART is using, which otherwise
+//! shows up as the UART silently going dead.
+//!
+//! A claim is dropped when the pad is disabled, which every driver does in
+//! its `free`, so pads can be handed from one driver to the next. Setting
+//! the same function again is allowed, e.g. when a driver is re-created.
+//!
+//! The registry is a development aid: it costs an atomic operation per pad
+//! configuration and the check cannot see pads programmed by other means,
+//! such as [`IomuxSnapshot::restore`](crate::iomux::IomuxSnapshot::restore).
+
+use crate::iomux::dump::PAD_COUNT;
+use crate::iomux::pad::FunctionSelect;
+#[cfg(not(any(feature = "k510", feature = "k210")))]
+use arbitrary_int::u3;
+use core::fmt;
+use core::sync::atomic::{AtomicU16, Ordering};
+
+/// Function of each pad plus one, or zero while the pad is unclaimed.
+static CLAIMS: [AtomicU16; PAD_COUNT] = [const { AtomicU16::new(0) }; PAD_COUNT];
+
+/// A pad was given a function while claimed with another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PadConflict {
+    /// Pad number.
+    pub pad: usize,
+    /// Function the pad is claimed with.
+    pub claimed: FunctionSelect,
+    /// Function that was requested.
+    pub requested: FunctionSelect,
+}
+
+impl fmt::Display for PadConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "pad {} claimed with function {}, requested function {}",
+            self.pad, self.claimed, self.requested
+        )
+    }
+}
+
+/// Claim `pad` for `function`.
+///
+/// Succeeds if the pad is unclaimed or already claimed for `function`.
+///
+/// # Panics
+///
+/// Panics if `pad` is not below [`PAD_COUNT`].
+pub fn claim(pad: usize, function: FunctionSelect) -> Result<(), PadConflict> {
+    assert!(pad < PAD_COUNT, "pad number out of range");
+    let requested = u16::from(function) + 1;
+    match CLAIMS[pad].compare_exchange(0, requested, Ordering::AcqRel, Ordering::Acquire) {
+        Ok(_) => Ok(()),
+        Err(current) if current == requested => Ok(()),
+        Err(current) => Err(PadConflict {
+            pad,
+            claimed: function_select(current - 1),
+            requested: function,
+        }),
+    }
+}
+
+/// Drop the claim on `pad`, if any.
+///
+/// # Panics
+///
+/// Panics if `pad` is not below [`PAD_COUNT`].
+pub fn release(pad: usize) {
+    assert!(pad < PAD_COUNT, "pad number out of range");
+    CLAIMS[pad].store(0, Ordering::Release);
+}
+
+/// Function `pad` is claimed with, if any.
+///
+/// # Panics
+///
+/// Panics if `pad` is not below [`PAD_COUNT`].
+pub fn claimed(pad: usize) -> Option<FunctionSelect> {
+    assert!(pad < PAD_COUNT, "pad number out of range");
+    match CLAIMS[pad].load(Ordering::Acquire) {
+        0 => None,
+        current => Some(function_select(current - 1)),
+    }
+}
+
+/// Function select value stored as `raw` in the registry.
+#[cfg(not(any(feature = "k510", feature = "k210")))]
+fn function_select(raw: u16) -> FunctionSelect {
+    u3::new(raw as u8)
+}
+
+/// Function select value stored as `raw` in the registry.
+#[cfg(any(feature = "k510", feature = "k210"))]
+fn function_select(raw: u16) -> FunctionSelect {
+    raw as u8
+}
+
+/// Drop every claim, e.g. after restoring a pad snapshot.
+pub fn release_all() {
+    for claim in &CLAIMS {
+        claim.store(0, Ordering::Release);
+    }
+}
+
+/// Pad number of the pad register at `address`.
+///
+/// The pad registers are consecutive words from the start of the IOMUX
+/// block, whose address is a multiple of the size of the pad table on every
+/// chip.
+#[inline]
+pub(crate) fn pad_number(address: usize) -> usize {
+    (address / 4) % PAD_COUNT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claim_and_release() {
+        // Tests share the registry, so this one keeps to pad 40.
+        assert_eq!(claim(40, function_select(1)), Ok(()));
+        assert_eq!(claim(40, function_select(1)), Ok(()));
+        assert_eq!(
+            claim(40, function_select(2)),
+            Err(PadConflict {
+                pad: 40,
+                claimed: function_select(1),
+                requested: function_select(2),
+            })
+        );
+        assert_eq!(claimed(40), Some(function_select(1)));
+        release(40);
+        assert_eq!(claimed(40), None);
+        assert_eq!(claim(40, function_select(2)), Ok(()));
+        release(40);
+    }
+
+    #[test]
+    fn pad_number_from_address() {
+        let base = 0x1000 * PAD_COUNT * 4;
+        assert_eq!(pad_number(base), 0);
+        assert_eq!(pad_number(base + 41 * 4), 41);
+    }
+}
@@ -0,0 +1,143 @@
+//! Pad banks, supply voltages and drive currents.
+//!
+//! The K230 pads are grouped in banks with their own IO supply, which the
+//! board ties to 1.8 V or 3.3 V. The strength codes a pad accepts and the
+//! current each code delivers depend on that voltage: 1.8 V pads use all 16
+//! codes, 3.3 V pads only the lower 8.
+//!
+//! Bank 0 is fixed at 1.8 V; the others default to 3.3 V. Boards that power
+//! a bank differently record it with [`set_bank_voltage`] before configuring
+//! pads in it.
+
+use crate::iomux::dump::PAD_COUNT;
+use crate::iomux::pad::Strength;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// IO supply voltage of a pad bank.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VoltageDomain {
+    /// 1.8 V supply.
+    V1_8,
+    /// 3.3 V supply.
+    V3_3,
+}
+
+/// Number of pad banks.
+pub const BANK_COUNT: usize = 6;
+
+/// First pad of each bank.
+const BANK_START: [usize; BANK_COUNT] = [0, 2, 14, 26, 38, 50];
+
+/// Bit mask of banks supplied with 1.8 V.
+static BANKS_1V8: AtomicU8 = AtomicU8::new(0b00_0001);
+
+/// Nominal source and sink current per strength code at 1.8 V, in mA.
+const CURRENT_1V8: [(u8, u8); 16] = [
+    (2, 2),
+    (3, 4),
+    (5, 6),
+    (6, 7),
+    (8, 9),
+    (9, 11),
+    (11, 12),
+    (12, 14),
+    (14, 16),
+    (15, 17),
+    (17, 19),
+    (18, 21),
+    (20, 23),
+    (21, 24),
+    (23, 26),
+    (24, 28),
+];
+
+/// Nominal source and sink current per strength code at 3.3 V, in mA.
+const CURRENT_3V3: [(u8, u8); 8] = [
+    (5, 6),
+    (10, 12),
+    (15, 18),
+    (20, 24),
+    (25, 30),
+    (30, 36),
+    (35, 42),
+    (40, 48),
+];
+
+/// Current a pad can source and sink at a strength code.
+///
+/// The values are nominal, for an output held 0.4 V from the rail; the
+/// datasheet's IO characteristics give the limits over process and
+/// temperature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DriveCurrent {
+    /// Current sourced while driving high, in mA.
+    pub source_ma: u8,
+    /// Current sunk while driving low, in mA.
+    pub sink_ma: u8,
+}
+
+/// Bank containing pad `pad`.
+///
+/// # Panics
+///
+/// Panics if `pad` is not below [`PAD_COUNT`].
+pub const fn bank(pad: usize) -> usize {
+    assert!(pad < PAD_COUNT, "pad number out of range");
+    let mut bank = BANK_COUNT - 1;
+    while BANK_START[bank] > pad {
+        bank -= 1;
+    }
+    bank
+}
+
+/// Record the IO supply voltage the board feeds `bank` with.
+///
+/// # Panics
+///
+/// Panics if `bank` is not below [`BANK_COUNT`].
+pub fn set_bank_voltage(bank: usize, voltage: VoltageDomain) {
+    assert!(bank < BANK_COUNT, "bank number out of range");
+    match voltage {
+        VoltageDomain::V1_8 => BANKS_1V8.fetch_or(1 << bank, Ordering::Relaxed),
+        VoltageDomain::V3_3 => BANKS_1V8.fetch_and(!(1 << bank), Ordering::Relaxed),
+    };
+}
+
+/// IO supply voltage of `bank`.
+pub fn bank_voltage(bank: usize) -> VoltageDomain {
+    if BANKS_1V8.load(Ordering::Relaxed) & (1 << bank) != 0 {
+        VoltageDomain::V1_8
+    } else {
+        VoltageDomain::V3_3
+    }
+}
+
+/// IO supply voltage of pad `pad`.
+pub fn voltage_domain(pad: usize) -> VoltageDomain {
+    bank_voltage(bank(pad))
+}
+
+/// Strongest strength code pad `pad` accepts.
+pub fn max_strength(pad: usize) -> Strength {
+    match voltage_domain(pad) {
+        VoltageDomain::V1_8 => Strength::_15,
+        VoltageDomain::V3_3 => Strength::_7,
+    }
+}
+
+/// Returns true if pad `pad` accepts strength code `strength`.
+pub fn is_valid_strength(pad: usize, strength: Strength) -> bool {
+    strength as u8 <= max_strength(pad) as u8
+}
+
+/// Nominal current of pad `pad` at strength code `strength`.
+///
+/// Returns `None` if the pad does not accept the code.
+pub fn drive_current(pad: usize, strength: Strength) -> Option<DriveCurrent> {
+    let (source_ma, sink_ma) = match voltage_domain(pad) {
+        VoltageDomain::V1_8 => CURRENT_1V8.get(strength as usize),
+        VoltageDomain::V3_3 => CURRENT_3V3.get(strength as usize),
+    }
+    .copied()?;
+    Some(DriveCurrent { source_ma, sink_ma })
+}
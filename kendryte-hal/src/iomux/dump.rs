@@ -0,0 +1,135 @@
+//! Pad configuration readback.
+//!
+//! Reads the configuration of every pad from the IOMUX registers, so a debug
+//! console can print the pin multiplexing table at runtime. A peripheral that
+//! is silently not routed to the expected pin shows up as a wrong function
+//! select or a disabled input or output in the table.
+
+use crate::iomux::MmioRegisterBlock;
+use crate::iomux::ops::{Pull, register_offset};
+use crate::iomux::pad::{self, FunctionSelect, SlewRate, Strength};
+use crate::trace::{self, Raw};
+use arbitrary_int::u1;
+use core::fmt;
+
+/// Number of pads controlled by the IOMUX.
+#[cfg(not(any(feature = "k510", feature = "k210")))]
+pub const PAD_COUNT: usize = 64;
+/// Number of pads controlled by the IOMUX.
+#[cfg(feature = "k510")]
+pub const PAD_COUNT: usize = 128;
+/// Number of pads controlled by the IOMUX.
+#[cfg(feature = "k210")]
+pub const PAD_COUNT: usize = 48;
+
+/// Configuration of a single pad.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PadConfig {
+    /// Pad number.
+    pub number: usize,
+    /// Selected alternate function.
+    pub function_select: FunctionSelect,
+    /// Pull resistor, or `None` if pull-up and pull-down are both enabled.
+    pub pull: Option<Pull>,
+    /// Output drive strength.
+    pub drive_strength: Strength,
+    /// Output slew rate.
+    pub slew_rate: SlewRate,
+    /// Input Schmitt trigger enable.
+    pub schmitt_trigger: bool,
+    /// Input enable.
+    pub input_enable: bool,
+    /// Output enable.
+    pub output_enable: bool,
+    /// Level currently seen on the pad.
+    pub input_data: u1,
+}
+
+impl PadConfig {
+    /// Decodes the pad register value of pad `number`.
+    pub fn from_register(number: usize, pad: pad::Pad) -> Self {
+        let pull = match (pad.pull_up_enable(), pad.pull_down_enable()) {
+            (false, false) => Some(Pull::None),
+            (true, false) => Some(Pull::Up),
+            (false, true) => Some(Pull::Down),
+            (true, true) => None,
+        };
+        Self {
+            number,
+            function_select: pad.function_select(),
+            pull,
+            drive_strength: pad.drive_strength(),
+            slew_rate: pad.slew_rate(),
+            schmitt_trigger: pad.schmitt_trigger_enable(),
+            input_enable: pad.input_enable(),
+            output_enable: pad.output_enable(),
+            input_data: pad.data_input(),
+        }
+    }
+}
+
+/// Prints one line of the pin multiplexing table, for example
+/// `io38 fn=1 -O pull=none drive=7 slew=fast st=off level=1`.
+impl fmt::Display for PadConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pull = match self.pull {
+            Some(Pull::None) => "none",
+            Some(Pull::Up) => "up",
+            Some(Pull::Down) => "down",
+            None => "both",
+        };
+        let slew = match self.slew_rate {
+            SlewRate::Fast => "fast",
+            SlewRate::Slow => "slow",
+        };
+        write!(
+            f,
+            "io{:<2} fn={} {}{} pull={:<4} drive={:<2} slew={} st={} level={}",
+            self.number,
+            self.function_select,
+            if self.input_enable { 'I' } else { '-' },
+            if self.output_enable { 'O' } else { '-' },
+            pull,
+            self.drive_strength as u8,
+            slew,
+            if self.schmitt_trigger { "on" } else { "off" },
+            self.input_data.value(),
+        )
+    }
+}
+
+/// Iterator over the configuration of all pads, returned by [`pad_configs`].
+pub struct PadConfigs {
+    iomux: MmioRegisterBlock<'static>,
+    next: usize,
+}
+
+impl Iterator for PadConfigs {
+    type Item = PadConfig;
+
+    fn next(&mut self) -> Option<PadConfig> {
+        if self.next >= PAD_COUNT {
+            return None;
+        }
+        let number = self.next;
+        self.next += 1;
+        // SAFETY: the pad register is only read.
+        let pad = unsafe { self.iomux.steal_pads_unchecked(number) }.read_pad();
+        trace::read("iomux", register_offset(number), pad.raw());
+        Some(PadConfig::from_register(number, pad))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = PAD_COUNT.saturating_sub(self.next);
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for PadConfigs {}
+
+/// Reads back the configuration of every pad.
+///
+/// Only reads registers, so it can be used while drivers own the pads.
+pub fn pad_configs(iomux: MmioRegisterBlock<'static>) -> PadConfigs {
+    PadConfigs { iomux, next: 0 }
+}
@@ -1,13 +1,24 @@
+#[cfg(feature = "pad-claims")]
+pub mod claims;
+pub mod drive;
+pub mod dump;
 pub mod ops;
 pub mod pad;
 mod register;
+pub mod snapshot;
 
 use crate::iomux::ops::PadOps;
 use core::marker::PhantomData;
+pub use drive::{DriveCurrent, VoltageDomain};
+pub use dump::{PadConfig, pad_configs};
 pub use register::*;
+pub use snapshot::IomuxSnapshot;
+
+crate::trace::impl_raw!(pad::Pad);
 
 pub struct FlexPad<'p> {
     inner: pad::MmioRegisterBlock<'static>,
+    offset: usize,
     _marker: PhantomData<&'p ()>,
 }
 
@@ -19,11 +30,16 @@ impl<'p> PadOps for FlexPad<'p> {
     fn inner_mut(&mut self) -> &mut pad::MmioRegisterBlock<'static> {
         &mut self.inner
     }
+
+    fn offset(&self) -> usize {
+        self.offset
+    }
 }
 
 impl<'p> FlexPad<'p> {
-    pub fn new(inner: pad::MmioRegisterBlock<'static>) -> Self {
+    pub fn new(mut inner: pad::MmioRegisterBlock<'static>) -> Self {
         Self {
+            offset: ops::pad_offset(inner.pointer_to_pad() as usize),
             inner,
             _marker: PhantomData,
         }
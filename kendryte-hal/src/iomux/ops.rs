@@ -1,6 +1,10 @@
 use super::pad;
-use crate::iomux::pad::{SlewRate, Strength};
-use arbitrary_int::{u1, u3};
+#[cfg(feature = "pad-claims")]
+use crate::iomux::claims;
+use crate::iomux::dump::PAD_COUNT;
+use crate::iomux::pad::{FunctionSelect, Pad, SlewRate, Strength};
+use crate::trace::{self, Raw};
+use arbitrary_int::u1;
 
 /// Pull-up/down configuration for a pad.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
@@ -17,20 +21,22 @@ pub trait PadOps {
     fn inner(&self) -> &pad::MmioRegisterBlock<'static>;
     /// Returns a reference to the underlying pad register.
     fn inner_mut(&mut self) -> &mut pad::MmioRegisterBlock<'static>;
+    /// Returns the byte offset of the pad register in the IOMUX block.
+    fn offset(&self) -> usize;
 
     /// Set the pull-up or pull-down configuration for the pad.
     fn set_pull(&mut self, pull: Pull) -> &mut Self {
         unsafe {
             match pull {
-                Pull::None => self
-                    .inner_mut()
-                    .modify_pad(|r| r.with_pull_up_enable(false).with_pull_down_enable(false)),
-                Pull::Up => self
-                    .inner_mut()
-                    .modify_pad(|r| r.with_pull_up_enable(true).with_pull_down_enable(false)),
-                Pull::Down => self
-                    .inner_mut()
-                    .modify_pad(|r| r.with_pull_up_enable(false).with_pull_down_enable(true)),
+                Pull::None => modify_pad(self, |r| {
+                    r.with_pull_up_enable(false).with_pull_down_enable(false)
+                }),
+                Pull::Up => modify_pad(self, |r| {
+                    r.with_pull_up_enable(true).with_pull_down_enable(false)
+                }),
+                Pull::Down => modify_pad(self, |r| {
+                    r.with_pull_up_enable(false).with_pull_down_enable(true)
+                }),
             };
         }
         self
@@ -39,8 +45,8 @@ pub trait PadOps {
     /// Get the current pull-up or pull-down configuration of the pad.
     /// Returns Some(Pull) if only one is enabled, or None if both are enabled (invalid state).
     fn pull(&self) -> Option<Pull> {
-        let is_pull_up = self.inner().read_pad().pull_up_enable();
-        let is_pull_down = self.inner().read_pad().pull_down_enable();
+        let is_pull_up = read_pad(self).pull_up_enable();
+        let is_pull_down = read_pad(self).pull_down_enable();
 
         match (is_pull_up, is_pull_down) {
             (true, false) => Some(Pull::Up),
@@ -53,8 +59,7 @@ pub trait PadOps {
     /// Enable the Schmitt trigger for the pad input.
     fn enable_schmitt_trigger(&mut self) -> &mut Self {
         unsafe {
-            self.inner_mut()
-                .modify_pad(|r| r.with_schmitt_trigger_enable(true));
+            modify_pad(self, |r| r.with_schmitt_trigger_enable(true));
         }
         self
     }
@@ -62,21 +67,28 @@ pub trait PadOps {
     /// Disable the Schmitt trigger for the pad input.
     fn disable_schmitt_trigger(&mut self) -> &mut Self {
         unsafe {
-            self.inner_mut()
-                .modify_pad(|r| r.with_schmitt_trigger_enable(false));
+            modify_pad(self, |r| r.with_schmitt_trigger_enable(false));
+        }
+        self
+    }
+
+    /// Enable or disable the Schmitt trigger for the pad input.
+    fn set_schmitt_trigger(&mut self, enable: bool) -> &mut Self {
+        unsafe {
+            modify_pad(self, |r| r.with_schmitt_trigger_enable(enable));
         }
         self
     }
 
     /// Check if the Schmitt trigger is enabled for the pad input.
     fn is_schmitt_trigger_enabled(&self) -> bool {
-        self.inner().read_pad().schmitt_trigger_enable()
+        read_pad(self).schmitt_trigger_enable()
     }
 
     /// Set the slew rate for the pad output.
     fn set_slew_rate(&mut self, slew_rate: SlewRate) -> &mut Self {
         unsafe {
-            self.inner_mut().modify_pad(|r| r.with_slew_rate(slew_rate));
+            modify_pad(self, |r| r.with_slew_rate(slew_rate));
         }
         self
     }
@@ -85,57 +97,66 @@ pub trait PadOps {
     /// The drive_strength parameter controls the output current capability.
     fn set_drive_strength(&mut self, drive_strength: Strength) -> &mut Self {
         unsafe {
-            self.inner_mut()
-                .modify_pad(|r| r.with_drive_strength(drive_strength));
+            modify_pad(self, |r| r.with_drive_strength(drive_strength));
         }
         self
     }
 
     /// Set the function select value for the pad.
-    fn set_function_select(&mut self, function_select: u3) -> &mut Self {
+    ///
+    /// With the `pad-claims` feature, panics if the pad is claimed with
+    /// another function, see [`claims`](crate::iomux::claims).
+    fn set_function_select(&mut self, function_select: FunctionSelect) -> &mut Self {
+        #[cfg(feature = "pad-claims")]
+        {
+            let pad = claims::pad_number(self.inner_mut().pointer_to_pad() as usize);
+            if let Err(conflict) = claims::claim(pad, function_select) {
+                panic!("{conflict}");
+            }
+        }
         unsafe {
-            self.inner_mut()
-                .modify_pad(|r| r.with_function_select(function_select));
+            modify_pad(self, |r| r.with_function_select(function_select));
         }
         self
     }
 
     /// Get the current slew rate setting of the pad.
     fn slew_rate(&self) -> SlewRate {
-        self.inner().read_pad().slew_rate()
+        read_pad(self).slew_rate()
     }
 
     /// Get the current drive strength setting of the pad.
     fn drive_strength(&self) -> Strength {
-        self.inner().read_pad().drive_strength()
+        read_pad(self).drive_strength()
     }
 
     /// Check if the pad input is enabled.
     fn is_input_enabled(&self) -> bool {
-        self.inner().read_pad().input_enable()
+        read_pad(self).input_enable()
     }
 
     /// Check if the pad output is enabled.
     fn is_output_enabled(&self) -> bool {
-        self.inner().read_pad().output_enable()
+        read_pad(self).output_enable()
     }
 
     /// Get the current function select value of the pad.
-    fn function_select(&self) -> u3 {
-        self.inner().read_pad().function_select()
+    fn function_select(&self) -> FunctionSelect {
+        read_pad(self).function_select()
     }
 
     /// Read the input data from the pad.
     fn input_data(&self) -> u1 {
-        self.inner().read_pad().data_input()
+        read_pad(self).data_input()
     }
 
     /// Configure the pad as input only.
     /// This enables input and disables output.
     fn set_input(&mut self) -> &mut Self {
         unsafe {
-            self.inner_mut()
-                .modify_pad(|r| r.with_input_enable(true).with_output_enable(false));
+            modify_pad(self, |r| {
+                r.with_input_enable(true).with_output_enable(false)
+            });
         }
         self
     }
@@ -144,8 +165,9 @@ pub trait PadOps {
     /// This enables output and disables input.
     fn set_output(&mut self) -> &mut Self {
         unsafe {
-            self.inner_mut()
-                .modify_pad(|r| r.with_input_enable(false).with_output_enable(true));
+            modify_pad(self, |r| {
+                r.with_input_enable(false).with_output_enable(true)
+            });
         }
         self
     }
@@ -153,18 +175,54 @@ pub trait PadOps {
     /// Configure the pad as bidirectional (input and output enabled).
     fn set_bidirectional(&mut self) -> &mut Self {
         unsafe {
-            self.inner_mut()
-                .modify_pad(|r| r.with_input_enable(true).with_output_enable(true));
+            modify_pad(self, |r| r.with_input_enable(true).with_output_enable(true));
         }
         self
     }
 
     /// Disable both input and output for the pad.
+    ///
+    /// With the `pad-claims` feature this also drops the pad's claim.
     fn set_disabled(&mut self) -> &mut Self {
         unsafe {
-            self.inner_mut()
-                .modify_pad(|r| r.with_input_enable(false).with_output_enable(false));
+            modify_pad(self, |r| {
+                r.with_input_enable(false).with_output_enable(false)
+            });
         }
+        #[cfg(feature = "pad-claims")]
+        claims::release(claims::pad_number(
+            self.inner_mut().pointer_to_pad() as usize
+        ));
         self
     }
 }
+
+/// Byte offset in the IOMUX block of the register of pad `number`.
+pub(crate) const fn register_offset(number: usize) -> usize {
+    number * core::mem::size_of::<pad::RegisterBlock>()
+}
+
+/// Byte offset in the IOMUX block of the pad register at `address`.
+pub(crate) fn pad_offset(address: usize) -> usize {
+    address % register_offset(PAD_COUNT)
+}
+
+/// Reads the pad register, reporting the access to [`crate::trace`].
+fn read_pad<P: PadOps + ?Sized>(pad: &P) -> Pad {
+    let value = pad.inner().read_pad();
+    trace::read("iomux", pad.offset(), value.raw());
+    value
+}
+
+/// Modifies the pad register, reporting the access to [`crate::trace`].
+///
+/// # Safety
+///
+/// Same as [`pad::MmioRegisterBlock::modify_pad`].
+unsafe fn modify_pad<P: PadOps + ?Sized>(pad: &mut P, f: impl FnOnce(Pad) -> Pad) {
+    let offset = pad.offset();
+    unsafe {
+        pad.inner_mut()
+            .modify_pad(|r| trace::modify("iomux", offset, r, f))
+    }
+}
@@ -1,7 +1,23 @@
-use arbitrary_int::{u1, u3};
+//! Pad register layout.
+//!
+//! The K230 packs the configuration of a pad into the low 14 bits with a
+//! 3-bit function select. The K510 IOMUX and the K210 FPIOA select one of up
+//! to 256 functions per pad in the low byte, with the other fields moved up
+//! to make room.
+
+use arbitrary_int::u1;
+#[cfg(not(any(feature = "k510", feature = "k210")))]
+use arbitrary_int::u3;
 use bitbybit::{bitenum, bitfield};
 use derive_mmio::Mmio;
 
+/// Function select value of a pad.
+#[cfg(not(any(feature = "k510", feature = "k210")))]
+pub type FunctionSelect = u3;
+/// Function select value of a pad.
+#[cfg(any(feature = "k510", feature = "k210"))]
+pub type FunctionSelect = u8;
+
 /// Pad Register Block.
 #[derive(Mmio)]
 #[repr(C)]
@@ -45,6 +61,7 @@ pub enum Strength {
 
 /// Pad represents the configuration of a single IO pad.
 /// Each field controls a specific aspect of the pad's behavior.
+#[cfg(not(any(feature = "k510", feature = "k210")))]
 #[bitfield(u32)]
 pub struct Pad {
     /// Input data from outside.
@@ -83,3 +100,45 @@ pub struct Pad {
     #[bit(0, rw)]
     pub schmitt_trigger_enable: bool,
 }
+
+/// Pad represents the configuration of a single IO pad.
+/// Each field controls a specific aspect of the pad's behavior.
+#[cfg(any(feature = "k510", feature = "k210"))]
+#[bitfield(u32)]
+pub struct Pad {
+    /// Input data from outside.
+    #[bit(31, r)]
+    pub data_input: u1,
+
+    /// Schmitt trigger enable, enables the Schmitt trigger for input.
+    #[bit(23, rw)]
+    pub schmitt_trigger_enable: bool,
+
+    /// Input enable, allows the pad to receive input.
+    #[bit(20, rw)]
+    pub input_enable: bool,
+
+    /// Slew rate control, sets the output transition speed.
+    #[bit(19, rw)]
+    pub slew_rate: SlewRate,
+
+    /// Pull down enable, enables the internal pull-down resistor.
+    #[bit(17, rw)]
+    pub pull_down_enable: bool,
+
+    /// Pull up enable, enables the internal pull-up resistor.
+    #[bit(16, rw)]
+    pub pull_up_enable: bool,
+
+    /// Output enable, allows the pad to drive output.
+    #[bit(12, rw)]
+    pub output_enable: bool,
+
+    /// Drive strength control, sets the output drive strength.
+    #[bits(8..=11, rw)]
+    pub drive_strength: Strength,
+
+    /// IO function select, determines the function of the pad.
+    #[bits(0..=7, rw)]
+    pub function_select: u8,
+}
@@ -1,3 +1,4 @@
+use super::dump::PAD_COUNT;
 use super::pad;
 use arbitrary_int::{u1, u3};
 use bitbybit::{bitenum, bitfield};
@@ -11,5 +12,5 @@ use derive_mmio::Mmio;
 #[repr(C)]
 pub struct RegisterBlock {
     #[mmio(Inner)]
-    pub pads: [pad::RegisterBlock; 64],
+    pub pads: [pad::RegisterBlock; PAD_COUNT],
 }
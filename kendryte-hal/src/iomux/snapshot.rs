@@ -0,0 +1,88 @@
+//! Pad configuration snapshot and restore.
+//!
+//! A second-stage firmware that hands over to another image, e.g. one loaded
+//! from an SD card, captures the pad configuration it found on entry and
+//! restores it before jumping, so the next image starts from the state the
+//! boot ROM left:
+//!
+//! ```ignore
+//! let pads = IomuxSnapshot::capture(&mut iomux);
+//! // ... use the pads ...
+//! pads.restore(&mut iomux);
+//! ```
+//!
+//! The snapshot holds raw register values, so it can be kept in memory shared
+//! between firmware stages.
+
+use crate::iomux::MmioRegisterBlock;
+use crate::iomux::dump::PAD_COUNT;
+use crate::iomux::ops::register_offset;
+use crate::iomux::pad::Pad;
+use crate::trace;
+
+/// Raw configuration of every pad.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(C)]
+pub struct IomuxSnapshot {
+    /// Pad register value, indexed by pad number.
+    pub pads: [u32; PAD_COUNT],
+}
+
+impl IomuxSnapshot {
+    /// Reads the configuration of every pad.
+    ///
+    /// Only reads registers, so it can be used while drivers own the pads.
+    pub fn capture(iomux: &mut MmioRegisterBlock<'static>) -> Self {
+        let mut pads = [0; PAD_COUNT];
+        for (number, pad) in pads.iter_mut().enumerate() {
+            // SAFETY: the pad register is only read.
+            *pad = unsafe { iomux.steal_pads_unchecked(number) }
+                .read_pad()
+                .raw_value();
+            trace::read("iomux", register_offset(number), *pad as u64);
+        }
+        Self { pads }
+    }
+
+    /// Writes the configuration back to every pad.
+    ///
+    /// Drivers still holding pads see their pads reconfigured, so release
+    /// or forget them first. The input level bit is read-only and ignored.
+    pub fn restore(&self, iomux: &mut MmioRegisterBlock<'static>) {
+        for (number, &pad) in self.pads.iter().enumerate() {
+            trace::write("iomux", register_offset(number), pad as u64);
+            unsafe {
+                iomux
+                    .steal_pads_unchecked(number)
+                    .write_pad(Pad::new_with_raw_value(pad))
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iomux::RegisterBlock;
+    use crate::mock;
+
+    #[test]
+    fn round_trip() {
+        let mut source = unsafe { RegisterBlock::new_mmio(mock::block::<RegisterBlock>()) };
+        for number in 0..PAD_COUNT {
+            let value = ((number as u32) << 8) | 0x3;
+            unsafe {
+                source
+                    .steal_pads_unchecked(number)
+                    .write_pad(Pad::new_with_raw_value(value))
+            };
+        }
+        let snapshot = IomuxSnapshot::capture(&mut source);
+        assert_eq!(snapshot.pads[5], (5 << 8) | 0x3);
+
+        let mut target = unsafe { RegisterBlock::new_mmio(mock::block::<RegisterBlock>()) };
+        let ((), log) = mock::capture(|| snapshot.restore(&mut target));
+        assert_eq!(mock::writes(&log)[5], (5 * 4, (5 << 8) | 0x3));
+        assert_eq!(IomuxSnapshot::capture(&mut target), snapshot);
+    }
+}
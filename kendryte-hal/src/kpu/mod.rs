@@ -0,0 +1,380 @@
+//! Low-level interface to the KPU neural network accelerator.
+//!
+//! The KPU runs a network one layer at a time. Each layer is described by
+//! twelve 64-bit layer arguments, produced by the model compiler, which are
+//! queued through the layer argument FIFO; the KPU computes the queued
+//! layers in order and raises its interrupt after a layer with the
+//! interrupt enable bit, set by [`Kpu::start`] on the last one. This driver
+//! does no more than that: it resets the engine, queues a [`Model`], and
+//! waits for the completion. Layers the KPU cannot compute, and any pre- and
+//! post-processing, stay with the application.
+//!
+//! ```ignore
+//! static BLOB: Aligned<[u8; N]> = Aligned(*include_bytes!("model.kpu"));
+//! let model = Model::from_bytes(&BLOB.0)?;
+//! let mut kpu = Kpu::new(p.kpu);
+//! kpu.write_ram(INPUT_OFFSET, &image);
+//! kpu.start(&model)?;
+//! kpu.wait(Timeout::from_millis(100))?;
+//! kpu.read_ram(OUTPUT_OFFSET, &mut output);
+//! ```
+//!
+//! The module is only built with the `k210` feature: the NPU of the K230 is
+//! driven by the vendor runtime and its interface is not published.
+//!
+//! # Memory layout
+//!
+//! - Feature maps live in the 2 MiB KPU RAM, [`RAM_SIZE`] bytes at
+//!   [`RAM_BASE`]. Layer arguments address it in 64 byte units from its
+//!   start, so the input and output offsets of a model are multiples of 64.
+//!   The CPU reaches it through the uncached alias used by
+//!   [`write_ram`](Kpu::write_ram) and [`read_ram`](Kpu::read_ram).
+//! - Weights, batch normalisation and activation tables are fetched from
+//!   main memory by the KPU at the absolute addresses in the layer
+//!   arguments, so they must be placed where the compiler was told they
+//!   would be, and written through the uncached alias of main memory, see
+//!   [`crate::dma::uncached`], since the KPU does not snoop the data cache.
+//! - The layer arguments themselves are read by the CPU and written to the
+//!   FIFO, so the [`Model`] blob can live anywhere, including flash mapped
+//!   memory, as long as it is aligned to 8 bytes.
+
+mod register;
+
+pub use register::*;
+
+crate::trace::traced_registers!($ "kpu", crate::kpu::RegisterBlock);
+
+use crate::instance::Instance;
+use crate::time::Timeout;
+use core::marker::PhantomData;
+
+/// Address of the uncached alias of the KPU RAM.
+pub const RAM_BASE: usize = 0x4060_0000;
+/// Size of the KPU RAM, in bytes.
+pub const RAM_SIZE: usize = 2 * 1024 * 1024;
+
+/// Number of 64-bit words in the arguments of one layer.
+pub const LAYER_WORDS: usize = 12;
+
+/// Interrupt enable bit in the first argument word of a layer.
+const LAYER_INT_EN: u64 = 1 << 0;
+
+/// Longest wait for room in the layer argument FIFO, in milliseconds.
+const FEED_TIMEOUT_MS: u32 = 100;
+
+/// Layer argument FIFO levels, in words, of the almost full and almost
+/// empty flags, as programmed by the SDK.
+const FIFO_FULL_THRESHOLD: u64 = 10;
+const FIFO_EMPTY_THRESHOLD: u64 = 1;
+
+/// Error type for KPU operations.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum KpuError {
+    /// The model is empty, misaligned or not made of whole layers.
+    InvalidModel,
+    /// A RAM access falls outside the KPU RAM.
+    OutOfRange,
+    /// The layer FIFO did not drain, or the model did not complete, in time.
+    Timeout,
+}
+
+/// Arguments of one layer, as produced by the model compiler.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(C)]
+pub struct LayerArgument(pub [u64; LAYER_WORDS]);
+
+/// Compiled network, the layer arguments of its KPU layers in order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Model<'a> {
+    layers: &'a [LayerArgument],
+}
+
+impl<'a> Model<'a> {
+    /// Creates a model from its layers.
+    #[inline]
+    pub const fn new(layers: &'a [LayerArgument]) -> Self {
+        Self { layers }
+    }
+
+    /// Interprets a blob of little-endian layer arguments.
+    ///
+    /// Fails with [`KpuError::InvalidModel`] unless the blob is 8 byte
+    /// aligned and holds at least one whole layer.
+    pub fn from_bytes(blob: &'a [u8]) -> Result<Self, KpuError> {
+        const LAYER_BYTES: usize = core::mem::size_of::<LayerArgument>();
+        if blob.is_empty()
+            || blob.len() % LAYER_BYTES != 0
+            || blob.as_ptr() as usize % core::mem::align_of::<LayerArgument>() != 0
+            || cfg!(target_endian = "big")
+        {
+            return Err(KpuError::InvalidModel);
+        }
+        // SAFETY: the blob is aligned and sized for whole layers, and every
+        // bit pattern is a valid `u64`.
+        let layers = unsafe {
+            core::slice::from_raw_parts(
+                blob.as_ptr().cast::<LayerArgument>(),
+                blob.len() / LAYER_BYTES,
+            )
+        };
+        Ok(Self { layers })
+    }
+
+    /// Layers of the model.
+    #[inline]
+    pub fn layers(&self) -> &'a [LayerArgument] {
+        self.layers
+    }
+}
+
+/// KPU driver.
+pub struct Kpu<'i> {
+    inner: MmioRegisterBlock<'static>,
+    _marker: PhantomData<&'i ()>,
+}
+
+impl<'i> Kpu<'i> {
+    /// Creates the driver and resets the engine.
+    pub fn new(instance: impl Instance<'i, R = MmioRegisterBlock<'static>>) -> Self {
+        let mut kpu = Self {
+            inner: instance.inner(),
+            _marker: PhantomData,
+        };
+        kpu.reset();
+        kpu
+    }
+
+    /// Drops queued layers and pending interrupts, and masks the interrupt.
+    ///
+    /// A layer being computed is not aborted; wait for it to complete before
+    /// reusing its RAM.
+    pub fn reset(&mut self) {
+        let all = INT_CALC_DONE | INT_LAYER_CFG_ALMOST_EMPTY | INT_LAYER_CFG_ALMOST_FULL;
+        unsafe {
+            write_reg!(self.inner, fifo_ctrl, write_fifo_ctrl, 0);
+            write_reg!(
+                self.inner,
+                fifo_ctrl,
+                write_fifo_ctrl,
+                FIFO_CTRL_DMA | FIFO_CTRL_GPU | FIFO_CTRL_CFG
+            );
+            write_reg!(
+                self.inner,
+                fifo_threshold,
+                write_fifo_threshold,
+                FIFO_EMPTY_THRESHOLD << 4 | FIFO_FULL_THRESHOLD
+            );
+            write_reg!(self.inner, eight_bit_mode, write_eight_bit_mode, 0);
+            write_reg!(self.inner, interrupt_mask, write_interrupt_mask, all);
+            write_reg!(self.inner, interrupt_clear, write_interrupt_clear, all);
+        }
+    }
+
+    /// Queues the layers of `model`, enabling the interrupt of the last one.
+    ///
+    /// The KPU starts with the first layer while the others are queued, so
+    /// this returns once the last layer is in the FIFO. Fails with
+    /// [`KpuError::InvalidModel`] if `model` has no layers, since nothing
+    /// would ever raise the interrupt, and with [`KpuError::Timeout`] if the
+    /// FIFO stops draining.
+    pub fn start(&mut self, model: &Model<'_>) -> Result<(), KpuError> {
+        if model.layers.is_empty() {
+            return Err(KpuError::InvalidModel);
+        }
+        unsafe {
+            write_reg!(
+                self.inner,
+                interrupt_clear,
+                write_interrupt_clear,
+                INT_CALC_DONE
+            )
+        };
+        let last = model.layers.len().saturating_sub(1);
+        for (index, layer) in model.layers.iter().enumerate() {
+            let inner = &self.inner;
+            Timeout::from_millis(FEED_TIMEOUT_MS).wait(KpuError::Timeout, || {
+                read_reg!(inner, interrupt_raw, read_interrupt_raw) & INT_LAYER_CFG_ALMOST_FULL == 0
+            })?;
+            for (word, &value) in layer.0.iter().enumerate() {
+                let value = match word {
+                    0 if index == last => value | LAYER_INT_EN,
+                    0 => value & !LAYER_INT_EN,
+                    _ => value,
+                };
+                unsafe {
+                    write_reg!(
+                        self.inner,
+                        layer_argument_fifo,
+                        write_layer_argument_fifo,
+                        value
+                    )
+                };
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true once the last layer of the model completed.
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        read_reg!(self.inner, interrupt_raw, read_interrupt_raw) & INT_CALC_DONE != 0
+    }
+
+    /// Waits for the model to complete and clears the completion.
+    pub fn wait(&mut self, timeout: Timeout) -> Result<(), KpuError> {
+        let inner = &self.inner;
+        timeout.wait(KpuError::Timeout, || {
+            read_reg!(inner, interrupt_raw, read_interrupt_raw) & INT_CALC_DONE != 0
+        })?;
+        unsafe {
+            write_reg!(
+                self.inner,
+                interrupt_clear,
+                write_interrupt_clear,
+                INT_CALC_DONE
+            )
+        };
+        Ok(())
+    }
+
+    /// Raise the KPU interrupt when a model completes.
+    pub fn listen(&mut self) {
+        unsafe {
+            modify_reg!(
+                self.inner,
+                interrupt_mask,
+                modify_interrupt_mask,
+                |mask| mask & !INT_CALC_DONE
+            )
+        };
+    }
+
+    /// Stop raising the KPU interrupt.
+    pub fn unlisten(&mut self) {
+        unsafe {
+            modify_reg!(
+                self.inner,
+                interrupt_mask,
+                modify_interrupt_mask,
+                |mask| mask | INT_CALC_DONE
+            )
+        };
+    }
+
+    /// Handles the KPU interrupt, returning true if a model completed.
+    ///
+    /// Clears the completion, so call it from the interrupt handler
+    /// instead of [`wait`](Self::wait).
+    pub fn on_interrupt(&mut self) -> bool {
+        let done = self.is_done();
+        unsafe {
+            write_reg!(
+                self.inner,
+                interrupt_clear,
+                write_interrupt_clear,
+                INT_CALC_DONE
+            )
+        };
+        done
+    }
+
+    /// Copies `data` into the KPU RAM at byte `offset`.
+    pub fn write_ram(&mut self, offset: usize, data: &[u8]) -> Result<(), KpuError> {
+        let addr = ram_address(offset, data.len())?;
+        // SAFETY: the range is inside the KPU RAM, which the driver owns
+        // while no model is running.
+        unsafe { core::ptr::copy_nonoverlapping(data.as_ptr(), addr as *mut u8, data.len()) };
+        Ok(())
+    }
+
+    /// Copies the KPU RAM at byte `offset` into `buf`.
+    pub fn read_ram(&mut self, offset: usize, buf: &mut [u8]) -> Result<(), KpuError> {
+        let addr = ram_address(offset, buf.len())?;
+        // SAFETY: as for `write_ram`.
+        unsafe { core::ptr::copy_nonoverlapping(addr as *const u8, buf.as_mut_ptr(), buf.len()) };
+        Ok(())
+    }
+}
+
+/// Address of `len` bytes at `offset` in the KPU RAM.
+fn ram_address(offset: usize, len: usize) -> Result<usize, KpuError> {
+    match offset.checked_add(len) {
+        Some(end) if end <= RAM_SIZE => Ok(RAM_BASE + offset),
+        _ => Err(KpuError::OutOfRange),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+
+    fn kpu() -> (*mut RegisterBlock, Kpu<'static>) {
+        let block = mock::block::<RegisterBlock>();
+        let kpu = Kpu {
+            inner: unsafe { RegisterBlock::new_mmio(block) },
+            _marker: PhantomData,
+        };
+        (block, kpu)
+    }
+
+    #[test]
+    fn model_from_bytes() {
+        #[repr(C, align(8))]
+        struct Blob([u8; 2 * 96 + 8]);
+        let mut blob = Blob([0; 2 * 96 + 8]);
+        blob.0[96] = 0x42;
+
+        let model = Model::from_bytes(&blob.0[..192]).unwrap();
+        assert_eq!(model.layers().len(), 2);
+        assert_eq!(model.layers()[1].0[0], 0x42);
+        assert_eq!(Model::from_bytes(&blob.0[..0]), Err(KpuError::InvalidModel));
+        assert_eq!(
+            Model::from_bytes(&blob.0[..100]),
+            Err(KpuError::InvalidModel)
+        );
+        assert_eq!(
+            Model::from_bytes(&blob.0[4..196]),
+            Err(KpuError::InvalidModel)
+        );
+    }
+
+    #[test]
+    fn start_and_wait() {
+        let (block, mut kpu) = kpu();
+        kpu.reset();
+        assert_eq!(kpu.inner.read_fifo_ctrl(), 0b111);
+        assert_eq!(kpu.inner.read_interrupt_mask(), 0b111);
+
+        let mut layers = [LayerArgument([0; LAYER_WORDS]); 2];
+        layers[0].0[0] = LAYER_INT_EN;
+        layers[1].0[LAYER_WORDS - 1] = 0x1234;
+        kpu.start(&Model::new(&layers)).unwrap();
+        // The FIFO register keeps the last word queued.
+        assert_eq!(kpu.inner.read_layer_argument_fifo(), 0x1234);
+
+        assert!(!kpu.is_done());
+        assert_eq!(kpu.wait(Timeout::from_ticks(0)), Err(KpuError::Timeout));
+        unsafe { (*block).interrupt_raw = INT_CALC_DONE };
+        assert_eq!(kpu.wait(Timeout::NEVER), Ok(()));
+        assert_eq!(kpu.inner.read_interrupt_clear(), INT_CALC_DONE);
+
+        kpu.listen();
+        assert_eq!(kpu.inner.read_interrupt_mask(), 0b110);
+    }
+
+    #[test]
+    fn start_rejects_empty_model() {
+        let (_block, mut kpu) = kpu();
+        assert_eq!(kpu.start(&Model::new(&[])), Err(KpuError::InvalidModel));
+        assert_eq!(kpu.inner.read_layer_argument_fifo(), 0);
+    }
+
+    #[test]
+    fn ram_bounds() {
+        assert_eq!(ram_address(0, RAM_SIZE), Ok(RAM_BASE));
+        assert_eq!(ram_address(64, RAM_SIZE), Err(KpuError::OutOfRange));
+        assert_eq!(ram_address(usize::MAX, 2), Err(KpuError::OutOfRange));
+    }
+}
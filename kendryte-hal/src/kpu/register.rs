@@ -0,0 +1,63 @@
+use derive_mmio::Mmio;
+
+/// KPU configuration register block.
+///
+/// Layout of the K210 KPU, as used by the Kendryte standalone SDK.
+#[derive(Mmio)]
+#[repr(C)]
+pub struct RegisterBlock {
+    /// Layer argument FIFO; every layer is queued as twelve writes.
+    pub layer_argument_fifo: u64,
+    /// Masked interrupt status.
+    #[mmio(PureRead)]
+    pub interrupt_status: u64,
+    /// Raw interrupt status.
+    #[mmio(PureRead)]
+    pub interrupt_raw: u64,
+    /// Interrupt mask, a set bit masks the interrupt.
+    pub interrupt_mask: u64,
+    /// Interrupt clear, write a set bit to clear the interrupt.
+    pub interrupt_clear: u64,
+    /// FIFO full and empty thresholds.
+    pub fifo_threshold: u64,
+    /// Output FIFO read by the DMA channel of the KPU.
+    #[mmio(PureRead)]
+    pub fifo_data_out: u64,
+    /// FIFO flush control, a cleared bit flushes the FIFO.
+    pub fifo_ctrl: u64,
+    /// Selects 8-bit instead of 16-bit feature maps.
+    pub eight_bit_mode: u64,
+}
+
+/// Interrupt bit set when a layer with its interrupt enabled completed.
+pub const INT_CALC_DONE: u64 = 1 << 0;
+/// Interrupt bit set while the layer argument FIFO is almost empty.
+pub const INT_LAYER_CFG_ALMOST_EMPTY: u64 = 1 << 1;
+/// Interrupt bit set while the layer argument FIFO is almost full.
+pub const INT_LAYER_CFG_ALMOST_FULL: u64 = 1 << 2;
+
+/// `fifo_ctrl` bit keeping the DMA FIFO, flushed while cleared.
+pub const FIFO_CTRL_DMA: u64 = 1 << 0;
+/// `fifo_ctrl` bit keeping the GPU FIFO, flushed while cleared.
+pub const FIFO_CTRL_GPU: u64 = 1 << 1;
+/// `fifo_ctrl` bit keeping the layer argument FIFO, flushed while cleared.
+pub const FIFO_CTRL_CFG: u64 = 1 << 2;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem::offset_of;
+
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, layer_argument_fifo), 0x00);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_status), 0x08);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_raw), 0x10);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_mask), 0x18);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_clear), 0x20);
+        assert_eq!(offset_of!(RegisterBlock, fifo_threshold), 0x28);
+        assert_eq!(offset_of!(RegisterBlock, fifo_data_out), 0x30);
+        assert_eq!(offset_of!(RegisterBlock, fifo_ctrl), 0x38);
+        assert_eq!(offset_of!(RegisterBlock, eight_bit_mode), 0x40);
+    }
+}
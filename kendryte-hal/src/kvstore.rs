@@ -0,0 +1,491 @@
+//! Key-value store for settings and calibration data in NOR flash.
+//!
+//! Values are appended to a log in one of two banks, so rewriting a setting
+//! programs fresh flash instead of erasing a sector each time. When the
+//! active bank is full, the live values are copied to the other bank, which
+//! becomes active once its header is written, and erases alternate between
+//! the two banks. Power loss at any point leaves either the old or the new
+//! bank valid: a bank without a valid header is ignored, and a record torn
+//! by power loss fails its CRC and is dropped with everything after it.
+//!
+//! ```ignore
+//! let layout = kvstore::Layout { bank_a: 0x7F_0000, bank_b: 0x7F_8000, bank_size: 0x8000 };
+//! let mut store = KvStore::open(flash, layout)?;
+//! store.set(KEY_GYRO_OFFSET, &offset.to_le_bytes())?;
+//! let mut buf = [0; 4];
+//! if let Some(len) = store.get(KEY_GYRO_OFFSET, &mut buf)? { /* ... */ }
+//! ```
+//!
+//! Works with any [`NorFlash`] whose write size divides 16, including
+//! [`SpiNor`](crate::flash::SpiNor). Bank layout, little endian:
+//!
+//! | Offset | Size | Field                                              |
+//! |--------|------|----------------------------------------------------|
+//! | 0      | 4    | magic, `b"KVS1"`                                   |
+//! | 4      | 4    | generation, incremented by every compaction        |
+//! | 8      | 4    | reserved, `0xFF`                                   |
+//! | 12     | 4    | CRC-32 of bytes 0..12                              |
+//! | 16     |      | records, each padded to a multiple of 16 bytes     |
+//!
+//! A record is a 16-bit key, a 16-bit length with bit 15 marking a removed
+//! key, the CRC-32 of those four bytes and the value, and the value. Keys
+//! are looked up by scanning the log, and compaction checks every record
+//! against the ones after it, so banks are meant to be a few sectors.
+
+use crate::crc::CRC32_IEEE;
+use embedded_storage::nor_flash::NorFlash;
+
+/// Magic bytes at the start of a bank header.
+pub const BANK_MAGIC: [u8; 4] = *b"KVS1";
+/// Longest value, in bytes.
+pub const MAX_VALUE_LEN: usize = 256;
+
+/// Records and the bank header are padded to this many bytes.
+const ALIGN: u32 = 16;
+/// Length of the bank header.
+const BANK_HEADER_LEN: u32 = 16;
+/// Length of a record header: key, length and CRC.
+const RECORD_HEADER_LEN: u32 = 8;
+/// Length bit marking a removed key.
+const REMOVED: u16 = 1 << 15;
+/// Largest padded record.
+const RECORD_BUF_LEN: usize = align_up(RECORD_HEADER_LEN + MAX_VALUE_LEN as u32) as usize;
+
+const fn align_up(len: u32) -> u32 {
+    len.div_ceil(ALIGN) * ALIGN
+}
+
+/// Placement of the two banks in flash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+    /// Flash offset of bank A.
+    pub bank_a: u32,
+    /// Flash offset of bank B.
+    pub bank_b: u32,
+    /// Size of each bank.
+    pub bank_size: u32,
+}
+
+/// Key-value store error types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KvError<E> {
+    /// Underlying flash error.
+    Flash(E),
+    /// Key `0xFFFF` is reserved for erased flash.
+    InvalidKey,
+    /// The value is longer than [`MAX_VALUE_LEN`].
+    ValueTooLarge,
+    /// The buffer passed to [`KvStore::get`] is shorter than the value.
+    BufferTooSmall,
+    /// The live values do not leave room for the new one.
+    StoreFull,
+}
+
+/// Header of a record in the active bank.
+#[derive(Clone, Copy, Debug)]
+struct Record {
+    /// Offset from the start of the bank.
+    at: u32,
+    key: u16,
+    len: u16,
+    removed: bool,
+}
+
+impl Record {
+    /// Bytes the record takes in the bank, with padding.
+    #[inline]
+    fn size(&self) -> u32 {
+        align_up(RECORD_HEADER_LEN + self.len as u32)
+    }
+}
+
+/// Key-value store on two flash banks.
+pub struct KvStore<F> {
+    flash: F,
+    layout: Layout,
+    /// Flash offset of the active bank.
+    active: u32,
+    generation: u32,
+    /// Offset of the first free byte in the active bank.
+    end: u32,
+    /// A torn record was found after the last valid one; it is left behind
+    /// by compacting before the next write.
+    torn: bool,
+}
+
+impl<F: NorFlash> KvStore<F> {
+    /// Opens the store, formatting bank A if neither bank holds one.
+    ///
+    /// Bank offsets and the bank size must be multiples of the flash erase
+    /// size.
+    pub fn open(flash: F, layout: Layout) -> Result<Self, KvError<F::Error>> {
+        assert!(
+            (ALIGN as usize).is_multiple_of(F::WRITE_SIZE),
+            "flash write size must divide 16"
+        );
+        let mut store = Self {
+            flash,
+            layout,
+            active: layout.bank_a,
+            generation: 0,
+            end: BANK_HEADER_LEN,
+            torn: false,
+        };
+        let a = store.bank_generation(layout.bank_a)?;
+        let b = store.bank_generation(layout.bank_b)?;
+        let (active, generation) = match (a, b) {
+            (Some(a), Some(b)) if b > a => (layout.bank_b, b),
+            (Some(a), _) => (layout.bank_a, a),
+            (None, Some(b)) => (layout.bank_b, b),
+            (None, None) => {
+                store.erase(layout.bank_a)?;
+                store.write_bank_header(layout.bank_a, 1)?;
+                (layout.bank_a, 1)
+            }
+        };
+        store.active = active;
+        store.generation = generation;
+        store.scan()?;
+        Ok(store)
+    }
+
+    /// Release the underlying flash.
+    #[inline]
+    pub fn free(self) -> F {
+        self.flash
+    }
+
+    /// Bytes of the active bank taken by the header and records, including
+    /// values that were overwritten or removed.
+    #[inline]
+    pub fn used(&self) -> u32 {
+        self.end
+    }
+
+    /// Number of compactions since the store was formatted, plus one.
+    #[inline]
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Reads the value of `key` into `buf` and returns its length, or
+    /// `None` if the key is not set.
+    pub fn get(&mut self, key: u16, buf: &mut [u8]) -> Result<Option<usize>, KvError<F::Error>> {
+        let Some(record) = self.latest(key)? else {
+            return Ok(None);
+        };
+        if record.removed {
+            return Ok(None);
+        }
+        let len = record.len as usize;
+        if buf.len() < len {
+            return Err(KvError::BufferTooSmall);
+        }
+        let address = self.active + record.at + RECORD_HEADER_LEN;
+        self.flash
+            .read(address, &mut buf[..len])
+            .map_err(KvError::Flash)?;
+        Ok(Some(len))
+    }
+
+    /// Sets `key` to `value`.
+    pub fn set(&mut self, key: u16, value: &[u8]) -> Result<(), KvError<F::Error>> {
+        if key == u16::MAX {
+            return Err(KvError::InvalidKey);
+        }
+        if value.len() > MAX_VALUE_LEN {
+            return Err(KvError::ValueTooLarge);
+        }
+        self.append(key, value, false)
+    }
+
+    /// Removes `key`; removing a key that is not set does nothing.
+    pub fn remove(&mut self, key: u16) -> Result<(), KvError<F::Error>> {
+        match self.latest(key)? {
+            Some(record) if !record.removed => self.append(key, &[], true),
+            _ => Ok(()),
+        }
+    }
+
+    /// Copies the live values to the other bank and makes it active.
+    ///
+    /// Called by [`set`](Self::set) when the active bank is full; calling
+    /// it earlier, e.g. at a convenient time after start-up, keeps the
+    /// erase out of a later write.
+    pub fn compact(&mut self) -> Result<(), KvError<F::Error>> {
+        let target = if self.active == self.layout.bank_a {
+            self.layout.bank_b
+        } else {
+            self.layout.bank_a
+        };
+        self.erase(target)?;
+        let mut buf = [0; RECORD_BUF_LEN];
+        let mut out = BANK_HEADER_LEN;
+        let mut at = BANK_HEADER_LEN;
+        while at < self.end {
+            let record = self.record(at)?;
+            at += record.size();
+            if record.removed || self.superseded(&record)? {
+                continue;
+            }
+            let size = record.size() as usize;
+            self.flash
+                .read(self.active + record.at, &mut buf[..size])
+                .map_err(KvError::Flash)?;
+            self.flash
+                .write(target + out, &buf[..size])
+                .map_err(KvError::Flash)?;
+            out += size as u32;
+        }
+        // The header goes last: until it is written, the old bank stays
+        // the valid one.
+        self.write_bank_header(target, self.generation.wrapping_add(1))?;
+        self.active = target;
+        self.generation = self.generation.wrapping_add(1);
+        self.end = out;
+        self.torn = false;
+        Ok(())
+    }
+
+    /// Appends a record, compacting first if it does not fit.
+    fn append(&mut self, key: u16, value: &[u8], removed: bool) -> Result<(), KvError<F::Error>> {
+        let len_field = value.len() as u16 | if removed { REMOVED } else { 0 };
+        let size = align_up(RECORD_HEADER_LEN + value.len() as u32);
+        if self.torn || self.end + size > self.layout.bank_size {
+            self.compact()?;
+            if self.end + size > self.layout.bank_size {
+                return Err(KvError::StoreFull);
+            }
+        }
+        let mut buf = [0xFF; RECORD_BUF_LEN];
+        buf[0..2].copy_from_slice(&key.to_le_bytes());
+        buf[2..4].copy_from_slice(&len_field.to_le_bytes());
+        buf[8..8 + value.len()].copy_from_slice(value);
+        let crc = record_crc(&buf[..4], value);
+        buf[4..8].copy_from_slice(&crc.to_le_bytes());
+        self.flash
+            .write(self.active + self.end, &buf[..size as usize])
+            .map_err(KvError::Flash)?;
+        self.end += size;
+        Ok(())
+    }
+
+    /// Last record of `key` in the active bank.
+    fn latest(&mut self, key: u16) -> Result<Option<Record>, KvError<F::Error>> {
+        let mut latest = None;
+        let mut at = BANK_HEADER_LEN;
+        while at < self.end {
+            let record = self.record(at)?;
+            if record.key == key {
+                latest = Some(record);
+            }
+            at += record.size();
+        }
+        Ok(latest)
+    }
+
+    /// Returns true if a later record has the key of `record`.
+    fn superseded(&mut self, record: &Record) -> Result<bool, KvError<F::Error>> {
+        let mut at = record.at + record.size();
+        while at < self.end {
+            let later = self.record(at)?;
+            if later.key == record.key {
+                return Ok(true);
+            }
+            at += later.size();
+        }
+        Ok(false)
+    }
+
+    /// Reads the record header at `at` in the active bank.
+    fn record(&mut self, at: u32) -> Result<Record, KvError<F::Error>> {
+        let mut header = [0; RECORD_HEADER_LEN as usize];
+        self.flash
+            .read(self.active + at, &mut header)
+            .map_err(KvError::Flash)?;
+        let len = u16::from_le_bytes([header[2], header[3]]);
+        Ok(Record {
+            at,
+            key: u16::from_le_bytes([header[0], header[1]]),
+            len: len & !REMOVED,
+            removed: len & REMOVED != 0,
+        })
+    }
+
+    /// Finds the end of the log in the active bank, checking every record.
+    fn scan(&mut self) -> Result<(), KvError<F::Error>> {
+        let mut buf = [0; RECORD_BUF_LEN];
+        let mut at = BANK_HEADER_LEN;
+        self.torn = false;
+        while at + RECORD_HEADER_LEN <= self.layout.bank_size {
+            let header = &mut buf[..RECORD_HEADER_LEN as usize];
+            self.flash
+                .read(self.active + at, header)
+                .map_err(KvError::Flash)?;
+            if header.iter().all(|&b| b == 0xFF) {
+                break;
+            }
+            let len = (u16::from_le_bytes([header[2], header[3]]) & !REMOVED) as usize;
+            let size = align_up(RECORD_HEADER_LEN + len as u32);
+            if len > MAX_VALUE_LEN || at + size > self.layout.bank_size {
+                self.torn = true;
+                break;
+            }
+            let (header, value) = buf.split_at_mut(RECORD_HEADER_LEN as usize);
+            let value = &mut value[..len];
+            self.flash
+                .read(self.active + at + RECORD_HEADER_LEN, value)
+                .map_err(KvError::Flash)?;
+            let crc = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+            if record_crc(&header[..4], value) != crc {
+                self.torn = true;
+                break;
+            }
+            at += size;
+        }
+        self.end = at;
+        Ok(())
+    }
+
+    /// Generation of the bank at `offset`, or `None` if its header is not
+    /// valid.
+    fn bank_generation(&mut self, offset: u32) -> Result<Option<u32>, KvError<F::Error>> {
+        let mut header = [0; BANK_HEADER_LEN as usize];
+        self.flash
+            .read(offset, &mut header)
+            .map_err(KvError::Flash)?;
+        let word = |at: usize| u32::from_le_bytes(header[at..at + 4].try_into().unwrap());
+        if header[..4] != BANK_MAGIC || word(12) != CRC32_IEEE.checksum(&header[..12]) {
+            return Ok(None);
+        }
+        Ok(Some(word(4)))
+    }
+
+    fn write_bank_header(&mut self, offset: u32, generation: u32) -> Result<(), KvError<F::Error>> {
+        let mut header = [0xFF; BANK_HEADER_LEN as usize];
+        header[..4].copy_from_slice(&BANK_MAGIC);
+        header[4..8].copy_from_slice(&generation.to_le_bytes());
+        let crc = CRC32_IEEE.checksum(&header[..12]);
+        header[12..].copy_from_slice(&crc.to_le_bytes());
+        self.flash.write(offset, &header).map_err(KvError::Flash)
+    }
+
+    fn erase(&mut self, offset: u32) -> Result<(), KvError<F::Error>> {
+        self.flash
+            .erase(offset, offset + self.layout.bank_size)
+            .map_err(KvError::Flash)
+    }
+}
+
+/// CRC over the key and length field of a record and its value.
+fn record_crc(key_and_len: &[u8], value: &[u8]) -> u32 {
+    let mut digest = CRC32_IEEE.digest();
+    digest.update(key_and_len);
+    digest.update(value);
+    digest.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::mock::{FLASH_SECTOR as SECTOR, RamFlash};
+    use embedded_storage::nor_flash::NorFlashErrorKind;
+    use std::vec::Vec;
+
+    const LAYOUT: Layout = Layout {
+        bank_a: 0,
+        bank_b: 2 * SECTOR as u32,
+        bank_size: 2 * SECTOR as u32,
+    };
+
+    fn get(store: &mut KvStore<RamFlash>, key: u16) -> Option<Vec<u8>> {
+        let mut buf = [0; MAX_VALUE_LEN];
+        let len = store.get(key, &mut buf).unwrap()?;
+        Some(buf[..len].to_vec())
+    }
+
+    #[test]
+    fn set_get_remove() {
+        let mut store = KvStore::open(RamFlash::new(4, 0xFF), LAYOUT).unwrap();
+        assert_eq!(get(&mut store, 1), None);
+        store.set(1, b"one").unwrap();
+        store.set(2, b"two").unwrap();
+        store.set(1, b"uno").unwrap();
+        assert_eq!(get(&mut store, 1).as_deref(), Some(&b"uno"[..]));
+        store.remove(2).unwrap();
+        assert_eq!(get(&mut store, 2), None);
+        assert_eq!(store.set(u16::MAX, b""), Err(KvError::InvalidKey));
+        assert_eq!(
+            store.set(3, &[0; MAX_VALUE_LEN + 1]),
+            Err(KvError::ValueTooLarge)
+        );
+        assert_eq!(store.get(1, &mut [0; 2]), Err(KvError::BufferTooSmall));
+
+        let mut store = KvStore::open(store.free(), LAYOUT).unwrap();
+        assert_eq!(get(&mut store, 1).as_deref(), Some(&b"uno"[..]));
+        assert_eq!(get(&mut store, 2), None);
+    }
+
+    #[test]
+    fn compaction_alternates_banks() {
+        let mut store = KvStore::open(RamFlash::new(4, 0xFF), LAYOUT).unwrap();
+        for i in 0..100_u32 {
+            store.set(7, &i.to_le_bytes()).unwrap();
+            store.set(8, b"constant").unwrap();
+        }
+        assert!(store.generation() > 3);
+        assert_eq!(
+            get(&mut store, 7).as_deref(),
+            Some(&99_u32.to_le_bytes()[..])
+        );
+        assert_eq!(get(&mut store, 8).as_deref(), Some(&b"constant"[..]));
+
+        let mut store = KvStore::open(store.free(), LAYOUT).unwrap();
+        assert_eq!(
+            get(&mut store, 7).as_deref(),
+            Some(&99_u32.to_le_bytes()[..])
+        );
+        // Only the latest record of each key survives compaction.
+        store.compact().unwrap();
+        assert_eq!(store.used(), BANK_HEADER_LEN + 2 * 16);
+        store.set(9, &[0; MAX_VALUE_LEN]).unwrap();
+        assert_eq!(store.set(10, &[0; MAX_VALUE_LEN]), Err(KvError::StoreFull));
+    }
+
+    #[test]
+    fn power_loss() {
+        let mut store = KvStore::open(RamFlash::new(4, 0xFF), LAYOUT).unwrap();
+        store.set(1, b"kept").unwrap();
+
+        // A record torn halfway is dropped, and the next write moves on to
+        // the other bank.
+        let mut flash = store.free();
+        flash.budget = Some(10);
+        let mut store = KvStore::open(flash, LAYOUT).unwrap();
+        assert_eq!(
+            store.set(1, b"lost value"),
+            Err(KvError::Flash(NorFlashErrorKind::Other))
+        );
+        let mut flash = store.free();
+        flash.budget = None;
+        let mut store = KvStore::open(flash, LAYOUT).unwrap();
+        assert_eq!(get(&mut store, 1).as_deref(), Some(&b"kept"[..]));
+        let generation = store.generation();
+        store.set(2, b"new").unwrap();
+        assert_eq!(store.generation(), generation + 1);
+
+        // A compaction interrupted before the header leaves the old bank.
+        let mut flash = store.free();
+        flash.budget = Some(40);
+        let mut store = KvStore::open(flash, LAYOUT).unwrap();
+        assert!(store.compact().is_err());
+        let mut flash = store.free();
+        flash.budget = None;
+        let mut store = KvStore::open(flash, LAYOUT).unwrap();
+        assert_eq!(store.generation(), generation + 1);
+        assert_eq!(get(&mut store, 1).as_deref(), Some(&b"kept"[..]));
+        assert_eq!(get(&mut store, 2).as_deref(), Some(&b"new"[..]));
+    }
+}
@@ -1,12 +1,42 @@
 //! SoC peripheral support for Cannan Kendryte chips.
 #![no_std]
 #![allow(unused)]
+pub mod blackbox;
 pub mod clocks;
+pub mod crc;
+pub mod delay;
+pub mod dma;
+pub mod error;
+pub mod firmware;
+pub mod flash;
 pub mod gpio;
 pub mod i2c;
+pub mod ident;
 pub mod instance;
 pub mod iomux;
+#[cfg(feature = "k210")]
+pub mod kpu;
+pub mod kvstore;
 pub mod lsadc;
+#[cfg(test)]
+mod mock;
+#[cfg(feature = "modbus")]
+pub mod modbus;
+pub mod onewire;
+pub mod ota;
 pub mod pwm;
+pub mod sccb;
+pub mod soc;
 pub mod spi;
+pub mod sync;
+pub mod sysctl;
+pub mod time;
+pub mod timer;
+pub mod trace;
 pub mod uart;
+pub mod util;
+pub mod wdt;
+pub mod ws2812;
+pub mod xmodem;
+
+pub use error::{Error, ErrorKind};
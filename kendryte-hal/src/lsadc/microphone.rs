@@ -0,0 +1,269 @@
+//! Microphone capture into fixed-size PCM frames.
+//!
+//! The K230 boards have no PDM or I2S driver in this crate yet, but an
+//! analog microphone module on an LSADC input is enough for level metering
+//! and voice activity experiments. [`Microphone`] samples one channel with
+//! the same timer triggered scheme as [`Sampler`], converts the 12-bit
+//! results to signed 16-bit PCM, and hands out whole frames of `N` samples
+//! through a [`FrameQueue`]:
+//!
+//! ```ignore
+//! static FRAMES: FrameQueue<256, 4> = FrameQueue::new();
+//! static MIC: Mutex<Option<(Microphone<'static, 256>, Producer<'static, PcmFrame<256>, 4>)>> =
+//!     Mutex::new(None);
+//!
+//! // In main:
+//! let (producer, mut frames) = FRAMES.split().unwrap();
+//! MIC.lock(|m| *m = Some((mic, producer)));
+//!
+//! // In the timer interrupt handler, at the sample rate:
+//! MIC.lock(|m| m.as_mut().map(|(mic, producer)| mic.trigger(producer)));
+//!
+//! // In the main loop:
+//! if let Some(frame) = frames.pop() {
+//!     process(&frame.samples, frame.timestamp);
+//! }
+//! ```
+//!
+//! The sample rate is the trigger rate, so it is set by the timer; the
+//! converter keeps up with a few tens of kHz. The microphone output is
+//! expected to be biased to mid-scale, which becomes zero in the PCM data.
+//!
+//! Samples are moved by the CPU, one per trigger, not by DMA. The LSADC
+//! DMA outputs only run in its continuous mode, where the converter paces
+//! itself instead of following a timer, and this crate has no driver for
+//! the peripheral DMA controller they feed. At audio rates a trigger costs
+//! a few register accesses, well within a sample period.
+
+use crate::instance::Instance;
+use crate::lsadc::sampler::{LsadcError, Sample, Sampler};
+use crate::lsadc::{ChannelSelect, MmioRegisterBlock};
+use crate::sync::{Producer, Queue};
+
+/// One frame of PCM samples.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PcmFrame<const N: usize> {
+    /// Signed 16-bit samples, oldest first.
+    pub samples: [i16; N],
+    /// Machine timer value when the conversion of the first sample started.
+    ///
+    /// If the first conversions of the frame were missed, this is the start
+    /// of the first one that was not, or 0 if every conversion was missed.
+    pub timestamp: u64,
+    /// Samples in this frame repeated from the previous one, because their
+    /// conversion had not finished by the next trigger.
+    pub missed: u32,
+}
+
+impl<const N: usize> PcmFrame<N> {
+    const EMPTY: Self = Self {
+        samples: [0; N],
+        timestamp: 0,
+        missed: 0,
+    };
+}
+
+/// Converts a 12-bit LSADC result to signed 16-bit PCM around mid-scale.
+#[inline]
+pub fn to_pcm(value: u16) -> i16 {
+    ((value as i16) - 2048) << 4
+}
+
+/// Queue of `F` frames of `N` samples, split into the handles fed by
+/// [`Microphone::trigger`] and drained by the main loop.
+pub type FrameQueue<const N: usize, const F: usize> = Queue<PcmFrame<N>, F>;
+
+/// Microphone on one LSADC channel, assembling frames of `N` samples.
+pub struct Microphone<'i, const N: usize> {
+    sampler: Sampler<'i>,
+    frame: PcmFrame<N>,
+    /// Samples already in `frame`.
+    len: usize,
+    /// Whether `frame.timestamp` has been set from a converted sample.
+    stamped: bool,
+    /// Last sample, repeated for a missed conversion.
+    last: i16,
+}
+
+impl<'i, const N: usize> Microphone<'i, N> {
+    /// Power up and calibrate the LSADC, then sample `channel`.
+    ///
+    /// Nothing is captured until the first [`trigger`](Self::trigger).
+    pub fn new(
+        instance: impl Instance<'i, R = MmioRegisterBlock<'static>>,
+        channel: ChannelSelect,
+    ) -> Result<Self, LsadcError> {
+        Self::with_sampler(Sampler::new(instance, &[channel])?)
+    }
+
+    fn with_sampler(sampler: Sampler<'i>) -> Result<Self, LsadcError> {
+        assert!(N > 0, "frames need at least one sample");
+        Ok(Self {
+            sampler,
+            frame: PcmFrame::EMPTY,
+            len: 0,
+            stamped: false,
+            last: 0,
+        })
+    }
+
+    /// Collect the conversion in flight and start the next, pushing the
+    /// frame to `frames` once it is full.
+    ///
+    /// Call this from the periodic interrupt handler.
+    #[cfg_attr(
+        feature = "ramfunc",
+        unsafe(link_section = ".ramfunc.lsadc_microphone")
+    )]
+    pub fn trigger<const F: usize>(&mut self, frames: &mut Producer<'_, PcmFrame<N>, F>) {
+        if let Some(result) = self.sampler.advance() {
+            self.record(result.ok(), frames);
+        }
+    }
+
+    /// Append a converted sample to the frame, or repeat the last one for a
+    /// missed conversion.
+    #[inline(always)]
+    fn record<const F: usize>(
+        &mut self,
+        sample: Option<Sample>,
+        frames: &mut Producer<'_, PcmFrame<N>, F>,
+    ) {
+        let value = match sample {
+            Some(sample) => {
+                if !self.stamped {
+                    self.frame.timestamp = sample.timestamp;
+                    self.stamped = true;
+                }
+                to_pcm(sample.value)
+            }
+            None => {
+                self.frame.missed += 1;
+                self.last
+            }
+        };
+        self.frame.samples[self.len] = value;
+        self.last = value;
+        self.len += 1;
+        if self.len == N {
+            frames.push(self.frame);
+            self.start_frame();
+        }
+    }
+
+    fn start_frame(&mut self) {
+        self.frame.timestamp = 0;
+        self.frame.missed = 0;
+        self.stamped = false;
+        self.len = 0;
+    }
+
+    /// Discard the partial frame and the conversion in flight.
+    pub fn reset(&mut self) {
+        self.sampler.reset();
+        self.start_frame();
+    }
+
+    /// Power down the LSADC and return its register block.
+    pub fn free(self) -> MmioRegisterBlock<'static> {
+        self.sampler.free()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsadc::{Cfg, Data, RegisterBlock, Trim};
+    use crate::mock;
+
+    #[test]
+    fn pcm_conversion() {
+        assert_eq!(to_pcm(2048), 0);
+        assert_eq!(to_pcm(0), i16::MIN);
+        assert_eq!(to_pcm(4095), 2047 << 4);
+    }
+
+    #[test]
+    fn frames_from_conversions() {
+        let block = mock::block::<RegisterBlock>();
+        let mut regs = unsafe { RegisterBlock::new_mmio(block) };
+        unsafe { regs.write_trim(Trim::new_with_raw_value(1 << 24)) };
+        let sampler = Sampler::configure(
+            unsafe { RegisterBlock::new_mmio(block) },
+            &[ChannelSelect::AdcIn2],
+        )
+        .unwrap();
+        let mut mic = Microphone::<2>::with_sampler(sampler).unwrap();
+        let queue = FrameQueue::<2, 2>::new();
+        let (mut producer, mut frames) = queue.split().unwrap();
+
+        // The first trigger only starts a conversion.
+        mic.trigger(&mut producer);
+        unsafe { regs.write_data(2, Data::new_with_raw_value(2048 + 1)) }.unwrap();
+        unsafe { regs.write_cfg(Cfg::new_with_raw_value(1 << 16)) };
+        mic.trigger(&mut producer);
+        assert_eq!(frames.pop(), None);
+
+        // The second conversion is missed and repeats the first sample.
+        unsafe { regs.write_cfg(Cfg::new_with_raw_value(0)) };
+        mic.trigger(&mut producer);
+        assert_eq!(
+            frames.pop(),
+            Some(PcmFrame {
+                samples: [16, 16],
+                timestamp: 0,
+                missed: 1,
+            })
+        );
+
+        // The queue keeps one slot free, so a second unread frame is dropped.
+        unsafe { regs.write_cfg(Cfg::new_with_raw_value(1 << 16)) };
+        for _ in 0..4 {
+            mic.trigger(&mut producer);
+        }
+        assert!(frames.pop().is_some());
+        assert_eq!(frames.pop(), None);
+        assert_eq!(frames.take_dropped(), 1);
+    }
+
+    #[test]
+    fn frame_stamped_by_first_conversion() {
+        let block = mock::block::<RegisterBlock>();
+        let mut regs = unsafe { RegisterBlock::new_mmio(block) };
+        unsafe { regs.write_trim(Trim::new_with_raw_value(1 << 24)) };
+        let sampler = Sampler::configure(
+            unsafe { RegisterBlock::new_mmio(block) },
+            &[ChannelSelect::AdcIn2],
+        )
+        .unwrap();
+        let mut mic = Microphone::<3>::with_sampler(sampler).unwrap();
+        let queue = FrameQueue::<3, 3>::new();
+        let (mut producer, mut frames) = queue.split().unwrap();
+        let sample = |timestamp| {
+            Some(Sample {
+                channel: 2,
+                value: 2048,
+                timestamp,
+            })
+        };
+
+        mic.record(None, &mut producer);
+        mic.record(sample(200), &mut producer);
+        mic.record(sample(300), &mut producer);
+        let frame = frames.pop().unwrap();
+        assert_eq!((frame.timestamp, frame.missed), (200, 1));
+
+        for _ in 0..3 {
+            mic.record(None, &mut producer);
+        }
+        let frame = frames.pop().unwrap();
+        assert_eq!((frame.timestamp, frame.missed), (0, 3));
+
+        mic.record(sample(700), &mut producer);
+        mic.reset();
+        mic.record(sample(900), &mut producer);
+        mic.record(None, &mut producer);
+        mic.record(None, &mut producer);
+        assert_eq!(frames.pop().map(|f| f.timestamp), Some(900));
+    }
+}
@@ -1,2 +1,11 @@
+crate::trace::traced_registers!($ "lsadc", crate::lsadc::RegisterBlock);
+
+pub mod microphone;
 mod register;
+pub mod sampler;
+
+pub use microphone::{FrameQueue, Microphone, PcmFrame};
 pub use register::*;
+pub use sampler::{LsadcError, Sample, SampleBuffer, Sampler};
+
+crate::trace::impl_raw!(Trim, Cfg, Mode, Thsd, DmaIntr, Data, DataDma);
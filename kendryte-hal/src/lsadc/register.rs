@@ -18,10 +18,13 @@ pub struct RegisterBlock {
     /// LSADC threshold interrupt control register.
     pub thsd: Thsd,
     /// LSADC's DMA error interrupt register.
+    #[mmio(PureRead)]
     pub dma_intr: DmaIntr,
     /// Input channel N Digital signal output.
+    #[mmio(PureRead)]
     pub data: [Data; 6],
     /// Continuous sampling channel N digital signal output.
+    #[mmio(PureRead)]
     pub data_dma: [DataDma; 3],
 }
 
@@ -29,9 +32,13 @@ pub struct RegisterBlock {
 #[bitenum(u2, exhaustive = true)]
 #[derive(Debug, PartialEq, Eq)]
 pub enum ReferenceVoltage {
+    /// 0.85 V.
     V085 = 0b00,
+    /// 0.90 V.
     V090 = 0b01,
+    /// 0.95 V.
     V095 = 0b10,
+    /// 1.00 V.
     V100 = 0b11,
 }
 
@@ -39,7 +46,9 @@ pub enum ReferenceVoltage {
 #[bitenum(u1, exhaustive = true)]
 #[derive(Debug, PartialEq, Eq)]
 pub enum ReferenceSelect {
+    /// External reference on the VREF pin.
     External = 0b0,
+    /// Internal bandgap reference, see [`ReferenceVoltage`].
     Internal = 0b1,
 }
 
@@ -47,11 +56,17 @@ pub enum ReferenceSelect {
 #[bitenum(u3, exhaustive = false)]
 #[derive(Debug, PartialEq, Eq)]
 pub enum ChannelSelect {
+    /// Input channel 0.
     AdcIn0 = 0b000,
+    /// Input channel 1.
     AdcIn1 = 0b001,
+    /// Input channel 2.
     AdcIn2 = 0b010,
+    /// Input channel 3.
     AdcIn3 = 0b011,
+    /// Input channel 4.
     AdcIn4 = 0b100,
+    /// Input channel 5.
     AdcIn5 = 0b101,
 }
 
@@ -59,9 +74,13 @@ pub enum ChannelSelect {
 #[bitenum(u2, exhaustive = true)]
 #[derive(Debug, PartialEq, Eq)]
 pub enum OutputMode {
+    /// One conversion per start, result in the channel's data register.
     SingleSampleRegister = 0b00,
+    /// Continuous sampling of the DMA1 channel.
     SingleChannelContinuousDma = 0b01,
+    /// Continuous sampling of the DMA1 and DMA2 channels.
     DualChannelContinuousDma = 0b10,
+    /// Continuous sampling of the DMA1, DMA2 and DMA3 channels.
     TripleChannelContinuousDma = 0b11,
 }
 
@@ -69,9 +88,13 @@ pub enum OutputMode {
 #[bitenum(u2, exhaustive = true)]
 #[derive(Debug, PartialEq, Eq)]
 pub enum ThresholdMode {
+    /// Interrupt when the sample is above the upper limit.
     HighPass = 0b00,
+    /// Interrupt when the sample is between the limits.
     BandPass = 0b01,
+    /// Interrupt when the sample is outside the limits.
     BandStop = 0b10,
+    /// Interrupt when the sample is below the lower limit.
     LowPass = 0b11,
 }
 
@@ -237,4 +260,37 @@ mod tests {
         assert_eq!(offset_of!(RegisterBlock, data), 0x14);
         assert_eq!(offset_of!(RegisterBlock, data_dma), 0x2C);
     }
+
+    #[test]
+    fn struct_cfg_functions() {
+        let cfg = Cfg::new_with_raw_value(0)
+            .with_input_channel(ChannelSelect::AdcIn5)
+            .with_start_of_conversion(true);
+        assert_eq!(cfg.raw_value(), 0x0000_0015);
+
+        let cfg = Cfg::new_with_raw_value(0x0001_1100);
+        assert!(cfg.sar_busy());
+        assert!(cfg.end_of_conversion());
+        assert!(cfg.data_output_valid());
+    }
+
+    #[test]
+    fn struct_mode_functions() {
+        let mode = Mode::new_with_raw_value(0)
+            .with_output_mode(OutputMode::TripleChannelContinuousDma)
+            .with_dma1_enable(true)
+            .with_dma1_channel(ChannelSelect::AdcIn1)
+            .with_dma2_channel(ChannelSelect::AdcIn2)
+            .with_dma3_channel(ChannelSelect::AdcIn3);
+        assert_eq!(mode.raw_value(), 0x0302_0303);
+    }
+
+    #[test]
+    fn struct_thsd_functions() {
+        let thsd = Thsd::new_with_raw_value(0)
+            .with_threshold_mode(ThresholdMode::BandStop)
+            .with_threshold_low(u12::new(0x123))
+            .with_threshold_high(u12::new(0xABC));
+        assert_eq!(thsd.raw_value(), 0x0ABC_1232);
+    }
 }
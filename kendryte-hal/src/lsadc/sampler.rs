@@ -0,0 +1,273 @@
+//! Timer triggered, timestamped ADC sampling.
+//!
+//! The LSADC has no hardware trigger input, so uniform sampling is driven by
+//! a periodic interrupt, usually a [`PwmTimer`](crate::pwm::PwmTimer). Each
+//! trigger collects the conversion started by the previous one and starts
+//! the next, so the handler never waits for the converter. Samples carry the
+//! machine timer value at which their conversion was started and are queued
+//! in a [`SampleBuffer`] for the control loop to drain.
+//!
+//! # Example
+//! ```ignore
+//! static TIMER: PwmTimerState = PwmTimerState::new();
+//! static SAMPLES: SampleBuffer<64> = SampleBuffer::new();
+//! static SAMPLER: Mutex<Option<(Sampler<'static>, Producer<'static, Sample, 64>)>> =
+//!     Mutex::new(None);
+//!
+//! // In main:
+//! let (producer, mut samples) = SAMPLES.split().unwrap();
+//! SAMPLER.lock(|s| *s = Some((sampler, producer)));
+//!
+//! // In the PWM0 interrupt handler:
+//! if TIMER.on_interrupt(pwm0_regs) {
+//!     SAMPLER.lock(|s| s.as_mut().map(|(s, producer)| s.trigger(producer)));
+//! }
+//!
+//! // In the control loop:
+//! while let Some(sample) = samples.pop() {
+//!     update(sample.channel, sample.value, sample.timestamp);
+//! }
+//! ```
+//!
+//! With several channels the converter visits them in turn, one per
+//! trigger, so every channel is sampled at the trigger rate divided by the
+//! number of channels.
+
+use crate::instance::Instance;
+use crate::lsadc::{Cfg, ChannelSelect, MmioRegisterBlock, OutputMode};
+use crate::sync::{Producer, Queue};
+use crate::time::Timeout;
+use arbitrary_int::u3;
+
+/// Longest wait for the offset calibration, in milliseconds.
+const CALIBRATION_TIMEOUT_MS: u32 = 10;
+
+/// Error type for LSADC operations.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum LsadcError {
+    /// The offset calibration did not finish.
+    CalibrationTimeout,
+}
+
+/// One conversion result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    /// Input channel, 0 to 5.
+    pub channel: u8,
+    /// 12-bit conversion result.
+    pub value: u16,
+    /// Machine timer value when the conversion was started.
+    pub timestamp: u64,
+}
+
+/// Queue of [`Sample`]s, split into the handles fed by [`Sampler::trigger`]
+/// and drained by the control loop.
+///
+/// Its dropped count also includes conversions that had not finished by
+/// the next trigger.
+pub type SampleBuffer<const N: usize> = Queue<Sample, N>;
+
+/// LSADC converting one channel per external trigger.
+pub struct Sampler<'i> {
+    inner: MmioRegisterBlock<'static>,
+    /// Bit mask of the channels to convert.
+    channels: u8,
+    /// Channel converted by the next trigger.
+    next: u8,
+    /// Channel and start time of the conversion in flight.
+    pending: Option<(u8, u64)>,
+    _marker: core::marker::PhantomData<&'i ()>,
+}
+
+impl<'i> Sampler<'i> {
+    /// Power up and calibrate the LSADC, then sample `channels` in turn.
+    ///
+    /// Nothing is converted until the first [`trigger`](Self::trigger).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channels` is empty.
+    pub fn new(
+        instance: impl Instance<'i, R = MmioRegisterBlock<'static>>,
+        channels: &[ChannelSelect],
+    ) -> Result<Self, LsadcError> {
+        Self::configure(instance.inner(), channels)
+    }
+
+    pub(super) fn configure(
+        mut inner: MmioRegisterBlock<'static>,
+        channels: &[ChannelSelect],
+    ) -> Result<Self, LsadcError> {
+        assert!(!channels.is_empty(), "no channel to sample");
+        unsafe {
+            modify_reg!(inner, trim, modify_trim, |r| r
+                .with_analog_power_enable(true));
+            modify_reg!(inner, trim, modify_trim, |r| r
+                .with_offset_calibration_enable(true));
+        }
+        Timeout::from_millis(CALIBRATION_TIMEOUT_MS)
+            .wait(LsadcError::CalibrationTimeout, || {
+                read_reg!(inner, trim, read_trim).offset_calibration_done()
+            })?;
+        unsafe {
+            modify_reg!(inner, trim, modify_trim, |r| r
+                .with_offset_calibration_enable(false));
+            modify_reg!(inner, mode, modify_mode, |r| {
+                r.with_output_mode(OutputMode::SingleSampleRegister)
+                    .with_dma1_enable(false)
+            });
+        }
+        let channels = channels.iter().fold(0, |mask, &channel| {
+            mask | (1 << channel.raw_value().value())
+        });
+        Ok(Self {
+            inner,
+            channels,
+            next: channels.trailing_zeros() as u8,
+            pending: None,
+            _marker: core::marker::PhantomData,
+        })
+    }
+
+    /// Collect the conversion in flight into `samples` and start the next.
+    ///
+    /// Call this from the periodic interrupt handler. A conversion that has
+    /// not finished by the next trigger is counted as dropped, so the
+    /// trigger period must be longer than a conversion.
+    #[cfg_attr(feature = "ramfunc", unsafe(link_section = ".ramfunc.lsadc_trigger"))]
+    pub fn trigger<const N: usize>(&mut self, samples: &mut Producer<'_, Sample, N>) {
+        match self.advance() {
+            Some(Ok(sample)) => {
+                samples.push(sample);
+            }
+            Some(Err(())) => samples.mark_dropped(),
+            None => {}
+        }
+    }
+
+    /// Collect the conversion in flight and start the next.
+    ///
+    /// Returns `None` if no conversion was in flight, and an error if it
+    /// had not finished.
+    #[inline(always)]
+    pub(super) fn advance(&mut self) -> Option<Result<Sample, ()>> {
+        let timestamp = crate::time::now();
+        let collected = self.pending.take().map(|(channel, started)| {
+            self.collect(channel)
+                .map(|value| Sample {
+                    channel,
+                    value,
+                    timestamp: started,
+                })
+                .ok_or(())
+        });
+        self.start(self.next);
+        self.pending = Some((self.next, timestamp));
+        self.next = self.following(self.next);
+        collected
+    }
+
+    /// Stop sampling, discarding the conversion in flight.
+    ///
+    /// The next [`trigger`](Self::trigger) starts again from the first
+    /// channel.
+    pub fn reset(&mut self) {
+        self.pending = None;
+        self.next = self.channels.trailing_zeros() as u8;
+    }
+
+    /// Power down the LSADC and return its register block.
+    pub fn free(mut self) -> MmioRegisterBlock<'static> {
+        unsafe {
+            modify_reg!(self.inner, trim, modify_trim, |r| r
+                .with_analog_power_enable(false))
+        };
+        self.inner
+    }
+
+    /// Result of the conversion of `channel`, if it finished.
+    fn collect(&mut self, channel: u8) -> Option<u16> {
+        if !read_reg!(self.inner, cfg, read_cfg).data_output_valid() {
+            return None;
+        }
+        let data = read_reg!(self.inner, data[channel as usize], read_data).ok()?;
+        Some(data.channel_data().value())
+    }
+
+    fn start(&mut self, channel: u8) {
+        let Ok(channel) = ChannelSelect::new_with_raw_value(u3::new(channel)) else {
+            unreachable!("channel mask built from valid channels")
+        };
+        unsafe {
+            write_reg!(
+                self.inner,
+                cfg,
+                write_cfg,
+                Cfg::new_with_raw_value(0)
+                    .with_input_channel(channel)
+                    .with_start_of_conversion(true)
+            )
+        };
+    }
+
+    /// Next channel in the mask after `channel`, wrapping around.
+    fn following(&self, channel: u8) -> u8 {
+        let above = self.channels & !((2 << channel) - 1);
+        if above != 0 {
+            above.trailing_zeros() as u8
+        } else {
+            self.channels.trailing_zeros() as u8
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsadc::{Data, RegisterBlock, Trim};
+    use crate::mock;
+
+    fn sampler(channels: &[ChannelSelect]) -> (Sampler<'static>, MmioRegisterBlock<'static>) {
+        let block = mock::block::<RegisterBlock>();
+        let mut regs = unsafe { RegisterBlock::new_mmio(block) };
+        // The mock has no calibration engine, so report it done up front.
+        unsafe { regs.write_trim(Trim::new_with_raw_value(1 << 24)) };
+        let sampler = Sampler::configure(unsafe { RegisterBlock::new_mmio(block) }, channels);
+        (sampler.unwrap(), regs)
+    }
+
+    #[test]
+    fn channels_in_turn() {
+        let (mut sampler, mut regs) = sampler(&[ChannelSelect::AdcIn1, ChannelSelect::AdcIn4]);
+        let buffer = SampleBuffer::<8>::new();
+        let (mut producer, mut samples) = buffer.split().unwrap();
+        assert!(regs.read_trim().analog_power_enable());
+        assert!(!regs.read_trim().offset_calibration_enable());
+
+        sampler.trigger(&mut producer);
+        assert_eq!(regs.read_cfg().input_channel(), Ok(ChannelSelect::AdcIn1));
+        assert!(samples.is_empty());
+
+        // Conversion finished: result of channel 1 is valid.
+        unsafe { regs.write_cfg(Cfg::new_with_raw_value(1 << 16)) };
+        unsafe { regs.write_data(1, Data::new_with_raw_value(0x123)) }.unwrap();
+        sampler.trigger(&mut producer);
+        assert_eq!(regs.read_cfg().input_channel(), Ok(ChannelSelect::AdcIn4));
+        assert_eq!(
+            samples.pop(),
+            Some(Sample {
+                channel: 1,
+                value: 0x123,
+                timestamp: 0
+            })
+        );
+
+        // Channel 4 never finished.
+        unsafe { regs.write_cfg(Cfg::new_with_raw_value(0)) };
+        sampler.trigger(&mut producer);
+        assert_eq!(regs.read_cfg().input_channel(), Ok(ChannelSelect::AdcIn1));
+        assert!(samples.is_empty());
+        assert_eq!(samples.take_dropped(), 1);
+    }
+}
@@ -0,0 +1,118 @@
+//! In-memory register blocks for host tests.
+//!
+//! A mock block is a zeroed register block in host memory that drivers
+//! program through the same accessors they use on hardware. Accesses made
+//! through [`crate::trace`] are also appended to a per-thread log, so tests
+//! can check the order registers are programmed in.
+//!
+//! The mock has no hardware behaviour: status bits a driver waits for must
+//! be set by the test, and write-only or read-to-clear registers read back
+//! what was last stored.
+//!
+//! [`RamFlash`] is the matching stand-in for NOR flash.
+
+extern crate std;
+
+use crate::trace::{Access, AccessKind};
+use embedded_storage::nor_flash::{
+    ErrorType, MultiwriteNorFlash, NorFlash, NorFlashErrorKind, ReadNorFlash,
+};
+use std::boxed::Box;
+use std::cell::RefCell;
+use std::vec::Vec;
+
+std::thread_local! {
+    static LOG: RefCell<Vec<Access>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Allocate a zeroed register block.
+///
+/// The block is leaked so it can back `'static` driver handles.
+pub(crate) fn block<T>() -> *mut T {
+    // SAFETY: register blocks only hold integer registers, for which all
+    // zeroes is a valid value.
+    Box::leak(unsafe { Box::<T>::new_zeroed().assume_init() })
+}
+
+/// Append an access to the log of the current thread.
+pub(crate) fn record(access: Access) {
+    LOG.with(|log| log.borrow_mut().push(access));
+}
+
+/// Run `f` and return its result with the register accesses it made.
+pub(crate) fn capture<R>(f: impl FnOnce() -> R) -> (R, Vec<Access>) {
+    LOG.with(|log| log.borrow_mut().clear());
+    let result = f();
+    (result, LOG.with(|log| log.take()))
+}
+
+/// Offsets and values of the writes in `log`, in order.
+pub(crate) fn writes(log: &[Access]) -> Vec<(usize, u64)> {
+    log.iter()
+        .filter(|a| a.kind == AccessKind::Write)
+        .map(|a| (a.offset, a.value))
+        .collect()
+}
+
+/// Erase size of [`RamFlash`].
+pub(crate) const FLASH_SECTOR: usize = 256;
+
+/// NOR flash in RAM; programming can only clear bits.
+pub(crate) struct RamFlash {
+    pub data: Vec<u8>,
+    /// Bytes that may still be programmed before power is "lost".
+    pub budget: Option<usize>,
+}
+
+impl RamFlash {
+    /// Flash of `sectors` sectors, every byte set to `fill`.
+    pub fn new(sectors: usize, fill: u8) -> Self {
+        Self {
+            data: std::vec![fill; sectors * FLASH_SECTOR],
+            budget: None,
+        }
+    }
+}
+
+impl ErrorType for RamFlash {
+    type Error = NorFlashErrorKind;
+}
+
+impl ReadNorFlash for RamFlash {
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        let offset = offset as usize;
+        bytes.copy_from_slice(&self.data[offset..offset + bytes.len()]);
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.data.len()
+    }
+}
+
+impl NorFlash for RamFlash {
+    const WRITE_SIZE: usize = 1;
+    const ERASE_SIZE: usize = FLASH_SECTOR;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        self.data[from as usize..to as usize].fill(0xFF);
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        for (i, &byte) in bytes.iter().enumerate() {
+            if let Some(budget) = &mut self.budget {
+                if *budget == 0 {
+                    return Err(NorFlashErrorKind::Other);
+                }
+                *budget -= 1;
+            }
+            self.data[offset as usize + i] &= byte;
+        }
+        Ok(())
+    }
+}
+
+impl MultiwriteNorFlash for RamFlash {}
@@ -0,0 +1,347 @@
+//! Modbus RTU framing for RS-485 buses.
+//!
+//! Works over any `embedded_io` serial port, usually a
+//! [`BlockingUart`](crate::uart::BlockingUart) in RS-485 mode, see
+//! [`enable_rs485`](crate::uart::BlockingUart::enable_rs485). RTU frames have
+//! no delimiters: a frame ends when the line has been silent for 3.5
+//! character times, and a gap of more than 1.5 character times inside a
+//! frame invalidates it. [`Rtu`] measures both with the machine timer, drops
+//! frames with a bad CRC or for another station, and keeps the silent
+//! interval before its own transmissions.
+//!
+//! A character is timestamped when it is read, not when it arrived, so the
+//! UART must run with its FIFOs disabled (`fifo: false` in its
+//! [`Config`](crate::uart::Config)) and [`Rtu::receive`] must poll faster
+//! than one character time. With the FIFO enabled, characters queue up
+//! unseen and the gaps measured are those of the polling loop instead of the
+//! line; without it, a character read late is at most one character time
+//! old, well inside the 1.5 character gap.
+//!
+//! ```ignore
+//! serial.enable_rs485(4, 4);
+//! let mut rtu = modbus::Rtu::new(serial, 19_200, Some(17));
+//! loop {
+//!     let request = rtu.receive(Timeout::NEVER)?;
+//!     let (address, function) = (request.address, request.function());
+//!     // ... handle the request ...
+//!     if address != modbus::BROADCAST {
+//!         rtu.send(17, &[function, 2, 0x12, 0x34])?;
+//!     }
+//! }
+//! ```
+//!
+//! Only the framing is handled; function codes and their payloads are left
+//! to the caller.
+
+use crate::crc::CRC16_MODBUS;
+use crate::soc::TIMER_FREQUENCY;
+use crate::time::{Timeout, now};
+use embedded_io::{Read, ReadReady, Write};
+
+/// Longest RTU frame: address, up to 253 bytes of PDU and the CRC.
+pub const MAX_FRAME_LEN: usize = 256;
+
+/// Longest protocol data unit, the function code and its data.
+pub const MAX_PDU_LEN: usize = MAX_FRAME_LEN - 3;
+
+/// Address of requests every station handles without answering.
+pub const BROADCAST: u8 = 0;
+
+/// Inter-character and inter-frame gaps of an RTU line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timing {
+    /// Longest gap between characters of a frame, 1.5 character times, in
+    /// machine timer ticks.
+    pub char_gap: u64,
+    /// Silence ending a frame, 3.5 character times, in machine timer ticks.
+    pub frame_gap: u64,
+}
+
+impl Timing {
+    /// Gaps for a line at `baud` with 11 bit characters.
+    ///
+    /// Above 19200 baud the fixed 750 and 1750 microseconds recommended by
+    /// the specification are used, since shorter gaps are hard to time for
+    /// the receivers. A `baud` of 0 is treated as 1.
+    pub const fn from_baud(baud: u32) -> Self {
+        const fn ticks(us: u64) -> u64 {
+            us * TIMER_FREQUENCY as u64 / 1_000_000
+        }
+        if baud > 19_200 {
+            return Self {
+                char_gap: ticks(750),
+                frame_gap: ticks(1_750),
+            };
+        }
+        let baud = if baud == 0 { 1 } else { baud };
+        let char_ticks = 11 * TIMER_FREQUENCY as u64 / baud as u64;
+        Self {
+            char_gap: char_ticks * 3 / 2,
+            frame_gap: char_ticks * 7 / 2,
+        }
+    }
+}
+
+/// Errors of a frame that failed its checks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameError {
+    /// Shorter than an address, a function code and the CRC.
+    TooShort,
+    /// Longer than [`MAX_FRAME_LEN`], or the output buffer is too small.
+    TooLong,
+    /// The CRC does not match.
+    Crc,
+}
+
+/// Errors of an [`Rtu`] transfer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModbusError<E> {
+    /// Underlying serial port error.
+    Serial(E),
+    /// No frame for this station arrived in time.
+    Timeout,
+    /// A frame to send could not be encoded.
+    Frame(FrameError),
+}
+
+/// A received frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Frame<'a> {
+    /// Station the frame is addressed to, or for responses, sent by.
+    pub address: u8,
+    /// Function code and data.
+    pub pdu: &'a [u8],
+}
+
+impl<'a> Frame<'a> {
+    /// Function code, with the high bit set in exception responses.
+    #[inline]
+    pub fn function(&self) -> u8 {
+        self.pdu[0]
+    }
+
+    /// Data following the function code.
+    #[inline]
+    pub fn data(&self) -> &'a [u8] {
+        &self.pdu[1..]
+    }
+}
+
+/// Writes the frame carrying `pdu` to `address` into `out` and returns its
+/// length.
+pub fn encode(address: u8, pdu: &[u8], out: &mut [u8]) -> Result<usize, FrameError> {
+    if pdu.is_empty() {
+        return Err(FrameError::TooShort);
+    }
+    let len = pdu.len() + 3;
+    if pdu.len() > MAX_PDU_LEN || out.len() < len {
+        return Err(FrameError::TooLong);
+    }
+    out[0] = address;
+    out[1..len - 2].copy_from_slice(pdu);
+    let crc = CRC16_MODBUS.checksum(&out[..len - 2]);
+    out[len - 2..len].copy_from_slice(&crc.to_le_bytes());
+    Ok(len)
+}
+
+/// Checks the CRC of a complete frame and splits it up.
+pub fn decode(frame: &[u8]) -> Result<Frame<'_>, FrameError> {
+    if frame.len() < 4 {
+        return Err(FrameError::TooShort);
+    }
+    if frame.len() > MAX_FRAME_LEN {
+        return Err(FrameError::TooLong);
+    }
+    let (body, crc) = frame.split_at(frame.len() - 2);
+    if CRC16_MODBUS.checksum(body) != u16::from_le_bytes([crc[0], crc[1]]) {
+        return Err(FrameError::Crc);
+    }
+    Ok(Frame {
+        address: body[0],
+        pdu: &body[1..],
+    })
+}
+
+/// Modbus RTU station on a serial port.
+pub struct Rtu<S> {
+    serial: S,
+    timing: Timing,
+    address: Option<u8>,
+    /// Machine timer value of the last character seen or sent.
+    last_activity: u64,
+    buf: [u8; MAX_FRAME_LEN],
+}
+
+impl<S: Read + ReadReady + Write> Rtu<S> {
+    /// Creates a station on a line at `baud`.
+    ///
+    /// With an `address`, only frames for it and broadcasts are received, as
+    /// a server does; a client passes `None` to receive every frame. The
+    /// serial port must not buffer received characters, see the
+    /// [module documentation](self).
+    pub fn new(serial: S, baud: u32, address: Option<u8>) -> Self {
+        Self {
+            serial,
+            timing: Timing::from_baud(baud),
+            address,
+            last_activity: now(),
+            buf: [0; MAX_FRAME_LEN],
+        }
+    }
+
+    /// Gaps used to delimit frames.
+    #[inline]
+    pub fn timing(&self) -> Timing {
+        self.timing
+    }
+
+    /// Returns the serial port.
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.serial
+    }
+
+    /// Waits for the next valid frame for this station.
+    ///
+    /// Frames with a bad CRC, a gap inside them or for another station are
+    /// dropped. Fails with [`ModbusError::Timeout`] if no frame has started
+    /// when `timeout` expires; a frame already started is always completed.
+    pub fn receive(&mut self, timeout: Timeout) -> Result<Frame<'_>, ModbusError<S::Error>> {
+        loop {
+            let Some(len) = self.read_frame(timeout)? else {
+                continue;
+            };
+            let frame = &self.buf[..len];
+            let wanted = match (decode(frame), self.address) {
+                (Ok(frame), Some(address)) => {
+                    frame.address == address || frame.address == BROADCAST
+                }
+                (Ok(_), None) => true,
+                (Err(_), _) => false,
+            };
+            if wanted {
+                return decode(&self.buf[..len]).map_err(ModbusError::Frame);
+            }
+        }
+    }
+
+    /// Sends `pdu` to `address`, or from it when answering a request.
+    ///
+    /// Waits for the line to be silent for 3.5 character times first, and
+    /// returns once the frame has been transmitted.
+    pub fn send(&mut self, address: u8, pdu: &[u8]) -> Result<(), ModbusError<S::Error>> {
+        let len = encode(address, pdu, &mut self.buf).map_err(ModbusError::Frame)?;
+        while now().wrapping_sub(self.last_activity) < self.timing.frame_gap {
+            core::hint::spin_loop();
+        }
+        self.serial
+            .write_all(&self.buf[..len])
+            .map_err(ModbusError::Serial)?;
+        self.serial.flush().map_err(ModbusError::Serial)?;
+        self.last_activity = now();
+        Ok(())
+    }
+
+    /// Reads characters until the line is silent for 3.5 character times.
+    ///
+    /// Returns the length of the frame in `buf`, or `None` if it had a gap
+    /// of more than 1.5 character times or overflowed the buffer.
+    fn read_frame(&mut self, timeout: Timeout) -> Result<Option<usize>, ModbusError<S::Error>> {
+        loop {
+            let expired = timeout.is_expired();
+            if self.read_byte(0)? {
+                break;
+            }
+            if expired {
+                return Err(ModbusError::Timeout);
+            }
+            core::hint::spin_loop();
+        }
+        let mut len = 1;
+        let mut valid = true;
+        loop {
+            let gap = now().wrapping_sub(self.last_activity);
+            if gap >= self.timing.frame_gap {
+                return Ok(valid.then_some(len));
+            }
+            let index = len.min(MAX_FRAME_LEN - 1);
+            if self.read_byte(index)? {
+                valid &= gap <= self.timing.char_gap && len < MAX_FRAME_LEN;
+                len += 1;
+            } else {
+                core::hint::spin_loop();
+            }
+        }
+    }
+
+    /// Stores a received character at `index` of the buffer, if one is
+    /// waiting, and stamps it with the current time.
+    ///
+    /// Without a receive FIFO the receiver holds one character at most, so
+    /// the stamp is late by no more than the polling interval.
+    fn read_byte(&mut self, index: usize) -> Result<bool, ModbusError<S::Error>> {
+        if !self.serial.read_ready().map_err(ModbusError::Serial)? {
+            return Ok(false);
+        }
+        let mut byte = [0];
+        if self.serial.read(&mut byte).map_err(ModbusError::Serial)? == 0 {
+            return Ok(false);
+        }
+        self.buf[index] = byte[0];
+        self.last_activity = now();
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Read holding registers 0x006B to 0x006D of station 17.
+    const REQUEST: [u8; 8] = [0x11, 0x03, 0x00, 0x6B, 0x00, 0x03, 0x76, 0x87];
+
+    #[test]
+    fn encode_request() {
+        let mut out = [0; MAX_FRAME_LEN];
+        let len = encode(0x11, &REQUEST[1..6], &mut out).unwrap();
+        assert_eq!(&out[..len], &REQUEST);
+        assert_eq!(encode(0x11, &[], &mut out), Err(FrameError::TooShort));
+        assert_eq!(
+            encode(0x11, &[0; MAX_PDU_LEN + 1], &mut out),
+            Err(FrameError::TooLong)
+        );
+        assert_eq!(
+            encode(0x11, &REQUEST[1..6], &mut out[..7]),
+            Err(FrameError::TooLong)
+        );
+    }
+
+    #[test]
+    fn decode_request() {
+        let frame = decode(&REQUEST).unwrap();
+        assert_eq!(frame.address, 0x11);
+        assert_eq!(frame.function(), 0x03);
+        assert_eq!(frame.data(), &[0x00, 0x6B, 0x00, 0x03]);
+
+        let mut corrupted = REQUEST;
+        corrupted[3] ^= 1;
+        assert_eq!(decode(&corrupted), Err(FrameError::Crc));
+        assert_eq!(decode(&REQUEST[..3]), Err(FrameError::TooShort));
+    }
+
+    #[test]
+    fn timing_from_baud() {
+        let char_ticks = 11 * TIMER_FREQUENCY as u64 / 9_600;
+        assert_eq!(
+            Timing::from_baud(9_600),
+            Timing {
+                char_gap: char_ticks * 3 / 2,
+                frame_gap: char_ticks * 7 / 2,
+            }
+        );
+        let fixed = Timing::from_baud(115_200);
+        assert_eq!(fixed, Timing::from_baud(38_400));
+        assert_eq!(fixed.frame_gap, 1_750 * TIMER_FREQUENCY as u64 / 1_000_000);
+        assert_eq!(Timing::from_baud(0), Timing::from_baud(1));
+    }
+}
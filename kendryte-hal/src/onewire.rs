@@ -0,0 +1,388 @@
+//! Bit-banged 1-Wire bus master.
+//!
+//! Drives a Dallas/Maxim 1-Wire bus, such as a chain of DS18B20 temperature
+//! sensors, from a single GPIO pin. The K230 GPIO has no open-drain output,
+//! so the line is driven low by switching the pin to output with a low level
+//! and released by switching it back to input, letting the pull-up raise it.
+//! The internal pad pull-up is enabled, but it is too weak for more than a
+//! short bus; fit an external 4.7 kOhm resistor to the supply.
+//!
+//! Slot timings follow the standard speed values of Maxim application note
+//! 126 and are measured against the machine timer from the start of each
+//! slot, so a slow register access does not add up over a byte. Machine
+//! interrupts are masked while a slot is timed: a device samples the line
+//! 15 µs into a write slot, and an interrupt handler running then would
+//! corrupt the bit.
+
+use crate::gpio::blocking::Dynamic;
+use crate::gpio::config::Pull;
+use crate::time::{interrupt_free, now};
+use crate::util::bitbang::{micros_to_ticks as ticks, wait_until};
+use embedded_hal::digital::PinState;
+
+/// Read ROM command, valid with a single device on the bus.
+pub const READ_ROM: u8 = 0x33;
+/// Match ROM command, addresses one device by its ROM code.
+pub const MATCH_ROM: u8 = 0x55;
+/// Skip ROM command, addresses every device on the bus.
+pub const SKIP_ROM: u8 = 0xCC;
+/// Search ROM command.
+pub const SEARCH_ROM: u8 = 0xF0;
+/// Alarm search command, only devices with an alarm condition take part.
+pub const ALARM_SEARCH: u8 = 0xEC;
+
+// Standard speed timings in microseconds, named as in application note 126.
+const A: u32 = 6;
+const B: u32 = 64;
+const C: u32 = 60;
+const D: u32 = 10;
+const E: u32 = 9;
+const F: u32 = 55;
+const H: u32 = 480;
+const I: u32 = 70;
+const J: u32 = 410;
+
+/// 1-Wire bus error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OneWireError {
+    /// No device answered the reset pulse with a presence pulse.
+    NoPresence,
+    /// The line stayed low while released.
+    BusShorted,
+    /// A ROM code or scratchpad failed its CRC check.
+    CrcMismatch,
+}
+
+/// 64-bit ROM code of a 1-Wire device.
+///
+/// Holds the family code, the 48-bit serial number and the CRC, in the order
+/// they are sent on the bus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rom(pub [u8; 8]);
+
+impl Rom {
+    /// Creates a ROM code, checking its CRC.
+    pub fn new(bytes: [u8; 8]) -> Result<Self, OneWireError> {
+        check_crc8(&bytes)?;
+        Ok(Self(bytes))
+    }
+
+    /// Family code, identifying the device type.
+    pub const fn family(&self) -> u8 {
+        self.0[0]
+    }
+
+    /// 48-bit serial number.
+    pub const fn serial(&self) -> u64 {
+        let b = &self.0;
+        u64::from_le_bytes([b[1], b[2], b[3], b[4], b[5], b[6], 0, 0])
+    }
+
+    /// CRC of the family code and serial number.
+    pub const fn crc(&self) -> u8 {
+        self.0[7]
+    }
+}
+
+/// State of a ROM search across calls to [`OneWire::search_next`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RomSearch {
+    command: u8,
+    rom: [u8; 8],
+    last_discrepancy: u8,
+    last_family_discrepancy: u8,
+    done: bool,
+}
+
+impl RomSearch {
+    /// Search for every device on the bus.
+    pub const fn new() -> Self {
+        Self::with_command(SEARCH_ROM)
+    }
+
+    /// Search for devices with an alarm condition.
+    pub const fn alarm() -> Self {
+        Self::with_command(ALARM_SEARCH)
+    }
+
+    /// Start over with the first device.
+    pub fn restart(&mut self) {
+        *self = Self::with_command(self.command);
+    }
+
+    /// Skip the remaining devices of the family of the last device found.
+    pub fn skip_family(&mut self) {
+        self.last_discrepancy = self.last_family_discrepancy;
+        self.last_family_discrepancy = 0;
+        self.done = self.last_discrepancy == 0;
+    }
+
+    const fn with_command(command: u8) -> Self {
+        Self {
+            command,
+            rom: [0; 8],
+            last_discrepancy: 0,
+            last_family_discrepancy: 0,
+            done: false,
+        }
+    }
+}
+
+impl Default for RomSearch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 1-Wire bus master on a GPIO pin.
+pub struct OneWire<'i, 'p> {
+    pin: Dynamic<'i, 'p>,
+}
+
+impl<'i, 'p> OneWire<'i, 'p> {
+    /// Creates a bus master on `pin` and releases the line.
+    pub fn new(mut pin: Dynamic<'i, 'p>) -> Self {
+        pin.configure_as_input(Pull::Up);
+        Self { pin }
+    }
+
+    /// Releases the line and returns the pin.
+    pub fn free(mut self) -> Dynamic<'i, 'p> {
+        self.release();
+        self.pin
+    }
+
+    /// Sends a reset pulse and returns whether a device answered.
+    ///
+    /// Fails with [`OneWireError::BusShorted`] if the line is low before the
+    /// reset pulse.
+    pub fn reset(&mut self) -> Result<bool, OneWireError> {
+        self.release();
+        if self.is_low() {
+            return Err(OneWireError::BusShorted);
+        }
+        let (presence, start) = interrupt_free(|| {
+            let start = now();
+            self.drive_low();
+            wait_until(start + ticks(H));
+            self.release();
+            let start = now();
+            wait_until(start + ticks(I));
+            (self.is_low(), start)
+        });
+        // Let the presence pulse end before the next slot.
+        wait_until(start + ticks(I + J));
+        Ok(presence)
+    }
+
+    /// Writes one bit.
+    #[cfg_attr(
+        feature = "ramfunc",
+        unsafe(link_section = ".ramfunc.onewire_write_bit")
+    )]
+    pub fn write_bit(&mut self, bit: bool) {
+        let (low, slot) = if bit { (A, A + B) } else { (C, C + D) };
+        interrupt_free(|| {
+            let start = now();
+            self.drive_low();
+            wait_until(start + ticks(low));
+            self.release();
+            wait_until(start + ticks(slot));
+        });
+    }
+
+    /// Reads one bit.
+    #[cfg_attr(
+        feature = "ramfunc",
+        unsafe(link_section = ".ramfunc.onewire_read_bit")
+    )]
+    pub fn read_bit(&mut self) -> bool {
+        interrupt_free(|| {
+            let start = now();
+            self.drive_low();
+            wait_until(start + ticks(A));
+            self.release();
+            wait_until(start + ticks(A + E));
+            let bit = !self.is_low();
+            wait_until(start + ticks(A + E + F));
+            bit
+        })
+    }
+
+    /// Writes one byte, least significant bit first.
+    pub fn write_byte(&mut self, byte: u8) {
+        for i in 0..8 {
+            self.write_bit(byte & (1 << i) != 0);
+        }
+    }
+
+    /// Reads one byte, least significant bit first.
+    pub fn read_byte(&mut self) -> u8 {
+        (0..8).fold(0, |byte, i| byte | ((self.read_bit() as u8) << i))
+    }
+
+    /// Writes `bytes` in order.
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_byte(byte);
+        }
+    }
+
+    /// Fills `buf` with bytes read from the bus.
+    pub fn read_bytes(&mut self, buf: &mut [u8]) {
+        for byte in buf {
+            *byte = self.read_byte();
+        }
+    }
+
+    /// Resets the bus and addresses every device.
+    pub fn skip_rom(&mut self) -> Result<(), OneWireError> {
+        self.reset_expect_presence()?;
+        self.write_byte(SKIP_ROM);
+        Ok(())
+    }
+
+    /// Resets the bus and addresses the device with ROM code `rom`.
+    pub fn match_rom(&mut self, rom: &Rom) -> Result<(), OneWireError> {
+        self.reset_expect_presence()?;
+        self.write_byte(MATCH_ROM);
+        self.write_bytes(&rom.0);
+        Ok(())
+    }
+
+    /// Reads the ROM code of the only device on the bus.
+    ///
+    /// With more than one device the codes collide and the CRC check fails.
+    pub fn read_rom(&mut self) -> Result<Rom, OneWireError> {
+        self.reset_expect_presence()?;
+        self.write_byte(READ_ROM);
+        let mut rom = [0; 8];
+        self.read_bytes(&mut rom);
+        Rom::new(rom)
+    }
+
+    /// Finds the next device of a ROM search.
+    ///
+    /// Returns `None` once every device has been found, or if none is on the
+    /// bus. Devices are found in ascending order of their ROM code read
+    /// least significant bit first.
+    pub fn search_next(&mut self, search: &mut RomSearch) -> Result<Option<Rom>, OneWireError> {
+        if search.done {
+            return Ok(None);
+        }
+        if !self.reset()? {
+            search.restart();
+            return Ok(None);
+        }
+        self.write_byte(search.command);
+
+        let mut last_zero = 0;
+        for bit in 1..=64u8 {
+            let index = usize::from((bit - 1) / 8);
+            let mask = 1 << ((bit - 1) % 8);
+            let id = self.read_bit();
+            let complement = self.read_bit();
+            let direction = match (id, complement) {
+                // No device is taking part any more.
+                (true, true) => {
+                    search.restart();
+                    return Ok(None);
+                }
+                // Devices disagree on this bit.
+                (false, false) => {
+                    let direction = if bit < search.last_discrepancy {
+                        search.rom[index] & mask != 0
+                    } else {
+                        bit == search.last_discrepancy
+                    };
+                    if !direction {
+                        last_zero = bit;
+                        if last_zero < 9 {
+                            search.last_family_discrepancy = last_zero;
+                        }
+                    }
+                    direction
+                }
+                (id, _) => id,
+            };
+            if direction {
+                search.rom[index] |= mask;
+            } else {
+                search.rom[index] &= !mask;
+            }
+            self.write_bit(direction);
+        }
+
+        search.last_discrepancy = last_zero;
+        search.done = last_zero == 0;
+        Rom::new(search.rom).map(Some)
+    }
+
+    fn reset_expect_presence(&mut self) -> Result<(), OneWireError> {
+        match self.reset()? {
+            true => Ok(()),
+            false => Err(OneWireError::NoPresence),
+        }
+    }
+
+    #[inline(always)]
+    fn drive_low(&mut self) {
+        self.pin.common.configure_as_output(PinState::Low);
+    }
+
+    #[inline(always)]
+    fn release(&mut self) {
+        self.pin.common.configure_as_input();
+    }
+
+    #[inline(always)]
+    fn is_low(&self) -> bool {
+        self.pin.common.read_input_state() == PinState::Low
+    }
+}
+
+/// Dallas/Maxim CRC-8 of `data`, polynomial x^8 + x^5 + x^4 + 1.
+///
+/// Running it over data followed by its CRC yields zero.
+pub fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0;
+    for &byte in data {
+        let mut byte = byte;
+        for _ in 0..8 {
+            let mix = (crc ^ byte) & 1;
+            crc >>= 1;
+            if mix != 0 {
+                crc ^= 0x8C;
+            }
+            byte >>= 1;
+        }
+    }
+    crc
+}
+
+/// Checks data whose last byte is the CRC-8 of the others.
+pub fn check_crc8(data: &[u8]) -> Result<(), OneWireError> {
+    match crc8(data) {
+        0 => Ok(()),
+        _ => Err(OneWireError::CrcMismatch),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc8() {
+        // Example ROM code from Maxim application note 27.
+        let rom = [0x02, 0x1C, 0xB8, 0x01, 0x00, 0x00, 0x00, 0xA2];
+        assert_eq!(crc8(&rom[..7]), 0xA2);
+        let rom = Rom::new(rom).expect("valid CRC");
+        assert_eq!(rom.family(), 0x02);
+        assert_eq!(rom.serial(), 0x01_B81C);
+        assert_eq!(
+            Rom::new([0x02, 0x1C, 0xB8, 0x01, 0x00, 0x00, 0x00, 0xA3]),
+            Err(OneWireError::CrcMismatch)
+        );
+    }
+}
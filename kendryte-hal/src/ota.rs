@@ -0,0 +1,547 @@
+//! A/B firmware update slots in NOR flash.
+//!
+//! Flash holds two image slots. Each slot starts with a [`SlotHeader`] and the
+//! image follows at [`IMAGE_OFFSET`]. The bootable slot with the highest
+//! sequence number is booted; a new image is always written to the slot the
+//! firmware is not running from, so an interrupted update leaves the running
+//! firmware untouched.
+//!
+//! The bootloader calls [`Ota::select_boot`] and jumps to the slot it
+//! returns. The booted firmware calls [`Ota::confirm`] once it works; an
+//! image that never confirms is invalidated on the next boot and the previous
+//! one is booted again. An update is [`Ota::begin_update`], any number of
+//! [`Ota::write_image`] calls and [`Ota::finish_update`].
+//!
+//! Header layout, little endian, [`HEADER_LEN`] bytes:
+//!
+//! | Offset | Size | Field                                               |
+//! |--------|------|-----------------------------------------------------|
+//! | 0      | 4    | magic, `b"KOTA"`                                    |
+//! | 4      | 4    | sequence number, compared modulo 2^32               |
+//! | 8      | 4    | state flags, see [`SlotState`]                      |
+//! | 12     | 4    | image length                                        |
+//! | 16     | 4    | CRC-32 of the image                                 |
+//! | 20     | 8    | reserved, `0xFF`                                    |
+//! | 28     | 4    | CRC-32 of bytes 0..8 and 12..28                     |
+//!
+//! The state flags are active low so they can be updated by programming
+//! without erasing the header sector. `cargo xtask gen-ota` builds slot
+//! images in this format.
+
+use crate::crc::CRC32_IEEE;
+use embedded_storage::nor_flash::MultiwriteNorFlash;
+
+/// Magic bytes at the start of a slot header.
+pub const HEADER_MAGIC: [u8; 4] = *b"KOTA";
+/// Length of a slot header in bytes.
+pub const HEADER_LEN: usize = 32;
+/// Offset of the image from the start of its slot.
+pub const IMAGE_OFFSET: u32 = 4096;
+
+/// Offset of the state flags in the header.
+const FLAGS_OFFSET: u32 = 8;
+/// Cleared once the slot has been booted.
+const FLAG_NOT_TRIED: u32 = 1 << 0;
+/// Cleared once the firmware in the slot has confirmed it works.
+const FLAG_NOT_CONFIRMED: u32 = 1 << 1;
+/// Cleared when the slot must not be booted.
+const FLAG_NOT_INVALID: u32 = 1 << 2;
+
+/// Bytes read from flash at a time when checking an image.
+const CHUNK_LEN: usize = 256;
+
+/// One of the two image slots.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Slot {
+    A,
+    B,
+}
+
+impl Slot {
+    /// Returns the other slot.
+    #[inline]
+    pub const fn other(self) -> Self {
+        match self {
+            Slot::A => Slot::B,
+            Slot::B => Slot::A,
+        }
+    }
+}
+
+/// Placement of the two slots in flash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+    /// Flash offset of slot A.
+    pub slot_a: u32,
+    /// Flash offset of slot B.
+    pub slot_b: u32,
+    /// Size of each slot, including the header sector.
+    pub slot_size: u32,
+}
+
+impl Layout {
+    /// Flash offset of `slot`.
+    #[inline]
+    pub const fn offset(&self, slot: Slot) -> u32 {
+        match slot {
+            Slot::A => self.slot_a,
+            Slot::B => self.slot_b,
+        }
+    }
+
+    /// Largest image that fits in a slot.
+    #[inline]
+    pub const fn max_image_len(&self) -> u32 {
+        self.slot_size - IMAGE_OFFSET
+    }
+}
+
+/// Boot state of a slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlotState {
+    /// Written but never booted.
+    New,
+    /// Booted but not confirmed. This is the running firmware until the
+    /// next boot, which invalidates the slot if it is still unconfirmed.
+    Trying,
+    /// Confirmed by the firmware it contains.
+    Confirmed,
+    /// Marked as not bootable.
+    Invalid,
+}
+
+/// Decoded slot header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SlotHeader {
+    /// Update sequence number, see [`is_newer`](Self::is_newer).
+    pub sequence: u32,
+    /// Length of the image in bytes.
+    pub image_len: u32,
+    /// CRC-32 of the image.
+    pub image_crc32: u32,
+    flags: u32,
+}
+
+impl SlotHeader {
+    /// Creates the header of a freshly written image.
+    #[inline]
+    pub const fn new(sequence: u32, image_len: u32, image_crc32: u32) -> Self {
+        Self {
+            sequence,
+            image_len,
+            image_crc32,
+            flags: u32::MAX,
+        }
+    }
+
+    /// Decodes a header, returning `None` if the magic or checksum is wrong.
+    pub fn decode(bytes: &[u8; HEADER_LEN]) -> Option<Self> {
+        let word = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        if bytes[..4] != HEADER_MAGIC || word(28) != header_crc(bytes) {
+            return None;
+        }
+        Some(Self {
+            sequence: word(4),
+            flags: word(8),
+            image_len: word(12),
+            image_crc32: word(16),
+        })
+    }
+
+    /// Encodes the header.
+    pub fn encode(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0xFF; HEADER_LEN];
+        bytes[..4].copy_from_slice(&HEADER_MAGIC);
+        bytes[4..8].copy_from_slice(&self.sequence.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.flags.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.image_len.to_le_bytes());
+        bytes[16..20].copy_from_slice(&self.image_crc32.to_le_bytes());
+        let crc = header_crc(&bytes);
+        bytes[28..32].copy_from_slice(&crc.to_le_bytes());
+        bytes
+    }
+
+    /// Returns the boot state.
+    pub const fn state(&self) -> SlotState {
+        if self.flags & FLAG_NOT_INVALID == 0 {
+            SlotState::Invalid
+        } else if self.flags & FLAG_NOT_CONFIRMED == 0 {
+            SlotState::Confirmed
+        } else if self.flags & FLAG_NOT_TRIED == 0 {
+            SlotState::Trying
+        } else {
+            SlotState::New
+        }
+    }
+
+    /// Returns true if the slot may be booted.
+    #[inline]
+    pub const fn is_bootable(&self) -> bool {
+        matches!(self.state(), SlotState::New | SlotState::Confirmed)
+    }
+
+    /// Returns true if this header was written after `other`.
+    ///
+    /// Sequence numbers wrap, so they are compared as serial numbers: a
+    /// sequence is newer if it is less than 2^31 ahead of the other.
+    #[inline]
+    pub const fn is_newer(&self, other: &SlotHeader) -> bool {
+        (self.sequence.wrapping_sub(other.sequence) as i32) > 0
+    }
+}
+
+/// Returns the newer of two optional slots.
+fn newest(a: Option<SlotHeader>, b: Option<SlotHeader>) -> Option<(Slot, SlotHeader)> {
+    match (a, b) {
+        (Some(a), Some(b)) if b.is_newer(&a) => Some((Slot::B, b)),
+        (Some(a), _) => Some((Slot::A, a)),
+        (None, Some(b)) => Some((Slot::B, b)),
+        (None, None) => None,
+    }
+}
+
+/// CRC over every header field except the state flags and the CRC itself.
+fn header_crc(bytes: &[u8; HEADER_LEN]) -> u32 {
+    let mut digest = CRC32_IEEE.digest();
+    digest.update(&bytes[..8]);
+    digest.update(&bytes[12..28]);
+    digest.finalize()
+}
+
+/// Update error types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtaError<E> {
+    /// Underlying flash error.
+    Flash(E),
+    /// The image does not fit in a slot.
+    ImageTooLarge,
+    /// The slot has no valid header.
+    NoHeader,
+    /// The image read back from flash does not match the expected CRC.
+    CrcMismatch,
+}
+
+/// A/B update manager on top of a NOR flash.
+///
+/// Works with any [`MultiwriteNorFlash`], including [`SpiNor`](crate::flash::SpiNor);
+/// its write size must divide 4 so the state flags can be updated in place.
+pub struct Ota<F> {
+    flash: F,
+    layout: Layout,
+}
+
+impl<F: MultiwriteNorFlash> Ota<F> {
+    /// Creates an update manager for the given slot layout.
+    ///
+    /// Slot offsets and the slot size must be multiples of the flash erase size.
+    #[inline]
+    pub fn new(flash: F, layout: Layout) -> Self {
+        Self { flash, layout }
+    }
+
+    /// Returns the slot layout.
+    #[inline]
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// Release the underlying flash.
+    #[inline]
+    pub fn free(self) -> F {
+        self.flash
+    }
+
+    /// Reads the header of `slot`, or `None` if the slot holds no image.
+    pub fn header(&mut self, slot: Slot) -> Result<Option<SlotHeader>, OtaError<F::Error>> {
+        let mut bytes = [0; HEADER_LEN];
+        self.flash
+            .read(self.layout.offset(slot), &mut bytes)
+            .map_err(OtaError::Flash)?;
+        Ok(SlotHeader::decode(&bytes))
+    }
+
+    /// Returns the bootable slot with the newest sequence number.
+    pub fn boot_slot(&mut self) -> Result<Option<(Slot, SlotHeader)>, OtaError<F::Error>> {
+        let a = self.header(Slot::A)?.filter(SlotHeader::is_bootable);
+        let b = self.header(Slot::B)?.filter(SlotHeader::is_bootable);
+        Ok(newest(a, b))
+    }
+
+    /// Chooses the slot to boot, for the bootloader.
+    ///
+    /// A slot still `Trying` from the previous boot never confirmed and is
+    /// invalidated. A `New` slot is then marked as tried, and the boot slot
+    /// returned, or `None` if no slot is bootable.
+    pub fn select_boot(&mut self) -> Result<Option<(Slot, SlotHeader)>, OtaError<F::Error>> {
+        for slot in [Slot::A, Slot::B] {
+            if let Some(header) = self.header(slot)?
+                && header.state() == SlotState::Trying
+            {
+                self.invalidate(slot)?;
+            }
+        }
+        let boot = self.boot_slot()?;
+        if let Some((slot, header)) = boot
+            && header.state() == SlotState::New
+        {
+            self.mark_tried(slot)?;
+        }
+        Ok(boot)
+    }
+
+    /// Returns the slot the running firmware was booted from.
+    ///
+    /// This is the newest slot that was booted, confirmed or not. Before the
+    /// first boot through [`select_boot`](Self::select_boot) it falls back to
+    /// [`boot_slot`](Self::boot_slot).
+    pub fn running_slot(&mut self) -> Result<Option<Slot>, OtaError<F::Error>> {
+        let booted = |header: &SlotHeader| {
+            matches!(header.state(), SlotState::Trying | SlotState::Confirmed)
+        };
+        let a = self.header(Slot::A)?.filter(booted);
+        let b = self.header(Slot::B)?.filter(booted);
+        match newest(a, b) {
+            Some((slot, _)) => Ok(Some(slot)),
+            None => Ok(self.boot_slot()?.map(|(slot, _)| slot)),
+        }
+    }
+
+    /// Records that `slot` is being booted.
+    ///
+    /// Called by [`select_boot`](Self::select_boot) before the bootloader
+    /// jumps to a slot in the `New` state. If the firmware never calls
+    /// [`confirm`](Self::confirm), the slot is invalidated on the next boot
+    /// and the previous image is used again.
+    pub fn mark_tried(&mut self, slot: Slot) -> Result<(), OtaError<F::Error>> {
+        self.clear_flags(slot, FLAG_NOT_TRIED)
+    }
+
+    /// Marks the running image in `slot` as good.
+    pub fn confirm(&mut self, slot: Slot) -> Result<(), OtaError<F::Error>> {
+        self.clear_flags(slot, FLAG_NOT_CONFIRMED)
+    }
+
+    /// Marks `slot` as not bootable.
+    pub fn invalidate(&mut self, slot: Slot) -> Result<(), OtaError<F::Error>> {
+        self.clear_flags(slot, FLAG_NOT_INVALID)
+    }
+
+    /// Erases the slot the firmware is not running from and returns it.
+    ///
+    /// Write the new image with [`write_image`](Self::write_image), then call
+    /// [`finish_update`](Self::finish_update) to make it bootable.
+    pub fn begin_update(&mut self) -> Result<Slot, OtaError<F::Error>> {
+        let target = match self.running_slot()? {
+            Some(slot) => slot.other(),
+            None => Slot::A,
+        };
+        let offset = self.layout.offset(target);
+        self.flash
+            .erase(offset, offset + self.layout.slot_size)
+            .map_err(OtaError::Flash)?;
+        Ok(target)
+    }
+
+    /// Writes part of the new image at `offset` from the image start.
+    pub fn write_image(
+        &mut self,
+        slot: Slot,
+        offset: u32,
+        data: &[u8],
+    ) -> Result<(), OtaError<F::Error>> {
+        if offset as u64 + data.len() as u64 > self.layout.max_image_len() as u64 {
+            return Err(OtaError::ImageTooLarge);
+        }
+        let address = self.layout.offset(slot) + IMAGE_OFFSET + offset;
+        self.flash.write(address, data).map_err(OtaError::Flash)
+    }
+
+    /// Checks the image written to `slot` and writes its header.
+    ///
+    /// `image_len` and `image_crc32` describe the image as sent by the update
+    /// source; if the image read back from flash does not match them, no
+    /// header is written and [`OtaError::CrcMismatch`] is returned.
+    ///
+    /// The new slot gets a sequence number newer than the other slot, so it
+    /// is booted next. The header is written last, so a slot whose update was
+    /// interrupted is never booted.
+    pub fn finish_update(
+        &mut self,
+        slot: Slot,
+        image_len: u32,
+        image_crc32: u32,
+    ) -> Result<SlotHeader, OtaError<F::Error>> {
+        if image_len > self.layout.max_image_len() {
+            return Err(OtaError::ImageTooLarge);
+        }
+        if self.image_crc(slot, image_len)? != image_crc32 {
+            return Err(OtaError::CrcMismatch);
+        }
+        let sequence = match self.header(slot.other())? {
+            Some(other) => other.sequence.wrapping_add(1),
+            None => 1,
+        };
+        let header = SlotHeader::new(sequence, image_len, image_crc32);
+        self.flash
+            .write(self.layout.offset(slot), &header.encode())
+            .map_err(OtaError::Flash)?;
+        Ok(header)
+    }
+
+    /// Reads back the image in `slot` and compares it with the header CRC.
+    pub fn verify(&mut self, slot: Slot) -> Result<(), OtaError<F::Error>> {
+        let header = self.header(slot)?.ok_or(OtaError::NoHeader)?;
+        if header.image_len > self.layout.max_image_len() {
+            return Err(OtaError::ImageTooLarge);
+        }
+        if self.image_crc(slot, header.image_len)? != header.image_crc32 {
+            return Err(OtaError::CrcMismatch);
+        }
+        Ok(())
+    }
+
+    fn image_crc(&mut self, slot: Slot, image_len: u32) -> Result<u32, OtaError<F::Error>> {
+        let start = self.layout.offset(slot) + IMAGE_OFFSET;
+        let mut digest = CRC32_IEEE.digest();
+        let mut buf = [0; CHUNK_LEN];
+        let mut done = 0;
+        while done < image_len {
+            let len = (image_len - done).min(CHUNK_LEN as u32) as usize;
+            self.flash
+                .read(start + done, &mut buf[..len])
+                .map_err(OtaError::Flash)?;
+            digest.update(&buf[..len]);
+            done += len as u32;
+        }
+        Ok(digest.finalize())
+    }
+
+    fn clear_flags(&mut self, slot: Slot, flags: u32) -> Result<(), OtaError<F::Error>> {
+        let header = self.header(slot)?.ok_or(OtaError::NoHeader)?;
+        let value = header.flags & !flags;
+        self.flash
+            .write(
+                self.layout.offset(slot) + FLAGS_OFFSET,
+                &value.to_le_bytes(),
+            )
+            .map_err(OtaError::Flash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{FLASH_SECTOR, RamFlash};
+    use embedded_storage::nor_flash::NorFlash;
+
+    const SLOT_SIZE: u32 = 2 * IMAGE_OFFSET;
+    const LAYOUT: Layout = Layout {
+        slot_a: 0,
+        slot_b: SLOT_SIZE,
+        slot_size: SLOT_SIZE,
+    };
+
+    fn ota() -> Ota<RamFlash> {
+        let sectors = 2 * SLOT_SIZE as usize / FLASH_SECTOR;
+        Ota::new(RamFlash::new(sectors, 0xFF), LAYOUT)
+    }
+
+    fn update(ota: &mut Ota<RamFlash>, image: &[u8]) -> Slot {
+        let slot = ota.begin_update().unwrap();
+        ota.write_image(slot, 0, image).unwrap();
+        let crc = CRC32_IEEE.checksum(image);
+        ota.finish_update(slot, image.len() as u32, crc).unwrap();
+        slot
+    }
+
+    fn state(ota: &mut Ota<RamFlash>, slot: Slot) -> SlotState {
+        ota.header(slot).unwrap().unwrap().state()
+    }
+
+    #[test]
+    fn header_round_trip() {
+        let header = SlotHeader::new(7, 1234, 0xDEAD_BEEF);
+        let mut bytes = header.encode();
+        assert_eq!(SlotHeader::decode(&bytes), Some(header));
+        assert_eq!(header.state(), SlotState::New);
+        bytes[5] ^= 1;
+        assert_eq!(SlotHeader::decode(&bytes), None);
+    }
+
+    #[test]
+    fn sequence_wraps() {
+        let old = SlotHeader::new(u32::MAX, 0, 0);
+        let new = SlotHeader::new(0, 0, 0);
+        assert!(new.is_newer(&old));
+        assert!(!old.is_newer(&new));
+        assert!(!new.is_newer(&new));
+
+        let ota = ota();
+        let mut flash = ota.free();
+        flash.write(LAYOUT.slot_a, &old.encode()).unwrap();
+        flash.write(LAYOUT.slot_b, &new.encode()).unwrap();
+        let mut ota = Ota::new(flash, LAYOUT);
+        assert_eq!(ota.boot_slot().unwrap(), Some((Slot::B, new)));
+    }
+
+    #[test]
+    fn finish_checks_crc() {
+        let mut ota = ota();
+        let slot = ota.begin_update().unwrap();
+        ota.write_image(slot, 0, b"image").unwrap();
+        let crc = CRC32_IEEE.checksum(b"imagf");
+        assert_eq!(ota.finish_update(slot, 5, crc), Err(OtaError::CrcMismatch));
+        assert_eq!(ota.header(slot).unwrap(), None);
+        assert_eq!(
+            ota.finish_update(slot, 4, CRC32_IEEE.checksum(b"imag"))
+                .map(|h| h.image_len),
+            Ok(4)
+        );
+        assert_eq!(
+            ota.finish_update(slot, LAYOUT.max_image_len() + 1, crc),
+            Err(OtaError::ImageTooLarge)
+        );
+    }
+
+    #[test]
+    fn update_never_erases_running_slot() {
+        let mut ota = ota();
+        assert_eq!(update(&mut ota, b"v1"), Slot::A);
+        assert_eq!(
+            ota.select_boot().unwrap().map(|(slot, _)| slot),
+            Some(Slot::A)
+        );
+        ota.confirm(Slot::A).unwrap();
+
+        assert_eq!(update(&mut ota, b"v2"), Slot::B);
+        let (slot, header) = ota.select_boot().unwrap().unwrap();
+        assert_eq!((slot, header.sequence), (Slot::B, 2));
+        assert_eq!(state(&mut ota, Slot::B), SlotState::Trying);
+
+        // Running unconfirmed from B, the update must go to A.
+        assert_eq!(ota.running_slot().unwrap(), Some(Slot::B));
+        assert_eq!(update(&mut ota, b"v3"), Slot::A);
+        ota.verify(Slot::B).unwrap();
+    }
+
+    #[test]
+    fn unconfirmed_image_falls_back() {
+        let mut ota = ota();
+        update(&mut ota, b"v1");
+        ota.select_boot().unwrap();
+        ota.confirm(Slot::A).unwrap();
+        update(&mut ota, b"v2");
+        ota.select_boot().unwrap();
+
+        // B never confirmed: the next boot drops it and boots A again.
+        assert_eq!(
+            ota.select_boot().unwrap().map(|(slot, _)| slot),
+            Some(Slot::A)
+        );
+        assert_eq!(state(&mut ota, Slot::B), SlotState::Invalid);
+        assert_eq!(ota.running_slot().unwrap(), Some(Slot::A));
+        assert_eq!(update(&mut ota, b"v3"), Slot::B);
+        assert_eq!(ota.header(Slot::B).unwrap().unwrap().sequence, 2);
+        ota.verify(Slot::A).unwrap();
+    }
+}
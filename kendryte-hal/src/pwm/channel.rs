@@ -1,6 +1,5 @@
-use core::convert::Infallible;
-
-use super::driver::Pwm;
+use super::driver::{CMP_NEVER, Pwm, PwmError, duty_threshold};
+use super::register::{Alignment, Enable};
 
 // There are only 3 channels used (4 in total) so we define each as a separate struct.
 /// PWM channel 1 (uses comparator 1)
@@ -18,26 +17,117 @@ pub struct Ch3<'a, 'i> {
 
 macro_rules! impl_channel {
     ($Ty:ident, $idx:expr) => {
+        impl<'a, 'i> $Ty<'a, 'i> {
+            /// The PWM block of the channel, to start and stop its counter
+            /// or change the period and prescaler shared by all channels.
+            #[inline]
+            pub fn pwm(&self) -> &'a Pwm<'i> {
+                self.pwm
+            }
+
+            /// Enable the channel output, restoring the last duty cycle.
+            ///
+            /// Other channels and the shared counter are not affected.
+            #[inline]
+            pub fn enable(&mut self) {
+                let disabled = self.pwm.disabled.get() & !(1 << $idx);
+                self.pwm.disabled.set(disabled);
+                let value = self.pwm.duty[$idx].get();
+                if self.pwm.raw.get() & (1 << $idx) == 0 {
+                    self.write_duty(value);
+                } else {
+                    self.pwm.write_cmp($idx, value as u32);
+                }
+            }
+
+            /// Disable the channel output, holding it low.
+            ///
+            /// The duty cycle is remembered and restored by [`Self::enable`];
+            /// other channels keep running.
+            #[inline]
+            pub fn disable(&mut self) {
+                let disabled = self.pwm.disabled.get() | (1 << $idx);
+                self.pwm.disabled.set(disabled);
+                self.pwm.write_cmp($idx, CMP_NEVER);
+            }
+
+            /// Returns true if the channel output is enabled.
+            #[inline]
+            pub fn is_enabled(&self) -> bool {
+                self.pwm.disabled.get() & (1 << $idx) == 0
+            }
+
+            /// Set left or center alignment of this channel's output.
+            #[inline]
+            pub fn set_alignment(&mut self, alignment: Alignment) {
+                self.pwm.set_cmp_alignment($idx, alignment);
+            }
+
+            /// Gang this channel's comparator with the next-highest comparator.
+            ///
+            /// While ganged, the output rises when this comparator fires and
+            /// falls when the next one does (comparator 0 for channel 3), so
+            /// pulses can be placed anywhere within the period with
+            /// [`Self::set_compare`].
+            #[inline]
+            pub fn set_gang(&mut self, enable: bool) {
+                let gang = if enable {
+                    Enable::Enabled
+                } else {
+                    Enable::Disabled
+                };
+                self.pwm.set_cmp_gang($idx, gang);
+            }
+
+            /// Write the raw comparator value, bypassing duty cycle conversion.
+            ///
+            /// The value is kept as is across period changes until the next
+            /// duty cycle is set.
+            #[inline]
+            pub fn set_compare(&mut self, value: u16) {
+                self.pwm.raw.set(self.pwm.raw.get() | (1 << $idx));
+                self.pwm.duty[$idx].set(value);
+                if self.is_enabled() {
+                    self.pwm.write_cmp($idx, value as u32);
+                }
+            }
+
+            /// Current duty cycle, out of [`max_duty_cycle`](embedded_hal::pwm::SetDutyCycle::max_duty_cycle).
+            ///
+            /// After [`Self::set_compare`] this is the raw comparator value instead.
+            #[inline]
+            pub fn duty_cycle(&self) -> u16 {
+                self.pwm.duty[$idx].get()
+            }
+
+            #[inline]
+            fn write_duty(&self, duty: u16) {
+                self.pwm
+                    .write_cmp($idx, duty_threshold(self.pwm.top(), duty));
+            }
+        }
+
         impl<'a, 'i> embedded_hal::pwm::ErrorType for $Ty<'a, 'i> {
-            type Error = Infallible;
+            type Error = PwmError;
         }
         impl<'a, 'i> embedded_hal::pwm::SetDutyCycle for $Ty<'a, 'i> {
+            /// Returns the current period; never 0, as embedded-hal requires.
             #[inline]
             fn max_duty_cycle(&self) -> u16 {
-                self.pwm.top()
+                self.pwm.top().max(1)
             }
 
             #[inline]
             fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Self::Error> {
-                let top = self.max_duty_cycle();
+                let top = self.pwm.top();
+                if top == 0 {
+                    return Err(PwmError::PeriodNotSet);
+                }
                 let duty = duty.min(top);
-                // Comparator outputs high when pwms >= cmpN.
-                // For left-aligned PWM with top set in cmp0, a high width of `duty`
-                // can be achieved by setting threshold = top - duty.
-                let threshold = (top - duty) as u32;
-                unsafe {
-                    self.pwm.inner.pwm_cmpn[$idx]
-                        .modify(|r| r.with_pwm_cpmn(arbitrary_int::u31::new(threshold)));
+                self.pwm.duty[$idx].set(duty);
+                self.pwm.raw.set(self.pwm.raw.get() & !(1 << $idx));
+                if self.is_enabled() {
+                    self.write_duty(duty);
                 }
                 Ok(())
             }
@@ -1,28 +1,71 @@
 use crate::instance::Instance;
-use core::convert::Infallible;
 
 use super::channel::{Ch1, Ch2, Ch3};
-use super::register::RegisterBlock;
+use super::register::{Alignment, Enable, RegisterBlock};
+
+/// Comparator value that the 16-bit scaled counter never reaches, holding the output low.
+pub(crate) const CMP_NEVER: u32 = 0x7FFF_FFFF;
+
+/// Maximum polling iterations while waiting for the counter to wrap.
+const MAX_ITERATIONS: u32 = 1_000_000;
+
+/// Error type for PWM channel operations.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum PwmError {
+    /// The period has not been set, so duty cycles cannot be converted to compare values.
+    PeriodNotSet,
+    /// The requested frequency cannot be reached from the PWM clock.
+    FrequencyOutOfRange,
+}
+
+impl embedded_hal::pwm::Error for PwmError {
+    fn kind(&self) -> embedded_hal::pwm::ErrorKind {
+        crate::Error::from(*self).kind().into()
+    }
+}
 
 /// PWM peripheral abstraction.
 ///
 /// This wraps a [`RegisterBlock`] and provides a safe(ish) API plus
 /// an embedded-hal implementation for channels 1-3.
+///
+/// [`split`](Self::split) borrows the driver exclusively, so each comparator
+/// has a single channel handle. The channels share the driver among
+/// themselves, and the counter can be started, stopped and given a new
+/// period or prescaler through [`Ch1::pwm`] and its siblings while they stay
+/// in use.
 pub struct Pwm<'i> {
     pub(crate) inner: &'static RegisterBlock,
     pub(crate) top: core::cell::Cell<u16>,
+    /// Last duty cycle set on each comparator, restored when a channel is re-enabled.
+    pub(crate) duty: [core::cell::Cell<u16>; 4],
+    /// Bit mask of disabled channels, indexed by comparator.
+    pub(crate) disabled: core::cell::Cell<u8>,
+    /// Bit mask of channels driven by raw compare values instead of a duty cycle.
+    pub(crate) raw: core::cell::Cell<u8>,
     _marker: core::marker::PhantomData<&'i ()>,
 }
 
 impl<'i> Pwm<'i> {
     /// Create a new PWM driver from a static register block reference.
     ///
-    /// Safety: `inner` must point to the PWM peripheral's memory-mapped registers.
+    /// Safety: `inner` must point to the PWM peripheral's memory-mapped
+    /// registers, and no other driver or token may use them while this one
+    /// exists.
     #[inline]
     pub const unsafe fn from_raw(inner: &'static RegisterBlock) -> Self {
         Self {
             inner,
             top: core::cell::Cell::new(0),
+            duty: [
+                core::cell::Cell::new(0),
+                core::cell::Cell::new(0),
+                core::cell::Cell::new(0),
+                core::cell::Cell::new(0),
+            ],
+            disabled: core::cell::Cell::new(0),
+            raw: core::cell::Cell::new(0),
             _marker: core::marker::PhantomData,
         }
     }
@@ -35,7 +78,7 @@ impl<'i> Pwm<'i> {
 
     /// Construct from a peripheral instance that implements [`Instance`].
     #[inline]
-    pub fn new<'a>(instance: impl Instance<'a, R = RegisterBlock>) -> Self {
+    pub fn new(instance: impl Instance<'i, R = &'static RegisterBlock>) -> Self {
         // Safe because Instance::inner yields a &'static to the MMIO block defined by SoC.
         unsafe { Self::from_raw(instance.inner()) }
     }
@@ -50,7 +93,7 @@ impl<'i> Pwm<'i> {
     /// - gang = Disabled for all channels
     pub fn reset_config(&mut self) {
         unsafe {
-            self.inner.pwm_cfg.modify(|r| {
+            modify_reg!(self.inner, pwm_cfg, |r| {
                 r.with_pwm_scale(arbitrary_int::u4::new(0))
                     .with_pwm_sticky(super::register::StickyMode::AutoClear)
                     .with_pwm_zero_cmp(super::register::Enable::Enabled)
@@ -70,39 +113,87 @@ impl<'i> Pwm<'i> {
     }
 
     /// Set prescaler (0..=15). Each increment divides by 2^n before compare.
-    pub fn set_scale(&mut self, scale: u8) {
+    pub fn set_scale(&self, scale: u8) {
         let s = if scale > 15 { 15 } else { scale };
         unsafe {
-            self.inner
-                .pwm_cfg
-                .modify(|r| r.with_pwm_scale(arbitrary_int::u4::new(s)));
+            modify_reg!(self.inner, pwm_cfg, |r| r
+                .with_pwm_scale(arbitrary_int::u4::new(s)));
         }
     }
 
     /// Set period (top) via comparator 0 when zero-compare mode is enabled.
     /// This value also becomes the embedded-hal max_duty for channels.
-    pub fn set_period(&mut self, top: u16) {
-        self.top.set(top);
-        unsafe {
-            self.inner.pwm_cmpn[0].modify(|r| r.with_pwm_cpmn(arbitrary_int::u31::new(top as u32)));
+    ///
+    /// Channel duty cycles are rescaled to keep their fraction of the period.
+    pub fn set_period(&self, top: u16) {
+        let old = self.top.replace(top);
+        self.write_cmp(0, top as u32);
+        self.rescale_duty(old, top);
+    }
+
+    /// Change prescaler and period together at the start of a PWM cycle.
+    ///
+    /// Waits for the counter to wrap before writing the new values, so the
+    /// running cycle finishes with the old settings. Channel duty cycles keep
+    /// their fraction of the period. Combine with [`Self::set_deglitch`] to
+    /// keep outputs from pulsing twice while the update lands.
+    pub fn set_scale_and_period(&self, scale: u8, top: u16) {
+        if read_reg!(self.inner, pwm_cfg).pwm_en_always() == Enable::Enabled {
+            let mut last = read_reg!(self.inner, pwms).pwms();
+            let mut iterations = 0;
+            while iterations < MAX_ITERATIONS {
+                let now = read_reg!(self.inner, pwms).pwms();
+                if now < last {
+                    break;
+                }
+                last = now;
+                iterations += 1;
+                core::hint::spin_loop();
+            }
         }
+        self.set_scale(scale);
+        self.set_period(top);
     }
 
     /// Start free-running counter.
-    pub fn start(&mut self) {
+    pub fn start(&self) {
         unsafe {
-            self.inner
-                .pwm_cfg
-                .modify(|r| r.with_pwm_en_always(super::register::Enable::Enabled));
+            modify_reg!(self.inner, pwm_cfg, |r| r
+                .with_pwm_en_always(super::register::Enable::Enabled));
         }
     }
 
     /// Stop counter.
-    pub fn stop(&mut self) {
+    pub fn stop(&self) {
         unsafe {
-            self.inner
-                .pwm_cfg
-                .modify(|r| r.with_pwm_en_always(super::register::Enable::Disabled));
+            modify_reg!(self.inner, pwm_cfg, |r| r
+                .with_pwm_en_always(super::register::Enable::Disabled));
+        }
+    }
+
+    /// Run the counter for a single PWM cycle.
+    ///
+    /// The hardware clears the one-shot enable when the cycle completes,
+    /// so this can be called again to emit another pulse.
+    pub fn start_oneshot(&self) {
+        unsafe {
+            modify_reg!(self.inner, pwm_cfg, |r| r
+                .with_pwm_en_oneshot(Enable::Enabled));
+        }
+    }
+
+    /// Enable or disable output deglitching.
+    ///
+    /// When enabled, each output can only go high once per PWM cycle, which
+    /// avoids glitches while comparator values are being updated.
+    pub fn set_deglitch(&self, enable: bool) {
+        let deglitch = if enable {
+            Enable::Enabled
+        } else {
+            Enable::Disabled
+        };
+        unsafe {
+            modify_reg!(self.inner, pwm_cfg, |r| r.with_pwm_deglitch(deglitch));
         }
     }
 
@@ -112,9 +203,92 @@ impl<'i> Pwm<'i> {
         self.top.get()
     }
 
+    /// Set alignment of comparator `idx` (0..=3).
+    pub(crate) fn set_cmp_alignment(&self, idx: usize, alignment: Alignment) {
+        unsafe {
+            modify_reg!(self.inner, pwm_cfg, |r| match idx {
+                0 => r.with_pwm_cmp0_center(alignment),
+                1 => r.with_pwm_cmp1_center(alignment),
+                2 => r.with_pwm_cmp2_center(alignment),
+                _ => r.with_pwm_cmp3_center(alignment),
+            });
+        }
+    }
+
+    /// Gang comparator `idx` (0..=3) with its next-highest neighbour.
+    pub(crate) fn set_cmp_gang(&self, idx: usize, gang: Enable) {
+        unsafe {
+            modify_reg!(self.inner, pwm_cfg, |r| match idx {
+                0 => r.with_pwm_cmp0_gang(gang),
+                1 => r.with_pwm_cmp1_gang(gang),
+                2 => r.with_pwm_cmp2_gang(gang),
+                _ => r.with_pwm_cmp3_gang(gang),
+            });
+        }
+    }
+
+    /// Rescale stored duty cycles from period `old` to `top` and rewrite enabled channels.
+    fn rescale_duty(&self, old: u16, top: u16) {
+        for idx in 1..4 {
+            let mask = 1 << idx;
+            if self.raw.get() & mask != 0 {
+                continue;
+            }
+            let duty = match old {
+                0 => self.duty[idx].get(),
+                old => (self.duty[idx].get() as u32 * top as u32 / old as u32) as u16,
+            };
+            let duty = duty.min(top);
+            self.duty[idx].set(duty);
+            if self.disabled.get() & mask == 0 {
+                self.write_cmp(idx, duty_threshold(top, duty));
+            }
+        }
+    }
+
+    /// Write raw value of comparator `idx` (0..=3).
+    pub(crate) fn write_cmp(&self, idx: usize, value: u32) {
+        unsafe {
+            modify_reg!(self.inner, pwm_cmpn[idx], |r| r
+                .with_pwm_cpmn(arbitrary_int::u31::new(value)));
+        }
+    }
+
     /// Split into three channels (1,2,3). Comparator 0 is reserved for period/top.
+    ///
+    /// The driver stays borrowed while any handle exists, so the block
+    /// cannot be split twice. The handles stay valid across period and
+    /// prescaler changes made through [`Ch1::pwm`], which rescale their duty
+    /// cycles.
     #[inline]
     pub fn split(&mut self) -> (Ch1<'_, 'i>, Ch2<'_, 'i>, Ch3<'_, 'i>) {
-        (Ch1 { pwm: self }, Ch2 { pwm: self }, Ch3 { pwm: self })
+        let pwm = &*self;
+        (Ch1 { pwm }, Ch2 { pwm }, Ch3 { pwm })
+    }
+}
+
+impl Pwm<'_> {
+    /// Stop the counter and restore the reset configuration.
+    ///
+    /// PWM output pads are not owned by this driver; once the PWM is freed
+    /// they can be reconfigured for another function. A driver created from
+    /// a borrowed token ends the borrow here.
+    pub fn free(mut self) {
+        self.stop();
+        self.reset_config();
+    }
+}
+
+/// Comparator threshold producing a high time of `duty` out of period `top`.
+///
+/// Comparator outputs high when pwms >= cmpN. For left-aligned PWM with top
+/// set in cmp0, a high width of `duty` can be achieved by setting
+/// threshold = top - duty. A duty of 0 parks the comparator out of range so
+/// the output stays fully low.
+#[inline]
+pub(crate) fn duty_threshold(top: u16, duty: u16) -> u32 {
+    match duty {
+        0 => CMP_NEVER,
+        duty => top.saturating_sub(duty) as u32,
     }
 }
@@ -1,9 +1,20 @@
+crate::trace::traced_registers!($ "pwm", crate::pwm::RegisterBlock);
+
 mod channel;
 mod driver;
+pub mod motor;
 pub mod pad;
 mod register;
+pub mod servo;
+mod timer;
 
 pub use channel::{Ch1, Ch2, Ch3};
-pub use driver::Pwm;
+pub(crate) use driver::CMP_NEVER;
+pub use driver::{Pwm, PwmError};
 pub use embedded_hal::pwm::SetDutyCycle;
+pub use motor::{Complementary, HBridge};
 pub use register::*;
+pub use servo::{Servo, ServoConfig};
+pub use timer::{PwmTimer, PwmTimerState};
+
+crate::trace::impl_raw!(PwmCfg, PwmCount, Pwms, PwmCmpn);
@@ -0,0 +1,284 @@
+//! DC motors on an H-bridge.
+//!
+//! Bridge drivers such as the DRV8833 or L298N take two inputs per motor:
+//! pulsing one while holding the other low turns the motor one way, swapping
+//! them turns it the other way. [`HBridge`] drives the two inputs from two
+//! PWM channels and inserts a dead time whenever the direction reverses, so
+//! the bridge never sees both inputs switching at once.
+//!
+//! Half bridges with separate high-side and low-side gate inputs need the
+//! two driven as complements, with a dead band around every edge so both
+//! transistors are never on together. [`Complementary`] generates such a
+//! pair from the channels of one PWM block.
+
+use super::driver::{Pwm, PwmError};
+use super::{Ch1, Ch2, Ch3};
+use crate::time::Timeout;
+use embedded_hal::pwm::SetDutyCycle;
+
+/// Direction of rotation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Input A pulsed, input B low.
+    Forward,
+    /// Input B pulsed, input A low.
+    Reverse,
+}
+
+/// Motor driven through two H-bridge inputs.
+///
+/// Both channels should belong to the same PWM block, so they share one
+/// frequency; 20 kHz keeps the switching inaudible.
+pub struct HBridge<A, B> {
+    a: A,
+    b: B,
+    dead_time_us: u32,
+    direction: Option<Direction>,
+}
+
+impl<A, B, E> HBridge<A, B>
+where
+    A: SetDutyCycle<Error = E>,
+    B: SetDutyCycle<Error = E>,
+{
+    /// Creates a motor on inputs `a` and `b` and lets it coast.
+    ///
+    /// `dead_time_us` is how long both inputs are held low when the
+    /// direction reverses.
+    pub fn new(a: A, b: B, dead_time_us: u32) -> Result<Self, E> {
+        let mut bridge = Self {
+            a,
+            b,
+            dead_time_us,
+            direction: None,
+        };
+        bridge.coast()?;
+        Ok(bridge)
+    }
+
+    /// Drives the motor in `direction` with `duty` out of
+    /// [`max_duty_cycle`](Self::max_duty_cycle).
+    pub fn drive(&mut self, direction: Direction, duty: u16) -> Result<(), E> {
+        if self.direction.is_some_and(|d| d != direction) {
+            self.coast()?;
+            let dead_time = Timeout::from_micros(self.dead_time_us);
+            while !dead_time.is_expired() {
+                core::hint::spin_loop();
+            }
+        }
+        match direction {
+            Direction::Forward => {
+                self.b.set_duty_cycle_fully_off()?;
+                self.a.set_duty_cycle(duty.min(self.a.max_duty_cycle()))?;
+            }
+            Direction::Reverse => {
+                self.a.set_duty_cycle_fully_off()?;
+                self.b.set_duty_cycle(duty.min(self.b.max_duty_cycle()))?;
+            }
+        }
+        self.direction = Some(direction);
+        Ok(())
+    }
+
+    /// Drives the motor at `speed` percent, negative for reverse.
+    ///
+    /// Values beyond ±100 are clamped.
+    pub fn set_speed_percent(&mut self, speed: i8) -> Result<(), E> {
+        let direction = if speed < 0 {
+            Direction::Reverse
+        } else {
+            Direction::Forward
+        };
+        let percent = speed.unsigned_abs().min(100) as u32;
+        let duty = self.max_duty_cycle() as u32 * percent / 100;
+        self.drive(direction, duty as u16)
+    }
+
+    /// Full scale duty cycle of the two inputs.
+    pub fn max_duty_cycle(&self) -> u16 {
+        self.a.max_duty_cycle().min(self.b.max_duty_cycle())
+    }
+
+    /// Holds both inputs low, letting the motor spin freely.
+    pub fn coast(&mut self) -> Result<(), E> {
+        self.a.set_duty_cycle_fully_off()?;
+        self.b.set_duty_cycle_fully_off()?;
+        self.direction = None;
+        Ok(())
+    }
+
+    /// Holds both inputs high, shorting the motor windings to stop it.
+    pub fn brake(&mut self) -> Result<(), E> {
+        self.a.set_duty_cycle_fully_on()?;
+        self.b.set_duty_cycle_fully_on()?;
+        self.direction = None;
+        Ok(())
+    }
+
+    /// Current direction, or `None` while coasting or braking.
+    pub fn direction(&self) -> Option<Direction> {
+        self.direction
+    }
+
+    /// Returns the two channels.
+    pub fn free(self) -> (A, B) {
+        (self.a, self.b)
+    }
+}
+
+/// Complementary outputs with a dead band, from one PWM block.
+///
+/// Channel 3 drives the high side, high for the last `duty` counts of each
+/// period. Channel 1, ganged with comparator 2, drives the low side: it
+/// rises `dead_counts` after the high side falls at the end of the period
+/// and falls `dead_counts` before the high side rises. The output of
+/// channel 2 is not used. Counts are periods of the prescaled PWM clock.
+pub struct Complementary<'a, 'i> {
+    low: Ch1<'a, 'i>,
+    low_fall: Ch2<'a, 'i>,
+    high: Ch3<'a, 'i>,
+    dead_counts: u16,
+    duty: u16,
+}
+
+impl<'a, 'i> Complementary<'a, 'i> {
+    /// Takes the channels of `pwm` and holds both outputs low.
+    ///
+    /// The period must be set before a duty cycle.
+    pub fn new(pwm: &'a mut Pwm<'i>, dead_counts: u16) -> Self {
+        let (mut low, low_fall, mut high) = pwm.split();
+        low.set_gang(true);
+        high.set_gang(false);
+        low.disable();
+        high.disable();
+        Self {
+            low,
+            low_fall,
+            high,
+            dead_counts,
+            duty: 0,
+        }
+    }
+
+    /// Changes the dead band, in counts, and reapplies the duty cycle.
+    pub fn set_dead_counts(&mut self, dead_counts: u16) -> Result<(), PwmError> {
+        self.dead_counts = dead_counts;
+        self.set_duty_cycle(self.duty)
+    }
+
+    /// Current high-side duty cycle.
+    pub fn duty(&self) -> u16 {
+        self.duty
+    }
+}
+
+impl embedded_hal::pwm::ErrorType for Complementary<'_, '_> {
+    type Error = PwmError;
+}
+
+impl SetDutyCycle for Complementary<'_, '_> {
+    /// Period of the PWM block; never 0, as embedded-hal requires.
+    fn max_duty_cycle(&self) -> u16 {
+        self.high.max_duty_cycle()
+    }
+
+    /// Sets the high-side duty cycle; the low side gets the rest of the
+    /// period minus the dead band on both edges.
+    fn set_duty_cycle(&mut self, duty: u16) -> Result<(), PwmError> {
+        let top = self.high.pwm.top();
+        if top == 0 {
+            return Err(PwmError::PeriodNotSet);
+        }
+        let (high, low) = complementary_compares(top, duty, self.dead_counts);
+        // Hold both outputs low while the comparators change, so a half
+        // written update never turns both on.
+        self.low.disable();
+        self.high.disable();
+        if let Some(threshold) = high {
+            self.high.set_compare(threshold);
+            self.high.enable();
+        }
+        if let Some((rise, fall)) = low {
+            self.low_fall.set_compare(fall);
+            self.low.set_compare(rise);
+            self.low.enable();
+        }
+        self.duty = duty.min(top);
+        Ok(())
+    }
+}
+
+/// Comparator values of a complementary pair with period `top`.
+///
+/// Returns the high-side threshold and the rise and fall of the ganged low
+/// side, with `None` for a side held low.
+fn complementary_compares(top: u16, duty: u16, dead: u16) -> (Option<u16>, Option<(u16, u16)>) {
+    let duty = duty.min(top);
+    let high = (duty != 0).then(|| top - duty);
+    let fall = (top - duty).saturating_sub(dead);
+    let low = (dead < fall).then_some((dead, fall));
+    (high, low)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+    use core::convert::Infallible;
+
+    struct Channel<'a>(&'a Cell<u16>);
+
+    impl embedded_hal::pwm::ErrorType for Channel<'_> {
+        type Error = Infallible;
+    }
+
+    impl SetDutyCycle for Channel<'_> {
+        fn max_duty_cycle(&self) -> u16 {
+            100
+        }
+
+        fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Infallible> {
+            self.0.set(duty);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn hbridge_drives_one_input_at_a_time() {
+        let (a, b) = (Cell::new(7), Cell::new(7));
+        let mut motor = HBridge::new(Channel(&a), Channel(&b), 0).unwrap();
+        assert_eq!((a.get(), b.get()), (0, 0));
+        assert_eq!(motor.direction(), None);
+
+        motor.set_speed_percent(60).unwrap();
+        assert_eq!((a.get(), b.get()), (60, 0));
+        assert_eq!(motor.direction(), Some(Direction::Forward));
+
+        motor.set_speed_percent(-120).unwrap();
+        assert_eq!((a.get(), b.get()), (0, 100));
+        assert_eq!(motor.direction(), Some(Direction::Reverse));
+
+        motor.brake().unwrap();
+        assert_eq!((a.get(), b.get()), (100, 100));
+        assert_eq!(motor.direction(), None);
+    }
+
+    #[test]
+    fn complementary_keeps_dead_band() {
+        // High side for the last 40 counts; low side from 5 to 55.
+        assert_eq!(
+            complementary_compares(100, 40, 5),
+            (Some(60), Some((5, 55)))
+        );
+        // Without a dead band the low side covers the rest of the period.
+        assert_eq!(
+            complementary_compares(100, 40, 0),
+            (Some(60), Some((0, 60)))
+        );
+        // Off: only the low side switches.
+        assert_eq!(complementary_compares(100, 0, 5), (None, Some((5, 95))));
+        // Too little room for the low side between the dead bands.
+        assert_eq!(complementary_compares(100, 90, 5), (Some(10), None));
+        assert_eq!(complementary_compares(100, 200, 5), (Some(0), None));
+    }
+}
@@ -0,0 +1,196 @@
+//! Hobby servos and PWM frequency selection.
+//!
+//! A standard servo expects a pulse every 20 ms (50 Hz) whose width, usually
+//! between 1 ms and 2 ms, sets the horn position. [`Pwm::set_frequency`]
+//! picks the prescaler and period for a target output frequency, and
+//! [`Servo`] turns angles into pulse widths on a channel.
+
+use super::driver::{Pwm, PwmError};
+use embedded_hal::pwm::SetDutyCycle;
+
+/// Largest prescaler exponent of the PWM block.
+const MAX_SCALE: u8 = 15;
+
+/// Prescaler exponent and period giving the closest output frequency to
+/// `frequency`, for a PWM block clocked at `pwm_clock` hertz.
+///
+/// The smallest prescaler whose period fits the 16-bit comparator is used,
+/// which gives the finest duty cycle resolution. Returns `None` if the
+/// frequency is zero, above half the PWM clock, or too low to reach with the
+/// largest prescaler.
+pub fn scale_and_period(pwm_clock: u32, frequency: u32) -> Option<(u8, u16)> {
+    if frequency == 0 {
+        return None;
+    }
+    (0..=MAX_SCALE).find_map(|scale| {
+        let counts = pwm_clock as u64 / ((frequency as u64) << scale);
+        if counts < 2 {
+            return None;
+        }
+        u16::try_from(counts - 1).ok().map(|top| (scale, top))
+    })
+}
+
+/// Output frequency of a PWM block clocked at `pwm_clock` hertz with
+/// prescaler exponent `scale` and period `top`.
+pub const fn frequency(pwm_clock: u32, scale: u8, top: u16) -> u32 {
+    ((pwm_clock as u64) / ((top as u64 + 1) << scale)) as u32
+}
+
+impl<'i> Pwm<'i> {
+    /// Set the output frequency, picking prescaler and period with
+    /// [`scale_and_period`], and return the frequency actually reached.
+    ///
+    /// `pwm_clock` is the frequency of the clock feeding the PWM block.
+    /// The change lands at the start of a PWM cycle, see
+    /// [`set_scale_and_period`](Self::set_scale_and_period).
+    pub fn set_frequency(&self, pwm_clock: u32, frequency: u32) -> Result<u32, PwmError> {
+        let (scale, top) =
+            scale_and_period(pwm_clock, frequency).ok_or(PwmError::FrequencyOutOfRange)?;
+        self.set_scale_and_period(scale, top);
+        Ok(self::frequency(pwm_clock, scale, top))
+    }
+}
+
+/// Pulse timing of a servo.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ServoConfig {
+    /// Pulse repetition frequency in hertz.
+    pub frequency: u32,
+    /// Pulse width at angle 0, in microseconds.
+    pub min_pulse_us: u32,
+    /// Pulse width at [`max_angle`](Self::max_angle), in microseconds.
+    pub max_pulse_us: u32,
+    /// Largest angle in degrees.
+    pub max_angle: u16,
+}
+
+impl Default for ServoConfig {
+    /// Standard 50 Hz servo, 1 ms to 2 ms over 180 degrees.
+    fn default() -> Self {
+        Self {
+            frequency: 50,
+            min_pulse_us: 1_000,
+            max_pulse_us: 2_000,
+            max_angle: 180,
+        }
+    }
+}
+
+impl ServoConfig {
+    /// Sets the pulse repetition frequency.
+    pub fn set_frequency(mut self, frequency: u32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Sets the pulse widths at both ends of the travel.
+    ///
+    /// Many servos travel further than the standard range, commonly from
+    /// 500 µs to 2500 µs.
+    pub fn set_pulse_range(mut self, min_pulse_us: u32, max_pulse_us: u32) -> Self {
+        self.min_pulse_us = min_pulse_us;
+        self.max_pulse_us = max_pulse_us;
+        self
+    }
+
+    /// Sets the largest angle.
+    pub fn set_max_angle(mut self, max_angle: u16) -> Self {
+        self.max_angle = max_angle.max(1);
+        self
+    }
+
+    /// Pulse width in microseconds for `angle` degrees, clamped to the travel.
+    ///
+    /// A `max_angle` of 0 is treated as 1.
+    pub fn pulse_us(&self, angle: u16) -> u32 {
+        let max_angle = self.max_angle.max(1);
+        let angle = angle.min(max_angle) as u32;
+        let span = self.max_pulse_us.saturating_sub(self.min_pulse_us);
+        self.min_pulse_us + span * angle / max_angle as u32
+    }
+}
+
+/// Servo driven by one PWM channel.
+///
+/// The PWM block must run at the servo's pulse frequency; use
+/// [`Pwm::set_frequency`] with [`ServoConfig::frequency`] before creating
+/// the servo.
+pub struct Servo<C> {
+    channel: C,
+    config: ServoConfig,
+    angle: Option<u16>,
+}
+
+impl<C: SetDutyCycle> Servo<C> {
+    /// Creates a servo on `channel`. The output is not changed until a
+    /// position is set.
+    pub fn new(channel: C, config: ServoConfig) -> Self {
+        Self {
+            channel,
+            config,
+            angle: None,
+        }
+    }
+
+    /// Moves to `angle` degrees, clamped to [`ServoConfig::max_angle`].
+    pub fn set_angle(&mut self, angle: u16) -> Result<(), C::Error> {
+        let angle = angle.min(self.config.max_angle.max(1));
+        self.set_pulse_us(self.config.pulse_us(angle))?;
+        self.angle = Some(angle);
+        Ok(())
+    }
+
+    /// Last angle set with [`set_angle`](Self::set_angle).
+    pub fn angle(&self) -> Option<u16> {
+        self.angle
+    }
+
+    /// Outputs pulses of `pulse_us` microseconds.
+    pub fn set_pulse_us(&mut self, pulse_us: u32) -> Result<(), C::Error> {
+        let period_us = 1_000_000 / self.config.frequency.max(1);
+        let max = self.channel.max_duty_cycle() as u64;
+        let duty = (pulse_us as u64 * max / period_us as u64).min(max);
+        self.angle = None;
+        self.channel.set_duty_cycle(duty as u16)
+    }
+
+    /// Stops the pulses; most servos then stop holding their position.
+    pub fn detach(&mut self) -> Result<(), C::Error> {
+        self.angle = None;
+        self.channel.set_duty_cycle_fully_off()
+    }
+
+    /// Returns the channel.
+    pub fn free(self) -> C {
+        self.channel
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale_and_period() {
+        // 50 Hz from 100 MHz needs a prescaler: 2^5 * 62500 counts.
+        assert_eq!(scale_and_period(100_000_000, 50), Some((5, 62_499)));
+        assert_eq!(frequency(100_000_000, 5, 62_499), 50);
+        assert_eq!(scale_and_period(100_000_000, 20_000), Some((0, 4_999)));
+        assert_eq!(scale_and_period(100_000_000, 0), None);
+        assert_eq!(scale_and_period(100_000_000, 60_000_000), None);
+
+        let config = ServoConfig::default();
+        assert_eq!(config.pulse_us(0), 1_000);
+        assert_eq!(config.pulse_us(90), 1_500);
+        assert_eq!(config.pulse_us(270), 2_000);
+
+        // A zero largest angle set directly does not divide by zero.
+        let config = ServoConfig {
+            max_angle: 0,
+            ..config
+        };
+        assert_eq!(config.pulse_us(0), 1_000);
+        assert_eq!(config.pulse_us(90), 2_000);
+    }
+}
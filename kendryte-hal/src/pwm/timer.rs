@@ -0,0 +1,169 @@
+use core::future::poll_fn;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::Poll;
+
+use atomic_waker::AtomicWaker;
+
+use super::driver::Pwm;
+use super::register::{InterruptPending, RegisterBlock, StickyMode};
+
+/// PWM block used as a periodic timer.
+///
+/// The counter runs from 0 to the period in comparator 0 and then resets,
+/// raising the comparator 0 interrupt pending bit once per period. The bit is
+/// sticky and routed to the interrupt controller until cleared.
+pub struct PwmTimer<'i> {
+    pwm: Pwm<'i>,
+}
+
+impl<'i> Pwm<'i> {
+    /// Turn this PWM block into a periodic timer.
+    ///
+    /// The counter advances at the PWM clock divided by `2^scale` and
+    /// expires every `period + 1` counts. The timer is left stopped; call
+    /// [`PwmTimer::start`] to run it. Channel outputs are not driven while
+    /// the block is used as a timer.
+    pub fn into_timer(mut self, scale: u8, period: u16) -> PwmTimer<'i> {
+        self.stop();
+        self.reset_config();
+        unsafe {
+            modify_reg!(self.inner, pwm_cfg, |r| r
+                .with_pwm_sticky(StickyMode::ManualClear));
+        }
+        self.set_scale(scale);
+        self.set_period(period);
+        let (mut ch1, mut ch2, mut ch3) = self.split();
+        ch1.disable();
+        ch2.disable();
+        ch3.disable();
+        let mut timer = PwmTimer { pwm: self };
+        timer.clear_pending();
+        timer
+    }
+}
+
+impl<'i> PwmTimer<'i> {
+    /// Start the counter from zero.
+    pub fn start(&mut self) {
+        unsafe {
+            modify_reg!(self.pwm.inner, pwm_count, |r| r
+                .with_counter(arbitrary_int::u31::new(0)));
+        }
+        self.pwm.start();
+    }
+
+    /// Run the counter for a single period.
+    pub fn start_oneshot(&mut self) {
+        self.pwm.start_oneshot();
+    }
+
+    /// Stop the counter.
+    pub fn stop(&mut self) {
+        self.pwm.stop();
+    }
+
+    /// Change prescaler and period, taking effect from the next period.
+    pub fn set_period(&mut self, scale: u8, period: u16) {
+        self.pwm.set_scale_and_period(scale, period);
+    }
+
+    /// Returns true if the timer expired since the pending flag was last cleared.
+    #[inline]
+    pub fn is_pending(&self) -> bool {
+        read_reg!(self.pwm.inner, pwm_cfg).pwm_cmp0_ip() == InterruptPending::Pending
+    }
+
+    /// Clear the expiry pending flag, deasserting the interrupt.
+    #[inline]
+    pub fn clear_pending(&mut self) {
+        clear_pending(self.pwm.inner);
+    }
+
+    /// Wait for the timer to expire without blocking.
+    ///
+    /// Returns `Ok` and clears the pending flag once per period.
+    pub fn wait(&mut self) -> embedded_hal_nb::nb::Result<(), core::convert::Infallible> {
+        if self.is_pending() {
+            self.clear_pending();
+            Ok(())
+        } else {
+            Err(embedded_hal_nb::nb::Error::WouldBlock)
+        }
+    }
+
+    /// Wait for the timer to expire, sleeping until the interrupt fires.
+    ///
+    /// The PWM interrupt must be routed to a handler calling
+    /// [`PwmTimerState::on_interrupt`] with the same `state`.
+    pub async fn tick(&mut self, state: &PwmTimerState) {
+        poll_fn(|cx| {
+            state.waker.register(cx.waker());
+            if state.fired.swap(false, Ordering::AcqRel) || self.is_pending() {
+                self.clear_pending();
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    /// Stop the timer and return the PWM driver with its reset configuration.
+    pub fn into_pwm(mut self) -> Pwm<'i> {
+        self.stop();
+        self.clear_pending();
+        self.pwm.reset_config();
+        self.pwm
+    }
+}
+
+/// Interrupt state shared between a [`PwmTimer`] and its interrupt handler.
+///
+/// Place one in a `static` per PWM block and call [`PwmTimerState::on_interrupt`]
+/// from the block's interrupt handler, e.g. one registered with
+/// `kendryte_rt::interrupt::register`.
+pub struct PwmTimerState {
+    waker: AtomicWaker,
+    fired: AtomicBool,
+}
+
+impl PwmTimerState {
+    /// Creates a new state with no registered waker.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            waker: AtomicWaker::new(),
+            fired: AtomicBool::new(false),
+        }
+    }
+
+    /// Handles a PWM timer interrupt.
+    ///
+    /// Clears the pending flag so the interrupt is deasserted, records the
+    /// expiry and wakes the task waiting in [`PwmTimer::tick`]. Returns true
+    /// if the timer had expired, so the handler can run its own callback.
+    pub fn on_interrupt(&self, regs: &RegisterBlock) -> bool {
+        if read_reg!(regs, pwm_cfg).pwm_cmp0_ip() != InterruptPending::Pending {
+            return false;
+        }
+        clear_pending(regs);
+        self.fired.store(true, Ordering::Release);
+        self.waker.wake();
+        true
+    }
+}
+
+impl Default for PwmTimerState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Clear the comparator 0 interrupt pending bit.
+#[inline]
+fn clear_pending(regs: &RegisterBlock) {
+    unsafe {
+        modify_reg!(regs, pwm_cfg, |r| r
+            .with_pwm_cmp0_ip(InterruptPending::NotPending));
+    }
+}
@@ -0,0 +1,412 @@
+//! Register init tables for OmniVision and GalaxyCore camera sensors.
+//!
+//! Image sensors are configured over SCCB, the I2C dialect of OmniVision,
+//! by writing long lists of registers taken from the vendor reference
+//! settings. [`Sccb`] applies such a list, a `&'static [Step]` kept next to
+//! the application, instead of hundreds of raw writes in `main`. Each access
+//! is retried after a short wait on a missing acknowledge, since sensors
+//! ignore the bus for a while after a reset, and writes are optionally read
+//! back:
+//!
+//! ```ignore
+//! let mut sensor = Sccb::new(i2c, &sccb::OV5647);
+//! sensor.probe(&mut delay)?;
+//! sensor.apply(&sccb::OV5647_RESET, &mut delay)?;
+//! sensor.apply(MODE_1080P30, &mut delay)?;
+//! sensor.apply(&sccb::OV5647_STREAM_ON, &mut delay)?;
+//! ```
+//!
+//! [`Sensor`] descriptors give the bus address, register address width and
+//! chip ID of the supported parts; the mode tables depend on the lane
+//! count, link rate and frame format the capture side is set up for, and
+//! come from the vendor settings of the module in use.
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::{Error as _, ErrorKind, I2c};
+
+/// Width of the register addresses of a sensor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddressWidth {
+    /// One address byte, as on older OmniVision parts.
+    Eight,
+    /// Two address bytes, most significant first.
+    Sixteen,
+}
+
+/// Static description of a sensor model.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sensor {
+    /// Model name, for diagnostics.
+    pub name: &'static str,
+    /// 7-bit bus address.
+    pub address: u8,
+    /// Width of the register addresses.
+    pub address_width: AddressWidth,
+    /// Registers holding the high and low byte of the chip ID.
+    pub id_registers: [u16; 2],
+    /// Expected chip ID.
+    pub id: u16,
+}
+
+/// OmniVision OV5647, the 5 megapixel MIPI sensor of the CanMV-K230 camera.
+pub const OV5647: Sensor = Sensor {
+    name: "OV5647",
+    address: 0x36,
+    address_width: AddressWidth::Sixteen,
+    id_registers: [0x300A, 0x300B],
+    id: 0x5647,
+};
+
+/// GalaxyCore GC2093, a 2 megapixel MIPI sensor.
+pub const GC2093: Sensor = Sensor {
+    name: "GC2093",
+    address: 0x37,
+    address_width: AddressWidth::Sixteen,
+    id_registers: [0x03F0, 0x03F1],
+    id: 0x2093,
+};
+
+/// OmniVision OV2640, a 2 megapixel DVP sensor.
+///
+/// Its ID registers are in register bank 1; select it by writing `0x01`
+/// to `0xFF` before probing.
+pub const OV2640: Sensor = Sensor {
+    name: "OV2640",
+    address: 0x30,
+    address_width: AddressWidth::Eight,
+    id_registers: [0x0A, 0x0B],
+    id: 0x2642,
+};
+
+/// One step of an init table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// Write a register, reading it back if verification is enabled.
+    Write(u16, u8),
+    /// Write a register that does not read back what was written, such as
+    /// a self-clearing reset bit.
+    WriteOnly(u16, u8),
+    /// Wait for a number of milliseconds.
+    Delay(u16),
+}
+
+/// Software reset of the OV5647, leaving it in standby.
+pub const OV5647_RESET: [Step; 3] = [
+    Step::WriteOnly(0x0103, 0x01),
+    Step::Delay(5),
+    Step::Write(0x0100, 0x00),
+];
+
+/// Starts streaming on the OV5647.
+pub const OV5647_STREAM_ON: [Step; 1] = [Step::Write(0x0100, 0x01)];
+
+/// Stops streaming on the OV5647.
+pub const OV5647_STREAM_OFF: [Step; 1] = [Step::Write(0x0100, 0x00)];
+
+/// Errors of sensor configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SccbError<E> {
+    /// Underlying I2C error, after the retries ran out.
+    I2c(E),
+    /// The chip ID registers did not hold the expected ID.
+    IdMismatch(u16),
+    /// A register read back a different value than was written.
+    Verify {
+        /// Register address.
+        register: u16,
+        /// Value written.
+        expected: u8,
+        /// Value read back.
+        read: u8,
+    },
+}
+
+/// Sensor on an SCCB bus.
+pub struct Sccb<I> {
+    i2c: I,
+    sensor: Sensor,
+    retries: u8,
+    retry_delay_us: u32,
+    verify: bool,
+}
+
+impl<I: I2c> Sccb<I> {
+    /// Creates a handle for `sensor` on `i2c`.
+    ///
+    /// Accesses are retried three times, one millisecond apart, on a missing
+    /// acknowledge, and writes are not read back; see
+    /// [`set_retries`](Self::set_retries),
+    /// [`set_retry_delay_us`](Self::set_retry_delay_us) and
+    /// [`set_verify`](Self::set_verify).
+    pub fn new(i2c: I, sensor: &Sensor) -> Self {
+        Self {
+            i2c,
+            sensor: *sensor,
+            retries: 3,
+            retry_delay_us: 1000,
+            verify: false,
+        }
+    }
+
+    /// Returns the I2C bus.
+    #[inline]
+    pub fn free(self) -> I {
+        self.i2c
+    }
+
+    /// Sets how often an access is repeated after a missing acknowledge.
+    #[inline]
+    pub fn set_retries(&mut self, retries: u8) {
+        self.retries = retries;
+    }
+
+    /// Sets the wait before each repeated access, in microseconds.
+    #[inline]
+    pub fn set_retry_delay_us(&mut self, us: u32) {
+        self.retry_delay_us = us;
+    }
+
+    /// Enables reading back every [`Step::Write`].
+    #[inline]
+    pub fn set_verify(&mut self, verify: bool) {
+        self.verify = verify;
+    }
+
+    /// Reads the chip ID and checks it against the sensor description.
+    pub fn probe(&mut self, delay: &mut impl DelayNs) -> Result<u16, SccbError<I::Error>> {
+        let [high, low] = self.sensor.id_registers;
+        let id = u16::from_be_bytes([self.read(high, delay)?, self.read(low, delay)?]);
+        if id != self.sensor.id {
+            return Err(SccbError::IdMismatch(id));
+        }
+        Ok(id)
+    }
+
+    /// Reads a register.
+    pub fn read(
+        &mut self,
+        register: u16,
+        delay: &mut impl DelayNs,
+    ) -> Result<u8, SccbError<I::Error>> {
+        let (address, len) = self.register_address(register);
+        let mut value = [0];
+        let sensor = self.sensor.address;
+        // SCCB has no repeated start, so the address is written in a
+        // transfer of its own.
+        self.retry(delay, |i2c| {
+            i2c.write(sensor, &address[..len])?;
+            i2c.read(sensor, &mut value)
+        })?;
+        Ok(value[0])
+    }
+
+    /// Writes a register.
+    pub fn write(
+        &mut self,
+        register: u16,
+        value: u8,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), SccbError<I::Error>> {
+        let (address, len) = self.register_address(register);
+        let mut bytes = [0; 3];
+        bytes[..len].copy_from_slice(&address[..len]);
+        bytes[len] = value;
+        let sensor = self.sensor.address;
+        self.retry(delay, |i2c| i2c.write(sensor, &bytes[..len + 1]))
+    }
+
+    /// Runs the steps of an init table in order, stopping at the first
+    /// error.
+    pub fn apply(
+        &mut self,
+        table: &[Step],
+        delay: &mut impl DelayNs,
+    ) -> Result<(), SccbError<I::Error>> {
+        for step in table {
+            match *step {
+                Step::Write(register, value) => {
+                    self.write(register, value, delay)?;
+                    if self.verify {
+                        let read = self.read(register, delay)?;
+                        if read != value {
+                            return Err(SccbError::Verify {
+                                register,
+                                expected: value,
+                                read,
+                            });
+                        }
+                    }
+                }
+                Step::WriteOnly(register, value) => self.write(register, value, delay)?,
+                Step::Delay(ms) => delay.delay_ms(ms as u32),
+            }
+        }
+        Ok(())
+    }
+
+    fn register_address(&self, register: u16) -> ([u8; 2], usize) {
+        match self.sensor.address_width {
+            AddressWidth::Eight => ([register as u8, 0], 1),
+            AddressWidth::Sixteen => (register.to_be_bytes(), 2),
+        }
+    }
+
+    /// Runs `f`, repeating it after the retry delay while the sensor does
+    /// not acknowledge.
+    fn retry(
+        &mut self,
+        delay: &mut impl DelayNs,
+        mut f: impl FnMut(&mut I) -> Result<(), I::Error>,
+    ) -> Result<(), SccbError<I::Error>> {
+        let mut attempts = 0;
+        loop {
+            match f(&mut self.i2c) {
+                Ok(()) => return Ok(()),
+                Err(error)
+                    if attempts < self.retries
+                        && matches!(error.kind(), ErrorKind::NoAcknowledge(_)) =>
+                {
+                    attempts += 1;
+                    delay.delay_us(self.retry_delay_us);
+                }
+                Err(error) => return Err(SccbError::I2c(error)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use embedded_hal::i2c::{ErrorType, NoAcknowledgeSource, Operation, SevenBitAddress};
+    use std::collections::BTreeMap;
+    use std::vec::Vec;
+
+    /// Sensor with 16-bit register addresses, missing the first `naks`
+    /// transfers.
+    struct Fake {
+        registers: BTreeMap<u16, u8>,
+        pointer: u16,
+        naks: usize,
+        writes: Vec<(u16, u8)>,
+    }
+
+    impl Fake {
+        fn new(naks: usize) -> Self {
+            let registers = BTreeMap::from([(0x300A, 0x56), (0x300B, 0x47)]);
+            Self {
+                registers,
+                pointer: 0,
+                naks,
+                writes: Vec::new(),
+            }
+        }
+    }
+
+    impl ErrorType for Fake {
+        type Error = ErrorKind;
+    }
+
+    impl I2c<SevenBitAddress> for Fake {
+        fn transaction(
+            &mut self,
+            address: u8,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            assert_eq!(address, 0x36);
+            if self.naks > 0 {
+                self.naks -= 1;
+                return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+            }
+            for operation in operations {
+                match operation {
+                    Operation::Write(bytes) => {
+                        self.pointer = u16::from_be_bytes([bytes[0], bytes[1]]);
+                        if let Some(&value) = bytes.get(2) {
+                            self.writes.push((self.pointer, value));
+                            // Bit 0 of 0x0103 clears itself.
+                            if self.pointer != 0x0103 {
+                                self.registers.insert(self.pointer, value);
+                            }
+                        }
+                    }
+                    Operation::Read(buf) => {
+                        buf[0] = self.registers.get(&self.pointer).copied().unwrap_or(0);
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    struct NoDelay;
+
+    impl DelayNs for NoDelay {
+        fn delay_ns(&mut self, _: u32) {}
+    }
+
+    /// Delay that only adds up the time waited.
+    #[derive(Default)]
+    struct Elapsed(u64);
+
+    impl DelayNs for Elapsed {
+        fn delay_ns(&mut self, ns: u32) {
+            self.0 += u64::from(ns);
+        }
+    }
+
+    #[test]
+    fn probe_with_retries() {
+        let mut sensor = Sccb::new(Fake::new(2), &OV5647);
+        let mut delay = Elapsed::default();
+        assert_eq!(sensor.probe(&mut delay), Ok(0x5647));
+        // Two missed transfers, each followed by the default 1 ms wait.
+        assert_eq!(delay.0, 2_000_000);
+
+        let mut sensor = Sccb::new(Fake::new(2), &OV5647);
+        sensor.set_retry_delay_us(50);
+        let mut delay = Elapsed::default();
+        sensor.read(0x300A, &mut delay).unwrap();
+        assert_eq!(delay.0, 100_000);
+
+        let mut sensor = Sccb::new(Fake::new(4), &OV5647);
+        assert_eq!(
+            sensor.probe(&mut NoDelay),
+            Err(SccbError::I2c(ErrorKind::NoAcknowledge(
+                NoAcknowledgeSource::Address
+            )))
+        );
+
+        let gc2093 = Sensor {
+            address: 0x36,
+            ..GC2093
+        };
+        let mut sensor = Sccb::new(Fake::new(0), &gc2093);
+        assert_eq!(sensor.probe(&mut NoDelay), Err(SccbError::IdMismatch(0)));
+    }
+
+    #[test]
+    fn apply_and_verify() {
+        let mut sensor = Sccb::new(Fake::new(0), &OV5647);
+        sensor.set_verify(true);
+        sensor.apply(&OV5647_RESET, &mut NoDelay).unwrap();
+        sensor.apply(&OV5647_STREAM_ON, &mut NoDelay).unwrap();
+        assert_eq!(
+            sensor.free().writes,
+            [(0x0103, 0x01), (0x0100, 0x00), (0x0100, 0x01)]
+        );
+
+        let mut sensor = Sccb::new(Fake::new(0), &OV5647);
+        sensor.set_verify(true);
+        assert_eq!(
+            sensor.apply(&[Step::Write(0x0103, 0x01)], &mut NoDelay),
+            Err(SccbError::Verify {
+                register: 0x0103,
+                expected: 0x01,
+                read: 0x00,
+            })
+        );
+    }
+}
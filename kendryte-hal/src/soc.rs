@@ -0,0 +1,225 @@
+//! Per-chip configuration of the peripheral IP blocks.
+//!
+//! The DesignWare SSI and UART blocks are configured at synthesis time, so a
+//! register field present on one Kendryte chip may be reserved on another.
+//! Drivers consult these tables before using optional fields and return a
+//! `NotSupported` error instead of writing reserved bits.
+//!
+//! The chip is selected with one of the `k230`, `k510` or `k210` features.
+//! Without any of them the K230 configuration is used.
+
+#[cfg(any(
+    all(feature = "k230", feature = "k510"),
+    all(feature = "k230", feature = "k210"),
+    all(feature = "k510", feature = "k210"),
+))]
+compile_error!("at most one of the `k230`, `k510` and `k210` features may be enabled");
+
+use crate::clocks::ClockId;
+use crate::sysctl::{ClockDivider, ClockGate, GateRegister};
+
+/// Frequency of the machine timer read by [`crate::time::now`], in Hz.
+#[cfg(not(any(feature = "k510", feature = "k210")))]
+pub const TIMER_FREQUENCY: u32 = 27_000_000;
+/// Frequency of the machine timer read by [`crate::time::now`], in Hz.
+#[cfg(feature = "k510")]
+pub const TIMER_FREQUENCY: u32 = 25_000_000;
+/// Frequency of the machine timer read by [`crate::time::now`], in Hz.
+///
+/// The CLINT timer runs at the CPU clock divided by 50.
+#[cfg(feature = "k210")]
+pub const TIMER_FREQUENCY: u32 = 7_800_000;
+
+/// Frequency of the CPU clock when the boot ROM hands over, in Hz.
+///
+/// This is the starting value of [`crate::clocks::Clocks::cpu`].
+#[cfg(not(any(feature = "k510", feature = "k210")))]
+pub const CPU_FREQUENCY: u32 = 800_000_000;
+/// Frequency of the CPU clock when the boot ROM hands over, in Hz.
+///
+/// This is the starting value of [`crate::clocks::Clocks::cpu`].
+#[cfg(feature = "k510")]
+pub const CPU_FREQUENCY: u32 = 800_000_000;
+/// Frequency of the CPU clock when the boot ROM hands over, in Hz.
+///
+/// This is the starting value of [`crate::clocks::Clocks::cpu`].
+#[cfg(feature = "k210")]
+pub const CPU_FREQUENCY: u32 = 390_000_000;
+
+/// Frequency of the UART serial clocks, in Hz.
+#[cfg(not(any(feature = "k510", feature = "k210")))]
+pub const UART_SCLK_FREQUENCY: u32 = 50_000_000;
+/// Frequency of the UART serial clocks, in Hz.
+///
+/// The K510 boot ROM leaves the low speed peripherals on the 25 MHz
+/// oscillator.
+#[cfg(feature = "k510")]
+pub const UART_SCLK_FREQUENCY: u32 = 25_000_000;
+/// Frequency of the UART serial clocks, in Hz.
+///
+/// The UARTs run from APB0, which the boot ROM leaves at half the CPU clock.
+#[cfg(feature = "k210")]
+pub const UART_SCLK_FREQUENCY: u32 = 195_000_000;
+
+/// Frequency of the I2C controller clocks, in Hz.
+#[cfg(not(any(feature = "k510", feature = "k210")))]
+pub const I2C_SCLK_FREQUENCY: u32 = 100_000_000;
+/// Frequency of the I2C controller clocks, in Hz.
+#[cfg(feature = "k510")]
+pub const I2C_SCLK_FREQUENCY: u32 = 25_000_000;
+/// Frequency of the I2C controller clocks, in Hz.
+///
+/// The I2C controllers run from APB0, like the UARTs.
+#[cfg(feature = "k210")]
+pub const I2C_SCLK_FREQUENCY: u32 = 195_000_000;
+
+/// Frequency of the SSI clocks the SPI serial clocks are divided from, in Hz.
+#[cfg(not(any(feature = "k510", feature = "k210")))]
+pub const SPI_SCLK_FREQUENCY: u32 = 50_000_000;
+/// Frequency of the SSI clocks the SPI serial clocks are divided from, in Hz.
+#[cfg(feature = "k510")]
+pub const SPI_SCLK_FREQUENCY: u32 = 25_000_000;
+/// Frequency of the SSI clocks the SPI serial clocks are divided from, in Hz.
+///
+/// The SSI clocks are PLL0 divided by two, the same rate as the CPU clock
+/// the boot ROM sets up.
+#[cfg(feature = "k210")]
+pub const SPI_SCLK_FREQUENCY: u32 = 390_000_000;
+
+/// Size of an L1 data cache line, in bytes.
+///
+/// Cache maintenance in [`crate::dma`] works on whole lines of this size.
+#[cfg(not(feature = "k510"))]
+pub const CACHE_LINE_SIZE: usize = 64;
+/// Size of an L1 data cache line, in bytes.
+///
+/// Cache maintenance in [`crate::dma`] works on whole lines of this size.
+#[cfg(feature = "k510")]
+pub const CACHE_LINE_SIZE: usize = 32;
+
+/// Optional features of an SPI controller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpiFeatures {
+    /// Widest data bus supported by the enhanced SPI modes: 1, 2, 4 or 8 lanes.
+    pub max_lanes: u8,
+    /// HyperBus frame format.
+    pub hyperbus: bool,
+    /// Execute-in-place memory mapped reads.
+    pub xip: bool,
+    /// CTRLR0 uses the legacy layout of older DesignWare SSI releases; see
+    /// [`ControlReg0::from_legacy`](crate::spi::ControlReg0::from_legacy).
+    pub legacy_ctrlr0: bool,
+}
+
+impl SpiFeatures {
+    /// Standard SPI only; used when the instance is not known.
+    pub const STANDARD: Self = Self {
+        max_lanes: 1,
+        hyperbus: false,
+        xip: false,
+        legacy_ctrlr0: false,
+    };
+}
+
+/// Optional features of a UART controller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UartFeatures {
+    /// 9-bit data frames through the LCR_EXT register.
+    pub nine_bit: bool,
+}
+
+impl UartFeatures {
+    /// 16550 compatible features only; used when the instance is not known.
+    pub const BASIC: Self = Self { nine_bit: false };
+}
+
+/// Features of SPI instance `N` on the selected chip.
+pub const fn spi<const N: usize>() -> SpiFeatures {
+    #[cfg(not(any(feature = "k510", feature = "k210")))]
+    return match N {
+        // SPI0 is the octal controller used for boot flash.
+        0 => SpiFeatures {
+            max_lanes: 8,
+            hyperbus: false,
+            xip: true,
+            legacy_ctrlr0: false,
+        },
+        1 | 2 => SpiFeatures {
+            max_lanes: 4,
+            hyperbus: false,
+            xip: false,
+            legacy_ctrlr0: false,
+        },
+        _ => SpiFeatures::STANDARD,
+    };
+    #[cfg(feature = "k510")]
+    return SpiFeatures::STANDARD;
+    #[cfg(feature = "k210")]
+    return match N {
+        // SPI0 and SPI1 are an older SSI release with a different CTRLR0.
+        0 | 1 => SpiFeatures {
+            max_lanes: 8,
+            hyperbus: false,
+            xip: false,
+            legacy_ctrlr0: true,
+        },
+        // SPI3 is the quad controller used for boot flash.
+        3 => SpiFeatures {
+            max_lanes: 4,
+            hyperbus: false,
+            xip: true,
+            legacy_ctrlr0: false,
+        },
+        _ => SpiFeatures::STANDARD,
+    };
+}
+
+/// Features of UART instance `N` on the selected chip.
+pub const fn uart<const N: usize>() -> UartFeatures {
+    #[cfg(not(any(feature = "k510", feature = "k210")))]
+    return UartFeatures { nine_bit: true };
+    #[cfg(any(feature = "k510", feature = "k210"))]
+    return UartFeatures::BASIC;
+}
+
+/// Clock enable bit of `clock` on the selected chip.
+///
+/// Returns `None` for clocks the HAL cannot gate on this chip.
+pub const fn clock_gate(clock: ClockId) -> Option<ClockGate> {
+    #[cfg(not(any(feature = "k510", feature = "k210")))]
+    return match clock {
+        ClockId::UartSclk(n @ 0..=4) => Some(ClockGate {
+            register: GateRegister::LowSpeed1,
+            bit: n,
+        }),
+        ClockId::I2cSclk(n @ 0..=4) => Some(ClockGate {
+            register: GateRegister::LowSpeed1,
+            bit: 5 + n,
+        }),
+        ClockId::SpiSclk(n @ 0..=2) => Some(ClockGate {
+            register: GateRegister::HighSpeed,
+            bit: 20 + n,
+        }),
+        _ => None,
+    };
+    #[cfg(any(feature = "k510", feature = "k210"))]
+    return None;
+}
+
+/// Divider of the debounce clock of GPIO controller `gpio` on the selected
+/// chip.
+///
+/// Returns `None` if the HAL cannot set the divider on this chip.
+pub const fn gpio_debounce_divider(gpio: u8) -> Option<ClockDivider> {
+    // Both GPIO controllers share one debounce clock.
+    #[cfg(not(any(feature = "k510", feature = "k210")))]
+    return match gpio {
+        0 | 1 => Some(ClockDivider {
+            shift: 15,
+            width: 10,
+        }),
+        _ => None,
+    };
+    #[cfg(any(feature = "k510", feature = "k210"))]
+    return None;
+}
@@ -0,0 +1,308 @@
+use crate::spi::driver::{Spi, SpiError, Word, read_ctrlr0};
+use crate::spi::register::{RegisterBlock, TransferMode};
+use atomic_waker::AtomicWaker;
+use core::future::{Future, poll_fn};
+use core::pin::pin;
+use core::task::Poll;
+use embedded_hal_async::delay::DelayNs;
+
+/// Interrupt state shared between an [`AsyncSpi`] and its interrupt handler.
+///
+/// Place one in a `static` per SPI instance and call [`SpiState::on_interrupt`]
+/// from the instance's interrupt handler.
+pub struct SpiState {
+    waker: AtomicWaker,
+}
+
+impl SpiState {
+    /// Creates a new state with no registered waker.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            waker: AtomicWaker::new(),
+        }
+    }
+
+    /// Handles an SPI interrupt.
+    ///
+    /// Masks the FIFO interrupts and wakes the pending future, which
+    /// re-enables the one it waits for on its next poll.
+    #[inline]
+    pub fn on_interrupt(&self, regs: &RegisterBlock) {
+        mask_all(regs);
+        self.waker.wake();
+    }
+}
+
+impl Default for SpiState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// FIFO condition an [`AsyncSpi`] future is waiting for.
+#[derive(Clone, Copy)]
+enum Event {
+    /// Transmit FIFO has room for another word.
+    TransmitNotFull,
+    /// Receive FIFO holds at least one word.
+    ReceiveNotEmpty,
+}
+
+/// Interrupt driven SPI master implementing embedded-hal-async `SpiBus`.
+///
+/// Every wait is bounded by the [timeout](Spi::set_timeout) of the wrapped
+/// driver, measured with `delay`, so a controller that stops raising
+/// interrupts fails the transfer with [`SpiError::BusyTimeout`] instead of
+/// hanging it.
+///
+/// A driver created with [`Spi::transmit_only`] never fills its receive
+/// FIFO: writes do not wait for frames to come back, and reads fail with
+/// [`SpiError::NotSupported`].
+pub struct AsyncSpi<'i, D> {
+    spi: Spi<'i>,
+    state: &'static SpiState,
+    delay: D,
+    transmit_only: bool,
+}
+
+impl<'i, D: DelayNs> AsyncSpi<'i, D> {
+    /// Wraps a configured blocking driver.
+    ///
+    /// The SPI interrupt must be routed to a handler calling
+    /// [`SpiState::on_interrupt`] with the same `state`. `delay` is usually
+    /// the async delay of the executor's time driver.
+    #[inline]
+    pub fn new(spi: Spi<'i>, state: &'static SpiState, delay: D) -> Self {
+        let transmit_only =
+            read_ctrlr0(spi.regs, spi.features()).transfer_mode() == TransferMode::TransmitOnly;
+        Self {
+            spi,
+            state,
+            delay,
+            transmit_only,
+        }
+    }
+
+    /// Returns the underlying blocking driver.
+    #[inline]
+    pub fn into_blocking(self) -> Spi<'i> {
+        mask_all(self.spi.regs);
+        self.spi
+    }
+
+    /// Send `write`, then receive `read.len()` frames, as one transfer.
+    ///
+    /// See [`Spi::write_then_read`].
+    pub async fn write_then_read<W: Word>(
+        &mut self,
+        write: &[W],
+        read: &mut [W],
+    ) -> Result<(), SpiError> {
+        self.check_receive::<W>()?;
+        for &w in write {
+            self.write_word(w).await?;
+            let _: W = self.read_word().await?;
+        }
+        for r in read.iter_mut() {
+            self.write_word(W::from_u32(0)).await?;
+            *r = self.read_word().await?;
+        }
+        self.spi.wait_idle()
+    }
+
+    /// Checks `W` against the frame size and that the controller receives.
+    fn check_receive<W: Word>(&self) -> Result<(), SpiError> {
+        self.spi.check_word::<W>()?;
+        if self.transmit_only {
+            return Err(SpiError::NotSupported);
+        }
+        Ok(())
+    }
+
+    /// Waits for `event`, giving up with [`SpiError::BusyTimeout`] once the
+    /// driver's timeout has passed.
+    async fn wait_for(&mut self, event: Event) -> Result<(), SpiError> {
+        let Self {
+            spi, state, delay, ..
+        } = self;
+        let regs = spi.regs;
+        let mut expired = pin!(delay.delay_us(spi.timeout_us));
+        let result = poll_fn(|cx| {
+            state.waker.register(cx.waker());
+            let sr = read_reg!(regs, sr);
+            let ready = match event {
+                Event::TransmitNotFull => sr.transmit_fifo_not_full(),
+                Event::ReceiveNotEmpty => sr.receive_fifo_not_empty(),
+            };
+            if ready {
+                return Poll::Ready(Ok(()));
+            }
+            if expired.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Err(SpiError::BusyTimeout));
+            }
+            // TXE fires once the transmit FIFO drains to TXFTLR and RXF once
+            // the receive FIFO exceeds RXFTLR; both thresholds are 0.
+            unsafe {
+                modify_reg!(regs, imr, |r| match event {
+                    Event::TransmitNotFull => r.with_transmit_fifo_empty_interrupt_mask(true),
+                    Event::ReceiveNotEmpty => r.with_receive_fifo_full_interrupt_mask(true),
+                })
+            };
+            Poll::Pending
+        })
+        .await;
+        mask_all(regs);
+        result
+    }
+
+    async fn write_word<W: Word>(&mut self, word: W) -> Result<(), SpiError> {
+        self.wait_for(Event::TransmitNotFull).await?;
+        self.spi.write_word(word);
+        Ok(())
+    }
+
+    async fn read_word<W: Word>(&mut self) -> Result<W, SpiError> {
+        self.wait_for(Event::ReceiveNotEmpty).await?;
+        Ok(self.spi.read_word())
+    }
+}
+
+impl<D> embedded_hal::spi::ErrorType for AsyncSpi<'_, D> {
+    type Error = SpiError;
+}
+
+impl<W: Word, D: DelayNs> embedded_hal_async::spi::SpiBus<W> for AsyncSpi<'_, D> {
+    async fn read(&mut self, words: &mut [W]) -> Result<(), Self::Error> {
+        self.check_receive::<W>()?;
+        for w in words.iter_mut() {
+            self.write_word(W::from_u32(0)).await?;
+            *w = self.read_word().await?;
+        }
+        Ok(())
+    }
+
+    async fn write(&mut self, words: &[W]) -> Result<(), Self::Error> {
+        self.spi.check_word::<W>()?;
+        if self.transmit_only {
+            for &w in words.iter() {
+                self.write_word(w).await?;
+            }
+            return Ok(());
+        }
+        for &w in words.iter() {
+            self.write_word(w).await?;
+            let _: W = self.read_word().await?;
+        }
+        Ok(())
+    }
+
+    /// Clocks `max(read.len(), write.len())` frames. Frames past the end of
+    /// `write` are sent as zero and frames past the end of `read` are dropped.
+    async fn transfer(&mut self, read: &mut [W], write: &[W]) -> Result<(), Self::Error> {
+        self.check_receive::<W>()?;
+        for i in 0..read.len().max(write.len()) {
+            self.write_word(write.get(i).copied().unwrap_or(W::from_u32(0)))
+                .await?;
+            let w = self.read_word().await?;
+            if let Some(r) = read.get_mut(i) {
+                *r = w;
+            }
+        }
+        Ok(())
+    }
+
+    async fn transfer_in_place(&mut self, words: &mut [W]) -> Result<(), Self::Error> {
+        self.check_receive::<W>()?;
+        for w in words.iter_mut() {
+            self.write_word(*w).await?;
+            *w = self.read_word().await?;
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        // Every word is read back above, so only the last frame can still be
+        // shifting out here, or the transmit FIFO is draining if the
+        // controller is transmit-only.
+        self.spi.wait_idle()
+    }
+}
+
+/// Mask the FIFO interrupts used by [`AsyncSpi`].
+#[inline]
+fn mask_all(regs: &RegisterBlock) {
+    unsafe {
+        modify_reg!(regs, imr, |r| {
+            r.with_transmit_fifo_empty_interrupt_mask(false)
+                .with_receive_fifo_full_interrupt_mask(false)
+        })
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+    use crate::soc::SpiFeatures;
+    use crate::spi::driver::{Config, modify_ctrlr0};
+    use crate::spi::register::StatusReg;
+    use core::task::{Context, Waker};
+    use embedded_hal_async::spi::SpiBus;
+
+    /// A delay that has always already elapsed, or never does.
+    struct Elapsed(bool);
+
+    impl DelayNs for Elapsed {
+        async fn delay_ns(&mut self, _ns: u32) {
+            if !self.0 {
+                core::future::pending::<()>().await;
+            }
+        }
+    }
+
+    fn async_spi(transmit_only: bool, sr: u32, elapsed: bool) -> AsyncSpi<'static, Elapsed> {
+        static STATE: SpiState = SpiState::new();
+        let regs = unsafe { &*mock::block::<RegisterBlock>() };
+        let mut spi = unsafe { Spi::from_regs_with_src_clock(regs, 50_000_000, Config::default()) };
+        // The host has no machine timer, so blocking waits only end at once
+        // with a zero timeout.
+        spi.set_timeout(0);
+        if transmit_only {
+            modify_ctrlr0(regs, SpiFeatures::STANDARD, |r| {
+                r.with_transfer_mode(TransferMode::TransmitOnly)
+            });
+        }
+        unsafe { regs.sr.write(StatusReg::new_with_raw_value(sr)) };
+        AsyncSpi::new(spi, &STATE, Elapsed(elapsed))
+    }
+
+    fn poll<F: Future>(future: F) -> Poll<F::Output> {
+        let mut cx = Context::from_waker(Waker::noop());
+        pin!(future).poll(&mut cx)
+    }
+
+    #[test]
+    fn transmit_only_write_completes() {
+        // Not busy, transmit FIFO not full, receive FIFO empty.
+        let mut spi = async_spi(true, 0b0010, false);
+        assert_eq!(poll(spi.write(&[0x55u8, 0xAA])), Poll::Ready(Ok(())));
+        let mut buf = [0u8; 2];
+        assert_eq!(
+            poll(spi.read(&mut buf)),
+            Poll::Ready(Err(SpiError::NotSupported))
+        );
+    }
+
+    #[test]
+    fn missing_receive_frame_times_out() {
+        let mut spi = async_spi(false, 0b0010, false);
+        assert!(poll(spi.write(&[0x55u8])).is_pending());
+
+        let mut spi = async_spi(false, 0b0010, true);
+        assert_eq!(
+            poll(spi.write(&[0x55u8])),
+            Poll::Ready(Err(SpiError::BusyTimeout))
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use super::driver::{Spi, SpiError, Word};
+use embedded_hal::digital::OutputPin;
+use embedded_hal::spi::{Operation, SpiBus, SpiDevice};
+
+/// SPI device with a chip select driven by a GPIO pin.
+///
+/// The controller's own chip select is released whenever its transmit FIFO
+/// runs empty, so it cannot frame a transaction made of several operations.
+/// This device drives chip select from an output pin instead: it is
+/// asserted (low) before the first operation and released only after the
+/// last one has finished shifting out, as [`SpiDevice`] requires.
+///
+/// The controller still needs a slave select line enabled in
+/// [`Config::ss_index`](super::Config::ss_index) to start transfers; leave
+/// its pad unassigned, or on a line no device listens to.
+pub struct CsDevice<'i, CS> {
+    spi: Spi<'i>,
+    cs: CS,
+}
+
+impl<'i, CS: OutputPin> CsDevice<'i, CS> {
+    /// Creates a device on `spi` with chip select `cs`, which is released.
+    pub fn new(spi: Spi<'i>, mut cs: CS) -> Result<Self, SpiError> {
+        cs.set_high().map_err(|_| SpiError::ChipSelect)?;
+        Ok(Self { spi, cs })
+    }
+
+    /// Returns the bus and the chip select pin.
+    pub fn free(self) -> (Spi<'i>, CS) {
+        (self.spi, self.cs)
+    }
+
+    /// The underlying bus, for configuration changes between transactions.
+    pub fn bus(&mut self) -> &mut Spi<'i> {
+        &mut self.spi
+    }
+
+    /// Send `command`, then receive `buf.len()` frames, under one chip select.
+    ///
+    /// The frames are received with [`Spi::read_only`], so software only
+    /// drains the receive FIFO, and chip select stays asserted across the
+    /// transfers it splits long reads into. The device sees a single read of
+    /// any length, such as a flash read loading a whole model image.
+    pub fn read_stream<W: Word>(&mut self, command: &[W], buf: &mut [W]) -> Result<(), SpiError> {
+        self.cs.set_low().map_err(|_| SpiError::ChipSelect)?;
+        let result = SpiBus::write(&mut self.spi, command).and_then(|()| {
+            self.spi.discard_rx();
+            self.spi.read_only(buf)
+        });
+        // Release chip select even if the read failed.
+        let released = self.cs.set_high().map_err(|_| SpiError::ChipSelect);
+        result.and(released)
+    }
+}
+
+impl<CS> embedded_hal::spi::ErrorType for CsDevice<'_, CS> {
+    type Error = SpiError;
+}
+
+impl<W: Word, CS: OutputPin> SpiDevice<W> for CsDevice<'_, CS> {
+    fn transaction(&mut self, operations: &mut [Operation<'_, W>]) -> Result<(), SpiError> {
+        self.cs.set_low().map_err(|_| SpiError::ChipSelect)?;
+        let result = operations
+            .iter_mut()
+            .try_for_each(|op| self.spi.run_operation(op))
+            .and_then(|()| SpiBus::<W>::flush(&mut self.spi));
+        // Release chip select even if an operation failed.
+        let released = self.cs.set_high().map_err(|_| SpiError::ChipSelect);
+        result.and(released)
+    }
+}
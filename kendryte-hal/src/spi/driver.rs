@@ -1,37 +1,175 @@
-use core::marker::PhantomData;
-
-use crate::clocks::Clocks;
+use crate::clocks::{ClockId, Clocks};
 use crate::instance::Numbered;
 use crate::iomux::FlexPad;
-use crate::spi::pad::{IntoPads, IntoTransmitOnly};
+use crate::soc::{self, SpiFeatures};
+use crate::spi::pad::{IntoPads, IntoTransmitOnly, SpiPads};
 use crate::spi::register::*;
-use arbitrary_int::{u2, u5, u14, u15, u30};
+use crate::sysctl::Sysctl;
+use crate::time::Timeout;
+use arbitrary_int::{u2, u4, u5, u14, u15, u30};
 
 /// Simple error type for SPI operations.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum SpiError {
     BusyTimeout,
     FifoOverflow,
     FifoUnderflow,
+    /// The configured data frame size does not fit in the word type used for the transfer.
+    InvalidWordSize,
+    /// The requested feature is not available on this SPI instance.
+    NotSupported,
+    /// A background transfer is still running or has not been collected.
+    TransferInProgress,
+    /// The transfer needs a different frame format than the one configured.
+    WrongFrameFormat,
+    /// Driving the GPIO chip select of a [`CsDevice`](super::CsDevice) failed.
+    ChipSelect,
+    /// The identification registers read back this value instead of an SPI controller's.
+    IdMismatch(u32),
 }
 
 impl embedded_hal::spi::Error for SpiError {
     fn kind(&self) -> embedded_hal::spi::ErrorKind {
         match self {
-            SpiError::BusyTimeout => embedded_hal::spi::ErrorKind::Other,
-            SpiError::FifoOverflow => embedded_hal::spi::ErrorKind::Overrun,
-            SpiError::FifoUnderflow => embedded_hal::spi::ErrorKind::Other,
+            SpiError::ChipSelect => embedded_hal::spi::ErrorKind::ChipSelectFault,
+            _ => crate::Error::from(*self).kind().into(),
         }
     }
 }
 
+/// Default bound on a single FIFO or busy wait, in microseconds.
+const DEFAULT_TIMEOUT_US: u32 = 100_000;
+
+/// Most frames a single receive-only, EEPROM read or sequential Microwire
+/// transfer can take.
+///
+/// CTRLR1.NDF holds the frame count minus one in 16 bits. The drivers split
+/// longer reads by themselves; code programming the controller for DMA
+/// must split its transfers at this size too.
+pub const MAX_TRANSFER_FRAMES: usize = 1 << 16;
+
 /// SPI mode (CPOL/CPHA)
 pub type Mode = embedded_hal::spi::Mode;
 
-/// Blocking SPI master implementing embedded-hal 1.0 `SpiBus<u8>`.
+/// Data word types that can be transferred over SPI.
+///
+/// A data frame of up to `BITS` bits is right-justified in the word.
+pub trait Word: Copy + 'static + sealed::Sealed {
+    /// Width of the word type in bits.
+    const BITS: u8;
+    /// Widen the word to a data register value.
+    fn into_u32(self) -> u32;
+    /// Truncate a data register value into the word type.
+    fn from_u32(value: u32) -> Self;
+}
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for u8 {}
+    impl Sealed for u16 {}
+    impl Sealed for u32 {}
+}
+
+macro_rules! impl_word {
+    ($($ty:ty),+) => {
+        $(
+            impl Word for $ty {
+                const BITS: u8 = <$ty>::BITS as u8;
+
+                #[inline]
+                fn into_u32(self) -> u32 {
+                    self as u32
+                }
+
+                #[inline]
+                fn from_u32(value: u32) -> Self {
+                    value as $ty
+                }
+            }
+        )+
+    };
+}
+
+impl_word!(u8, u16, u32);
+
+/// Blocking SPI master implementing embedded-hal 1.0 `SpiBus` for `u8`, `u16` and `u32` words.
+///
+/// The controller releases its own chip select whenever the transmit FIFO
+/// runs empty, so it cannot hold a device selected across a transaction.
+/// Wrap the bus in a [`CsDevice`](super::CsDevice) for an `SpiDevice`.
 pub struct Spi<'i> {
-    regs: &'static RegisterBlock,
-    _pads: PhantomData<FlexPad<'i>>,
+    pub(super) regs: &'static RegisterBlock,
+    pads: Option<SpiPads<'i>>,
+    data_bits: u8,
+    features: SpiFeatures,
+    pub(super) timeout_us: u32,
+    clock: Option<ClockId>,
+    /// Frequency of the SSI clock the serial clock is divided from, in Hz.
+    pub(super) src_clock_hz: u32,
+    context: Option<Context>,
+}
+
+/// Register state of an SPI controller kept across a clock gate or power-down.
+#[derive(Clone, Copy)]
+struct Context {
+    ctrlr0: ControlReg0,
+    ctrlr1: ControlReg1,
+    mwcr: MicrowireControlReg,
+    ser: SlaveEnableReg,
+    baudr: BaudRateSelectReg,
+    txftlr: TransmitFifoThresholdLevelReg,
+    rxftlr: ReceiveFifoThresholdLevelReg,
+    imr: InterruptMaskReg,
+    dmacr: DmaControlReg,
+    dmatdlr: DmaTransmitDataLevelReg,
+    dmardlr: DmaReceiveDataLevelReg,
+    rx_sample_delay: RxSampleDelayReg,
+    spi_ctrlr0: SpiControlReg0,
+    ssienr: SsiEnableReg,
+}
+
+impl Context {
+    fn save(regs: &RegisterBlock) -> Self {
+        Context {
+            ctrlr0: read_reg!(regs, ctrlr0),
+            ctrlr1: read_reg!(regs, ctrlr1),
+            mwcr: read_reg!(regs, mwcr),
+            ser: read_reg!(regs, ser),
+            baudr: read_reg!(regs, baudr),
+            txftlr: read_reg!(regs, txftlr),
+            rxftlr: read_reg!(regs, rxftlr),
+            imr: read_reg!(regs, imr),
+            dmacr: read_reg!(regs, dmacr),
+            dmatdlr: read_reg!(regs, dmatdlr_axiawlen),
+            dmardlr: read_reg!(regs, dmardlr_axiarlen),
+            rx_sample_delay: read_reg!(regs, rx_sample_delay),
+            spi_ctrlr0: read_reg!(regs, spi_ctrlr0),
+            ssienr: read_reg!(regs, ssienr),
+        }
+    }
+
+    /// Programs the saved state with the controller disabled, then enables
+    /// it again if it was enabled when saved.
+    fn restore(&self, regs: &RegisterBlock) {
+        unsafe {
+            modify_reg!(regs, ssienr, |r| r.with_ssi_enable(false));
+            write_reg!(regs, ctrlr0, self.ctrlr0);
+            write_reg!(regs, ctrlr1, self.ctrlr1);
+            write_reg!(regs, mwcr, self.mwcr);
+            write_reg!(regs, ser, self.ser);
+            write_reg!(regs, baudr, self.baudr);
+            write_reg!(regs, txftlr, self.txftlr);
+            write_reg!(regs, rxftlr, self.rxftlr);
+            write_reg!(regs, imr, self.imr);
+            write_reg!(regs, dmacr, self.dmacr);
+            write_reg!(regs, dmatdlr_axiawlen, self.dmatdlr);
+            write_reg!(regs, dmardlr_axiarlen, self.dmardlr);
+            write_reg!(regs, rx_sample_delay, self.rx_sample_delay);
+            write_reg!(regs, spi_ctrlr0, self.spi_ctrlr0);
+            write_reg!(regs, ssienr, self.ssienr);
+        }
+    }
 }
 
 /// Configuration for SPI
@@ -39,10 +177,65 @@ pub struct Spi<'i> {
 pub struct Config {
     pub frequency: u32,
     pub mode: Mode,
-    /// data frame size in bits (4..=16 typical, controller supports up to 32). We use 8 by default
+    /// data frame size in bits (4..=32). We use 8 by default.
+    /// Frames up to 8 bits use `u8` words, up to 16 bits `u16` words and up to 32 bits `u32` words.
     pub data_bits: u8,
     /// slave select bit index (0-based)
     pub ss_index: u8,
+    /// Receive data sampling point; see [`Spi::calibrate`].
+    pub rx_sampling: RxSampling,
+    /// Serial protocol; `mode` only applies to Motorola SPI.
+    /// The reserved format falls back to Motorola SPI.
+    pub frame_format: FrameFormat,
+    /// Control word settings used with [`FrameFormat::NationalMicrowire`].
+    pub microwire: MicrowireConfig,
+}
+
+/// Settings of the National Semiconductor Microwire frame format.
+///
+/// Every Microwire transfer starts with a half-duplex control word sent
+/// by the master, followed by one or more data words in the direction the
+/// control word selects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MicrowireConfig {
+    /// Control word size in bits (1..=16). We use 8 by default.
+    pub control_bits: u8,
+    /// Send a single control word for a whole block of data words instead
+    /// of one per data word.
+    pub sequential: bool,
+    /// Wait for the slave to signal ready after each transmitted data word.
+    pub handshaking: bool,
+}
+
+impl Default for MicrowireConfig {
+    fn default() -> Self {
+        Self {
+            control_bits: 8,
+            sequential: false,
+            handshaking: false,
+        }
+    }
+}
+
+/// Edge of the internal SSI clock used to sample received data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SampleEdge {
+    #[default]
+    Rising,
+    Falling,
+}
+
+/// Receive data sampling point.
+///
+/// At high serial clock rates the data from the slave arrives late because
+/// of pad and trace delays. Delaying the sample by a number of SSI clock
+/// cycles moves it back into the valid window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RxSampling {
+    /// Sample delay in SSI clock cycles.
+    pub delay: u8,
+    /// SSI clock edge the sample is taken on.
+    pub edge: SampleEdge,
 }
 
 impl Default for Config {
@@ -52,6 +245,9 @@ impl Default for Config {
             mode: embedded_hal::spi::MODE_0,
             data_bits: 8,
             ss_index: 0,
+            rx_sampling: RxSampling::default(),
+            frame_format: FrameFormat::MotorolaSpi,
+            microwire: MicrowireConfig::default(),
         }
     }
 }
@@ -59,51 +255,80 @@ impl Default for Config {
 impl<'i> Spi<'i> {
     /// Create and configure an SPI master instance for numbered instance N.
     pub fn new<const N: usize>(
-        instance: impl Numbered<'i, N, R = RegisterBlock>,
+        instance: impl Numbered<'i, N, R = &'static RegisterBlock>,
         cfg: Config,
         clocks: Clocks,
     ) -> Self {
+        let clock = ClockId::SpiSclk(N as u8);
+        let src_clock_hz = clocks.frequency(clock).0;
+        Self::configured(
+            instance.inner(),
+            soc::spi::<N>(),
+            Some(clock),
+            src_clock_hz,
+            cfg,
+        )
+    }
+
+    /// Like [`Spi::new`], but first checks that the instance is an SPI
+    /// controller; see [`crate::ident`].
+    pub fn try_new<const N: usize>(
+        instance: impl Numbered<'i, N, R = &'static RegisterBlock>,
+        cfg: Config,
+        clocks: Clocks,
+    ) -> Result<Self, SpiError> {
         let regs = instance.inner();
-        Self::configure::<N>(regs, cfg, clocks);
-        Spi {
+        crate::ident::spi(regs)?;
+        let clock = ClockId::SpiSclk(N as u8);
+        let src_clock_hz = clocks.frequency(clock).0;
+        Ok(Self::configured(
             regs,
-            _pads: PhantomData,
-        }
+            soc::spi::<N>(),
+            Some(clock),
+            src_clock_hz,
+            cfg,
+        ))
     }
 
     /// Create a new SPI with full-duplex pads (bouffalo-hal style API).
     #[inline]
     pub fn with_pads<const N: usize>(
-        instance: impl Numbered<'i, N, R = RegisterBlock>,
+        instance: impl Numbered<'i, N, R = &'static RegisterBlock>,
         pads: impl IntoPads<'i, N>,
         cfg: Config,
         clocks: Clocks,
     ) -> Self {
-        let pads = pads.into_full_duplex_pads();
-        core::mem::forget(pads);
-        Self::new(instance, cfg, clocks)
+        let (clk, mosi, miso, cs) = pads.into_full_duplex_pads();
+        let mut spi = Self::new(instance, cfg, clocks);
+        spi.pads = Some(SpiPads {
+            clk,
+            mosi,
+            miso: Some(miso),
+            cs,
+        });
+        spi
     }
 
     /// Create a new SPI in transmit-only mode with pads.
     #[inline]
     pub fn transmit_only<const N: usize>(
-        instance: impl Numbered<'i, N, R = RegisterBlock>,
+        instance: impl Numbered<'i, N, R = &'static RegisterBlock>,
         pads: impl IntoTransmitOnly<'i, N>,
         cfg: Config,
         clocks: Clocks,
     ) -> Self {
-        let pads = pads.into_transmit_only_pads();
-        core::mem::forget(pads);
-        let regs = instance.inner();
-        Self::configure::<N>(regs, cfg, clocks);
-        unsafe {
-            regs.ctrlr0
-                .modify(|r| r.with_transfer_mode(TransferMode::TransmitOnly));
-        }
-        Spi {
-            regs,
-            _pads: PhantomData,
-        }
+        let (clk, mosi, cs) = pads.into_transmit_only_pads();
+        let mut spi = Self::new(instance, cfg, clocks);
+        modify_ctrlr0(spi.regs, spi.features, |r| {
+            r.with_transfer_mode(TransferMode::TransmitOnly)
+        });
+        spi.pads = Some(SpiPads {
+            clk,
+            mosi,
+            miso: None,
+            cs,
+        });
+        spi
     }
 
     /// Create from a raw register pointer and a known source clock (Hz).
@@ -113,78 +338,38 @@ impl<'i> Spi<'i> {
         src_clock_hz: u32,
         cfg: Config,
     ) -> Self {
-        // Temporarily emulate a Clocks value by computing divider directly
-        // Disable controller before changing config
-        unsafe { regs.ssienr.modify(|r| r.with_ssi_enable(false)) };
-
-        // Frame format and clock mode
-        let (scpol, scph) = match (cfg.mode.polarity, cfg.mode.phase) {
-            (
-                embedded_hal::spi::Polarity::IdleLow,
-                embedded_hal::spi::Phase::CaptureOnFirstTransition,
-            ) => (SerialClockPolarity::Low, SerialClockPhase::Middle),
-            (
-                embedded_hal::spi::Polarity::IdleLow,
-                embedded_hal::spi::Phase::CaptureOnSecondTransition,
-            ) => (SerialClockPolarity::Low, SerialClockPhase::Start),
-            (
-                embedded_hal::spi::Polarity::IdleHigh,
-                embedded_hal::spi::Phase::CaptureOnFirstTransition,
-            ) => (SerialClockPolarity::High, SerialClockPhase::Middle),
-            (
-                embedded_hal::spi::Polarity::IdleHigh,
-                embedded_hal::spi::Phase::CaptureOnSecondTransition,
-            ) => (SerialClockPolarity::High, SerialClockPhase::Start),
-        };
-        let dfs = u5::new((cfg.data_bits.saturating_sub(1)).min(31));
-        unsafe {
-            regs.ctrlr0.modify(|r| {
-                r.with_frame_format(FrameFormat::MotorolaSpi)
-                    .with_serial_clock_polarity(scpol)
-                    .with_serial_clock_phase(scph)
-                    .with_transfer_mode(TransferMode::TransmitAndReceive)
-                    .with_slave_output_enable(false)
-                    .with_shift_register_loop(false)
-                    .with_slave_select_toggle_enable(false)
-                    .with_spi_frame_format(SpiFrameFormat::Standard)
-                    .with_ssi_is_master(WorkingMode::Master)
-                    .with_data_frame_size(dfs)
-            })
-        };
-
-        let mut div2 = src_clock_hz / cfg.frequency;
-        if div2 < 2 {
-            div2 = 2;
-        }
-        if div2 % 2 == 1 {
-            div2 += 1;
-        }
-        let sckdv = u15::new(((div2 / 2) as u16).max(1));
-        unsafe { regs.baudr.modify(|r| r.with_ssi_clock_divider(sckdv)) };
-        unsafe {
-            regs.txftlr.modify(|r| {
-                r.with_transmit_fifo_threshold(u2::new(0))
-                    .with_transfer_start_fifo_level(u14::new(0))
-            })
-        };
-        unsafe { regs.rxftlr.modify(|r| r.with_receive_fifo_threshold(0u8)) };
-        let ser = (1u32 << (cfg.ss_index as u32)) & 0x3FFF_FFFF;
-        unsafe {
-            regs.ser
-                .modify(|r| r.with_slave_select_enable(u30::new(ser)))
-        };
-        unsafe { regs.icr.modify(|r| r.with_interrupt_clear(true)) };
-        unsafe { regs.ssienr.modify(|r| r.with_ssi_enable(true)) };
+        Self::configured(regs, SpiFeatures::STANDARD, None, src_clock_hz, cfg)
+    }
 
+    /// Configures the controller and builds a driver without pads.
+    fn configured(
+        regs: &'static RegisterBlock,
+        features: SpiFeatures,
+        clock: Option<ClockId>,
+        src_clock_hz: u32,
+        cfg: Config,
+    ) -> Self {
+        Self::configure(regs, features, src_clock_hz, cfg);
         Spi {
             regs,
-            _pads: PhantomData,
+            pads: None,
+            data_bits: clamp_data_bits(cfg.data_bits),
+            features,
+            timeout_us: DEFAULT_TIMEOUT_US,
+            clock,
+            src_clock_hz,
+            context: None,
         }
     }
 
-    fn configure<const N: usize>(regs: &'static RegisterBlock, cfg: Config, clocks: Clocks) {
+    fn configure(
+        regs: &'static RegisterBlock,
+        features: SpiFeatures,
+        src_clock_hz: u32,
+        cfg: Config,
+    ) {
         // Disable controller before changing config
-        unsafe { regs.ssienr.modify(|r| r.with_ssi_enable(false)) };
+        unsafe { modify_reg!(regs, ssienr, |r| r.with_ssi_enable(false)) };
 
         // Frame format and clock mode
         let (scpol, scph) = match (cfg.mode.polarity, cfg.mode.phase) {
@@ -206,148 +391,700 @@ impl<'i> Spi<'i> {
             ) => (SerialClockPolarity::High, SerialClockPhase::Start),
         };
 
-        // data frame size is encoded as n-1 per Synopsys SSI, map 8 -> 7
-        let dfs = u5::new((cfg.data_bits.saturating_sub(1)).min(31));
+        let dfs = data_frame_size(cfg.data_bits);
 
-        unsafe {
-            regs.ctrlr0.modify(|r| {
-                r.with_frame_format(FrameFormat::MotorolaSpi)
-                    .with_serial_clock_polarity(scpol)
-                    .with_serial_clock_phase(scph)
-                    .with_transfer_mode(TransferMode::TransmitAndReceive)
-                    .with_slave_output_enable(false)
-                    .with_shift_register_loop(false)
-                    .with_slave_select_toggle_enable(false)
-                    .with_spi_frame_format(SpiFrameFormat::Standard)
-                    .with_ssi_is_master(WorkingMode::Master)
-                    .with_data_frame_size(dfs)
-            })
-        };
+        modify_ctrlr0(regs, features, |r| {
+            r.with_serial_clock_polarity(scpol)
+                .with_serial_clock_phase(scph)
+                .with_transfer_mode(TransferMode::TransmitAndReceive)
+                .with_slave_output_enable(false)
+                .with_shift_register_loop(false)
+                .with_slave_select_toggle_enable(false)
+                .with_spi_frame_format(SpiFrameFormat::Standard)
+                .with_ssi_is_master(WorkingMode::Master)
+                .with_data_frame_size(dfs)
+        });
+        write_rx_sampling(regs, cfg.rx_sampling);
+        write_frame_format(regs, features, cfg.frame_format, cfg.microwire);
 
         // Program baud rate divider: Fsclk = Fssi_clk / (2 * ssi_clock_divider)
-        let src = clocks.uart_sclk::<N>().0; // reuse UART clock until a dedicated clock API is available
-        let mut div2 = src / cfg.frequency;
-        if div2 < 2 {
-            div2 = 2;
-        }
-        if div2 % 2 == 1 {
-            div2 += 1;
-        } // ensure even
-        let sckdv = u15::new(((div2 / 2) as u16).max(1));
-        unsafe { regs.baudr.modify(|r| r.with_ssi_clock_divider(sckdv)) };
+        let sckdv = clock_divider(src_clock_hz, cfg.frequency);
+        unsafe { modify_reg!(regs, baudr, |r| r.with_ssi_clock_divider(sckdv)) };
 
         // Default thresholds: start when at least 1 entry, RX trigger at 1
         unsafe {
-            regs.txftlr.modify(|r| {
+            modify_reg!(regs, txftlr, |r| {
                 r.with_transmit_fifo_threshold(u2::new(0))
                     .with_transfer_start_fifo_level(u14::new(0))
             })
         };
-        unsafe { regs.rxftlr.modify(|r| r.with_receive_fifo_threshold(0u8)) };
+        unsafe { modify_reg!(regs, rxftlr, |r| r.with_receive_fifo_threshold(0u8)) };
 
         // Select slave
         let ser = (1u32 << (cfg.ss_index as u32)) & 0x3FFF_FFFF;
+        unsafe { modify_reg!(regs, ser, |r| r.with_slave_select_enable(u30::new(ser))) };
+
+        // Clear interrupts and enable
+        unsafe { modify_reg!(regs, icr, |r| r.with_interrupt_clear(true)) };
+        unsafe { modify_reg!(regs, ssienr, |r| r.with_ssi_enable(true)) };
+    }
+
+    /// Disable the controller and release the pads it was created with.
+    ///
+    /// Waits for any ongoing transfer to finish, deselects all slaves and
+    /// disables the SSI. Pads are returned with input and output disabled,
+    /// ready to be configured for another function. Returns `None` if the
+    /// driver was created without pads.
+    pub fn free(self) -> Option<SpiPads<'i>> {
+        let _ = self.wait_idle();
+        unsafe { modify_reg!(self.regs, ser, |r| r.with_slave_select_enable(u30::new(0))) };
+        unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(false)) };
+        let mut pads = self.pads;
+        if let Some(pads) = pads.as_mut() {
+            pads.disable();
+        }
+        pads
+    }
+
+    /// Saves the controller configuration and gates its clock.
+    ///
+    /// Waits for the current transfer to finish first. The driver must not
+    /// transfer data until [`resume`](Self::resume). Drivers created with
+    /// [`Spi::from_regs_with_src_clock`] do not know their clock and only
+    /// save the configuration. Suspending a suspended driver does nothing.
+    pub fn suspend(&mut self, sysctl: &mut Sysctl<'_>) -> Result<(), SpiError> {
+        if self.context.is_some() {
+            return Ok(());
+        }
+        self.wait_idle()?;
+        self.context = Some(Context::save(self.regs));
+        if let Some(clock) = self.clock {
+            sysctl.disable_clock(clock);
+        }
+        Ok(())
+    }
+
+    /// Enables the clock and restores the configuration saved by
+    /// [`suspend`](Self::suspend).
+    ///
+    /// The registers are rewritten even if they kept their values, so the
+    /// controller also comes back after its power domain was switched off.
+    /// Does nothing if the driver is not suspended.
+    pub fn resume(&mut self, sysctl: &mut Sysctl<'_>) {
+        if let Some(context) = self.context.take() {
+            if let Some(clock) = self.clock {
+                sysctl.enable_clock(clock);
+            }
+            context.restore(self.regs);
+        }
+    }
+
+    /// Returns true between [`suspend`](Self::suspend) and [`resume`](Self::resume).
+    #[inline]
+    pub fn is_suspended(&self) -> bool {
+        self.context.is_some()
+    }
+
+    /// Data frame size in bits.
+    #[inline]
+    pub fn data_bits(&self) -> u8 {
+        self.data_bits
+    }
+
+    /// Change the data frame size, e.g. to switch between 8-bit commands and 16-bit data.
+    ///
+    /// Waits for the current transfer to finish, as the controller must be
+    /// disabled while the frame size is reprogrammed. Sizes outside 4..=32
+    /// are clamped to that range, and [`data_bits`](Self::data_bits) returns
+    /// the size in effect.
+    pub fn set_data_bits(&mut self, data_bits: u8) {
+        let data_bits = clamp_data_bits(data_bits);
+        let _ = self.wait_idle();
+        unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(false)) };
+        modify_ctrlr0(self.regs, self.features, |r| {
+            r.with_data_frame_size(data_frame_size(data_bits))
+        });
+        unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(true)) };
+        self.data_bits = data_bits;
+    }
+
+    /// Highest serial clock frequency instance `N` can generate, in Hz.
+    ///
+    /// The serial clock is the SSI clock divided by an even value of at
+    /// least 2. Whether data survives that rate depends on the wiring and
+    /// the device; [`loopback_sweep`](Self::loopback_sweep) checks the
+    /// controller side.
+    pub fn max_achievable_frequency<const N: usize>(clocks: Clocks) -> u32 {
+        clocks.frequency(ClockId::SpiSclk(N as u8)).0 / 2
+    }
+
+    /// Serial clock frequency, in Hz.
+    #[inline]
+    pub fn frequency(&self) -> u32 {
+        let sckdv = read_reg!(self.regs, baudr).ssi_clock_divider().value() as u32;
+        self.src_clock_hz / (2 * sckdv.max(1))
+    }
+
+    /// Change the serial clock frequency, returning the one achieved.
+    ///
+    /// The divider is chosen as for [`Config::frequency`]. Waits for the
+    /// current transfer to finish.
+    pub fn set_frequency(&mut self, frequency: u32) -> u32 {
+        let _ = self.wait_idle();
+        let sckdv = clock_divider(self.src_clock_hz, frequency);
+        unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(false)) };
+        unsafe { modify_reg!(self.regs, baudr, |r| r.with_ssi_clock_divider(sckdv)) };
+        unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(true)) };
+        self.frequency()
+    }
+
+    /// Change the bound on a single FIFO or busy wait, in microseconds.
+    ///
+    /// A wait that exceeds it fails with [`SpiError::BusyTimeout`].
+    #[inline]
+    pub fn set_timeout(&mut self, timeout_us: u32) {
+        self.timeout_us = timeout_us;
+    }
+
+    /// Optional controller features available on this instance.
+    ///
+    /// Drivers created with [`Spi::from_regs_with_src_clock`] assume standard SPI only.
+    #[inline]
+    pub fn features(&self) -> SpiFeatures {
+        self.features
+    }
+
+    /// Select the number of data lanes used in the data phase.
+    ///
+    /// Dual, quad and octal formats additionally need the instruction and
+    /// address phases set up in `spi_ctrlr0`. Fails with
+    /// [`SpiError::NotSupported`] if the instance has fewer lanes.
+    pub fn set_spi_frame_format(&mut self, format: SpiFrameFormat) -> Result<(), SpiError> {
+        let lanes = match format {
+            SpiFrameFormat::Standard => 1,
+            SpiFrameFormat::Dual => 2,
+            SpiFrameFormat::Quad => 4,
+            SpiFrameFormat::Octal => 8,
+        };
+        if lanes > self.features.max_lanes {
+            return Err(SpiError::NotSupported);
+        }
+        self.wait_idle()?;
+        unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(false)) };
+        modify_ctrlr0(self.regs, self.features, |r| {
+            r.with_spi_frame_format(format)
+        });
+        unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(true)) };
+        Ok(())
+    }
+
+    /// Enable or disable the HyperBus frame format.
+    ///
+    /// Fails with [`SpiError::NotSupported`] if the instance was synthesized
+    /// without HyperBus support.
+    pub fn set_hyperbus(&mut self, enable: bool) -> Result<(), SpiError> {
+        if !self.features.hyperbus {
+            return Err(SpiError::NotSupported);
+        }
+        self.wait_idle()?;
+        unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(false)) };
+        modify_ctrlr0(self.regs, self.features, |r| {
+            r.with_spi_hyperbus_enable(enable)
+        });
+        unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(true)) };
+        Ok(())
+    }
+
+    /// Switch the serial protocol.
+    ///
+    /// Texas Instruments SSP frames are full-duplex like Motorola SPI, so
+    /// the `SpiBus` methods work unchanged in both. Microwire transfers go
+    /// through [`microwire_write`](Self::microwire_write) and
+    /// [`microwire_read`](Self::microwire_read) instead. Fails with
+    /// [`SpiError::NotSupported`] for the reserved format.
+    pub fn set_frame_format(
+        &mut self,
+        format: FrameFormat,
+        microwire: MicrowireConfig,
+    ) -> Result<(), SpiError> {
+        if format == FrameFormat::Reserved {
+            return Err(SpiError::NotSupported);
+        }
+        self.wait_idle()?;
+        unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(false)) };
+        write_frame_format(self.regs, self.features, format, microwire);
+        unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(true)) };
+        Ok(())
+    }
+
+    /// Configured serial protocol.
+    #[inline]
+    pub fn frame_format(&self) -> FrameFormat {
+        read_ctrlr0(self.regs, self.features).frame_format()
+    }
+
+    /// Send `control` followed by the data words in `words`.
+    ///
+    /// In sequential mode one control word precedes the whole block; otherwise
+    /// `control` is repeated before every data word. Fails with
+    /// [`SpiError::WrongFrameFormat`] unless the Microwire format is configured.
+    pub fn microwire_write<W: Word>(&mut self, control: u16, words: &[W]) -> Result<(), SpiError> {
+        self.check_word::<W>()?;
+        let sequential = self.begin_microwire(MicrowireControlMode::Transmit)?;
+        for (i, &w) in words.iter().enumerate() {
+            if i == 0 || !sequential {
+                self.wait_tfnf()?;
+                self.write_control(control);
+            }
+            self.wait_tfnf()?;
+            self.write_word(w);
+        }
+        self.wait_idle()
+    }
+
+    /// Send `control` and receive `words.len()` data words.
+    ///
+    /// In sequential mode the slave streams the whole block after a single
+    /// control word; otherwise `control` is repeated for every data word.
+    /// Fails with [`SpiError::WrongFrameFormat`] unless the Microwire format
+    /// is configured.
+    pub fn microwire_read<W: Word>(
+        &mut self,
+        control: u16,
+        words: &mut [W],
+    ) -> Result<(), SpiError> {
+        self.check_word::<W>()?;
+        let sequential = self.begin_microwire(MicrowireControlMode::Receive)?;
+        if sequential {
+            for chunk in words.chunks_mut(MAX_TRANSFER_FRAMES) {
+                self.wait_idle()?;
+                unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(false)) };
+                unsafe {
+                    modify_reg!(self.regs, ctrlr1, |r| r
+                        .with_number_of_data_frames((chunk.len() - 1) as u16))
+                };
+                unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(true)) };
+                self.write_control(control);
+                for w in chunk.iter_mut() {
+                    self.wait_rfne()?;
+                    *w = self.read_word();
+                }
+            }
+        } else {
+            for w in words.iter_mut() {
+                self.wait_tfnf()?;
+                self.write_control(control);
+                self.wait_rfne()?;
+                *w = self.read_word();
+            }
+        }
+        self.wait_idle()
+    }
+
+    /// Set the Microwire data direction, returning whether sequential mode is on.
+    fn begin_microwire(&mut self, direction: MicrowireControlMode) -> Result<bool, SpiError> {
+        if self.frame_format() != FrameFormat::NationalMicrowire {
+            return Err(SpiError::WrongFrameFormat);
+        }
+        let mwcr = read_reg!(self.regs, mwcr);
+        if mwcr.microwire_direction() != direction {
+            self.wait_idle()?;
+            unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(false)) };
+            unsafe { modify_reg!(self.regs, mwcr, |r| r.with_microwire_direction(direction)) };
+            unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(true)) };
+        }
+        Ok(mwcr.microwire_mode() == MicrowireTransferMode::Sequential)
+    }
+
+    /// Queue a Microwire control word, truncated to the control frame size.
+    #[inline]
+    fn write_control(&self, control: u16) {
+        let bits = read_ctrlr0(self.regs, self.features)
+            .control_frame_size()
+            .value() as u32
+            + 1;
+        let data = control as u32 & ((1 << bits) - 1);
+        unsafe { modify_reg!(self.regs, dr_ssi_ctrl[0], |r| r.with_data(data)) };
+    }
+
+    /// Receive `buf.len()` frames with the controller generating the clock.
+    ///
+    /// Uses receive-only mode with the frame count in CTRLR1, so after a
+    /// single dummy write the controller clocks in all frames on its own.
+    /// Unlike [`SpiBus::read`](embedded_hal::spi::SpiBus::read), software
+    /// only has to drain the receive FIFO, which keeps up at high clock rates.
+    /// Transfers longer than [`MAX_TRANSFER_FRAMES`] are split. The
+    /// controller's own chip select is released between the parts; use
+    /// [`CsDevice::read_stream`](super::CsDevice::read_stream) for devices
+    /// that must see one continuous read. Returns [`SpiError::FifoOverflow`]
+    /// if frames were lost anyway.
+    pub fn read_only<W: Word>(&mut self, buf: &mut [W]) -> Result<(), SpiError> {
+        self.check_word::<W>()?;
+        self.keeping_transfer_mode(|spi| {
+            for chunk in buf.chunks_mut(MAX_TRANSFER_FRAMES) {
+                spi.start_receive(TransferMode::ReceiveOnly, chunk.len())?;
+                // A write to the data register starts the transfer; the data is ignored.
+                spi.write_word(W::from_u32(0));
+                spi.drain(chunk)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Send a command, then receive `buf.len()` frames, in EEPROM read mode.
+    ///
+    /// The controller shifts out the command and then clocks in the frame
+    /// count programmed in CTRLR1 by itself, so software only has to drain
+    /// the receive FIFO, as with [`read_only`](Self::read_only). Nothing is
+    /// received while the command is sent. The command must fit in the
+    /// transmit FIFO, or the read fails with [`SpiError::BusyTimeout`].
+    ///
+    /// Reads longer than [`MAX_TRANSFER_FRAMES`] are split into several
+    /// EEPROM reads, and chip select is released between them, so each one
+    /// needs its own command. `command` is called with the index in `buf`
+    /// of each part's first frame and returns the command reading from
+    /// there, for a flash the read opcode with the address advanced:
+    ///
+    /// ```ignore
+    /// let mut model = [0u8; 4 << 20];
+    /// spi.eeprom_read(&mut model, |offset| {
+    ///     let [_, a2, a1, a0] = (base + offset as u32).to_be_bytes();
+    ///     [0x03, a2, a1, a0]
+    /// })?;
+    /// ```
+    ///
+    /// An empty command makes the part a receive-only transfer.
+    pub fn eeprom_read<W: Word, C: AsRef<[W]>>(
+        &mut self,
+        buf: &mut [W],
+        mut command: impl FnMut(usize) -> C,
+    ) -> Result<(), SpiError> {
+        self.check_word::<W>()?;
+        self.keeping_transfer_mode(|spi| {
+            for (i, chunk) in buf.chunks_mut(MAX_TRANSFER_FRAMES).enumerate() {
+                let cmd = command(i * MAX_TRANSFER_FRAMES);
+                let cmd = cmd.as_ref();
+                if cmd.is_empty() {
+                    spi.start_receive(TransferMode::ReceiveOnly, chunk.len())?;
+                    spi.write_word(W::from_u32(0));
+                } else {
+                    spi.start_receive(TransferMode::EepromRead, chunk.len())?;
+                    spi.queue_command(cmd)?;
+                }
+                spi.drain(chunk)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Run `f`, then restore the transfer mode the controller was in.
+    fn keeping_transfer_mode(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<(), SpiError>,
+    ) -> Result<(), SpiError> {
+        let mode = read_ctrlr0(self.regs, self.features).transfer_mode();
+        let result = f(self);
+        let _ = self.wait_idle();
+        unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(false)) };
+        modify_ctrlr0(self.regs, self.features, |r| r.with_transfer_mode(mode));
+        unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(true)) };
+        result
+    }
+
+    /// Switch to `mode` with a transfer length of `frames` frames.
+    fn start_receive(&mut self, mode: TransferMode, frames: usize) -> Result<(), SpiError> {
+        self.wait_idle()?;
+        unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(false)) };
+        modify_ctrlr0(self.regs, self.features, |r| r.with_transfer_mode(mode));
         unsafe {
-            regs.ser
-                .modify(|r| r.with_slave_select_enable(u30::new(ser)))
+            modify_reg!(self.regs, ctrlr1, |r| r
+                .with_number_of_data_frames((frames - 1) as u16))
         };
+        unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(true)) };
+        Ok(())
+    }
 
-        // Clear interrupts and enable
-        unsafe { regs.icr.modify(|r| r.with_interrupt_clear(true)) };
-        unsafe { regs.ssienr.modify(|r| r.with_ssi_enable(true)) };
+    /// Queue all of `command` before the transfer starts.
+    ///
+    /// An EEPROM read switches to receiving as soon as the transmit FIFO
+    /// runs empty, so the slaves stay deselected, which holds the transfer
+    /// back, until the whole command is in the FIFO.
+    fn queue_command<W: Word>(&mut self, command: &[W]) -> Result<(), SpiError> {
+        let ser = read_reg!(self.regs, ser);
+        unsafe { modify_reg!(self.regs, ser, |r| r.with_slave_select_enable(u30::new(0))) };
+        let queued = command.iter().try_for_each(|&w| {
+            self.wait_tfnf()?;
+            self.write_word(w);
+            Ok(())
+        });
+        unsafe { write_reg!(self.regs, ser, ser) };
+        queued
+    }
+
+    /// Drop frames left in the receive FIFO by a transmit.
+    pub(super) fn discard_rx(&self) {
+        while read_reg!(self.regs, sr).receive_fifo_not_empty() {
+            let _ = read_reg!(self.regs, dr_ssi_ctrl[0]);
+        }
+    }
+
+    /// Read `buf.len()` frames of a running transfer from the receive FIFO.
+    ///
+    /// Overflow is checked while waiting for each frame: once frames are
+    /// lost the FIFO may never fill up to `buf.len()`, and the wait would
+    /// otherwise time out first.
+    fn drain<W: Word>(&mut self, buf: &mut [W]) -> Result<(), SpiError> {
+        for w in buf.iter_mut() {
+            Timeout::from_micros(self.timeout_us).wait(SpiError::BusyTimeout, || {
+                self.rx_overflowed() || read_reg!(self.regs, sr).receive_fifo_not_empty()
+            })?;
+            self.check_rx_overflow()?;
+            *w = self.read_word();
+        }
+        self.check_rx_overflow()
     }
 
     #[inline]
-    fn wait_tfnf(&self) {
-        while !self.regs.sr.read().transmit_fifo_not_full() {
-            core::hint::spin_loop();
+    fn rx_overflowed(&self) -> bool {
+        read_reg!(self.regs, risr).receive_fifo_overflow_raw_interrupt_status()
+    }
+
+    /// Returns [`SpiError::FifoOverflow`] and clears the status if the
+    /// receive FIFO has overflowed.
+    fn check_rx_overflow(&self) -> Result<(), SpiError> {
+        if self.rx_overflowed() {
+            // Reading RXOICR clears the overflow status.
+            let _ = read_reg!(self.regs, rxoicr);
+            return Err(SpiError::FifoOverflow);
         }
+        Ok(())
+    }
+
+    /// Change the receive data sampling point.
+    pub fn set_rx_sampling(&mut self, sampling: RxSampling) {
+        let _ = self.wait_idle();
+        unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(false)) };
+        write_rx_sampling(self.regs, sampling);
+        unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(true)) };
     }
 
+    /// Find the best receive data sampling point.
+    ///
+    /// Scans delays `0..=max_delay` on both clock edges, calling `probe` at
+    /// each setting. `probe` reads a known pattern, e.g. a flash JEDEC ID, and
+    /// returns whether it was received correctly. The setting in the middle
+    /// of the widest passing window is applied and returned. If no setting
+    /// passes, the previous one is restored and `None` is returned.
+    pub fn calibrate<E>(
+        &mut self,
+        max_delay: u8,
+        mut probe: impl FnMut(&mut Self) -> Result<bool, E>,
+    ) -> Result<Option<RxSampling>, E> {
+        let previous = read_rx_sampling(self.regs);
+        // Best window so far as (edge, first delay, length).
+        let mut best: Option<(SampleEdge, u8, u16)> = None;
+        for edge in [SampleEdge::Rising, SampleEdge::Falling] {
+            let mut start = 0;
+            let mut len = 0u16;
+            for delay in 0..=max_delay {
+                self.set_rx_sampling(RxSampling { delay, edge });
+                let pass = match probe(self) {
+                    Ok(pass) => pass,
+                    Err(e) => {
+                        self.set_rx_sampling(previous);
+                        return Err(e);
+                    }
+                };
+                if pass {
+                    if len == 0 {
+                        start = delay;
+                    }
+                    len += 1;
+                    if best.is_none_or(|(_, _, best_len)| len > best_len) {
+                        best = Some((edge, start, len));
+                    }
+                } else {
+                    len = 0;
+                }
+            }
+        }
+        let sampling = best.map(|(edge, start, len)| RxSampling {
+            delay: start + ((len - 1) / 2) as u8,
+            edge,
+        });
+        self.set_rx_sampling(sampling.unwrap_or(previous));
+        Ok(sampling)
+    }
+
+    /// Send `write`, then receive `read.len()` frames, as one transfer.
+    ///
+    /// For command/response devices: the frames received while `write` is
+    /// sent are dropped and zeros are sent while `read` is filled.
+    pub fn write_then_read<W: Word>(
+        &mut self,
+        write: &[W],
+        read: &mut [W],
+    ) -> Result<(), SpiError> {
+        self.check_word::<W>()?;
+        let skip = write.len();
+        self.exchange(
+            skip + read.len(),
+            |i| write.get(i).copied().unwrap_or(W::from_u32(0)),
+            |i, w| {
+                if let Some(r) = i.checked_sub(skip).and_then(|i| read.get_mut(i)) {
+                    *r = w;
+                }
+            },
+        )?;
+        self.wait_idle()
+    }
+
+    /// Clock `len` frames, sending `tx(i)` as frame `i` and passing the frame
+    /// received with it to `rx`.
+    #[inline]
+    pub(super) fn exchange<W: Word>(
+        &mut self,
+        len: usize,
+        mut tx: impl FnMut(usize) -> W,
+        mut rx: impl FnMut(usize, W),
+    ) -> Result<(), SpiError> {
+        for i in 0..len {
+            self.wait_tfnf()?;
+            self.write_word(tx(i));
+            self.wait_rfne()?;
+            rx(i, self.read_word());
+        }
+        Ok(())
+    }
+
+    /// Check that frames of the configured size fit in word type `W`.
     #[inline]
-    fn wait_rfne(&self) {
-        while !self.regs.sr.read().receive_fifo_not_empty() {
-            core::hint::spin_loop();
+    pub(super) fn check_word<W: Word>(&self) -> Result<(), SpiError> {
+        if self.data_bits > W::BITS {
+            Err(SpiError::InvalidWordSize)
+        } else {
+            Ok(())
         }
     }
 
+    /// Mask of the valid bits in a data frame.
     #[inline]
-    fn wait_idle(&self) {
-        while self.regs.sr.read().busy() {
-            core::hint::spin_loop();
+    pub(super) fn frame_mask(&self) -> u32 {
+        match self.data_bits {
+            32.. => u32::MAX,
+            n => (1 << n) - 1,
         }
     }
+
+    #[inline]
+    pub(super) fn write_word<W: Word>(&self, word: W) {
+        let data = word.into_u32() & self.frame_mask();
+        unsafe { modify_reg!(self.regs, dr_ssi_ctrl[0], |r| r.with_data(data)) };
+    }
+
+    #[inline]
+    pub(super) fn read_word<W: Word>(&self) -> W {
+        W::from_u32(read_reg!(self.regs, dr_ssi_ctrl[0]).data() & self.frame_mask())
+    }
+
+    #[inline]
+    fn wait_tfnf(&self) -> Result<(), SpiError> {
+        Timeout::from_micros(self.timeout_us).wait(SpiError::BusyTimeout, || {
+            read_reg!(self.regs, sr).transmit_fifo_not_full()
+        })
+    }
+
+    #[inline]
+    fn wait_rfne(&self) -> Result<(), SpiError> {
+        Timeout::from_micros(self.timeout_us).wait(SpiError::BusyTimeout, || {
+            read_reg!(self.regs, sr).receive_fifo_not_empty()
+        })
+    }
+
+    #[inline]
+    pub(super) fn wait_idle(&self) -> Result<(), SpiError> {
+        Timeout::from_micros(self.timeout_us)
+            .wait(SpiError::BusyTimeout, || !read_reg!(self.regs, sr).busy())
+    }
 }
 
 impl embedded_hal::spi::ErrorType for Spi<'_> {
     type Error = SpiError;
 }
 
-impl embedded_hal::spi::SpiBus<u8> for Spi<'_> {
-    fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+impl<W: Word> embedded_hal::spi::SpiBus<W> for Spi<'_> {
+    fn read(&mut self, words: &mut [W]) -> Result<(), Self::Error> {
+        self.check_word::<W>()?;
         for b in words.iter_mut() {
             // write dummy to generate clock
-            self.wait_tfnf();
-            unsafe { self.regs.dr_ssi_ctrl[0].modify(|r| r.with_data(0)) };
-            self.wait_rfne();
-            *b = self.regs.dr_ssi_ctrl[0].read().data() as u8;
+            self.wait_tfnf()?;
+            self.write_word(W::from_u32(0));
+            self.wait_rfne()?;
+            *b = self.read_word();
         }
         Ok(())
     }
 
-    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+    fn write(&mut self, words: &[W]) -> Result<(), Self::Error> {
+        self.check_word::<W>()?;
         for &b in words.iter() {
-            self.wait_tfnf();
-            unsafe { self.regs.dr_ssi_ctrl[0].modify(|r| r.with_data(b as u32)) };
+            self.wait_tfnf()?;
+            self.write_word(b);
             // read and drop if data is received to keep FIFO balanced in full-duplex
-            if self.regs.sr.read().receive_fifo_not_empty() {
-                let _ = self.regs.dr_ssi_ctrl[0].read().data();
+            if read_reg!(self.regs, sr).receive_fifo_not_empty() {
+                let _ = read_reg!(self.regs, dr_ssi_ctrl[0]).data();
             }
         }
-        self.wait_idle();
+        self.wait_idle()?;
         Ok(())
     }
 
-    fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
-        assert_eq!(read.len(), write.len());
-        for (rb, &wb) in read.iter_mut().zip(write.iter()) {
-            self.wait_tfnf();
-            unsafe { self.regs.dr_ssi_ctrl[0].modify(|r| r.with_data(wb as u32)) };
-            self.wait_rfne();
-            *rb = self.regs.dr_ssi_ctrl[0].read().data() as u8;
-        }
-        Ok(())
+    /// Clocks `max(read.len(), write.len())` frames. Frames past the end of
+    /// `write` are sent as zero and frames past the end of `read` are dropped.
+    fn transfer(&mut self, read: &mut [W], write: &[W]) -> Result<(), Self::Error> {
+        self.check_word::<W>()?;
+        self.exchange(
+            read.len().max(write.len()),
+            |i| write.get(i).copied().unwrap_or(W::from_u32(0)),
+            |i, w| {
+                if let Some(r) = read.get_mut(i) {
+                    *r = w;
+                }
+            },
+        )
     }
 
-    fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+    fn transfer_in_place(&mut self, words: &mut [W]) -> Result<(), Self::Error> {
+        self.check_word::<W>()?;
         for w in words.iter_mut() {
             let wb = *w;
-            self.wait_tfnf();
-            unsafe { self.regs.dr_ssi_ctrl[0].modify(|r| r.with_data(wb as u32)) };
-            self.wait_rfne();
-            *w = self.regs.dr_ssi_ctrl[0].read().data() as u8;
+            self.wait_tfnf()?;
+            self.write_word(wb);
+            self.wait_rfne()?;
+            *w = self.read_word();
         }
         Ok(())
     }
 
     fn flush(&mut self) -> Result<(), Self::Error> {
-        self.wait_idle();
-        Ok(())
+        self.wait_idle()
     }
 }
 
-impl embedded_hal_nb::spi::FullDuplex<u8> for Spi<'_> {
-    fn read(&mut self) -> embedded_hal_nb::nb::Result<u8, Self::Error> {
-        if self.regs.sr.read().receive_fifo_not_empty() {
-            Ok(self.regs.dr_ssi_ctrl[0].read().data() as u8)
+impl<W: Word> embedded_hal_nb::spi::FullDuplex<W> for Spi<'_> {
+    fn read(&mut self) -> embedded_hal_nb::nb::Result<W, Self::Error> {
+        self.check_word::<W>()?;
+        if read_reg!(self.regs, sr).receive_fifo_not_empty() {
+            Ok(self.read_word())
         } else {
             Err(embedded_hal_nb::nb::Error::WouldBlock)
         }
     }
 
-    fn write(&mut self, word: u8) -> embedded_hal_nb::nb::Result<(), Self::Error> {
-        if self.regs.sr.read().transmit_fifo_not_full() {
-            unsafe { self.regs.dr_ssi_ctrl[0].modify(|r| r.with_data(word as u32)) };
+    fn write(&mut self, word: W) -> embedded_hal_nb::nb::Result<(), Self::Error> {
+        self.check_word::<W>()?;
+        if read_reg!(self.regs, sr).transmit_fifo_not_full() {
+            self.write_word(word);
             Ok(())
         } else {
             Err(embedded_hal_nb::nb::Error::WouldBlock)
@@ -355,32 +1092,308 @@ impl embedded_hal_nb::spi::FullDuplex<u8> for Spi<'_> {
     }
 }
 
-impl embedded_hal::spi::SpiDevice<u8> for Spi<'_> {
-    fn transaction<'a>(
+impl Spi<'_> {
+    /// Run one operation of a [`CsDevice`](super::CsDevice) transaction.
+    pub(super) fn run_operation<W: Word>(
         &mut self,
-        operations: &mut [embedded_hal::spi::Operation<'a, u8>],
-    ) -> Result<(), Self::Error> {
-        for op in operations {
-            match op {
-                embedded_hal::spi::Operation::Read(buf) => {
-                    embedded_hal::spi::SpiBus::read(self, buf)?
-                }
-                embedded_hal::spi::Operation::Write(buf) => {
-                    embedded_hal::spi::SpiBus::write(self, buf)?
-                }
-                embedded_hal::spi::Operation::Transfer(read, write) => {
-                    embedded_hal::spi::SpiBus::transfer(self, read, write)?
-                }
-                embedded_hal::spi::Operation::TransferInPlace(buf) => {
-                    embedded_hal::spi::SpiBus::transfer_in_place(self, buf)?
-                }
-                embedded_hal::spi::Operation::DelayNs(delay) => {
-                    for _ in 0..*delay {
-                        core::hint::spin_loop();
-                    }
+        op: &mut embedded_hal::spi::Operation<'_, W>,
+    ) -> Result<(), SpiError> {
+        use embedded_hal::spi::{Operation, SpiBus};
+        match op {
+            Operation::Read(buf) => SpiBus::read(self, buf),
+            Operation::Write(buf) => SpiBus::write(self, buf),
+            Operation::Transfer(read, write) => SpiBus::transfer(self, read, write),
+            Operation::TransferInPlace(buf) => SpiBus::transfer_in_place(self, buf),
+            Operation::DelayNs(ns) => {
+                // The delay counts from the end of the previous operation.
+                self.wait_idle()?;
+                let ticks = *ns as u64 * soc::TIMER_FREQUENCY as u64 / 1_000_000_000;
+                let delay = Timeout::from_ticks(ticks.max(1));
+                while !delay.is_expired() {
+                    core::hint::spin_loop();
                 }
+                Ok(())
             }
         }
-        Ok(())
+    }
+}
+
+/// Encode a data frame size in bits for CTRLR0.DFS.
+///
+/// The K230 SSI is configured with a maximum transfer size of 32 bits, so the
+/// frame size is held entirely in the 5-bit DFS field and encoded as `n - 1`.
+/// Sizes are clamped to the supported 4..=32 range.
+#[inline]
+fn data_frame_size(data_bits: u8) -> u5 {
+    u5::new(clamp_data_bits(data_bits) - 1)
+}
+
+/// Clamp a data frame size to the 4..=32 bits the controller supports.
+#[inline]
+fn clamp_data_bits(data_bits: u8) -> u8 {
+    data_bits.clamp(4, 32)
+}
+
+/// Divider for BAUDR.SCKDV dividing `src_clock_hz` down to about `frequency`.
+///
+/// The serial clock is `src_clock_hz / (2 * sckdv)`, so the whole divisor
+/// is rounded up to an even value of at least 2.
+#[inline]
+fn clock_divider(src_clock_hz: u32, frequency: u32) -> u15 {
+    let div2 = (src_clock_hz / frequency.max(1)).max(2);
+    let div2 = div2 + div2 % 2;
+    u15::new((div2 / 2).min(u15::MAX.value() as u32) as u16)
+}
+
+/// Read CTRLR0, converting from the legacy layout on instances that use it.
+#[inline]
+pub(super) fn read_ctrlr0(regs: &RegisterBlock, features: SpiFeatures) -> ControlReg0 {
+    let value = read_reg!(regs, ctrlr0);
+    if features.legacy_ctrlr0 {
+        ControlReg0::from_legacy(value.raw_value())
+    } else {
+        value
+    }
+}
+
+/// Update CTRLR0 with `f`, converting to and from the legacy layout on
+/// instances that use it; the controller must be disabled.
+#[inline]
+pub(super) fn modify_ctrlr0(
+    regs: &RegisterBlock,
+    features: SpiFeatures,
+    f: impl FnOnce(ControlReg0) -> ControlReg0,
+) {
+    if features.legacy_ctrlr0 {
+        unsafe {
+            modify_reg!(regs, ctrlr0, |r| {
+                ControlReg0::new_with_raw_value(
+                    f(ControlReg0::from_legacy(r.raw_value())).to_legacy(),
+                )
+            })
+        };
+    } else {
+        unsafe { modify_reg!(regs, ctrlr0, f) };
+    }
+}
+
+/// Program the frame format and Microwire control; the controller must be disabled.
+fn write_frame_format(
+    regs: &RegisterBlock,
+    features: SpiFeatures,
+    format: FrameFormat,
+    microwire: MicrowireConfig,
+) {
+    let format = match format {
+        FrameFormat::Reserved => FrameFormat::MotorolaSpi,
+        format => format,
+    };
+    let cfs = u4::new(microwire.control_bits.clamp(1, 16) - 1);
+    modify_ctrlr0(regs, features, |r| {
+        r.with_frame_format(format).with_control_frame_size(cfs)
+    });
+    let mode = if microwire.sequential {
+        MicrowireTransferMode::Sequential
+    } else {
+        MicrowireTransferMode::NonSequential
+    };
+    unsafe {
+        modify_reg!(regs, mwcr, |r| {
+            r.with_microwire_mode(mode)
+                .with_microwire_handshaking(microwire.handshaking)
+        })
+    };
+}
+
+/// Program the receive sampling point; the controller must be disabled.
+#[inline]
+fn write_rx_sampling(regs: &RegisterBlock, sampling: RxSampling) {
+    unsafe {
+        modify_reg!(regs, rx_sample_delay, |r| {
+            r.with_rx_sample_delay(sampling.delay)
+                .with_rx_sampling_edge(sampling.edge == SampleEdge::Falling)
+        })
+    };
+}
+
+#[inline]
+fn read_rx_sampling(regs: &RegisterBlock) -> RxSampling {
+    let r = read_reg!(regs, rx_sample_delay);
+    RxSampling {
+        delay: r.rx_sample_delay(),
+        edge: if r.rx_sampling_edge() {
+            SampleEdge::Falling
+        } else {
+            SampleEdge::Rising
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::mock;
+    use std::vec::Vec;
+
+    fn configured(cfg: Config) -> &'static RegisterBlock {
+        let regs = unsafe { &*mock::block::<RegisterBlock>() };
+        Spi::configure(
+            regs,
+            soc::spi::<0>(),
+            Clocks.frequency(ClockId::SpiSclk(0)).0,
+            cfg,
+        );
+        regs
+    }
+
+    #[test]
+    fn configure_frame_format() {
+        let regs = configured(Config {
+            mode: embedded_hal::spi::MODE_3,
+            data_bits: 16,
+            ss_index: 2,
+            rx_sampling: RxSampling {
+                delay: 3,
+                edge: SampleEdge::Falling,
+            },
+            ..Config::default()
+        });
+        let ctrlr0 = read_ctrlr0(regs, soc::spi::<0>());
+        assert_eq!(ctrlr0.frame_format(), FrameFormat::MotorolaSpi);
+        assert_eq!(ctrlr0.serial_clock_polarity(), SerialClockPolarity::High);
+        assert_eq!(ctrlr0.serial_clock_phase(), SerialClockPhase::Start);
+        assert_eq!(ctrlr0.transfer_mode(), TransferMode::TransmitAndReceive);
+        assert_eq!(ctrlr0.ssi_is_master(), WorkingMode::Master);
+        assert_eq!(ctrlr0.data_frame_size(), u5::new(15));
+        assert_eq!(regs.ser.read().slave_select_enable(), u30::new(1 << 2));
+        assert_eq!(
+            read_rx_sampling(regs),
+            RxSampling {
+                delay: 3,
+                edge: SampleEdge::Falling
+            }
+        );
+        assert!(regs.ssienr.read().ssi_enable());
+    }
+
+    #[test]
+    fn configure_microwire() {
+        let regs = configured(Config {
+            frame_format: FrameFormat::NationalMicrowire,
+            microwire: MicrowireConfig {
+                control_bits: 12,
+                sequential: true,
+                handshaking: true,
+            },
+            ..Config::default()
+        });
+        let ctrlr0 = read_ctrlr0(regs, soc::spi::<0>());
+        assert_eq!(ctrlr0.frame_format(), FrameFormat::NationalMicrowire);
+        assert_eq!(ctrlr0.control_frame_size(), u4::new(11));
+        let mwcr = regs.mwcr.read();
+        assert_eq!(mwcr.microwire_mode(), MicrowireTransferMode::Sequential);
+        assert!(mwcr.microwire_handshaking());
+    }
+
+    #[test]
+    #[cfg(not(any(feature = "k510", feature = "k210")))]
+    fn configure_clock_divider() {
+        // The 50 MHz source is divided by an even value of at least 2.
+        for (frequency, sckdv) in [(1_000_000, 25), (7_000_000, 4), (100_000_000, 1)] {
+            let regs = configured(Config {
+                frequency,
+                ..Config::default()
+            });
+            assert_eq!(regs.baudr.read().ssi_clock_divider(), u15::new(sckdv));
+        }
+    }
+
+    #[test]
+    fn context_restore() {
+        let regs = configured(Config {
+            mode: embedded_hal::spi::MODE_3,
+            data_bits: 16,
+            ss_index: 1,
+            ..Config::default()
+        });
+        let context = Context::save(regs);
+
+        let fresh = unsafe { &*mock::block::<RegisterBlock>() };
+        context.restore(fresh);
+        assert_eq!(
+            fresh.ctrlr0.read().raw_value(),
+            regs.ctrlr0.read().raw_value()
+        );
+        assert_eq!(
+            fresh.baudr.read().raw_value(),
+            regs.baudr.read().raw_value()
+        );
+        assert_eq!(fresh.ser.read().slave_select_enable(), u30::new(1 << 1));
+        assert_eq!(read_rx_sampling(fresh), read_rx_sampling(regs));
+        assert!(fresh.ssienr.read().ssi_enable());
+    }
+
+    #[test]
+    fn data_bits_clamped() {
+        let regs = unsafe { &*mock::block::<RegisterBlock>() };
+        let mut spi = unsafe {
+            Spi::from_regs_with_src_clock(
+                regs,
+                50_000_000,
+                Config {
+                    data_bits: 40,
+                    ..Config::default()
+                },
+            )
+        };
+        assert_eq!(spi.data_bits(), 32);
+        assert_eq!(spi.frame_mask(), u32::MAX);
+        spi.set_data_bits(2);
+        assert_eq!(spi.data_bits(), 4);
+        assert_eq!(regs.ctrlr0.read().data_frame_size(), u5::new(3));
+        assert_eq!(spi.frame_mask(), 0xF);
+    }
+
+    #[test]
+    fn eeprom_read_chunks() {
+        let regs = unsafe { &*mock::block::<RegisterBlock>() };
+        let mut spi = unsafe { Spi::from_regs_with_src_clock(regs, 50_000_000, Config::default()) };
+        // Not busy, transmit FIFO not full, receive FIFO not empty.
+        unsafe { regs.sr.write(StatusReg::new_with_raw_value(0b1010)) };
+
+        let mut buf = std::vec![0u8; MAX_TRANSFER_FRAMES + 3];
+        let mut offsets = Vec::new();
+        spi.eeprom_read(&mut buf[..], |offset| {
+            offsets.push(offset);
+            [0x03, (offset >> 16) as u8]
+        })
+        .unwrap();
+        assert_eq!(offsets, [0, MAX_TRANSFER_FRAMES]);
+        // The mock data register reads back the last command frame.
+        assert_eq!(buf[0], 0);
+        assert_eq!(buf[MAX_TRANSFER_FRAMES], 1);
+        assert_eq!(regs.ctrlr1.read().number_of_data_frames(), 2);
+        assert_eq!(
+            regs.ctrlr0.read().transfer_mode(),
+            TransferMode::TransmitAndReceive
+        );
+        assert_eq!(regs.ser.read().slave_select_enable(), u30::new(1));
+    }
+
+    #[test]
+    fn drain_reports_overflow_before_timeout() {
+        let regs = unsafe { &*mock::block::<RegisterBlock>() };
+        let mut spi = unsafe { Spi::from_regs_with_src_clock(regs, 50_000_000, Config::default()) };
+        // Receive FIFO empty, but frames were lost.
+        unsafe { regs.sr.write(StatusReg::new_with_raw_value(0b0010)) };
+        unsafe {
+            regs.risr
+                .write(RawInterruptStatusReg::new_with_raw_value(1 << 3))
+        };
+
+        let mut buf = [0u8; 4];
+        assert_eq!(spi.read_only(&mut buf[..]), Err(SpiError::FifoOverflow));
     }
 }
@@ -0,0 +1,429 @@
+use crate::spi::driver::{Spi, SpiError};
+use crate::spi::register::RegisterBlock;
+use crate::time::Timeout;
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// Upper bound on frames written but not yet read back.
+///
+/// Keeps the receive FIFO from overflowing while the interrupt handler is
+/// delayed; every DesignWare SSI configuration used on Kendryte chips has
+/// deeper FIFOs than this.
+const MAX_IN_FLIGHT: usize = 8;
+
+const IDLE: u8 = 0;
+const RUNNING: u8 = 1;
+const DONE: u8 = 2;
+const FAILED: u8 = 3;
+
+/// Progress of the transfer owned by the interrupt handler.
+struct Engine {
+    write: *const u8,
+    read: *mut u8,
+    len: usize,
+    tx_pos: usize,
+    rx_pos: usize,
+    frame_mask: u32,
+    error: Option<SpiError>,
+}
+
+/// Transfer state shared between an [`InterruptSpi`] and its interrupt handler.
+///
+/// Place one in a `static` per SPI instance and call
+/// [`SpiTransferState::on_interrupt`] from the instance's interrupt handler.
+pub struct SpiTransferState {
+    status: AtomicU8,
+    engine: UnsafeCell<Engine>,
+    callback: Option<fn(Result<(), SpiError>)>,
+}
+
+// SAFETY: the engine is only accessed by the interrupt handler while the
+// status is RUNNING, and only by the driver otherwise.
+unsafe impl Sync for SpiTransferState {}
+
+impl SpiTransferState {
+    /// Creates a new idle state.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            status: AtomicU8::new(IDLE),
+            engine: UnsafeCell::new(Engine {
+                write: core::ptr::null(),
+                read: core::ptr::null_mut(),
+                len: 0,
+                tx_pos: 0,
+                rx_pos: 0,
+                frame_mask: 0,
+                error: None,
+            }),
+            callback: None,
+        }
+    }
+
+    /// Creates a new idle state calling `callback` when a transfer finishes.
+    ///
+    /// The callback runs in interrupt context with the transfer result.
+    #[inline]
+    pub const fn with_callback(callback: fn(Result<(), SpiError>)) -> Self {
+        let mut state = Self::new();
+        state.callback = Some(callback);
+        state
+    }
+
+    /// Handles an SPI interrupt.
+    ///
+    /// Drains the receive FIFO, refills the transmit FIFO and finishes the
+    /// transfer once every frame has been read back.
+    #[cfg_attr(
+        feature = "ramfunc",
+        unsafe(link_section = ".ramfunc.spi_on_interrupt")
+    )]
+    pub fn on_interrupt(&self, regs: &RegisterBlock) {
+        if self.status.load(Ordering::Acquire) != RUNNING {
+            mask_all(regs);
+            return;
+        }
+        // SAFETY: the status is RUNNING, so the driver does not touch the engine.
+        let engine = unsafe { &mut *self.engine.get() };
+        service(regs, engine);
+        let finished = engine.error.is_some() || engine.rx_pos == engine.len;
+        if !finished {
+            return;
+        }
+        mask_all(regs);
+        let result = match engine.error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        };
+        let status = if result.is_ok() { DONE } else { FAILED };
+        self.status.store(status, Ordering::Release);
+        if let Some(callback) = self.callback {
+            callback(result);
+        }
+    }
+}
+
+impl Default for SpiTransferState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Buffers of a finished [`InterruptSpi`] transfer.
+pub struct Completion {
+    /// Buffer the received frames were stored in.
+    pub read: &'static mut [u8],
+    /// Buffer the transmitted frames were taken from.
+    pub write: &'static [u8],
+    /// Outcome of the transfer.
+    pub result: Result<(), SpiError>,
+}
+
+/// Interrupt driven SPI master for `u8` words.
+///
+/// The transmit FIFO is refilled and the receive FIFO drained from the TXE
+/// and RXF interrupts, so the core is free while a transfer runs. Transfers
+/// are started with [`start_transfer`](Self::start_transfer) and collected
+/// with [`poll_complete`](Self::poll_complete), or run to completion with
+/// [`transfer`](Self::transfer).
+pub struct InterruptSpi<'i> {
+    spi: Spi<'i>,
+    state: &'static SpiTransferState,
+    buffers: Option<(*mut u8, *const u8, usize)>,
+}
+
+impl<'i> InterruptSpi<'i> {
+    /// Wraps a configured blocking driver.
+    ///
+    /// The SPI interrupt must be routed to a handler calling
+    /// [`SpiTransferState::on_interrupt`] with the same `state`.
+    #[inline]
+    pub fn new(spi: Spi<'i>, state: &'static SpiTransferState) -> Self {
+        Self {
+            spi,
+            state,
+            buffers: None,
+        }
+    }
+
+    /// Returns the underlying blocking driver, aborting any running transfer.
+    pub fn into_blocking(mut self) -> Spi<'i> {
+        self.abort();
+        self.spi
+    }
+
+    /// Returns true while a transfer is running.
+    #[inline]
+    pub fn is_busy(&self) -> bool {
+        self.state.status.load(Ordering::Acquire) == RUNNING
+    }
+
+    /// Starts a full-duplex transfer of `write.len()` frames in the background.
+    ///
+    /// The buffers are handed back by [`poll_complete`](Self::poll_complete).
+    /// Fails with [`SpiError::TransferInProgress`] if the previous transfer
+    /// has not been collected yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `read` and `write` have different lengths.
+    pub fn start_transfer(
+        &mut self,
+        read: &'static mut [u8],
+        write: &'static [u8],
+    ) -> Result<(), SpiError> {
+        assert_eq!(read.len(), write.len());
+        self.spi.check_word::<u8>()?;
+        if self.buffers.is_some() {
+            return Err(SpiError::TransferInProgress);
+        }
+        let len = write.len();
+        // SAFETY: the buffers are 'static and only handed back once the
+        // interrupt handler has finished with them.
+        unsafe { self.start(read.as_mut_ptr(), write.as_ptr(), len) };
+        self.buffers = Some((read.as_mut_ptr(), write.as_ptr(), len));
+        Ok(())
+    }
+
+    /// Collects the transfer started with [`start_transfer`](Self::start_transfer).
+    ///
+    /// Returns `None` while the transfer is running or if none was started.
+    pub fn poll_complete(&mut self) -> Option<Completion> {
+        let (read, write, len) = self.buffers?;
+        let result = self.take_result()?;
+        self.buffers = None;
+        // SAFETY: the pointers come from the 'static slices passed to
+        // `start_transfer`, which the interrupt handler no longer uses.
+        let (read, write) = unsafe {
+            (
+                core::slice::from_raw_parts_mut(read, len),
+                core::slice::from_raw_parts(write, len),
+            )
+        };
+        Some(Completion {
+            read,
+            write,
+            result,
+        })
+    }
+
+    /// Runs a full-duplex transfer and waits for it to finish.
+    ///
+    /// The wait is bounded by the driver timeout for each frame; on expiry
+    /// the transfer is aborted and [`SpiError::BusyTimeout`] is returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `read` and `write` have different lengths.
+    pub fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), SpiError> {
+        assert_eq!(read.len(), write.len());
+        self.spi.check_word::<u8>()?;
+        if self.buffers.is_some() {
+            return Err(SpiError::TransferInProgress);
+        }
+        // SAFETY: the transfer is finished or aborted before the borrows end.
+        unsafe { self.start(read.as_mut_ptr(), write.as_ptr(), write.len()) };
+        let frames = u32::try_from(write.len()).unwrap_or(u32::MAX);
+        let timeout = Timeout::from_micros(self.spi.timeout_us.saturating_mul(frames));
+        if timeout
+            .wait(SpiError::BusyTimeout, || !self.is_busy())
+            .is_err()
+        {
+            self.abort();
+            return Err(SpiError::BusyTimeout);
+        }
+        self.take_result().unwrap_or(Ok(()))
+    }
+
+    /// Stops a running transfer and drops its buffers.
+    ///
+    /// Frames the transfer left in the FIFOs are discarded, so they do not
+    /// leak into the next one.
+    fn abort(&mut self) {
+        mask_all(self.spi.regs);
+        flush_fifos(self.spi.regs);
+        self.state.status.store(IDLE, Ordering::Release);
+        self.buffers = None;
+    }
+
+    /// Takes the result of a finished transfer, leaving the state idle.
+    fn take_result(&mut self) -> Option<Result<(), SpiError>> {
+        match self.state.status.load(Ordering::Acquire) {
+            DONE => {
+                self.state.status.store(IDLE, Ordering::Release);
+                Some(Ok(()))
+            }
+            FAILED => {
+                // SAFETY: the interrupt handler is done with the engine.
+                let error = unsafe { (*self.state.engine.get()).error };
+                self.state.status.store(IDLE, Ordering::Release);
+                Some(Err(error.unwrap_or(SpiError::FifoOverflow)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Arms the engine and unmasks the FIFO interrupts.
+    ///
+    /// # Safety
+    ///
+    /// `read` and `write` must be valid for `len` frames until the transfer
+    /// finishes or is aborted.
+    unsafe fn start(&mut self, read: *mut u8, write: *const u8, len: usize) {
+        let regs = self.spi.regs;
+        // Drop frames and a stale overflow left by an earlier transfer.
+        flush_fifos(regs);
+        let _ = read_reg!(regs, rxoicr);
+        // SAFETY: the status is not RUNNING, so the interrupt handler does
+        // not touch the engine.
+        let engine = unsafe { &mut *self.state.engine.get() };
+        *engine = Engine {
+            write,
+            read,
+            len,
+            tx_pos: 0,
+            rx_pos: 0,
+            frame_mask: self.spi.frame_mask(),
+            error: None,
+        };
+        if len == 0 {
+            self.state.status.store(DONE, Ordering::Release);
+            return;
+        }
+        self.state.status.store(RUNNING, Ordering::Release);
+        // TXE fires once the transmit FIFO drains to TXFTLR and RXF once the
+        // receive FIFO exceeds RXFTLR; both thresholds are 0.
+        unsafe {
+            modify_reg!(regs, imr, |r| {
+                r.with_transmit_fifo_empty_interrupt_mask(true)
+                    .with_receive_fifo_full_interrupt_mask(true)
+            })
+        };
+    }
+}
+
+/// Moves frames between the FIFOs and the transfer buffers.
+#[cfg_attr(feature = "ramfunc", unsafe(link_section = ".ramfunc.spi_service"))]
+fn service(regs: &RegisterBlock, engine: &mut Engine) {
+    if read_reg!(regs, risr).receive_fifo_overflow_raw_interrupt_status() {
+        // Reading RXOICR clears the overflow status.
+        let _ = read_reg!(regs, rxoicr);
+        engine.error = Some(SpiError::FifoOverflow);
+        return;
+    }
+    while engine.rx_pos < engine.tx_pos && read_reg!(regs, sr).receive_fifo_not_empty() {
+        let data = (read_reg!(regs, dr_ssi_ctrl[0]).data() & engine.frame_mask) as u8;
+        // SAFETY: rx_pos < len, and the driver keeps the buffer alive.
+        unsafe { engine.read.add(engine.rx_pos).write(data) };
+        engine.rx_pos += 1;
+    }
+    while engine.tx_pos < engine.len
+        && engine.tx_pos - engine.rx_pos < MAX_IN_FLIGHT
+        && read_reg!(regs, sr).transmit_fifo_not_full()
+    {
+        // SAFETY: tx_pos < len, and the driver keeps the buffer alive.
+        let data = unsafe { engine.write.add(engine.tx_pos).read() } as u32;
+        unsafe {
+            modify_reg!(regs, dr_ssi_ctrl[0], |r| r
+                .with_data(data & engine.frame_mask));
+        }
+        engine.tx_pos += 1;
+    }
+    if engine.tx_pos == engine.len {
+        // Nothing left to send; only received frames drive the transfer now.
+        unsafe {
+            modify_reg!(regs, imr, |r| r
+                .with_transmit_fifo_empty_interrupt_mask(false))
+        };
+    }
+}
+
+/// Mask the FIFO interrupts used by [`InterruptSpi`].
+#[inline]
+fn mask_all(regs: &RegisterBlock) {
+    unsafe {
+        modify_reg!(regs, imr, |r| {
+            r.with_transmit_fifo_empty_interrupt_mask(false)
+                .with_receive_fifo_full_interrupt_mask(false)
+        })
+    };
+}
+
+/// Empty both FIFOs.
+///
+/// The controller clears its FIFOs while SSIENR is disabled; the previous
+/// enable state is restored.
+fn flush_fifos(regs: &RegisterBlock) {
+    let ssienr = read_reg!(regs, ssienr);
+    unsafe {
+        modify_reg!(regs, ssienr, |r| r.with_ssi_enable(false));
+        write_reg!(regs, ssienr, ssienr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::mock;
+    use crate::spi::driver::Config;
+    use crate::spi::register::{RawInterruptStatusReg, StatusReg};
+    use std::boxed::Box;
+
+    fn interrupt_spi() -> (&'static RegisterBlock, InterruptSpi<'static>) {
+        let regs = unsafe { &*mock::block::<RegisterBlock>() };
+        let spi = unsafe { Spi::from_regs_with_src_clock(regs, 50_000_000, Config::default()) };
+        // Not busy, transmit FIFO not full.
+        unsafe { regs.sr.write(StatusReg::new_with_raw_value(0b0010)) };
+        let state = Box::leak(Box::new(SpiTransferState::new()));
+        (regs, InterruptSpi::new(spi, state))
+    }
+
+    fn buffers(len: usize) -> (&'static mut [u8], &'static [u8]) {
+        (std::vec![0; len].leak(), std::vec![0xA5; len].leak())
+    }
+
+    #[test]
+    fn abort_flushes_and_masks() {
+        let (regs, mut spi) = interrupt_spi();
+        let (read, write) = buffers(4);
+        spi.start_transfer(read, write).unwrap();
+        assert!(spi.is_busy());
+        assert!(regs.imr.read().transmit_fifo_empty_interrupt_mask());
+
+        let _spi = spi.into_blocking();
+        let imr = regs.imr.read();
+        assert!(!imr.transmit_fifo_empty_interrupt_mask());
+        assert!(!imr.receive_fifo_full_interrupt_mask());
+        // The FIFO flush leaves the controller enabled.
+        assert!(regs.ssienr.read().ssi_enable());
+    }
+
+    #[test]
+    fn transfer_after_overflow_starts_clean() {
+        let (regs, mut spi) = interrupt_spi();
+        let state = spi.state;
+        let (read, write) = buffers(4);
+        spi.start_transfer(read, write).unwrap();
+
+        unsafe {
+            regs.risr
+                .write(RawInterruptStatusReg::new_with_raw_value(1 << 3))
+        };
+        state.on_interrupt(regs);
+        let completion = spi.poll_complete().unwrap();
+        assert_eq!(completion.result, Err(SpiError::FifoOverflow));
+
+        unsafe {
+            regs.risr
+                .write(RawInterruptStatusReg::new_with_raw_value(0))
+        };
+        spi.start_transfer(completion.read, completion.write)
+            .unwrap();
+        assert!(spi.is_busy());
+        assert!(regs.ssienr.read().ssi_enable());
+        state.on_interrupt(regs);
+        assert!(spi.is_busy());
+    }
+}
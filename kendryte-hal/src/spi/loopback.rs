@@ -0,0 +1,184 @@
+//! Loopback self-test and throughput measurement.
+//!
+//! [`Loopback::Internal`] sets the shift register loop bit, which feeds the
+//! transmit shift register straight back into the receive one. It needs no
+//! wiring and checks the controller and driver at a given serial clock.
+//! [`Loopback::External`] leaves the controller alone and expects MOSI to be
+//! bridged to MISO, e.g. with a jumper at the device footprint, so pad and
+//! trace delays are part of the test.
+//!
+//! [`Spi::loopback_sweep`] runs the test at increasing clock rates and
+//! reports each step, which tells how fast the wiring can be driven:
+//!
+//! ```ignore
+//! let mut buf = [0u8; 1024];
+//! let best = spi.loopback_sweep(Loopback::External, &mut buf, |report| {
+//!     writeln!(uart, "{:>9} Hz {:>5} errors", report.frequency, report.errors).ok();
+//! })?;
+//! ```
+
+use super::driver::{Spi, SpiError, modify_ctrlr0};
+use crate::soc::TIMER_FREQUENCY;
+use crate::time::now;
+
+/// How the received data gets back to the controller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Loopback {
+    /// Inside the controller, through the shift register loop bit.
+    Internal,
+    /// Through a bridge from MOSI to MISO outside the chip.
+    External,
+}
+
+/// Outcome of one loopback run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoopbackReport {
+    /// Serial clock frequency the run was made at, in Hz.
+    pub frequency: u32,
+    /// Frames sent.
+    pub frames: usize,
+    /// Frames received different from the ones sent.
+    pub errors: usize,
+    /// Frame size in bits.
+    pub data_bits: u8,
+    /// Duration of the transfer in machine timer ticks.
+    pub ticks: u64,
+}
+
+impl LoopbackReport {
+    /// Returns true if every frame came back unchanged.
+    #[inline]
+    pub fn passed(&self) -> bool {
+        self.errors == 0
+    }
+
+    /// Data rate the driver kept up, in bits per second.
+    ///
+    /// This is below [`frequency`](Self::frequency) when software cannot
+    /// keep the FIFOs busy. Returns `None` if the run was too short to time.
+    pub fn throughput(&self) -> Option<u64> {
+        let bits = self.frames as u64 * self.data_bits as u64;
+        (bits * TIMER_FREQUENCY as u64).checked_div(self.ticks)
+    }
+}
+
+impl Spi<'_> {
+    /// Send a test pattern of `buf.len()` frames at `frequency` and check
+    /// it comes back.
+    ///
+    /// `buf` holds the received frames afterwards. The serial clock and the
+    /// loop bit are restored before returning. Frames must fit in a byte.
+    pub fn loopback_test(
+        &mut self,
+        loopback: Loopback,
+        frequency: u32,
+        buf: &mut [u8],
+    ) -> Result<LoopbackReport, SpiError> {
+        self.check_word::<u8>()?;
+        let previous = self.frequency();
+        let frequency = self.set_frequency(frequency);
+        if loopback == Loopback::Internal {
+            self.set_shift_register_loop(true);
+        }
+        let mask = self.frame_mask() as u8;
+        let start = now();
+        let result = self
+            .exchange(buf.len(), |i| pattern(i) & mask, |i, w| buf[i] = w)
+            .and_then(|()| self.wait_idle());
+        let ticks = now() - start;
+        if loopback == Loopback::Internal {
+            self.set_shift_register_loop(false);
+        }
+        self.set_frequency(previous);
+        result?;
+        let errors = buf
+            .iter()
+            .enumerate()
+            .filter(|&(i, &w)| w != pattern(i) & mask)
+            .count();
+        Ok(LoopbackReport {
+            frequency,
+            frames: buf.len(),
+            errors,
+            data_bits: self.data_bits(),
+            ticks,
+        })
+    }
+
+    /// Run [`loopback_test`](Self::loopback_test) at increasing clock rates,
+    /// passing each report to `report`.
+    ///
+    /// Starts at 1/64 of the highest rate and doubles up to the highest.
+    /// Returns the highest frequency up to which every run passed, or `None`
+    /// if the slowest one failed already.
+    pub fn loopback_sweep(
+        &mut self,
+        loopback: Loopback,
+        buf: &mut [u8],
+        mut report: impl FnMut(&LoopbackReport),
+    ) -> Result<Option<u32>, SpiError> {
+        let max = self.src_clock_hz / 2;
+        let mut best = None;
+        let mut failed = false;
+        for shift in (0..=6).rev() {
+            let step = self.loopback_test(loopback, max >> shift, buf)?;
+            report(&step);
+            failed |= !step.passed();
+            if !failed {
+                best = Some(step.frequency);
+            }
+        }
+        Ok(best)
+    }
+
+    fn set_shift_register_loop(&mut self, enable: bool) {
+        let _ = self.wait_idle();
+        unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(false)) };
+        modify_ctrlr0(self.regs, self.features(), |r| {
+            r.with_shift_register_loop(enable)
+        });
+        unsafe { modify_reg!(self.regs, ssienr, |r| r.with_ssi_enable(true)) };
+    }
+}
+
+/// Test frame `i`: a byte sequence that does not repeat within 256 frames
+/// and toggles every bit often.
+#[inline]
+fn pattern(i: usize) -> u8 {
+    (i as u8).wrapping_mul(0x9D) ^ 0x5A
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+    use crate::spi::{Config, RegisterBlock, StatusReg};
+
+    #[test]
+    fn sweep_internal() {
+        let regs = unsafe { &*mock::block::<RegisterBlock>() };
+        let mut spi = unsafe { Spi::from_regs_with_src_clock(regs, 50_000_000, Config::default()) };
+        // The mock data register reads back what was written, like the loop.
+        unsafe { regs.sr.write(StatusReg::new_with_raw_value(0b1010)) };
+        let before = regs.baudr.read().raw_value();
+
+        let mut buf = [0; 64];
+        let mut steps = [0; 7];
+        let mut n = 0;
+        let best = spi.loopback_sweep(Loopback::Internal, &mut buf, |report| {
+            assert!(report.passed());
+            steps[n] = report.frequency;
+            n += 1;
+        });
+        assert_eq!(best, Ok(Some(25_000_000)));
+        assert_eq!(
+            steps,
+            [
+                390_625, 781_250, 1_562_500, 3_125_000, 6_250_000, 12_500_000, 25_000_000
+            ]
+        );
+        assert_eq!(buf[1], pattern(1));
+        assert!(!regs.ctrlr0.read().shift_register_loop());
+        assert_eq!(regs.baudr.read().raw_value(), before);
+    }
+}
@@ -1,8 +1,63 @@
 mod register;
 pub use register::*;
 
+crate::trace::traced_registers!($ "spi", crate::spi::RegisterBlock);
+
 mod driver;
 pub use driver::*;
 
+mod device;
+pub use device::CsDevice;
+
+mod asynch;
+pub use asynch::{AsyncSpi, SpiState};
+
+mod interrupt;
+pub use interrupt::{Completion, InterruptSpi, SpiTransferState};
+
+mod loopback;
+pub use loopback::{Loopback, LoopbackReport};
+
 pub mod pad;
-pub use pad::{IntoPads, IntoSpiClk, IntoSpiCs, IntoSpiMiso, IntoSpiMosi, IntoTransmitOnly};
+pub use pad::{
+    IntoPads, IntoSpiClk, IntoSpiCs, IntoSpiMiso, IntoSpiMosi, IntoTransmitOnly, SpiPads,
+};
+
+crate::trace::impl_raw!(
+    ControlReg0,
+    ControlReg1,
+    SsiEnableReg,
+    MicrowireControlReg,
+    SlaveEnableReg,
+    BaudRateSelectReg,
+    TransmitFifoThresholdLevelReg,
+    ReceiveFifoThresholdLevelReg,
+    TransmitFifoLevelReg,
+    ReceiveFifoLevelReg,
+    StatusReg,
+    InterruptMaskReg,
+    InterruptStatusReg,
+    RawInterruptStatusReg,
+    TransmitFifoErrorInterruptClearReg,
+    ReceiveFifoOverflowInterruptClearReg,
+    ReceiveFifoUnderflowInterruptClearReg,
+    MultiMasterInterruptClearReg,
+    InterruptClearReg,
+    DmaControlReg,
+    DmaTransmitDataLevelReg,
+    DmaReceiveDataLevelReg,
+    IdentificationReg,
+    ComponentVersionReg,
+    DataReg,
+    RxSampleDelayReg,
+    SpiControlReg0,
+    DdrDriveEdgeReg,
+    SpiControlReg1,
+    SpiTransmitErrorClearReg,
+    SpiDeviceReg,
+    SpiAddressReg,
+    AxiAddressReg0,
+    AxiAddressReg1,
+    AxiErrorClearReg,
+    DoneClearReg,
+);
@@ -1,4 +1,29 @@
 use crate::iomux::FlexPad;
+use crate::iomux::ops::PadOps;
+
+/// Pads held by an SPI driver, returned by [`Spi::free`](crate::spi::Spi::free).
+pub struct SpiPads<'a> {
+    /// Serial clock pad.
+    pub clk: FlexPad<'a>,
+    /// Master output, slave input pad.
+    pub mosi: FlexPad<'a>,
+    /// Master input, slave output pad, absent in transmit-only mode.
+    pub miso: Option<FlexPad<'a>>,
+    /// Chip select pad.
+    pub cs: FlexPad<'a>,
+}
+
+impl<'a> SpiPads<'a> {
+    /// Disable input and output on every pad so they can be reassigned.
+    pub(crate) fn disable(&mut self) {
+        self.clk.set_disabled();
+        self.mosi.set_disabled();
+        if let Some(miso) = self.miso.as_mut() {
+            miso.set_disabled();
+        }
+        self.cs.set_disabled();
+    }
+}
 
 /// Pads that can be converted into valid full-duplex SPI pads.
 pub trait IntoPads<'a, const I: usize> {
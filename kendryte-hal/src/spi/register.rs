@@ -253,6 +253,56 @@ pub struct ControlReg0 {
     pub ssi_is_master: WorkingMode,
 }
 
+/// Fields of CTRLR0 as (offset in the legacy layout, offset in [`ControlReg0`], width).
+const LEGACY_CTRLR0_FIELDS: [(u32, u32, u32); 9] = [
+    // Data Frame Size in 32-bit mode (DFS_32).
+    (16, 0, 5),
+    // Frame Format (FRF).
+    (4, 6, 2),
+    // Serial Clock Phase (SCPH).
+    (6, 8, 1),
+    // Serial Clock Polarity (SCPOL).
+    (7, 9, 1),
+    // Transfer Mode (TMOD).
+    (8, 10, 2),
+    // Slave Output Enable (SLV_OE).
+    (10, 12, 1),
+    // Shift Register Loop (SRL).
+    (11, 13, 1),
+    // Control Frame Size (CFS).
+    (12, 16, 4),
+    // SPI Frame Format (SPI_FRF).
+    (21, 22, 2),
+];
+
+impl ControlReg0 {
+    /// Convert a CTRLR0 value in the legacy layout of older DesignWare SSI
+    /// releases, such as the K210 SPI0 and SPI1, into this layout.
+    ///
+    /// The legacy controllers are master only, so the result always selects
+    /// master mode.
+    pub fn from_legacy(raw: u32) -> Self {
+        let value = LEGACY_CTRLR0_FIELDS
+            .iter()
+            .fold(0, |value, &(legacy, offset, width)| {
+                value | (((raw >> legacy) & ((1 << width) - 1)) << offset)
+            });
+        Self::new_with_raw_value(value).with_ssi_is_master(WorkingMode::Master)
+    }
+
+    /// Convert this value into the legacy CTRLR0 layout.
+    ///
+    /// Fields the legacy layout lacks, such as HyperBus enable, are dropped.
+    pub fn to_legacy(self) -> u32 {
+        let raw = self.raw_value();
+        LEGACY_CTRLR0_FIELDS
+            .iter()
+            .fold(0, |value, &(legacy, offset, width)| {
+                value | (((raw >> offset) & ((1 << width) - 1)) << legacy)
+            })
+    }
+}
+
 /// Control Register 1 (CTRLR1)
 ///
 /// CTRLR1 is a Control Register 1 Offset Address: 0x4 Total Reset Value:0x0
@@ -1412,4 +1462,23 @@ mod tests {
         assert_eq!(size_of::<AxiErrorClearReg>(), 4);
         assert_eq!(size_of::<DoneClearReg>(), 4);
     }
+
+    #[test]
+    fn legacy_ctrlr0_round_trip() {
+        let value = ControlReg0::new_with_raw_value(0)
+            .with_data_frame_size(u5::new(15))
+            .with_frame_format(FrameFormat::NationalMicrowire)
+            .with_serial_clock_phase(SerialClockPhase::Start)
+            .with_transfer_mode(TransferMode::ReceiveOnly)
+            .with_control_frame_size(u4::new(7))
+            .with_spi_frame_format(SpiFrameFormat::Quad)
+            .with_ssi_is_master(WorkingMode::Master);
+        // DFS_32 = 15, FRF = 2, SCPH = 1, TMOD = 2, CFS = 7, SPI_FRF = 2.
+        let legacy = (15 << 16) | (2 << 4) | (1 << 6) | (2 << 8) | (7 << 12) | (2 << 21);
+        assert_eq!(value.to_legacy(), legacy);
+        assert_eq!(
+            ControlReg0::from_legacy(legacy).raw_value(),
+            value.raw_value()
+        );
+    }
 }
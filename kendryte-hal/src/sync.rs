@@ -0,0 +1,320 @@
+//! Sharing drivers between the main loop and interrupt handlers.
+//!
+//! Drivers own their registers and take `&mut self`, so a driver used from
+//! both the main loop and an interrupt handler must live behind a lock that
+//! the handler cannot preempt. [`Mutex`] masks machine interrupts on the
+//! current hart while its closure runs and spins on an atomic flag against
+//! the other harts, so it is safe to take from any context:
+//!
+//! ```ignore
+//! use kendryte_hal::sync::Mutex;
+//! use kendryte_hal::uart::BlockingUartTx;
+//!
+//! static LOG: Mutex<Option<BlockingUartTx<'static, 'static>>> = Mutex::new(None);
+//!
+//! // In main, after splitting the UART with `into_split`:
+//! LOG.lock(|log| *log = Some(tx));
+//!
+//! // From anywhere, including interrupt handlers:
+//! LOG.lock(|log| {
+//!     if let Some(tx) = log {
+//!         let _ = embedded_io::Write::write_all(tx, b"tick\r\n");
+//!     }
+//! });
+//! ```
+//!
+//! Interrupts stay masked for the whole closure, so keep it short: writing
+//! a long log line to a slow UART delays every other interrupt.
+//!
+//! Code written against `critical_section::Mutex` works the same way once
+//! the `critical-section` feature of `kendryte-rt` provides its
+//! implementation, which also masks interrupts and locks out other harts.
+//!
+//! Data produced by an interrupt handler and consumed by the main loop, such
+//! as GPIO events or ADC samples, goes through a [`Queue`] instead, which
+//! needs no lock. The queue is split once into a [`Producer`] for the handler
+//! and a [`Consumer`] for the main loop; each handle is the only one of its
+//! kind, which is what makes the lock-free ring sound:
+//!
+//! ```ignore
+//! use kendryte_hal::sync::{Mutex, Producer, Queue};
+//!
+//! static QUEUE: Queue<u32, 16> = Queue::new();
+//! static PRODUCER: Mutex<Option<Producer<'static, u32, 16>>> = Mutex::new(None);
+//!
+//! // In main:
+//! let (producer, mut consumer) = QUEUE.split().unwrap();
+//! PRODUCER.lock(|p| *p = Some(producer));
+//!
+//! // In the interrupt handler:
+//! PRODUCER.lock(|p| p.as_mut().map(|p| p.push(42)));
+//!
+//! // In the main loop:
+//! while let Some(value) = consumer.pop() { /* ... */ }
+//! ```
+
+use crate::time::interrupt_free;
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+
+/// Lock usable from interrupt handlers and from every hart.
+///
+/// The lock is not reentrant: locking a mutex again from inside its own
+/// closure deadlocks.
+pub struct Mutex<T> {
+    locked: AtomicBool,
+    value: UnsafeCell<T>,
+}
+
+// SAFETY: the value is only accessed inside `lock`, which holds the flag
+// with interrupts masked, so at most one context on one hart accesses it.
+unsafe impl<T: Send> Sync for Mutex<T> {}
+
+impl<T> Mutex<T> {
+    /// Creates an unlocked mutex holding `value`.
+    pub const fn new(value: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Runs `f` with exclusive access to the value.
+    ///
+    /// Machine interrupts are masked on this hart until `f` returns.
+    pub fn lock<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        interrupt_free(|| {
+            while self
+                .locked
+                .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+            {
+                core::hint::spin_loop();
+            }
+            // SAFETY: the flag is held, see the `Sync` implementation.
+            let result = f(unsafe { &mut *self.value.get() });
+            self.locked.store(false, Ordering::Release);
+            result
+        })
+    }
+
+    /// Runs `f` with exclusive access to the value, or returns `None` if
+    /// another hart holds the lock.
+    pub fn try_lock<R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        interrupt_free(|| {
+            self.locked
+                .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .ok()?;
+            // SAFETY: the flag is held, see the `Sync` implementation.
+            let result = f(unsafe { &mut *self.value.get() });
+            self.locked.store(false, Ordering::Release);
+            Some(result)
+        })
+    }
+
+    /// Mutable access without locking, as the borrow is already exclusive.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+
+    /// Returns the value.
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: Default> Default for Mutex<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+/// Fixed capacity single producer, single consumer queue.
+///
+/// Items are only pushed and popped through the handles returned by
+/// [`split`](Self::split). One of the `N` slots is kept free to tell a full
+/// queue from an empty one.
+pub struct Queue<T, const N: usize> {
+    slots: [UnsafeCell<MaybeUninit<T>>; N],
+    /// Index of the next slot to read, owned by the consumer.
+    head: AtomicUsize,
+    /// Index of the next slot to write, owned by the producer.
+    tail: AtomicUsize,
+    dropped: AtomicU32,
+    split: AtomicBool,
+}
+
+// SAFETY: there is at most one producer and one consumer, see `split`. A
+// slot is only written by the producer while it is outside `head..tail`,
+// and only read by the consumer while it is inside.
+unsafe impl<T: Send, const N: usize> Sync for Queue<T, N> {}
+
+impl<T, const N: usize> Queue<T, N> {
+    /// Creates an empty queue.
+    pub const fn new() -> Self {
+        assert!(N >= 2, "a queue needs at least two slots");
+        Self {
+            slots: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            dropped: AtomicU32::new(0),
+            split: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the producer and consumer handles, or `None` if the queue has
+    /// been split before.
+    pub fn split(&self) -> Option<(Producer<'_, T, N>, Consumer<'_, T, N>)> {
+        if self.split.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        Some((Producer { queue: self }, Consumer { queue: self }))
+    }
+
+    /// Returns true if no items are waiting.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire) == self.tail.load(Ordering::Acquire)
+    }
+}
+
+impl<T, const N: usize> Default for Queue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for Queue<T, N> {
+    fn drop(&mut self) {
+        let tail = *self.tail.get_mut();
+        let mut head = *self.head.get_mut();
+        while head != tail {
+            // SAFETY: slots in `head..tail` hold items not yet popped.
+            unsafe { self.slots[head].get_mut().assume_init_drop() };
+            head = (head + 1) % N;
+        }
+    }
+}
+
+/// Pushing end of a [`Queue`].
+pub struct Producer<'a, T, const N: usize> {
+    queue: &'a Queue<T, N>,
+}
+
+// SAFETY: the handle is unique, so it may move to another context as long
+// as the items may.
+unsafe impl<T: Send, const N: usize> Send for Producer<'_, T, N> {}
+
+impl<T, const N: usize> Producer<'_, T, N> {
+    /// Appends an item, or counts it as dropped if the queue is full.
+    ///
+    /// Returns false if the item was dropped.
+    pub fn push(&mut self, item: T) -> bool {
+        let queue = self.queue;
+        let tail = queue.tail.load(Ordering::Relaxed);
+        let next = (tail + 1) % N;
+        if next == queue.head.load(Ordering::Acquire) {
+            self.mark_dropped();
+            return false;
+        }
+        // SAFETY: this is the only producer, and the slot at `tail` is
+        // outside `head..tail`, so the consumer does not read it.
+        unsafe { (*queue.slots[tail].get()).write(item) };
+        queue.tail.store(next, Ordering::Release);
+        true
+    }
+
+    /// Counts an item lost before it could be pushed.
+    #[inline]
+    pub fn mark_dropped(&mut self) {
+        self.queue.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns true if a push would drop the item.
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        (self.queue.tail.load(Ordering::Relaxed) + 1) % N == self.queue.head.load(Ordering::Acquire)
+    }
+}
+
+/// Popping end of a [`Queue`].
+pub struct Consumer<'a, T, const N: usize> {
+    queue: &'a Queue<T, N>,
+}
+
+// SAFETY: the handle is unique, so it may move to another context as long
+// as the items may.
+unsafe impl<T: Send, const N: usize> Send for Consumer<'_, T, N> {}
+
+impl<T, const N: usize> Consumer<'_, T, N> {
+    /// Removes the oldest item.
+    pub fn pop(&mut self) -> Option<T> {
+        let queue = self.queue;
+        let head = queue.head.load(Ordering::Relaxed);
+        if head == queue.tail.load(Ordering::Acquire) {
+            return None;
+        }
+        // SAFETY: this is the only consumer, and the slot at `head` was
+        // written before `tail` moved past it.
+        let item = unsafe { (*queue.slots[head].get()).assume_init_read() };
+        queue.head.store((head + 1) % N, Ordering::Release);
+        Some(item)
+    }
+
+    /// Returns true if no items are waiting.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Number of items dropped since the last call.
+    #[inline]
+    pub fn take_dropped(&mut self) -> u32 {
+        self.queue.dropped.swap(0, Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn queue_split_once() {
+        let queue = Queue::<u8, 3>::new();
+        let (mut producer, mut consumer) = queue.split().unwrap();
+        assert!(queue.split().is_none());
+
+        assert!(producer.push(1));
+        assert!(producer.push(2));
+        assert!(producer.is_full());
+        assert!(!producer.push(3));
+        producer.mark_dropped();
+        assert_eq!(consumer.pop(), Some(1));
+        assert!(producer.push(4));
+        assert_eq!(consumer.pop(), Some(2));
+        assert_eq!(consumer.pop(), Some(4));
+        assert_eq!(consumer.pop(), None);
+        assert!(consumer.is_empty());
+        assert_eq!(consumer.take_dropped(), 2);
+        assert_eq!(consumer.take_dropped(), 0);
+    }
+
+    #[test]
+    fn queue_drops_unread_items() {
+        let item = Rc::new(());
+        {
+            let queue = Queue::<Rc<()>, 4>::new();
+            let (mut producer, mut consumer) = queue.split().unwrap();
+            for _ in 0..3 {
+                producer.push(item.clone());
+            }
+            drop(consumer.pop());
+            assert_eq!(Rc::strong_count(&item), 3);
+        }
+        assert_eq!(Rc::strong_count(&item), 1);
+    }
+}
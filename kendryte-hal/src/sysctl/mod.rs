@@ -0,0 +1,210 @@
+//! System controller clock gating.
+//!
+//! The functional clock of each peripheral is switched on and off in the
+//! clock enable registers of the system controller. Drivers gate their
+//! clock through [`Sysctl`] in their `suspend` methods and enable it again
+//! in `resume`, so a peripheral that is not used for a while draws no
+//! dynamic power but keeps its driver:
+//!
+//! ```ignore
+//! let mut sysctl = Sysctl::new(p.sysctl);
+//! serial.suspend(&mut sysctl)?;
+//! // ... sleep ...
+//! serial.resume(&mut sysctl);
+//! ```
+//!
+//! Which bit gates which clock depends on the chip, see
+//! [`soc::clock_gate`](crate::soc::clock_gate). Clocks without a known gate
+//! are left running.
+//!
+//! The system controller also divides the debounce clock of the GPIO
+//! controllers, which sets how long an input must be stable before a pin
+//! with [`set_debounce`](crate::gpio::PinCommon::set_debounce) enabled sees
+//! the change:
+//!
+//! ```ignore
+//! sysctl.set_gpio_debounce_divider(0, 512);
+//! button.set_debounce(true)?;
+//! ```
+
+mod register;
+pub use register::*;
+
+crate::trace::traced_registers!($ "sysctl", crate::sysctl::RegisterBlock);
+
+use crate::clocks::ClockId;
+use crate::instance::Instance;
+use crate::soc;
+use core::marker::PhantomData;
+
+/// Clock enable register holding a gate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GateRegister {
+    /// [`RegisterBlock::hs_clken`].
+    HighSpeed,
+    /// [`RegisterBlock::ls_clken0`].
+    LowSpeed0,
+    /// [`RegisterBlock::ls_clken1`].
+    LowSpeed1,
+}
+
+/// Location of the enable bit of a clock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClockGate {
+    /// Register holding the bit.
+    pub register: GateRegister,
+    /// Bit number, set to let the clock run.
+    pub bit: u8,
+}
+
+/// Location of a clock divider in [`RegisterBlock::ls_clkdiv`].
+///
+/// The field holds the divider minus one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClockDivider {
+    /// Lowest bit of the field.
+    pub shift: u8,
+    /// Width of the field in bits.
+    pub width: u8,
+}
+
+impl ClockDivider {
+    /// Largest divider the field can hold.
+    #[inline]
+    pub const fn max(&self) -> u32 {
+        1 << self.width
+    }
+
+    const fn mask(&self) -> u32 {
+        ((1 << self.width) - 1) << self.shift
+    }
+}
+
+/// System controller clock gates and dividers.
+pub struct Sysctl<'i> {
+    inner: MmioRegisterBlock<'static>,
+    _marker: PhantomData<&'i ()>,
+}
+
+// SAFETY: the driver owns its register block exclusively.
+unsafe impl Send for Sysctl<'_> {}
+
+impl<'i> Sysctl<'i> {
+    /// Creates the clock gate driver.
+    #[inline]
+    pub fn new(instance: impl Instance<'i, R = MmioRegisterBlock<'static>>) -> Self {
+        Sysctl {
+            inner: instance.inner(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Let `clock` run.
+    ///
+    /// Returns false if the chip has no known gate for `clock`, which then
+    /// keeps running.
+    pub fn enable_clock(&mut self, clock: ClockId) -> bool {
+        self.set_clock(clock, true)
+    }
+
+    /// Stop `clock`.
+    ///
+    /// Returns false if the chip has no known gate for `clock`, which then
+    /// keeps running.
+    pub fn disable_clock(&mut self, clock: ClockId) -> bool {
+        self.set_clock(clock, false)
+    }
+
+    /// Returns whether `clock` runs, or `None` if its gate is not known.
+    pub fn is_clock_enabled(&mut self, clock: ClockId) -> Option<bool> {
+        let gate = soc::clock_gate(clock)?;
+        let value = match gate.register {
+            GateRegister::HighSpeed => read_reg!(self.inner, hs_clken, read_hs_clken),
+            GateRegister::LowSpeed0 => read_reg!(self.inner, ls_clken0, read_ls_clken0),
+            GateRegister::LowSpeed1 => read_reg!(self.inner, ls_clken1, read_ls_clken1),
+        };
+        Some(value & (1 << gate.bit) != 0)
+    }
+
+    /// Divide the debounce clock of GPIO controller `gpio` by `divider`.
+    ///
+    /// Returns false, leaving the divider unchanged, if the chip has no
+    /// known divider for `gpio` or `divider` is zero or above
+    /// [`ClockDivider::max`]. On the K230 both GPIO controllers share one
+    /// debounce clock.
+    pub fn set_gpio_debounce_divider(&mut self, gpio: u8, divider: u32) -> bool {
+        let Some(field) = soc::gpio_debounce_divider(gpio) else {
+            return false;
+        };
+        if divider == 0 || divider > field.max() {
+            return false;
+        }
+        unsafe {
+            modify_reg!(self.inner, ls_clkdiv, modify_ls_clkdiv, |value| {
+                (value & !field.mask()) | ((divider - 1) << field.shift)
+            })
+        };
+        true
+    }
+
+    /// Returns the divider of the debounce clock of GPIO controller `gpio`,
+    /// or `None` if it is not known.
+    pub fn gpio_debounce_divider(&self, gpio: u8) -> Option<u32> {
+        let field = soc::gpio_debounce_divider(gpio)?;
+        let value = read_reg!(self.inner, ls_clkdiv, read_ls_clkdiv);
+        Some(((value & field.mask()) >> field.shift) + 1)
+    }
+
+    fn set_clock(&mut self, clock: ClockId, enable: bool) -> bool {
+        let Some(gate) = soc::clock_gate(clock) else {
+            return false;
+        };
+        let update = |value: u32| {
+            if enable {
+                value | (1 << gate.bit)
+            } else {
+                value & !(1 << gate.bit)
+            }
+        };
+        unsafe {
+            match gate.register {
+                GateRegister::HighSpeed => {
+                    modify_reg!(self.inner, hs_clken, modify_hs_clken, update)
+                }
+                GateRegister::LowSpeed0 => {
+                    modify_reg!(self.inner, ls_clken0, modify_ls_clken0, update)
+                }
+                GateRegister::LowSpeed1 => {
+                    modify_reg!(self.inner, ls_clken1, modify_ls_clken1, update)
+                }
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+
+    #[test]
+    #[cfg(not(any(feature = "k510", feature = "k210")))]
+    fn debounce_divider_keeps_other_fields() {
+        let mut sysctl = Sysctl {
+            inner: unsafe { RegisterBlock::new_mmio(mock::block::<RegisterBlock>()) },
+            _marker: PhantomData,
+        };
+        unsafe { write_reg!(sysctl.inner, ls_clkdiv, write_ls_clkdiv, 0x7) };
+        assert!(sysctl.set_gpio_debounce_divider(0, 512));
+        assert_eq!(sysctl.gpio_debounce_divider(1), Some(512));
+        assert_eq!(
+            read_reg!(sysctl.inner, ls_clkdiv, read_ls_clkdiv),
+            (511 << 15) | 0x7
+        );
+        assert!(!sysctl.set_gpio_debounce_divider(0, 0));
+        assert!(!sysctl.set_gpio_debounce_divider(0, 1025));
+        assert!(!sysctl.set_gpio_debounce_divider(2, 2));
+        assert_eq!(sysctl.gpio_debounce_divider(0), Some(512));
+    }
+}
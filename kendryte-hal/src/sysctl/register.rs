@@ -0,0 +1,39 @@
+use derive_mmio::Mmio;
+
+/// System controller clock register block.
+///
+/// Covers the clock enable registers of the K230 clock management unit.
+/// Each bit gates the functional clock of one peripheral; a set bit lets the
+/// clock run. The low speed divider register follows them.
+#[derive(Mmio)]
+#[repr(C)]
+pub struct RegisterBlock {
+    _reserved0: [u8; 0x18],
+    /// High speed clock enable register.
+    /// Gates the SSI controller clocks, among others.
+    pub hs_clken: u32,
+    _reserved1: [u8; 0x08],
+    /// Low speed clock enable register 0.
+    /// Gates the APB bus clocks of the low speed peripherals.
+    pub ls_clken0: u32,
+    /// Low speed clock enable register 1.
+    /// Gates the UART and I2C core clocks.
+    pub ls_clken1: u32,
+    _reserved2: [u8; 0x04],
+    /// Low speed clock divider register.
+    /// Divides the GPIO debounce clock, among others.
+    pub ls_clkdiv: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem::offset_of;
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, hs_clken), 0x18);
+        assert_eq!(offset_of!(RegisterBlock, ls_clken0), 0x24);
+        assert_eq!(offset_of!(RegisterBlock, ls_clken1), 0x28);
+        assert_eq!(offset_of!(RegisterBlock, ls_clkdiv), 0x30);
+    }
+}
@@ -0,0 +1,103 @@
+//! Machine timer based timeouts for blocking drivers.
+//!
+//! Register waits in blocking drivers are bounded by a [`Timeout`], so a
+//! peripheral that never becomes ready makes the driver return a timeout
+//! error instead of hanging, and lets a watchdog-driven main loop recover.
+
+use crate::soc::TIMER_FREQUENCY;
+
+/// Returns the current value of the machine timer.
+///
+/// The timer counts at [`TIMER_FREQUENCY`] Hz.
+#[inline]
+pub fn now() -> u64 {
+    #[cfg(all(target_arch = "riscv64", feature = "k210"))]
+    {
+        // The K210 cores have no `time` CSR; read mtime from the CLINT instead.
+        const MTIME: *const u64 = 0x0200_BFF8 as *const u64;
+        unsafe { MTIME.read_volatile() }
+    }
+    #[cfg(all(target_arch = "riscv64", not(feature = "k210")))]
+    {
+        let time: u64;
+        unsafe { core::arch::asm!("rdtime {}", out(reg) time, options(nomem, nostack)) };
+        time
+    }
+    #[cfg(not(target_arch = "riscv64"))]
+    0
+}
+
+/// Runs `f` with machine interrupts masked.
+///
+/// Used by bit-banged protocols whose timing an interrupt handler would
+/// break. Interrupts are re-enabled only if they were enabled on entry.
+#[inline(always)]
+pub(crate) fn interrupt_free<R>(f: impl FnOnce() -> R) -> R {
+    #[cfg(target_arch = "riscv64")]
+    let mstatus = {
+        let mstatus: usize;
+        unsafe { core::arch::asm!("csrrci {}, mstatus, 8", out(reg) mstatus, options(nostack)) };
+        mstatus
+    };
+    let result = f();
+    #[cfg(target_arch = "riscv64")]
+    if mstatus & 8 != 0 {
+        unsafe { core::arch::asm!("csrsi mstatus, 8", options(nostack)) };
+    }
+    result
+}
+
+/// Deadline for a bounded wait.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timeout {
+    deadline: Option<u64>,
+}
+
+impl Timeout {
+    /// A timeout that never expires.
+    pub const NEVER: Self = Self { deadline: None };
+
+    /// Expire `ticks` machine timer ticks from now.
+    #[inline]
+    pub fn from_ticks(ticks: u64) -> Self {
+        Self {
+            deadline: Some(now().saturating_add(ticks)),
+        }
+    }
+
+    /// Expire `us` microseconds from now.
+    #[inline]
+    pub fn from_micros(us: u32) -> Self {
+        Self::from_ticks(us as u64 * TIMER_FREQUENCY as u64 / 1_000_000)
+    }
+
+    /// Expire `ms` milliseconds from now.
+    #[inline]
+    pub fn from_millis(ms: u32) -> Self {
+        Self::from_ticks(ms as u64 * TIMER_FREQUENCY as u64 / 1_000)
+    }
+
+    /// Returns true once the deadline has passed.
+    #[inline]
+    pub fn is_expired(&self) -> bool {
+        self.deadline.is_some_and(|deadline| now() >= deadline)
+    }
+
+    /// Spin until `f` returns true, or return `error` once the deadline passes.
+    ///
+    /// `f` is checked once more after expiry, so a condition that became true
+    /// while the caller was preempted is not reported as a timeout.
+    #[inline]
+    pub fn wait<E>(self, error: E, mut f: impl FnMut() -> bool) -> Result<(), E> {
+        loop {
+            let expired = self.is_expired();
+            if f() {
+                return Ok(());
+            }
+            if expired {
+                return Err(error);
+            }
+            core::hint::spin_loop();
+        }
+    }
+}
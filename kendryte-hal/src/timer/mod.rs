@@ -0,0 +1,12 @@
+//! General purpose timers.
+//!
+//! The DesignWare APB timers block holds up to eight 32-bit down counters
+//! sharing one register block. Each counter reloads from its load count when
+//! it reaches zero in user-defined mode, or runs down from the maximum value
+//! in free-running mode, and raises its interrupt on every wrap.
+//!
+//! Only the register layout is modelled so far; runtime crates hand out the
+//! block as a peripheral token.
+
+mod register;
+pub use register::*;
@@ -0,0 +1,96 @@
+use bitbybit::{bitenum, bitfield};
+use derive_mmio::Mmio;
+
+/// Number of counters the register block has room for.
+pub const TIMER_COUNT: usize = 8;
+
+/// Timers register block.
+///
+/// Registers of the DesignWare APB timers.
+#[derive(Mmio)]
+#[repr(C)]
+pub struct RegisterBlock {
+    /// Per-counter registers.
+    #[mmio(Inner)]
+    pub timers: [Timer; TIMER_COUNT],
+    /// Interrupt Status Register of all counters (TIMERSINTSTATUS).
+    #[mmio(PureRead)]
+    pub int_status: u32,
+    /// Interrupt Clear Register of all counters (TIMERSEOI).
+    /// Reading it clears every pending interrupt.
+    pub eoi: u32,
+    /// Raw Interrupt Status Register of all counters (TIMERSRAWINTSTATUS).
+    #[mmio(PureRead)]
+    pub raw_int_status: u32,
+    /// Component Version Register (TIMERS_COMP_VERSION).
+    #[mmio(PureRead)]
+    pub comp_version: u32,
+    /// Second load count of each counter, used in PWM mode (TIMERNLOADCOUNT2).
+    pub load_count2: [u32; TIMER_COUNT],
+}
+
+/// Registers of a single counter.
+#[derive(Mmio)]
+#[repr(C)]
+pub struct Timer {
+    /// Load Count Register (TIMERNLOADCOUNT).
+    pub load_count: u32,
+    /// Current Value Register (TIMERNCURRENTVALUE).
+    #[mmio(PureRead)]
+    pub current_value: u32,
+    /// Control Register (TIMERNCONTROLREG).
+    pub control: Control,
+    /// Interrupt Clear Register (TIMERNEOI).
+    /// Reading it clears the interrupt of this counter.
+    pub eoi: u32,
+    /// Interrupt Status Register (TIMERNINTSTATUS).
+    #[mmio(PureRead)]
+    pub int_status: u32,
+}
+
+/// How a counter restarts after reaching zero.
+#[bitenum(u1, exhaustive = true)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Count down from the maximum value.
+    FreeRunning = 0b0,
+    /// Count down from the load count.
+    UserDefined = 0b1,
+}
+
+/// Control Register (TIMERNCONTROLREG).
+#[bitfield(u32, default = 0x0)]
+#[derive(Debug, PartialEq, Eq)]
+pub struct Control {
+    /// Enable the counter (TIMER_ENABLE).
+    #[bit(0, rw)]
+    pub enable: bool,
+    /// Restart behaviour (TIMER_MODE).
+    #[bit(1, rw)]
+    pub mode: Mode,
+    /// Mask the interrupt (TIMER_INTERRUPT_MASK).
+    #[bit(2, rw)]
+    pub interrupt_mask: bool,
+    /// Toggle the output with a duty cycle set by the two load counts
+    /// (TIMER_PWM).
+    #[bit(3, rw)]
+    pub pwm: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem::offset_of;
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, timers), 0x00);
+        assert_eq!(offset_of!(RegisterBlock, int_status), 0xA0);
+        assert_eq!(offset_of!(RegisterBlock, eoi), 0xA4);
+        assert_eq!(offset_of!(RegisterBlock, raw_int_status), 0xA8);
+        assert_eq!(offset_of!(RegisterBlock, comp_version), 0xAC);
+        assert_eq!(offset_of!(RegisterBlock, load_count2), 0xB0);
+        assert_eq!(offset_of!(Timer, control), 0x08);
+        assert_eq!(offset_of!(Timer, int_status), 0x10);
+        assert_eq!(core::mem::size_of::<Timer>(), 0x14);
+    }
+}
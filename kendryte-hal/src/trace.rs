@@ -0,0 +1,276 @@
+//! Register access tracing.
+//!
+//! With the `reg-trace` feature enabled, drivers report every register read
+//! and write to a sink registered with [`set_sink`]. The sink sees the
+//! peripheral name, the register offset and the value, which is enough to
+//! compare a driver against the reference manual or to replay a register
+//! sequence, without a logic analyzer.
+//!
+//! Accesses made while the sink runs are not reported, so a sink may print
+//! through a traced UART. Without the feature the hooks compile to nothing.
+//!
+//! Drivers go through the `read_reg!`, `write_reg!` and `modify_reg!`
+//! macros that [`traced_registers!`] defines in each driver module, instead
+//! of calling the register accessors directly.
+
+#[cfg(feature = "reg-trace")]
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// Direction of a register access.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessKind {
+    /// Register read.
+    Read,
+    /// Register write.
+    Write,
+}
+
+/// A single register access.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Access {
+    /// Name of the peripheral kind, such as `"uart"`.
+    pub peripheral: &'static str,
+    /// Byte offset of the register in the register block.
+    pub offset: usize,
+    /// Value read or written, zero-extended for registers narrower than
+    /// 64 bits.
+    pub value: u64,
+    /// Whether the register was read or written.
+    pub kind: AccessKind,
+}
+
+/// Registered sink, stored as a type-erased `fn(&Access)`.
+static SINK: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+#[cfg(feature = "reg-trace")]
+/// Set while the sink runs, to drop accesses made by the sink itself.
+static IN_SINK: AtomicBool = AtomicBool::new(false);
+
+/// Register `sink` to receive register accesses, replacing any previous one.
+pub fn set_sink(sink: fn(&Access)) {
+    SINK.store(sink as *mut (), Ordering::Release);
+}
+
+/// Remove the sink; subsequent accesses are not reported.
+pub fn clear_sink() {
+    SINK.store(core::ptr::null_mut(), Ordering::Release);
+}
+
+/// Register value that can be reported to the sink.
+pub(crate) trait Raw {
+    fn raw(&self) -> u64;
+}
+
+impl Raw for u32 {
+    #[inline(always)]
+    fn raw(&self) -> u64 {
+        *self as u64
+    }
+}
+
+impl Raw for u64 {
+    #[inline(always)]
+    fn raw(&self) -> u64 {
+        *self
+    }
+}
+
+/// Implement [`Raw`] for bitbybit register types.
+macro_rules! impl_raw {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl $crate::trace::Raw for $ty {
+                #[inline(always)]
+                fn raw(&self) -> u64 {
+                    self.raw_value() as u64
+                }
+            }
+        )+
+    };
+}
+pub(crate) use impl_raw;
+
+/// Define `read_reg!`, `write_reg!` and `modify_reg!` for a driver module.
+///
+/// The macros report accesses to registers of `$block` as peripheral
+/// `$name`, and are visible in the rest of the module and its submodules.
+/// Registers of blocks derived with `Mmio` are named by field and accessor;
+/// registers that are fields with `read`, `write` and `modify` methods of
+/// their own by field only. Register arrays take an index, and reads from
+/// `Mmio` arrays return the accessor's `Result`:
+///
+/// ```ignore
+/// crate::trace::traced_registers!($ "spi", crate::spi::RegisterBlock);
+///
+/// let status = read_reg!(self.inner, status, read_status);
+/// let ctrlr0 = read_reg!(self.regs, ctrlr0);
+/// unsafe { modify_reg!(self.regs, dr_ssi_ctrl[0], |r| r.with_data(data)) };
+/// ```
+///
+/// The leading `$` lets the generated macros declare metavariables.
+macro_rules! traced_registers {
+    ($d:tt $name:literal, $block:ty) => {
+        /// Read a register, reporting the access to [`crate::trace`].
+        #[allow(unused_macros)]
+        macro_rules! read_reg {
+                    ($d regs:expr, $d field:ident, $d read:ident) => {{
+                        let value = $d regs.$d read();
+                        let offset = core::mem::offset_of!($block, $d field);
+                        $crate::trace::read($name, offset, $crate::trace::Raw::raw(&value));
+                        value
+                    }};
+                    ($d regs:expr, $d field:ident [$d index:expr], $d read:ident) => {{
+                        let index = $d index;
+                        let value = $d regs.$d read(index);
+                        if let Ok(value) = &value {
+                            let offset = $crate::trace::element_offset(
+                                core::mem::offset_of!($block, $d field),
+                                index,
+                                value,
+                            );
+                            $crate::trace::read($name, offset, $crate::trace::Raw::raw(value));
+                        }
+                        value
+                    }};
+                    ($d regs:expr, $d field:ident [$d index:expr]) => {{
+                        let index = $d index;
+                        let register = &$d regs.$d field[index];
+                        let value = register.read();
+                        let offset = $crate::trace::element_offset(
+                            core::mem::offset_of!($block, $d field),
+                            index,
+                            register,
+                        );
+                        $crate::trace::read($name, offset, $crate::trace::Raw::raw(&value));
+                        value
+                    }};
+                    ($d regs:expr, $d field:ident) => {{
+                        let value = $d regs.$d field.read();
+                        let offset = core::mem::offset_of!($block, $d field);
+                        $crate::trace::read($name, offset, $crate::trace::Raw::raw(&value));
+                        value
+                    }};
+                }
+
+        /// Write a register, reporting the access to [`crate::trace`].
+        #[allow(unused_macros)]
+        macro_rules! write_reg {
+                    ($d regs:expr, $d field:ident, $d write:ident, $d value:expr) => {{
+                        let value = $d value;
+                        let offset = core::mem::offset_of!($block, $d field);
+                        $crate::trace::write($name, offset, $crate::trace::Raw::raw(&value));
+                        $d regs.$d write(value)
+                    }};
+                    ($d regs:expr, $d field:ident [$d index:expr], $d value:expr) => {{
+                        let value = $d value;
+                        let index = $d index;
+                        let register = &$d regs.$d field[index];
+                        let offset = $crate::trace::element_offset(
+                            core::mem::offset_of!($block, $d field),
+                            index,
+                            register,
+                        );
+                        $crate::trace::write($name, offset, $crate::trace::Raw::raw(&value));
+                        register.write(value)
+                    }};
+                    ($d regs:expr, $d field:ident, $d value:expr) => {{
+                        let value = $d value;
+                        let offset = core::mem::offset_of!($block, $d field);
+                        $crate::trace::write($name, offset, $crate::trace::Raw::raw(&value));
+                        $d regs.$d field.write(value)
+                    }};
+                }
+
+        /// Modify a register, reporting the read and the write to
+        /// [`crate::trace`].
+        #[allow(unused_macros)]
+        macro_rules! modify_reg {
+                    ($d regs:expr, $d field:ident, $d modify:ident, $d f:expr) => {{
+                        let offset = core::mem::offset_of!($block, $d field);
+                        $d regs.$d modify(|r| $crate::trace::modify($name, offset, r, $d f))
+                    }};
+                    ($d regs:expr, $d field:ident [$d index:expr], $d f:expr) => {{
+                        let index = $d index;
+                        let register = &$d regs.$d field[index];
+                        let offset = $crate::trace::element_offset(
+                            core::mem::offset_of!($block, $d field),
+                            index,
+                            register,
+                        );
+                        register.modify(|r| $crate::trace::modify($name, offset, r, $d f))
+                    }};
+                    ($d regs:expr, $d field:ident, $d f:expr) => {{
+                        let offset = core::mem::offset_of!($block, $d field);
+                        $d regs.$d field.modify(|r| $crate::trace::modify($name, offset, r, $d f))
+                    }};
+                }
+    };
+}
+pub(crate) use traced_registers;
+
+/// Offset of element `index` of the register array at `offset`.
+#[inline(always)]
+pub(crate) fn element_offset<T>(offset: usize, index: usize, element: &T) -> usize {
+    offset + index * core::mem::size_of_val(element)
+}
+
+/// Apply `f` to the value read from a register, reporting the read and the
+/// write.
+///
+/// Taking `f` as an argument lets closures passed to `modify_reg!` leave
+/// the register type to inference.
+#[inline(always)]
+pub(crate) fn modify<T: Raw>(
+    peripheral: &'static str,
+    offset: usize,
+    value: T,
+    f: impl FnOnce(T) -> T,
+) -> T {
+    read(peripheral, offset, value.raw());
+    let value = f(value);
+    write(peripheral, offset, value.raw());
+    value
+}
+
+/// Report a register read.
+#[inline(always)]
+pub(crate) fn read(peripheral: &'static str, offset: usize, value: u64) {
+    report(peripheral, offset, value, AccessKind::Read);
+}
+
+/// Report a register write.
+#[inline(always)]
+pub(crate) fn write(peripheral: &'static str, offset: usize, value: u64) {
+    report(peripheral, offset, value, AccessKind::Write);
+}
+
+#[inline(always)]
+fn report(peripheral: &'static str, offset: usize, value: u64, kind: AccessKind) {
+    #[cfg(any(feature = "reg-trace", test))]
+    let access = Access {
+        peripheral,
+        offset,
+        value,
+        kind,
+    };
+    // Host tests check register sequences through the mock log.
+    #[cfg(test)]
+    crate::mock::record(access);
+    #[cfg(feature = "reg-trace")]
+    record(access);
+    #[cfg(not(any(feature = "reg-trace", test)))]
+    let _ = (peripheral, offset, value, kind);
+}
+
+#[cfg(feature = "reg-trace")]
+#[inline(never)]
+fn record(access: Access) {
+    let sink = SINK.load(Ordering::Acquire);
+    if sink.is_null() || IN_SINK.swap(true, Ordering::Acquire) {
+        return;
+    }
+    // SAFETY: only `fn(&Access)` pointers are stored in SINK.
+    let sink: fn(&Access) = unsafe { core::mem::transmute(sink) };
+    sink(&access);
+    IN_SINK.store(false, Ordering::Release);
+}
@@ -0,0 +1,145 @@
+use crate::uart::MmioRegisterBlock;
+use crate::uart::blocking::{
+    BlockingUart, blocking_flush, blocking_read, blocking_write, read_ready, write_ready,
+};
+use crate::uart::error::UartError;
+use atomic_waker::AtomicWaker;
+use core::future::poll_fn;
+use core::task::Poll;
+
+/// Interrupt state shared between an [`AsyncUart`] and its interrupt handler.
+///
+/// Place one in a `static` per UART instance and call [`UartState::on_interrupt`]
+/// from the instance's interrupt handler.
+pub struct UartState {
+    waker: AtomicWaker,
+}
+
+impl UartState {
+    /// Creates a new state with no registered waker.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            waker: AtomicWaker::new(),
+        }
+    }
+
+    /// Handles a UART interrupt.
+    ///
+    /// Masks the data interrupts and wakes the pending future, which
+    /// re-enables the one it waits for on its next poll.
+    #[inline]
+    pub fn on_interrupt(&self, inner: &mut MmioRegisterBlock) {
+        mask_all(inner);
+        self.waker.wake();
+    }
+}
+
+impl Default for UartState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Line condition an [`AsyncUart`] future is waiting for.
+#[derive(Clone, Copy)]
+enum Event {
+    /// Received data is waiting.
+    DataReady,
+    /// The transmitter has room for another character.
+    TransmitReady,
+}
+
+/// Interrupt driven UART implementing embedded-io-async `Read` and `Write`.
+pub struct AsyncUart<'i, 't, 'r> {
+    uart: BlockingUart<'i, 't, 'r>,
+    state: &'static UartState,
+}
+
+impl<'i, 't, 'r> AsyncUart<'i, 't, 'r> {
+    /// Wraps a configured blocking driver.
+    ///
+    /// The UART interrupt must be routed to a handler calling
+    /// [`UartState::on_interrupt`] with the same `state`.
+    #[inline]
+    pub fn new(uart: BlockingUart<'i, 't, 'r>, state: &'static UartState) -> Self {
+        Self { uart, state }
+    }
+
+    /// Returns the underlying blocking driver.
+    #[inline]
+    pub fn into_blocking(mut self) -> BlockingUart<'i, 't, 'r> {
+        mask_all(&mut self.uart.inner);
+        self.uart
+    }
+
+    async fn wait_for(&mut self, event: Event) {
+        let inner = &mut self.uart.inner;
+        poll_fn(|cx| {
+            self.state.waker.register(cx.waker());
+            let ready = match event {
+                Event::DataReady => read_ready(inner),
+                Event::TransmitReady => write_ready(inner),
+            };
+            if ready {
+                return Poll::Ready(());
+            }
+            unsafe {
+                modify_reg!(inner, ier_dlh, modify_ier_dlh, |r| match event {
+                    Event::DataReady => r.with_receive_data_available_interrupt_enable(true),
+                    Event::TransmitReady => r.with_transmit_empty_interrupt_enable(true),
+                })
+            };
+            Poll::Pending
+        })
+        .await;
+        mask_all(inner);
+    }
+}
+
+impl embedded_io::ErrorType for AsyncUart<'_, '_, '_> {
+    type Error = UartError;
+}
+
+impl embedded_io_async::Read for AsyncUart<'_, '_, '_> {
+    /// Waits for at least one byte, then returns everything already received
+    /// that fits in `buf`.
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.uart.check_rx()?;
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        self.wait_for(Event::DataReady).await;
+        Ok(blocking_read(&self.uart.inner, buf))
+    }
+}
+
+impl embedded_io_async::Write for AsyncUart<'_, '_, '_> {
+    /// Waits for room in the transmitter, then queues as much of `buf` as fits.
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.uart.check_tx()?;
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        self.wait_for(Event::TransmitReady).await;
+        Ok(blocking_write(&mut self.uart.inner, buf))
+    }
+
+    /// Waits for the transmitter to take the last character, then spins
+    /// until it has been shifted out.
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.uart.check_tx()?;
+        self.wait_for(Event::TransmitReady).await;
+        blocking_flush(&self.uart.inner, &self.uart.tx_mode)
+    }
+}
+
+/// Mask the data interrupts used by [`AsyncUart`].
+#[inline]
+fn mask_all(inner: &mut MmioRegisterBlock) {
+    unsafe {
+        modify_reg!(inner, ier_dlh, modify_ier_dlh, |r| r
+            .with_receive_data_available_interrupt_enable(false)
+            .with_transmit_empty_interrupt_enable(false));
+    }
+}
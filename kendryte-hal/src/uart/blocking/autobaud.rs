@@ -0,0 +1,177 @@
+use crate::time::Timeout;
+use crate::uart::MmioRegisterBlock;
+use crate::uart::blocking::read_ready;
+use crate::uart::config::{divisor, set_divisor};
+use crate::uart::error::UartError;
+use embedded_time::rate::Baud;
+
+/// Baud rates tried by default, fastest first.
+pub const STANDARD_BAUDS: [u32; 10] = [
+    921_600, 460_800, 230_400, 115_200, 57_600, 38_400, 19_200, 9_600, 4_800, 2_400,
+];
+
+/// Settings of the auto-baud search.
+///
+/// The DesignWare UART cannot time a start bit itself, so the search
+/// programs each candidate rate in turn and listens for the sync character
+/// the remote keeps sending. At a wrong rate the character arrives garbled or
+/// with a framing error; at the right one it arrives intact.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AutoBaudConfig {
+    /// Character the remote sends while the rate is detected.
+    ///
+    /// `0x55` (`U`) alternates every bit, so it is least likely to be
+    /// received intact at a wrong rate.
+    pub sync: u8,
+    /// Rates to try, in order.
+    pub candidates: &'static [u32],
+    /// Time to listen at each rate, in milliseconds.
+    pub window_ms: u32,
+    /// Number of consecutive intact sync characters that confirm a rate.
+    pub matches: usize,
+    /// Largest deviation, in percent, of the rate a divisor produces from
+    /// the candidate; candidates no divisor reaches are skipped.
+    pub tolerance_percent: u32,
+}
+
+impl Default for AutoBaudConfig {
+    fn default() -> Self {
+        Self {
+            sync: 0x55,
+            candidates: &STANDARD_BAUDS,
+            window_ms: 100,
+            matches: 2,
+            tolerance_percent: 4,
+        }
+    }
+}
+
+impl AutoBaudConfig {
+    /// Sets the sync character.
+    pub fn set_sync(mut self, sync: u8) -> Self {
+        self.sync = sync;
+        self
+    }
+
+    /// Sets the rates to try.
+    pub fn set_candidates(mut self, candidates: &'static [u32]) -> Self {
+        self.candidates = candidates;
+        self
+    }
+
+    /// Sets the time to listen at each rate.
+    pub fn set_window_ms(mut self, window_ms: u32) -> Self {
+        self.window_ms = window_ms;
+        self
+    }
+
+    /// Sets the number of sync characters that confirm a rate.
+    pub fn set_matches(mut self, matches: usize) -> Self {
+        self.matches = matches.max(1);
+        self
+    }
+
+    /// Sets the largest rate deviation a candidate may have.
+    pub fn set_tolerance_percent(mut self, tolerance_percent: u32) -> Self {
+        self.tolerance_percent = tolerance_percent;
+        self
+    }
+}
+
+/// Searches for the rate at which the remote's sync characters arrive intact.
+///
+/// Leaves the matching divisor programmed and returns the rate. If no
+/// candidate matches, restores the original divisor and fails with
+/// [`UartError::Timeout`].
+pub(crate) fn auto_baud(
+    uart: &mut MmioRegisterBlock,
+    sclk: u32,
+    config: &AutoBaudConfig,
+) -> Result<Baud, UartError> {
+    let original = divisor(uart);
+    for &baud in config.candidates {
+        let Some(candidate) = candidate_divisor(sclk, baud, config.tolerance_percent) else {
+            continue;
+        };
+        set_divisor(uart, candidate);
+        if listen(uart, config) {
+            return Ok(Baud::new(baud));
+        }
+    }
+    set_divisor(uart, original);
+    Err(UartError::Timeout)
+}
+
+/// Divisor closest to `baud`, if the rate it produces is within
+/// `tolerance_percent` of `baud`.
+///
+/// The divisor is rounded rather than truncated: at a 50 MHz clock,
+/// 460800 baud needs 6.78, and 7 is 3% off where 6 would be 13% off.
+fn candidate_divisor(sclk: u32, baud: u32, tolerance_percent: u32) -> Option<u16> {
+    let step = 16 * u64::from(baud);
+    if step == 0 {
+        return None;
+    }
+    let divisor = (u64::from(sclk) + step / 2) / step;
+    let divisor = u16::try_from(divisor).ok().filter(|&d| d != 0)?;
+    let actual = u64::from(sclk) / (16 * u64::from(divisor));
+    let deviation = actual.abs_diff(u64::from(baud)) * 100;
+    (deviation <= u64::from(tolerance_percent) * u64::from(baud)).then_some(divisor)
+}
+
+/// Returns true if `config.matches` sync characters in a row arrive intact
+/// within the listening window.
+fn listen(uart: &mut MmioRegisterBlock, config: &AutoBaudConfig) -> bool {
+    while read_ready(uart) {
+        let _ = read_reg!(uart, rbr_thr_dll, read_rbr_thr_dll).receiver_buffer();
+    }
+    // Reading LSR clears errors latched at the previous rate.
+    let _ = read_reg!(uart, lsr, read_lsr);
+
+    let timeout = Timeout::from_millis(config.window_ms);
+    let mut matched = 0;
+    while matched < config.matches {
+        let lsr = loop {
+            let lsr = read_reg!(uart, lsr, read_lsr);
+            if lsr.data_ready() {
+                break lsr;
+            }
+            if timeout.is_expired() {
+                return false;
+            }
+            core::hint::spin_loop();
+        };
+        let ch = read_reg!(uart, rbr_thr_dll, read_rbr_thr_dll).receiver_buffer();
+        let intact = !(lsr.framing_error() || lsr.parity_error() || lsr.break_interrupt());
+        if intact && ch == config.sync {
+            matched += 1;
+        } else {
+            matched = 0;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn divisor_is_rounded() {
+        assert_eq!(candidate_divisor(50_000_000, 460_800, 4), Some(7));
+        assert_eq!(candidate_divisor(50_000_000, 115_200, 4), Some(27));
+        assert_eq!(candidate_divisor(50_000_000, 2_400, 4), Some(1302));
+        assert_eq!(candidate_divisor(100_000_000, 921_600, 4), Some(7));
+    }
+
+    #[test]
+    fn divisor_outside_tolerance_is_skipped() {
+        // 3 gives 1041666 baud and 4 gives 781250, both over 13% off.
+        assert_eq!(candidate_divisor(50_000_000, 921_600, 4), None);
+        // 7 gives 446428 baud, 3.1% below 460800.
+        assert_eq!(candidate_divisor(50_000_000, 460_800, 3), None);
+        assert_eq!(candidate_divisor(50_000_000, 0, 4), None);
+        assert_eq!(candidate_divisor(1_000_000, 921_600, 100), None);
+        assert_eq!(candidate_divisor(u32::MAX, 300, 4), None);
+    }
+}
@@ -1,27 +1,77 @@
+mod autobaud;
+mod loopback;
 mod rx;
 mod tx;
+mod view;
 
+pub use autobaud::{AutoBaudConfig, STANDARD_BAUDS};
+pub use loopback::LoopbackReport;
 pub use rx::BlockingUartRx;
 pub use tx::BlockingUartTx;
+pub(crate) use view::{RxRegisters, RxView, TxRegisters, TxView};
 
 use super::pad::FlexPad;
-use crate::clocks::Clocks;
+use crate::clocks::{ClockId, Clocks};
 use crate::instance::Numbered;
+use crate::iomux::ops::PadOps;
+use crate::soc::{self, UartFeatures};
+use crate::sysctl::Sysctl;
+use crate::time::Timeout;
 use crate::uart::MmioRegisterBlock;
 use crate::uart::config::{Config, set_divisor, set_parity_mode, set_stop_bits, set_word_length};
-use crate::uart::config::{disable_fifo, enable_fifo};
+use crate::uart::config::{Context, restore, save};
+use crate::uart::config::{DmaConfig, disable_fifo, enable_fifo, fifo_depth, set_dma};
+use crate::uart::config::{flush_fifos, rx_fifo_level, set_fifo_thresholds, tx_fifo_level};
 use crate::uart::error::UartError;
 use crate::uart::pad::{IntoUartSin, IntoUartSout};
+use crate::uart::{ReceiverInterruptThreshold, TransmitterEmptyThreshold};
 use core::marker::PhantomData;
+use embedded_time::rate::Baud;
+
+/// LCR_EXT bit selecting 9-bit data frames.
+const LCR_EXT_DLS_E: u32 = 1 << 0;
+
+/// DMASA bit acknowledging a DMA transfer from software.
+const DMASA_ACK: u32 = 1 << 0;
+
+/// TCR bit enabling RS-485 mode.
+const TCR_RS485_EN: u32 = 1 << 0;
+/// TCR bit making the receiver enable signal active high.
+const TCR_RE_POL: u32 = 1 << 1;
+/// TCR bit making the driver enable signal active high.
+const TCR_DE_POL: u32 = 1 << 2;
+/// TCR transfer mode with DE and RE switched by the transmitter.
+const TCR_XFER_MODE_HALF_DUPLEX: u32 = 2 << 3;
+
+/// USR bit set while the transmit FIFO has room for another character.
+const USR_TFNF: u32 = 1 << 1;
+/// USR bit set while the transmit FIFO is empty.
+const USR_TFE: u32 = 1 << 2;
 
 /// Checks if the UART is ready to read data.
-pub(crate) fn read_ready(uart: &MmioRegisterBlock) -> bool {
-    uart.read_lsr().data_ready()
+pub(crate) fn read_ready(uart: &impl RxRegisters) -> bool {
+    uart.lsr().data_ready()
+}
+
+/// Address of RBR/THR, the data register DMA channels transfer through.
+#[inline]
+fn thr_address(uart: &MmioRegisterBlock) -> usize {
+    uart.ptr() as usize + core::mem::offset_of!(crate::uart::RegisterBlock, rbr_thr_dll)
 }
 
 /// Checks if the UART is ready to write data.
-pub(crate) fn write_ready(uart: &mut MmioRegisterBlock) -> bool {
-    uart.read_lsr().transmitter_empty() || uart.read_lsr().transmitter_holding_empty()
+///
+/// Reads USR rather than LSR: reading LSR clears the receive error bits,
+/// which belong to whoever owns the receiver.
+pub(crate) fn write_ready(uart: &impl TxRegisters) -> bool {
+    uart.usr() & USR_TFNF != 0
+}
+
+/// Checks if the transmit FIFO, or THR without FIFOs, is empty.
+///
+/// The last character may still be in the shift register.
+pub(crate) fn tx_fifo_empty(uart: &impl TxRegisters) -> bool {
+    uart.usr() & USR_TFE != 0
 }
 
 /// Reads data from UART in a blocking manner.
@@ -29,11 +79,15 @@ pub(crate) fn write_ready(uart: &mut MmioRegisterBlock) -> bool {
 /// This function attempts to read data from the UART into the provided buffer.
 /// It will read as much data as possible until either the buffer is full or no more data is available.
 /// Returns the number of bytes actually read.
-pub(crate) fn blocking_read(uart: &MmioRegisterBlock, buf: &mut [u8]) -> usize {
+#[cfg_attr(
+    feature = "ramfunc",
+    unsafe(link_section = ".ramfunc.uart_blocking_read")
+)]
+pub(crate) fn blocking_read(uart: &impl RxRegisters, buf: &mut [u8]) -> usize {
     let mut count = 0_usize;
     for ch in buf {
         if read_ready(uart) {
-            *ch = uart.read_rbr_thr_dll().receiver_buffer();
+            *ch = uart.rbr();
             count += 1;
         } else {
             break;
@@ -47,13 +101,15 @@ pub(crate) fn blocking_read(uart: &MmioRegisterBlock, buf: &mut [u8]) -> usize {
 /// This function attempts to write data from the provided buffer to the UART.
 /// It will write as much data as possible until either all data is written or the FIFO becomes full.
 /// Returns the number of bytes actually written.
-pub(crate) fn blocking_write(uart: &mut MmioRegisterBlock, buf: &[u8]) -> usize {
+#[cfg_attr(
+    feature = "ramfunc",
+    unsafe(link_section = ".ramfunc.uart_blocking_write")
+)]
+pub(crate) fn blocking_write(uart: &mut impl TxRegisters, buf: &[u8]) -> usize {
     let mut count = 0_usize;
     for ch in buf {
         if write_ready(uart) {
-            unsafe {
-                uart.modify_rbr_thr_dll(|r| r.with_transmitter_holding(*ch));
-            }
+            uart.write_thr(*ch);
             count += 1;
         } else {
             break;
@@ -62,25 +118,213 @@ pub(crate) fn blocking_write(uart: &mut MmioRegisterBlock, buf: &[u8]) -> usize
     count
 }
 
+/// How writes through `embedded_io::Write` treat a busy transmitter.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct TxMode {
+    /// Wait for room until the whole buffer is queued.
+    pub(crate) blocking: bool,
+    /// Most bytes a single write found no room for.
+    pub(crate) watermark: usize,
+    /// Time to send one character at the configured baud rate, in microseconds.
+    pub(crate) char_time_us: u32,
+    /// Characters the transmitter queues: the FIFO depth, or 1 without FIFOs.
+    pub(crate) depth: u32,
+}
+
+impl TxMode {
+    #[inline]
+    pub(crate) fn new(config: &Config, fifo_depth: usize) -> Self {
+        Self {
+            blocking: config.blocking_tx,
+            watermark: 0,
+            char_time_us: char_time_us(config.baud.0),
+            depth: match config.fifo {
+                true => fifo_depth.max(1) as u32,
+                false => 1,
+            },
+        }
+    }
+
+    /// Follows a change of the baud rate.
+    #[inline]
+    pub(crate) fn set_baud(&mut self, baud: u32) {
+        self.char_time_us = char_time_us(baud);
+    }
+
+    /// Bound for the transmitter to drain: twice the time a full FIFO and
+    /// the shift register take at the configured baud rate.
+    #[inline]
+    pub(crate) fn drain_timeout(&self) -> Timeout {
+        Timeout::from_micros(
+            self.char_time_us
+                .saturating_mul(self.depth + 1)
+                .saturating_mul(2),
+        )
+    }
+
+    /// Waits one character time, for the last character to leave the shift
+    /// register once the FIFO is empty.
+    ///
+    /// USR has no shift register status of its own, and its busy bit also
+    /// counts received characters, so the character time stands in for it.
+    #[inline]
+    pub(crate) fn wait_shifted_out(&self) {
+        let _ = Timeout::from_micros(self.char_time_us).wait((), || false);
+    }
+
+    /// Non-blocking flush: once the FIFO is empty, waits for the last
+    /// character to leave the shift register and returns true.
+    pub(crate) fn poll_flush(&self, uart: &impl TxRegisters) -> bool {
+        let empty = tx_fifo_empty(uart);
+        if empty {
+            self.wait_shifted_out();
+        }
+        empty
+    }
+
+    /// Queues `buf` and returns the number of bytes queued.
+    ///
+    /// Waits for room for at least the first byte, and fails with
+    /// [`UartError::Timeout`] if the transmitter makes no progress for a
+    /// [`drain_timeout`](Self::drain_timeout), so a non-empty `buf` never
+    /// yields `Ok(0)`. A blocking write then keeps waiting until the whole
+    /// buffer is queued, and only returns short if the transmitter stalls.
+    pub(crate) fn write(
+        &mut self,
+        uart: &mut impl TxRegisters,
+        buf: &[u8],
+    ) -> Result<usize, UartError> {
+        let mut count = blocking_write(uart, buf);
+        self.watermark = self.watermark.max(buf.len() - count);
+        if count == buf.len() || (count > 0 && !self.blocking) {
+            return Ok(count);
+        }
+        loop {
+            let ready = self
+                .drain_timeout()
+                .wait(UartError::Timeout, || write_ready(uart));
+            if let Err(error) = ready {
+                return if count == 0 { Err(error) } else { Ok(count) };
+            }
+            count += blocking_write(uart, &buf[count..]);
+            if count == buf.len() || !self.blocking {
+                return Ok(count);
+            }
+        }
+    }
+
+    /// Queues all of `buf`, failing with [`UartError::Timeout`] if the
+    /// transmitter stalls.
+    pub(crate) fn write_all(
+        &mut self,
+        uart: &mut impl TxRegisters,
+        mut buf: &[u8],
+    ) -> Result<(), UartError> {
+        while !buf.is_empty() {
+            let count = self.write(uart, buf)?;
+            buf = &buf[count..];
+        }
+        Ok(())
+    }
+}
+
+/// Bits in the longest character: start bit, 8 data bits, parity and 2 stop bits.
+const MAX_CHAR_BITS: u32 = 12;
+
+/// Time to send the longest character at `baud`, in microseconds.
+#[inline]
+const fn char_time_us(baud: u32) -> u32 {
+    (MAX_CHAR_BITS * 1_000_000).div_ceil(if baud == 0 { 1 } else { baud })
+}
+
+/// Fills `buf` completely, or fails with [`UartError::Timeout`] once `timeout` expires.
+pub(crate) fn read_exact_timeout(
+    uart: &impl RxRegisters,
+    buf: &mut [u8],
+    timeout: Timeout,
+) -> Result<(), UartError> {
+    for ch in buf {
+        timeout.wait(UartError::Timeout, || read_ready(uart))?;
+        *ch = uart.rbr();
+    }
+    Ok(())
+}
+
+/// Reads into `buf` up to and including `delimiter`, returning the number of bytes read.
+pub(crate) fn read_until(
+    uart: &impl RxRegisters,
+    delimiter: u8,
+    buf: &mut [u8],
+    timeout: Timeout,
+) -> Result<usize, UartError> {
+    for (i, ch) in buf.iter_mut().enumerate() {
+        timeout.wait(UartError::Timeout, || read_ready(uart))?;
+        *ch = uart.rbr();
+        if *ch == delimiter {
+            return Ok(i + 1);
+        }
+    }
+    Err(UartError::BufferFull)
+}
+
+/// Reads a `\n` or `\r\n` terminated line, returning its length without the terminator.
+pub(crate) fn read_line(
+    uart: &impl RxRegisters,
+    buf: &mut [u8],
+    timeout: Timeout,
+) -> Result<usize, UartError> {
+    let len = read_until(uart, b'\n', buf, timeout)? - 1;
+    Ok(match len {
+        1.. if buf[len - 1] == b'\r' => len - 1,
+        _ => len,
+    })
+}
+
 /// Flushes the UART transmitter by waiting until all data has been sent.
 ///
-/// This function blocks until the transmitter is completely empty.
-pub(crate) fn blocking_flush(uart: &mut MmioRegisterBlock) {
-    while !uart.read_lsr().transmitter_empty() {
-        core::hint::spin_loop();
+/// This function blocks until the transmit FIFO is empty and then for one
+/// more character time, so the last character has left the shift register.
+/// Fails with [`UartError::Timeout`] if the FIFO does not drain within the
+/// [`TxMode::drain_timeout`] of `mode`.
+pub(crate) fn blocking_flush(uart: &impl TxRegisters, mode: &TxMode) -> Result<(), UartError> {
+    mode.drain_timeout()
+        .wait(UartError::Timeout, || tx_fifo_empty(uart))?;
+    mode.wait_shifted_out();
+    Ok(())
+}
+
+/// Disables all UART interrupts and the FIFO.
+pub(crate) fn deconfigure(uart: &mut MmioRegisterBlock) {
+    unsafe {
+        modify_reg!(uart, ier_dlh, modify_ier_dlh, |r| {
+            r.with_modem_status_interrupt_enable(false)
+                .with_transmit_empty_interrupt_enable(false)
+                .with_receive_data_available_interrupt_enable(false)
+                .with_receive_line_status_interrupt_enable(false)
+                .with_programmable_threshold_interrupt_enable(false)
+        });
     }
+    disable_fifo(uart);
 }
 
 /// A wrapper struct for UART that provides blocking operations.
 ///
 /// This struct implements blocking read and write operations for UART communication.
 pub struct BlockingUart<'i, 't, 'r> {
-    inner: MmioRegisterBlock<'static>,
-    tx: Option<BlockingUartTx<'i, 't>>,
-    rx: Option<BlockingUartRx<'i, 'r>>,
+    pub(super) inner: MmioRegisterBlock<'static>,
+    tx: Option<FlexPad<'t>>,
+    rx: Option<FlexPad<'r>>,
+    features: UartFeatures,
+    sclk: u32,
+    clock: ClockId,
+    context: Option<Context>,
+    pub(super) tx_mode: TxMode,
     _marker: PhantomData<&'i ()>,
 }
 
+// SAFETY: the driver owns its register block and pads exclusively.
+unsafe impl Send for BlockingUart<'_, '_, '_> {}
+
 impl<'i, 't, 'r> BlockingUart<'i, 't, 'r> {
     /// Creates a new BlockingUart instance with the specified configuration.
     ///
@@ -95,34 +339,46 @@ impl<'i, 't, 'r> BlockingUart<'i, 't, 'r> {
     ) -> Self {
         let mut inner = instance.inner();
         Self::configure::<N>(&mut inner, config, clocks);
+        let tx_mode = TxMode::new(&config, fifo_depth(&inner));
 
-        let mut blocking_uart_tx = None;
-        let mut blocking_uart_rx = None;
-
-        if let Some(tx) = tx {
-            let tx = tx.into_uart_sout();
-            blocking_uart_tx = Some(BlockingUartTx {
-                inner: unsafe { inner.clone() },
-                tx,
-                _marker: PhantomData,
-            });
+        BlockingUart {
+            inner,
+            tx: tx.map(IntoUartSout::into_uart_sout),
+            rx: rx.map(IntoUartSin::into_uart_sin),
+            features: soc::uart::<N>(),
+            sclk: clocks.uart_sclk::<N>().0,
+            clock: ClockId::UartSclk(N as u8),
+            context: None,
+            tx_mode,
+            _marker: PhantomData,
         }
+    }
 
-        if let Some(rx) = rx {
-            let rx = rx.into_uart_sin();
-            blocking_uart_rx = Some(BlockingUartRx {
-                inner: unsafe { inner.clone() },
-                rx,
-                _marker: PhantomData,
-            })
-        }
+    /// Like [`BlockingUart::new`], but first checks that the instance is a
+    /// UART controller; see [`crate::ident`].
+    pub fn try_new<const N: usize>(
+        instance: impl Numbered<'i, N, R = MmioRegisterBlock<'static>>,
+        tx: Option<impl IntoUartSout<'t, N>>,
+        rx: Option<impl IntoUartSin<'r, N>>,
+        config: Config,
+        clocks: Clocks,
+    ) -> Result<Self, UartError> {
+        let mut inner = instance.inner();
+        crate::ident::uart(&inner)?;
+        Self::configure::<N>(&mut inner, config, clocks);
+        let tx_mode = TxMode::new(&config, fifo_depth(&inner));
 
-        BlockingUart {
-            inner: unsafe { inner.clone() },
-            tx: blocking_uart_tx,
-            rx: blocking_uart_rx,
+        Ok(BlockingUart {
+            inner,
+            tx: tx.map(IntoUartSout::into_uart_sout),
+            rx: rx.map(IntoUartSin::into_uart_sin),
+            features: soc::uart::<N>(),
+            sclk: clocks.uart_sclk::<N>().0,
+            clock: ClockId::UartSclk(N as u8),
+            context: None,
+            tx_mode,
             _marker: PhantomData,
-        }
+        })
     }
 
     /// Configures the UART peripheral with the specified settings.
@@ -134,7 +390,7 @@ impl<'i, 't, 'r> BlockingUart<'i, 't, 'r> {
         clocks: Clocks,
     ) {
         unsafe {
-            uart.modify_ier_dlh(|r| {
+            modify_reg!(uart, ier_dlh, modify_ier_dlh, |r| {
                 r.with_modem_status_interrupt_enable(false)
                     .with_transmit_empty_interrupt_enable(false)
                     .with_receive_data_available_interrupt_enable(false)
@@ -153,17 +409,373 @@ impl<'i, 't, 'r> BlockingUart<'i, 't, 'r> {
             true => enable_fifo(uart),
             false => disable_fifo(uart),
         }
+        set_fifo_thresholds(uart, config.tx_threshold, config.rx_threshold);
+    }
+
+    /// Optional controller features available on this instance.
+    #[inline]
+    pub fn features(&self) -> UartFeatures {
+        self.features
+    }
+
+    /// Enable or disable 9-bit data frames.
+    ///
+    /// When enabled the word length setting is ignored and frames carry
+    /// 9 data bits. Fails with [`UartError::NotSupported`] if the instance
+    /// was synthesized without 9-bit support.
+    pub fn set_nine_bit(&mut self, enable: bool) -> Result<(), UartError> {
+        if !self.features.nine_bit {
+            return Err(UartError::NotSupported);
+        }
+        let lcr_ext = read_reg!(self.inner, lcr_ext, read_lcr_ext);
+        let lcr_ext = if enable {
+            lcr_ext | LCR_EXT_DLS_E
+        } else {
+            lcr_ext & !LCR_EXT_DLS_E
+        };
+        unsafe { write_reg!(self.inner, lcr_ext, write_lcr_ext, lcr_ext) };
+        Ok(())
+    }
+
+    /// Transmit and receive FIFO depth in characters.
+    ///
+    /// Returns 0 if the instance was synthesized without FIFOs.
+    #[inline]
+    pub fn fifo_depth(&self) -> usize {
+        fifo_depth(&self.inner)
+    }
+
+    /// Changes the FIFO interrupt thresholds set in [`Config`].
+    pub fn set_fifo_thresholds(
+        &mut self,
+        tx: TransmitterEmptyThreshold,
+        rx: ReceiverInterruptThreshold,
+    ) {
+        set_fifo_thresholds(&mut self.inner, tx, rx);
+    }
+
+    /// Number of characters waiting in the transmit FIFO.
+    #[inline]
+    pub fn tx_fifo_level(&self) -> usize {
+        tx_fifo_level(&self.inner)
+    }
+
+    /// Number of characters waiting in the receive FIFO.
+    #[inline]
+    pub fn rx_fifo_level(&self) -> usize {
+        rx_fifo_level(&self.inner)
+    }
+
+    /// Most bytes a single write found no room for in the transmitter.
+    ///
+    /// Zero means the line always kept up with the writers. With
+    /// [`Config::blocking_tx`] disabled, anything else means some write
+    /// returned short, and output was lost if its caller ignored the count.
+    #[inline]
+    pub fn tx_watermark(&self) -> usize {
+        self.tx_mode.watermark
+    }
+
+    /// Resets [`tx_watermark`](Self::tx_watermark) to zero.
+    #[inline]
+    pub fn reset_tx_watermark(&mut self) {
+        self.tx_mode.watermark = 0;
+    }
+
+    /// Raise the UART interrupt while received data is waiting.
+    ///
+    /// With the FIFO enabled the interrupt is raised once the receive
+    /// threshold is reached, or when characters sit in the FIFO unread for
+    /// a few character times.
+    pub fn listen_rx(&mut self) {
+        unsafe {
+            modify_reg!(self.inner, ier_dlh, modify_ier_dlh, |r| r
+                .with_receive_data_available_interrupt_enable(true));
+        }
+    }
+
+    /// Stop raising the UART interrupt for received data.
+    pub fn unlisten_rx(&mut self) {
+        unsafe {
+            modify_reg!(self.inner, ier_dlh, modify_ier_dlh, |r| r
+                .with_receive_data_available_interrupt_enable(false));
+        }
+    }
+
+    /// Switch to half-duplex RS-485 mode.
+    ///
+    /// The controller asserts the driver enable output while characters are
+    /// shifted out and the receiver enable output otherwise, so a transceiver
+    /// wired to them needs no GPIO toggling around each frame. Both outputs
+    /// are active high; `de_assertion` and `de_deassertion` are the times, in
+    /// serial clock cycles, that DE is asserted before the first start bit and
+    /// kept after the last stop bit.
+    pub fn enable_rs485(&mut self, de_assertion: u8, de_deassertion: u8) {
+        let det = (de_deassertion as u32) << 16 | de_assertion as u32;
+        unsafe {
+            write_reg!(self.inner, det, write_det, det);
+            write_reg!(
+                self.inner,
+                tcr,
+                write_tcr,
+                TCR_RS485_EN | TCR_RE_POL | TCR_DE_POL | TCR_XFER_MODE_HALF_DUPLEX
+            );
+            write_reg!(self.inner, de_en, write_de_en, 1);
+            write_reg!(self.inner, re_en, write_re_en, 1);
+        }
+    }
+
+    /// Leave RS-485 mode; the DE and RE outputs are released.
+    pub fn disable_rs485(&mut self) {
+        unsafe {
+            write_reg!(self.inner, de_en, write_de_en, 0);
+            write_reg!(self.inner, re_en, write_re_en, 0);
+            write_reg!(self.inner, tcr, write_tcr, 0);
+        }
+    }
+
+    /// Discards everything in the transmit and receive FIFOs.
+    ///
+    /// Characters already in the transmit shift register still go out.
+    pub fn fifo_flush(&mut self) {
+        flush_fifos(&mut self.inner, true, true);
+    }
+
+    /// Configures the DMA handshake interface.
+    ///
+    /// Use [`DmaConfig::tx_burst`] and [`DmaConfig::rx_burst`] with
+    /// [`fifo_depth`](Self::fifo_depth) to pick the burst size of the DMA
+    /// channels, and [`dma_tx_address`](Self::dma_tx_address) and
+    /// [`dma_rx_address`](Self::dma_rx_address) as their peripheral address.
+    pub fn configure_dma(&mut self, config: DmaConfig) {
+        set_dma(&mut self.inner, config);
+    }
+
+    /// Address of the transmit holding register, the destination of a transmit DMA channel.
+    #[inline]
+    pub fn dma_tx_address(&self) -> usize {
+        thr_address(&self.inner)
+    }
+
+    /// Address of the receive buffer register, the source of a receive DMA channel.
+    #[inline]
+    pub fn dma_rx_address(&self) -> usize {
+        thr_address(&self.inner)
+    }
+
+    /// Acknowledges a DMA transfer from software.
+    ///
+    /// Clears the request handshake after the DMA controller aborted a
+    /// transfer, so the next request can be raised.
+    pub fn dma_software_ack(&mut self) {
+        unsafe { write_reg!(self.inner, dmasa, write_dmasa, DMASA_ACK) };
+    }
+
+    /// Reads exactly `buf.len()` bytes.
+    ///
+    /// Fails with [`UartError::Timeout`] if they have not all arrived when
+    /// `timeout` expires; bytes received until then are left in `buf`.
+    pub fn read_exact_timeout(
+        &mut self,
+        buf: &mut [u8],
+        timeout: Timeout,
+    ) -> Result<(), UartError> {
+        self.check_rx()?;
+        read_exact_timeout(&self.inner, buf, timeout)
+    }
+
+    /// Reads bytes into `buf` until `delimiter` is received.
+    ///
+    /// Returns the number of bytes read, including the delimiter. Fails with
+    /// [`UartError::BufferFull`] if `buf` fills up first, or with
+    /// [`UartError::Timeout`] once `timeout` expires.
+    pub fn read_until(
+        &mut self,
+        delimiter: u8,
+        buf: &mut [u8],
+        timeout: Timeout,
+    ) -> Result<usize, UartError> {
+        self.check_rx()?;
+        read_until(&self.inner, delimiter, buf, timeout)
+    }
+
+    /// Reads a line terminated by `\n` or `\r\n`.
+    ///
+    /// Returns the length of the line without its terminator; errors are as
+    /// for [`read_until`](Self::read_until).
+    pub fn read_line(&mut self, buf: &mut [u8], timeout: Timeout) -> Result<usize, UartError> {
+        self.check_rx()?;
+        read_line(&self.inner, buf, timeout)
+    }
+
+    /// Detects the baud rate of the remote and switches to it.
+    ///
+    /// The remote must keep sending `config.sync` during the search, as
+    /// bootloaders waiting for a sync character or GPS units streaming
+    /// sentences do. Each candidate rate is tried for `config.window_ms`;
+    /// the first at which `config.matches` sync characters in a row arrive
+    /// without framing or parity errors is kept and returned. Candidates no
+    /// divisor of the UART clock reaches within `config.tolerance_percent`
+    /// are skipped. Fails with [`UartError::Timeout`], restoring the
+    /// previous rate, if none matches.
+    pub fn auto_baud(&mut self, config: AutoBaudConfig) -> Result<Baud, UartError> {
+        self.check_rx()?;
+        let baud = autobaud::auto_baud(&mut self.inner, self.sclk, &config)?;
+        self.tx_mode.set_baud(baud.0);
+        Ok(baud)
+    }
+
+    /// Runs an internal loopback self-test at the configured baud rate.
+    ///
+    /// Uses the MCR loopback bit so that transmitted characters are routed back
+    /// to the receiver inside the controller, without touching the pads.
+    /// A passing report on a link that does not work on the board points at
+    /// pad multiplexing or wiring rather than the UART configuration.
+    pub fn loopback_test(&mut self) -> LoopbackReport {
+        loopback::loopback_test(&mut self.inner)
+    }
+
+    /// Saves the UART configuration and gates its clock.
+    ///
+    /// Waits until pending data has been transmitted first. The driver must
+    /// not transfer data until [`resume`](Self::resume); characters arriving
+    /// in the meantime are lost. Suspending a suspended UART does nothing.
+    pub fn suspend(&mut self, sysctl: &mut Sysctl<'_>) -> Result<(), UartError> {
+        if self.context.is_some() {
+            return Ok(());
+        }
+        if self.tx.is_some() {
+            blocking_flush(&self.inner, &self.tx_mode)?;
+        }
+        self.context = Some(save(&mut self.inner, self.features));
+        sysctl.disable_clock(self.clock);
+        Ok(())
+    }
+
+    /// Enables the clock and restores the configuration saved by
+    /// [`suspend`](Self::suspend).
+    ///
+    /// The registers are rewritten even if they kept their values, so the
+    /// UART also comes back after its power domain was switched off. Does
+    /// nothing if the UART is not suspended.
+    pub fn resume(&mut self, sysctl: &mut Sysctl<'_>) {
+        if let Some(context) = self.context.take() {
+            sysctl.enable_clock(self.clock);
+            restore(&mut self.inner, &context);
+        }
+    }
+
+    /// Returns true between [`suspend`](Self::suspend) and [`resume`](Self::resume).
+    #[inline]
+    pub fn is_suspended(&self) -> bool {
+        self.context.is_some()
+    }
+
+    /// Deconfigures the UART and releases its pads.
+    ///
+    /// Waits until pending data has been transmitted, then disables UART
+    /// interrupts and the FIFO. Returned pads have input and output disabled,
+    /// ready to be configured for another function.
+    pub fn free(mut self) -> (Option<FlexPad<'t>>, Option<FlexPad<'r>>) {
+        if self.tx.is_some() {
+            let _ = blocking_flush(&self.inner, &self.tx_mode);
+        }
+        deconfigure(&mut self.inner);
+        (
+            self.tx.map(|mut tx| {
+                tx.set_disabled();
+                tx
+            }),
+            self.rx.map(|mut rx| {
+                rx.set_disabled();
+                rx
+            }),
+        )
     }
 
     /// Splits the BlockingUart into separate transmitter and receiver handles.
     /// Returns ownership of the transmitter and receiver, if available.
+    ///
+    /// See [`into_split`](Self::into_split) when both pads are present.
     pub fn split(
-        self,
+        mut self,
     ) -> (
         Option<BlockingUartTx<'i, 't>>,
         Option<BlockingUartRx<'i, 'r>>,
     ) {
-        (self.tx, self.rx)
+        // SAFETY: the driver is consumed, so the halves get the only views of
+        // the transmit and receive registers, and they touch no other register.
+        let (tx_regs, rx_regs) =
+            unsafe { (TxView::new(&mut self.inner), RxView::new(&mut self.inner)) };
+        let tx = self.tx.map(|tx| BlockingUartTx {
+            inner: tx_regs,
+            tx,
+            tx_mode: self.tx_mode,
+            _marker: PhantomData,
+        });
+        let rx = self.rx.map(|rx| BlockingUartRx {
+            inner: rx_regs,
+            rx,
+            _marker: PhantomData,
+        });
+        (tx, rx)
+    }
+
+    /// Splits the BlockingUart into a transmitter and a receiver that are
+    /// owned independently of each other.
+    ///
+    /// Returns the driver unchanged if it was created without a TX or an RX
+    /// pad. Each half is `Send` and borrows nothing but what the driver was
+    /// created from, so a UART created from owned peripheral and pad tokens
+    /// splits into `BlockingUartTx<'static, 'static>` and
+    /// `BlockingUartRx<'static, 'static>`. Those can be moved into another
+    /// task or kept in a `static` [`Mutex`](crate::sync::Mutex) for an
+    /// interrupt handler:
+    ///
+    /// ```ignore
+    /// static RX: Mutex<Option<BlockingUartRx<'static, 'static>>> = Mutex::new(None);
+    ///
+    /// let uart = BlockingUart::new(p.uart0, Some(p.iomux.io38), Some(p.iomux.io39), config, c);
+    /// let Ok((tx, rx)) = uart.into_split() else { panic!("UART without pads") };
+    /// RX.lock(|slot| *slot = Some(rx));
+    /// ```
+    // The error hands the unsplit driver back to the caller.
+    #[allow(clippy::result_large_err)]
+    pub fn into_split(mut self) -> Result<(BlockingUartTx<'i, 't>, BlockingUartRx<'i, 'r>), Self> {
+        let (tx, rx) = match (self.tx.take(), self.rx.take()) {
+            (Some(tx), Some(rx)) => (tx, rx),
+            (tx, rx) => {
+                self.tx = tx;
+                self.rx = rx;
+                return Err(self);
+            }
+        };
+        // SAFETY: as in `split`, the driver is consumed and each half gets
+        // the only view of its registers.
+        let (tx_regs, rx_regs) =
+            unsafe { (TxView::new(&mut self.inner), RxView::new(&mut self.inner)) };
+        let tx = BlockingUartTx {
+            inner: tx_regs,
+            tx,
+            tx_mode: self.tx_mode,
+            _marker: PhantomData,
+        };
+        let rx = BlockingUartRx {
+            inner: rx_regs,
+            rx,
+            _marker: PhantomData,
+        };
+        Ok((tx, rx))
+    }
+
+    #[inline]
+    pub(super) fn check_tx(&self) -> Result<(), UartError> {
+        self.tx.as_ref().map(|_| ()).ok_or(UartError::NotFoundTx)
+    }
+
+    #[inline]
+    pub(super) fn check_rx(&self) -> Result<(), UartError> {
+        self.rx.as_ref().map(|_| ()).ok_or(UartError::NotFoundRx)
     }
 }
 
@@ -173,35 +785,39 @@ impl<'i, 't, 'r> embedded_io::ErrorType for BlockingUart<'i, 't, 'r> {
 
 impl<'i, 't, 'r> embedded_io::Read for BlockingUart<'i, 't, 'r> {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
-        self.rx.as_mut().ok_or(UartError::NotFoundRx)?.read(buf)
+        self.check_rx()?;
+        Ok(blocking_read(&self.inner, buf))
     }
 }
 
 impl<'i, 't, 'r> embedded_io::Write for BlockingUart<'i, 't, 'r> {
     fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
-        self.tx.as_mut().ok_or(UartError::NotFoundRx)?.write(buf)
+        self.check_tx()?;
+        self.tx_mode.write(&mut self.inner, buf)
     }
 
     fn flush(&mut self) -> Result<(), Self::Error> {
-        self.tx.as_mut().ok_or(UartError::NotFoundRx)?.flush()
+        self.check_tx()?;
+        blocking_flush(&self.inner, &self.tx_mode)
     }
+
     fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error> {
-        self.tx
-            .as_mut()
-            .ok_or(UartError::NotFoundRx)?
-            .write_all(buf)
+        self.check_tx()?;
+        self.tx_mode.write_all(&mut self.inner, buf)
     }
 }
 
 impl<'i, 't, 'r> embedded_io::ReadReady for BlockingUart<'i, 't, 'r> {
     fn read_ready(&mut self) -> Result<bool, Self::Error> {
-        self.rx.as_mut().ok_or(UartError::NotFoundRx)?.read_ready()
+        self.check_rx()?;
+        Ok(read_ready(&self.inner))
     }
 }
 
 impl<'i, 't, 'r> embedded_io::WriteReady for BlockingUart<'i, 't, 'r> {
     fn write_ready(&mut self) -> Result<bool, Self::Error> {
-        self.tx.as_mut().ok_or(UartError::NotFoundRx)?.write_ready()
+        self.check_tx()?;
+        Ok(write_ready(&self.inner))
     }
 }
 
@@ -211,16 +827,108 @@ impl<'i, 't, 'r> embedded_hal_nb::serial::ErrorType for BlockingUart<'i, 't, 'r>
 
 impl<'i, 't, 'r> embedded_hal_nb::serial::Read for BlockingUart<'i, 't, 'r> {
     fn read(&mut self) -> embedded_hal_nb::nb::Result<u8, Self::Error> {
-        self.rx.as_mut().ok_or(UartError::NotFoundRx)?.read()
+        self.check_rx()?;
+        let mut buf = [0];
+        match blocking_read(&self.inner, &mut buf) {
+            0 => Err(embedded_hal_nb::nb::Error::WouldBlock),
+            _ => Ok(buf[0]),
+        }
     }
 }
 
 impl<'i, 't, 'r> embedded_hal_nb::serial::Write for BlockingUart<'i, 't, 'r> {
     fn write(&mut self, word: u8) -> embedded_hal_nb::nb::Result<(), Self::Error> {
-        self.tx.as_mut().ok_or(UartError::NotFoundRx)?.write(word)
+        self.check_tx()?;
+        match blocking_write(&mut self.inner, &[word]) {
+            0 => Err(embedded_hal_nb::nb::Error::WouldBlock),
+            _ => Ok(()),
+        }
     }
 
     fn flush(&mut self) -> embedded_hal_nb::nb::Result<(), Self::Error> {
-        self.tx.as_mut().ok_or(UartError::NotFoundRx)?.flush()
+        self.check_tx()?;
+        match self.tx_mode.poll_flush(&self.inner) {
+            true => Ok(()),
+            false => Err(embedded_hal_nb::nb::Error::WouldBlock),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::mock;
+    use crate::trace::AccessKind;
+    use crate::uart::{Lsr, RbrThrDll, RegisterBlock};
+    use std::vec::Vec;
+
+    #[test]
+    fn tx_mode_from_config() {
+        let mode = TxMode::new(&Config::new(), 32);
+        assert_eq!(mode.char_time_us, 105);
+        assert_eq!(mode.depth, 1);
+
+        let config = Config::new().set_fifo(true).set_baud(Baud::new(9600));
+        let mut mode = TxMode::new(&config, 32);
+        assert_eq!(mode.char_time_us, 1250);
+        assert_eq!(mode.depth, 32);
+        mode.set_baud(921_600);
+        assert_eq!(mode.char_time_us, 14);
+    }
+
+    #[test]
+    fn write_leaves_receiver_registers_alone() {
+        let block = mock::block::<RegisterBlock>();
+        let mut uart = unsafe { RegisterBlock::new_mmio(block) };
+        unsafe { (*block).usr = USR_TFNF };
+        let (count, log) = mock::capture(|| blocking_write(&mut uart, b"ok"));
+        assert_eq!(count, 2);
+        let reads: Vec<_> = log
+            .iter()
+            .filter(|access| access.kind == AccessKind::Read)
+            .map(|access| access.offset)
+            .collect();
+        assert_eq!(reads, [0x7C, 0x7C]);
+        assert_eq!(
+            mock::writes(&log),
+            [(0x00, b'o' as u64), (0x00, b'k' as u64)]
+        );
+        unsafe { (*block).usr = 0 };
+        assert_eq!(blocking_write(&mut uart, b"!"), 0);
+    }
+
+    #[test]
+    fn split_views_stay_on_their_registers() {
+        let block = mock::block::<RegisterBlock>();
+        let mut uart = unsafe { RegisterBlock::new_mmio(block) };
+        let (mut tx, rx) = unsafe { (TxView::new(&mut uart), RxView::new(&mut uart)) };
+        let offsets = |log: &[crate::trace::Access]| -> Vec<_> {
+            log.iter()
+                .map(|access| (access.kind, access.offset))
+                .collect()
+        };
+
+        unsafe { (*block).usr = USR_TFNF };
+        let (count, log) = mock::capture(|| blocking_write(&mut tx, b"a"));
+        assert_eq!(count, 1);
+        assert_eq!(
+            offsets(&log),
+            [(AccessKind::Read, 0x7C), (AccessKind::Write, 0x00)]
+        );
+        assert_eq!(mock::writes(&log), [(0x00, b'a' as u64)]);
+
+        unsafe {
+            (*block).lsr = Lsr::new_with_raw_value(1);
+            (*block).rbr_thr_dll = RbrThrDll::new_with_raw_value(b'z' as u32);
+        }
+        let mut buf = [0];
+        let (count, log) = mock::capture(|| blocking_read(&rx, &mut buf));
+        assert_eq!((count, buf), (1, [b'z']));
+        assert_eq!(
+            offsets(&log),
+            [(AccessKind::Read, 0x14), (AccessKind::Read, 0x00)]
+        );
     }
 }
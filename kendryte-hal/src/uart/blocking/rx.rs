@@ -1,35 +1,83 @@
 use crate::iomux::FlexPad;
-use crate::uart::blocking::{blocking_read, read_ready};
-use crate::uart::{MmioRegisterBlock, RegisterBlock, UartError};
+use crate::iomux::ops::PadOps;
+use crate::time::Timeout;
+use crate::uart::UartError;
+use crate::uart::blocking::{
+    RxView, blocking_read, read_exact_timeout, read_line, read_ready, read_until,
+};
 use core::marker::PhantomData;
 
 /// A UART receiver for blocking operations.
 /// This struct implements blocking read operations for UART communication.
 pub struct BlockingUartRx<'i, 'r> {
-    /// Holds a reference to the UART register block.
-    pub(crate) inner: MmioRegisterBlock<'static>,
+    /// RBR and LSR of the UART.
+    pub(crate) inner: RxView,
     /// Contains a mutable handle to the RX pad.
     pub(crate) rx: FlexPad<'r>,
     /// Uses PhantomData for lifetime tracking.
     pub(crate) _marker: PhantomData<&'i ()>,
 }
 
+// SAFETY: see the `Send` implementation of `BlockingUartTx`.
+unsafe impl Send for BlockingUartRx<'_, '_> {}
+
+impl<'i, 'r> BlockingUartRx<'i, 'r> {
+    /// Releases the RX pad.
+    ///
+    /// The returned pad has input and output disabled. The UART itself stays
+    /// configured, as the transmitter half may still be in use.
+    pub fn free(self) -> FlexPad<'r> {
+        self.release()
+    }
+
+    /// Reads exactly `buf.len()` bytes.
+    ///
+    /// Fails with [`UartError::Timeout`] if they have not all arrived when
+    /// `timeout` expires; bytes received until then are left in `buf`.
+    pub fn read_exact_timeout(
+        &mut self,
+        buf: &mut [u8],
+        timeout: Timeout,
+    ) -> Result<(), UartError> {
+        read_exact_timeout(&self.inner, buf, timeout)
+    }
+
+    /// Reads bytes into `buf` until `delimiter` is received.
+    ///
+    /// Returns the number of bytes read, including the delimiter. Fails with
+    /// [`UartError::BufferFull`] if `buf` fills up first, or with
+    /// [`UartError::Timeout`] once `timeout` expires.
+    pub fn read_until(
+        &mut self,
+        delimiter: u8,
+        buf: &mut [u8],
+        timeout: Timeout,
+    ) -> Result<usize, UartError> {
+        read_until(&self.inner, delimiter, buf, timeout)
+    }
+
+    /// Reads a line terminated by `\n` or `\r\n`.
+    ///
+    /// Returns the length of the line without its terminator; errors are as
+    /// for [`read_until`](Self::read_until).
+    pub fn read_line(&mut self, buf: &mut [u8], timeout: Timeout) -> Result<usize, UartError> {
+        read_line(&self.inner, buf, timeout)
+    }
+
+    /// Disables the RX pad and returns it.
+    pub(crate) fn release(mut self) -> FlexPad<'r> {
+        self.rx.set_disabled();
+        self.rx
+    }
+}
+
 impl<'i, 'r> embedded_io::ErrorType for BlockingUartRx<'i, 'r> {
     type Error = UartError;
 }
 
 impl<'i, 'r> embedded_io::Read for BlockingUartRx<'i, 'r> {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
-        let mut count = 0_usize;
-        for ch in buf {
-            if self.inner.read_lsr().data_ready() {
-                *ch = self.inner.read_rbr_thr_dll().receiver_buffer();
-                count += 1;
-            } else {
-                break;
-            }
-        }
-        Ok(count)
+        Ok(blocking_read(&self.inner, buf))
     }
 }
 
@@ -40,7 +88,7 @@ impl<'i, 'r> embedded_hal_nb::serial::ErrorType for BlockingUartRx<'i, 'r> {
 impl<'i, 'r> embedded_hal_nb::serial::Read for BlockingUartRx<'i, 'r> {
     fn read(&mut self) -> embedded_hal_nb::nb::Result<u8, Self::Error> {
         let mut buf = [0];
-        let len = blocking_read(&mut self.inner, &mut buf);
+        let len = blocking_read(&self.inner, &mut buf);
         match len {
             0 => Err(embedded_hal_nb::nb::Error::WouldBlock),
             _ => Ok(buf[0]),
@@ -50,6 +98,6 @@ impl<'i, 'r> embedded_hal_nb::serial::Read for BlockingUartRx<'i, 'r> {
 
 impl<'i, 'r> embedded_io::ReadReady for BlockingUartRx<'i, 'r> {
     fn read_ready(&mut self) -> Result<bool, Self::Error> {
-        Ok(read_ready(&mut self.inner))
+        Ok(read_ready(&self.inner))
     }
 }
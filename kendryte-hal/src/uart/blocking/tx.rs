@@ -1,40 +1,74 @@
 use crate::iomux::FlexPad;
-use crate::uart::blocking::{blocking_flush, blocking_write, write_ready};
-use crate::uart::{MmioRegisterBlock, UartError};
+use crate::iomux::ops::PadOps;
+use crate::uart::UartError;
+use crate::uart::blocking::{TxMode, TxView, blocking_flush, blocking_write, write_ready};
 use core::marker::PhantomData;
 
 /// A UART transmitter for blocking operations.
 /// This struct implements blocking write operations for UART communication.
 pub struct BlockingUartTx<'i, 't> {
-    /// Holds a reference to the UART register block.
-    pub(crate) inner: MmioRegisterBlock<'static>,
+    /// THR and USR of the UART.
+    pub(crate) inner: TxView,
     /// Contains a mutable handle to the TX pad.
     pub(crate) tx: FlexPad<'t>,
+    /// Write behaviour taken over from the full driver.
+    pub(crate) tx_mode: TxMode,
     /// Uses PhantomData for lifetime tracking.
     pub(crate) _marker: PhantomData<&'i ()>,
 }
 
+// SAFETY: the transmitter only reaches THR and USR through its view, and
+// the receiver half only RBR and LSR. THR and RBR share an address, but the
+// controller keeps written and read characters apart, so neither half can
+// disturb the other's state.
+unsafe impl Send for BlockingUartTx<'_, '_> {}
+
+impl<'i, 't> BlockingUartTx<'i, 't> {
+    /// Waits for pending data to be sent and releases the TX pad.
+    ///
+    /// The returned pad has input and output disabled. The UART itself stays
+    /// configured, as the receiver half may still be in use.
+    pub fn free(mut self) -> FlexPad<'t> {
+        let _ = blocking_flush(&self.inner, &self.tx_mode);
+        self.release()
+    }
+
+    /// Most bytes a single write found no room for in the transmitter.
+    ///
+    /// See [`BlockingUart::tx_watermark`](super::BlockingUart::tx_watermark).
+    #[inline]
+    pub fn tx_watermark(&self) -> usize {
+        self.tx_mode.watermark
+    }
+
+    /// Resets [`tx_watermark`](Self::tx_watermark) to zero.
+    #[inline]
+    pub fn reset_tx_watermark(&mut self) {
+        self.tx_mode.watermark = 0;
+    }
+
+    /// Disables the TX pad and returns it.
+    pub(crate) fn release(mut self) -> FlexPad<'t> {
+        self.tx.set_disabled();
+        self.tx
+    }
+}
+
 impl<'i, 't> embedded_io::ErrorType for BlockingUartTx<'i, 't> {
     type Error = UartError;
 }
 
 impl<'i, 't> embedded_io::Write for BlockingUartTx<'i, 't> {
     fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
-        Ok(blocking_write(&mut self.inner, buf))
+        self.tx_mode.write(&mut self.inner, buf)
     }
 
     fn flush(&mut self) -> Result<(), Self::Error> {
-        blocking_flush(&mut self.inner);
-        Ok(())
+        blocking_flush(&self.inner, &self.tx_mode)
     }
-    fn write_all(&mut self, mut buf: &[u8]) -> Result<(), Self::Error> {
-        while !buf.is_empty() {
-            match self.write(buf) {
-                Ok(n) => buf = &buf[n..],
-                Err(e) => return Err(e),
-            }
-        }
-        Ok(())
+
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error> {
+        self.tx_mode.write_all(&mut self.inner, buf)
     }
 }
 
@@ -52,7 +86,7 @@ impl<'i, 't> embedded_hal_nb::serial::Write for BlockingUartTx<'i, 't> {
     }
 
     fn flush(&mut self) -> embedded_hal_nb::nb::Result<(), Self::Error> {
-        match self.inner.read_lsr().transmitter_empty() {
+        match self.tx_mode.poll_flush(&self.inner) {
             true => Ok(()),
             false => Err(embedded_hal_nb::nb::Error::WouldBlock),
         }
@@ -61,6 +95,6 @@ impl<'i, 't> embedded_hal_nb::serial::Write for BlockingUartTx<'i, 't> {
 
 impl<'i, 't> embedded_io::WriteReady for BlockingUartTx<'i, 't> {
     fn write_ready(&mut self) -> Result<bool, Self::Error> {
-        Ok(write_ready(&mut self.inner))
+        Ok(write_ready(&self.inner))
     }
 }
@@ -0,0 +1,143 @@
+//! Register access of the transmit and receive paths.
+//!
+//! The helpers shared by [`BlockingUart`](super::BlockingUart) and its split
+//! halves are generic over [`TxRegisters`] and [`RxRegisters`]. The full
+//! register block implements both; a split hands each half a view of only
+//! the registers its path touches, so the halves never share a handle to
+//! the whole block.
+
+use crate::uart::{Lsr, MmioRegisterBlock, RbrThrDll, RegisterBlock};
+use core::mem::offset_of;
+
+/// Registers the transmit path uses.
+pub(crate) trait TxRegisters {
+    /// Reads the UART status register.
+    fn usr(&self) -> u32;
+    /// Queues `ch` in the transmit holding register.
+    fn write_thr(&mut self, ch: u8);
+}
+
+/// Registers the receive path uses.
+pub(crate) trait RxRegisters {
+    /// Reads the line status register, clearing its error bits.
+    fn lsr(&self) -> Lsr;
+    /// Pops a received character from the receive buffer register.
+    fn rbr(&self) -> u8;
+}
+
+/// THR value queueing `ch`.
+///
+/// THR shares its address with RBR, so it is written without reading the
+/// register first; a read-modify-write would pop a received character.
+#[inline]
+fn thr(ch: u8) -> RbrThrDll {
+    RbrThrDll::new_with_raw_value(0).with_transmitter_holding(ch)
+}
+
+impl TxRegisters for MmioRegisterBlock<'_> {
+    #[inline]
+    fn usr(&self) -> u32 {
+        read_reg!(self, usr, read_usr)
+    }
+
+    #[inline]
+    fn write_thr(&mut self, ch: u8) {
+        unsafe { write_reg!(self, rbr_thr_dll, write_rbr_thr_dll, thr(ch)) };
+    }
+}
+
+impl RxRegisters for MmioRegisterBlock<'_> {
+    #[inline]
+    fn lsr(&self) -> Lsr {
+        read_reg!(self, lsr, read_lsr)
+    }
+
+    #[inline]
+    fn rbr(&self) -> u8 {
+        read_reg!(self, rbr_thr_dll, read_rbr_thr_dll).receiver_buffer()
+    }
+}
+
+/// THR and USR of one UART, owned by its transmitter half.
+pub(crate) struct TxView {
+    thr: *mut RbrThrDll,
+    usr: *const u32,
+}
+
+impl TxView {
+    /// Takes the transmit registers of `uart`.
+    ///
+    /// # Safety
+    ///
+    /// Nothing else may write THR while the view exists.
+    #[inline]
+    pub(crate) unsafe fn new(uart: &mut MmioRegisterBlock) -> Self {
+        Self {
+            thr: uart.pointer_to_rbr_thr_dll(),
+            usr: uart.pointer_to_usr(),
+        }
+    }
+}
+
+impl TxRegisters for TxView {
+    #[inline]
+    fn usr(&self) -> u32 {
+        // SAFETY: USR is a read-only status register without side effects.
+        let value = unsafe { self.usr.read_volatile() };
+        crate::trace::read("uart", offset_of!(RegisterBlock, usr), value.into());
+        value
+    }
+
+    #[inline]
+    fn write_thr(&mut self, ch: u8) {
+        let value = thr(ch);
+        let offset = offset_of!(RegisterBlock, rbr_thr_dll);
+        crate::trace::write("uart", offset, value.raw_value().into());
+        // SAFETY: the view is the only writer of THR, see `TxView::new`.
+        unsafe { self.thr.write_volatile(value) };
+    }
+}
+
+/// RBR and LSR of one UART, owned by its receiver half.
+pub(crate) struct RxView {
+    rbr: *const RbrThrDll,
+    lsr: *const Lsr,
+}
+
+impl RxView {
+    /// Takes the receive registers of `uart`.
+    ///
+    /// # Safety
+    ///
+    /// Nothing else may read RBR or LSR while the view exists.
+    #[inline]
+    pub(crate) unsafe fn new(uart: &mut MmioRegisterBlock) -> Self {
+        Self {
+            rbr: uart.pointer_to_rbr_thr_dll(),
+            lsr: uart.pointer_to_lsr(),
+        }
+    }
+}
+
+impl RxRegisters for RxView {
+    #[inline]
+    fn lsr(&self) -> Lsr {
+        // SAFETY: the view is the only reader of LSR, see `RxView::new`.
+        let value = unsafe { self.lsr.read_volatile() };
+        crate::trace::read(
+            "uart",
+            offset_of!(RegisterBlock, lsr),
+            value.raw_value().into(),
+        );
+        value
+    }
+
+    #[inline]
+    fn rbr(&self) -> u8 {
+        // SAFETY: the view is the only reader of RBR, see `RxView::new`.
+        let value = unsafe { self.rbr.read_volatile() };
+        let offset = offset_of!(RegisterBlock, rbr_thr_dll);
+        crate::trace::read("uart", offset, value.raw_value().into());
+        value.receiver_buffer()
+    }
+}
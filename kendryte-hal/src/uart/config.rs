@@ -1,4 +1,8 @@
-use crate::uart::{MmioRegisterBlock, ParityType, RegisterBlock, StopBits, WordLength};
+use crate::soc::UartFeatures;
+use crate::uart::{
+    DmaTransferMode, IerDlh, Lcr, Mcr, MmioRegisterBlock, ParityType, ReceiverInterruptThreshold,
+    RegisterBlock, StopBits, TransmitterEmptyThreshold, WordLength,
+};
 use embedded_time::rate::Baud;
 
 /// Represents different parity checking modes for UART communication.
@@ -31,6 +35,24 @@ pub struct Config {
     /// Length of data words.
     pub word_length: WordLength,
     pub fifo: bool,
+    /// Transmit FIFO level at or below which the THRE interrupt fires in
+    /// programmable THRE mode.
+    pub tx_threshold: TransmitterEmptyThreshold,
+    /// Receive FIFO level at or above which the receive data interrupt fires.
+    ///
+    /// Higher levels mean fewer interrupts but more latency; characters
+    /// below the level are reported by the character timeout instead.
+    pub rx_threshold: ReceiverInterruptThreshold,
+    /// Makes `embedded_io::Write::write` wait for room in the transmitter
+    /// until the whole buffer is queued, instead of returning after the
+    /// bytes that fit.
+    ///
+    /// Either way a write waits for room for its first byte and fails with
+    /// [`UartError::Timeout`](super::UartError::Timeout) rather than
+    /// returning `Ok(0)` if the transmitter stalls, and `write_all`, which
+    /// `write!` goes through, queues the whole buffer. Callers that ignore
+    /// the count returned by `write` lose output only without this option.
+    pub blocking_tx: bool,
 }
 
 impl Config {
@@ -41,6 +63,9 @@ impl Config {
     /// - No parity.
     /// - 1 stop bit.
     /// - 8 bits word length.
+    /// - FIFO disabled, with the transmit threshold at empty and the
+    ///   receive threshold at one character.
+    /// - Blocking transmit.
     pub fn new() -> Self {
         Self {
             baud: Baud::new(115200),
@@ -48,6 +73,9 @@ impl Config {
             stop_bits: StopBits::_1,
             word_length: WordLength::_8,
             fifo: false,
+            tx_threshold: TransmitterEmptyThreshold::Empty,
+            rx_threshold: ReceiverInterruptThreshold::OneChar,
+            blocking_tx: true,
         }
     }
 
@@ -79,17 +107,138 @@ impl Config {
         self.fifo = fifo;
         self
     }
+
+    /// Sets the transmit FIFO threshold.
+    pub fn set_tx_threshold(mut self, threshold: TransmitterEmptyThreshold) -> Self {
+        self.tx_threshold = threshold;
+        self
+    }
+
+    /// Sets the receive FIFO threshold.
+    pub fn set_rx_threshold(mut self, threshold: ReceiverInterruptThreshold) -> Self {
+        self.rx_threshold = threshold;
+        self
+    }
+
+    /// Sets whether writes wait until the whole buffer is queued.
+    pub fn set_blocking_tx(mut self, blocking: bool) -> Self {
+        self.blocking_tx = blocking;
+        self
+    }
+}
+
+/// DMA handshake configuration.
+///
+/// Selects how the `dma_tx_req`/`dma_rx_req` handshake lines behave and at
+/// which FIFO levels they assert. The thresholds only apply while the FIFO
+/// is enabled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DmaConfig {
+    /// Handshake mode.
+    ///
+    /// Mode 0 requests one character at a time. Mode 1 requests a burst
+    /// whenever the FIFO crosses its threshold and needs the FIFO enabled.
+    pub mode: DmaTransferMode,
+    /// Transmit FIFO level at or below which a transmit burst is requested.
+    pub tx_threshold: TransmitterEmptyThreshold,
+    /// Receive FIFO level at or above which a receive burst is requested.
+    pub rx_threshold: ReceiverInterruptThreshold,
+    /// Enables programmable THRE interrupt mode.
+    ///
+    /// While enabled the THRE interrupt follows `tx_threshold` and the
+    /// THRE bit in LSR reports a full transmit FIFO instead of an empty one,
+    /// so byte-wise writes through the driver must not be mixed with it.
+    pub programmable_thre: bool,
+}
+
+impl DmaConfig {
+    /// Creates a new DmaConfig with default settings.
+    ///
+    /// Default settings are:
+    /// - Mode 1 (burst) handshake.
+    /// - Transmit request when the FIFO is half full.
+    /// - Receive request when the FIFO is half full.
+    /// - Programmable THRE mode disabled.
+    pub fn new() -> Self {
+        Self {
+            mode: DmaTransferMode::Mode1,
+            tx_threshold: TransmitterEmptyThreshold::HalfFull,
+            rx_threshold: ReceiverInterruptThreshold::HalfFull,
+            programmable_thre: false,
+        }
+    }
+
+    /// Sets the handshake mode.
+    pub fn set_mode(mut self, mode: DmaTransferMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets the transmit request threshold.
+    pub fn set_tx_threshold(mut self, threshold: TransmitterEmptyThreshold) -> Self {
+        self.tx_threshold = threshold;
+        self
+    }
+
+    /// Sets the receive request threshold.
+    pub fn set_rx_threshold(mut self, threshold: ReceiverInterruptThreshold) -> Self {
+        self.rx_threshold = threshold;
+        self
+    }
+
+    /// Sets programmable THRE interrupt mode.
+    pub fn set_programmable_thre(mut self, enable: bool) -> Self {
+        self.programmable_thre = enable;
+        self
+    }
+
+    /// Largest transmit burst, in characters, that fits the FIFO when a
+    /// request asserts.
+    ///
+    /// Program the DMA channel with a burst no larger than this, otherwise
+    /// characters are written into a full FIFO and lost.
+    pub fn tx_burst(&self, fifo_depth: usize) -> usize {
+        let level = match self.tx_threshold {
+            TransmitterEmptyThreshold::Empty => 0,
+            TransmitterEmptyThreshold::TwoCharsLeft => 2,
+            TransmitterEmptyThreshold::QuarterFull => fifo_depth / 4,
+            TransmitterEmptyThreshold::HalfFull => fifo_depth / 2,
+        };
+        fifo_depth.saturating_sub(level).max(1)
+    }
+
+    /// Largest receive burst, in characters, available when a request asserts.
+    ///
+    /// Program the DMA channel with a burst no larger than this, otherwise
+    /// it reads from an empty FIFO.
+    pub fn rx_burst(&self, fifo_depth: usize) -> usize {
+        let level = match self.rx_threshold {
+            ReceiverInterruptThreshold::OneChar => 1,
+            ReceiverInterruptThreshold::QuarterFull => fifo_depth / 4,
+            ReceiverInterruptThreshold::HalfFull => fifo_depth / 2,
+            ReceiverInterruptThreshold::AlmostFull => fifo_depth.saturating_sub(2),
+        };
+        level.max(1)
+    }
+}
+
+impl Default for DmaConfig {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// Gets the current divisor value from UART registers.
 pub(crate) fn divisor(uart: &mut MmioRegisterBlock) -> u16 {
     unsafe {
-        uart.modify_lcr(|r| r.with_divisor_latch_access_enable(true));
+        modify_reg!(uart, lcr, modify_lcr, |r| r
+            .with_divisor_latch_access_enable(true));
     }
-    let dll = uart.read_rbr_thr_dll().divisor_latch_lsb();
-    let dlh = uart.read_ier_dlh().divisor_latch_hsb();
+    let dll = read_reg!(uart, rbr_thr_dll, read_rbr_thr_dll).divisor_latch_lsb();
+    let dlh = read_reg!(uart, ier_dlh, read_ier_dlh).divisor_latch_hsb();
     unsafe {
-        uart.modify_lcr(|r| r.with_divisor_latch_access_enable(false));
+        modify_reg!(uart, lcr, modify_lcr, |r| r
+            .with_divisor_latch_access_enable(false));
     }
     u16::from_le_bytes([dll, dlh])
 }
@@ -97,19 +246,23 @@ pub(crate) fn divisor(uart: &mut MmioRegisterBlock) -> u16 {
 /// Sets the divisor value in UART registers.
 pub(crate) fn set_divisor(uart: &mut MmioRegisterBlock, divisor: u16) {
     unsafe {
-        uart.modify_lcr(|r| r.with_divisor_latch_access_enable(true));
+        modify_reg!(uart, lcr, modify_lcr, |r| r
+            .with_divisor_latch_access_enable(true));
     }
     let [divisor_lsb, divisor_hsb] = divisor.to_le_bytes();
     unsafe {
-        uart.modify_rbr_thr_dll(|r| r.with_divisor_latch_lsb(divisor_lsb));
-        uart.modify_ier_dlh(|r| r.with_divisor_latch_hsb(divisor_hsb));
-        uart.modify_lcr(|r| r.with_divisor_latch_access_enable(false));
+        modify_reg!(uart, rbr_thr_dll, modify_rbr_thr_dll, |r| r
+            .with_divisor_latch_lsb(divisor_lsb));
+        modify_reg!(uart, ier_dlh, modify_ier_dlh, |r| r
+            .with_divisor_latch_hsb(divisor_hsb));
+        modify_reg!(uart, lcr, modify_lcr, |r| r
+            .with_divisor_latch_access_enable(false));
     }
 }
 
 /// Gets the current parity mode from UART registers.
 pub(crate) fn parity_mode(uart: &mut MmioRegisterBlock) -> ParityMode {
-    let lcr = uart.read_lcr();
+    let lcr = read_reg!(uart, lcr, read_lcr);
     let flags = (
         lcr.parity_enable(),
         lcr.parity_type(),
@@ -126,7 +279,7 @@ pub(crate) fn parity_mode(uart: &mut MmioRegisterBlock) -> ParityMode {
 
 /// Sets the parity mode in UART registers.
 pub(crate) fn set_parity_mode(uart: &mut MmioRegisterBlock, parity: ParityMode) {
-    let lcr = uart.read_lcr();
+    let lcr = read_reg!(uart, lcr, read_lcr);
     let lcr = match parity {
         ParityMode::None => lcr.with_parity_enable(false),
         ParityMode::Odd => lcr
@@ -147,41 +300,314 @@ pub(crate) fn set_parity_mode(uart: &mut MmioRegisterBlock, parity: ParityMode)
             .with_parity_type(ParityType::Even),
     };
     unsafe {
-        uart.write_lcr(lcr);
+        write_reg!(uart, lcr, write_lcr, lcr);
     }
 }
 
 /// Gets the current stop bits setting from UART registers.
 pub(crate) fn stop_bits(uart: &mut MmioRegisterBlock) -> StopBits {
-    uart.read_lcr().stop_bits()
+    read_reg!(uart, lcr, read_lcr).stop_bits()
 }
 
 /// Sets the stop bits in UART registers.
 pub(crate) fn set_stop_bits(uart: &mut MmioRegisterBlock, stop_bits: StopBits) {
     unsafe {
-        uart.modify_lcr(|r| r.with_stop_bits(stop_bits));
+        modify_reg!(uart, lcr, modify_lcr, |r| r.with_stop_bits(stop_bits));
     }
 }
 
 /// Gets the current word length from UART registers.
 pub(crate) fn word_length(uart: &mut MmioRegisterBlock) -> WordLength {
-    uart.read_lcr().word_length()
+    read_reg!(uart, lcr, read_lcr).word_length()
 }
 
 /// Sets the word length in UART registers.
 pub(crate) fn set_word_length(uart: &mut MmioRegisterBlock, word_length: WordLength) {
     unsafe {
-        uart.modify_lcr(|r| r.with_word_length(word_length));
+        modify_reg!(uart, lcr, modify_lcr, |r| r.with_word_length(word_length));
     }
 }
 
 pub(crate) fn enable_fifo(uart: &mut MmioRegisterBlock) {
     unsafe {
-        uart.modify_iir_fcr(|r| r.with_fifo_enable(true));
+        modify_reg!(uart, iir_fcr, modify_iir_fcr, |r| r.with_fifo_enable(true));
     }
 }
 pub(crate) fn disable_fifo(uart: &mut MmioRegisterBlock) {
     unsafe {
-        uart.modify_iir_fcr(|r| r.with_fifo_enable(false));
+        modify_reg!(uart, iir_fcr, modify_iir_fcr, |r| r.with_fifo_enable(false));
+    }
+}
+
+/// Sets the FIFO interrupt thresholds.
+///
+/// Goes through the shadow registers so that the other write-only FCR
+/// fields are left as they are.
+pub(crate) fn set_fifo_thresholds(
+    uart: &mut MmioRegisterBlock,
+    tx: TransmitterEmptyThreshold,
+    rx: ReceiverInterruptThreshold,
+) {
+    unsafe {
+        write_reg!(uart, stet, write_stet, tx as u32);
+        write_reg!(uart, srt, write_srt, rx as u32);
+    }
+}
+
+/// SRR bit resetting the receive FIFO.
+const SRR_RFR: u32 = 1 << 1;
+/// SRR bit resetting the transmit FIFO.
+const SRR_XFR: u32 = 1 << 2;
+
+/// Discards the contents of the selected FIFOs.
+///
+/// The reset bits clear themselves.
+pub(crate) fn flush_fifos(uart: &mut MmioRegisterBlock, tx: bool, rx: bool) {
+    let mut srr = 0;
+    if tx {
+        srr |= SRR_XFR;
+    }
+    if rx {
+        srr |= SRR_RFR;
+    }
+    unsafe { write_reg!(uart, srr, write_srr, srr) };
+}
+
+/// Number of characters in the transmit FIFO.
+pub(crate) fn tx_fifo_level(uart: &MmioRegisterBlock) -> usize {
+    read_reg!(uart, tfl, read_tfl) as usize
+}
+
+/// Number of characters in the receive FIFO.
+pub(crate) fn rx_fifo_level(uart: &MmioRegisterBlock) -> usize {
+    read_reg!(uart, rfl, read_rfl) as usize
+}
+
+/// CPR field holding the FIFO depth in units of 16 characters.
+const CPR_FIFO_MODE_SHIFT: u32 = 16;
+const CPR_FIFO_MODE_MASK: u32 = 0xFF;
+
+/// Gets the FIFO depth in characters, or 0 if the FIFO was not synthesized.
+pub(crate) fn fifo_depth(uart: &MmioRegisterBlock) -> usize {
+    let fifo_mode = (read_reg!(uart, cpr, read_cpr) >> CPR_FIFO_MODE_SHIFT) & CPR_FIFO_MODE_MASK;
+    fifo_mode as usize * 16
+}
+
+/// Applies the DMA handshake configuration.
+///
+/// Goes through the shadow registers so that the other write-only FCR
+/// fields are left as they are.
+pub(crate) fn set_dma(uart: &mut MmioRegisterBlock, config: DmaConfig) {
+    unsafe {
+        write_reg!(uart, sdmam, write_sdmam, config.mode as u32);
+        write_reg!(uart, stet, write_stet, config.tx_threshold as u32);
+        write_reg!(uart, srt, write_srt, config.rx_threshold as u32);
+        modify_reg!(uart, ier_dlh, modify_ier_dlh, |r| {
+            r.with_programmable_threshold_interrupt_enable(config.programmable_thre)
+        });
+    }
+}
+
+/// Register state of a UART kept across a clock gate or power-down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Context {
+    divisor: u16,
+    lcr: Lcr,
+    ier: IerDlh,
+    mcr: Mcr,
+    fifo_enable: u32,
+    tx_threshold: u32,
+    rx_threshold: u32,
+    dma_mode: u32,
+    lcr_ext: Option<u32>,
+}
+
+/// Reads back the configuration of `uart`.
+///
+/// The write-only FCR fields are read through their shadow registers, and
+/// LCR_EXT only if `features` say it exists.
+pub(crate) fn save(uart: &mut MmioRegisterBlock, features: UartFeatures) -> Context {
+    Context {
+        divisor: divisor(uart),
+        lcr: read_reg!(uart, lcr, read_lcr),
+        ier: read_reg!(uart, ier_dlh, read_ier_dlh),
+        mcr: read_reg!(uart, mcr, read_mcr),
+        fifo_enable: read_reg!(uart, sfe, read_sfe),
+        tx_threshold: read_reg!(uart, stet, read_stet),
+        rx_threshold: read_reg!(uart, srt, read_srt),
+        dma_mode: read_reg!(uart, sdmam, read_sdmam),
+        lcr_ext: features
+            .nine_bit
+            .then(|| read_reg!(uart, lcr_ext, read_lcr_ext)),
+    }
+}
+
+/// Programs the configuration saved by [`save`].
+///
+/// Interrupts are enabled last, once the line settings they depend on are
+/// back in place.
+pub(crate) fn restore(uart: &mut MmioRegisterBlock, context: &Context) {
+    set_divisor(uart, context.divisor);
+    unsafe {
+        write_reg!(
+            uart,
+            lcr,
+            write_lcr,
+            context.lcr.with_divisor_latch_access_enable(false)
+        );
+        if let Some(lcr_ext) = context.lcr_ext {
+            write_reg!(uart, lcr_ext, write_lcr_ext, lcr_ext);
+        }
+        write_reg!(uart, sfe, write_sfe, context.fifo_enable);
+        write_reg!(uart, stet, write_stet, context.tx_threshold);
+        write_reg!(uart, srt, write_srt, context.rx_threshold);
+        write_reg!(uart, sdmam, write_sdmam, context.dma_mode);
+        write_reg!(uart, mcr, write_mcr, context.mcr);
+        write_reg!(uart, ier_dlh, write_ier_dlh, context.ier);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+
+    fn uart() -> (*mut RegisterBlock, MmioRegisterBlock<'static>) {
+        let block = mock::block::<RegisterBlock>();
+        (block, unsafe { RegisterBlock::new_mmio(block) })
+    }
+
+    #[test]
+    fn divisor_latch_sequence() {
+        let (_, mut uart) = uart();
+        let ((), log) = mock::capture(|| set_divisor(&mut uart, 0x0123));
+        let writes = mock::writes(&log);
+        let offsets: [usize; 4] = core::array::from_fn(|i| writes[i].0);
+        assert_eq!(offsets, [0x0C, 0x00, 0x04, 0x0C]);
+        assert_ne!(writes[0].1 & 0x80, 0, "DLAB set before the divisor");
+        assert_eq!(writes[1].1 & 0xFF, 0x23);
+        assert_eq!(writes[2].1 & 0xFF, 0x01);
+        assert_eq!(writes[3].1 & 0x80, 0, "DLAB cleared after the divisor");
+        assert_eq!(divisor(&mut uart), 0x0123);
+    }
+
+    #[test]
+    fn line_control_round_trip() {
+        let (_, mut uart) = uart();
+        for parity in [
+            ParityMode::None,
+            ParityMode::Odd,
+            ParityMode::Even,
+            ParityMode::High,
+            ParityMode::Low,
+        ] {
+            set_parity_mode(&mut uart, parity);
+            assert_eq!(parity_mode(&mut uart), parity);
+        }
+        set_stop_bits(&mut uart, StopBits::_2);
+        set_word_length(&mut uart, WordLength::_7);
+        assert_eq!(stop_bits(&mut uart), StopBits::_2);
+        assert_eq!(word_length(&mut uart), WordLength::_7);
+        assert!(!uart.read_lcr().divisor_latch_access_enable());
+    }
+
+    #[test]
+    fn dma_uses_shadow_registers() {
+        let (_, mut uart) = uart();
+        let config = DmaConfig::new()
+            .set_tx_threshold(TransmitterEmptyThreshold::QuarterFull)
+            .set_rx_threshold(ReceiverInterruptThreshold::AlmostFull)
+            .set_programmable_thre(true);
+        let ((), log) = mock::capture(|| set_dma(&mut uart, config));
+        let writes = mock::writes(&log);
+        assert_eq!(writes[0], (0x94, DmaTransferMode::Mode1 as u64));
+        assert_eq!(
+            writes[1],
+            (0xA0, TransmitterEmptyThreshold::QuarterFull as u64)
+        );
+        assert_eq!(
+            writes[2],
+            (0x9C, ReceiverInterruptThreshold::AlmostFull as u64)
+        );
+        assert!(
+            uart.read_ier_dlh()
+                .programmable_threshold_interrupt_enable()
+        );
+        // FCR is write-only; the shadow registers must be used instead.
+        assert!(log.iter().all(|a| a.offset != 0x08));
+    }
+
+    #[test]
+    fn fifo_thresholds_and_flush() {
+        let (block, mut uart) = uart();
+        let ((), log) = mock::capture(|| {
+            set_fifo_thresholds(
+                &mut uart,
+                TransmitterEmptyThreshold::TwoCharsLeft,
+                ReceiverInterruptThreshold::HalfFull,
+            );
+            flush_fifos(&mut uart, true, true);
+        });
+        let writes = mock::writes(&log);
+        assert_eq!(
+            writes[0],
+            (0xA0, TransmitterEmptyThreshold::TwoCharsLeft as u64)
+        );
+        assert_eq!(
+            writes[1],
+            (0x9C, ReceiverInterruptThreshold::HalfFull as u64)
+        );
+        assert_eq!(writes[2], (0x88, (SRR_RFR | SRR_XFR) as u64));
+        assert!(log.iter().all(|a| a.offset != 0x08));
+        unsafe {
+            (*block).tfl = 3;
+            (*block).rfl = 17;
+        }
+        assert_eq!(tx_fifo_level(&uart), 3);
+        assert_eq!(rx_fifo_level(&uart), 17);
+    }
+
+    #[test]
+    fn fifo_depth_from_cpr() {
+        let (block, uart) = uart();
+        assert_eq!(fifo_depth(&uart), 0);
+        unsafe { (*block).cpr = 0x0004_0000 };
+        assert_eq!(fifo_depth(&uart), 64);
+    }
+
+    #[test]
+    fn context_restore_order() {
+        let (_, mut saved) = uart();
+        set_divisor(&mut saved, 0x0036);
+        set_word_length(&mut saved, WordLength::_7);
+        set_fifo_thresholds(
+            &mut saved,
+            TransmitterEmptyThreshold::QuarterFull,
+            ReceiverInterruptThreshold::HalfFull,
+        );
+        unsafe { saved.write_sfe(1) };
+        let context = save(&mut saved, UartFeatures { nine_bit: false });
+
+        let (_, mut fresh) = uart();
+        let ((), log) = mock::capture(|| restore(&mut fresh, &context));
+        let writes = mock::writes(&log);
+        assert_eq!(writes[0].0, 0x0C);
+        assert_ne!(writes[0].1 & 0x80, 0, "divisor restored first");
+        assert_eq!(writes[4].0, 0x0C);
+        assert_eq!(writes[4].1 & 0x80, 0, "DLAB cleared by the saved LCR");
+        assert_eq!(writes.last().map(|w| w.0), Some(0x04), "IER restored last");
+        // No LCR_EXT without 9-bit support, and FCR only through shadows.
+        assert!(log.iter().all(|a| a.offset != 0xCC && a.offset != 0x08));
+        assert_eq!(divisor(&mut fresh), 0x0036);
+        assert_eq!(word_length(&mut fresh), WordLength::_7);
+        assert_eq!(fresh.read_sfe(), 1);
+        assert_eq!(
+            fresh.read_stet(),
+            TransmitterEmptyThreshold::QuarterFull as u32
+        );
+        assert_eq!(
+            fresh.read_srt(),
+            ReceiverInterruptThreshold::HalfFull as u32
+        );
     }
 }
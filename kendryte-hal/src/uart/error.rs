@@ -1,5 +1,6 @@
 /// Indicate different error conditions that may occur during UART communication.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum UartError {
     /// Framing error occurred.
     Framing,
@@ -11,16 +12,24 @@ pub enum UartError {
     NotFoundTx,
     /// Receive (RX) resource not found.
     NotFoundRx,
+    /// The requested feature is not available on this UART instance.
+    NotSupported,
+    /// The transmitter did not drain, or expected data did not arrive, in time.
+    Timeout,
+    /// The buffer filled up before the delimiter was received.
+    BufferFull,
+    /// The identification registers read back this value instead of a UART's.
+    IdMismatch(u32),
 }
 
 impl embedded_io::Error for UartError {
     fn kind(&self) -> embedded_io::ErrorKind {
-        embedded_io::ErrorKind::Other
+        crate::Error::from(*self).kind().into()
     }
 }
 
 impl embedded_hal_nb::serial::Error for UartError {
     fn kind(&self) -> embedded_hal_nb::serial::ErrorKind {
-        embedded_hal_nb::serial::ErrorKind::Other
+        crate::Error::from(*self).kind().into()
     }
 }
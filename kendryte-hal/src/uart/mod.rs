@@ -1,10 +1,18 @@
+crate::trace::traced_registers!($ "uart", crate::uart::RegisterBlock);
+
+mod asynch;
 mod blocking;
 mod config;
 mod error;
 pub mod pad;
 mod register;
 
-pub use blocking::BlockingUart;
-pub use config::{Config, ParityMode};
+pub use asynch::{AsyncUart, UartState};
+pub use blocking::{
+    AutoBaudConfig, BlockingUart, BlockingUartRx, BlockingUartTx, LoopbackReport, STANDARD_BAUDS,
+};
+pub use config::{Config, DmaConfig, ParityMode};
 pub use error::UartError;
 pub use register::*;
+
+crate::trace::impl_raw!(RbrThrDll, IerDlh, IirFcr, Lcr, Mcr, Lsr, Msr, Scr);
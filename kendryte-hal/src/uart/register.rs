@@ -161,8 +161,8 @@ pub struct IerDlh {
     #[bit(7, rw)]
     pub programmable_threshold_interrupt_enable: bool,
 
-    /// Divisor latch MSB.
-    #[bits(8..=15, rw)]
+    /// Divisor Latch MSB (DLH, DLAB=1, read/write).
+    #[bits(0..=7, rw)]
     pub divisor_latch_hsb: u8,
 }
 
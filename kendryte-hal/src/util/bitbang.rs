@@ -0,0 +1,188 @@
+//! Calibrated busy waits for bit-banged protocols.
+//!
+//! Protocols driven from a GPIO pin, such as 1-Wire or WS2812, need their
+//! edges placed to within a fraction of a microsecond. Two kinds of waits
+//! are provided:
+//!
+//! - Deadlines, [`wait_until`] on the machine timer and [`wait_cycles`] on
+//!   `mcycle`, measured from a start time taken once per bit. Time spent
+//!   between the waits, such as pin accesses, does not add up, so these are
+//!   preferred for protocols with a fixed bit slot.
+//! - Relative delays, [`delay_cycles`] and [`delay_ns`], which take the cost
+//!   of the call itself off the wait once [`calibrate`] has measured it.
+//!
+//! [`generate_pulse`] drives a single pulse of a given width on any
+//! [`OutputPin`]:
+//!
+//! ```ignore
+//! use kendryte_hal::gpio::PinState;
+//! use kendryte_hal::util::bitbang;
+//!
+//! bitbang::calibrate();
+//! // 10 µs trigger pulse for an ultrasonic range finder.
+//! bitbang::generate_pulse(&mut trigger, PinState::High, 10_000)?;
+//! ```
+//!
+//! Cycle counts follow the CPU frequency recorded in [`Clocks`], so they
+//! stay right after [`Clocks::set_cpu_frequency`]. Interrupt handlers
+//! running during a wait lengthen it; mask them around timing critical
+//! sequences, as [`generate_pulse`] does.
+
+use crate::clocks::Clocks;
+use crate::delay::{cycles, to_cycles};
+use crate::soc::TIMER_FREQUENCY;
+use crate::time::{interrupt_free, now};
+use core::sync::atomic::{AtomicU32, Ordering};
+use embedded_hal::digital::{OutputPin, PinState};
+
+/// Cycles a call to [`delay_cycles`] costs besides its wait.
+static OVERHEAD: AtomicU32 = AtomicU32::new(0);
+
+/// Number of runs [`calibrate`] takes the fastest of.
+const CALIBRATION_RUNS: u32 = 16;
+
+/// Machine timer ticks in `us` microseconds, rounded down.
+#[inline(always)]
+pub const fn micros_to_ticks(us: u32) -> u64 {
+    us as u64 * TIMER_FREQUENCY as u64 / 1_000_000
+}
+
+/// Spin until the machine timer reaches `deadline`.
+#[inline(always)]
+pub fn wait_until(deadline: u64) {
+    while now() < deadline {
+        core::hint::spin_loop();
+    }
+}
+
+/// CPU cycles in `ns` nanoseconds at the current CPU frequency, rounded up.
+#[inline]
+pub fn ns_to_cycles(ns: u32) -> u64 {
+    to_cycles(Clocks.cpu().0, ns, 1_000_000_000)
+}
+
+/// Spin until `count` CPU cycles have passed since `start`, a value
+/// returned by [`cycles`].
+#[inline(always)]
+pub fn wait_cycles(start: u64, count: u64) {
+    while cycles().wrapping_sub(start) < count {
+        core::hint::spin_loop();
+    }
+}
+
+/// Spin for `count` CPU cycles from the call.
+///
+/// The cost of the call, as measured by [`calibrate`], is taken off the
+/// wait; shorter counts return as soon as possible.
+#[inline(never)]
+#[cfg_attr(
+    feature = "ramfunc",
+    unsafe(link_section = ".ramfunc.bitbang_delay_cycles")
+)]
+pub fn delay_cycles(count: u64) {
+    let start = cycles();
+    let overhead = OVERHEAD.load(Ordering::Relaxed) as u64;
+    wait_cycles(start, count.saturating_sub(overhead));
+}
+
+/// Spin for `ns` nanoseconds from the call, see [`delay_cycles`].
+#[inline]
+pub fn delay_ns(ns: u32) {
+    delay_cycles(ns_to_cycles(ns));
+}
+
+/// Measure the cost of a [`delay_cycles`] call and take it off later delays.
+///
+/// Returns the cost in CPU cycles. The fastest of several runs is kept, so
+/// an interrupt during calibration does not inflate it. Calibrate again
+/// after enabling caches, since they change the cost.
+pub fn calibrate() -> u32 {
+    OVERHEAD.store(0, Ordering::Relaxed);
+    let overhead = (0..CALIBRATION_RUNS)
+        .map(|_| {
+            let start = cycles();
+            delay_cycles(0);
+            cycles().wrapping_sub(start)
+        })
+        .min()
+        .unwrap_or(0)
+        .min(u32::MAX as u64) as u32;
+    OVERHEAD.store(overhead, Ordering::Relaxed);
+    overhead
+}
+
+/// Cycles taken off every [`delay_cycles`], zero until [`calibrate`] runs.
+#[inline]
+pub fn overhead() -> u32 {
+    OVERHEAD.load(Ordering::Relaxed)
+}
+
+/// Drive `pin` to `level` for `width_ns` nanoseconds, then to the opposite
+/// level.
+///
+/// Both edges come the same time after their pin write is started, so the
+/// width is timed from the start of the first write and holds for any pin
+/// driver. Pulses shorter than one pin write come out at that length.
+/// Machine interrupts are masked during the pulse.
+pub fn generate_pulse<P: OutputPin>(
+    pin: &mut P,
+    level: PinState,
+    width_ns: u32,
+) -> Result<(), P::Error> {
+    let width = ns_to_cycles(width_ns);
+    interrupt_free(|| {
+        let start = cycles();
+        pin.set_state(level)?;
+        wait_cycles(start, width);
+        pin.set_state(!level)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::Infallible;
+    use embedded_hal::digital::ErrorType;
+
+    struct Recorder {
+        states: [Option<PinState>; 2],
+        writes: usize,
+    }
+
+    impl ErrorType for Recorder {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for Recorder {
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            self.set_state(PinState::Low)
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            self.set_state(PinState::High)
+        }
+
+        fn set_state(&mut self, state: PinState) -> Result<(), Infallible> {
+            self.states[self.writes] = Some(state);
+            self.writes += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(micros_to_ticks(0), 0);
+        assert_eq!(micros_to_ticks(1_000_000), TIMER_FREQUENCY as u64);
+        assert_eq!(ns_to_cycles(0), 0);
+    }
+
+    #[test]
+    fn test_pulse_levels() {
+        let mut pin = Recorder {
+            states: [None; 2],
+            writes: 0,
+        };
+        generate_pulse(&mut pin, PinState::Low, 0).unwrap();
+        assert_eq!(pin.states, [Some(PinState::Low), Some(PinState::High)]);
+    }
+}
@@ -0,0 +1,3 @@
+//! Helpers shared by drivers and available to applications.
+
+pub mod bitbang;
@@ -0,0 +1,147 @@
+//! Watchdog timer.
+//!
+//! The watchdog counts down from a timeout and resets the chip when it
+//! reaches zero, unless software restarts the count with
+//! [`Watchdog::feed`] first. Timeouts are powers of two: period `n` lasts
+//! 2^(16+n) cycles of the watchdog clock, see [`timeout_cycles`]. Once
+//! started the watchdog cannot be stopped again short of a reset:
+//!
+//! ```ignore
+//! let mut wdt = Watchdog::new(p.wdt0);
+//! wdt.start(period_for(WDT_CLOCK_HZ as u64 * 2).unwrap());
+//! loop {
+//!     // ... work ...
+//!     wdt.feed();
+//! }
+//! ```
+
+mod register;
+pub use register::*;
+
+crate::trace::traced_registers!($ "wdt", crate::wdt::RegisterBlock);
+crate::trace::impl_raw!(Control, TimeoutRange);
+
+use crate::instance::Instance;
+use arbitrary_int::u4;
+use core::marker::PhantomData;
+
+/// Value written to WDT_CRR to restart the counter.
+const RESTART_KEY: u32 = 0x76;
+
+/// Watchdog clock cycles before a watchdog started with `period` times out.
+#[inline]
+pub const fn timeout_cycles(period: u4) -> u64 {
+    1 << (16 + period.value() as u32)
+}
+
+/// Shortest period lasting at least `cycles` watchdog clock cycles, or
+/// `None` if even the longest is shorter.
+pub fn period_for(cycles: u64) -> Option<u4> {
+    (0..16)
+        .map(u4::new)
+        .find(|&period| timeout_cycles(period) >= cycles)
+}
+
+/// Watchdog timer driver.
+pub struct Watchdog<'i> {
+    inner: MmioRegisterBlock<'static>,
+    _marker: PhantomData<&'i ()>,
+}
+
+// SAFETY: the driver owns its register block exclusively.
+unsafe impl Send for Watchdog<'_> {}
+
+impl<'i> Watchdog<'i> {
+    /// Creates the watchdog driver, leaving the watchdog as it is.
+    #[inline]
+    pub fn new(instance: impl Instance<'i, R = MmioRegisterBlock<'static>>) -> Self {
+        Watchdog {
+            inner: instance.inner(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Starts the watchdog with a timeout of `period`, or changes the
+    /// timeout of a running one, and restarts the count.
+    ///
+    /// A timeout resets the chip without raising the interrupt first.
+    pub fn start(&mut self, period: u4) {
+        unsafe {
+            write_reg!(
+                self.inner,
+                torr,
+                write_torr,
+                TimeoutRange::DEFAULT
+                    .with_period(period)
+                    .with_initial_period(period)
+            );
+            modify_reg!(self.inner, cr, modify_cr, |r| r
+                .with_response_mode(ResponseMode::Reset)
+                .with_enable(true));
+        }
+        self.feed();
+    }
+
+    /// Restarts the count from the full timeout.
+    #[inline]
+    pub fn feed(&mut self) {
+        unsafe { write_reg!(self.inner, crr, write_crr, RESTART_KEY) };
+    }
+
+    /// Returns true if the watchdog has been started since the last reset.
+    #[inline]
+    pub fn is_running(&mut self) -> bool {
+        read_reg!(self.inner, cr, read_cr).enable()
+    }
+
+    /// Watchdog clock cycles left before the current count times out.
+    #[inline]
+    pub fn remaining(&mut self) -> u32 {
+        read_reg!(self.inner, ccvr, read_ccvr)
+    }
+
+    /// Resets the chip through the watchdog with the shortest timeout.
+    ///
+    /// The chip resets 65536 watchdog clock cycles later, as the count may
+    /// not be restarted any more.
+    pub fn reset(&mut self) -> ! {
+        self.start(u4::new(0));
+        loop {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+
+    #[test]
+    fn timeout_periods() {
+        assert_eq!(timeout_cycles(u4::new(0)), 1 << 16);
+        assert_eq!(timeout_cycles(u4::new(15)), 1 << 31);
+        assert_eq!(period_for(0), Some(u4::new(0)));
+        assert_eq!(period_for(1 << 16), Some(u4::new(0)));
+        assert_eq!(period_for((1 << 16) + 1), Some(u4::new(1)));
+        assert_eq!(period_for(50_000_000), Some(u4::new(10)));
+        assert_eq!(period_for((1 << 31) + 1), None);
+    }
+
+    #[test]
+    fn start_and_feed() {
+        let mut wdt = Watchdog {
+            inner: unsafe { RegisterBlock::new_mmio(mock::block::<RegisterBlock>()) },
+            _marker: PhantomData,
+        };
+        assert!(!wdt.is_running());
+        wdt.start(u4::new(10));
+        assert!(wdt.is_running());
+        let cr = wdt.inner.read_cr();
+        assert_eq!(cr.response_mode(), ResponseMode::Reset);
+        let torr = wdt.inner.read_torr();
+        assert_eq!(torr.period(), u4::new(10));
+        assert_eq!(torr.initial_period(), u4::new(10));
+        assert_eq!(wdt.inner.read_crr(), RESTART_KEY);
+    }
+}
@@ -0,0 +1,92 @@
+use arbitrary_int::{u3, u4};
+use bitbybit::{bitenum, bitfield};
+use derive_mmio::Mmio;
+
+/// Watchdog register block.
+///
+/// Registers of the DesignWare APB watchdog timer.
+#[derive(Mmio)]
+#[repr(C)]
+pub struct RegisterBlock {
+    /// Control Register (WDT_CR).
+    pub cr: Control,
+    /// Timeout Range Register (WDT_TORR).
+    pub torr: TimeoutRange,
+    /// Current Counter Value Register (WDT_CCVR).
+    #[mmio(PureRead)]
+    pub ccvr: u32,
+    /// Counter Restart Register (WDT_CRR).
+    /// Writing the restart key restarts the counter and clears the interrupt.
+    pub crr: u32,
+    /// Interrupt Status Register (WDT_STAT).
+    #[mmio(PureRead)]
+    pub stat: u32,
+    /// Interrupt Clear Register (WDT_EOI).
+    /// Reading it clears the interrupt without restarting the counter.
+    pub eoi: u32,
+    _reserved0: [u8; 0xE0],
+    /// Component Version Register (WDT_COMP_VERSION).
+    #[mmio(PureRead)]
+    pub comp_version: u32,
+    /// Component Type Register (WDT_COMP_TYPE).
+    #[mmio(PureRead)]
+    pub comp_type: u32,
+}
+
+/// What the watchdog does when the counter reaches zero.
+#[bitenum(u1, exhaustive = true)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum ResponseMode {
+    /// Reset the system at once.
+    Reset = 0b0,
+    /// Raise the interrupt, and reset the system if it is still pending
+    /// at the second timeout.
+    InterruptThenReset = 0b1,
+}
+
+/// Control Register (WDT_CR).
+#[bitfield(u32, default = 0x0)]
+#[derive(Debug, PartialEq, Eq)]
+pub struct Control {
+    /// Enable the watchdog (WDT_EN).
+    /// Once set, it can only be cleared by a system reset.
+    #[bit(0, rw)]
+    pub enable: bool,
+    /// Response to a timeout (RMOD).
+    #[bit(1, rw)]
+    pub response_mode: ResponseMode,
+    /// Reset pulse length of 2^(n+1) bus clock cycles (RPL).
+    #[bits(2..=4, rw)]
+    pub reset_pulse_length: u3,
+}
+
+/// Timeout Range Register (WDT_TORR).
+///
+/// A period `n` times out after 2^(16+n) watchdog clock cycles.
+#[bitfield(u32, default = 0x0)]
+#[derive(Debug, PartialEq, Eq)]
+pub struct TimeoutRange {
+    /// Period loaded on every restart (TOP).
+    #[bits(0..=3, rw)]
+    pub period: u4,
+    /// Period of the first count after the watchdog is enabled (TOP_INIT).
+    #[bits(4..=7, rw)]
+    pub initial_period: u4,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem::offset_of;
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, cr), 0x00);
+        assert_eq!(offset_of!(RegisterBlock, torr), 0x04);
+        assert_eq!(offset_of!(RegisterBlock, ccvr), 0x08);
+        assert_eq!(offset_of!(RegisterBlock, crr), 0x0C);
+        assert_eq!(offset_of!(RegisterBlock, stat), 0x10);
+        assert_eq!(offset_of!(RegisterBlock, eoi), 0x14);
+        assert_eq!(offset_of!(RegisterBlock, comp_version), 0xF8);
+        assert_eq!(offset_of!(RegisterBlock, comp_type), 0xFC);
+    }
+}
@@ -0,0 +1,94 @@
+use super::{Framebuffer, RESET_US};
+use crate::delay::cycles;
+use crate::time::{Timeout, interrupt_free};
+use crate::util::bitbang::{ns_to_cycles, wait_cycles};
+use embedded_hal::digital::OutputPin;
+
+/// Length of one bit, in nanoseconds.
+const PERIOD_NS: u32 = 1_250;
+/// High time of a 0 bit, in nanoseconds.
+const ZERO_NS: u32 = 400;
+/// High time of a 1 bit, in nanoseconds.
+const ONE_NS: u32 = 800;
+
+/// WS2812 driver bit-banging the signal on a GPIO output.
+///
+/// Each bit is timed against `mcycle` from the start of its rising edge, so
+/// the time spent writing the pin does not add up over a frame. This needs
+/// no peripheral besides the pin, but the CPU is busy for the whole frame
+/// and the pin writes must take well under the 0.4 µs high time of a 0 bit;
+/// run the code from SRAM with the `ramfunc` feature if it is fetched from
+/// slow memory. Interrupts are masked while each LED is sent, as in
+/// [`Ws2812Pwm`](super::Ws2812Pwm).
+///
+/// Cycle counts are taken from the CPU frequency when the driver is created;
+/// create it again after changing the CPU clock.
+pub struct Ws2812Gpio<P> {
+    pin: P,
+    period: u64,
+    zero: u64,
+    one: u64,
+}
+
+impl<P: OutputPin> Ws2812Gpio<P> {
+    /// Creates a driver on `pin`, driving it low.
+    pub fn new(mut pin: P) -> Result<Self, P::Error> {
+        pin.set_low()?;
+        Ok(Self {
+            pin,
+            period: ns_to_cycles(PERIOD_NS),
+            zero: ns_to_cycles(ZERO_NS),
+            one: ns_to_cycles(ONE_NS),
+        })
+    }
+
+    /// Returns the pin.
+    pub fn free(self) -> P {
+        self.pin
+    }
+
+    /// Sends a frame and latches it.
+    pub fn show<const N: usize>(&mut self, frame: &Framebuffer<N>) -> Result<(), P::Error> {
+        self.write(frame.bytes())
+    }
+
+    /// Sends raw bytes, in wire order, and latches them.
+    pub fn write(&mut self, bytes: impl IntoIterator<Item = u8>) -> Result<(), P::Error> {
+        let mut bytes = bytes.into_iter();
+        loop {
+            // Mask interrupts for one LED at a time, not the whole frame.
+            let sent = interrupt_free(|| {
+                let mut sent = 0;
+                for byte in bytes.by_ref().take(3) {
+                    for i in (0..8).rev() {
+                        self.send_bit(byte & (1 << i) != 0)?;
+                    }
+                    sent += 1;
+                }
+                Ok(sent)
+            })?;
+            if sent < 3 {
+                break;
+            }
+        }
+        let latch = Timeout::from_micros(RESET_US);
+        while !latch.is_expired() {
+            core::hint::spin_loop();
+        }
+        Ok(())
+    }
+
+    /// Drives one bit period, leaving the line low.
+    #[cfg_attr(
+        feature = "ramfunc",
+        unsafe(link_section = ".ramfunc.ws2812_gpio_send_bit")
+    )]
+    fn send_bit(&mut self, bit: bool) -> Result<(), P::Error> {
+        let start = cycles();
+        self.pin.set_high()?;
+        wait_cycles(start, if bit { self.one } else { self.zero });
+        self.pin.set_low()?;
+        wait_cycles(start, self.period);
+        Ok(())
+    }
+}
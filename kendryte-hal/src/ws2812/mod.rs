@@ -0,0 +1,178 @@
+//! WS2812 ("NeoPixel") addressable LED strips.
+//!
+//! WS2812 LEDs take a single-wire 800 kHz signal in which every bit is one
+//! 1.25 µs period whose high time tells a 0 (0.4 µs) from a 1 (0.8 µs). Each
+//! LED takes 24 bits, green, red and blue, most significant bit first, and
+//! passes the rest on to the next LED; holding the line low for longer than
+//! the reset time latches the colours.
+//!
+//! The K230 has no peripheral for this protocol, so the waveform is made in
+//! one of three ways:
+//!
+//! - [`Ws2812Spi`] encodes each bit as three SPI bits on MOSI, clocked at
+//!   2.4 MHz. The FIFO keeps the timing, so this is the preferred option.
+//! - [`Ws2812Pwm`] emits each bit as a one-shot PWM cycle. Every bit is
+//!   started by the CPU, so it needs a fast PWM clock and costs CPU time
+//!   for the whole frame.
+//! - [`Ws2812Gpio`] bit-bangs the signal on any GPIO output with the
+//!   cycle counted waits of [`bitbang`](crate::util::bitbang), for boards
+//!   where the strip is not on an SPI or PWM pad.
+//!
+//! Colours are kept in a [`Framebuffer`], which applies brightness and gamma
+//! correction when the frame is sent.
+
+mod gpio;
+mod pwm;
+mod spi;
+
+pub use gpio::Ws2812Gpio;
+pub use pwm::Ws2812Pwm;
+pub use spi::{SPI_FREQUENCY, Ws2812Spi};
+
+/// Time the line must stay low to latch a frame, in microseconds.
+///
+/// Older WS2812 parts latch after 50 µs; WS2812B V5 and SK6812 need 280 µs.
+pub const RESET_US: u32 = 300;
+
+/// Colour of one LED.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rgb {
+    /// Red intensity.
+    pub r: u8,
+    /// Green intensity.
+    pub g: u8,
+    /// Blue intensity.
+    pub b: u8,
+}
+
+impl Rgb {
+    /// LED switched off.
+    pub const OFF: Self = Self::new(0, 0, 0);
+
+    /// Creates a colour from its components.
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+}
+
+/// Gamma 2.8 correction table.
+///
+/// LED brightness is linear in the PWM duty the LED applies, but perceived
+/// brightness is not; without correction low values look far too bright and
+/// fades jump at the start.
+const GAMMA: [u8; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 3, 3, 3, 3, 3, 3, 3, 4, 4, 4, 4, 4, 5, 5, 5,
+    5, 6, 6, 6, 6, 7, 7, 7, 7, 8, 8, 8, 9, 9, 9, 10, 10, 10, 11, 11, 11, 12, 12, 13, 13, 13, 14,
+    14, 15, 15, 16, 16, 17, 17, 18, 18, 19, 19, 20, 20, 21, 21, 22, 22, 23, 24, 24, 25, 25, 26, 27,
+    27, 28, 29, 29, 30, 31, 32, 32, 33, 34, 35, 35, 36, 37, 38, 39, 39, 40, 41, 42, 43, 44, 45, 46,
+    47, 48, 49, 50, 50, 51, 52, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63, 64, 66, 67, 68, 69, 70, 72,
+    73, 74, 75, 77, 78, 79, 81, 82, 83, 85, 86, 87, 89, 90, 92, 93, 95, 96, 98, 99, 101, 102, 104,
+    105, 107, 109, 110, 112, 114, 115, 117, 119, 120, 122, 124, 126, 127, 129, 131, 133, 135, 137,
+    138, 140, 142, 144, 146, 148, 150, 152, 154, 156, 158, 160, 162, 164, 167, 169, 171, 173, 175,
+    177, 180, 182, 184, 186, 189, 191, 193, 196, 198, 200, 203, 205, 208, 210, 213, 215, 218, 220,
+    223, 225, 228, 231, 233, 236, 239, 241, 244, 247, 249, 252, 255,
+];
+
+/// Gamma corrects one colour component.
+#[inline]
+pub const fn gamma(value: u8) -> u8 {
+    GAMMA[value as usize]
+}
+
+/// Colours of a strip of `N` LEDs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Framebuffer<const N: usize> {
+    pixels: [Rgb; N],
+    brightness: u8,
+    gamma: bool,
+}
+
+impl<const N: usize> Framebuffer<N> {
+    /// Creates a framebuffer with every LED off, full brightness and gamma
+    /// correction enabled.
+    pub const fn new() -> Self {
+        Self {
+            pixels: [Rgb::OFF; N],
+            brightness: u8::MAX,
+            gamma: true,
+        }
+    }
+
+    /// Number of LEDs.
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    /// Returns true if the strip has no LEDs.
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Sets the colour of LED `index`; out of range indices are ignored.
+    pub fn set(&mut self, index: usize, color: Rgb) {
+        if let Some(pixel) = self.pixels.get_mut(index) {
+            *pixel = color;
+        }
+    }
+
+    /// Colour of LED `index`, or `None` if out of range.
+    pub fn get(&self, index: usize) -> Option<Rgb> {
+        self.pixels.get(index).copied()
+    }
+
+    /// Sets every LED to `color`.
+    pub fn fill(&mut self, color: Rgb) {
+        self.pixels.fill(color);
+    }
+
+    /// Switches every LED off.
+    pub fn clear(&mut self) {
+        self.fill(Rgb::OFF);
+    }
+
+    /// Colours of all LEDs, in strip order.
+    pub fn pixels(&self) -> &[Rgb; N] {
+        &self.pixels
+    }
+
+    /// Colours of all LEDs, in strip order.
+    pub fn pixels_mut(&mut self) -> &mut [Rgb; N] {
+        &mut self.pixels
+    }
+
+    /// Sets the global brightness, scaling every component by `brightness / 255`.
+    pub fn set_brightness(&mut self, brightness: u8) {
+        self.brightness = brightness;
+    }
+
+    /// Global brightness.
+    pub fn brightness(&self) -> u8 {
+        self.brightness
+    }
+
+    /// Enables or disables gamma correction.
+    pub fn set_gamma(&mut self, enable: bool) {
+        self.gamma = enable;
+    }
+
+    /// Bytes sent to the strip, in wire order.
+    ///
+    /// Each LED is sent as green, red, blue, gamma corrected and then scaled
+    /// by the brightness.
+    pub fn bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.pixels
+            .iter()
+            .flat_map(|p| [p.g, p.r, p.b])
+            .map(|value| {
+                let value = if self.gamma { gamma(value) } else { value };
+                ((value as u16 * (self.brightness as u16 + 1)) >> 8) as u8
+            })
+    }
+}
+
+impl<const N: usize> Default for Framebuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
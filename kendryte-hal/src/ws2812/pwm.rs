@@ -0,0 +1,112 @@
+use super::{Framebuffer, RESET_US};
+use crate::pwm::{CMP_NEVER, Enable, Pwm};
+use crate::time::{Timeout, interrupt_free};
+
+crate::trace::traced_registers!($ "pwm", crate::pwm::RegisterBlock);
+
+/// Bit rate of the WS2812 signal, in hertz.
+const BIT_RATE: u32 = 800_000;
+
+/// Slowest PWM clock giving usable high times, in hertz.
+const MIN_CLOCK: u32 = 8_000_000;
+
+/// Maximum polling iterations while waiting for a one-shot cycle to end.
+const MAX_ITERATIONS: u32 = 10_000;
+
+/// WS2812 driver emitting each bit as a one-shot PWM cycle.
+///
+/// The PWM period is set to one 1.25 µs bit and the channel comparator to the
+/// high time of the bit being sent; the pulse sits at the end of the cycle,
+/// and the low time before it belongs to the previous bit. The CPU starts
+/// every cycle, so the low time grows by the time it takes to notice that
+/// the previous one ended. Interrupts are masked while each LED is sent so
+/// that gap stays short; between LEDs it may be a few microseconds, which
+/// WS2812 parts tolerate well below the reset time.
+///
+/// The driver takes over the PWM block's prescaler and period, so the other
+/// channels of the block cannot be used for anything else meanwhile.
+pub struct Ws2812Pwm<'a, 'i> {
+    pwm: &'a Pwm<'i>,
+    channel: usize,
+    zero: u32,
+    one: u32,
+}
+
+impl<'a, 'i> Ws2812Pwm<'a, 'i> {
+    /// Creates a driver on PWM channel `channel` of `pwm`, whose counter
+    /// runs at `pwm_clock` hertz.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel` is not 1, 2 or 3, or if `pwm_clock` is below
+    /// 8 MHz or so fast that a bit period does not fit the 16-bit counter.
+    pub fn new(pwm: &'a Pwm<'i>, channel: usize, pwm_clock: u32) -> Self {
+        assert!((1..=3).contains(&channel), "PWM channel out of range");
+        assert!(pwm_clock >= MIN_CLOCK, "PWM clock too slow for WS2812");
+        let top = u16::try_from(pwm_clock / BIT_RATE - 1).expect("PWM clock too fast for WS2812");
+        pwm.stop();
+        pwm.set_scale(0);
+        pwm.set_period(top);
+        let high = |ns: u32| (pwm_clock as u64 * ns as u64 / 1_000_000_000) as u16;
+        let threshold = |high: u16| top.saturating_sub(high) as u32;
+        let driver = Self {
+            pwm,
+            channel,
+            zero: threshold(high(400)),
+            one: threshold(high(800)),
+        };
+        driver.park();
+        driver
+    }
+
+    /// Sends a frame and latches it.
+    pub fn show<const N: usize>(&mut self, frame: &Framebuffer<N>) {
+        self.write(frame.bytes());
+    }
+
+    /// Sends raw bytes, in wire order, and latches them.
+    pub fn write(&mut self, bytes: impl IntoIterator<Item = u8>) {
+        let mut bytes = bytes.into_iter();
+        loop {
+            // Mask interrupts for one LED at a time, not the whole frame.
+            let sent = interrupt_free(|| {
+                let mut sent = 0;
+                for byte in bytes.by_ref().take(3) {
+                    for i in (0..8).rev() {
+                        self.send_bit(byte & (1 << i) != 0);
+                    }
+                    sent += 1;
+                }
+                sent
+            });
+            if sent < 3 {
+                break;
+            }
+        }
+        self.park();
+        let latch = Timeout::from_micros(RESET_US);
+        while !latch.is_expired() {
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Runs one PWM cycle with the high time of `bit`.
+    #[cfg_attr(feature = "ramfunc", unsafe(link_section = ".ramfunc.ws2812_send_bit"))]
+    fn send_bit(&self, bit: bool) {
+        self.pwm
+            .write_cmp(self.channel, if bit { self.one } else { self.zero });
+        self.pwm.start_oneshot();
+        let mut iterations = 0;
+        while read_reg!(self.pwm.inner, pwm_cfg).pwm_en_oneshot() == Enable::Enabled
+            && iterations < MAX_ITERATIONS
+        {
+            iterations += 1;
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Holds the output low.
+    fn park(&self) {
+        self.pwm.write_cmp(self.channel, CMP_NEVER);
+    }
+}
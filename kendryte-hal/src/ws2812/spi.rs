@@ -0,0 +1,96 @@
+use super::{Framebuffer, RESET_US};
+use embedded_hal::spi::SpiBus;
+
+/// SPI clock frequency the encoding is timed for, in hertz.
+pub const SPI_FREQUENCY: u32 = 2_400_000;
+
+/// SPI bytes per LED byte.
+const BYTES_PER_BYTE: usize = 3;
+
+/// LED bytes encoded per SPI write.
+const CHUNK: usize = 24;
+
+/// WS2812 driver encoding the signal on SPI MOSI.
+///
+/// Each LED bit becomes three SPI bits, `100` for a 0 and `110` for a 1,
+/// which at [`SPI_FREQUENCY`] gives high times of 0.42 µs and 0.83 µs in a
+/// 1.25 µs period. Configure the bus for 8-bit words at that frequency and
+/// connect MOSI to the strip's data input; clock and chip select are unused.
+///
+/// The frame is encoded and written in chunks of a few LEDs from a buffer on
+/// the stack. Every encoded bit ends low, so the line stays low between
+/// chunks, which the LEDs take as a slightly longer low time; the gap must
+/// stay well below the reset time.
+pub struct Ws2812Spi<B> {
+    bus: B,
+}
+
+impl<B: SpiBus<u8>> Ws2812Spi<B> {
+    /// Creates a driver on `bus`, which must run at [`SPI_FREQUENCY`].
+    pub fn new(bus: B) -> Self {
+        Self { bus }
+    }
+
+    /// Returns the bus.
+    pub fn free(self) -> B {
+        self.bus
+    }
+
+    /// Sends a frame and latches it.
+    pub fn show<const N: usize>(&mut self, frame: &Framebuffer<N>) -> Result<(), B::Error> {
+        self.write(frame.bytes())
+    }
+
+    /// Sends raw bytes, in wire order, and latches them.
+    pub fn write(&mut self, bytes: impl IntoIterator<Item = u8>) -> Result<(), B::Error> {
+        let mut buf = [0; CHUNK * BYTES_PER_BYTE];
+        let mut len = 0;
+        for byte in bytes {
+            buf[len..len + BYTES_PER_BYTE].copy_from_slice(&encode(byte));
+            len += BYTES_PER_BYTE;
+            if len == buf.len() {
+                self.bus.write(&buf)?;
+                len = 0;
+            }
+        }
+        if len != 0 {
+            self.bus.write(&buf[..len])?;
+        }
+        self.latch()
+    }
+
+    /// Holds MOSI low for the reset time by sending zero bytes.
+    fn latch(&mut self) -> Result<(), B::Error> {
+        let mut remaining = (RESET_US * (SPI_FREQUENCY / 1_000_000)).div_ceil(8) as usize;
+        let zeros = [0; CHUNK * BYTES_PER_BYTE];
+        while remaining != 0 {
+            let len = remaining.min(zeros.len());
+            self.bus.write(&zeros[..len])?;
+            remaining -= len;
+        }
+        self.bus.flush()
+    }
+}
+
+/// Encodes one LED byte as three SPI bytes.
+fn encode(byte: u8) -> [u8; BYTES_PER_BYTE] {
+    let mut bits = 0_u32;
+    for i in (0..8).rev() {
+        let pattern = if byte & (1 << i) != 0 { 0b110 } else { 0b100 };
+        bits = bits << 3 | pattern;
+    }
+    let [_, a, b, c] = bits.to_be_bytes();
+    [a, b, c]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode(0x00), [0x92, 0x49, 0x24]);
+        assert_eq!(encode(0xFF), [0xDB, 0x6D, 0xB6]);
+        assert_eq!(encode(0x80), [0xD2, 0x49, 0x24]);
+    }
+}
@@ -0,0 +1,512 @@
+//! XMODEM-1K and YMODEM receivers for firmware updates over a serial console.
+//!
+//! Works over any `embedded_io` serial port, usually a
+//! [`BlockingUart`](crate::uart::BlockingUart) with
+//! [`Config::blocking_tx`](crate::uart::Config::blocking_tx) enabled. Every
+//! packet is checked with CRC-16/XMODEM, and its payload is handed to a
+//! callback together with its offset in the file, so it can be written to
+//! flash as it arrives instead of being buffered:
+//!
+//! ```ignore
+//! let file = xmodem::receive_ymodem(&mut serial, xmodem::Config::default(), |offset, data| {
+//!     flash.write(offset as u32, data).is_ok()
+//! })?;
+//! ```
+//!
+//! Both 128 and 1024 byte packets are accepted. XMODEM pads the last packet
+//! to its full size, so the callback sees the padding; YMODEM sends the file
+//! size and the padding is cut off. Only the CRC variant of the protocols is
+//! supported, the receiver starts the transfer by sending `C`.
+
+use crate::crc::CRC16_XMODEM;
+use crate::time::Timeout;
+use embedded_io::{Read, ReadReady, Write};
+
+const SOH: u8 = 0x01;
+const STX: u8 = 0x02;
+const EOT: u8 = 0x04;
+const ACK: u8 = 0x06;
+const NAK: u8 = 0x15;
+const CAN: u8 = 0x18;
+/// Requests a transfer with CRC-16 instead of the arithmetic checksum.
+const CRC_REQUEST: u8 = b'C';
+
+/// Quiet time on the line after which a bad packet is considered over, in
+/// milliseconds.
+const PURGE_MS: u32 = 100;
+
+/// Longest file name kept from a YMODEM header.
+pub const MAX_NAME_LEN: usize = 64;
+
+/// Receiver settings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+    /// Interval between transfer requests while waiting for the sender, in
+    /// milliseconds.
+    pub start_interval: u32,
+    /// Transfer requests sent before giving up with [`XmodemError::Timeout`].
+    pub start_retries: u8,
+    /// Longest gap within a transfer, in milliseconds.
+    pub timeout: u32,
+    /// Consecutive bad or missing packets before giving up with
+    /// [`XmodemError::TooManyErrors`].
+    pub max_errors: u8,
+}
+
+impl Default for Config {
+    /// Waits a minute for the sender to start and allows ten bad packets in
+    /// a row.
+    fn default() -> Self {
+        Self {
+            start_interval: 3_000,
+            start_retries: 20,
+            timeout: 1_000,
+            max_errors: 10,
+        }
+    }
+}
+
+/// Errors of a transfer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XmodemError<E> {
+    /// Underlying serial port error.
+    Serial(E),
+    /// The sender did not start a transfer.
+    Timeout,
+    /// Too many bad or missing packets in a row.
+    TooManyErrors,
+    /// The sender cancelled the transfer.
+    Cancelled,
+    /// The callback stopped the transfer.
+    Aborted,
+    /// A packet was lost without the sender noticing.
+    OutOfSequence,
+    /// The YMODEM header packet could not be decoded.
+    InvalidHeader,
+}
+
+/// File announced in a YMODEM header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileInfo {
+    name: [u8; MAX_NAME_LEN],
+    name_len: usize,
+    /// File size in bytes, if the sender gave it.
+    pub size: Option<u32>,
+    /// Bytes passed to the callback.
+    pub received: u32,
+}
+
+impl FileInfo {
+    /// File name, cut to [`MAX_NAME_LEN`] bytes.
+    pub fn name(&self) -> &[u8] {
+        &self.name[..self.name_len]
+    }
+
+    /// Decodes a header packet payload, `name\0size ...`, or returns `None`
+    /// for the empty header closing a batch.
+    fn parse(payload: &[u8]) -> Result<Option<Self>, ()> {
+        let name_end = payload.iter().position(|&b| b == 0).ok_or(())?;
+        if name_end == 0 {
+            return Ok(None);
+        }
+        let name_len = name_end.min(MAX_NAME_LEN);
+        let mut name = [0; MAX_NAME_LEN];
+        name[..name_len].copy_from_slice(&payload[..name_len]);
+
+        let digits = payload[name_end + 1..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit());
+        let mut size = None;
+        for &digit in digits {
+            let value = size.unwrap_or(0_u32);
+            size = Some(
+                value
+                    .checked_mul(10)
+                    .and_then(|v| v.checked_add((digit - b'0') as u32))
+                    .ok_or(())?,
+            );
+        }
+        Ok(Some(Self {
+            name,
+            name_len,
+            size,
+            received: 0,
+        }))
+    }
+}
+
+/// Receive one file with XMODEM-1K and return its length including padding.
+///
+/// `sink` is called with the offset and payload of every new packet; return
+/// false from it to cancel the transfer, e.g. after a flash write failed.
+pub fn receive_xmodem<S>(
+    serial: &mut S,
+    config: Config,
+    sink: impl FnMut(u32, &[u8]) -> bool,
+) -> Result<u32, XmodemError<S::Error>>
+where
+    S: Read + ReadReady + Write,
+{
+    Session::new(serial, config).transfer(None, false, sink)
+}
+
+/// Receive the first file of a YMODEM batch.
+///
+/// `sink` is called as for [`receive_xmodem`], without the padding of the
+/// last packet. Returns `None` if the sender closed the batch without a file.
+/// Further files in the batch are refused.
+pub fn receive_ymodem<S>(
+    serial: &mut S,
+    config: Config,
+    sink: impl FnMut(u32, &[u8]) -> bool,
+) -> Result<Option<FileInfo>, XmodemError<S::Error>>
+where
+    S: Read + ReadReady + Write,
+{
+    let mut session = Session::new(serial, config);
+    let Some(mut file) = session.header()? else {
+        return Ok(None);
+    };
+    file.received = session.transfer(file.size, true, sink)?;
+    if session.header()?.is_some() {
+        session.cancel()?;
+    }
+    Ok(Some(file))
+}
+
+/// A packet that passed its checks.
+enum Packet {
+    /// Payload in `Session::buf[2..2 + len]`.
+    Data { number: u8, len: usize },
+    /// End of file.
+    End,
+    /// Two CAN in a row.
+    Cancel,
+}
+
+struct Session<'s, S> {
+    serial: &'s mut S,
+    config: Config,
+    /// Block number, its complement, payload and CRC of the last packet.
+    buf: [u8; 1024 + 4],
+}
+
+impl<'s, S: Read + ReadReady + Write> Session<'s, S> {
+    fn new(serial: &'s mut S, config: Config) -> Self {
+        Self {
+            serial,
+            config,
+            buf: [0; 1024 + 4],
+        }
+    }
+
+    /// Receive a YMODEM header, acknowledging it.
+    fn header(&mut self) -> Result<Option<FileInfo>, XmodemError<S::Error>> {
+        match self.next_packet(false)? {
+            Packet::Data { number: 0, len } => {
+                let file = match FileInfo::parse(&self.buf[2..2 + len]) {
+                    Ok(file) => file,
+                    Err(()) => {
+                        self.cancel()?;
+                        return Err(XmodemError::InvalidHeader);
+                    }
+                };
+                self.send(ACK)?;
+                Ok(file)
+            }
+            Packet::Cancel => Err(XmodemError::Cancelled),
+            _ => {
+                self.cancel()?;
+                Err(XmodemError::OutOfSequence)
+            }
+        }
+    }
+
+    /// Receive the data packets of a file until EOT.
+    ///
+    /// `size` cuts off the padding of the last packet. YMODEM senders repeat
+    /// EOT after a NAK, so the end of file is only acknowledged the second
+    /// time if `ymodem`.
+    fn transfer(
+        &mut self,
+        size: Option<u32>,
+        ymodem: bool,
+        mut sink: impl FnMut(u32, &[u8]) -> bool,
+    ) -> Result<u32, XmodemError<S::Error>> {
+        let mut expected = 1_u8;
+        let mut received = 0_u32;
+        let mut started = false;
+        loop {
+            match self.next_packet(started)? {
+                Packet::Data { number, len } => {
+                    started = true;
+                    // The sender missed our ACK and repeated the packet.
+                    if number == expected.wrapping_sub(1) {
+                        self.send(ACK)?;
+                        continue;
+                    }
+                    if number != expected {
+                        self.cancel()?;
+                        return Err(XmodemError::OutOfSequence);
+                    }
+                    let len = match size {
+                        Some(size) => len.min(size.saturating_sub(received) as usize),
+                        None => len,
+                    };
+                    if !sink(received, &self.buf[2..2 + len]) {
+                        self.cancel()?;
+                        return Err(XmodemError::Aborted);
+                    }
+                    received += len as u32;
+                    expected = expected.wrapping_add(1);
+                    self.send(ACK)?;
+                }
+                Packet::End => {
+                    if ymodem {
+                        self.send(NAK)?;
+                        let _ = self.read_packet(self.config.timeout)?;
+                    }
+                    self.send(ACK)?;
+                    return Ok(received);
+                }
+                Packet::Cancel => return Err(XmodemError::Cancelled),
+            }
+        }
+    }
+
+    /// Wait for the next valid packet.
+    ///
+    /// Until the sender has `started`, the packet is requested with `C` every
+    /// [`Config::start_interval`]; afterwards a bad or missing packet is
+    /// answered with NAK.
+    fn next_packet(&mut self, started: bool) -> Result<Packet, XmodemError<S::Error>> {
+        let (timeout, limit) = if started {
+            (self.config.timeout, self.config.max_errors)
+        } else {
+            (self.config.start_interval, self.config.start_retries)
+        };
+        let mut errors = 0;
+        loop {
+            if !started {
+                self.send(CRC_REQUEST)?;
+            }
+            if let Some(packet) = self.read_packet(timeout)? {
+                return Ok(packet);
+            }
+            errors += 1;
+            if errors >= limit {
+                self.cancel()?;
+                return Err(if started {
+                    XmodemError::TooManyErrors
+                } else {
+                    XmodemError::Timeout
+                });
+            }
+            if started {
+                self.purge()?;
+                self.send(NAK)?;
+            }
+        }
+    }
+
+    /// Read one packet, or `None` if it is missing, truncated or corrupted.
+    fn read_packet(&mut self, timeout: u32) -> Result<Option<Packet>, XmodemError<S::Error>> {
+        let len = match self.read_byte(timeout)? {
+            Some(SOH) => 128,
+            Some(STX) => 1024,
+            Some(EOT) => return Ok(Some(Packet::End)),
+            Some(CAN) => {
+                return Ok(match self.read_byte(self.config.timeout)? {
+                    Some(CAN) => Some(Packet::Cancel),
+                    _ => None,
+                });
+            }
+            _ => return Ok(None),
+        };
+        for i in 0..len + 4 {
+            match self.read_byte(self.config.timeout)? {
+                Some(byte) => self.buf[i] = byte,
+                None => return Ok(None),
+            }
+        }
+        let number = self.buf[0];
+        let crc = u16::from_be_bytes([self.buf[len + 2], self.buf[len + 3]]);
+        if number != !self.buf[1] || CRC16_XMODEM.checksum(&self.buf[2..2 + len]) != crc {
+            return Ok(None);
+        }
+        Ok(Some(Packet::Data { number, len }))
+    }
+
+    /// Read a byte, or `None` if none arrives within `timeout` milliseconds.
+    fn read_byte(&mut self, timeout: u32) -> Result<Option<u8>, XmodemError<S::Error>> {
+        let timeout = Timeout::from_millis(timeout);
+        loop {
+            let expired = timeout.is_expired();
+            if self.serial.read_ready().map_err(XmodemError::Serial)? {
+                let mut byte = [0];
+                if self.serial.read(&mut byte).map_err(XmodemError::Serial)? == 1 {
+                    return Ok(Some(byte[0]));
+                }
+            }
+            if expired {
+                return Ok(None);
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Discard input until the line has been quiet for [`PURGE_MS`].
+    fn purge(&mut self) -> Result<(), XmodemError<S::Error>> {
+        while self.read_byte(PURGE_MS)?.is_some() {}
+        Ok(())
+    }
+
+    fn send(&mut self, byte: u8) -> Result<(), XmodemError<S::Error>> {
+        self.serial
+            .write_all(&[byte])
+            .map_err(XmodemError::Serial)?;
+        self.serial.flush().map_err(XmodemError::Serial)
+    }
+
+    /// Tell the sender to stop.
+    fn cancel(&mut self) -> Result<(), XmodemError<S::Error>> {
+        self.serial
+            .write_all(&[CAN; 3])
+            .map_err(XmodemError::Serial)?;
+        self.serial.flush().map_err(XmodemError::Serial)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::vec::Vec;
+
+    /// Serial port replaying scripted input and recording the output.
+    struct Script {
+        input: Vec<u8>,
+        position: usize,
+        output: Vec<u8>,
+    }
+
+    impl Script {
+        fn new(input: Vec<u8>) -> Self {
+            Self {
+                input,
+                position: 0,
+                output: Vec::new(),
+            }
+        }
+    }
+
+    impl embedded_io::ErrorType for Script {
+        type Error = core::convert::Infallible;
+    }
+
+    impl Read for Script {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            buf[0] = self.input[self.position];
+            self.position += 1;
+            Ok(1)
+        }
+    }
+
+    impl ReadReady for Script {
+        fn read_ready(&mut self) -> Result<bool, Self::Error> {
+            // Timeouts never expire on the host, so running dry is a bug.
+            assert!(self.position < self.input.len(), "input exhausted");
+            Ok(true)
+        }
+    }
+
+    impl Write for Script {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            self.output.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    fn packet(number: u8, payload: &[u8]) -> Vec<u8> {
+        let len = if payload.len() <= 128 { 128 } else { 1024 };
+        let mut data = payload.to_vec();
+        data.resize(len, 0x1A);
+        let mut packet = std::vec![if len == 128 { SOH } else { STX }, number, !number];
+        packet.extend_from_slice(&data);
+        packet.extend_from_slice(&CRC16_XMODEM.checksum(&data).to_be_bytes());
+        packet
+    }
+
+    #[test]
+    fn xmodem_receive() {
+        let mut input = packet(1, &[0x11; 128]);
+        input.extend(packet(2, &[0x22; 1000]));
+        // A repeated packet is acknowledged but not passed on.
+        input.extend(packet(2, &[0x22; 1000]));
+        input.push(EOT);
+        let mut serial = Script::new(input);
+
+        let mut data = Vec::new();
+        let len = receive_xmodem(&mut serial, Config::default(), |offset, chunk| {
+            assert_eq!(offset as usize, data.len());
+            data.extend_from_slice(chunk);
+            true
+        })
+        .unwrap();
+
+        assert_eq!(len, 128 + 1024);
+        assert_eq!(&data[..128], &[0x11; 128]);
+        assert_eq!(&data[128..1128], &[0x22; 1000]);
+        assert_eq!(&data[1128..], &[0x1A; 24]);
+        assert_eq!(serial.output, [CRC_REQUEST, ACK, ACK, ACK, ACK]);
+    }
+
+    #[test]
+    fn ymodem_receive() {
+        let mut input = packet(0, b"update.bin\x00130 14620034127 100644");
+        input.extend(packet(1, &[0x33; 130]));
+        input.extend([EOT, EOT]);
+        // An empty header closes the batch.
+        input.extend(packet(0, &[0; 128]));
+        let mut serial = Script::new(input);
+
+        let mut data = Vec::new();
+        let file = receive_ymodem(&mut serial, Config::default(), |_, chunk| {
+            data.extend_from_slice(chunk);
+            true
+        })
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(file.name(), b"update.bin");
+        assert_eq!(file.size, Some(130));
+        assert_eq!(file.received, 130);
+        assert_eq!(data, [0x33; 130]);
+        assert_eq!(
+            serial.output,
+            [
+                CRC_REQUEST,
+                ACK,
+                CRC_REQUEST,
+                ACK,
+                NAK,
+                ACK,
+                CRC_REQUEST,
+                ACK
+            ]
+        );
+    }
+
+    #[test]
+    fn aborted_by_sink() {
+        let mut serial = Script::new(packet(1, &[0; 128]));
+        let result = receive_xmodem(&mut serial, Config::default(), |_, _| false);
+        assert_eq!(result, Err(XmodemError::Aborted));
+        assert_eq!(serial.output, [CRC_REQUEST, CAN, CAN, CAN]);
+    }
+}
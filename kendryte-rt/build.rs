@@ -7,6 +7,10 @@ fn main() {
     };
     #[cfg(feature = "k230")]
     std::fs::write(&ld, LINKER_SCRIPT_K230).unwrap();
+    #[cfg(feature = "k510")]
+    std::fs::write(&ld, LINKER_SCRIPT_K510).unwrap();
+    #[cfg(feature = "k210")]
+    std::fs::write(&ld, LINKER_SCRIPT_K210).unwrap();
 
     println!("cargo:rustc-link-search={}", out.display());
     let _ = (ld, out);
@@ -18,6 +22,93 @@ OUTPUT_ARCH(riscv)
 
 ENTRY(_start)
 
+PROVIDE(__pre_init = __kendryte_rt_default_hook);
+PROVIDE(__post_clock_init = __kendryte_rt_default_hook);
+PROVIDE(__idle = __kendryte_rt_default_idle);
+PROVIDE(__pre_jump = __kendryte_rt_default_hook);
+PROVIDE(MachineSoft = DefaultHandler);
+PROVIDE(MachineTimer = DefaultHandler);
+PROVIDE(MachineExternal = __kendryte_rt_default_external);
+PROVIDE(DefaultHandler = __kendryte_rt_default_handler);
+PROVIDE(exceptions = __kendryte_rt_default_exception);
+PROVIDE(UART0 = DefaultHandler);
+PROVIDE(UART1 = DefaultHandler);
+PROVIDE(UART2 = DefaultHandler);
+PROVIDE(UART3 = DefaultHandler);
+PROVIDE(UART4 = DefaultHandler);
+PROVIDE(I2C0 = DefaultHandler);
+PROVIDE(I2C1 = DefaultHandler);
+PROVIDE(I2C2 = DefaultHandler);
+PROVIDE(I2C3 = DefaultHandler);
+PROVIDE(I2C4 = DefaultHandler);
+PROVIDE(GPIO0_0 = DefaultHandler);
+PROVIDE(GPIO0_1 = DefaultHandler);
+PROVIDE(GPIO0_2 = DefaultHandler);
+PROVIDE(GPIO0_3 = DefaultHandler);
+PROVIDE(GPIO0_4 = DefaultHandler);
+PROVIDE(GPIO0_5 = DefaultHandler);
+PROVIDE(GPIO0_6 = DefaultHandler);
+PROVIDE(GPIO0_7 = DefaultHandler);
+PROVIDE(GPIO0_8 = DefaultHandler);
+PROVIDE(GPIO0_9 = DefaultHandler);
+PROVIDE(GPIO0_10 = DefaultHandler);
+PROVIDE(GPIO0_11 = DefaultHandler);
+PROVIDE(GPIO0_12 = DefaultHandler);
+PROVIDE(GPIO0_13 = DefaultHandler);
+PROVIDE(GPIO0_14 = DefaultHandler);
+PROVIDE(GPIO0_15 = DefaultHandler);
+PROVIDE(GPIO0_16 = DefaultHandler);
+PROVIDE(GPIO0_17 = DefaultHandler);
+PROVIDE(GPIO0_18 = DefaultHandler);
+PROVIDE(GPIO0_19 = DefaultHandler);
+PROVIDE(GPIO0_20 = DefaultHandler);
+PROVIDE(GPIO0_21 = DefaultHandler);
+PROVIDE(GPIO0_22 = DefaultHandler);
+PROVIDE(GPIO0_23 = DefaultHandler);
+PROVIDE(GPIO0_24 = DefaultHandler);
+PROVIDE(GPIO0_25 = DefaultHandler);
+PROVIDE(GPIO0_26 = DefaultHandler);
+PROVIDE(GPIO0_27 = DefaultHandler);
+PROVIDE(GPIO0_28 = DefaultHandler);
+PROVIDE(GPIO0_29 = DefaultHandler);
+PROVIDE(GPIO0_30 = DefaultHandler);
+PROVIDE(GPIO0_31 = DefaultHandler);
+PROVIDE(GPIO1_0 = DefaultHandler);
+PROVIDE(GPIO1_1 = DefaultHandler);
+PROVIDE(GPIO1_2 = DefaultHandler);
+PROVIDE(GPIO1_3 = DefaultHandler);
+PROVIDE(GPIO1_4 = DefaultHandler);
+PROVIDE(GPIO1_5 = DefaultHandler);
+PROVIDE(GPIO1_6 = DefaultHandler);
+PROVIDE(GPIO1_7 = DefaultHandler);
+PROVIDE(GPIO1_8 = DefaultHandler);
+PROVIDE(GPIO1_9 = DefaultHandler);
+PROVIDE(GPIO1_10 = DefaultHandler);
+PROVIDE(GPIO1_11 = DefaultHandler);
+PROVIDE(GPIO1_12 = DefaultHandler);
+PROVIDE(GPIO1_13 = DefaultHandler);
+PROVIDE(GPIO1_14 = DefaultHandler);
+PROVIDE(GPIO1_15 = DefaultHandler);
+PROVIDE(GPIO1_16 = DefaultHandler);
+PROVIDE(GPIO1_17 = DefaultHandler);
+PROVIDE(GPIO1_18 = DefaultHandler);
+PROVIDE(GPIO1_19 = DefaultHandler);
+PROVIDE(GPIO1_20 = DefaultHandler);
+PROVIDE(GPIO1_21 = DefaultHandler);
+PROVIDE(GPIO1_22 = DefaultHandler);
+PROVIDE(GPIO1_23 = DefaultHandler);
+PROVIDE(GPIO1_24 = DefaultHandler);
+PROVIDE(GPIO1_25 = DefaultHandler);
+PROVIDE(GPIO1_26 = DefaultHandler);
+PROVIDE(GPIO1_27 = DefaultHandler);
+PROVIDE(GPIO1_28 = DefaultHandler);
+PROVIDE(GPIO1_29 = DefaultHandler);
+PROVIDE(GPIO1_30 = DefaultHandler);
+PROVIDE(GPIO1_31 = DefaultHandler);
+PROVIDE(SPI0 = DefaultHandler);
+PROVIDE(SPI1 = DefaultHandler);
+PROVIDE(SPI2 = DefaultHandler);
+
 MEMORY {
     SPL : ORIGIN = 0x80300000, LENGTH = 0x100000
 }
@@ -27,11 +118,172 @@ SECTIONS
     .text : ALIGN(4) {
         stext = .;
         KEEP(*(.text.entry))
+        . = ALIGN(64);
+        KEEP(*(.text.trap.vector))
+        *(.text .text.*)
+        . = ALIGN(4);
+        etext = .;
+    } > SPL
+
+    .ramfunc : ALIGN(4) {
+        sramfunc = .;
+        *(.ramfunc .ramfunc.*)
+        . = ALIGN(4);
+        eramfunc = .;
+    } > SPL
+
+    .rodata : ALIGN(4) {
+        srodata = .;
+        *(.rodata .rodata.*)
+        *(.srodata .srodata.*)
+        . = ALIGN(4);
+        erodata = .;
+    } > SPL
+
+    .data : ALIGN(4) {
+        sdata = .;
+        *(.data .data.*)
+        *(.sdata .sdata.*)
+        . = ALIGN(4);
+        edata = .;
+    } > SPL
+    sidata = LOADADDR(.data);
+
+    .trailer : ALIGN(4) {
+        strailer = .;
+        KEEP(*(.trailer))
+        etrailer = .;
+    } > SPL
+
+    .bss (NOLOAD) : ALIGN(4) {
+        *(.bss.uninit)
+        sbss = .;
+        *(.bss .bss.*)
+        *(.sbss .sbss.*)
+        ebss = .;
+    } > SPL
+
+    /DISCARD/ : {
+        *(.eh_frame)
+    }
+}
+";
+
+#[cfg(feature = "k510")]
+const LINKER_SCRIPT_K510: &[u8] = b"
+OUTPUT_ARCH(riscv)
+
+ENTRY(_start)
+
+PROVIDE(__pre_init = __kendryte_rt_default_hook);
+PROVIDE(__post_clock_init = __kendryte_rt_default_hook);
+PROVIDE(__idle = __kendryte_rt_default_idle);
+PROVIDE(__pre_jump = __kendryte_rt_default_hook);
+PROVIDE(MachineSoft = DefaultHandler);
+PROVIDE(MachineTimer = DefaultHandler);
+PROVIDE(MachineExternal = __kendryte_rt_default_external);
+PROVIDE(DefaultHandler = __kendryte_rt_default_handler);
+PROVIDE(exceptions = __kendryte_rt_default_exception);
+
+MEMORY {
+    SPL : ORIGIN = 0x80000000, LENGTH = 0x100000
+}
+
+SECTIONS
+{
+    .text : ALIGN(4) {
+        stext = .;
+        KEEP(*(.text.entry))
+        . = ALIGN(64);
+        KEEP(*(.text.trap.vector))
+        *(.text .text.*)
+        . = ALIGN(4);
+        etext = .;
+    } > SPL
+
+    .ramfunc : ALIGN(4) {
+        sramfunc = .;
+        *(.ramfunc .ramfunc.*)
+        . = ALIGN(4);
+        eramfunc = .;
+    } > SPL
+
+    .rodata : ALIGN(4) {
+        srodata = .;
+        *(.rodata .rodata.*)
+        *(.srodata .srodata.*)
+        . = ALIGN(4);
+        erodata = .;
+    } > SPL
+
+    .data : ALIGN(4) {
+        sdata = .;
+        *(.data .data.*)
+        *(.sdata .sdata.*)
+        . = ALIGN(4);
+        edata = .;
+    } > SPL
+    sidata = LOADADDR(.data);
+
+    .trailer : ALIGN(4) {
+        strailer = .;
+        KEEP(*(.trailer))
+        etrailer = .;
+    } > SPL
+
+    .bss (NOLOAD) : ALIGN(4) {
+        *(.bss.uninit)
+        sbss = .;
+        *(.bss .bss.*)
+        *(.sbss .sbss.*)
+        ebss = .;
+    } > SPL
+
+    /DISCARD/ : {
+        *(.eh_frame)
+    }
+}
+";
+
+#[cfg(feature = "k210")]
+const LINKER_SCRIPT_K210: &[u8] = b"
+OUTPUT_ARCH(riscv)
+
+ENTRY(_start)
+
+PROVIDE(__pre_init = __kendryte_rt_default_hook);
+PROVIDE(__post_clock_init = __kendryte_rt_default_hook);
+PROVIDE(__idle = __kendryte_rt_default_idle);
+PROVIDE(__pre_jump = __kendryte_rt_default_hook);
+PROVIDE(MachineSoft = DefaultHandler);
+PROVIDE(MachineTimer = DefaultHandler);
+PROVIDE(MachineExternal = __kendryte_rt_default_external);
+PROVIDE(DefaultHandler = __kendryte_rt_default_handler);
+PROVIDE(exceptions = __kendryte_rt_default_exception);
+
+MEMORY {
+    SPL : ORIGIN = 0x80000000, LENGTH = 0x600000
+}
+
+SECTIONS
+{
+    .text : ALIGN(4) {
+        stext = .;
+        KEEP(*(.text.entry))
+        . = ALIGN(64);
+        KEEP(*(.text.trap.vector))
         *(.text .text.*)
         . = ALIGN(4);
         etext = .;
     } > SPL
 
+    .ramfunc : ALIGN(4) {
+        sramfunc = .;
+        *(.ramfunc .ramfunc.*)
+        . = ALIGN(4);
+        eramfunc = .;
+    } > SPL
+
     .rodata : ALIGN(4) {
         srodata = .;
         *(.rodata .rodata.*)
@@ -49,6 +301,12 @@ SECTIONS
     } > SPL
     sidata = LOADADDR(.data);
 
+    .trailer : ALIGN(4) {
+        strailer = .;
+        KEEP(*(.trailer))
+        etrailer = .;
+    } > SPL
+
     .bss (NOLOAD) : ALIGN(4) {
         *(.bss.uninit)
         sbss = .;
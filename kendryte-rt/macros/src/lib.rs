@@ -83,6 +83,10 @@ pub fn entry(args: TokenStream, input: TokenStream) -> TokenStream {
 /// dispatch table / trap trampoline can call into it.
 ///
 /// Expected signature: `[unsafe] fn() [-> !]` (no parameters, optional never return type).
+///
+/// The trap entry calls the core interrupt handlers by name: `MachineSoft`,
+/// `MachineTimer`, `MachineExternal`, and `DefaultHandler` for any other
+/// interrupt.
 #[proc_macro_attribute]
 pub fn interrupt(args: TokenStream, input: TokenStream) -> TokenStream {
     if !args.is_empty() {
@@ -162,6 +166,9 @@ pub fn interrupt(args: TokenStream, input: TokenStream) -> TokenStream {
 /// The generated function is exported with symbol name `exceptions` so a single
 /// entry point can be invoked by the trap trampoline. Only one such function
 /// should be defined in a program.
+///
+/// With the runtime's `stack-guard` feature, a stack overflow is reported as
+/// a panic before the handler body runs.
 #[proc_macro_attribute]
 pub fn exception(args: TokenStream, input: TokenStream) -> TokenStream {
     if !args.is_empty() {
@@ -224,8 +231,168 @@ pub fn exception(args: TokenStream, input: TokenStream) -> TokenStream {
         #(#attrs)*
         #export_attr
         pub #unsafety extern "C" fn #ident(#inputs) #output {
+            ::kendryte_rt::__check_stack_overflow();
+            #(#stmts)*
+        }
+    )
+    .into()
+}
+
+/// Startup hook called before `.bss` is cleared.
+///
+/// Expected signature: `unsafe fn()`.
+///
+/// The function runs on the runtime stack before statics are initialized, so
+/// it must not read or write any `static`. Use it for early pad or power-rail
+/// configuration through raw register accesses. Only one such function should
+/// be defined in a program.
+#[proc_macro_attribute]
+pub fn pre_init(args: TokenStream, input: TokenStream) -> TokenStream {
+    startup_hook(args, input, "pre_init", "__pre_init", true)
+}
+
+/// Startup hook called right after clock setup, before `main`.
+///
+/// Expected signature: `[unsafe] fn()`.
+///
+/// Statics are initialized at this point, but peripherals have not been handed
+/// to `main` yet. Only one such function should be defined in a program.
+#[proc_macro_attribute]
+pub fn post_clock_init(args: TokenStream, input: TokenStream) -> TokenStream {
+    startup_hook(args, input, "post_clock_init", "__post_clock_init", false)
+}
+
+/// Idle hook run by `kendryte_rt::idle`.
+///
+/// Expected signature: `[unsafe] fn()`.
+///
+/// Replaces the default `wfi`, for example to enter a deeper sleep state.
+/// The hook should return once an interrupt has been handled. Only one such
+/// function should be defined in a program.
+#[proc_macro_attribute]
+pub fn idle(args: TokenStream, input: TokenStream) -> TokenStream {
+    startup_hook(args, input, "idle", "__idle", false)
+}
+
+/// Hook run by `kendryte_rt::boot::jump` before handing over to another image.
+///
+/// Expected signature: `[unsafe] fn()`.
+///
+/// Runs with interrupts and drivers still usable; reset the peripherals the
+/// program configured, so the next image finds them as after reset. Only
+/// one such function should be defined in a program.
+#[proc_macro_attribute]
+pub fn pre_jump(args: TokenStream, input: TokenStream) -> TokenStream {
+    startup_hook(args, input, "pre_jump", "__pre_jump", false)
+}
+
+fn startup_hook(
+    args: TokenStream,
+    input: TokenStream,
+    name: &str,
+    symbol: &str,
+    require_unsafe: bool,
+) -> TokenStream {
+    if !args.is_empty() {
+        return parse::Error::new(
+            Span::call_site(),
+            format!("#[{name}] attribute accepts no arguments"),
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let f = parse_macro_input!(input as ItemFn);
+
+    let valid_signature = f.sig.constness.is_none()
+        && f.sig.asyncness.is_none()
+        && f.vis == Visibility::Inherited
+        && f.sig.abi.is_none()
+        && f.sig.inputs.is_empty()
+        && f.sig.generics.params.is_empty()
+        && f.sig.generics.where_clause.is_none()
+        && f.sig.variadic.is_none()
+        && (f.sig.unsafety.is_some() || !require_unsafe)
+        && match f.sig.output {
+            ReturnType::Default => true,
+            ReturnType::Type(_, ref ty) => match **ty {
+                Type::Tuple(ref tuple) => tuple.elems.is_empty(),
+                _ => false,
+            },
+        };
+
+    if !valid_signature {
+        let expected = if require_unsafe {
+            "unsafe fn()"
+        } else {
+            "[unsafe] fn()"
+        };
+        return parse::Error::new(
+            f.sig.span(),
+            format!("`#[{name}]` function must have signature `{expected}`"),
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let attrs = f.attrs;
+    let unsafety = f.sig.unsafety;
+    let stmts = f.block.stmts;
+    let ident = f.sig.ident;
+
+    #[cfg(feature = "nightly")]
+    let export_attr = quote!(#[unsafe(export_name = #symbol)]);
+    #[cfg(not(feature = "nightly"))]
+    let export_attr = quote!(#[export_name = #symbol]);
+
+    quote!(
+        #(#attrs)*
+        #export_attr
+        pub #unsafety extern "C" fn #ident() {
             #(#stmts)*
         }
     )
     .into()
 }
+
+/// Place a function in on-chip RAM.
+///
+/// Puts the function in a `.ramfunc.<name>` section and keeps it from being
+/// inlined into callers elsewhere. The runtime linker script collects the
+/// section into the on-chip SRAM the image is loaded to and runs from, so
+/// no copy is needed and no memory is set aside for it. Use it for
+/// interrupt handlers and hot paths that must not stall on instruction
+/// fetches once images can execute in place from external flash.
+///
+/// Can be combined with `#[interrupt]` and `#[exception]`.
+#[proc_macro_attribute]
+pub fn ramfunc(args: TokenStream, input: TokenStream) -> TokenStream {
+    if !args.is_empty() {
+        return parse::Error::new(
+            Span::call_site(),
+            "#[ramfunc] attribute accepts no arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let f = parse_macro_input!(input as ItemFn);
+
+    if f.sig.constness.is_some() || f.sig.asyncness.is_some() {
+        return parse::Error::new(
+            f.sig.span(),
+            "`#[ramfunc]` cannot be applied to `const` or `async` functions",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let section = format!(".ramfunc.{}", f.sig.ident);
+
+    quote!(
+        #[unsafe(link_section = #section)]
+        #[inline(never)]
+        #f
+    )
+    .into()
+}
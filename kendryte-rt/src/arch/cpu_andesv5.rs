@@ -0,0 +1,78 @@
+//! Andes V5 (AX25MP) specific CPU support code.
+
+/// Entry function for Andes V5 core.
+#[cfg(target_arch = "riscv64")]
+#[unsafe(naked)]
+#[unsafe(link_section = ".text.entry")]
+#[unsafe(export_name = "_start")]
+pub unsafe extern "C" fn start() -> ! {
+    use crate::{__init_trap, __paint_stack_guard, __pre_init, STACK, STACK_SIZE, main};
+    core::arch::naked_asm!(
+        // Disable interrupt.
+        "csrw   mie, zero",
+
+        // Only hart 0 runs the ROM runtime; park the other harts.
+        "csrr   t0, mhartid
+        bnez    t0, 3f",
+
+        // Enable instruction and data caches (mcache_ctl IC_EN and DC_EN).
+        "li     t0, 0x3
+        csrs    0x7ca, t0",
+
+        // Prepare programming language stack.
+        "la    sp, {stack}
+        li     t0, {stack_size}
+        add    sp, sp, t0",
+
+        // Fill the canary below the stack.
+        "call   {paint}",
+
+        // Run board specific early initialization.
+        "call   {pre_init}",
+
+        // Clear `.bss` section.
+        "la    t1, sbss
+        la     t2, ebss
+    1:  bgeu   t1, t2, 2f
+        sw     zero, 0(t1)
+        addi   t1, t1, 4
+        j      1b
+    2:",
+
+        // Install the trap vector.
+        "call   {init_trap}",
+
+        // Start Rust main function.
+        "call   {main}",
+
+        // Platform halt if main function returns.
+        "
+    3:  wfi
+        j       3b",
+
+        stack      = sym STACK,
+        stack_size = const STACK_SIZE,
+        pre_init   = sym __pre_init,
+        paint      = sym __paint_stack_guard,
+        init_trap  = sym __init_trap,
+        main       = sym main,
+    )
+}
+
+/// Write back and invalidate the data cache, then invalidate the
+/// instruction cache.
+#[cfg(target_arch = "riscv64")]
+#[inline]
+pub(crate) unsafe fn flush_caches() {
+    unsafe {
+        core::arch::asm!(
+            // mcctlcommand = L1D_WBINVAL_ALL.
+            "li     t0, 6
+            csrw    0x7cc, t0",
+            "fence
+            fence.i",
+            out("t0") _,
+            options(nostack),
+        )
+    };
+}
@@ -6,7 +6,7 @@
 #[unsafe(link_section = ".text.entry")]
 #[unsafe(export_name = "_start")]
 pub unsafe extern "C" fn start() -> ! {
-    use crate::{STACK, STACK_SIZE, main};
+    use crate::{__init_trap, __paint_stack_guard, __pre_init, STACK, STACK_SIZE, main};
     core::arch::naked_asm!(
         // Disable interrupt.
         "csrw   mie, zero",
@@ -16,6 +16,12 @@ pub unsafe extern "C" fn start() -> ! {
         li     t0, {stack_size}
         add    sp, sp, t0",
 
+        // Fill the canary below the stack.
+        "call   {paint}",
+
+        // Run board specific early initialization.
+        "call   {pre_init}",
+
         // Clear `.bss` section.
         "la    t1, sbss
         la     t2, ebss
@@ -25,6 +31,9 @@ pub unsafe extern "C" fn start() -> ! {
         j      1b
     2:",
 
+        // Install the trap vector.
+        "call   {init_trap}",
+
         // Start Rust main function.
         "call   {main}",
 
@@ -35,8 +44,30 @@ pub unsafe extern "C" fn start() -> ! {
 
         stack      = sym STACK,
         stack_size = const STACK_SIZE,
+        pre_init   = sym __pre_init,
+        paint      = sym __paint_stack_guard,
+        init_trap  = sym __init_trap,
         main       = sym main,
     )
 }
 
+/// Write back and invalidate the data cache, then invalidate the
+/// instruction cache, with the T-Head cache maintenance instructions.
+#[cfg(target_arch = "riscv64")]
+#[inline]
+pub(crate) unsafe fn flush_caches() {
+    unsafe {
+        core::arch::asm!(
+            // th.dcache.ciall
+            ".long  0x0030000b",
+            // th.icache.iall
+            ".long  0x0100000b",
+            // th.sync.s
+            ".long  0x0190000b",
+            "fence.i",
+            options(nostack),
+        )
+    };
+}
+
 // TODO multi-core baremetal entry.
@@ -0,0 +1,70 @@
+//! Generic RISC-V CPU support code for cores implementing privileged
+//! specification version 1.9.1, such as the K210 cores.
+
+/// Entry function for generic RISC-V cores.
+#[cfg(target_arch = "riscv64")]
+#[unsafe(naked)]
+#[unsafe(link_section = ".text.entry")]
+#[unsafe(export_name = "_start")]
+pub unsafe extern "C" fn start() -> ! {
+    use crate::{__init_trap, __paint_stack_guard, __pre_init, STACK, STACK_SIZE, main};
+    core::arch::naked_asm!(
+        // Disable interrupt.
+        "csrw   mie, zero",
+
+        // Only hart 0 runs the ROM runtime; park the other harts.
+        "csrr   t0, mhartid
+        bnez    t0, 3f",
+
+        // Turn on the FPU (mstatus.FS = Initial); privileged specification 1.9.1
+        // cores start with it off and trap on the first floating point instruction.
+        "li     t0, 0x2000
+        csrs    mstatus, t0
+        fscsr   zero",
+
+        // Prepare programming language stack.
+        "la    sp, {stack}
+        li     t0, {stack_size}
+        add    sp, sp, t0",
+
+        // Fill the canary below the stack.
+        "call   {paint}",
+
+        // Run board specific early initialization.
+        "call   {pre_init}",
+
+        // Clear `.bss` section.
+        "la    t1, sbss
+        la     t2, ebss
+    1:  bgeu   t1, t2, 2f
+        sw     zero, 0(t1)
+        addi   t1, t1, 4
+        j      1b
+    2:",
+
+        // Install the trap vector.
+        "call   {init_trap}",
+
+        // Start Rust main function.
+        "call   {main}",
+
+        // Platform halt if main function returns.
+        "
+    3:  wfi
+        j       3b",
+
+        stack      = sym STACK,
+        stack_size = const STACK_SIZE,
+        pre_init   = sym __pre_init,
+        paint      = sym __paint_stack_guard,
+        init_trap  = sym __init_trap,
+        main       = sym main,
+    )
+}
+
+/// Order outstanding memory accesses and invalidate the instruction cache.
+#[cfg(target_arch = "riscv64")]
+#[inline]
+pub(crate) unsafe fn flush_caches() {
+    unsafe { core::arch::asm!("fence", "fence.i", options(nostack)) };
+}
@@ -4,6 +4,12 @@
 pub mod rve;
 pub mod rvi;
 
+// Physical memory protection.
+pub mod pmp;
+
+// Trap entry and vector table.
+pub mod trap;
+
 // CPU specific supports, including entry assembly code and stack implementation.
 
 // K230 cpu supports.
@@ -11,9 +17,137 @@ pub mod rvi;
 pub mod cpu_c908;
 
 // K510 cpu supports.
-// #[cfg(any(doc, feature = "cpu-andesv5"))]
-// TODO pub mod cpu_andesv5;
+#[cfg(any(doc, feature = "cpu-andesv5"))]
+pub mod cpu_andesv5;
 
 // For K210 chip, which is actually a BOOM RISC-V IP core with RISC-V privileged
 // specification version 1.9.1.
-// TODO pub mod generic;
+#[cfg(any(doc, feature = "cpu-generic"))]
+pub mod generic;
+
+use core::ops::Range;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Length in bytes of the instruction at `pc`.
+///
+/// # Safety
+///
+/// `pc` must point to a readable, 2-byte aligned instruction.
+#[inline]
+pub(crate) unsafe fn instruction_len(pc: usize) -> usize {
+    // Compressed instructions have anything but 0b11 in their low two bits.
+    let low = unsafe { (pc as *const u16).read_volatile() };
+    if low & 0b11 == 0b11 { 4 } else { 2 }
+}
+
+/// Size of the guard region at the bottom of each stack, in bytes.
+pub const STACK_GUARD_SIZE: usize = 256;
+
+/// Most stacks checked for overflows, one per hart on the supported chips.
+const MAX_GUARDED_STACKS: usize = 4;
+
+/// Guard regions checked for overflows, as start and end addresses; a zero
+/// start marks a free slot.
+static GUARDS: [(AtomicUsize, AtomicUsize); MAX_GUARDED_STACKS] =
+    [const { (AtomicUsize::new(0), AtomicUsize::new(0)) }; MAX_GUARDED_STACKS];
+
+/// Adds `guard` to the regions checked for overflows; see
+/// [`rvi::Stack::register_guard`].
+fn register_guard(guard: Range<usize>) -> bool {
+    for (start, end) in &GUARDS {
+        if start.load(Ordering::Acquire) == guard.start {
+            return true;
+        }
+        if start
+            .compare_exchange(0, guard.start, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+        {
+            end.store(guard.end, Ordering::Release);
+            return true;
+        }
+    }
+    false
+}
+
+/// Returns true if `fault` is an address in a registered guard region, or
+/// the canary of a registered guard region is damaged.
+pub(crate) fn stack_overflowed(fault: Option<usize>) -> bool {
+    GUARDS.iter().any(|(start, end)| {
+        let (start, end) = (start.load(Ordering::Acquire), end.load(Ordering::Acquire));
+        if start == 0 || end <= start {
+            return false;
+        }
+        let guard = unsafe { core::slice::from_raw_parts(start as *const u8, end - start) };
+        fault.is_some_and(|fault| (start..end).contains(&fault)) || !guard_intact(guard)
+    })
+}
+
+/// Byte pattern filling an unused stack guard region.
+const STACK_CANARY: u8 = 0xA5;
+
+/// Fill `guard` with the canary pattern.
+fn paint_guard(guard: &mut [u8]) {
+    for byte in guard {
+        // Volatile, so the pattern is written even though nothing reads it.
+        unsafe { (byte as *mut u8).write_volatile(STACK_CANARY) };
+    }
+}
+
+/// Returns true if `guard` still holds the canary pattern.
+fn guard_intact(guard: &[u8]) -> bool {
+    guard
+        .iter()
+        .all(|byte| unsafe { (byte as *const u8).read_volatile() } == STACK_CANARY)
+}
+
+/// Fill the part of `stack` below the current stack pointer with the canary
+/// pattern.
+///
+/// Everything from the stack pointer up, including the frames of the
+/// callers, is left alone. The fill is written out here rather than through
+/// [`paint_guard`], since a call would put a frame in the painted range.
+#[inline(never)]
+fn paint_free(stack: &mut [u8]) {
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    let sp = {
+        let sp: usize;
+        unsafe { core::arch::asm!("mv {}, sp", out(reg) sp, options(nomem, nostack)) };
+        sp
+    };
+    #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+    let sp = 0_usize;
+    let free = sp.saturating_sub(stack.as_ptr() as usize).min(stack.len());
+    for byte in &mut stack[..free] {
+        unsafe { (byte as *mut u8).write_volatile(STACK_CANARY) };
+    }
+}
+
+/// Number of bytes at the bottom of `stack` still holding the canary pattern.
+fn untouched(stack: &[u8]) -> usize {
+    stack
+        .iter()
+        .take_while(|byte| unsafe { (*byte as *const u8).read_volatile() } == STACK_CANARY)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+
+    #[test]
+    fn registered_guards_report_overflows() {
+        let guard = std::vec![0_u8; STACK_GUARD_SIZE].leak();
+        paint_guard(guard);
+        let range = guard.as_ptr_range();
+        let range = range.start as usize..range.end as usize;
+        assert!(register_guard(range.clone()));
+        assert!(!stack_overflowed(None));
+        assert!(!stack_overflowed(Some(range.end)));
+        assert!(stack_overflowed(Some(range.start)));
+
+        guard[STACK_GUARD_SIZE - 1] = 0;
+        assert!(stack_overflowed(None));
+    }
+}
@@ -0,0 +1,234 @@
+//! RISC-V physical memory protection.
+//!
+//! [`Pmp`] collects regions in a declarative builder and programs them in
+//! one go during startup, for example to make code read-only, to trap null
+//! pointer accesses or to keep one hart from scribbling over another hart's
+//! stack:
+//!
+//! ```ignore
+//! use kendryte_rt::arch::pmp::{Pmp, Region};
+//!
+//! unsafe {
+//!     Pmp::new()
+//!         // Accesses to the first 4 KiB fault, catching null pointers.
+//!         .region(Region::new(0, 0x1000).lock())
+//!         .region(Region::new(stext, etext - stext).read().execute().lock())
+//!         // Everything else stays accessible.
+//!         .region(Region::new(0, usize::MAX).read().write().execute())
+//!         .apply()
+//! }
+//! .unwrap();
+//! ```
+//!
+//! Entries are matched in order, so earlier regions take precedence over
+//! later, overlapping ones. Machine mode code is only restricted by locked
+//! regions, and a locked region stays in force until the next reset.
+//!
+//! The pmpcfg layout used here is the RV64 one. The K210 implements the
+//! 1.9.1 privileged specification, which predates this PMP, so it is not
+//! supported there.
+
+/// Number of PMP entries implemented by the supported cores.
+pub const ENTRIES: usize = 16;
+
+const R: u8 = 1 << 0;
+const W: u8 = 1 << 1;
+const X: u8 = 1 << 2;
+const OFF: u8 = 0 << 3;
+const TOR: u8 = 1 << 3;
+const NA4: u8 = 2 << 3;
+const NAPOT: u8 = 3 << 3;
+const A_MASK: u8 = 3 << 3;
+const L: u8 = 1 << 7;
+
+/// Error programming PMP regions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PmpError {
+    /// The regions need more than [`ENTRIES`] entries.
+    TooManyRegions,
+    /// A region's start or end is not 4-byte aligned.
+    Misaligned,
+}
+
+/// Address range with its access permissions.
+///
+/// A new region grants no access; add permissions with [`read`](Self::read),
+/// [`write`](Self::write) and [`execute`](Self::execute).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    start: usize,
+    size: usize,
+    cfg: u8,
+}
+
+impl Region {
+    /// Region of `size` bytes starting at `start`.
+    ///
+    /// Naturally aligned power-of-two regions use one entry; other regions
+    /// use two, or one if they start at 0 or where the previous one ended.
+    /// A `size` reaching past the end of the address space is clamped.
+    pub const fn new(start: usize, size: usize) -> Self {
+        Self {
+            start,
+            size,
+            cfg: 0,
+        }
+    }
+
+    /// Allow reads.
+    pub const fn read(mut self) -> Self {
+        self.cfg |= R;
+        self
+    }
+
+    /// Allow writes. Writes without reads are reserved and fault.
+    pub const fn write(mut self) -> Self {
+        self.cfg |= W;
+        self
+    }
+
+    /// Allow instruction fetches.
+    pub const fn execute(mut self) -> Self {
+        self.cfg |= X;
+        self
+    }
+
+    /// Lock the region, enforcing it in machine mode until reset.
+    pub const fn lock(mut self) -> Self {
+        self.cfg |= L;
+        self
+    }
+}
+
+/// Builder for the PMP entries of the current hart.
+#[derive(Clone, Debug)]
+pub struct Pmp {
+    /// Encoded `pmpaddr` value and `pmpcfg` byte of each entry.
+    entries: [(usize, u8); ENTRIES],
+    used: usize,
+    error: Option<PmpError>,
+}
+
+impl Pmp {
+    /// Creates a builder with no regions.
+    pub const fn new() -> Self {
+        Self {
+            entries: [(0, OFF); ENTRIES],
+            used: 0,
+            error: None,
+        }
+    }
+
+    /// Adds a region after the ones added so far.
+    ///
+    /// Errors are reported by [`apply`](Self::apply).
+    pub fn region(mut self, region: Region) -> Self {
+        if self.error.is_none() {
+            if let Err(e) = self.push_region(region) {
+                self.error = Some(e);
+            }
+        }
+        self
+    }
+
+    /// Number of entries the regions added so far occupy.
+    pub fn entries_used(&self) -> usize {
+        self.used
+    }
+
+    /// Programs the regions into the PMP entries, disabling the others.
+    ///
+    /// Entries locked earlier cannot be changed and keep their settings.
+    ///
+    /// # Safety
+    ///
+    /// Locked regions apply to the running code immediately; they must
+    /// leave its code, stack and data accessible.
+    pub unsafe fn apply(self) -> Result<(), PmpError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        // Turn everything off first, so no half-programmed entry is live.
+        unsafe {
+            write_pmpcfg0(0);
+            write_pmpcfg2(0);
+        }
+        let mut cfg = [0u64; 2];
+        for (i, &(addr, entry_cfg)) in self.entries[..self.used].iter().enumerate() {
+            unsafe { write_pmpaddr(i, addr) };
+            cfg[i / 8] |= (entry_cfg as u64) << (i % 8 * 8);
+        }
+        unsafe {
+            write_pmpcfg0(cfg[0] as usize);
+            write_pmpcfg2(cfg[1] as usize);
+        }
+        Ok(())
+    }
+
+    fn push_region(&mut self, region: Region) -> Result<(), PmpError> {
+        let Region { start, size, cfg } = region;
+        let napot = size.is_power_of_two() && size >= 4 && start % size == 0;
+        if napot {
+            return if size == 4 {
+                self.push(start >> 2, cfg | NA4)
+            } else {
+                self.push((start | (size / 2 - 1)) >> 2, cfg | NAPOT)
+            };
+        }
+        let end = start.saturating_add(size);
+        if start % 4 != 0 || (end % 4 != 0 && end != usize::MAX) {
+            return Err(PmpError::Misaligned);
+        }
+        // TOR entries start where the previous entry's address points.
+        let previous = match self.used {
+            0 => Some(0),
+            n => {
+                let (addr, cfg) = self.entries[n - 1];
+                (cfg & A_MASK == TOR || cfg & A_MASK == OFF).then_some(addr)
+            }
+        };
+        if previous != Some(start >> 2) {
+            self.push(start >> 2, OFF)?;
+        }
+        self.push(end >> 2, cfg | TOR)
+    }
+
+    fn push(&mut self, addr: usize, cfg: u8) -> Result<(), PmpError> {
+        let entry = self
+            .entries
+            .get_mut(self.used)
+            .ok_or(PmpError::TooManyRegions)?;
+        *entry = (addr, cfg);
+        self.used += 1;
+        Ok(())
+    }
+}
+
+impl Default for Pmp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe fn write_pmpcfg0(value: usize) {
+    unsafe { core::arch::asm!("csrw pmpcfg0, {}", in(reg) value, options(nostack)) };
+}
+
+unsafe fn write_pmpcfg2(value: usize) {
+    unsafe { core::arch::asm!("csrw pmpcfg2, {}", in(reg) value, options(nostack)) };
+}
+
+macro_rules! write_pmpaddr_n {
+    ($index:expr, $value:expr, $($n:literal)+) => {
+        match $index {
+            $($n => unsafe {
+                core::arch::asm!(concat!("csrw pmpaddr", $n, ", {}"), in(reg) $value, options(nostack))
+            },)+
+            _ => unreachable!(),
+        }
+    };
+}
+
+unsafe fn write_pmpaddr(index: usize, value: usize) {
+    write_pmpaddr_n!(index, value, 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15);
+}
@@ -1,12 +1,89 @@
 //! RISC-V RV32E and RV64E structures.
 
+use crate::arch::pmp::Region;
+use crate::arch::{
+    STACK_GUARD_SIZE, guard_intact, instruction_len, paint_free, paint_guard, register_guard,
+    untouched,
+};
+use crate::interrupt::Trap;
+
 /// RISC-V program stack.
 ///
 /// RV32E stacks are 4-byte aligned.
 #[repr(align(4))]
 pub struct Stack<const N: usize>(pub(crate) [u8; N]);
 
+impl<const N: usize> Stack<N> {
+    /// Guard region at the bottom of the stack, which the stack only reaches
+    /// when it overflows.
+    #[inline]
+    pub fn guard(&self) -> core::ops::Range<usize> {
+        let start = self.0.as_ptr() as usize;
+        start..start + N.min(STACK_GUARD_SIZE)
+    }
+
+    /// Fills the guard region with the canary pattern.
+    ///
+    /// The entry code does this for the runtime stack when the `stack-guard`
+    /// feature is enabled.
+    #[inline]
+    pub fn paint_guard(&mut self) {
+        paint_guard(&mut self.0[..N.min(STACK_GUARD_SIZE)]);
+    }
+
+    /// Returns true if the canary in the guard region is intact, i.e. the
+    /// stack has not overflowed since it was painted.
+    #[inline]
+    pub fn guard_intact(&self) -> bool {
+        guard_intact(&self.0[..N.min(STACK_GUARD_SIZE)])
+    }
+
+    /// Checks this stack for overflows on exceptions, see
+    /// [`rvi::Stack::register_guard`](crate::arch::rvi::Stack::register_guard).
+    #[inline]
+    pub fn register_guard(&'static self) -> bool {
+        register_guard(self.guard())
+    }
+
+    /// Fills the stack below the current stack pointer with the canary
+    /// pattern, so [`unused`](Self::unused) can tell how deep it grows.
+    ///
+    /// Painting the stack in use leaves its live frames intact; a stack
+    /// that is not in use is painted whole. The entry code does this for
+    /// the runtime stack when the `stack-usage` feature is enabled.
+    #[inline]
+    pub fn paint(&mut self) {
+        paint_free(&mut self.0);
+    }
+
+    /// Bytes at the bottom of the stack not written since it was painted.
+    ///
+    /// A frame storing the canary byte at the deepest point makes this a
+    /// few bytes too large.
+    #[inline]
+    pub fn unused(&self) -> usize {
+        untouched(&self.0)
+    }
+
+    /// Most bytes of the stack in use at once since it was painted.
+    #[inline]
+    pub fn high_water_mark(&self) -> usize {
+        N - self.unused()
+    }
+
+    /// Locked PMP region denying all access to the guard region.
+    ///
+    /// An overflow then faults at the first access to the guard instead of
+    /// being found later by the canary check.
+    #[inline]
+    pub fn guard_region(&self) -> Region {
+        let guard = self.guard();
+        Region::new(guard.start, guard.len()).lock()
+    }
+}
+
 /// RISC-V 'E' instruction base Trap stack frame declaration.
+#[derive(Clone, Debug)]
 #[repr(C)]
 pub struct TrapFrame {
     /// Return address register.
@@ -36,3 +113,94 @@ pub struct TrapFrame {
     /// Machine status register.
     pub mstatus: usize,
 }
+
+impl TrapFrame {
+    /// Address of the trapping instruction, or of the instruction to
+    /// resume at for interrupts.
+    #[inline]
+    pub fn pc(&self) -> usize {
+        self.mepc
+    }
+
+    /// Sets the address execution resumes at after the handler returns.
+    #[inline]
+    pub fn set_pc(&mut self, pc: usize) {
+        self.mepc = pc;
+    }
+
+    /// Return address register at the time of the trap.
+    #[inline]
+    pub fn ra(&self) -> usize {
+        self.ra
+    }
+
+    /// Stack pointer at the time of the trap.
+    ///
+    /// The trap entry saves the frame on the interrupted stack, so the
+    /// interrupted stack pointer is the address just above the frame.
+    #[inline]
+    pub fn sp(&self) -> usize {
+        self as *const Self as usize + core::mem::size_of::<Self>()
+    }
+
+    /// Argument register `a<n>`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is not below 6.
+    #[inline]
+    pub fn arg(&self, n: usize) -> usize {
+        match n {
+            0 => self.a0,
+            1 => self.a1,
+            2 => self.a2,
+            3 => self.a3,
+            4 => self.a4,
+            5 => self.a5,
+            _ => panic!("no argument register a{}", n),
+        }
+    }
+
+    /// Sets argument register `a<n>`, for example to return a value from an
+    /// emulated call in `a0`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is not below 6.
+    #[inline]
+    pub fn set_arg(&mut self, n: usize, value: usize) {
+        *self.arg_mut(n) = value;
+    }
+
+    fn arg_mut(&mut self, n: usize) -> &mut usize {
+        match n {
+            0 => &mut self.a0,
+            1 => &mut self.a1,
+            2 => &mut self.a2,
+            3 => &mut self.a3,
+            4 => &mut self.a4,
+            5 => &mut self.a5,
+            _ => panic!("no argument register a{}", n),
+        }
+    }
+
+    /// Decodes the cause of the trap.
+    #[inline]
+    pub fn cause(&self) -> Trap {
+        Trap::from_mcause(self.mcause)
+    }
+
+    /// Resumes after the trapping instruction instead of retrying it.
+    ///
+    /// Used once an exception handler has emulated the instruction. Both
+    /// 16-bit compressed and 32-bit instructions are handled.
+    ///
+    /// # Safety
+    ///
+    /// The instruction at [`pc`](Self::pc) is read to find its length, so
+    /// the trap must not be an instruction access fault or page fault.
+    #[inline]
+    pub unsafe fn skip_instruction(&mut self) {
+        self.mepc += unsafe { instruction_len(self.mepc) };
+    }
+}
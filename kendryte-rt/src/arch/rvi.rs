@@ -1,5 +1,12 @@
 //! RISC-V RV32I and RV64I structures.
 
+use crate::arch::pmp::Region;
+use crate::arch::{
+    STACK_GUARD_SIZE, guard_intact, instruction_len, paint_free, paint_guard, register_guard,
+    untouched,
+};
+use crate::interrupt::Trap;
+
 /// RISC-V program stack.
 ///
 /// In standard RISC-V ABI specification, the stack grows downward and
@@ -7,7 +14,128 @@
 #[repr(align(16))]
 pub struct Stack<const N: usize>(pub(crate) [u8; N]);
 
+impl<const N: usize> Stack<N> {
+    /// Guard region at the bottom of the stack, which the stack only reaches
+    /// when it overflows.
+    #[inline]
+    pub fn guard(&self) -> core::ops::Range<usize> {
+        let start = self.0.as_ptr() as usize;
+        start..start + N.min(STACK_GUARD_SIZE)
+    }
+
+    /// Fills the guard region with the canary pattern.
+    ///
+    /// The entry code does this for the runtime stack when the `stack-guard`
+    /// feature is enabled.
+    #[inline]
+    pub fn paint_guard(&mut self) {
+        paint_guard(&mut self.0[..N.min(STACK_GUARD_SIZE)]);
+    }
+
+    /// Returns true if the canary in the guard region is intact, i.e. the
+    /// stack has not overflowed since it was painted.
+    #[inline]
+    pub fn guard_intact(&self) -> bool {
+        guard_intact(&self.0[..N.min(STACK_GUARD_SIZE)])
+    }
+
+    /// Fills the stack below the current stack pointer with the canary
+    /// pattern, so [`unused`](Self::unused) can tell how deep it grows.
+    ///
+    /// Painting the stack in use leaves its live frames intact; a stack
+    /// that is not in use is painted whole. The entry code does this for
+    /// the runtime stack when the `stack-usage` feature is enabled.
+    #[inline]
+    pub fn paint(&mut self) {
+        paint_free(&mut self.0);
+    }
+
+    /// Bytes at the bottom of the stack not written since it was painted.
+    ///
+    /// A frame storing the canary byte at the deepest point makes this a
+    /// few bytes too large.
+    #[inline]
+    pub fn unused(&self) -> usize {
+        untouched(&self.0)
+    }
+
+    /// Most bytes of the stack in use at once since it was painted.
+    #[inline]
+    pub fn high_water_mark(&self) -> usize {
+        N - self.unused()
+    }
+
+    /// Locked PMP region denying all access to the guard region.
+    ///
+    /// An overflow then faults at the first access to the guard instead of
+    /// being found later by the canary check. The fault is taken on the same
+    /// stack unless the hart has a trap stack, see
+    /// [`install_trap_stack`](Self::install_trap_stack), so lock the region
+    /// only on harts that have one.
+    #[inline]
+    pub fn guard_region(&self) -> Region {
+        let guard = self.guard();
+        Region::new(guard.start, guard.len()).lock()
+    }
+
+    /// Checks this stack for overflows on exceptions, as the runtime stack is
+    /// with the `stack-guard` feature.
+    ///
+    /// A hart started on a stack of its own registers it after painting its
+    /// guard with [`paint_guard`](Self::paint_guard). Returns false if too
+    /// many stacks are registered already.
+    #[inline]
+    pub fn register_guard(&'static self) -> bool {
+        register_guard(self.guard())
+    }
+
+    /// Makes this stack the trap stack of the current hart.
+    ///
+    /// Traps taken on another stack switch to this one before saving their
+    /// frame, so the exception raised by an overflowing stack, such as an
+    /// access fault on its [`guard_region`](Self::guard_region), is handled
+    /// and reported instead of faulting again. Handlers run on this stack,
+    /// and traps nested in them stay on it. The runtime installs
+    /// [`TRAP_STACK`](crate::arch::trap::TRAP_STACK) on hart 0 with the
+    /// `stack-guard` feature.
+    ///
+    /// # Safety
+    ///
+    /// The stack must not be used for anything else, and each hart needs a
+    /// trap stack of its own. Call it outside of trap handlers.
+    #[inline]
+    pub unsafe fn install_trap_stack(&'static mut self) {
+        let top = self.0.as_mut_ptr() as usize + N;
+        #[cfg(target_arch = "riscv64")]
+        unsafe {
+            core::arch::asm!("csrw mscratch, {}", in(reg) top, options(nomem, nostack))
+        };
+        #[cfg(not(target_arch = "riscv64"))]
+        let _ = top;
+    }
+}
+
 /// RISC-V 'I' instruction base Trap stack frame declaration.
+///
+/// The frame holds every general purpose register, so exception handlers
+/// can emulate an instruction whatever registers it names. For example, a
+/// fixup for misaligned 32-bit loads:
+///
+/// ```ignore
+/// #[exception]
+/// fn exceptions(tf: &mut TrapFrame) {
+///     let Trap::Exception(ExceptionCause::LoadMisaligned) = tf.cause() else {
+///         panic!("unhandled exception {:?}", tf.cause());
+///     };
+///     let insn = unsafe { (tf.pc() as *const u32).read_unaligned() };
+///     // Only `lw`; other loads need their own width and extension.
+///     assert_eq!(insn & 0x707f, 0x2003, "unsupported load");
+///     let value = unsafe { (trap_value() as *const i32).read_unaligned() };
+///     tf.set_reg((insn as usize >> 7) & 0x1f, value as usize);
+///     unsafe { tf.skip_instruction() };
+/// }
+/// ```
+#[derive(Clone, Debug)]
 #[repr(C)]
 pub struct TrapFrame {
     /// Return address register.
@@ -48,4 +176,219 @@ pub struct TrapFrame {
     pub mepc: usize,
     /// Machine status register.
     pub mstatus: usize,
+    /// Stack pointer at the time of the trap.
+    sp: usize,
+    /// Global pointer register.
+    pub gp: usize,
+    /// Thread pointer register.
+    pub tp: usize,
+    /// Saved register 0, the frame pointer.
+    pub s0: usize,
+    /// Saved register 1.
+    pub s1: usize,
+    /// Saved register 2.
+    pub s2: usize,
+    /// Saved register 3.
+    pub s3: usize,
+    /// Saved register 4.
+    pub s4: usize,
+    /// Saved register 5.
+    pub s5: usize,
+    /// Saved register 6.
+    pub s6: usize,
+    /// Saved register 7.
+    pub s7: usize,
+    /// Saved register 8.
+    pub s8: usize,
+    /// Saved register 9.
+    pub s9: usize,
+    /// Saved register 10.
+    pub s10: usize,
+    /// Saved register 11.
+    pub s11: usize,
+}
+
+impl TrapFrame {
+    /// Address of the trapping instruction, or of the instruction to
+    /// resume at for interrupts.
+    #[inline]
+    pub fn pc(&self) -> usize {
+        self.mepc
+    }
+
+    /// Sets the address execution resumes at after the handler returns.
+    #[inline]
+    pub fn set_pc(&mut self, pc: usize) {
+        self.mepc = pc;
+    }
+
+    /// Return address register at the time of the trap.
+    #[inline]
+    pub fn ra(&self) -> usize {
+        self.ra
+    }
+
+    /// Stack pointer at the time of the trap.
+    ///
+    /// The frame itself may be on the trap stack rather than the
+    /// interrupted one.
+    #[inline]
+    pub fn sp(&self) -> usize {
+        self.sp
+    }
+
+    /// Argument register `a<n>`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is not below 8.
+    #[inline]
+    pub fn arg(&self, n: usize) -> usize {
+        match n {
+            0 => self.a0,
+            1 => self.a1,
+            2 => self.a2,
+            3 => self.a3,
+            4 => self.a4,
+            5 => self.a5,
+            6 => self.a6,
+            7 => self.a7,
+            _ => panic!("no argument register a{}", n),
+        }
+    }
+
+    /// Sets argument register `a<n>`, for example to return a value from an
+    /// emulated call in `a0`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is not below 8.
+    #[inline]
+    pub fn set_arg(&mut self, n: usize, value: usize) {
+        *self.arg_mut(n) = value;
+    }
+
+    fn arg_mut(&mut self, n: usize) -> &mut usize {
+        match n {
+            0 => &mut self.a0,
+            1 => &mut self.a1,
+            2 => &mut self.a2,
+            3 => &mut self.a3,
+            4 => &mut self.a4,
+            5 => &mut self.a5,
+            6 => &mut self.a6,
+            7 => &mut self.a7,
+            _ => panic!("no argument register a{}", n),
+        }
+    }
+
+    /// General purpose register `x<n>` at the time of the trap.
+    ///
+    /// `x0` reads as zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is not below 32.
+    #[inline]
+    pub fn reg(&self, n: usize) -> usize {
+        match n {
+            0 => 0,
+            2 => self.sp,
+            10..=17 => self.arg(n - 10),
+            n => *self.reg_ref(n),
+        }
+    }
+
+    /// Sets general purpose register `x<n>`, restored when the handler
+    /// returns. Writes to `x0` are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is not below 32, or if `n` is 2: the trap entry needs
+    /// the stack pointer to return to the interrupted stack.
+    #[inline]
+    pub fn set_reg(&mut self, n: usize, value: usize) {
+        match n {
+            0 => {}
+            2 => panic!("the stack pointer cannot be set from a trap"),
+            n => *self.reg_mut(n) = value,
+        }
+    }
+
+    fn reg_ref(&self, n: usize) -> &usize {
+        match n {
+            1 => &self.ra,
+            3 => &self.gp,
+            4 => &self.tp,
+            5 => &self.t0,
+            6 => &self.t1,
+            7 => &self.t2,
+            8 => &self.s0,
+            9 => &self.s1,
+            18 => &self.s2,
+            19 => &self.s3,
+            20 => &self.s4,
+            21 => &self.s5,
+            22 => &self.s6,
+            23 => &self.s7,
+            24 => &self.s8,
+            25 => &self.s9,
+            26 => &self.s10,
+            27 => &self.s11,
+            28 => &self.t3,
+            29 => &self.t4,
+            30 => &self.t5,
+            31 => &self.t6,
+            _ => panic!("no register x{}", n),
+        }
+    }
+
+    fn reg_mut(&mut self, n: usize) -> &mut usize {
+        match n {
+            1 => &mut self.ra,
+            3 => &mut self.gp,
+            4 => &mut self.tp,
+            5 => &mut self.t0,
+            6 => &mut self.t1,
+            7 => &mut self.t2,
+            8 => &mut self.s0,
+            9 => &mut self.s1,
+            10..=17 => self.arg_mut(n - 10),
+            18 => &mut self.s2,
+            19 => &mut self.s3,
+            20 => &mut self.s4,
+            21 => &mut self.s5,
+            22 => &mut self.s6,
+            23 => &mut self.s7,
+            24 => &mut self.s8,
+            25 => &mut self.s9,
+            26 => &mut self.s10,
+            27 => &mut self.s11,
+            28 => &mut self.t3,
+            29 => &mut self.t4,
+            30 => &mut self.t5,
+            31 => &mut self.t6,
+            _ => panic!("no register x{}", n),
+        }
+    }
+
+    /// Decodes the cause of the trap.
+    #[inline]
+    pub fn cause(&self) -> Trap {
+        Trap::from_mcause(self.mcause)
+    }
+
+    /// Resumes after the trapping instruction instead of retrying it.
+    ///
+    /// Used once an exception handler has emulated the instruction. Both
+    /// 16-bit compressed and 32-bit instructions are handled.
+    ///
+    /// # Safety
+    ///
+    /// The instruction at [`pc`](Self::pc) is read to find its length, so
+    /// the trap must not be an instruction access fault or page fault.
+    #[inline]
+    pub unsafe fn skip_instruction(&mut self) {
+        self.mepc += unsafe { instruction_len(self.mepc) };
+    }
 }
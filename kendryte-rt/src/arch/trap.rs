@@ -0,0 +1,335 @@
+//! Machine trap entry and vector table.
+//!
+//! In direct mode every trap enters [`trap_entry`], which saves a
+//! [`TrapFrame`] and decodes `mcause` to pick the handler. In vectored mode (`mtvec` MODE = 1) the core jumps to
+//! [`trap_vector`] plus four times the interrupt code, where a stub per
+//! cause calls its handler directly, so an interrupt does not pay for the
+//! decode. Exceptions always enter the first slot of the table, which is
+//! the direct mode entry.
+//!
+//! Handlers are found by symbol name and default to the runtime's:
+//!
+//! | Cause                    | Symbol            |
+//! |--------------------------|-------------------|
+//! | Machine software         | `MachineSoft`     |
+//! | Machine timer            | `MachineTimer`    |
+//! | Machine external         | `MachineExternal` |
+//! | Other interrupts         | `DefaultHandler`  |
+//! | Exceptions               | `exceptions`      |
+//!
+//! Define them with `#[interrupt]` and `#[exception]`. The default
+//! `MachineExternal` claims PLIC sources and runs the handlers registered in
+//! [`interrupt`](crate::interrupt). Floating point registers are not saved,
+//! so handlers must not use floating point arithmetic.
+//!
+//! The frame is saved on the interrupted stack, unless the hart has a trap
+//! stack, see [`Stack::install_trap_stack`]. A trap taken on another stack
+//! then switches to the trap stack first, and traps nested in a handler stay
+//! on it, so an exception raised by an overflowing stack is still handled.
+//! With `stack-guard` the runtime installs [`TRAP_STACK`] on hart 0.
+//!
+//! With the `irq-trace` feature every handler call is timed, see
+//! [`irq_trace`](crate::irq_trace).
+
+use crate::arch::rvi::{Stack, TrapFrame};
+use crate::interrupt::Trap;
+
+/// Stack space reserved for a trap, keeping `sp` 16-byte aligned.
+///
+/// The offsets in [`trap_common`] follow the field order of [`TrapFrame`].
+const FRAME_SIZE: usize = 272;
+
+const _: () = assert!(core::mem::size_of::<TrapFrame>() == 34 * 8);
+const _: () = assert!(FRAME_SIZE == core::mem::size_of::<TrapFrame>());
+
+/// Size of [`TRAP_STACK`] in bytes.
+pub const TRAP_STACK_SIZE: usize = 8 * 1024;
+
+/// Trap stack of hart 0, installed by the entry code with `stack-guard`.
+#[cfg(feature = "stack-guard")]
+#[unsafe(link_section = ".bss.uninit")]
+pub static mut TRAP_STACK: Stack<TRAP_STACK_SIZE> = Stack([0; TRAP_STACK_SIZE]);
+
+unsafe extern "C" {
+    fn MachineSoft();
+    fn MachineTimer();
+    fn MachineExternal();
+    fn DefaultHandler();
+    fn exceptions(frame: &mut TrapFrame);
+}
+
+macro_rules! trap_stub {
+    ($(#[$doc:meta])* $name:ident => $handler:path) => {
+        $(#[$doc])*
+        #[cfg(target_arch = "riscv64")]
+        #[unsafe(naked)]
+        #[unsafe(link_section = ".text.trap")]
+        pub unsafe extern "C" fn $name() -> ! {
+            core::arch::naked_asm!(
+                // Switch to the trap stack in mscratch, which leaves the
+                // interrupted stack pointer there. It is zero while already
+                // on the trap stack, or without one; then switch back.
+                "csrrw  sp, mscratch, sp
+                bnez    sp, 1f
+                csrrw   sp, mscratch, sp
+            1:",
+                // Free t0 to carry the handler to the common code.
+                "addi   sp, sp, -{frame_size}
+                sd      t0, 8(sp)
+                la      t0, {handler}
+                j       {common}",
+                frame_size = const FRAME_SIZE,
+                handler    = sym $handler,
+                common     = sym trap_common,
+            )
+        }
+    };
+}
+
+trap_stub! {
+    /// Direct mode trap entry, decoding `mcause` to find the handler.
+    trap_entry => trap_dispatch
+}
+trap_stub! {
+    /// Vectored entry of the machine software interrupt.
+    machine_soft_entry => MachineSoft
+}
+trap_stub! {
+    /// Vectored entry of the machine timer interrupt.
+    machine_timer_entry => MachineTimer
+}
+trap_stub! {
+    /// Vectored entry of the machine external interrupt.
+    machine_external_entry => MachineExternal
+}
+trap_stub! {
+    /// Vectored entry of every other interrupt.
+    default_entry => DefaultHandler
+}
+
+/// Vectored mode trap table, one jump per interrupt code.
+///
+/// The linker script aligns it to 64 bytes, as some cores require for a
+/// vectored `mtvec`.
+#[cfg(target_arch = "riscv64")]
+#[unsafe(naked)]
+#[unsafe(link_section = ".text.trap.vector")]
+pub unsafe extern "C" fn trap_vector() -> ! {
+    core::arch::naked_asm!(
+        // Every slot must be exactly four bytes.
+        ".option push
+        .option norvc",
+        // Exceptions and user software interrupt.
+        "j      {entry}",
+        // Supervisor software interrupt and reserved.
+        "j      {default}
+        j       {default}",
+        "j      {soft}",
+        // User and supervisor timer interrupts, reserved.
+        "j      {default}
+        j       {default}
+        j       {default}",
+        "j      {timer}",
+        // User and supervisor external interrupts, reserved.
+        "j      {default}
+        j       {default}
+        j       {default}",
+        "j      {external}",
+        // Reserved and platform local interrupts.
+        ".rept  20
+        j       {default}
+        .endr",
+        ".option pop",
+        entry    = sym trap_entry,
+        soft     = sym machine_soft_entry,
+        timer    = sym machine_timer_entry,
+        external = sym machine_external_entry,
+        default  = sym default_entry,
+    )
+}
+
+/// Save the general purpose registers, trap CSRs and interrupted stack
+/// pointer, call the handler in `t0` with the frame in `a0`, then restore
+/// and return from the trap.
+///
+/// Entered from a stub, which has reserved the frame and saved `t0`.
+#[cfg(target_arch = "riscv64")]
+#[unsafe(naked)]
+#[unsafe(link_section = ".text.trap")]
+unsafe extern "C" fn trap_common() -> ! {
+    core::arch::naked_asm!(
+        "sd     ra, 0(sp)
+        sd      t1, 16(sp)
+        sd      t2, 24(sp)
+        sd      a0, 32(sp)
+        sd      a1, 40(sp)
+        sd      a2, 48(sp)
+        sd      a3, 56(sp)
+        sd      a4, 64(sp)
+        sd      a5, 72(sp)
+        sd      a6, 80(sp)
+        sd      a7, 88(sp)
+        sd      t3, 96(sp)
+        sd      t4, 104(sp)
+        sd      t5, 112(sp)
+        sd      t6, 120(sp)",
+        // Handlers preserve the remaining registers; they are saved anyway so
+        // exception handlers can read and change them through the frame.
+        "sd     gp, 160(sp)
+        sd      tp, 168(sp)
+        sd      s0, 176(sp)
+        sd      s1, 184(sp)
+        sd      s2, 192(sp)
+        sd      s3, 200(sp)
+        sd      s4, 208(sp)
+        sd      s5, 216(sp)
+        sd      s6, 224(sp)
+        sd      s7, 232(sp)
+        sd      s8, 240(sp)
+        sd      s9, 248(sp)
+        sd      s10, 256(sp)
+        sd      s11, 264(sp)",
+        "csrr   t1, mcause
+        sd      t1, 128(sp)
+        csrr    t1, mepc
+        sd      t1, 136(sp)
+        csrr    t1, mstatus
+        sd      t1, 144(sp)",
+        // Coming from another stack, mscratch holds the interrupted stack
+        // pointer; clear it so nested traps stay on this stack. Otherwise
+        // the interrupted stack pointer is just above the frame.
+        "csrrw  t1, mscratch, zero
+        bnez    t1, 1f
+        addi    t1, sp, {frame_size}
+    1:  sd      t1, 152(sp)",
+        // With `irq-trace`, call the handler through the tracing hooks.
+        "mv     a0, sp
+        .if     {trace}
+        mv      a1, t0
+        call    {traced}
+        .else
+        jalr    t0
+        .endif",
+        // The handler may have moved the return address or, after nesting,
+        // changed the previous privilege and interrupt enable bits.
+        "ld     t1, 136(sp)
+        csrw    mepc, t1
+        ld      t1, 144(sp)
+        csrw    mstatus, t1",
+        // Leaving the trap stack for another one, hand the trap stack back
+        // to mscratch for the next trap.
+        "ld     t1, 152(sp)
+        addi    t2, sp, {frame_size}
+        beq     t1, t2, 2f
+        csrw    mscratch, t2
+    2:",
+        "ld     ra, 0(sp)
+        ld      t0, 8(sp)
+        ld      t1, 16(sp)
+        ld      t2, 24(sp)
+        ld      a0, 32(sp)
+        ld      a1, 40(sp)
+        ld      a2, 48(sp)
+        ld      a3, 56(sp)
+        ld      a4, 64(sp)
+        ld      a5, 72(sp)
+        ld      a6, 80(sp)
+        ld      a7, 88(sp)
+        ld      t3, 96(sp)
+        ld      t4, 104(sp)
+        ld      t5, 112(sp)
+        ld      t6, 120(sp)
+        ld      gp, 160(sp)
+        ld      tp, 168(sp)
+        ld      s0, 176(sp)
+        ld      s1, 184(sp)
+        ld      s2, 192(sp)
+        ld      s3, 200(sp)
+        ld      s4, 208(sp)
+        ld      s5, 216(sp)
+        ld      s6, 224(sp)
+        ld      s7, 232(sp)
+        ld      s8, 240(sp)
+        ld      s9, 248(sp)
+        ld      s10, 256(sp)
+        ld      s11, 264(sp)
+        ld      sp, 152(sp)
+        mret",
+        frame_size = const FRAME_SIZE,
+        trace      = const cfg!(feature = "irq-trace") as u8,
+        traced     = sym traced_call,
+    )
+}
+
+/// Call `handler` between the [`irq_trace`](crate::irq_trace) hooks.
+///
+/// Handlers taking no arguments ignore the frame passed in `a0`, as they do
+/// when called from [`trap_common`] directly.
+extern "C" fn traced_call(frame: &mut TrapFrame, handler: unsafe extern "C" fn(&mut TrapFrame)) {
+    #[cfg(feature = "irq-trace")]
+    {
+        let mcause = frame.mcause;
+        let entry = crate::irq_trace::enter(mcause);
+        unsafe { handler(frame) };
+        crate::irq_trace::exit(mcause, entry);
+    }
+    #[cfg(not(feature = "irq-trace"))]
+    unsafe {
+        handler(frame)
+    };
+}
+
+/// Direct mode dispatch on the decoded `mcause`.
+extern "C" fn trap_dispatch(frame: &mut TrapFrame) {
+    unsafe {
+        match frame.cause() {
+            Trap::Interrupt(3) => MachineSoft(),
+            Trap::Interrupt(7) => MachineTimer(),
+            Trap::Interrupt(11) => MachineExternal(),
+            Trap::Interrupt(_) => DefaultHandler(),
+            Trap::Exception(_) => exceptions(frame),
+        }
+    }
+}
+
+/// Default `MachineExternal`, dispatching PLIC sources to the handlers
+/// registered in [`interrupt`](crate::interrupt).
+#[doc(hidden)]
+#[unsafe(no_mangle)]
+pub extern "C" fn __kendryte_rt_default_external() {
+    #[cfg(any(feature = "k230", feature = "k210"))]
+    crate::interrupt::handle_external();
+    #[cfg(not(any(feature = "k230", feature = "k210")))]
+    unsafe {
+        DefaultHandler()
+    };
+}
+
+/// Default `DefaultHandler`, for interrupts nobody asked for.
+#[doc(hidden)]
+#[unsafe(no_mangle)]
+pub extern "C" fn __kendryte_rt_default_handler() {
+    let mcause: usize;
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    unsafe {
+        core::arch::asm!("csrr {}, mcause", out(reg) mcause, options(nomem, nostack))
+    };
+    #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+    {
+        mcause = 0;
+    }
+    panic!("unhandled interrupt {:?}", Trap::from_mcause(mcause))
+}
+
+/// Default `exceptions`, reporting the exception as a panic.
+#[doc(hidden)]
+#[unsafe(no_mangle)]
+pub extern "C" fn __kendryte_rt_default_exception(frame: &mut TrapFrame) {
+    crate::__check_stack_overflow();
+    panic!(
+        "unhandled exception {:?} at {:#x}, mtval {:#x}",
+        frame.cause(),
+        frame.pc(),
+        crate::interrupt::trap_value()
+    )
+}
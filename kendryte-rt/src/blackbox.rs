@@ -0,0 +1,87 @@
+//! Global flash log for events and the panic message.
+//!
+//! `main` opens a [`Blackbox`](kendryte_hal::blackbox::Blackbox), usually
+//! dumps what the previous run left in it, and registers it with
+//! [`set_blackbox`]. Afterwards [`log`] and [`log_fmt`] work from anywhere,
+//! and with the `panic-blackbox` feature the panic handler adds the panic
+//! message before printing it to the console:
+//!
+//! ```ignore
+//! static mut LOG: Option<Blackbox<Flash>> = None;
+//!
+//! let mut log = Blackbox::open(flash, REGION)?;
+//! log.dump(&mut uart)?;
+//! kendryte_rt::blackbox::set_blackbox(unsafe { (*addr_of_mut!(LOG)).insert(log) });
+//! ```
+//!
+//! Records before a log is registered are discarded. Writing to flash takes
+//! milliseconds when a sector has to be erased, so this is for rare events,
+//! not tracing.
+
+use crate::console::{disable_interrupts, restore_interrupts};
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+pub use kendryte_hal::blackbox::Kind;
+use kendryte_hal::blackbox::Log;
+
+struct Global {
+    locked: AtomicBool,
+    inner: UnsafeCell<Option<&'static mut dyn Log>>,
+}
+
+// SAFETY: access to `inner` is serialized by `locked` with interrupts disabled.
+unsafe impl Sync for Global {}
+
+static BLACKBOX: Global = Global {
+    locked: AtomicBool::new(false),
+    inner: UnsafeCell::new(None),
+};
+
+/// Register `log` as the global flash log, replacing any previous one.
+///
+/// The flash behind it must not be used elsewhere from then on.
+pub fn set_blackbox<L: Log + Send>(log: &'static mut L) {
+    with_blackbox(|slot| *slot = Some(log));
+}
+
+/// Remove the global flash log; subsequent records are discarded.
+pub fn clear_blackbox() {
+    with_blackbox(|slot| *slot = None);
+}
+
+/// Append a record to the global flash log.
+pub fn log(kind: Kind, payload: &[u8]) {
+    with_blackbox(|slot| {
+        if let Some(log) = slot {
+            log.log(kind, payload);
+        }
+    });
+}
+
+/// Append a record with formatted text to the global flash log.
+pub fn log_fmt(kind: Kind, args: fmt::Arguments) {
+    with_blackbox(|slot| {
+        if let Some(log) = slot {
+            log.log_fmt(kind, args);
+        }
+    });
+}
+
+/// Run `f` on the log slot with interrupts disabled.
+///
+/// If the log is already in use, e.g. a panic while writing a record, `f`
+/// is skipped instead of deadlocking.
+fn with_blackbox(f: impl FnOnce(&mut Option<&'static mut dyn Log>)) {
+    let mie = disable_interrupts();
+    if BLACKBOX
+        .locked
+        .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .is_ok()
+    {
+        // SAFETY: the lock grants exclusive access to the log slot.
+        f(unsafe { &mut *BLACKBOX.inner.get() });
+        BLACKBOX.locked.store(false, Ordering::Release);
+    }
+    restore_interrupts(mie);
+}
@@ -0,0 +1,88 @@
+//! Handing over to another image.
+//!
+//! A bootloader built on this runtime loads the next image, e.g. from an SD
+//! card or over UART, and calls [`jump`] to run it. The next image starts
+//! like it would from the boot ROM: interrupts off, caches coherent with
+//! what was written to memory, and no interrupt source left enabled.
+//!
+//! Peripherals the bootloader configured are reset by the `#[pre_jump]`
+//! hook, which runs before anything else is torn down, so it can still
+//! print and use drivers:
+//!
+//! ```ignore
+//! #[pre_jump]
+//! fn pre_jump() {
+//!     // Restore the pad and GPIO state saved on entry.
+//! }
+//!
+//! unsafe { kendryte_rt::boot::jump(load_addr, hart_id, dtb_addr) }
+//! ```
+
+unsafe extern "C" {
+    fn __pre_jump();
+}
+
+/// Jump to the image entry at `addr`, passing `arg0` and `arg1` in `a0`
+/// and `a1`.
+///
+/// Runs the `#[pre_jump]` hook and flushes the console, then disables
+/// interrupts, masks every interrupt source, stops the machine timer,
+/// writes back and invalidates the caches and jumps. The stack and trap
+/// vector are left for the next image to set up.
+///
+/// # Safety
+///
+/// `addr` must be the entry point of a complete image for this hart, and
+/// nothing of the current program may be used by it afterwards.
+pub unsafe fn jump(addr: usize, arg0: usize, arg1: usize) -> ! {
+    unsafe { __pre_jump() };
+    crate::console::flush();
+
+    crate::interrupt::disable();
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    unsafe {
+        core::arch::asm!("csrw mie, zero", options(nomem, nostack))
+    };
+    reset_interrupt_sources();
+
+    #[cfg(target_arch = "riscv64")]
+    unsafe {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "cpu-c908")] {
+                crate::arch::cpu_c908::flush_caches();
+            } else if #[cfg(feature = "cpu-andesv5")] {
+                crate::arch::cpu_andesv5::flush_caches();
+            } else if #[cfg(feature = "cpu-generic")] {
+                crate::arch::generic::flush_caches();
+            }
+        }
+        core::arch::asm!(
+            "jr     {addr}",
+            addr = in(reg) addr,
+            in("a0") arg0,
+            in("a1") arg1,
+            options(noreturn),
+        )
+    }
+    #[cfg(not(target_arch = "riscv64"))]
+    unreachable!("jump on a foreign architecture")
+}
+
+/// Disable every PLIC source and push the machine timer out of reach.
+fn reset_interrupt_sources() {
+    #[cfg(any(feature = "k230", feature = "k210"))]
+    {
+        use crate::interrupt::{MAX_INTERRUPTS, plic};
+        #[cfg(feature = "k210")]
+        use crate::soc::k210::CLINT_BASE;
+        #[cfg(feature = "k230")]
+        use crate::soc::k230::CLINT_BASE;
+
+        for irq in 1..MAX_INTERRUPTS {
+            plic::disable(irq);
+        }
+        plic::set_threshold(0);
+        let mtimecmp = (CLINT_BASE + 0x4000) as *mut u64;
+        unsafe { mtimecmp.write_volatile(u64::MAX) };
+    }
+}
@@ -0,0 +1,208 @@
+//! Global console for `print!` and `println!`.
+//!
+//! `main` registers any `embedded_io::Write`, usually a UART transmitter, with
+//! [`set_console`]. Afterwards the print macros work from anywhere, including
+//! interrupt handlers and panic handlers, without passing a handle around.
+//! Output before a console is registered is discarded.
+
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Object-safe view of an `embedded_io::Write` used as console.
+trait ConsoleWrite {
+    fn write_all(&mut self, buf: &[u8]);
+    fn flush(&mut self);
+}
+
+impl<W: embedded_io::Write> ConsoleWrite for W {
+    #[inline]
+    fn write_all(&mut self, buf: &[u8]) {
+        let _ = embedded_io::Write::write_all(self, buf);
+    }
+
+    #[inline]
+    fn flush(&mut self) {
+        let _ = embedded_io::Write::flush(self);
+    }
+}
+
+struct Console {
+    locked: AtomicBool,
+    inner: UnsafeCell<Option<&'static mut dyn ConsoleWrite>>,
+}
+
+// SAFETY: access to `inner` is serialized by `locked` with interrupts disabled.
+unsafe impl Sync for Console {}
+
+static CONSOLE: Console = Console {
+    locked: AtomicBool::new(false),
+    inner: UnsafeCell::new(None),
+};
+
+/// Register `writer` as the global console, replacing any previous one.
+///
+/// The writer is usually a UART transmitter placed in a `static`.
+pub fn set_console<W: embedded_io::Write + Send>(writer: &'static mut W) {
+    with_console(|console| *console = Some(writer));
+}
+
+/// Remove the global console; subsequent output is discarded.
+pub fn clear_console() {
+    with_console(|console| *console = None);
+}
+
+/// Returns true if a console has been registered.
+pub fn has_console() -> bool {
+    let mut present = false;
+    with_console(|console| present = console.is_some());
+    present
+}
+
+/// Flush the global console.
+pub fn flush() {
+    with_console(|console| {
+        if let Some(w) = console {
+            w.flush();
+        }
+    });
+}
+
+#[doc(hidden)]
+pub fn _print(args: fmt::Arguments) {
+    struct Adapter<'a>(&'a mut dyn ConsoleWrite);
+
+    impl fmt::Write for Adapter<'_> {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0.write_all(s.as_bytes());
+            Ok(())
+        }
+    }
+
+    with_console(|console| {
+        if let Some(w) = console {
+            let _ = fmt::Write::write_fmt(&mut Adapter(&mut **w), args);
+        }
+    });
+}
+
+/// Run `f` on the console with interrupts disabled.
+///
+/// If the console is already in use, e.g. a panic while printing or another
+/// hart printing at the same time, `f` is skipped instead of deadlocking.
+fn with_console(f: impl FnOnce(&mut Option<&'static mut dyn ConsoleWrite>)) {
+    let mie = disable_interrupts();
+    if CONSOLE
+        .locked
+        .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .is_ok()
+    {
+        // SAFETY: the lock grants exclusive access to the console slot.
+        f(unsafe { &mut *CONSOLE.inner.get() });
+        CONSOLE.locked.store(false, Ordering::Release);
+    }
+    restore_interrupts(mie);
+}
+
+/// Clear MIE in mstatus, returning whether it was set.
+#[inline]
+pub(crate) fn disable_interrupts() -> bool {
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    {
+        let mstatus: usize;
+        unsafe {
+            core::arch::asm!("csrrci {0}, mstatus, {mask}", out(reg) mstatus, mask = const 1 << 3, options(nostack));
+        }
+        mstatus & (1 << 3) != 0
+    }
+    #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+    false
+}
+
+/// Set MIE in mstatus again if it was set before [`disable_interrupts`].
+#[inline]
+pub(crate) fn restore_interrupts(mie: bool) {
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    if mie {
+        unsafe {
+            core::arch::asm!("csrrsi zero, mstatus, {mask}", mask = const 1 << 3, options(nostack));
+        }
+    }
+}
+
+/// Print to the global console.
+#[macro_export]
+macro_rules! print {
+    ($($arg:tt)*) => {
+        $crate::console::_print(format_args!($($arg)*))
+    };
+}
+
+/// Print to the global console, followed by `\r\n`.
+#[macro_export]
+macro_rules! println {
+    () => {
+        $crate::console::_print(format_args!("\r\n"))
+    };
+    ($($arg:tt)*) => {
+        $crate::console::_print(format_args!("{}\r\n", format_args!($($arg)*)))
+    };
+}
+
+/// Whether the `panic-watchdog` handler halts instead of resetting the chip.
+#[cfg(feature = "panic-watchdog")]
+static PANIC_HALT: AtomicBool = AtomicBool::new(cfg!(debug_assertions));
+
+/// Choose whether a panic halts the hart instead of resetting the chip.
+///
+/// With `panic-watchdog` the panic handler resets the chip through watchdog
+/// 0 once the message is out, so unattended devices recover. Debug builds
+/// halt by default instead, leaving the panicked state for a debugger; call
+/// this early in `main` to override the default either way.
+#[cfg(feature = "panic-watchdog")]
+pub fn set_panic_halt(halt: bool) {
+    PANIC_HALT.store(halt, Ordering::Relaxed);
+}
+
+/// Panic handler printing the panic message to the global console.
+///
+/// With `panic-blackbox` the message is added to the global flash log
+/// first, see [`blackbox`](crate::blackbox). With `panic-watchdog` the chip
+/// is reset afterwards, see [`set_panic_halt`].
+#[cfg(any(
+    feature = "panic-console",
+    feature = "panic-blackbox",
+    feature = "panic-watchdog"
+))]
+#[panic_handler]
+fn panic(info: &core::panic::PanicInfo) -> ! {
+    // A record holds little more than a line, so log the file name and line
+    // first and drop the `panicked at` prefix and directories.
+    #[cfg(feature = "panic-blackbox")]
+    match info.location() {
+        Some(location) => {
+            let file = location.file();
+            let file = file.rsplit(['/', '\\']).next().unwrap_or(file);
+            crate::blackbox::log_fmt(
+                crate::blackbox::Kind::Panic,
+                format_args!("{}:{} {}", file, location.line(), info.message()),
+            );
+        }
+        None => crate::blackbox::log_fmt(
+            crate::blackbox::Kind::Panic,
+            format_args!("{}", info.message()),
+        ),
+    }
+    crate::println!("{}", info);
+    flush();
+    #[cfg(all(feature = "panic-watchdog", feature = "k230"))]
+    if !PANIC_HALT.load(Ordering::Relaxed) {
+        // SAFETY: another driver of the watchdog can at most restart the
+        // count once more before the chip resets.
+        let wdt0 = unsafe { crate::soc::k230::WDT0::steal() };
+        kendryte_hal::wdt::Watchdog::new(wdt0).reset();
+    }
+    loop {
+        core::hint::spin_loop();
+    }
+}
@@ -0,0 +1,74 @@
+//! `critical-section` implementation for every hart.
+//!
+//! Entering a critical section masks machine interrupts on the current hart
+//! and takes a spinlock against the other harts. A nested section finds the
+//! lock already held by its own hart and leaves it to the outermost one, so
+//! libraries built on `critical_section::with` may call each other freely:
+//!
+//! ```ignore
+//! use core::cell::RefCell;
+//! use critical_section::Mutex;
+//! use kendryte_hal::uart::BlockingUartTx;
+//!
+//! static LOG: Mutex<RefCell<Option<BlockingUartTx<'static, 'static>>>> =
+//!     Mutex::new(RefCell::new(None));
+//!
+//! critical_section::with(|cs| {
+//!     if let Some(tx) = LOG.borrow_ref_mut(cs).as_mut() {
+//!         let _ = embedded_io::Write::write_all(tx, b"tick\r\n");
+//!     }
+//! });
+//! ```
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use critical_section::RawRestoreState;
+
+/// Value of [`OWNER`] while no hart holds the lock.
+const FREE: usize = usize::MAX;
+
+/// Hart holding the lock, or [`FREE`].
+static OWNER: AtomicUsize = AtomicUsize::new(FREE);
+
+/// Restore state bit: machine interrupts were enabled on entry.
+const MIE_WAS_SET: u8 = 1 << 0;
+/// Restore state bit: this section took the lock and releases it.
+const LOCK_TAKEN: u8 = 1 << 1;
+
+struct HartLock;
+
+critical_section::set_impl!(HartLock);
+
+unsafe impl critical_section::Impl for HartLock {
+    unsafe fn acquire() -> RawRestoreState {
+        let (mstatus, hart): (usize, usize);
+        unsafe {
+            core::arch::asm!("csrrci {}, mstatus, 8", out(reg) mstatus, options(nostack));
+            core::arch::asm!("csrr {}, mhartid", out(reg) hart, options(nomem, nostack));
+        }
+        let mut state = match mstatus & 8 {
+            0 => 0,
+            _ => MIE_WAS_SET,
+        };
+        // Only this hart stores its own id, so with interrupts masked a
+        // match means an enclosing section on this hart holds the lock.
+        if OWNER.load(Ordering::Relaxed) != hart {
+            while OWNER
+                .compare_exchange_weak(FREE, hart, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+            {
+                core::hint::spin_loop();
+            }
+            state |= LOCK_TAKEN;
+        }
+        state
+    }
+
+    unsafe fn release(state: RawRestoreState) {
+        if state & LOCK_TAKEN != 0 {
+            OWNER.store(FREE, Ordering::Release);
+        }
+        if state & MIE_WAS_SET != 0 {
+            unsafe { core::arch::asm!("csrsi mstatus, 8", options(nostack)) };
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! Embassy time driver and interrupt mode executor.
+//!
+//! With the `embassy` feature the runtime provides the embassy-time driver,
+//! counting microseconds derived from the machine timer and waking timers
+//! from its interrupt, so `embassy_time::Timer` works without further setup.
+//! The machine timer interrupt is taken over by the driver and must not be
+//! defined with `#[interrupt]`.
+//!
+//! [`InterruptExecutor`] polls its tasks from the machine software
+//! interrupt, which a task being woken raises. Tasks thus preempt the main
+//! loop, while being preempted themselves by external interrupt handlers:
+//!
+//! ```ignore
+//! static EXECUTOR: InterruptExecutor = InterruptExecutor::new();
+//!
+//! #[interrupt]
+//! fn MachineSoft() {
+//!     unsafe { EXECUTOR.on_interrupt() }
+//! }
+//!
+//! #[entry]
+//! fn main(p: Peripherals, c: Clocks) -> ! {
+//!     let spawner = EXECUTOR.start();
+//!     spawner.spawn(blink(led)).unwrap();
+//!     kendryte_rt::interrupt::enable();
+//!     loop {
+//!         kendryte_rt::idle();
+//!     }
+//! }
+//! ```
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::Waker;
+use embassy_executor::{SendSpawner, raw};
+use embassy_time_driver::{Driver, TICK_HZ};
+use embassy_time_queue_utils::Queue;
+use kendryte_hal::soc::TIMER_FREQUENCY;
+use kendryte_hal::sync::Mutex;
+use kendryte_hal::time::now;
+
+#[cfg(feature = "k210")]
+use crate::soc::k210::CLINT_BASE;
+#[cfg(feature = "k230")]
+use crate::soc::k230::CLINT_BASE;
+
+/// `msip` of hart 0.
+const MSIP: *mut u32 = CLINT_BASE as *mut u32;
+/// `mtimecmp` of hart 0.
+const MTIMECMP: *mut u64 = (CLINT_BASE + 0x4000) as *mut u64;
+
+/// Machine software interrupt enable bit of `mie`.
+const MSIE: usize = 1 << 3;
+/// Machine timer interrupt enable bit of `mie`.
+const MTIE: usize = 1 << 7;
+
+/// Converts machine timer ticks to embassy ticks.
+fn to_embassy(ticks: u64) -> u64 {
+    (ticks as u128 * TICK_HZ as u128 / TIMER_FREQUENCY as u128) as u64
+}
+
+/// Converts embassy ticks to machine timer ticks, rounding up.
+fn to_mtime(ticks: u64) -> u64 {
+    let mtime = (ticks as u128 * TIMER_FREQUENCY as u128).div_ceil(TICK_HZ as u128);
+    mtime.min(u64::MAX as u128) as u64
+}
+
+struct MtimeDriver {
+    queue: Mutex<Queue>,
+}
+
+impl MtimeDriver {
+    /// Programs `mtimecmp` for `at`, in embassy ticks.
+    ///
+    /// Returns false if `at` has already passed, in which case the queue
+    /// must be processed again.
+    fn set_alarm(&self, at: u64) -> bool {
+        let compare = to_mtime(at);
+        unsafe { MTIMECMP.write_volatile(compare) };
+        compare == u64::MAX || now() < compare
+    }
+
+    fn rearm(&self, queue: &mut Queue) {
+        let mut next = queue.next_expiration(self.now());
+        while !self.set_alarm(next) {
+            next = queue.next_expiration(self.now());
+        }
+    }
+
+    fn on_interrupt(&self) {
+        self.queue.lock(|queue| {
+            unsafe { MTIMECMP.write_volatile(u64::MAX) };
+            self.rearm(queue);
+        });
+    }
+}
+
+impl Driver for MtimeDriver {
+    fn now(&self) -> u64 {
+        to_embassy(now())
+    }
+
+    fn schedule_wake(&self, at: u64, waker: &Waker) {
+        self.queue.lock(|queue| {
+            if queue.schedule_wake(at, waker) {
+                self.rearm(queue);
+            }
+        });
+    }
+}
+
+embassy_time_driver::time_driver_impl!(static DRIVER: MtimeDriver = MtimeDriver {
+    queue: Mutex::new(Queue::new()),
+});
+
+/// Enables the machine timer interrupt driving embassy timers.
+///
+/// Called by [`InterruptExecutor::start`]; call it directly when running
+/// another executor.
+pub fn init_time_driver() {
+    unsafe {
+        MTIMECMP.write_volatile(u64::MAX);
+        core::arch::asm!("csrs mie, {}", in(reg) MTIE, options(nomem, nostack));
+    }
+}
+
+/// Wakes the embassy timers that expired.
+#[doc(hidden)]
+#[unsafe(no_mangle)]
+pub extern "C" fn MachineTimer() {
+    DRIVER.on_interrupt();
+}
+
+/// Raises the machine software interrupt to have the executor polled.
+#[unsafe(export_name = "__pender")]
+fn pender(_context: *mut ()) {
+    unsafe { MSIP.write_volatile(1) };
+}
+
+/// Executor polling its tasks from the machine software interrupt of hart 0.
+pub struct InterruptExecutor {
+    started: AtomicBool,
+    executor: UnsafeCell<MaybeUninit<raw::Executor>>,
+}
+
+// SAFETY: the executor is only written once by `start`, guarded by `started`.
+unsafe impl Sync for InterruptExecutor {}
+
+impl InterruptExecutor {
+    /// Creates an executor that has not been started.
+    pub const fn new() -> Self {
+        Self {
+            started: AtomicBool::new(false),
+            executor: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Starts the executor and returns a spawner for its tasks.
+    ///
+    /// Enables the machine software and timer interrupts; tasks run once
+    /// machine interrupts are enabled globally.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the executor was already started.
+    pub fn start(&'static self) -> SendSpawner {
+        if self.started.swap(true, Ordering::AcqRel) {
+            panic!("InterruptExecutor::start called twice");
+        }
+        let executor = raw::Executor::new(core::ptr::null_mut());
+        let executor = unsafe { (*self.executor.get()).write(executor) };
+        init_time_driver();
+        unsafe {
+            core::arch::asm!("csrs mie, {}", in(reg) MSIE, options(nomem, nostack));
+            MSIP.write_volatile(1);
+        }
+        executor.spawner().make_send()
+    }
+
+    /// Polls the tasks; call from the `MachineSoft` handler.
+    ///
+    /// # Safety
+    ///
+    /// Must only be called from the machine software interrupt handler.
+    pub unsafe fn on_interrupt(&'static self) {
+        unsafe { MSIP.write_volatile(0) };
+        if !self.started.load(Ordering::Acquire) {
+            return;
+        }
+        unsafe { (*self.executor.get()).assume_init_ref().poll() };
+    }
+}
+
+impl Default for InterruptExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
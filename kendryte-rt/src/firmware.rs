@@ -0,0 +1,47 @@
+//! Metadata of the running firmware.
+//!
+//! The runtime reserves space for the firmware metadata trailer at the end of
+//! the loaded image, in the `.trailer` section between `.data` and `.bss`.
+//! `cargo xtask gen-image --fw-version <VERSION>` fills it in, see
+//! [`kendryte_hal::firmware`] for the format:
+//!
+//! ```ignore
+//! if let Some(info) = kendryte_rt::firmware::info() {
+//!     println!("firmware {} ({})", info.version, info.git_hash_hex());
+//! }
+//! ```
+
+use kendryte_hal::firmware::{FirmwareInfo, RESERVED_TRAILER, TRAILER_LEN};
+
+/// Space for the trailer, replaced by `cargo xtask gen-image --fw-version`.
+#[used]
+#[unsafe(link_section = ".trailer")]
+static TRAILER: [u8; TRAILER_LEN] = RESERVED_TRAILER;
+
+unsafe extern "C" {
+    static stext: u8;
+    static etrailer: u8;
+}
+
+/// The loaded image, from the first instruction up to the end of the trailer.
+///
+/// `.data` is part of the image and runs in place, so once statics have been
+/// written the image no longer matches its trailer checksums; check the
+/// payload with [`FirmwareInfo::verify`] before that, or on a copy read from
+/// storage.
+pub fn image() -> &'static [u8] {
+    // SAFETY: the linker script places `.trailer` after the other loaded
+    // sections of the same memory region, starting at `stext`. The bytes are
+    // read through the linker symbols so the compiler cannot assume they
+    // still hold `RESERVED_TRAILER`.
+    unsafe {
+        let start = core::ptr::addr_of!(stext);
+        let end = core::ptr::addr_of!(etrailer);
+        core::slice::from_raw_parts(start, end as usize - start as usize)
+    }
+}
+
+/// Metadata of the running firmware, or `None` if the trailer was not filled in.
+pub fn info() -> Option<FirmwareInfo> {
+    FirmwareInfo::parse(image())
+}
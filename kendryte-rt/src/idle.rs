@@ -0,0 +1,67 @@
+//! Idle hook and CPU load measurement.
+//!
+//! A main loop with nothing left to do calls [`idle`], which runs the idle
+//! hook, `wfi` unless replaced with `#[idle]`. Machine timer ticks spent in
+//! the hook are accumulated, so [`cpu_load`] reports how busy the core was
+//! since the last [`reset_cpu_load`], and with it how much headroom the
+//! blocking drivers leave.
+//!
+//! Interrupt handlers that run while the hook waits are counted as idle
+//! time, so the load covers the code calling [`idle`] only.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+use kendryte_hal::time::now;
+
+// Idle hook, defined with `#[idle]`. The linker script falls back to
+// `__kendryte_rt_default_idle` if missing.
+unsafe extern "C" {
+    fn __idle();
+}
+
+/// Start of the measurement window, in machine timer ticks.
+static WINDOW_START: AtomicU64 = AtomicU64::new(0);
+/// Ticks spent in the idle hook since the window started.
+static IDLE_TICKS: AtomicU64 = AtomicU64::new(0);
+
+/// CPU usage over a measurement window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CpuLoad {
+    /// Machine timer ticks spent outside the idle hook.
+    pub busy_ticks: u64,
+    /// Length of the window in machine timer ticks.
+    pub total_ticks: u64,
+}
+
+impl CpuLoad {
+    /// Load as a percentage from 0 to 100.
+    pub fn percent(&self) -> u8 {
+        if self.total_ticks == 0 {
+            return 0;
+        }
+        (self.busy_ticks.min(self.total_ticks) * 100 / self.total_ticks) as u8
+    }
+}
+
+/// Run the idle hook once, accounting the time spent in it as idle.
+#[inline]
+pub fn idle() {
+    let start = now();
+    unsafe { __idle() };
+    IDLE_TICKS.fetch_add(now().wrapping_sub(start), Ordering::Relaxed);
+}
+
+/// CPU load since the last [`reset_cpu_load`], or since boot.
+pub fn cpu_load() -> CpuLoad {
+    let total_ticks = now().wrapping_sub(WINDOW_START.load(Ordering::Relaxed));
+    let idle_ticks = IDLE_TICKS.load(Ordering::Relaxed);
+    CpuLoad {
+        busy_ticks: total_ticks.saturating_sub(idle_ticks),
+        total_ticks,
+    }
+}
+
+/// Start a new measurement window.
+pub fn reset_cpu_load() {
+    IDLE_TICKS.store(0, Ordering::Relaxed);
+    WINDOW_START.store(now(), Ordering::Relaxed);
+}
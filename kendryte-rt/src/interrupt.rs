@@ -1,8 +1,9 @@
 //! Basic interrupt and exception handling framework (initial minimal version).
 //!
 //! This is an MVP implementation: a fixed-size table of interrupt handlers
-//! that can be registered at runtime. A real implementation may switch to
-//! linker-time collection or vector mode mtvec.
+//! that can be registered at runtime. Traps reach it through the entry code
+//! in [`arch::trap`](crate::arch::trap), in direct or vectored mode, see
+//! [`set_trap_mode`].
 
 #![allow(dead_code)]
 
@@ -35,6 +36,24 @@ pub unsafe fn register(irq: usize, handler: IrqHandler) {
 	if irq < MAX_INTERRUPTS { unsafe { IRQ_TABLE[irq] = Some(handler); } }
 }
 
+/// Register `handler` for the interrupt source of peripheral `P` and enable
+/// the source on the PLIC.
+///
+/// Returns `false`, registering nothing, if the interrupt source of `P` is
+/// not known.
+#[cfg(any(feature = "k230", feature = "k210"))]
+pub fn register_peripheral<P: kendryte_hal::instance::PeripheralInfo>(handler: IrqHandler) -> bool {
+	let Some(irq) = P::IRQ else {
+		return false;
+	};
+	unsafe { register(irq, handler) };
+	if plic::priority(irq) == 0 {
+		plic::set_priority(irq, 1);
+	}
+	plic::enable(irq);
+	true
+}
+
 /// Dispatch an interrupt number (called from trap trampoline).
 pub(crate) fn dispatch_irq(irq: usize) {
 	unsafe {
@@ -48,8 +67,8 @@ pub(crate) fn dispatch_irq(irq: usize) {
 pub fn software_trigger(irq: usize) { dispatch_irq(irq); }
 
 /// Called for unhandled exceptions (placeholder). Users can implement an
-/// `#[exception] fn exceptions(tf: &mut TrapFrame)`; and assembly side will
-/// call symbol `exceptions` if present.
+/// `#[exception] fn exceptions(tf: &mut TrapFrame)`; the trap entry calls
+/// symbol `exceptions` for every exception.
 #[inline(always)]
 pub fn unhandled_exception() -> ! { loop { core::hint::spin_loop(); } }
 
@@ -59,3 +78,293 @@ pub fn enable() {
 		core::arch::asm!("csrrs zero, mstatus, {mask}", mask = const 1 << 3, options(nostack, preserves_flags));
 	}
 }
+
+/// Disable global machine interrupts (clear MIE in mstatus).
+pub fn disable() {
+	unsafe {
+		core::arch::asm!("csrrc zero, mstatus, {mask}", mask = const 1 << 3, options(nostack, preserves_flags));
+	}
+}
+
+/// Enable machine external interrupts from the PLIC (set MEIE in mie).
+pub fn enable_external() {
+	unsafe {
+		core::arch::asm!("csrrs zero, mie, {mask}", mask = const 1 << 11, options(nostack, preserves_flags));
+	}
+}
+
+/// How traps find their handler, selected by the MODE field of `mtvec`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrapMode {
+	/// Every trap enters one entry, which decodes `mcause`.
+	Direct,
+	/// Each interrupt enters its own stub, which calls its handler directly.
+	Vectored,
+}
+
+/// Point `mtvec` at the runtime trap entry in `mode`.
+///
+/// The entry code calls this on startup, with [`TrapMode::Vectored`] if the
+/// `vectored-interrupts` feature is enabled. Cores without vectored mode,
+/// such as the K210 cores, keep trapping to the first slot of the vector
+/// table, which is the direct entry.
+pub fn set_trap_mode(mode: TrapMode) {
+	#[cfg(target_arch = "riscv64")]
+	{
+		let mtvec = match mode {
+			TrapMode::Direct => crate::arch::trap::trap_entry as usize,
+			TrapMode::Vectored => crate::arch::trap::trap_vector as usize | 1,
+		};
+		unsafe { core::arch::asm!("csrw mtvec, {}", in(reg) mtvec, options(nomem, nostack)) };
+	}
+}
+
+/// Current trap mode, read from `mtvec`.
+pub fn trap_mode() -> TrapMode {
+	let mtvec: usize;
+	#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+	unsafe {
+		core::arch::asm!("csrr {}, mtvec", out(reg) mtvec, options(nomem, nostack))
+	};
+	#[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+	{
+		mtvec = 0;
+	}
+	if mtvec & 0b11 == 1 { TrapMode::Vectored } else { TrapMode::Direct }
+}
+
+/// Platform-level interrupt controller, as seen by hart 0 in machine mode.
+///
+/// A source interrupts only while its priority is above the threshold, so
+/// priorities order sources against each other and the threshold masks the
+/// levels a running handler must not be preempted by.
+#[cfg(any(feature = "k230", feature = "k210"))]
+pub mod plic {
+	#[cfg(feature = "k230")]
+	use crate::soc::k230::PLIC_BASE;
+	#[cfg(feature = "k210")]
+	use crate::soc::k210::PLIC_BASE;
+
+	/// Highest priority level. Sources at priority 0 never interrupt.
+	#[cfg(feature = "k230")]
+	pub const MAX_PRIORITY: u8 = 31;
+	/// Highest priority level. Sources at priority 0 never interrupt.
+	#[cfg(feature = "k210")]
+	pub const MAX_PRIORITY: u8 = 7;
+
+	const PRIORITY: usize = PLIC_BASE;
+	const ENABLE: usize = PLIC_BASE + 0x2000;
+	const THRESHOLD: usize = PLIC_BASE + 0x20_0000;
+	const CLAIM: usize = PLIC_BASE + 0x20_0004;
+
+	#[inline]
+	fn reg(addr: usize) -> *mut u32 {
+		addr as *mut u32
+	}
+
+	/// Set the priority of source `irq`, clamped to [`MAX_PRIORITY`].
+	pub fn set_priority(irq: usize, priority: u8) {
+		unsafe { reg(PRIORITY + irq * 4).write_volatile(priority.min(MAX_PRIORITY) as u32) };
+	}
+
+	/// Priority of source `irq`.
+	pub fn priority(irq: usize) -> u8 {
+		unsafe { reg(PRIORITY + irq * 4).read_volatile() as u8 }
+	}
+
+	/// Allow source `irq` to interrupt hart 0.
+	pub fn enable(irq: usize) {
+		let r = reg(ENABLE + irq / 32 * 4);
+		unsafe { r.write_volatile(r.read_volatile() | 1 << (irq % 32)) };
+	}
+
+	/// Stop source `irq` from interrupting hart 0.
+	pub fn disable(irq: usize) {
+		let r = reg(ENABLE + irq / 32 * 4);
+		unsafe { r.write_volatile(r.read_volatile() & !(1 << (irq % 32))) };
+	}
+
+	/// Current priority threshold.
+	pub fn threshold() -> u8 {
+		unsafe { reg(THRESHOLD).read_volatile() as u8 }
+	}
+
+	/// Mask sources with a priority at or below `threshold`.
+	pub fn set_threshold(threshold: u8) {
+		unsafe { reg(THRESHOLD).write_volatile(threshold.min(MAX_PRIORITY) as u32) };
+	}
+
+	/// Claim the highest priority pending source, if any.
+	pub fn claim() -> Option<usize> {
+		match unsafe { reg(CLAIM).read_volatile() } {
+			0 => None,
+			irq => Some(irq as usize),
+		}
+	}
+
+	/// Signal that the handler of a claimed source has finished.
+	pub fn complete(irq: usize) {
+		unsafe { reg(CLAIM).write_volatile(irq as u32) };
+	}
+}
+
+/// Run `f` with interrupts of a priority above `priority` allowed to preempt it.
+///
+/// Saves the trap state that a nested trap overwrites, raises the PLIC
+/// threshold to `priority` and re-enables MIE for the duration of `f`, then
+/// restores everything, so `f` runs like the rest of the handler but can be
+/// interrupted by more urgent sources. A motor control PWM handler at a high
+/// priority can thus preempt UART logging running in a low priority one.
+///
+/// Handlers run with interrupts disabled unless they call this, so a handler
+/// opts in to preemption only for the part of it that is safe to interrupt.
+/// The machine timer and software interrupts are not gated by the PLIC
+/// threshold and can preempt `f` whatever `priority` is.
+///
+/// `mepc` and `mstatus` are restored, but `mcause` and `mtval` are not:
+/// read them before calling this if the handler needs them.
+///
+/// Must be called from an interrupt handler. The trap entry has to save its
+/// frame on the interrupted stack, so that nested traps do not overwrite it.
+#[cfg(any(feature = "k230", feature = "k210"))]
+pub fn nested<R>(priority: u8, f: impl FnOnce() -> R) -> R {
+	let (mepc, mstatus): (usize, usize);
+	unsafe {
+		core::arch::asm!("csrr {}, mepc", out(reg) mepc, options(nomem, nostack));
+		core::arch::asm!("csrr {}, mstatus", out(reg) mstatus, options(nomem, nostack));
+	}
+	let threshold = plic::threshold();
+	plic::set_threshold(priority.max(threshold));
+	enable();
+	let result = f();
+	disable();
+	plic::set_threshold(threshold);
+	unsafe {
+		core::arch::asm!("csrw mepc, {}", in(reg) mepc, options(nomem, nostack));
+		core::arch::asm!("csrw mstatus, {}", in(reg) mstatus, options(nomem, nostack));
+	}
+	result
+}
+
+/// Claim, dispatch and complete pending external interrupts.
+///
+/// Call from the machine external interrupt handler. Registered handlers
+/// run with interrupts disabled; a handler that may be preempted by more
+/// urgent sources calls [`nested`] itself.
+#[cfg(any(feature = "k230", feature = "k210"))]
+pub fn handle_external() {
+	while let Some(irq) = plic::claim() {
+		dispatch_irq(irq);
+		plic::complete(irq);
+	}
+}
+
+/// Synchronous exception cause, decoded from `mcause`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExceptionCause {
+	/// Instruction address misaligned.
+	InstructionMisaligned,
+	/// Instruction access fault.
+	InstructionFault,
+	/// Illegal instruction.
+	IllegalInstruction,
+	/// Breakpoint, raised by `ebreak`.
+	Breakpoint,
+	/// Load address misaligned.
+	LoadMisaligned,
+	/// Load access fault.
+	LoadFault,
+	/// Store or AMO address misaligned.
+	StoreMisaligned,
+	/// Store or AMO access fault.
+	StoreFault,
+	/// Environment call from U-mode.
+	UserEnvCall,
+	/// Environment call from S-mode.
+	SupervisorEnvCall,
+	/// Environment call from M-mode.
+	MachineEnvCall,
+	/// Instruction page fault.
+	InstructionPageFault,
+	/// Load page fault.
+	LoadPageFault,
+	/// Store or AMO page fault.
+	StorePageFault,
+	/// Reserved or custom cause code.
+	Unknown(usize),
+}
+
+impl ExceptionCause {
+	/// Decodes an exception code, the `mcause` value without the interrupt bit.
+	pub const fn from_code(code: usize) -> Self {
+		match code {
+			0 => Self::InstructionMisaligned,
+			1 => Self::InstructionFault,
+			2 => Self::IllegalInstruction,
+			3 => Self::Breakpoint,
+			4 => Self::LoadMisaligned,
+			5 => Self::LoadFault,
+			6 => Self::StoreMisaligned,
+			7 => Self::StoreFault,
+			8 => Self::UserEnvCall,
+			9 => Self::SupervisorEnvCall,
+			11 => Self::MachineEnvCall,
+			12 => Self::InstructionPageFault,
+			13 => Self::LoadPageFault,
+			15 => Self::StorePageFault,
+			code => Self::Unknown(code),
+		}
+	}
+
+	/// Returns true if [`trap_value`] holds a faulting data or instruction address.
+	pub const fn has_address(self) -> bool {
+		matches!(
+			self,
+			Self::InstructionMisaligned
+				| Self::InstructionFault
+				| Self::LoadMisaligned
+				| Self::LoadFault
+				| Self::StoreMisaligned
+				| Self::StoreFault
+				| Self::InstructionPageFault
+				| Self::LoadPageFault
+				| Self::StorePageFault
+		)
+	}
+}
+
+/// Decoded `mcause` register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trap {
+	/// Asynchronous interrupt with its interrupt code.
+	Interrupt(usize),
+	/// Synchronous exception.
+	Exception(ExceptionCause),
+}
+
+impl Trap {
+	/// Decodes a raw `mcause` value.
+	pub const fn from_mcause(mcause: usize) -> Self {
+		const INTERRUPT: usize = 1 << (usize::BITS - 1);
+		if mcause & INTERRUPT != 0 {
+			Self::Interrupt(mcause & !INTERRUPT)
+		} else {
+			Self::Exception(ExceptionCause::from_code(mcause))
+		}
+	}
+}
+
+/// Read `mtval`, the faulting address or instruction of the current trap.
+///
+/// Only meaningful in an exception handler, before anything else can trap.
+#[inline]
+pub fn trap_value() -> usize {
+	#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+	{
+		let value: usize;
+		unsafe { core::arch::asm!("csrr {}, mtval", out(reg) value, options(nomem, nostack)) };
+		value
+	}
+	#[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+	0
+}
@@ -0,0 +1,276 @@
+//! Trap duration and latency tracing.
+//!
+//! With the `irq-trace` feature the trap entry times every handler it
+//! calls. Each trap leaves a [`Record`] in a ring buffer of the last
+//! [`CAPACITY`] traps and is folded into per-cause [`Stats`], so a handler
+//! that runs long enough to overrun a UART FIFO or delay a PWM update shows
+//! up without a debugger:
+//!
+//! ```ignore
+//! kendryte_rt::irq_trace::reset();
+//! // ... run the workload ...
+//! kendryte_rt::irq_trace::dump(&mut console)?;
+//! ```
+//!
+//! Times are in machine timer ticks, see [`TIMER_FREQUENCY`]. The duration
+//! covers the handler only, not saving and restoring the trap frame. For
+//! machine timer interrupts the latency from `mtimecmp` to the handler is
+//! recorded as well; other causes have no reference point and report none.
+//! A trap nested in another handler is recorded before the one it
+//! preempted, whose duration includes it.
+
+use crate::interrupt::Trap;
+use core::fmt;
+use kendryte_hal::soc::TIMER_FREQUENCY;
+use kendryte_hal::sync::Mutex;
+use kendryte_hal::time::now;
+
+#[cfg(feature = "k210")]
+use crate::soc::k210::CLINT_BASE;
+#[cfg(feature = "k230")]
+use crate::soc::k230::CLINT_BASE;
+
+/// Number of traps kept in the ring buffer.
+pub const CAPACITY: usize = 64;
+
+/// Interrupt codes with statistics of their own; higher codes share the last.
+const INTERRUPT_SLOTS: usize = 16;
+
+/// One traced trap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Record {
+    /// Raw `mcause` of the trap.
+    pub mcause: usize,
+    /// Machine timer value when the handler was called.
+    pub start: u64,
+    /// Ticks spent in the handler.
+    pub duration: u64,
+    /// Ticks from the interrupt becoming pending to the handler being called,
+    /// if known.
+    pub latency: Option<u64>,
+}
+
+impl Record {
+    /// Decodes the cause of the trap.
+    #[inline]
+    pub fn cause(&self) -> Trap {
+        Trap::from_mcause(self.mcause)
+    }
+}
+
+const EMPTY_RECORD: Record = Record {
+    mcause: 0,
+    start: 0,
+    duration: 0,
+    latency: None,
+};
+
+/// Accumulated statistics of one trap cause.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Number of traps.
+    pub count: u32,
+    /// Ticks spent in the handler over all traps.
+    pub total_ticks: u64,
+    /// Longest time spent in the handler, in ticks.
+    pub max_ticks: u64,
+    /// Longest known latency, in ticks.
+    pub max_latency: u64,
+}
+
+impl Stats {
+    const EMPTY: Self = Self {
+        count: 0,
+        total_ticks: 0,
+        max_ticks: 0,
+        max_latency: 0,
+    };
+
+    /// Mean time spent in the handler, in ticks.
+    pub fn mean_ticks(&self) -> u64 {
+        match self.count {
+            0 => 0,
+            count => self.total_ticks / count as u64,
+        }
+    }
+
+    fn add(&mut self, record: &Record) {
+        self.count = self.count.saturating_add(1);
+        self.total_ticks = self.total_ticks.saturating_add(record.duration);
+        self.max_ticks = self.max_ticks.max(record.duration);
+        self.max_latency = self.max_latency.max(record.latency.unwrap_or(0));
+    }
+}
+
+struct Trace {
+    records: [Record; CAPACITY],
+    /// Slot the next record is written to.
+    next: usize,
+    /// Number of valid records, up to `CAPACITY`.
+    len: usize,
+    interrupts: [Stats; INTERRUPT_SLOTS],
+    exceptions: Stats,
+}
+
+impl Trace {
+    const fn new() -> Self {
+        Self {
+            records: [EMPTY_RECORD; CAPACITY],
+            next: 0,
+            len: 0,
+            interrupts: [Stats::EMPTY; INTERRUPT_SLOTS],
+            exceptions: Stats::EMPTY,
+        }
+    }
+
+    fn stats_mut(&mut self, trap: Trap) -> &mut Stats {
+        match trap {
+            Trap::Interrupt(code) => &mut self.interrupts[code.min(INTERRUPT_SLOTS - 1)],
+            Trap::Exception(_) => &mut self.exceptions,
+        }
+    }
+}
+
+static TRACE: Mutex<Trace> = Mutex::new(Trace::new());
+
+/// State sampled by [`enter`] before the handler runs.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Entry {
+    /// Machine timer value when the handler was called.
+    start: u64,
+    /// `mtimecmp` of a machine timer interrupt.
+    compare: Option<u64>,
+}
+
+/// Called by the trap entry before the handler.
+///
+/// `mtimecmp` is sampled here rather than in [`exit`]: timer handlers
+/// acknowledge the interrupt by moving it, after which it no longer marks
+/// when the interrupt became pending.
+#[inline]
+pub(crate) fn enter(mcause: usize) -> Entry {
+    Entry {
+        compare: timer_compare(Trap::from_mcause(mcause)),
+        start: now(),
+    }
+}
+
+/// Called by the trap entry after the handler returned.
+pub(crate) fn exit(mcause: usize, entry: Entry) {
+    let record = new_record(mcause, entry, now());
+    TRACE.lock(|trace| {
+        let next = trace.next;
+        trace.records[next] = record;
+        trace.next = (next + 1) % CAPACITY;
+        trace.len = (trace.len + 1).min(CAPACITY);
+        trace.stats_mut(record.cause()).add(&record);
+    });
+}
+
+fn new_record(mcause: usize, entry: Entry, end: u64) -> Record {
+    Record {
+        mcause,
+        start: entry.start,
+        duration: end.wrapping_sub(entry.start),
+        latency: entry
+            .compare
+            .map(|compare| entry.start.saturating_sub(compare)),
+    }
+}
+
+/// `mtimecmp` of hart 0 if `trap` is a machine timer interrupt.
+fn timer_compare(trap: Trap) -> Option<u64> {
+    #[cfg(any(feature = "k230", feature = "k210"))]
+    if trap == Trap::Interrupt(7) {
+        const MTIMECMP: *const u64 = (CLINT_BASE + 0x4000) as *const u64;
+        return Some(unsafe { MTIMECMP.read_volatile() });
+    }
+    None
+}
+
+/// Copies the most recent traps into `out`, oldest first, and returns how
+/// many were copied.
+pub fn records(out: &mut [Record]) -> usize {
+    TRACE.lock(|trace| {
+        let count = out.len().min(trace.len);
+        let first = (trace.next + CAPACITY - count) % CAPACITY;
+        for (i, record) in out[..count].iter_mut().enumerate() {
+            *record = trace.records[(first + i) % CAPACITY];
+        }
+        count
+    })
+}
+
+/// Statistics of `trap` since the last [`reset`].
+///
+/// Exceptions share one set of statistics, as do interrupt codes of 15
+/// and above.
+pub fn stats(trap: Trap) -> Stats {
+    TRACE.lock(|trace| *trace.stats_mut(trap))
+}
+
+/// Clears the ring buffer and the statistics.
+pub fn reset() {
+    TRACE.lock(|trace| *trace = Trace::new());
+}
+
+/// Writes a line of statistics per cause that trapped since the last
+/// [`reset`], with times in microseconds.
+pub fn dump(w: &mut impl fmt::Write) -> fmt::Result {
+    let (interrupts, exceptions) = TRACE.lock(|trace| (trace.interrupts, trace.exceptions));
+    for (code, stats) in interrupts.iter().enumerate() {
+        if stats.count != 0 {
+            write_stats(w, format_args!("interrupt {code}"), stats)?;
+        }
+    }
+    if exceptions.count != 0 {
+        write_stats(w, format_args!("exceptions"), &exceptions)?;
+    }
+    Ok(())
+}
+
+fn write_stats(w: &mut impl fmt::Write, name: fmt::Arguments, stats: &Stats) -> fmt::Result {
+    writeln!(
+        w,
+        "{name}: {} traps, mean {} us, max {} us, max latency {} us",
+        stats.count,
+        micros(stats.mean_ticks()),
+        micros(stats.max_ticks),
+        micros(stats.max_latency),
+    )
+}
+
+fn micros(ticks: u64) -> u64 {
+    ticks * 1_000_000 / TIMER_FREQUENCY as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `mcause` of a machine timer interrupt.
+    const TIMER: usize = 1 << (usize::BITS - 1) | 7;
+
+    #[test]
+    fn latency_uses_compare_sampled_on_entry() {
+        let entry = Entry {
+            start: 130,
+            compare: Some(100),
+        };
+        let record = new_record(TIMER, entry, 180);
+        assert_eq!(record.duration, 50);
+        assert_eq!(record.latency, Some(30));
+
+        let entry = Entry {
+            start: 130,
+            compare: None,
+        };
+        assert_eq!(new_record(11, entry, 131).latency, None);
+    }
+
+    #[test]
+    fn only_timer_interrupts_sample_compare() {
+        assert_eq!(enter(11).compare, None);
+        assert_eq!(enter(2).compare, None);
+    }
+}
@@ -2,14 +2,38 @@
 #![no_std]
 #![allow(unused)]
 
+#[cfg(all(feature = "embassy", feature = "rtic"))]
+compile_error!("the `embassy` and `rtic` features both drive the machine timer interrupt");
+
 #[macro_use]
 mod macros;
 
 pub mod arch;
+pub mod blackbox;
+pub mod boot;
+pub mod console;
+#[cfg(feature = "critical-section")]
+pub mod critical;
+#[cfg(all(feature = "embassy", any(feature = "k230", feature = "k210")))]
+pub mod embassy;
+#[cfg(any(feature = "k230", feature = "k510", feature = "k210"))]
+pub mod firmware;
+mod idle;
 pub mod interrupt;
+#[cfg(feature = "irq-trace")]
+pub mod irq_trace;
+#[cfg(all(feature = "rtic", feature = "k230"))]
+pub mod rtic;
 pub mod soc;
+#[cfg(all(feature = "timers", any(feature = "k230", feature = "k210")))]
+pub mod timer;
+#[cfg(any(feature = "stack-usage", feature = "heap-stats"))]
+pub mod usage;
 
-pub use kendryte_rt_macros::{entry, exception, interrupt};
+pub use idle::{CpuLoad, cpu_load, idle, reset_cpu_load};
+pub use kendryte_rt_macros::{
+    entry, exception, idle, interrupt, post_clock_init, pre_init, pre_jump, ramfunc,
+};
 
 // Simple println-like macro for UART tx that implements `core::fmt::Write`.
 // Usage: uprintln!(tx, "Hello {}", 123);
@@ -29,6 +53,16 @@ cfg_if::cfg_if! {
         pub use kendryte_hal::clocks::Clocks;
         #[doc(hidden)]
         pub use soc::k230::__rom_init_params;
+    } else if #[cfg(feature = "k510")] {
+        pub use soc::k510::{Peripherals, STACK, STACK_SIZE};
+        pub use kendryte_hal::clocks::Clocks;
+        #[doc(hidden)]
+        pub use soc::k510::__rom_init_params;
+    } else if #[cfg(feature = "k210")] {
+        pub use soc::k210::{Peripherals, STACK, STACK_SIZE};
+        pub use kendryte_hal::clocks::Clocks;
+        #[doc(hidden)]
+        pub use soc::k210::__rom_init_params;
     } else {
         #[doc(hidden)]
         pub static STACK: [u8; 0] = [];
@@ -40,3 +74,112 @@ cfg_if::cfg_if! {
 unsafe extern "Rust" {
     fn main() -> !;
 }
+
+// Startup hooks, defined with `#[pre_init]` and `#[post_clock_init]`.
+// The linker script falls back to `__kendryte_rt_default_hook` if missing.
+unsafe extern "C" {
+    fn __pre_init();
+    fn __post_clock_init();
+}
+
+/// Default startup hook, doing nothing.
+#[doc(hidden)]
+#[unsafe(no_mangle)]
+pub extern "C" fn __kendryte_rt_default_hook() {}
+
+/// Default idle hook, waiting for an interrupt.
+#[doc(hidden)]
+#[unsafe(no_mangle)]
+pub extern "C" fn __kendryte_rt_default_idle() {
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    unsafe {
+        core::arch::asm!("wfi", options(nomem, nostack))
+    };
+}
+
+/// Installs the trap vector; called by the entry code before `main`.
+#[doc(hidden)]
+#[unsafe(no_mangle)]
+pub extern "C" fn __init_trap() {
+    // The trap entry switches stacks whenever mscratch is nonzero, and it
+    // is not reset by the hardware, so clear whatever the boot ROM or loader
+    // left in it before traps can be taken.
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    unsafe {
+        core::arch::asm!("csrw mscratch, zero", options(nomem, nostack))
+    };
+    #[cfg(all(
+        feature = "stack-guard",
+        any(feature = "k230", feature = "k510", feature = "k210")
+    ))]
+    unsafe {
+        (*core::ptr::addr_of!(STACK)).register_guard();
+        (*core::ptr::addr_of_mut!(arch::trap::TRAP_STACK)).install_trap_stack();
+    }
+    #[cfg(feature = "vectored-interrupts")]
+    interrupt::set_trap_mode(interrupt::TrapMode::Vectored);
+    #[cfg(not(feature = "vectored-interrupts"))]
+    interrupt::set_trap_mode(interrupt::TrapMode::Direct);
+}
+
+/// Paints the canary below the runtime stack, or over all of its free part
+/// with `stack-usage`; called by the entry code before `.bss` is cleared.
+#[doc(hidden)]
+#[unsafe(no_mangle)]
+pub extern "C" fn __paint_stack_guard() {
+    #[cfg(all(
+        feature = "stack-usage",
+        any(feature = "k230", feature = "k510", feature = "k210")
+    ))]
+    unsafe {
+        (*core::ptr::addr_of_mut!(STACK)).paint()
+    };
+    #[cfg(all(
+        feature = "stack-guard",
+        not(feature = "stack-usage"),
+        any(feature = "k230", feature = "k510", feature = "k210")
+    ))]
+    unsafe {
+        (*core::ptr::addr_of_mut!(STACK)).paint_guard()
+    };
+}
+
+/// Returns true if the runtime stack, or another stack registered with
+/// [`Stack::register_guard`](arch::rvi::Stack::register_guard), has
+/// overflowed into its guard region.
+#[cfg(feature = "stack-guard")]
+pub fn stack_overflowed() -> bool {
+    arch::stack_overflowed(None)
+}
+
+/// Reports a stack overflow instead of the exception being handled.
+///
+/// Called at the start of `#[exception]` handlers. An overflow is detected
+/// when the canary of a registered stack is damaged, or when the exception
+/// is an access fault on its guard region, as raised by
+/// [`arch::rvi::Stack::guard_region`]. The handler runs on the trap stack,
+/// so the report does not touch the overflowed stack.
+#[doc(hidden)]
+#[inline(always)]
+pub fn __check_stack_overflow() {
+    #[cfg(feature = "stack-guard")]
+    {
+        use interrupt::{ExceptionCause, Trap};
+        let mcause: usize;
+        unsafe { core::arch::asm!("csrr {}, mcause", out(reg) mcause, options(nomem, nostack)) };
+        let fault = matches!(
+            Trap::from_mcause(mcause),
+            Trap::Exception(ExceptionCause::LoadFault | ExceptionCause::StoreFault)
+        )
+        .then(interrupt::trap_value);
+        if arch::stack_overflowed(fault) {
+            report_stack_overflow();
+        }
+    }
+}
+
+#[cfg(feature = "stack-guard")]
+#[inline(never)]
+fn report_stack_overflow() -> ! {
+    panic!("stack overflow")
+}
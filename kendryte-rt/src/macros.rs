@@ -1,9 +1,39 @@
+/// Declares peripheral tokens.
+///
+/// Each entry names the token, the address and type of its register block,
+/// optionally the derive-mmio handle type, and optionally instance metadata
+/// in braces:
+///
+/// ```ignore
+/// peripheral! {
+///     use kendryte_hal::uart;
+///     /// Universal Asynchronous Receiver Transmitter 0.
+///     pub struct UART0 => 0x9140_0000, uart::RegisterBlock, uart::MmioRegisterBlock<'static> {
+///         irq = 16, clock = ClockId::UartSclk(0)
+///     };
+/// }
+/// ```
+///
+/// Doc comments are kept on the token type, followed by its address and
+/// metadata, and the metadata is exposed through
+/// [`PeripheralInfo`](kendryte_hal::instance::PeripheralInfo).
 macro_rules! peripheral {
+    (@irq) => { None };
+    (@irq irq = $value:expr $(, $key:ident = $rest:expr)*) => { Some($value) };
+    (@irq $other:ident = $ignored:expr $(, $key:ident = $rest:expr)*) => {
+        peripheral!(@irq $($key = $rest),*)
+    };
+    (@clock) => { None };
+    (@clock clock = $value:expr $(, $key:ident = $rest:expr)*) => { Some($value) };
+    (@clock $other:ident = $ignored:expr $(, $key:ident = $rest:expr)*) => {
+        peripheral!(@clock $($key = $rest),*)
+    };
     (
         $(use $mod_path:path;)*
         $(
             $(#[$doc:meta])*
-            pub struct $name:ident => $addr:expr, $register_block:ty, $mmio_register_block:ty;
+            pub struct $name:ident => $addr:expr, $register_block:ty $(, $mmio_register_block:ty)?
+                $({ $($key:ident = $value:expr),+ $(,)? })?;
         )+
     ) => {
         $(use $mod_path;)*
@@ -11,26 +41,56 @@ macro_rules! peripheral {
         $(
             $(#[$doc])*
             ///
-            /// # Safety
-            ///
-            /// This function is unsafe because it creates a memory-mapped I/O register block
-            /// at the specified address. The caller must ensure that:
-            /// - The address is valid and points to the correct hardware registers
-            /// - No other code is concurrently accessing the same registers
-            /// - The hardware is properly initialized
+            #[doc = concat!("Register block at `", stringify!($addr), "`.")]
+            $($(
+            #[doc = concat!("- `", stringify!($key), "`: `", stringify!($value), "`")]
+            )+)?
             #[allow(non_camel_case_types)]
             pub struct $name(());
 
             impl $name {
-                /// Creates a new MMIO register block for this peripheral
+                /// Steals this peripheral instance.
                 ///
                 /// # Safety
                 ///
-                /// See struct-level safety documentation
+                /// The caller must ensure that no driver created from another
+                /// instance of this peripheral is still in use.
+                #[inline]
+                pub const unsafe fn steal() -> Self {
+                    $name(())
+                }
+
+                /// Returns a pointer to the register block of this peripheral.
+                #[inline]
+                pub const fn ptr() -> *const $register_block {
+                    $addr as *const $register_block
+                }
+                $(
+                /// Creates a new MMIO register block for this peripheral.
+                ///
+                /// # Safety
+                ///
+                /// The caller must ensure that no other code is concurrently
+                /// accessing the same registers.
                 #[inline]
                 pub const unsafe fn mmio_register_block() -> $mmio_register_block {
                    unsafe { <$register_block>::new_mmio_at($addr) }
                 }
+                )?
+            }
+
+            impl ::kendryte_hal::instance::Steal for $name {
+                #[inline]
+                unsafe fn steal() -> Self {
+                    $name(())
+                }
+            }
+
+            impl ::kendryte_hal::instance::PeripheralInfo for $name {
+                const BASE: usize = $addr;
+                const IRQ: Option<usize> = peripheral!(@irq $($($key = $value),+)?);
+                const CLOCK: Option<::kendryte_hal::clocks::ClockId> =
+                    peripheral!(@clock $($($key = $value),+)?);
             }
         )+
     };
@@ -0,0 +1,427 @@
+//! Device support for RTIC 2 applications on the K230.
+//!
+//! RTIC expects a device crate describing the interrupt controller, the way
+//! a PAC does on Cortex-M. This module is that crate for the big core of
+//! the K230: pass it as the device of an application using the RISC-V CLINT
+//! backend of RTIC.
+//!
+//! ```ignore
+//! #[rtic::app(device = kendryte_rt::rtic, backend = H0, dispatchers = [SoftLow])]
+//! mod app {
+//!     #[task(binds = UART0, priority = 2)]
+//!     fn on_uart0(_: on_uart0::Context) { /* ... */ }
+//!
+//!     #[task(priority = 1)]
+//!     async fn blink(_: blink::Context) { /* ... */ }
+//! }
+//! ```
+//!
+//! Software tasks are dispatched from the machine software interrupt, which
+//! the backend raises through [`CLINT`] and handles in a `MachineSoft` of
+//! its own; it must then not be defined with `#[interrupt]`.
+//!
+//! Hardware tasks bind to the PLIC sources named by [`Interrupt`]. Each one
+//! is an `extern "C"` function of the same name, which the machine external
+//! interrupt handler of this module claims and calls, preempted only by
+//! sources of a higher [`Priority`]. Sources without a task fall back to
+//! `DefaultHandler`. This replaces the default machine external interrupt
+//! handler, so handlers registered in [`interrupt`](crate::interrupt) are
+//! not called.
+//!
+//! [`Mtime`] is an RTIC monotonic over the machine timer, so tasks can
+//! `Mtime::delay(...).await`. It takes over the machine timer interrupt,
+//! which must then not be defined with `#[interrupt]`.
+
+use crate::interrupt::{nested, plic};
+use crate::soc::k230::CLINT_BASE;
+use kendryte_hal::soc::TIMER_FREQUENCY;
+use riscv_pac::result::{Error, Result};
+use riscv_pac::{ExternalInterruptNumber, HartIdNumber, InterruptNumber, PriorityNumber};
+use rtic_time::TimerQueueBasedMonotonic;
+use rtic_time::timer_queue::{TimerQueue, TimerQueueBackend};
+
+macro_rules! interrupts {
+    ($($name:ident = $value:literal),+ $(,)?) => {
+        /// PLIC interrupt sources of the K230 with a driver in this runtime.
+        ///
+        /// Each port A pin of GPIO0 and GPIO1 has a source of its own,
+        /// `GPIO<controller>_<pin>`. The SPI sources are the combined
+        /// interrupt line of each controller.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        #[repr(usize)]
+        #[allow(non_camel_case_types)]
+        pub enum Interrupt {
+            $($name = $value,)+
+        }
+
+        unsafe impl InterruptNumber for Interrupt {
+            const MAX_INTERRUPT_NUMBER: usize = max(&[$($value),+]);
+
+            #[inline]
+            fn number(self) -> usize {
+                self as usize
+            }
+
+            #[inline]
+            fn from_number(value: usize) -> Result<Self> {
+                match value {
+                    $($value => Ok(Self::$name),)+
+                    _ => Err(Error::InvalidVariant(value)),
+                }
+            }
+        }
+
+        unsafe extern "C" {
+            $(fn $name();)+
+            fn DefaultHandler();
+        }
+
+        impl Interrupt {
+            /// Hardware task bound to this source.
+            fn handler(self) -> unsafe extern "C" fn() {
+                match self {
+                    $(Self::$name => $name,)+
+                }
+            }
+        }
+    };
+}
+
+interrupts!(
+    UART0 = 16,
+    UART1 = 17,
+    UART2 = 18,
+    UART3 = 19,
+    UART4 = 20,
+    I2C0 = 21,
+    I2C1 = 22,
+    I2C2 = 23,
+    I2C3 = 24,
+    I2C4 = 25,
+    GPIO0_0 = 32,
+    GPIO0_1 = 33,
+    GPIO0_2 = 34,
+    GPIO0_3 = 35,
+    GPIO0_4 = 36,
+    GPIO0_5 = 37,
+    GPIO0_6 = 38,
+    GPIO0_7 = 39,
+    GPIO0_8 = 40,
+    GPIO0_9 = 41,
+    GPIO0_10 = 42,
+    GPIO0_11 = 43,
+    GPIO0_12 = 44,
+    GPIO0_13 = 45,
+    GPIO0_14 = 46,
+    GPIO0_15 = 47,
+    GPIO0_16 = 48,
+    GPIO0_17 = 49,
+    GPIO0_18 = 50,
+    GPIO0_19 = 51,
+    GPIO0_20 = 52,
+    GPIO0_21 = 53,
+    GPIO0_22 = 54,
+    GPIO0_23 = 55,
+    GPIO0_24 = 56,
+    GPIO0_25 = 57,
+    GPIO0_26 = 58,
+    GPIO0_27 = 59,
+    GPIO0_28 = 60,
+    GPIO0_29 = 61,
+    GPIO0_30 = 62,
+    GPIO0_31 = 63,
+    GPIO1_0 = 64,
+    GPIO1_1 = 65,
+    GPIO1_2 = 66,
+    GPIO1_3 = 67,
+    GPIO1_4 = 68,
+    GPIO1_5 = 69,
+    GPIO1_6 = 70,
+    GPIO1_7 = 71,
+    GPIO1_8 = 72,
+    GPIO1_9 = 73,
+    GPIO1_10 = 74,
+    GPIO1_11 = 75,
+    GPIO1_12 = 76,
+    GPIO1_13 = 77,
+    GPIO1_14 = 78,
+    GPIO1_15 = 79,
+    GPIO1_16 = 80,
+    GPIO1_17 = 81,
+    GPIO1_18 = 82,
+    GPIO1_19 = 83,
+    GPIO1_20 = 84,
+    GPIO1_21 = 85,
+    GPIO1_22 = 86,
+    GPIO1_23 = 87,
+    GPIO1_24 = 88,
+    GPIO1_25 = 89,
+    GPIO1_26 = 90,
+    GPIO1_27 = 91,
+    GPIO1_28 = 92,
+    GPIO1_29 = 93,
+    GPIO1_30 = 94,
+    GPIO1_31 = 95,
+    SPI0 = 146,
+    SPI1 = 155,
+    SPI2 = 164,
+);
+
+const fn max(values: &[usize]) -> usize {
+    let mut max = 0;
+    let mut i = 0;
+    while i < values.len() {
+        if values[i] > max {
+            max = values[i];
+        }
+        i += 1;
+    }
+    max
+}
+
+unsafe impl ExternalInterruptNumber for Interrupt {}
+
+macro_rules! priorities {
+    ($($name:ident = $value:literal),+ $(,)?) => {
+        /// PLIC priority levels; sources at `P0` never interrupt.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+        #[repr(u8)]
+        pub enum Priority {
+            $($name = $value,)+
+        }
+
+        unsafe impl PriorityNumber for Priority {
+            const MAX_PRIORITY_NUMBER: usize = plic::MAX_PRIORITY as usize;
+
+            #[inline]
+            fn number(self) -> usize {
+                self as usize
+            }
+
+            #[inline]
+            fn from_number(value: usize) -> Result<Self> {
+                match value {
+                    $($value => Ok(Self::$name),)+
+                    _ => Err(Error::InvalidVariant(value)),
+                }
+            }
+        }
+    };
+}
+
+priorities!(
+    P0 = 0,
+    P1 = 1,
+    P2 = 2,
+    P3 = 3,
+    P4 = 4,
+    P5 = 5,
+    P6 = 6,
+    P7 = 7,
+    P8 = 8,
+    P9 = 9,
+    P10 = 10,
+    P11 = 11,
+    P12 = 12,
+    P13 = 13,
+    P14 = 14,
+    P15 = 15,
+    P16 = 16,
+    P17 = 17,
+    P18 = 18,
+    P19 = 19,
+    P20 = 20,
+    P21 = 21,
+    P22 = 22,
+    P23 = 23,
+    P24 = 24,
+    P25 = 25,
+    P26 = 26,
+    P27 = 27,
+    P28 = 28,
+    P29 = 29,
+    P30 = 30,
+    P31 = 31,
+);
+
+/// Harts the runtime runs RTIC applications on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u16)]
+pub enum HartId {
+    H0 = 0,
+}
+
+unsafe impl HartIdNumber for HartId {
+    const MAX_HART_ID_NUMBER: usize = Self::H0 as usize;
+
+    #[inline]
+    fn number(self) -> usize {
+        self as usize
+    }
+
+    #[inline]
+    fn from_number(value: usize) -> Result<Self> {
+        match value {
+            0 => Ok(Self::H0),
+            _ => Err(Error::InvalidVariant(value)),
+        }
+    }
+}
+
+/// Core-local interruptor, through which the CLINT backend of RTIC pends
+/// the machine software interrupt that runs software tasks.
+pub struct CLINT;
+
+impl CLINT {
+    /// Machine software interrupt registers.
+    #[inline]
+    pub const fn mswi() -> Mswi {
+        Mswi
+    }
+}
+
+/// Machine software interrupt registers of the CLINT.
+pub struct Mswi;
+
+/// Machine software interrupt enable bit of `mie`.
+const MSIE: usize = 1 << 3;
+
+impl Mswi {
+    /// `msip` register of `hart_id`.
+    #[inline]
+    pub fn msip<H: HartIdNumber>(&self, hart_id: H) -> Msip {
+        Msip((CLINT_BASE as *mut u32).wrapping_add(hart_id.number()))
+    }
+
+    /// Allows the machine software interrupt to interrupt the current hart.
+    #[inline]
+    pub fn enable(&self) {
+        unsafe { core::arch::asm!("csrs mie, {}", in(reg) MSIE, options(nomem, nostack)) };
+    }
+
+    /// Stops the machine software interrupt from interrupting the current
+    /// hart.
+    #[inline]
+    pub fn disable(&self) {
+        unsafe { core::arch::asm!("csrc mie, {}", in(reg) MSIE, options(nomem, nostack)) };
+    }
+}
+
+/// Machine software interrupt pending register of one hart.
+pub struct Msip(*mut u32);
+
+impl Msip {
+    /// Returns true if the software interrupt is pending.
+    #[inline]
+    pub fn is_pending(&self) -> bool {
+        unsafe { self.0.read_volatile() & 1 != 0 }
+    }
+
+    /// Raises the software interrupt.
+    #[inline]
+    pub fn pend(&self) {
+        unsafe { self.0.write_volatile(1) };
+    }
+
+    /// Clears the software interrupt.
+    #[inline]
+    pub fn unpend(&self) {
+        unsafe { self.0.write_volatile(0) };
+    }
+}
+
+/// Sets the PLIC priority of `interrupt` and allows it to interrupt hart 0.
+pub fn unmask(interrupt: Interrupt, priority: Priority) {
+    plic::set_priority(interrupt.number(), priority as u8);
+    plic::enable(interrupt.number());
+}
+
+/// Stops `interrupt` from interrupting hart 0.
+pub fn mask(interrupt: Interrupt) {
+    plic::disable(interrupt.number());
+}
+
+/// Claims pending PLIC sources and calls the handler named after each.
+#[doc(hidden)]
+#[unsafe(no_mangle)]
+pub extern "C" fn MachineExternal() {
+    while let Some(irq) = plic::claim() {
+        let handler = match Interrupt::from_number(irq) {
+            Ok(interrupt) => interrupt.handler(),
+            Err(_) => DefaultHandler,
+        };
+        nested(plic::priority(irq), || unsafe { handler() });
+        plic::complete(irq);
+    }
+}
+
+/// `mtimecmp` of hart 0.
+const MTIMECMP: *mut u64 = (CLINT_BASE + 0x4000) as *mut u64;
+
+/// Machine timer interrupt enable bit of `mie`.
+const MTIE: usize = 1 << 7;
+
+/// Timer queue backend driving [`Mtime`].
+pub struct MtimeBackend;
+
+static TIMER_QUEUE: TimerQueue<MtimeBackend> = TimerQueue::new();
+
+impl TimerQueueBackend for MtimeBackend {
+    type Ticks = u64;
+
+    fn now() -> u64 {
+        kendryte_hal::time::now()
+    }
+
+    fn set_compare(instant: u64) {
+        unsafe { MTIMECMP.write_volatile(instant) };
+    }
+
+    fn clear_compare_flag() {
+        // The interrupt is pending while mtime >= mtimecmp.
+        unsafe { MTIMECMP.write_volatile(u64::MAX) };
+    }
+
+    fn pend_interrupt() {
+        unsafe { MTIMECMP.write_volatile(0) };
+    }
+
+    fn enable_timer() {
+        unsafe { core::arch::asm!("csrs mie, {}", in(reg) MTIE, options(nomem, nostack)) };
+    }
+
+    fn disable_timer() {
+        unsafe { core::arch::asm!("csrc mie, {}", in(reg) MTIE, options(nomem, nostack)) };
+    }
+
+    fn timer_queue() -> &'static TimerQueue<Self> {
+        &TIMER_QUEUE
+    }
+}
+
+/// RTIC monotonic counting machine timer ticks.
+pub struct Mtime;
+
+impl Mtime {
+    /// Starts the monotonic; call once from the `#[init]` task.
+    pub fn start() {
+        unsafe { MTIMECMP.write_volatile(u64::MAX) };
+        TIMER_QUEUE.initialize(MtimeBackend);
+    }
+}
+
+impl TimerQueueBasedMonotonic for Mtime {
+    type Backend = MtimeBackend;
+    type Instant = fugit::Instant<u64, 1, TIMER_FREQUENCY>;
+    type Duration = fugit::Duration<u64, 1, TIMER_FREQUENCY>;
+}
+
+rtic_time::impl_embedded_hal_delay_fugit!(Mtime);
+rtic_time::impl_embedded_hal_async_delay_fugit!(Mtime);
+
+/// Wakes the tasks waiting on [`Mtime`].
+#[doc(hidden)]
+#[unsafe(no_mangle)]
+pub extern "C" fn MachineTimer() {
+    unsafe { TIMER_QUEUE.on_monotonic_interrupt() };
+}
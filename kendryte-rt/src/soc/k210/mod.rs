@@ -0,0 +1,162 @@
+//! Kendryte K210 chip.
+
+mod pads;
+mod peripheral;
+
+use crate::arch::rvi::Stack;
+use core::sync::atomic::{AtomicBool, Ordering};
+use kendryte_hal::clocks::{ClockId, Clocks};
+pub use pads::{Pad, Pads};
+
+/// Platform-level interrupt controller.
+pub const PLIC_BASE: usize = 0x0C00_0000;
+
+/// Core-local interruptor, holding the machine timer.
+pub const CLINT_BASE: usize = 0x0200_0000;
+
+/// Platform stack size.
+pub const STACK_SIZE: usize = 32 * 1024;
+
+/// Stack for current platform.
+#[cfg(any(doc, feature = "k210"))]
+#[unsafe(link_section = ".bss.uninit")]
+pub static mut STACK: Stack<STACK_SIZE> = Stack([0; STACK_SIZE]);
+
+peripheral! {
+    use kendryte_hal::gpio;
+    use kendryte_hal::i2c;
+    use kendryte_hal::iomux;
+    use kendryte_hal::spi;
+    use kendryte_hal::uart;
+    /// Field Programmable Input/Output Array, the pad multiplexer.
+    pub struct IOMUX => 0x502B_0000, iomux::RegisterBlock, iomux::MmioRegisterBlock<'static>;
+    /// General Purpose Input/Output 0.
+    pub struct GPIO0 => 0x5020_0000, gpio::RegisterBlock, gpio::MmioRegisterBlock<'static> {
+        irq = 23
+    };
+    /// Universal Asynchronous Receiver Transmitter 1.
+    pub struct UART1 => 0x5021_0000, uart::RegisterBlock, uart::MmioRegisterBlock<'static> {
+        irq = 11, clock = ClockId::UartSclk(1)
+    };
+    /// Universal Asynchronous Receiver Transmitter 2.
+    pub struct UART2 => 0x5022_0000, uart::RegisterBlock, uart::MmioRegisterBlock<'static> {
+        irq = 12, clock = ClockId::UartSclk(2)
+    };
+    /// Universal Asynchronous Receiver Transmitter 3.
+    pub struct UART3 => 0x5023_0000, uart::RegisterBlock, uart::MmioRegisterBlock<'static> {
+        irq = 13, clock = ClockId::UartSclk(3)
+    };
+    /// Inter-Integrated Circuit 0.
+    pub struct I2C0 => 0x5028_0000, i2c::RegisterBlock, i2c::MmioRegisterBlock<'static> {
+        irq = 8, clock = ClockId::I2cSclk(0)
+    };
+    /// Inter-Integrated Circuit 1.
+    pub struct I2C1 => 0x5029_0000, i2c::RegisterBlock, i2c::MmioRegisterBlock<'static> {
+        irq = 9, clock = ClockId::I2cSclk(1)
+    };
+    /// Inter-Integrated Circuit 2.
+    pub struct I2C2 => 0x502A_0000, i2c::RegisterBlock, i2c::MmioRegisterBlock<'static> {
+        irq = 10, clock = ClockId::I2cSclk(2)
+    };
+    /// Serial Peripheral Interface 0.
+    pub struct SPI0 => 0x5200_0000, spi::RegisterBlock { irq = 1, clock = ClockId::SpiSclk(0) };
+    /// Serial Peripheral Interface 1.
+    pub struct SPI1 => 0x5300_0000, spi::RegisterBlock { irq = 2, clock = ClockId::SpiSclk(1) };
+    /// Serial Peripheral Interface 3, the quad controller for boot flash.
+    pub struct SPI3 => 0x5400_0000, spi::RegisterBlock { irq = 4, clock = ClockId::SpiSclk(3) };
+    /// Neural network accelerator.
+    pub struct KPU => 0x4080_0000, kendryte_hal::kpu::RegisterBlock, kendryte_hal::kpu::MmioRegisterBlock<'static> {
+        irq = 25
+    };
+}
+
+// TODO UARTHS and GPIOHS are SiFive IP blocks without a driver in kendryte-hal.
+
+/// Peripherals available on ROM start.
+pub struct Peripherals {
+    /// Field Programmable Input/Output Array, the pad multiplexer.
+    pub iomux: Pads,
+    /// General Purpose Input/Output 0.
+    pub gpio0: GPIO0,
+    /// Universal Asynchronous Receiver Transmitter 1.
+    pub uart1: UART1,
+    /// Universal Asynchronous Receiver Transmitter 2.
+    pub uart2: UART2,
+    /// Universal Asynchronous Receiver Transmitter 3.
+    pub uart3: UART3,
+    /// Inter-Integrated Circuit 0.
+    pub i2c0: I2C0,
+    /// Inter-Integrated Circuit 1.
+    pub i2c1: I2C1,
+    /// Inter-Integrated Circuit 2.
+    pub i2c2: I2C2,
+    /// Serial Peripheral Interface 0.
+    pub spi0: SPI0,
+    /// Serial Peripheral Interface 1.
+    pub spi1: SPI1,
+    /// Serial Peripheral Interface 3.
+    pub spi3: SPI3,
+    /// Neural network accelerator.
+    pub kpu: KPU,
+}
+
+/// Set once the peripherals have been handed out.
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+impl Peripherals {
+    /// Takes the peripherals, returning `None` if they were already taken.
+    #[inline]
+    pub fn try_take() -> Option<Self> {
+        if TAKEN.swap(true, Ordering::AcqRel) {
+            None
+        } else {
+            Some(unsafe { Self::steal() })
+        }
+    }
+
+    /// Takes the peripherals.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the peripherals were already taken.
+    #[inline]
+    pub fn take() -> Self {
+        Self::try_take().expect("peripherals already taken")
+    }
+
+    /// Steals the peripherals regardless of whether they were taken.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that drivers built from previously handed out
+    /// tokens are no longer in use.
+    #[inline]
+    pub unsafe fn steal() -> Self {
+        Peripherals {
+            iomux: Pads::new(),
+            gpio0: GPIO0(()),
+            uart1: UART1(()),
+            uart2: UART2(()),
+            uart3: UART3(()),
+            i2c0: I2C0(()),
+            i2c1: I2C1(()),
+            i2c2: I2C2(()),
+            spi0: SPI0(()),
+            spi1: SPI1(()),
+            spi3: SPI3(()),
+            kpu: KPU(()),
+        }
+    }
+}
+
+// Used by macros only.
+#[allow(unused)]
+#[doc(hidden)]
+#[inline(always)]
+pub fn __rom_init_params() -> (Peripherals, Clocks) {
+    TAKEN.store(true, Ordering::Release);
+    let peripherals = unsafe { Peripherals::steal() };
+    let clocks = Clocks;
+    unsafe { crate::__post_clock_init() };
+    (peripherals, clocks)
+}
@@ -0,0 +1,105 @@
+use crate::soc::k210::GPIO0;
+use crate::soc::k210::pads::Pad;
+use kendryte_hal::gpio::pad::IntoGpio;
+use kendryte_hal::gpio::{GpioPort, MmioRegisterBlock};
+use kendryte_hal::instance::{Instance, Numbered};
+use kendryte_hal::iomux::ops::PadOps;
+use kendryte_hal::iomux::{FlexPad, IntoFlexPad};
+
+macro_rules! gpio {
+    (
+        $(
+            ($GPIOx:ty, $n:literal)
+        ),+ $(,)?
+    ) => {
+        $(
+            impl Instance<'static> for $GPIOx {
+                type R = MmioRegisterBlock<'static>;
+
+                #[inline]
+                fn inner(self) -> Self::R {
+                    unsafe { <$GPIOx>::mmio_register_block() }
+                }
+            }
+
+            impl Numbered<'static, $n> for $GPIOx {}
+
+            impl<'i> Instance<'i> for &'i $GPIOx {
+                type R = MmioRegisterBlock<'static>;
+
+                #[inline]
+                fn inner(self) -> Self::R {
+                    unsafe { <$GPIOx>::mmio_register_block() }
+                }
+            }
+
+            impl<'i> Numbered<'i, $n> for &'i $GPIOx {}
+
+            impl<'i> Instance<'i> for &'i mut $GPIOx {
+                type R = MmioRegisterBlock<'static>;
+
+                #[inline]
+                fn inner(self) -> Self::R {
+                    unsafe { <$GPIOx>::mmio_register_block() }
+                }
+            }
+
+            impl<'i> Numbered<'i, $n> for &'i mut $GPIOx {}
+        )+
+    };
+}
+
+gpio! {
+    (GPIO0, 0),
+}
+
+/// FPIOA function of GPIO pin 0; pin `n` is this plus `n`.
+const GPIO_FUNCTION: u8 = 56;
+
+/// Number of pins of the GPIO controller.
+const GPIO_PINS: usize = 8;
+
+// Any pad can carry a GPIO pin; pad `P` is routed to pin `P % 8`, so pads
+// eight apart share a pin and cannot be used as GPIO together.
+
+impl<const P: usize> IntoGpio<'static, 0> for Pad<P> {
+    const PORT: GpioPort = GpioPort::A;
+    const PIN_NUM: usize = P % GPIO_PINS;
+
+    #[inline]
+    fn into_gpio(self) -> FlexPad<'static> {
+        let mut flex_pad = self.into_flex_pad();
+        flex_pad
+            .set_bidirectional()
+            .set_function_select(GPIO_FUNCTION + (P % GPIO_PINS) as u8);
+        flex_pad
+    }
+}
+
+impl<'p, const P: usize> IntoGpio<'p, 0> for &'p Pad<P> {
+    const PORT: GpioPort = GpioPort::A;
+    const PIN_NUM: usize = P % GPIO_PINS;
+
+    #[inline]
+    fn into_gpio(self) -> FlexPad<'p> {
+        let mut flex_pad = self.into_flex_pad();
+        flex_pad
+            .set_bidirectional()
+            .set_function_select(GPIO_FUNCTION + (P % GPIO_PINS) as u8);
+        flex_pad
+    }
+}
+
+impl<'p, const P: usize> IntoGpio<'p, 0> for &'p mut Pad<P> {
+    const PORT: GpioPort = GpioPort::A;
+    const PIN_NUM: usize = P % GPIO_PINS;
+
+    #[inline]
+    fn into_gpio(self) -> FlexPad<'p> {
+        let mut flex_pad = self.into_flex_pad();
+        flex_pad
+            .set_bidirectional()
+            .set_function_select(GPIO_FUNCTION + (P % GPIO_PINS) as u8);
+        flex_pad
+    }
+}
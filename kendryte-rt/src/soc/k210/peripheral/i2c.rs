@@ -0,0 +1,94 @@
+use crate::soc::k210::pads::Pad;
+use crate::soc::k210::{I2C0, I2C1, I2C2};
+use kendryte_hal::i2c::MmioRegisterBlock;
+use kendryte_hal::i2c::pad::{IntoI2cScl, IntoI2cSda};
+use kendryte_hal::instance::{Instance, Numbered};
+use kendryte_hal::iomux::ops::PadOps;
+use kendryte_hal::iomux::{FlexPad, IntoFlexPad};
+
+macro_rules! i2c {
+    (
+        $(
+            ($I2Cx:ty, $n:literal)
+        ),+ $(,)?
+    ) => {
+        $(
+            impl Instance<'static> for $I2Cx {
+                type R = MmioRegisterBlock<'static>;
+
+                #[inline]
+                fn inner(self) -> Self::R {
+                    unsafe { <$I2Cx>::mmio_register_block() }
+                }
+            }
+
+            impl Numbered<'static, $n> for $I2Cx {}
+
+            impl<'i> Instance<'i> for &'i mut $I2Cx {
+                type R = MmioRegisterBlock<'static>;
+
+                #[inline]
+                fn inner(self) -> Self::R {
+                    unsafe { <$I2Cx>::mmio_register_block() }
+                }
+            }
+
+            impl<'i> Numbered<'i, $n> for &'i mut $I2Cx {}
+        )+
+    };
+}
+
+i2c! {
+    (I2C0, 0),
+    (I2C1, 1),
+    (I2C2, 2),
+}
+
+macro_rules! pad_i2c {
+    (
+        $(
+            ($i2c_num:expr, $scl_function:expr, $sda_function:expr)
+        ),+ $(,)?
+    ) => {
+        $(
+            impl<const P: usize> IntoI2cScl<'static, $i2c_num> for Pad<P> {
+                fn into_i2c_scl(self) -> FlexPad<'static> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_bidirectional().set_function_select($scl_function);
+                    flex_pad
+                }
+            }
+
+            impl<'p, const P: usize> IntoI2cScl<'p, $i2c_num> for &'p mut Pad<P> {
+                fn into_i2c_scl(self) -> FlexPad<'p> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_bidirectional().set_function_select($scl_function);
+                    flex_pad
+                }
+            }
+
+            impl<const P: usize> IntoI2cSda<'static, $i2c_num> for Pad<P> {
+                fn into_i2c_sda(self) -> FlexPad<'static> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_bidirectional().set_function_select($sda_function);
+                    flex_pad
+                }
+            }
+
+            impl<'p, const P: usize> IntoI2cSda<'p, $i2c_num> for &'p mut Pad<P> {
+                fn into_i2c_sda(self) -> FlexPad<'p> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_bidirectional().set_function_select($sda_function);
+                    flex_pad
+                }
+            }
+        )+
+    };
+}
+
+// Any pad can carry an I2C signal.
+pad_i2c! {
+    (0, 126, 127),
+    (1, 128, 129),
+    (2, 130, 131),
+}
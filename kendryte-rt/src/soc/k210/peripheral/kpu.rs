@@ -0,0 +1,21 @@
+use crate::soc::k210::KPU;
+use kendryte_hal::instance::Instance;
+use kendryte_hal::kpu::MmioRegisterBlock;
+
+impl Instance<'static> for KPU {
+    type R = MmioRegisterBlock<'static>;
+
+    #[inline]
+    fn inner(self) -> Self::R {
+        unsafe { KPU::mmio_register_block() }
+    }
+}
+
+impl<'i> Instance<'i> for &'i mut KPU {
+    type R = MmioRegisterBlock<'static>;
+
+    #[inline]
+    fn inner(self) -> Self::R {
+        unsafe { KPU::mmio_register_block() }
+    }
+}
@@ -0,0 +1,5 @@
+mod gpio;
+mod i2c;
+mod kpu;
+mod spi;
+mod uart;
@@ -0,0 +1,127 @@
+use crate::soc::k210::pads::Pad;
+use crate::soc::k210::{SPI0, SPI1, SPI3};
+use kendryte_hal::instance::{Instance, Numbered};
+use kendryte_hal::iomux::ops::PadOps;
+use kendryte_hal::iomux::{FlexPad, IntoFlexPad};
+use kendryte_hal::spi::RegisterBlock;
+use kendryte_hal::spi::pad::{IntoSpiClk, IntoSpiCs, IntoSpiMiso, IntoSpiMosi};
+
+macro_rules! spi {
+    (
+        $(
+            ($SPIx:ty, $n:literal)
+        ),+ $(,)?
+    ) => {
+        $(
+            impl Instance<'static> for $SPIx {
+                type R = &'static RegisterBlock;
+
+                #[inline]
+                fn inner(self) -> Self::R {
+                    unsafe { &*<$SPIx>::ptr() }
+                }
+            }
+
+            impl Numbered<'static, $n> for $SPIx {}
+
+            impl<'i> Instance<'i> for &'i mut $SPIx {
+                type R = &'static RegisterBlock;
+
+                #[inline]
+                fn inner(self) -> Self::R {
+                    unsafe { &*<$SPIx>::ptr() }
+                }
+            }
+
+            impl<'i> Numbered<'i, $n> for &'i mut $SPIx {}
+        )+
+    };
+}
+
+spi! {
+    (SPI0, 0),
+    (SPI1, 1),
+    (SPI3, 3),
+}
+
+macro_rules! pad_spi {
+    (
+        $(
+            ($spi_num:expr, $clk_function:expr, $mosi_function:expr, $miso_function:expr, $cs_function:expr)
+        ),+ $(,)?
+    ) => {
+        $(
+            impl<const P: usize> IntoSpiClk<'static, $spi_num> for Pad<P> {
+                fn into_spi_clk(self) -> FlexPad<'static> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_output().set_function_select($clk_function);
+                    flex_pad
+                }
+            }
+
+            impl<'p, const P: usize> IntoSpiClk<'p, $spi_num> for &'p mut Pad<P> {
+                fn into_spi_clk(self) -> FlexPad<'p> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_output().set_function_select($clk_function);
+                    flex_pad
+                }
+            }
+
+            impl<const P: usize> IntoSpiMosi<'static, $spi_num> for Pad<P> {
+                fn into_spi_mosi(self) -> FlexPad<'static> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_output().set_function_select($mosi_function);
+                    flex_pad
+                }
+            }
+
+            impl<'p, const P: usize> IntoSpiMosi<'p, $spi_num> for &'p mut Pad<P> {
+                fn into_spi_mosi(self) -> FlexPad<'p> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_output().set_function_select($mosi_function);
+                    flex_pad
+                }
+            }
+
+            impl<const P: usize> IntoSpiMiso<'static, $spi_num> for Pad<P> {
+                fn into_spi_miso(self) -> FlexPad<'static> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_input().set_function_select($miso_function);
+                    flex_pad
+                }
+            }
+
+            impl<'p, const P: usize> IntoSpiMiso<'p, $spi_num> for &'p mut Pad<P> {
+                fn into_spi_miso(self) -> FlexPad<'p> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_input().set_function_select($miso_function);
+                    flex_pad
+                }
+            }
+
+            impl<const P: usize> IntoSpiCs<'static, $spi_num> for Pad<P> {
+                fn into_spi_cs(self) -> FlexPad<'static> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_output().set_function_select($cs_function);
+                    flex_pad
+                }
+            }
+
+            impl<'p, const P: usize> IntoSpiCs<'p, $spi_num> for &'p mut Pad<P> {
+                fn into_spi_cs(self) -> FlexPad<'p> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_output().set_function_select($cs_function);
+                    flex_pad
+                }
+            }
+        )+
+    };
+}
+
+// Any pad can carry an SPI signal. In standard SPI, MOSI is data line 0 and
+// MISO is data line 1; the chip select is SS0. SPI3 only connects to the
+// dedicated flash pads.
+pad_spi! {
+    (0, 17, 4, 5, 12),
+    (1, 83, 70, 71, 78),
+}
@@ -0,0 +1,94 @@
+use crate::soc::k210::pads::Pad;
+use crate::soc::k210::{UART1, UART2, UART3};
+use kendryte_hal::instance::{Instance, Numbered};
+use kendryte_hal::iomux::ops::PadOps;
+use kendryte_hal::iomux::{FlexPad, IntoFlexPad};
+use kendryte_hal::uart::MmioRegisterBlock;
+use kendryte_hal::uart::pad::{IntoUartSin, IntoUartSout};
+
+macro_rules! uart {
+    (
+        $(
+            ($UARTx:ty, $n:literal)
+        ),+ $(,)?
+    ) => {
+        $(
+            impl Instance<'static> for $UARTx {
+                type R = MmioRegisterBlock<'static>;
+
+                #[inline]
+                fn inner(self) -> Self::R {
+                    unsafe { <$UARTx>::mmio_register_block() }
+                }
+            }
+
+            impl Numbered<'static, $n> for $UARTx {}
+
+            impl<'i> Instance<'i> for &'i mut $UARTx {
+                type R = MmioRegisterBlock<'static>;
+
+                #[inline]
+                fn inner(self) -> Self::R {
+                    unsafe { <$UARTx>::mmio_register_block() }
+                }
+            }
+
+            impl<'i> Numbered<'i, $n> for &'i mut $UARTx {}
+        )+
+    };
+}
+
+uart! {
+    (UART1, 1),
+    (UART2, 2),
+    (UART3, 3),
+}
+
+macro_rules! pad_uart {
+    (
+        $(
+            ($uart_num:expr, $sin_function:expr, $sout_function:expr)
+        ),+ $(,)?
+    ) => {
+        $(
+            impl<const P: usize> IntoUartSout<'static, $uart_num> for Pad<P> {
+                fn into_uart_sout(self) -> FlexPad<'static> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_output().set_function_select($sout_function);
+                    flex_pad
+                }
+            }
+
+            impl<'p, const P: usize> IntoUartSout<'p, $uart_num> for &'p mut Pad<P> {
+                fn into_uart_sout(self) -> FlexPad<'p> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_output().set_function_select($sout_function);
+                    flex_pad
+                }
+            }
+
+            impl<const P: usize> IntoUartSin<'static, $uart_num> for Pad<P> {
+                fn into_uart_sin(self) -> FlexPad<'static> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_input().set_function_select($sin_function);
+                    flex_pad
+                }
+            }
+
+            impl<'p, const P: usize> IntoUartSin<'p, $uart_num> for &'p mut Pad<P> {
+                fn into_uart_sin(self) -> FlexPad<'p> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_input().set_function_select($sin_function);
+                    flex_pad
+                }
+            }
+        )+
+    };
+}
+
+// Any pad can carry a UART signal; the FPIOA functions are RX then TX.
+pad_uart! {
+    (1, 64, 65),
+    (2, 66, 67),
+    (3, 68, 69),
+}
@@ -4,8 +4,16 @@ mod pads;
 mod peripheral;
 
 use crate::arch::rvi::Stack;
-use kendryte_hal::{clocks::Clocks, gpio, iomux, pwm, spi, uart};
-use pads::Pads;
+use core::sync::atomic::{AtomicBool, Ordering};
+use kendryte_hal::clocks::ClockId;
+use kendryte_hal::{clocks::Clocks, gpio, i2c, iomux, lsadc, pwm, spi, sysctl, uart, wdt};
+pub use pads::{Pad, Pads};
+
+/// Platform-level interrupt controller of the C908 core.
+pub const PLIC_BASE: usize = 0xF_0000_0000;
+
+/// Core-local interruptor of the C908 core, holding the machine timer.
+pub const CLINT_BASE: usize = 0xF_0400_0000;
 
 /// Platform stack size.
 pub const STACK_SIZE: usize = 32 * 1024;
@@ -17,8 +25,15 @@ pub static mut STACK: Stack<STACK_SIZE> = Stack([0; STACK_SIZE]);
 
 peripheral! {
     use kendryte_hal::gpio;
+    use kendryte_hal::i2c;
     use kendryte_hal::iomux;
+    use kendryte_hal::lsadc;
+    use kendryte_hal::sysctl;
+    use kendryte_hal::timer;
     use kendryte_hal::uart;
+    use kendryte_hal::wdt;
+    /// System controller clock gates.
+    pub struct SYSCTL => 0x9110_0000, sysctl::RegisterBlock, sysctl::MmioRegisterBlock<'static>;
     /// Input/Output Multiplexer.
     pub struct IOMUX => 0x9110_5000, iomux::RegisterBlock, iomux::MmioRegisterBlock<'static>;
     /// General Purpose Input/Output 0.
@@ -26,23 +41,67 @@ peripheral! {
     /// General Purpose Input/Output 1.
     pub struct GPIO1 => 0x9140_C000, gpio::RegisterBlock, gpio::MmioRegisterBlock<'static>;
     /// Universal Asynchronous Receiver Transmitter 0.
-    pub struct UART0 => 0x9140_0000, uart::RegisterBlock, uart::MmioRegisterBlock<'static>;
+    pub struct UART0 => 0x9140_0000, uart::RegisterBlock, uart::MmioRegisterBlock<'static> {
+        irq = 16, clock = ClockId::UartSclk(0)
+    };
     /// Universal Asynchronous Receiver Transmitter 1.
-    pub struct UART1 => 0x9140_1000, uart::RegisterBlock, uart::MmioRegisterBlock<'static>;
+    pub struct UART1 => 0x9140_1000, uart::RegisterBlock, uart::MmioRegisterBlock<'static> {
+        irq = 17, clock = ClockId::UartSclk(1)
+    };
     /// Universal Asynchronous Receiver Transmitter 2.
-    pub struct UART2 => 0x9140_2000, uart::RegisterBlock, uart::MmioRegisterBlock<'static>;
+    pub struct UART2 => 0x9140_2000, uart::RegisterBlock, uart::MmioRegisterBlock<'static> {
+        irq = 18, clock = ClockId::UartSclk(2)
+    };
     /// Universal Asynchronous Receiver Transmitter 3.
-    pub struct UART3 => 0x9140_3000, uart::RegisterBlock, uart::MmioRegisterBlock<'static>;
+    pub struct UART3 => 0x9140_3000, uart::RegisterBlock, uart::MmioRegisterBlock<'static> {
+        irq = 19, clock = ClockId::UartSclk(3)
+    };
     /// Universal Asynchronous Receiver Transmitter 4.
-    pub struct UART4 => 0x9140_4000, uart::RegisterBlock;
-    /// Serial Peripheral Interface 0.
-    pub struct SPI0  => 0x9140_5000, spi::RegisterBlock;
+    pub struct UART4 => 0x9140_4000, uart::RegisterBlock, uart::MmioRegisterBlock<'static> {
+        irq = 20, clock = ClockId::UartSclk(4)
+    };
+    /// Inter-Integrated Circuit 0.
+    pub struct I2C0 => 0x9140_5000, i2c::RegisterBlock, i2c::MmioRegisterBlock<'static> {
+        irq = 21, clock = ClockId::I2cSclk(0)
+    };
+    /// Inter-Integrated Circuit 1.
+    pub struct I2C1 => 0x9140_6000, i2c::RegisterBlock, i2c::MmioRegisterBlock<'static> {
+        irq = 22, clock = ClockId::I2cSclk(1)
+    };
+    /// Inter-Integrated Circuit 2.
+    pub struct I2C2 => 0x9140_7000, i2c::RegisterBlock, i2c::MmioRegisterBlock<'static> {
+        irq = 23, clock = ClockId::I2cSclk(2)
+    };
+    /// Inter-Integrated Circuit 3.
+    pub struct I2C3 => 0x9140_8000, i2c::RegisterBlock, i2c::MmioRegisterBlock<'static> {
+        irq = 24, clock = ClockId::I2cSclk(3)
+    };
+    /// Inter-Integrated Circuit 4.
+    pub struct I2C4 => 0x9140_9000, i2c::RegisterBlock, i2c::MmioRegisterBlock<'static> {
+        irq = 25, clock = ClockId::I2cSclk(4)
+    };
+    /// Low Speed Analog to Digital Converter.
+    pub struct LSADC => 0x9140_D000, lsadc::RegisterBlock, lsadc::MmioRegisterBlock<'static>;
+    /// Serial Peripheral Interface 0, the octal controller for boot flash.
+    pub struct SPI0  => 0x9158_4000, spi::RegisterBlock { clock = ClockId::SpiSclk(0) };
+    /// Serial Peripheral Interface 1.
+    pub struct SPI1  => 0x9158_2000, spi::RegisterBlock { clock = ClockId::SpiSclk(1) };
+    /// Serial Peripheral Interface 2.
+    pub struct SPI2  => 0x9158_3000, spi::RegisterBlock { clock = ClockId::SpiSclk(2) };
     /// Pulse Width Modulation 0.
     pub struct PWM0  => 0x9140_A000, pwm::RegisterBlock;
+    /// General purpose timers.
+    pub struct TIMER => 0x9110_5800, timer::RegisterBlock, timer::MmioRegisterBlock<'static>;
+    /// Watchdog Timer 0.
+    pub struct WDT0 => 0x9110_6000, wdt::RegisterBlock, wdt::MmioRegisterBlock<'static>;
+    /// Watchdog Timer 1.
+    pub struct WDT1 => 0x9110_6800, wdt::RegisterBlock, wdt::MmioRegisterBlock<'static>;
 }
 
 /// Peripherals available on ROM start.
 pub struct Peripherals {
+    /// System controller clock gates.
+    pub sysctl: SYSCTL,
     /// Input/Output Multiplexer.
     pub iomux: Pads,
     /// General Purpose Input/Output 0.
@@ -59,10 +118,99 @@ pub struct Peripherals {
     pub uart3: UART3,
     /// Universal Asynchronous Receiver Transmitter 4.
     pub uart4: UART4,
+    /// Inter-Integrated Circuit 0.
+    pub i2c0: I2C0,
+    /// Inter-Integrated Circuit 1.
+    pub i2c1: I2C1,
+    /// Inter-Integrated Circuit 2.
+    pub i2c2: I2C2,
+    /// Inter-Integrated Circuit 3.
+    pub i2c3: I2C3,
+    /// Inter-Integrated Circuit 4.
+    pub i2c4: I2C4,
+    /// Low Speed Analog to Digital Converter.
+    pub lsadc: LSADC,
     /// Serial Peripheral Interface 0.
     pub spi0: SPI0,
+    /// Serial Peripheral Interface 1.
+    pub spi1: SPI1,
+    /// Serial Peripheral Interface 2.
+    pub spi2: SPI2,
     /// Pulse Width Modulation 0.
     pub pwm0: PWM0,
+    /// General purpose timers.
+    pub timer: TIMER,
+    /// Watchdog Timer 0.
+    pub wdt0: WDT0,
+    /// Watchdog Timer 1.
+    pub wdt1: WDT1,
+}
+
+/// Set once the peripherals have been handed out.
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+impl Peripherals {
+    /// Takes the peripherals, returning `None` if they were already taken.
+    ///
+    /// The `#[entry]` function receives the peripherals on startup, so this
+    /// only succeeds when called before `main` or from code that does not
+    /// use the entry parameters.
+    #[inline]
+    pub fn try_take() -> Option<Self> {
+        if TAKEN.swap(true, Ordering::AcqRel) {
+            None
+        } else {
+            Some(unsafe { Self::steal() })
+        }
+    }
+
+    /// Takes the peripherals.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the peripherals were already taken.
+    #[inline]
+    pub fn take() -> Self {
+        Self::try_take().expect("peripherals already taken")
+    }
+
+    /// Steals the peripherals regardless of whether they were taken.
+    ///
+    /// This is intended for recovery code, such as panic or exception
+    /// handlers, and for integration tests that re-initialize drivers.
+    ///
+    /// # Safety
+    ///
+    /// Every peripheral and pad is a singleton token. The caller must ensure
+    /// that drivers built from previously handed out tokens are no longer in
+    /// use, otherwise two drivers may configure the same hardware concurrently.
+    #[inline]
+    pub unsafe fn steal() -> Self {
+        Peripherals {
+            sysctl: SYSCTL(()),
+            iomux: Pads::new(),
+            gpio0: GPIO0(()),
+            gpio1: GPIO1(()),
+            uart0: UART0(()),
+            uart1: UART1(()),
+            uart2: UART2(()),
+            uart3: UART3(()),
+            uart4: UART4(()),
+            i2c0: I2C0(()),
+            i2c1: I2C1(()),
+            i2c2: I2C2(()),
+            i2c3: I2C3(()),
+            i2c4: I2C4(()),
+            lsadc: LSADC(()),
+            spi0: SPI0(()),
+            spi1: SPI1(()),
+            spi2: SPI2(()),
+            pwm0: PWM0(()),
+            timer: TIMER(()),
+            wdt0: WDT0(()),
+            wdt1: WDT1(()),
+        }
+    }
 }
 
 // Used by macros only.
@@ -70,17 +218,9 @@ pub struct Peripherals {
 #[doc(hidden)]
 #[inline(always)]
 pub fn __rom_init_params() -> (Peripherals, Clocks) {
-    let peripherals = Peripherals {
-        iomux: Pads::new(),
-        gpio0: GPIO0(()),
-        gpio1: GPIO1(()),
-        uart0: UART0(()),
-        uart1: UART1(()),
-        uart2: UART2(()),
-        uart3: UART3(()),
-        uart4: UART4(()),
-        spi0: SPI0(()),
-        pwm0: PWM0(()),
-    };
-    (peripherals, Clocks)
+    TAKEN.store(true, Ordering::Release);
+    let peripherals = unsafe { Peripherals::steal() };
+    let clocks = Clocks;
+    unsafe { crate::__post_clock_init() };
+    (peripherals, clocks)
 }
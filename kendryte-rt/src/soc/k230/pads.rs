@@ -30,6 +30,18 @@ impl<const N: usize> Pad<N> {
     fn new() -> Self {
         Pad(())
     }
+
+    /// Steals this pad.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that no driver created from another instance
+    /// of this pad is still in use.
+    #[inline]
+    pub const unsafe fn steal() -> Self {
+        Pad(())
+    }
+
     #[inline]
     pub unsafe fn mmio_register_block() -> pad::MmioRegisterBlock<'static> {
         unsafe {
@@ -107,6 +119,21 @@ pub struct Pads {
 }
 
 impl Pads {
+    /// Reads back the configuration of every pad.
+    ///
+    /// Only reads registers, so it works while the pads are owned by drivers:
+    ///
+    /// ```ignore
+    /// for pad in Pads::configs() {
+    ///     println!("{pad}");
+    /// }
+    /// ```
+    #[inline]
+    pub fn configs() -> iomux::dump::PadConfigs {
+        // SAFETY: the returned iterator never writes the pad registers.
+        iomux::pad_configs(unsafe { IOMUX::mmio_register_block() })
+    }
+
     pub(crate) fn new() -> Self {
         Self {
             io0: Pad::<0>::new(),
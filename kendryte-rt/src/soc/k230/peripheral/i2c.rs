@@ -0,0 +1,43 @@
+use crate::soc::k230::{I2C0, I2C1, I2C2, I2C3, I2C4};
+use kendryte_hal::i2c::MmioRegisterBlock;
+use kendryte_hal::instance::{Instance, Numbered};
+
+macro_rules! i2c {
+    (
+        $(
+            ($I2Cx:ty, $n:literal)
+        ),+ $(,)?
+    ) => {
+        $(
+            impl Instance<'static> for $I2Cx {
+                type R = MmioRegisterBlock<'static>;
+
+                #[inline]
+                fn inner(self) -> Self::R {
+                    unsafe { <$I2Cx>::mmio_register_block() }
+                }
+            }
+
+            impl Numbered<'static, $n> for $I2Cx {}
+
+            impl<'i> Instance<'i> for &'i mut $I2Cx {
+                type R = MmioRegisterBlock<'static>;
+
+                #[inline]
+                fn inner(self) -> Self::R {
+                    unsafe { <$I2Cx>::mmio_register_block() }
+                }
+            }
+
+            impl<'i> Numbered<'i, $n> for &'i mut $I2Cx {}
+        )+
+    };
+}
+
+i2c! {
+    (I2C0, 0),
+    (I2C1, 1),
+    (I2C2, 2),
+    (I2C3, 3),
+    (I2C4, 4),
+}
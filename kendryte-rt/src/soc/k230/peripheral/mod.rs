@@ -1,4 +1,7 @@
 mod gpio;
+mod i2c;
 mod pwm;
 mod spi;
+mod sysctl;
 mod uart;
+mod wdt;
@@ -8,25 +8,17 @@ use kendryte_hal::pwm::RegisterBlock;
 use kendryte_hal::pwm::pad::IntoPwmOut;
 
 impl Instance<'static> for PWM0 {
-    type R = RegisterBlock;
+    type R = &'static RegisterBlock;
     #[inline]
-    fn inner(self) -> &'static Self::R {
-        unsafe { &*PWM0::ptr() }
-    }
-}
-
-impl<'i> Instance<'i> for &'i PWM0 {
-    type R = RegisterBlock;
-    #[inline]
-    fn inner(self) -> &'static Self::R {
+    fn inner(self) -> Self::R {
         unsafe { &*PWM0::ptr() }
     }
 }
 
 impl<'i> Instance<'i> for &'i mut PWM0 {
-    type R = RegisterBlock;
+    type R = &'static RegisterBlock;
     #[inline]
-    fn inner(self) -> &'static Self::R {
+    fn inner(self) -> Self::R {
         unsafe { &*PWM0::ptr() }
     }
 }
@@ -1,4 +1,4 @@
-use crate::soc::k230::SPI0;
+use crate::soc::k230::{SPI0, SPI1, SPI2};
 use kendryte_hal::instance::{Instance, Numbered};
 use kendryte_hal::spi::RegisterBlock;
 
@@ -10,10 +10,10 @@ macro_rules! spi {
     ) => {
         $(
             impl Instance<'static> for $SPIx {
-                type R = RegisterBlock;
+                type R = &'static RegisterBlock;
 
                 #[inline]
-                fn inner(self) -> &'static Self::R {
+                fn inner(self) -> Self::R {
                     unsafe { &*<$SPIx>::ptr() }
                 }
             }
@@ -21,10 +21,10 @@ macro_rules! spi {
             impl Numbered<'static, $n> for $SPIx {}
 
             impl<'i> Instance<'i> for &'i mut $SPIx {
-                type R = RegisterBlock;
+                type R = &'static RegisterBlock;
 
                 #[inline]
-                fn inner(self) -> &'static Self::R {
+                fn inner(self) -> Self::R {
                     unsafe { &*<$SPIx>::ptr() }
                 }
             }
@@ -36,4 +36,6 @@ macro_rules! spi {
 
 spi! {
     (SPI0, 0),
+    (SPI1, 1),
+    (SPI2, 2),
 }
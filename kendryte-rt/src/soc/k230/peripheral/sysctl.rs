@@ -0,0 +1,21 @@
+use crate::soc::k230::SYSCTL;
+use kendryte_hal::instance::Instance;
+use kendryte_hal::sysctl::MmioRegisterBlock;
+
+impl Instance<'static> for SYSCTL {
+    type R = MmioRegisterBlock<'static>;
+
+    #[inline]
+    fn inner(self) -> Self::R {
+        unsafe { SYSCTL::mmio_register_block() }
+    }
+}
+
+impl<'i> Instance<'i> for &'i mut SYSCTL {
+    type R = MmioRegisterBlock<'static>;
+
+    #[inline]
+    fn inner(self) -> Self::R {
+        unsafe { SYSCTL::mmio_register_block() }
+    }
+}
@@ -0,0 +1,29 @@
+use crate::soc::k230::{WDT0, WDT1};
+use kendryte_hal::instance::Instance;
+use kendryte_hal::wdt::MmioRegisterBlock;
+
+macro_rules! wdt {
+    ($($WDTx:ty),+ $(,)?) => {
+        $(
+            impl Instance<'static> for $WDTx {
+                type R = MmioRegisterBlock<'static>;
+
+                #[inline]
+                fn inner(self) -> Self::R {
+                    unsafe { <$WDTx>::mmio_register_block() }
+                }
+            }
+
+            impl<'i> Instance<'i> for &'i mut $WDTx {
+                type R = MmioRegisterBlock<'static>;
+
+                #[inline]
+                fn inner(self) -> Self::R {
+                    unsafe { <$WDTx>::mmio_register_block() }
+                }
+            }
+        )+
+    };
+}
+
+wdt!(WDT0, WDT1);
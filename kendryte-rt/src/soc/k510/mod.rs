@@ -1,6 +1,13 @@
 //! Kendryte K510 chip.
 
+mod pads;
+mod peripheral;
+
 use crate::arch::rvi::Stack;
+use core::sync::atomic::{AtomicBool, Ordering};
+use kendryte_hal::clocks::ClockId;
+use kendryte_hal::clocks::Clocks;
+pub use pads::{Pad, Pads};
 
 /// Platform stack size.
 pub const STACK_SIZE: usize = 32 * 1024;
@@ -10,4 +17,99 @@ pub const STACK_SIZE: usize = 32 * 1024;
 #[unsafe(link_section = ".bss.uninit")]
 pub static mut STACK: Stack<STACK_SIZE> = Stack([0; STACK_SIZE]);
 
-// TODO K510 peripherals using soc! macro
+peripheral! {
+    use kendryte_hal::gpio;
+    use kendryte_hal::iomux;
+    use kendryte_hal::uart;
+    /// Input/Output Multiplexer.
+    pub struct IOMUX => 0x9704_0000, iomux::RegisterBlock, iomux::MmioRegisterBlock<'static>;
+    /// General Purpose Input/Output 0.
+    pub struct GPIO0 => 0x9705_0000, gpio::RegisterBlock, gpio::MmioRegisterBlock<'static>;
+    /// Universal Asynchronous Receiver Transmitter 0.
+    pub struct UART0 => 0x9600_0000, uart::RegisterBlock, uart::MmioRegisterBlock<'static> {
+        clock = ClockId::UartSclk(0)
+    };
+    /// Universal Asynchronous Receiver Transmitter 1.
+    pub struct UART1 => 0x9601_0000, uart::RegisterBlock, uart::MmioRegisterBlock<'static> {
+        clock = ClockId::UartSclk(1)
+    };
+    /// Universal Asynchronous Receiver Transmitter 2.
+    pub struct UART2 => 0x9602_0000, uart::RegisterBlock, uart::MmioRegisterBlock<'static> {
+        clock = ClockId::UartSclk(2)
+    };
+    /// Universal Asynchronous Receiver Transmitter 3.
+    pub struct UART3 => 0x9603_0000, uart::RegisterBlock, uart::MmioRegisterBlock<'static> {
+        clock = ClockId::UartSclk(3)
+    };
+}
+
+/// Peripherals available on ROM start.
+pub struct Peripherals {
+    /// Input/Output Multiplexer.
+    pub iomux: Pads,
+    /// General Purpose Input/Output 0.
+    pub gpio0: GPIO0,
+    /// Universal Asynchronous Receiver Transmitter 0.
+    pub uart0: UART0,
+    /// Universal Asynchronous Receiver Transmitter 1.
+    pub uart1: UART1,
+    /// Universal Asynchronous Receiver Transmitter 2.
+    pub uart2: UART2,
+    /// Universal Asynchronous Receiver Transmitter 3.
+    pub uart3: UART3,
+}
+
+/// Set once the peripherals have been handed out.
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+impl Peripherals {
+    /// Takes the peripherals, returning `None` if they were already taken.
+    #[inline]
+    pub fn try_take() -> Option<Self> {
+        if TAKEN.swap(true, Ordering::AcqRel) {
+            None
+        } else {
+            Some(unsafe { Self::steal() })
+        }
+    }
+
+    /// Takes the peripherals.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the peripherals were already taken.
+    #[inline]
+    pub fn take() -> Self {
+        Self::try_take().expect("peripherals already taken")
+    }
+
+    /// Steals the peripherals regardless of whether they were taken.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that drivers built from previously handed out
+    /// tokens are no longer in use.
+    #[inline]
+    pub unsafe fn steal() -> Self {
+        Peripherals {
+            iomux: Pads::new(),
+            gpio0: GPIO0(()),
+            uart0: UART0(()),
+            uart1: UART1(()),
+            uart2: UART2(()),
+            uart3: UART3(()),
+        }
+    }
+}
+
+// Used by macros only.
+#[allow(unused)]
+#[doc(hidden)]
+#[inline(always)]
+pub fn __rom_init_params() -> (Peripherals, Clocks) {
+    TAKEN.store(true, Ordering::Release);
+    let peripherals = unsafe { Peripherals::steal() };
+    let clocks = Clocks;
+    unsafe { crate::__post_clock_init() };
+    (peripherals, clocks)
+}
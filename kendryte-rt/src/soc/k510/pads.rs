@@ -0,0 +1,329 @@
+use crate::soc::k510::IOMUX;
+use kendryte_hal::iomux;
+use kendryte_hal::iomux::pad;
+use kendryte_hal::iomux::{FlexPad, IntoFlexPad};
+
+/// An IO pad of the K510.
+///
+/// Every pad can be routed to any function of the IOMUX, so the peripheral
+/// pad traits are implemented for all pads of a function rather than from a
+/// table of alternatives.
+pub struct Pad<const N: usize>(());
+
+impl<const N: usize> IntoFlexPad<'static> for Pad<N> {
+    fn into_flex_pad(self) -> FlexPad<'static> {
+        unsafe { FlexPad::new(Pad::<N>::mmio_register_block()) }
+    }
+}
+
+impl<'p, const N: usize> IntoFlexPad<'p> for &'p Pad<N> {
+    fn into_flex_pad(self) -> FlexPad<'p> {
+        unsafe { FlexPad::new(Pad::<N>::mmio_register_block()) }
+    }
+}
+
+impl<'p, const N: usize> IntoFlexPad<'p> for &'p mut Pad<N> {
+    fn into_flex_pad(self) -> FlexPad<'p> {
+        unsafe { FlexPad::new(Pad::<N>::mmio_register_block()) }
+    }
+}
+
+impl<const N: usize> Pad<N> {
+    fn new() -> Self {
+        Pad(())
+    }
+
+    /// Steals this pad.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that no driver created from another instance
+    /// of this pad is still in use.
+    #[inline]
+    pub const unsafe fn steal() -> Self {
+        Pad(())
+    }
+
+    #[inline]
+    pub unsafe fn mmio_register_block() -> pad::MmioRegisterBlock<'static> {
+        unsafe {
+            let mut iomux = IOMUX::mmio_register_block();
+            iomux.steal_pads_unchecked(N)
+        }
+    }
+}
+
+pub struct Pads {
+    pub io0: Pad<0>,
+    pub io1: Pad<1>,
+    pub io2: Pad<2>,
+    pub io3: Pad<3>,
+    pub io4: Pad<4>,
+    pub io5: Pad<5>,
+    pub io6: Pad<6>,
+    pub io7: Pad<7>,
+    pub io8: Pad<8>,
+    pub io9: Pad<9>,
+    pub io10: Pad<10>,
+    pub io11: Pad<11>,
+    pub io12: Pad<12>,
+    pub io13: Pad<13>,
+    pub io14: Pad<14>,
+    pub io15: Pad<15>,
+    pub io16: Pad<16>,
+    pub io17: Pad<17>,
+    pub io18: Pad<18>,
+    pub io19: Pad<19>,
+    pub io20: Pad<20>,
+    pub io21: Pad<21>,
+    pub io22: Pad<22>,
+    pub io23: Pad<23>,
+    pub io24: Pad<24>,
+    pub io25: Pad<25>,
+    pub io26: Pad<26>,
+    pub io27: Pad<27>,
+    pub io28: Pad<28>,
+    pub io29: Pad<29>,
+    pub io30: Pad<30>,
+    pub io31: Pad<31>,
+    pub io32: Pad<32>,
+    pub io33: Pad<33>,
+    pub io34: Pad<34>,
+    pub io35: Pad<35>,
+    pub io36: Pad<36>,
+    pub io37: Pad<37>,
+    pub io38: Pad<38>,
+    pub io39: Pad<39>,
+    pub io40: Pad<40>,
+    pub io41: Pad<41>,
+    pub io42: Pad<42>,
+    pub io43: Pad<43>,
+    pub io44: Pad<44>,
+    pub io45: Pad<45>,
+    pub io46: Pad<46>,
+    pub io47: Pad<47>,
+    pub io48: Pad<48>,
+    pub io49: Pad<49>,
+    pub io50: Pad<50>,
+    pub io51: Pad<51>,
+    pub io52: Pad<52>,
+    pub io53: Pad<53>,
+    pub io54: Pad<54>,
+    pub io55: Pad<55>,
+    pub io56: Pad<56>,
+    pub io57: Pad<57>,
+    pub io58: Pad<58>,
+    pub io59: Pad<59>,
+    pub io60: Pad<60>,
+    pub io61: Pad<61>,
+    pub io62: Pad<62>,
+    pub io63: Pad<63>,
+    pub io64: Pad<64>,
+    pub io65: Pad<65>,
+    pub io66: Pad<66>,
+    pub io67: Pad<67>,
+    pub io68: Pad<68>,
+    pub io69: Pad<69>,
+    pub io70: Pad<70>,
+    pub io71: Pad<71>,
+    pub io72: Pad<72>,
+    pub io73: Pad<73>,
+    pub io74: Pad<74>,
+    pub io75: Pad<75>,
+    pub io76: Pad<76>,
+    pub io77: Pad<77>,
+    pub io78: Pad<78>,
+    pub io79: Pad<79>,
+    pub io80: Pad<80>,
+    pub io81: Pad<81>,
+    pub io82: Pad<82>,
+    pub io83: Pad<83>,
+    pub io84: Pad<84>,
+    pub io85: Pad<85>,
+    pub io86: Pad<86>,
+    pub io87: Pad<87>,
+    pub io88: Pad<88>,
+    pub io89: Pad<89>,
+    pub io90: Pad<90>,
+    pub io91: Pad<91>,
+    pub io92: Pad<92>,
+    pub io93: Pad<93>,
+    pub io94: Pad<94>,
+    pub io95: Pad<95>,
+    pub io96: Pad<96>,
+    pub io97: Pad<97>,
+    pub io98: Pad<98>,
+    pub io99: Pad<99>,
+    pub io100: Pad<100>,
+    pub io101: Pad<101>,
+    pub io102: Pad<102>,
+    pub io103: Pad<103>,
+    pub io104: Pad<104>,
+    pub io105: Pad<105>,
+    pub io106: Pad<106>,
+    pub io107: Pad<107>,
+    pub io108: Pad<108>,
+    pub io109: Pad<109>,
+    pub io110: Pad<110>,
+    pub io111: Pad<111>,
+    pub io112: Pad<112>,
+    pub io113: Pad<113>,
+    pub io114: Pad<114>,
+    pub io115: Pad<115>,
+    pub io116: Pad<116>,
+    pub io117: Pad<117>,
+    pub io118: Pad<118>,
+    pub io119: Pad<119>,
+    pub io120: Pad<120>,
+    pub io121: Pad<121>,
+    pub io122: Pad<122>,
+    pub io123: Pad<123>,
+    pub io124: Pad<124>,
+    pub io125: Pad<125>,
+    pub io126: Pad<126>,
+    pub io127: Pad<127>,
+}
+
+impl Pads {
+    /// Reads back the configuration of every pad.
+    ///
+    /// Only reads registers, so it works while the pads are owned by drivers.
+    #[inline]
+    pub fn configs() -> iomux::dump::PadConfigs {
+        // SAFETY: the returned iterator never writes the pad registers.
+        iomux::pad_configs(unsafe { IOMUX::mmio_register_block() })
+    }
+
+    pub(crate) fn new() -> Self {
+        Self {
+            io0: Pad::<0>::new(),
+            io1: Pad::<1>::new(),
+            io2: Pad::<2>::new(),
+            io3: Pad::<3>::new(),
+            io4: Pad::<4>::new(),
+            io5: Pad::<5>::new(),
+            io6: Pad::<6>::new(),
+            io7: Pad::<7>::new(),
+            io8: Pad::<8>::new(),
+            io9: Pad::<9>::new(),
+            io10: Pad::<10>::new(),
+            io11: Pad::<11>::new(),
+            io12: Pad::<12>::new(),
+            io13: Pad::<13>::new(),
+            io14: Pad::<14>::new(),
+            io15: Pad::<15>::new(),
+            io16: Pad::<16>::new(),
+            io17: Pad::<17>::new(),
+            io18: Pad::<18>::new(),
+            io19: Pad::<19>::new(),
+            io20: Pad::<20>::new(),
+            io21: Pad::<21>::new(),
+            io22: Pad::<22>::new(),
+            io23: Pad::<23>::new(),
+            io24: Pad::<24>::new(),
+            io25: Pad::<25>::new(),
+            io26: Pad::<26>::new(),
+            io27: Pad::<27>::new(),
+            io28: Pad::<28>::new(),
+            io29: Pad::<29>::new(),
+            io30: Pad::<30>::new(),
+            io31: Pad::<31>::new(),
+            io32: Pad::<32>::new(),
+            io33: Pad::<33>::new(),
+            io34: Pad::<34>::new(),
+            io35: Pad::<35>::new(),
+            io36: Pad::<36>::new(),
+            io37: Pad::<37>::new(),
+            io38: Pad::<38>::new(),
+            io39: Pad::<39>::new(),
+            io40: Pad::<40>::new(),
+            io41: Pad::<41>::new(),
+            io42: Pad::<42>::new(),
+            io43: Pad::<43>::new(),
+            io44: Pad::<44>::new(),
+            io45: Pad::<45>::new(),
+            io46: Pad::<46>::new(),
+            io47: Pad::<47>::new(),
+            io48: Pad::<48>::new(),
+            io49: Pad::<49>::new(),
+            io50: Pad::<50>::new(),
+            io51: Pad::<51>::new(),
+            io52: Pad::<52>::new(),
+            io53: Pad::<53>::new(),
+            io54: Pad::<54>::new(),
+            io55: Pad::<55>::new(),
+            io56: Pad::<56>::new(),
+            io57: Pad::<57>::new(),
+            io58: Pad::<58>::new(),
+            io59: Pad::<59>::new(),
+            io60: Pad::<60>::new(),
+            io61: Pad::<61>::new(),
+            io62: Pad::<62>::new(),
+            io63: Pad::<63>::new(),
+            io64: Pad::<64>::new(),
+            io65: Pad::<65>::new(),
+            io66: Pad::<66>::new(),
+            io67: Pad::<67>::new(),
+            io68: Pad::<68>::new(),
+            io69: Pad::<69>::new(),
+            io70: Pad::<70>::new(),
+            io71: Pad::<71>::new(),
+            io72: Pad::<72>::new(),
+            io73: Pad::<73>::new(),
+            io74: Pad::<74>::new(),
+            io75: Pad::<75>::new(),
+            io76: Pad::<76>::new(),
+            io77: Pad::<77>::new(),
+            io78: Pad::<78>::new(),
+            io79: Pad::<79>::new(),
+            io80: Pad::<80>::new(),
+            io81: Pad::<81>::new(),
+            io82: Pad::<82>::new(),
+            io83: Pad::<83>::new(),
+            io84: Pad::<84>::new(),
+            io85: Pad::<85>::new(),
+            io86: Pad::<86>::new(),
+            io87: Pad::<87>::new(),
+            io88: Pad::<88>::new(),
+            io89: Pad::<89>::new(),
+            io90: Pad::<90>::new(),
+            io91: Pad::<91>::new(),
+            io92: Pad::<92>::new(),
+            io93: Pad::<93>::new(),
+            io94: Pad::<94>::new(),
+            io95: Pad::<95>::new(),
+            io96: Pad::<96>::new(),
+            io97: Pad::<97>::new(),
+            io98: Pad::<98>::new(),
+            io99: Pad::<99>::new(),
+            io100: Pad::<100>::new(),
+            io101: Pad::<101>::new(),
+            io102: Pad::<102>::new(),
+            io103: Pad::<103>::new(),
+            io104: Pad::<104>::new(),
+            io105: Pad::<105>::new(),
+            io106: Pad::<106>::new(),
+            io107: Pad::<107>::new(),
+            io108: Pad::<108>::new(),
+            io109: Pad::<109>::new(),
+            io110: Pad::<110>::new(),
+            io111: Pad::<111>::new(),
+            io112: Pad::<112>::new(),
+            io113: Pad::<113>::new(),
+            io114: Pad::<114>::new(),
+            io115: Pad::<115>::new(),
+            io116: Pad::<116>::new(),
+            io117: Pad::<117>::new(),
+            io118: Pad::<118>::new(),
+            io119: Pad::<119>::new(),
+            io120: Pad::<120>::new(),
+            io121: Pad::<121>::new(),
+            io122: Pad::<122>::new(),
+            io123: Pad::<123>::new(),
+            io124: Pad::<124>::new(),
+            io125: Pad::<125>::new(),
+            io126: Pad::<126>::new(),
+            io127: Pad::<127>::new(),
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use crate::soc::k510::GPIO0;
+use crate::soc::k510::pads::Pad;
+use kendryte_hal::gpio::pad::IntoGpio;
+use kendryte_hal::gpio::{GpioPort, MmioRegisterBlock};
+use kendryte_hal::instance::{Instance, Numbered};
+use kendryte_hal::iomux::ops::PadOps;
+use kendryte_hal::iomux::{FlexPad, IntoFlexPad};
+
+macro_rules! gpio {
+    (
+        $(
+            ($GPIOx:ty, $n:literal)
+        ),+ $(,)?
+    ) => {
+        $(
+            impl Instance<'static> for $GPIOx {
+                type R = MmioRegisterBlock<'static>;
+
+                #[inline]
+                fn inner(self) -> Self::R {
+                    unsafe { <$GPIOx>::mmio_register_block() }
+                }
+            }
+
+            impl Numbered<'static, $n> for $GPIOx {}
+
+            impl<'i> Instance<'i> for &'i $GPIOx {
+                type R = MmioRegisterBlock<'static>;
+
+                #[inline]
+                fn inner(self) -> Self::R {
+                    unsafe { <$GPIOx>::mmio_register_block() }
+                }
+            }
+
+            impl<'i> Numbered<'i, $n> for &'i $GPIOx {}
+
+            impl<'i> Instance<'i> for &'i mut $GPIOx {
+                type R = MmioRegisterBlock<'static>;
+
+                #[inline]
+                fn inner(self) -> Self::R {
+                    unsafe { <$GPIOx>::mmio_register_block() }
+                }
+            }
+
+            impl<'i> Numbered<'i, $n> for &'i mut $GPIOx {}
+        )+
+    };
+}
+
+gpio! {
+    (GPIO0, 0),
+}
+
+// NOTE: The function numbers below are placeholders and must be verified against the K510 TRM.
+
+/// IOMUX function of GPIO pin 0; pin `n` is this plus `n`.
+const GPIO_FUNCTION: u8 = 32;
+
+macro_rules! pad_gpio {
+    (
+        $(
+           ($pad_num:expr, $pin_num:expr)
+        ),+ $(,)?
+    ) => {
+        $(
+            impl IntoGpio<'static, 0> for Pad<$pad_num> {
+                const PORT: GpioPort = GpioPort::A;
+                const PIN_NUM: usize = $pin_num;
+
+                #[inline]
+                fn into_gpio(self) -> FlexPad<'static> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_bidirectional()
+                        .set_function_select(GPIO_FUNCTION + $pin_num);
+                    flex_pad
+                }
+            }
+
+            impl<'p> IntoGpio<'p, 0> for &'p Pad<$pad_num> {
+                const PORT: GpioPort = GpioPort::A;
+                const PIN_NUM: usize = $pin_num;
+
+                #[inline]
+                fn into_gpio(self) -> FlexPad<'p> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_bidirectional()
+                        .set_function_select(GPIO_FUNCTION + $pin_num);
+                    flex_pad
+                }
+            }
+
+            impl<'p> IntoGpio<'p, 0> for &'p mut Pad<$pad_num> {
+                const PORT: GpioPort = GpioPort::A;
+                const PIN_NUM: usize = $pin_num;
+
+                #[inline]
+                fn into_gpio(self) -> FlexPad<'p> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_bidirectional()
+                        .set_function_select(GPIO_FUNCTION + $pin_num);
+                    flex_pad
+                }
+            }
+        )+
+    };
+}
+
+// The controller has 32 pins; pad n is wired to pin n.
+pad_gpio! {
+    (0, 0),
+    (1, 1),
+    (2, 2),
+    (3, 3),
+    (4, 4),
+    (5, 5),
+    (6, 6),
+    (7, 7),
+    (8, 8),
+    (9, 9),
+    (10, 10),
+    (11, 11),
+    (12, 12),
+    (13, 13),
+    (14, 14),
+    (15, 15),
+    (16, 16),
+    (17, 17),
+    (18, 18),
+    (19, 19),
+    (20, 20),
+    (21, 21),
+    (22, 22),
+    (23, 23),
+    (24, 24),
+    (25, 25),
+    (26, 26),
+    (27, 27),
+    (28, 28),
+    (29, 29),
+    (30, 30),
+    (31, 31),
+}
@@ -0,0 +1,2 @@
+mod gpio;
+mod uart;
@@ -0,0 +1,98 @@
+use crate::soc::k510::pads::Pad;
+use crate::soc::k510::{UART0, UART1, UART2, UART3};
+use kendryte_hal::instance::{Instance, Numbered};
+use kendryte_hal::iomux::ops::PadOps;
+use kendryte_hal::iomux::{FlexPad, IntoFlexPad};
+use kendryte_hal::uart::MmioRegisterBlock;
+use kendryte_hal::uart::pad::{IntoUartSin, IntoUartSout};
+
+macro_rules! uart {
+    (
+        $(
+            ($UARTx:ty, $n:literal)
+        ),+ $(,)?
+    ) => {
+        $(
+            impl Instance<'static> for $UARTx {
+                type R = MmioRegisterBlock<'static>;
+
+                #[inline]
+                fn inner(self) -> Self::R {
+                    unsafe { <$UARTx>::mmio_register_block() }
+                }
+            }
+
+            impl Numbered<'static, $n> for $UARTx {}
+
+            impl<'i> Instance<'i> for &'i mut $UARTx {
+                type R = MmioRegisterBlock<'static>;
+
+                #[inline]
+                fn inner(self) -> Self::R {
+                    unsafe { <$UARTx>::mmio_register_block() }
+                }
+            }
+
+            impl<'i> Numbered<'i, $n> for &'i mut $UARTx {}
+        )+
+    };
+}
+
+uart! {
+    (UART0, 0),
+    (UART1, 1),
+    (UART2, 2),
+    (UART3, 3),
+}
+
+// NOTE: The function numbers below are placeholders and must be verified against the K510 TRM.
+
+macro_rules! pad_uart {
+    (
+        $(
+            ($uart_num:expr, $sin_function:expr, $sout_function:expr)
+        ),+ $(,)?
+    ) => {
+        $(
+            impl<const P: usize> IntoUartSout<'static, $uart_num> for Pad<P> {
+                fn into_uart_sout(self) -> FlexPad<'static> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_output().set_function_select($sout_function);
+                    flex_pad
+                }
+            }
+
+            impl<'p, const P: usize> IntoUartSout<'p, $uart_num> for &'p mut Pad<P> {
+                fn into_uart_sout(self) -> FlexPad<'p> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_output().set_function_select($sout_function);
+                    flex_pad
+                }
+            }
+
+            impl<const P: usize> IntoUartSin<'static, $uart_num> for Pad<P> {
+                fn into_uart_sin(self) -> FlexPad<'static> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_input().set_function_select($sin_function);
+                    flex_pad
+                }
+            }
+
+            impl<'p, const P: usize> IntoUartSin<'p, $uart_num> for &'p mut Pad<P> {
+                fn into_uart_sin(self) -> FlexPad<'p> {
+                    let mut flex_pad = self.into_flex_pad();
+                    flex_pad.set_input().set_function_select($sin_function);
+                    flex_pad
+                }
+            }
+        )+
+    };
+}
+
+// Any pad can carry a UART signal.
+pad_uart! {
+    (0, 96, 97),
+    (1, 98, 99),
+    (2, 100, 101),
+    (3, 102, 103),
+}
@@ -1,5 +1,11 @@
 //! System on Chip (SoC) modules for Kendryte chips.
+//!
+//! Only the module of the selected chip is built, as the pad and register
+//! layouts of kendryte-hal follow the chip feature.
 
+#[cfg(feature = "k210")]
+pub mod k210;
+#[cfg(not(any(feature = "k510", feature = "k210")))]
 pub mod k230;
-// TODO pub mod k510;
-// TODO pub mod k210;
+#[cfg(feature = "k510")]
+pub mod k510;
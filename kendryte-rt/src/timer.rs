@@ -0,0 +1,190 @@
+//! Software timers multiplexed over the machine timer interrupt.
+//!
+//! A [`Timers`] table holds a fixed number of one-shot and periodic
+//! callbacks and keeps `mtimecmp` programmed for the earliest of them, so
+//! blinking, keepalives and timeouts share the single machine timer without
+//! allocation. Place the table in a `static` and call
+//! [`Timers::on_interrupt`] from the machine timer interrupt handler:
+//!
+//! ```ignore
+//! static TIMERS: Timers<4> = Timers::new();
+//!
+//! fn blink() { /* toggle the LED */ }
+//!
+//! TIMERS.every(500_000, blink);
+//! kendryte_rt::interrupt::enable();
+//! ```
+//!
+//! Callbacks run in interrupt context and may start or cancel timers.
+
+use core::cell::UnsafeCell;
+use kendryte_hal::soc::TIMER_FREQUENCY;
+use kendryte_hal::time::now;
+
+#[cfg(feature = "k210")]
+use crate::soc::k210::CLINT_BASE;
+#[cfg(feature = "k230")]
+use crate::soc::k230::CLINT_BASE;
+
+/// `mtimecmp` of hart 0.
+const MTIMECMP: *mut u64 = (CLINT_BASE + 0x4000) as *mut u64;
+
+/// Handle to a started timer, used to cancel it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimerId {
+    slot: usize,
+    generation: u32,
+}
+
+#[derive(Clone, Copy)]
+struct Entry {
+    callback: Option<fn()>,
+    deadline: u64,
+    /// Reload interval in ticks, 0 for one-shot timers.
+    period: u64,
+    generation: u32,
+}
+
+const EMPTY: Entry = Entry {
+    callback: None,
+    deadline: 0,
+    period: 0,
+    generation: 0,
+};
+
+/// Fixed capacity table of `N` software timers.
+pub struct Timers<const N: usize> {
+    entries: UnsafeCell<[Entry; N]>,
+}
+
+// SAFETY: `entries` is only accessed with machine interrupts disabled.
+unsafe impl<const N: usize> Sync for Timers<N> {}
+
+impl<const N: usize> Timers<N> {
+    /// Creates a table with no running timers.
+    pub const fn new() -> Self {
+        Self {
+            entries: UnsafeCell::new([EMPTY; N]),
+        }
+    }
+
+    /// Calls `callback` once after `us` microseconds.
+    ///
+    /// Returns `None` if all `N` timers are in use.
+    pub fn after(&self, us: u32, callback: fn()) -> Option<TimerId> {
+        self.start(ticks(us), 0, callback)
+    }
+
+    /// Calls `callback` every `us` microseconds, starting `us` from now.
+    ///
+    /// Returns `None` if all `N` timers are in use.
+    pub fn every(&self, us: u32, callback: fn()) -> Option<TimerId> {
+        let period = ticks(us).max(1);
+        self.start(period, period, callback)
+    }
+
+    /// Stops a timer. Does nothing if it already fired or was cancelled.
+    pub fn cancel(&self, id: TimerId) {
+        self.with_entries(|entries| {
+            let entry = &mut entries[id.slot];
+            if entry.callback.is_some() && entry.generation == id.generation {
+                entry.callback = None;
+            }
+            program(entries);
+        });
+    }
+
+    /// Returns true while the timer is pending.
+    pub fn is_active(&self, id: TimerId) -> bool {
+        self.with_entries(|entries| {
+            let entry = &entries[id.slot];
+            entry.callback.is_some() && entry.generation == id.generation
+        })
+    }
+
+    /// Runs the callbacks of expired timers and re-arms the machine timer.
+    ///
+    /// Call from the machine timer interrupt handler.
+    pub fn on_interrupt(&self) {
+        let mut due = [None; N];
+        self.with_entries(|entries| {
+            let now = now();
+            for (entry, due) in entries.iter_mut().zip(due.iter_mut()) {
+                let Some(callback) = entry.callback else {
+                    continue;
+                };
+                if entry.deadline > now {
+                    continue;
+                }
+                *due = Some(callback);
+                if entry.period == 0 {
+                    entry.callback = None;
+                } else {
+                    // Skip missed periods instead of firing in a burst.
+                    entry.deadline += entry.period;
+                    if entry.deadline <= now {
+                        entry.deadline = now + entry.period;
+                    }
+                }
+            }
+            program(entries);
+        });
+        for callback in due.into_iter().flatten() {
+            callback();
+        }
+    }
+
+    fn start(&self, delay: u64, period: u64, callback: fn()) -> Option<TimerId> {
+        let id = self.with_entries(|entries| {
+            let slot = entries.iter().position(|e| e.callback.is_none())?;
+            let entry = &mut entries[slot];
+            entry.generation = entry.generation.wrapping_add(1);
+            entry.callback = Some(callback);
+            entry.deadline = now().saturating_add(delay);
+            entry.period = period;
+            program(entries);
+            Some(TimerId {
+                slot,
+                generation: entry.generation,
+            })
+        })?;
+        // Set MTIE so the machine timer interrupt reaches the handler.
+        unsafe { core::arch::asm!("csrrs zero, mie, {}", const 1 << 7, options(nostack)) };
+        Some(id)
+    }
+
+    /// Runs `f` on the entries with machine interrupts disabled.
+    fn with_entries<R>(&self, f: impl FnOnce(&mut [Entry; N]) -> R) -> R {
+        let mstatus: usize;
+        // Clear MIE, keeping the previous value to restore it afterwards.
+        unsafe { core::arch::asm!("csrrci {}, mstatus, 8", out(reg) mstatus, options(nostack)) };
+        // SAFETY: interrupts are disabled, so this is the only reference.
+        let result = f(unsafe { &mut *self.entries.get() });
+        if mstatus & 8 != 0 {
+            unsafe { core::arch::asm!("csrsi mstatus, 8", options(nostack)) };
+        }
+        result
+    }
+}
+
+impl<const N: usize> Default for Timers<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Program `mtimecmp` for the earliest pending deadline.
+fn program(entries: &[Entry]) {
+    let next = entries
+        .iter()
+        .filter(|e| e.callback.is_some())
+        .map(|e| e.deadline)
+        .min()
+        .unwrap_or(u64::MAX);
+    unsafe { MTIMECMP.write_volatile(next) };
+}
+
+#[inline]
+fn ticks(us: u32) -> u64 {
+    us as u64 * TIMER_FREQUENCY as u64 / 1_000_000
+}
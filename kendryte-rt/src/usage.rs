@@ -0,0 +1,207 @@
+//! Stack and heap usage at runtime.
+//!
+//! With the `stack-usage` feature the entry code fills the free runtime
+//! stack with a known pattern before `main`. [`stack_usage`] scans for the
+//! deepest byte written since, which gives the most stack used so far, so
+//! [`STACK_SIZE`](crate::STACK_SIZE) can be chosen from a measurement
+//! instead of a guess. Interrupt handlers run on the same stack; let the
+//! application go through its worst case paths, interrupts included, before
+//! reading it.
+//!
+//! With the `heap-stats` feature, [`TrackingHeap`] wraps the global
+//! allocator and counts the bytes in use and their peak, read with
+//! [`heap_usage`]:
+//!
+//! ```ignore
+//! use embedded_alloc::LlffHeap;
+//! use kendryte_rt::usage::{self, TrackingHeap};
+//!
+//! #[global_allocator]
+//! static HEAP: TrackingHeap<LlffHeap> = TrackingHeap::new(LlffHeap::empty());
+//!
+//! // After running the workload:
+//! usage::report();
+//! ```
+//!
+//! [`report`] prints both to the global console.
+
+#[cfg(feature = "heap-stats")]
+use core::alloc::{GlobalAlloc, Layout};
+use core::fmt;
+#[cfg(feature = "heap-stats")]
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Peak use of a stack.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StackUsage {
+    /// Size of the stack in bytes.
+    pub size: usize,
+    /// Most bytes in use at once since the stack was painted.
+    pub peak: usize,
+}
+
+impl StackUsage {
+    /// Bytes never used since the stack was painted.
+    pub fn headroom(&self) -> usize {
+        self.size.saturating_sub(self.peak)
+    }
+
+    /// Peak use as a percentage from 0 to 100.
+    pub fn percent(&self) -> u8 {
+        if self.size == 0 {
+            return 0;
+        }
+        (self.peak.min(self.size) * 100 / self.size) as u8
+    }
+}
+
+impl fmt::Display for StackUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "stack: {} of {} bytes used at peak ({}%), {} bytes headroom",
+            self.peak,
+            self.size,
+            self.percent(),
+            self.headroom()
+        )
+    }
+}
+
+/// Peak use of the runtime stack since boot.
+#[cfg(all(
+    feature = "stack-usage",
+    any(feature = "k230", feature = "k510", feature = "k210")
+))]
+pub fn stack_usage() -> StackUsage {
+    StackUsage {
+        size: crate::STACK_SIZE,
+        peak: unsafe { (*core::ptr::addr_of!(crate::STACK)).high_water_mark() },
+    }
+}
+
+/// Heap use recorded by [`TrackingHeap`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HeapUsage {
+    /// Bytes allocated and not yet freed.
+    pub used: usize,
+    /// Most bytes allocated at once since boot or [`reset_heap_peak`].
+    pub peak: usize,
+    /// Allocations not yet freed.
+    pub allocations: usize,
+    /// Allocation requests the allocator could not satisfy.
+    pub failures: usize,
+}
+
+impl fmt::Display for HeapUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "heap: {} bytes in {} allocations, {} bytes at peak, {} failed",
+            self.used, self.allocations, self.peak, self.failures
+        )
+    }
+}
+
+#[cfg(feature = "heap-stats")]
+static HEAP_USED: AtomicUsize = AtomicUsize::new(0);
+#[cfg(feature = "heap-stats")]
+static HEAP_PEAK: AtomicUsize = AtomicUsize::new(0);
+#[cfg(feature = "heap-stats")]
+static HEAP_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+#[cfg(feature = "heap-stats")]
+static HEAP_FAILURES: AtomicUsize = AtomicUsize::new(0);
+
+/// Global allocator wrapper recording heap use.
+///
+/// Counts are kept in statics, as there is only one global allocator, and
+/// include only the sizes requested, not the allocator's own overhead.
+#[cfg(feature = "heap-stats")]
+pub struct TrackingHeap<A> {
+    inner: A,
+}
+
+#[cfg(feature = "heap-stats")]
+impl<A> TrackingHeap<A> {
+    /// Wraps `inner`.
+    pub const fn new(inner: A) -> Self {
+        Self { inner }
+    }
+
+    /// Returns the wrapped allocator, for example to initialize it.
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+}
+
+#[cfg(feature = "heap-stats")]
+fn record(ptr: *mut u8, freed: usize, allocated: usize) {
+    if ptr.is_null() {
+        HEAP_FAILURES.fetch_add(1, Ordering::Relaxed);
+        return;
+    }
+    let used = HEAP_USED.fetch_add(allocated, Ordering::Relaxed) + allocated;
+    HEAP_USED.fetch_sub(freed, Ordering::Relaxed);
+    HEAP_PEAK.fetch_max(used.saturating_sub(freed), Ordering::Relaxed);
+}
+
+#[cfg(feature = "heap-stats")]
+unsafe impl<A: GlobalAlloc> GlobalAlloc for TrackingHeap<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { self.inner.alloc(layout) };
+        if !ptr.is_null() {
+            HEAP_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+        record(ptr, 0, layout.size());
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { self.inner.alloc_zeroed(layout) };
+        if !ptr.is_null() {
+            HEAP_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+        record(ptr, 0, layout.size());
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.inner.dealloc(ptr, layout) };
+        HEAP_ALLOCATIONS.fetch_sub(1, Ordering::Relaxed);
+        HEAP_USED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = unsafe { self.inner.realloc(ptr, layout, new_size) };
+        record(new, layout.size(), new_size);
+        new
+    }
+}
+
+/// Heap use since boot.
+#[cfg(feature = "heap-stats")]
+pub fn heap_usage() -> HeapUsage {
+    HeapUsage {
+        used: HEAP_USED.load(Ordering::Relaxed),
+        peak: HEAP_PEAK.load(Ordering::Relaxed),
+        allocations: HEAP_ALLOCATIONS.load(Ordering::Relaxed),
+        failures: HEAP_FAILURES.load(Ordering::Relaxed),
+    }
+}
+
+/// Start a new peak measurement from the bytes in use now.
+#[cfg(feature = "heap-stats")]
+pub fn reset_heap_peak() {
+    HEAP_PEAK.store(HEAP_USED.load(Ordering::Relaxed), Ordering::Relaxed);
+}
+
+/// Print the usage of every enabled measurement to the global console.
+pub fn report() {
+    #[cfg(all(
+        feature = "stack-usage",
+        any(feature = "k230", feature = "k510", feature = "k210")
+    ))]
+    crate::println!("{}", stack_usage());
+    #[cfg(feature = "heap-stats")]
+    crate::println!("{}", heap_usage());
+}
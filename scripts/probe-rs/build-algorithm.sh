@@ -0,0 +1,30 @@
+#!/usr/bin/env bash
+# ------------------------------------------------------------
+# Build the K230 SPI NOR flash algorithm and write it into the
+# probe-rs target description next to this script.
+#
+# Usage (from the workspace root):
+#   scripts/probe-rs/build-algorithm.sh
+#
+# Dependencies:
+#   1. rustup target add riscv64gc-unknown-none-elf
+#   2. target-gen from probe-rs:  cargo install target-gen
+# ------------------------------------------------------------
+set -euo pipefail
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+ROOT="$(cd "$SCRIPT_DIR/../.." && pwd)"
+TARGET=riscv64gc-unknown-none-elf
+
+if ! command -v target-gen >/dev/null 2>&1; then
+  echo "error: target-gen not found, install it with: cargo install target-gen" >&2
+  exit 1
+fi
+
+cargo build --manifest-path "$ROOT/Cargo.toml" -p k230-flash-algorithm \
+  --target "$TARGET" --release
+
+target-gen elf --fixed-load-address --update --name k230-spi-nor \
+  "$ROOT/target/$TARGET/release/k230-flash-algorithm" "$SCRIPT_DIR/K230.yaml"
+
+echo "Updated $SCRIPT_DIR/K230.yaml"
@@ -0,0 +1,41 @@
+/*
+ * Flash algorithm layout expected by probe-rs `target-gen`.
+ *
+ * Everything the algorithm touches lives in `PrgCode`, linked at the start
+ * of on-chip SRAM where probe-rs loads it (`--fixed-load-address`). The
+ * stack and the page buffer are placed behind it by the debug host.
+ */
+OUTPUT_ARCH(riscv)
+
+SECTIONS
+{
+    . = 0x80200000;
+
+    PrgCode : ALIGN(4) {
+        KEEP(*(.entry .entry.*))
+        *(.text .text.*)
+        *(.rodata .rodata.*)
+        *(.srodata .srodata.*)
+        *(.data .data.*)
+        *(.sdata .sdata.*)
+        *(.sbss .sbss.*)
+        *(.bss .bss.*)
+        . = ALIGN(4);
+    }
+
+    /* Kept for tools that expect the section, unused by the algorithm. */
+    PrgData : ALIGN(4) {
+        KEEP(*(PrgData))
+        . = ALIGN(4);
+    }
+
+    /* Device description read by `target-gen`, never by the algorithm. */
+    DevDscr : ALIGN(4) {
+        KEEP(*(DeviceData))
+        . = ALIGN(4);
+    }
+
+    /DISCARD/ : {
+        *(.eh_frame .eh_frame_hdr)
+    }
+}
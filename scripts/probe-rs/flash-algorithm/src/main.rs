@@ -0,0 +1,218 @@
+//! probe-rs flash algorithm for SPI NOR flash on the K230.
+//!
+//! The debug host loads this program into on-chip SRAM and calls it to
+//! erase and program the boot flash, so images can be written over JTAG
+//! instead of through the boot ROM's serial download. The flash is not
+//! memory mapped; probe-rs addresses it through a window starting at
+//! [`FLASH_BASE`], and the algorithm turns window addresses into flash
+//! offsets for [`SpiNor`].
+//!
+//! There is no runtime crate here, since its entry code and linker script
+//! would get in the way of the debug host. The peripheral tokens are
+//! defined locally and the pads are configured directly. The wiring matches
+//! the SPI0 pads known to `kendryte-rt` and the `spi-demo` example, with
+//! chip select driven as a GPIO so it stays asserted across each command.
+//! Boards with the flash on other pads need the constants below changed.
+//!
+//! Build and regenerate the target description with
+//! `scripts/probe-rs/build-algorithm.sh`.
+
+#![no_std]
+#![no_main]
+
+use arbitrary_int::u3;
+use core::num::NonZeroU32;
+use embedded_storage::nor_flash::NorFlash;
+use flash_algorithm::*;
+use kendryte_hal::clocks::Clocks;
+use kendryte_hal::flash::{FlashError, SpiNor};
+use kendryte_hal::gpio::pad::IntoGpio;
+use kendryte_hal::gpio::{self, DriveStrength, GpioPort, Output, PinState};
+use kendryte_hal::instance::{Instance, Numbered};
+use kendryte_hal::iomux::ops::PadOps;
+use kendryte_hal::iomux::{self, FlexPad};
+use kendryte_hal::spi::{self, CsDevice, Spi, SpiError};
+
+/// Start of the address window probe-rs uses for the flash.
+///
+/// Must match the `Nvm` region in `K230.yaml`.
+const FLASH_BASE: u32 = 0xC000_0000;
+/// Size of the address window; larger parts are only programmed this far.
+const FLASH_SIZE: u32 = 0x0100_0000;
+/// Program page size of every SFDP part this crate supports.
+const PAGE_SIZE: u32 = 0x100;
+/// Erase unit used by [`SpiNor`].
+const SECTOR_SIZE: u32 = 0x1000;
+
+/// Serial clock of the flash; low enough for long wires to a header.
+const SPI_FREQUENCY: u32 = 10_000_000;
+
+const IOMUX_BASE: usize = 0x9110_5000;
+const GPIO1_BASE: usize = 0x9140_C000;
+const SPI0_BASE: usize = 0x9158_4000;
+
+/// SPI0 serial clock, data out and data in pads, and their function select.
+const CLK_PAD: usize = 40;
+const MOSI_PAD: usize = 41;
+const MISO_PAD: usize = 39;
+const SPI_FUNCTION: u8 = 2;
+/// Chip select pad, driven as GPIO1 port A pin 6.
+const CS_PAD: usize = 38;
+
+// Error codes returned to the debug host, which only reports them.
+const ERROR_PROBE: u32 = 1;
+const ERROR_SPI: u32 = 2;
+const ERROR_UNSUPPORTED: u32 = 3;
+const ERROR_NOT_ALIGNED: u32 = 4;
+const ERROR_OUT_OF_BOUNDS: u32 = 5;
+const ERROR_TIMEOUT: u32 = 6;
+
+algorithm!(Algorithm, {
+    device_name: "k230-spi-nor",
+    device_type: DeviceType::ExtSpi,
+    flash_address: FLASH_BASE,
+    flash_size: FLASH_SIZE,
+    page_size: PAGE_SIZE,
+    empty_value: 0xFF,
+    program_time_out: 1000,
+    erase_time_out: 2000,
+    sectors: [{
+        size: SECTOR_SIZE,
+        address: 0x0,
+    }]
+});
+
+type Flash = SpiNor<CsDevice<'static, Output<'static, 'static>>>;
+
+struct Algorithm {
+    flash: Flash,
+}
+
+impl FlashAlgorithm for Algorithm {
+    fn new(_address: u32, _clock: u32, _function: Function) -> Result<Self, ErrorCode> {
+        let flash = unsafe { open() }.map_err(error_code)?;
+        Ok(Self { flash })
+    }
+
+    fn erase_all(&mut self) -> Result<(), ErrorCode> {
+        self.flash.erase_chip().map_err(error_code)
+    }
+
+    fn erase_sector(&mut self, address: u32) -> Result<(), ErrorCode> {
+        let offset = offset(address, SECTOR_SIZE)?;
+        self.flash
+            .erase(offset, offset + SECTOR_SIZE)
+            .map_err(error_code)
+    }
+
+    fn program_page(&mut self, address: u32, data: &[u8]) -> Result<(), ErrorCode> {
+        let offset = offset(address, data.len() as u32)?;
+        self.flash.write(offset, data).map_err(error_code)
+    }
+}
+
+/// Configures the pads and SPI0, then probes the flash.
+///
+/// # Safety
+///
+/// Takes over SPI0, GPIO1 and the flash pads without tokens; nothing else
+/// may run on the chip while the algorithm is loaded.
+unsafe fn open() -> Result<Flash, FlashError<SpiError>> {
+    for pad in [CLK_PAD, MOSI_PAD] {
+        unsafe { flex_pad(pad) }
+            .set_output()
+            .set_function_select(u3::new(SPI_FUNCTION));
+    }
+    unsafe { flex_pad(MISO_PAD) }
+        .set_input()
+        .set_function_select(u3::new(SPI_FUNCTION));
+
+    let spi = Spi::new(
+        Spi0,
+        spi::Config {
+            frequency: SPI_FREQUENCY,
+            ..Default::default()
+        },
+        Clocks,
+    );
+    let cs = Output::new(Gpio1, CsPad, PinState::High, DriveStrength::Medium);
+    let device = CsDevice::new(spi, cs).map_err(FlashError::Spi)?;
+    SpiNor::new(device)
+}
+
+/// Translates a window address into a flash offset, checking that the
+/// `len` bytes from it fit in the window.
+fn offset(address: u32, len: u32) -> Result<u32, ErrorCode> {
+    address
+        .checked_sub(FLASH_BASE)
+        .filter(|offset| offset.checked_add(len).is_some_and(|end| end <= FLASH_SIZE))
+        .ok_or(code(ERROR_OUT_OF_BOUNDS))
+}
+
+fn error_code(e: FlashError<SpiError>) -> ErrorCode {
+    code(match e {
+        FlashError::Spi(_) => ERROR_SPI,
+        FlashError::NoSfdp => ERROR_PROBE,
+        FlashError::Unsupported => ERROR_UNSUPPORTED,
+        FlashError::NotAligned => ERROR_NOT_ALIGNED,
+        FlashError::OutOfBounds => ERROR_OUT_OF_BOUNDS,
+        FlashError::Timeout => ERROR_TIMEOUT,
+    })
+}
+
+const fn code(value: u32) -> ErrorCode {
+    match NonZeroU32::new(value) {
+        Some(code) => code,
+        None => panic!("error codes are non-zero"),
+    }
+}
+
+/// Returns the IOMUX registers of pad `n`.
+///
+/// # Safety
+///
+/// The caller must be the only user of the pad.
+unsafe fn flex_pad(n: usize) -> FlexPad<'static> {
+    let mut iomux = unsafe { iomux::RegisterBlock::new_mmio_at(IOMUX_BASE) };
+    FlexPad::new(unsafe { iomux.steal_pads_unchecked(n) })
+}
+
+/// SPI0 token, standing in for the one of `kendryte-rt`.
+struct Spi0;
+
+impl Instance<'static> for Spi0 {
+    type R = &'static spi::RegisterBlock;
+
+    fn inner(self) -> Self::R {
+        unsafe { &*(SPI0_BASE as *const spi::RegisterBlock) }
+    }
+}
+
+impl Numbered<'static, 0> for Spi0 {}
+
+/// GPIO1 token, standing in for the one of `kendryte-rt`.
+struct Gpio1;
+
+impl Instance<'static> for Gpio1 {
+    type R = gpio::MmioRegisterBlock<'static>;
+
+    fn inner(self) -> Self::R {
+        unsafe { gpio::RegisterBlock::new_mmio_at(GPIO1_BASE) }
+    }
+}
+
+impl Numbered<'static, 1> for Gpio1 {}
+
+/// Chip select pad in its GPIO function.
+struct CsPad;
+
+impl IntoGpio<'static, 1> for CsPad {
+    const PORT: GpioPort = GpioPort::A;
+    const PIN_NUM: usize = 6;
+
+    fn into_gpio(self) -> FlexPad<'static> {
+        let mut pad = unsafe { flex_pad(CS_PAD) };
+        pad.set_bidirectional().set_function_select(u3::new(1));
+        pad
+    }
+}
@@ -0,0 +1,26 @@
+#!/usr/bin/env bash
+# ------------------------------------------------------------
+# Cargo runner executing a kendryte-rt example on the Renode K230 model.
+# UART0 and UART3 output is printed to the terminal until Ctrl-C.
+#
+# Usage (from the workspace root):
+#   cargo run -p uart-demo --target riscv64gc-unknown-none-elf --release
+#   scripts/renode/run.sh target/riscv64gc-unknown-none-elf/release/uart-demo
+#
+# CI checks example output with the Robot suite instead:
+#   renode-test scripts/renode/examples.robot
+#
+# Dependencies:
+#   1. Renode 1.15 or newer on PATH (https://renode.io)
+# ------------------------------------------------------------
+set -euo pipefail
+
+if [[ $# -lt 1 ]]; then
+  grep '^# ' "$0" | sed 's/^# \{0,1\}//'; exit 1
+fi
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+ELF="$(cd "$(dirname "$1")" && pwd)/$(basename "$1")"
+
+exec renode --console --disable-xwt \
+  -e "\$elf=@$ELF; include @$SCRIPT_DIR/k230.resc; start"
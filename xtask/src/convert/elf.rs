@@ -1,25 +1,54 @@
+use crate::convert::format::OutputFormat;
 use crate::error::{XtaskError, XtaskResult};
-use crate::generate::image::{EncryptionType, gen_image};
-use object::{Object, ObjectSection, SectionFlags, SectionKind};
+use crate::generate::header::ImageHeader;
+use crate::generate::image::{EncryptionType, gen_image, gen_image_with_header};
+use crate::generate::metadata::{Metadata, fill_trailer};
+use object::elf::{FileHeader32, FileHeader64, PT_LOAD};
+use object::read::elf::{FileHeader, ProgramHeader};
+use object::{Endianness, FileKind, Object, ObjectSection, SectionFlags, SectionKind};
 use std::fs;
 use std::path::Path;
 
 /// Convert an ELF payload to a Kendryte flashable image.
 pub fn elf_to_image_bytes(elf_data: &[u8], encryption: EncryptionType) -> XtaskResult<Vec<u8>> {
-    let bin = elf_to_bin_bytes(elf_data)?;
+    elf_to_image_bytes_with_base(elf_data, None, encryption)
+}
+
+/// Convert an ELF payload to a Kendryte flashable image whose payload starts at `base`.
+///
+/// See [`elf_to_bin_bytes_with_base`] for how `base` is used.
+pub fn elf_to_image_bytes_with_base(
+    elf_data: &[u8],
+    base: Option<u64>,
+    encryption: EncryptionType,
+) -> XtaskResult<Vec<u8>> {
+    let bin = elf_to_bin_bytes_with_base(elf_data, base)?;
     let image = gen_image(&bin, encryption)?;
     Ok(image)
 }
 
 /// Convert an ELF file directly into a flashable image on disk.
+///
+/// With `metadata`, the trailer space at the end of the payload is filled in
+/// before the image is generated. `header` sets the magic and format version checked by
+/// the BootROM. The image is written to flash from offset 0, so `hex`
+/// and `uf2` output is addressed from 0 as well.
 pub fn elf_to_image(
     input: impl AsRef<Path>,
     output: impl AsRef<Path>,
+    base: Option<u64>,
     encryption: EncryptionType,
+    header: &ImageHeader,
+    format: OutputFormat,
+    metadata: Option<&Metadata>,
 ) -> XtaskResult<()> {
     let elf_data = fs::read(&input)?;
-    let image = elf_to_image_bytes(&elf_data, encryption)?;
-    fs::write(output, image)?;
+    let mut bin = elf_to_bin_bytes_with_base(&elf_data, base)?;
+    if let Some(metadata) = metadata {
+        bin = fill_trailer(&bin, metadata)?;
+    }
+    let image = gen_image_with_header(&bin, encryption, header)?;
+    fs::write(output, format.encode(&image, 0)?)?;
     Ok(())
 }
 
@@ -31,6 +60,41 @@ pub fn elf_to_image(
 /// Ref: https://github.com/llvm/llvm-project/blob/main/llvm/lib/ObjCopy/ELF/ELFObjcopy.cpp  `Error
 /// objcopy::elf::executeObjcopyOnBinary()` method
 pub fn elf_to_bin_bytes(elf_data: &[u8]) -> XtaskResult<Vec<u8>> {
+    elf_to_bin_bytes_with_base(elf_data, None)
+}
+
+/// Convert ELF to binary, placing every byte at its load address relative to `base`.
+///
+/// Linked executables are laid out from their `PT_LOAD` program headers: each segment is
+/// written at `p_paddr - base` and gaps between segments are filled with zeros. Without
+/// `base` the lowest segment address is used. Overlapping segments are reported as
+/// warnings and the later segment wins.
+///
+/// Files without loadable segments, such as relocatable objects, fall back to packing
+/// ALLOC sections by file offset, in which case `base` is ignored.
+pub fn elf_to_bin_bytes_with_base(elf_data: &[u8], base: Option<u64>) -> XtaskResult<Vec<u8>> {
+    let (_, data) = elf_to_bin_bytes_with_address(elf_data, base)?;
+    Ok(data)
+}
+
+/// Convert ELF to binary like [`elf_to_bin_bytes_with_base`], also returning the load
+/// address of the first byte.
+///
+/// The address is 0 when the file has no loadable segments.
+pub fn elf_to_bin_bytes_with_address(
+    elf_data: &[u8],
+    base: Option<u64>,
+) -> XtaskResult<(u64, Vec<u8>)> {
+    let segments = match FileKind::parse(elf_data) {
+        Ok(FileKind::Elf32) => get_load_segments::<FileHeader32<Endianness>>(elf_data)?,
+        Ok(FileKind::Elf64) => get_load_segments::<FileHeader64<Endianness>>(elf_data)?,
+        _ => Vec::new(),
+    };
+    if !segments.is_empty() {
+        log_segment_info(&segments);
+        return process_segments(&segments, base);
+    }
+
     // Parse the ELF file
     let elf_file =
         object::File::parse(elf_data).map_err(|e| XtaskError::ElfParseError(e.to_string()))?;
@@ -46,25 +110,122 @@ pub fn elf_to_bin_bytes(elf_data: &[u8]) -> XtaskResult<Vec<u8>> {
     // Create final binary output
     let output_data = process_sections(sections)?;
 
-    Ok(output_data)
+    Ok((0, output_data))
 }
 
 /// Wrapper function for converting ELF to binary, takes input and output file paths
-pub fn elf_to_bin(input_path: impl AsRef<Path>, output_path: impl AsRef<Path>) -> XtaskResult<()> {
+pub fn elf_to_bin(
+    input_path: impl AsRef<Path>,
+    output_path: impl AsRef<Path>,
+    base: Option<u64>,
+    format: OutputFormat,
+) -> XtaskResult<()> {
     // Read the ELF file
     let elf_data = fs::read(input_path)?;
 
     // Convert ELF to binary
-    let bin_data = elf_to_bin_bytes(&elf_data)?;
+    let (address, bin_data) = elf_to_bin_bytes_with_address(&elf_data, base)?;
 
     // Write the binary data to the output file
-    fs::write(output_path, bin_data)?;
+    fs::write(output_path, format.encode(&bin_data, address)?)?;
 
     Ok(())
 }
 
 // The following functions are helpers for elf2bin module
 
+/// File-backed part of a `PT_LOAD` segment.
+struct LoadSegment<'a> {
+    paddr: u64,
+    memsz: u64,
+    data: &'a [u8],
+}
+
+/// Get the `PT_LOAD` segments that have file contents, sorted by physical address.
+fn get_load_segments<'a, Elf: FileHeader<Endian = Endianness>>(
+    elf_data: &'a [u8],
+) -> XtaskResult<Vec<LoadSegment<'a>>> {
+    let parse_error = |e: object::Error| XtaskError::ElfParseError(e.to_string());
+    let header = Elf::parse(elf_data).map_err(parse_error)?;
+    let endian = header.endian().map_err(parse_error)?;
+    let mut segments = Vec::new();
+    for phdr in header
+        .program_headers(endian, elf_data)
+        .map_err(parse_error)?
+    {
+        let file_size: u64 = phdr.p_filesz(endian).into();
+        if phdr.p_type(endian) != PT_LOAD || file_size == 0 {
+            continue;
+        }
+        let data = phdr.data(endian, elf_data).map_err(|()| {
+            XtaskError::ElfParseError("segment data lies outside the file".to_string())
+        })?;
+        segments.push(LoadSegment {
+            paddr: phdr.p_paddr(endian).into(),
+            memsz: phdr.p_memsz(endian).into(),
+            data,
+        });
+    }
+    segments.sort_by_key(|s| s.paddr);
+    Ok(segments)
+}
+
+/// Log segment information using `println`
+fn log_segment_info(segments: &[LoadSegment]) {
+    println!("Found {} loadable segments", segments.len());
+
+    for segment in segments {
+        println!(
+            "Segment: load address 0x{:x} with file size 0x{:x}, memory size 0x{:x}",
+            segment.paddr,
+            segment.data.len(),
+            segment.memsz,
+        );
+    }
+}
+
+/// Lay out segments at their load addresses relative to `base`.
+fn process_segments(segments: &[LoadSegment], base: Option<u64>) -> XtaskResult<(u64, Vec<u8>)> {
+    let base = base.unwrap_or(segments[0].paddr);
+    if let Some(segment) = segments.iter().find(|s| s.paddr < base) {
+        return Err(XtaskError::SegmentBelowBase {
+            address: segment.paddr,
+            base,
+        });
+    }
+    let end = segments
+        .iter()
+        .map(|s| s.paddr + s.data.len() as u64)
+        .max()
+        .unwrap_or(base);
+    let total =
+        usize::try_from(end - base).map_err(|_| XtaskError::SectionSizeOverflow(end - base))?;
+    let mut output = vec![0u8; total];
+
+    let mut previous_end = base;
+    for segment in segments {
+        if segment.paddr < previous_end {
+            eprintln!(
+                "Warning: segment at 0x{:x} overlaps the previous segment ending at 0x{:x}",
+                segment.paddr, previous_end
+            );
+        }
+        let start = (segment.paddr - base) as usize;
+        let end = start + segment.data.len();
+        println!(
+            "Writing segment: paddr=0x{:x} data_len=0x{:x} -> out[0x{:x}..0x{:x}]",
+            segment.paddr,
+            segment.data.len(),
+            start,
+            end
+        );
+        output[start..end].copy_from_slice(segment.data);
+        previous_end = previous_end.max(segment.paddr + segment.data.len() as u64);
+    }
+
+    Ok((base, output))
+}
+
 /// Log section information using `println`
 fn log_section_info(sections: &[object::Section]) {
     println!("Found {} loadable sections", sections.len());
@@ -254,6 +415,79 @@ mod tests {
         );
     }
 
+    /// Build a little-endian RV64 executable with one `PT_LOAD` segment per `(paddr, data)`.
+    fn build_test_executable(segments: &[(u64, &[u8])]) -> Vec<u8> {
+        const EHDR_SIZE: usize = 64;
+        const PHDR_SIZE: usize = 56;
+        let mut elf = Vec::new();
+        elf.extend_from_slice(b"\x7fELF\x02\x01\x01\x00");
+        elf.extend_from_slice(&[0; 8]);
+        elf.extend_from_slice(&object::elf::ET_EXEC.to_le_bytes());
+        elf.extend_from_slice(&object::elf::EM_RISCV.to_le_bytes());
+        elf.extend_from_slice(&1u32.to_le_bytes()); // e_version
+        elf.extend_from_slice(&segments[0].0.to_le_bytes()); // e_entry
+        elf.extend_from_slice(&(EHDR_SIZE as u64).to_le_bytes()); // e_phoff
+        elf.extend_from_slice(&0u64.to_le_bytes()); // e_shoff
+        elf.extend_from_slice(&0u32.to_le_bytes()); // e_flags
+        elf.extend_from_slice(&(EHDR_SIZE as u16).to_le_bytes());
+        elf.extend_from_slice(&(PHDR_SIZE as u16).to_le_bytes());
+        elf.extend_from_slice(&(segments.len() as u16).to_le_bytes());
+        elf.extend_from_slice(&[0; 6]); // e_shentsize, e_shnum, e_shstrndx
+
+        let mut offset = EHDR_SIZE + PHDR_SIZE * segments.len();
+        for (paddr, data) in segments {
+            elf.extend_from_slice(&PT_LOAD.to_le_bytes());
+            elf.extend_from_slice(&(object::elf::PF_R | object::elf::PF_X).to_le_bytes());
+            elf.extend_from_slice(&(offset as u64).to_le_bytes());
+            elf.extend_from_slice(&paddr.to_le_bytes()); // p_vaddr
+            elf.extend_from_slice(&paddr.to_le_bytes()); // p_paddr
+            elf.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            elf.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            elf.extend_from_slice(&4u64.to_le_bytes());
+            offset += data.len();
+        }
+        for (_, data) in segments {
+            elf.extend_from_slice(data);
+        }
+        elf
+    }
+
+    #[test]
+    fn test_elf_to_bin_bytes_uses_load_addresses() {
+        let elf = build_test_executable(&[
+            (0x8030_0000, b"\x13\x05\x00\x00"),
+            (0x8030_0010, b"\x12\x34\x56\x78"),
+        ]);
+        let bin = elf_to_bin_bytes(&elf).expect("elf to bin");
+        let mut expected = vec![0u8; 0x14];
+        expected[..4].copy_from_slice(b"\x13\x05\x00\x00");
+        expected[0x10..].copy_from_slice(b"\x12\x34\x56\x78");
+        assert_eq!(bin, expected);
+    }
+
+    #[test]
+    fn test_elf_to_bin_bytes_with_base() {
+        let elf = build_test_executable(&[(0x8030_0008, b"\xaa\xbb")]);
+        let bin = elf_to_bin_bytes_with_base(&elf, Some(0x8030_0000)).expect("elf to bin");
+        assert_eq!(bin, b"\0\0\0\0\0\0\0\0\xaa\xbb");
+
+        let err = elf_to_bin_bytes_with_base(&elf, Some(0x8030_0010)).unwrap_err();
+        assert!(matches!(
+            err,
+            XtaskError::SegmentBelowBase {
+                address: 0x8030_0008,
+                base: 0x8030_0010
+            }
+        ));
+    }
+
+    #[test]
+    fn test_elf_to_bin_bytes_overlapping_segments() {
+        let elf = build_test_executable(&[(0x1000, b"\x01\x02\x03\x04"), (0x1002, b"\xff")]);
+        let bin = elf_to_bin_bytes(&elf).expect("elf to bin");
+        assert_eq!(bin, b"\x01\x02\xff\x04");
+    }
+
     #[test]
     fn test_elf_to_image_bytes_consistent_with_gen_image() {
         let elf = build_test_elf();
@@ -270,7 +504,7 @@ mod tests {
         std::fs::write(input.path(), &elf).expect("write elf");
 
         let output = NamedTempFile::new().expect("output file");
-        elf_to_bin(input.path(), output.path()).expect("elf to bin file");
+        elf_to_bin(input.path(), output.path(), None, OutputFormat::Bin).expect("elf to bin file");
 
         let data = std::fs::read(output.path()).expect("read bin");
         assert!(!data.is_empty());
@@ -0,0 +1,199 @@
+//! Output file formats for converted firmware.
+//!
+//! Besides raw binaries, firmware can be written as Intel HEX for external
+//! programmers or as UF2 for drag-and-drop bootloaders. Both formats carry the
+//! load address of every byte.
+
+use crate::error::{XtaskError, XtaskResult};
+use std::str::FromStr;
+
+/// Data bytes per Intel HEX record.
+const HEX_RECORD_LEN: usize = 16;
+
+/// First UF2 magic number, "UF2\n".
+const UF2_MAGIC_START0: u32 = 0x0A32_4655;
+/// Second UF2 magic number.
+const UF2_MAGIC_START1: u32 = 0x9E5D_5157;
+/// Final UF2 magic number.
+const UF2_MAGIC_END: u32 = 0x0AB1_6F30;
+/// Size of a UF2 block.
+const UF2_BLOCK_LEN: usize = 512;
+/// Payload bytes per UF2 block, as used by most UF2 bootloaders.
+const UF2_PAYLOAD_LEN: usize = 256;
+
+/// Output file formats.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Raw binary.
+    #[default]
+    Bin,
+    /// Intel HEX with 32-bit addresses.
+    Hex,
+    /// USB Flashing Format with 256-byte payloads.
+    Uf2,
+}
+
+impl FromStr for OutputFormat {
+    type Err = XtaskError;
+
+    /// Parse output format from string.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "bin" => Ok(Self::Bin),
+            "hex" => Ok(Self::Hex),
+            "uf2" => Ok(Self::Uf2),
+            _ => Err(XtaskError::InvalidOutputFormat),
+        }
+    }
+}
+
+impl OutputFormat {
+    /// File extension for this format, using `bin_extension` for raw binaries.
+    pub fn extension<'a>(self, bin_extension: &'a str) -> &'a str {
+        match self {
+            Self::Bin => bin_extension,
+            Self::Hex => "hex",
+            Self::Uf2 => "uf2",
+        }
+    }
+
+    /// Encode `data` loaded at `address` in this format.
+    pub fn encode(self, data: &[u8], address: u64) -> XtaskResult<Vec<u8>> {
+        match self {
+            Self::Bin => Ok(data.to_vec()),
+            Self::Hex => to_intel_hex(data, address).map(String::into_bytes),
+            Self::Uf2 => to_uf2(data, address),
+        }
+    }
+}
+
+/// Check that `len` bytes from `address` fit in the 32-bit address space.
+fn check_address_range(address: u64, len: usize) -> XtaskResult<u32> {
+    let end = address + len as u64;
+    if end > 1 << 32 {
+        return Err(XtaskError::AddressOverflow(end));
+    }
+    Ok(address as u32)
+}
+
+/// Append an Intel HEX record with its checksum.
+fn push_hex_record(out: &mut String, offset: u16, record_type: u8, data: &[u8]) {
+    let mut checksum = (data.len() as u8)
+        .wrapping_add((offset >> 8) as u8)
+        .wrapping_add(offset as u8)
+        .wrapping_add(record_type);
+    out.push_str(&format!(
+        ":{:02X}{:04X}{:02X}",
+        data.len(),
+        offset,
+        record_type
+    ));
+    for &byte in data {
+        out.push_str(&format!("{byte:02X}"));
+        checksum = checksum.wrapping_add(byte);
+    }
+    out.push_str(&format!("{:02X}\n", checksum.wrapping_neg()));
+}
+
+/// Encode `data` loaded at `address` as Intel HEX.
+///
+/// Records never cross a 64 KiB boundary; an extended linear address record
+/// is emitted whenever the upper 16 address bits change.
+pub fn to_intel_hex(data: &[u8], address: u64) -> XtaskResult<String> {
+    let mut address = check_address_range(address, data.len())?;
+    let mut out = String::new();
+    let mut upper = None;
+    let mut rest = data;
+    while !rest.is_empty() {
+        if upper != Some(address >> 16) {
+            upper = Some(address >> 16);
+            push_hex_record(&mut out, 0, 0x04, &((address >> 16) as u16).to_be_bytes());
+        }
+        let to_boundary = 0x1_0000 - (address & 0xFFFF) as usize;
+        let len = rest.len().min(HEX_RECORD_LEN).min(to_boundary);
+        push_hex_record(&mut out, address as u16, 0x00, &rest[..len]);
+        rest = &rest[len..];
+        address = address.wrapping_add(len as u32);
+    }
+    push_hex_record(&mut out, 0, 0x01, &[]);
+    Ok(out)
+}
+
+/// Encode `data` loaded at `address` as UF2 blocks.
+///
+/// The family ID field is left unset, as no UF2 family is registered for
+/// Kendryte chips yet.
+pub fn to_uf2(data: &[u8], address: u64) -> XtaskResult<Vec<u8>> {
+    let address = check_address_range(address, data.len())?;
+    let block_count = data.len().div_ceil(UF2_PAYLOAD_LEN);
+    let mut out = Vec::with_capacity(block_count * UF2_BLOCK_LEN);
+    for (block_no, payload) in data.chunks(UF2_PAYLOAD_LEN).enumerate() {
+        let target = address + (block_no * UF2_PAYLOAD_LEN) as u32;
+        let header = [
+            UF2_MAGIC_START0,
+            UF2_MAGIC_START1,
+            0, // flags
+            target,
+            payload.len() as u32,
+            block_no as u32,
+            block_count as u32,
+            0, // file size or family ID
+        ];
+        for word in header {
+            out.extend(word.to_le_bytes());
+        }
+        out.extend(payload);
+        out.resize(out.len() + UF2_BLOCK_LEN - 32 - 4 - payload.len(), 0);
+        out.extend(UF2_MAGIC_END.to_le_bytes());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intel_hex_records() {
+        let hex = to_intel_hex(&[0x13, 0x05, 0x00, 0x00], 0x8030_0000).expect("hex");
+        assert_eq!(hex, ":0200000480304A\n:0400000013050000E4\n:00000001FF\n");
+    }
+
+    #[test]
+    fn test_intel_hex_splits_at_64k_boundary() {
+        let hex = to_intel_hex(&[0xAA, 0xBB], 0x1_FFFF).expect("hex");
+        assert_eq!(
+            hex,
+            ":020000040001F9\n:01FFFF00AA57\n:020000040002F8\n:01000000BB44\n:00000001FF\n"
+        );
+    }
+
+    #[test]
+    fn test_uf2_blocks() {
+        let data: Vec<u8> = (0..=255).chain(0..4).collect();
+        let uf2 = to_uf2(&data, 0x8030_0000).expect("uf2");
+        assert_eq!(uf2.len(), 2 * UF2_BLOCK_LEN);
+
+        let word = |block: usize, index: usize| {
+            let at = block * UF2_BLOCK_LEN + index * 4;
+            u32::from_le_bytes(uf2[at..at + 4].try_into().unwrap())
+        };
+        assert_eq!(word(0, 0), UF2_MAGIC_START0);
+        assert_eq!(word(0, 3), 0x8030_0000);
+        assert_eq!(word(0, 4), 256);
+        assert_eq!(word(1, 3), 0x8030_0100);
+        assert_eq!(word(1, 4), 4);
+        assert_eq!(word(1, 5), 1);
+        assert_eq!(word(1, 6), 2);
+        assert_eq!(word(1, 127), UF2_MAGIC_END);
+        assert_eq!(&uf2[UF2_BLOCK_LEN + 32..UF2_BLOCK_LEN + 36], &[0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_address_overflow() {
+        assert!(matches!(
+            to_uf2(&[0; 4], 0xFFFF_FFFE),
+            Err(XtaskError::AddressOverflow(0x1_0000_0002))
+        ));
+    }
+}
@@ -4,3 +4,4 @@
 //! into binary payloads and Kendryte-ready flashable images.
 
 pub mod elf;
+pub mod format;
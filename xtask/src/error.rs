@@ -13,6 +13,18 @@ pub enum XtaskError {
     #[error("Invalid encryption type!")]
     InvalidEncryptionType,
 
+    /// Error for invalid output format specification.
+    #[error("Invalid output format!")]
+    InvalidOutputFormat,
+
+    /// Error for invalid firmware metadata, such as a malformed version.
+    #[error("Invalid firmware metadata: {0}")]
+    InvalidMetadata(String),
+
+    /// Error for an invalid image magic or format version.
+    #[error("Invalid image header: {0}")]
+    InvalidImageHeader(String),
+
     /// Wrapper for standard I/O errors.
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
@@ -29,6 +41,10 @@ pub enum XtaskError {
     #[error("Sm2 error: {0}")]
     Sm2Error(#[from] Sm2Error),
 
+    /// Errors from opening or configuring a serial port.
+    #[error("Serial port error: {0}")]
+    Serial(#[from] serialport::Error),
+
     /// Errors when parsing RSA key components.
     #[error("RSA parse error: {0}")]
     RsaParseError(String),
@@ -37,6 +53,58 @@ pub enum XtaskError {
     #[error("ELF parsing error: {0}")]
     ElfParseError(String),
 
+    /// Errors when a loadable segment starts below the requested base address.
+    #[error("Segment at 0x{address:x} lies below base address 0x{base:x}")]
+    SegmentBelowBase { address: u64, base: u64 },
+
+    /// Errors when output ending at the given address does not fit in 32-bit addresses.
+    #[error("Address 0x{0:x} does not fit in the 32-bit address space")]
+    AddressOverflow(u64),
+
+    /// Errors when an image does not fit in an update slot.
+    #[error("Image of 0x{len:x} bytes does not fit in a slot holding 0x{max:x} bytes")]
+    SlotOverflow { len: u64, max: u64 },
+
+    /// Errors when parsing the MEMORY command of a linker script.
+    #[error("Linker script parse error: {0}")]
+    LinkerScriptParseError(String),
+
+    /// Errors when sections do not fit in the memory regions.
+    #[error("Memory regions overflowed: {0}")]
+    RegionOverflow(String),
+
+    /// Errors when a SoC name is not one of the supported SoCs.
+    #[error("Unknown SoC `{0}`, expected one of k230, k510, k210")]
+    UnknownSoc(String),
+
+    /// Errors when a requested example does not exist.
+    #[error("No example named `{0}`")]
+    UnknownExample(String),
+
+    /// Errors when some examples failed to build or convert.
+    #[error("Examples failed: {0}")]
+    ExamplesFailed(String),
+
+    /// Errors when parsing a pinmux sheet.
+    #[error("Pinmux sheet parse error: {0}")]
+    PinmuxParseError(String),
+
+    /// Errors when the pad tables in the sources differ from the pinmux sheet.
+    #[error("Pad tables differ from the pinmux sheet: {0}")]
+    PinmuxMismatch(String),
+
+    /// Errors when the register definitions cannot be turned into an SVD file.
+    #[error("SVD export error: {0}")]
+    SvdExportError(String),
+
+    /// Errors when a signing key cannot be used for secure boot.
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+
+    /// Errors when writing an OTP payload without confirmation.
+    #[error("Refusing to write an OTP payload without --yes-i-know")]
+    ProvisionNotConfirmed,
+
     /// Errors when processing ELF sections larger than supported size.
     #[error("Section size {0} is too large to fit in memory")]
     SectionSizeOverflow(u64),
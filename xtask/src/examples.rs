@@ -0,0 +1,269 @@
+//! Build orchestration for the peripheral examples.
+//!
+//! Finds every example under `examples/peripherals`, builds the ones that
+//! support the selected SoC for the bare-metal target and converts each
+//! resulting ELF into a flashable image. A failing example does not stop the
+//! others; failures are collected and reported together at the end.
+//!
+//! The SoCs an example supports are the SoC features it enables on its
+//! `kendryte-rt` dependency. An example enabling none is SoC independent and
+//! is built with `kendryte-rt/<soc>` for whichever SoC is selected.
+
+use crate::convert::elf::elf_to_image;
+use crate::convert::format::OutputFormat;
+use crate::error::{XtaskError, XtaskResult};
+use crate::generate::header::ImageHeader;
+use crate::generate::image::EncryptionType;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Target triple the examples are built for.
+pub const TARGET: &str = "riscv64gc-unknown-none-elf";
+
+/// SoC features of `kendryte-rt`.
+pub const SOCS: [&str; 3] = ["k230", "k510", "k210"];
+
+/// Directory holding the examples, relative to the workspace root.
+const EXAMPLES_DIR: &str = "examples/peripherals";
+
+/// An example crate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Example {
+    /// Package name, which is also the name of its binary.
+    pub name: String,
+    /// SoC features enabled on `kendryte-rt`; empty if SoC independent.
+    pub socs: Vec<String>,
+}
+
+impl Example {
+    /// Returns true if the example can be built for `soc`.
+    pub fn supports(&self, soc: &str) -> bool {
+        self.socs.is_empty() || self.socs.iter().any(|s| s == soc)
+    }
+}
+
+/// Parse the package name and SoC features out of an example manifest.
+///
+/// Returns `None` if the manifest has no package name.
+pub fn parse_manifest(manifest: &str) -> Option<Example> {
+    let mut section = "";
+    let mut name = None;
+    let mut socs = Vec::new();
+    for line in manifest.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            section = line;
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        if section == "[package]" && key == "name" {
+            name = Some(value.trim().trim_matches('"').to_string());
+        } else if section == "[dependencies]" && key == "kendryte-rt" {
+            let features = value
+                .split_once("features")
+                .and_then(|(_, rest)| rest.split_once('['))
+                .and_then(|(_, rest)| rest.split_once(']'))
+                .map_or("", |(list, _)| list);
+            socs = features
+                .split(',')
+                .map(|f| f.trim().trim_matches('"'))
+                .filter(|f| SOCS.contains(f))
+                .map(str::to_string)
+                .collect();
+        }
+    }
+    Some(Example { name: name?, socs })
+}
+
+/// Find the examples of the workspace at `root`, sorted by name.
+pub fn discover(root: &Path) -> XtaskResult<Vec<Example>> {
+    let mut examples = Vec::new();
+    for entry in fs::read_dir(root.join(EXAMPLES_DIR))? {
+        let manifest = entry?.path().join("Cargo.toml");
+        if !manifest.is_file() {
+            continue;
+        }
+        if let Some(example) = parse_manifest(&fs::read_to_string(&manifest)?) {
+            examples.push(example);
+        }
+    }
+    examples.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(examples)
+}
+
+/// Options for [`build_examples`].
+#[derive(Debug, Clone)]
+pub struct BuildOptions {
+    /// Workspace root.
+    pub root: PathBuf,
+    /// SoC to build for, one of [`SOCS`].
+    pub soc: String,
+    /// Only build these examples; all if empty.
+    pub only: Vec<String>,
+    /// Extra features passed to every build.
+    pub features: Vec<String>,
+    /// Build with the release profile.
+    pub release: bool,
+    /// Directory for the images; next to the ELF files if `None`.
+    pub out_dir: Option<PathBuf>,
+    /// Encryption of the images.
+    pub encryption: EncryptionType,
+}
+
+/// What happened to each example.
+#[derive(Debug, Default)]
+pub struct BuildSummary {
+    /// Examples built and converted, with the path of their image.
+    pub built: Vec<(String, PathBuf)>,
+    /// Examples not supporting the selected SoC.
+    pub skipped: Vec<String>,
+    /// Examples that failed to build or convert, with the reason.
+    pub failed: Vec<(String, String)>,
+}
+
+impl fmt::Display for BuildSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, image) in &self.built {
+            writeln!(f, "  ok      {name:<24} {}", image.display())?;
+        }
+        for name in &self.skipped {
+            writeln!(f, "  skipped {name}")?;
+        }
+        for (name, reason) in &self.failed {
+            writeln!(f, "  FAILED  {name:<24} {reason}")?;
+        }
+        writeln!(
+            f,
+            "{} built, {} skipped, {} failed",
+            self.built.len(),
+            self.skipped.len(),
+            self.failed.len()
+        )
+    }
+}
+
+/// Build the examples and convert them to images.
+///
+/// Returns an error only if the examples cannot be found or a requested
+/// example does not exist; build and conversion failures are recorded in the
+/// summary.
+pub fn build_examples(options: &BuildOptions) -> XtaskResult<BuildSummary> {
+    if !SOCS.contains(&options.soc.as_str()) {
+        return Err(XtaskError::UnknownSoc(options.soc.clone()));
+    }
+    let examples = discover(&options.root)?;
+    if let Some(missing) = options
+        .only
+        .iter()
+        .find(|name| !examples.iter().any(|e| &e.name == *name))
+    {
+        return Err(XtaskError::UnknownExample(missing.clone()));
+    }
+
+    let profile = if options.release { "release" } else { "debug" };
+    let target_dir = std::env::var_os("CARGO_TARGET_DIR")
+        .map_or_else(|| options.root.join("target"), PathBuf::from)
+        .join(TARGET)
+        .join(profile);
+    if let Some(out_dir) = &options.out_dir {
+        fs::create_dir_all(out_dir)?;
+    }
+
+    let mut summary = BuildSummary::default();
+    for example in examples {
+        if !options.only.is_empty() && !options.only.contains(&example.name) {
+            continue;
+        }
+        if !example.supports(&options.soc) {
+            summary.skipped.push(example.name);
+            continue;
+        }
+        println!("----- Building {} for {} -----", example.name, options.soc);
+        if let Err(reason) = cargo_build(&example, options) {
+            summary.failed.push((example.name, reason));
+            continue;
+        }
+        let elf = target_dir.join(&example.name);
+        let image = match &options.out_dir {
+            Some(out_dir) => out_dir.join(&example.name).with_extension("img"),
+            None => elf.with_extension("img"),
+        };
+        match elf_to_image(
+            &elf,
+            &image,
+            None,
+            options.encryption,
+            &ImageHeader::default(),
+            OutputFormat::Bin,
+            None,
+        ) {
+            Ok(()) => summary.built.push((example.name, image)),
+            Err(err) => summary.failed.push((example.name, err.to_string())),
+        }
+    }
+    Ok(summary)
+}
+
+/// Run `cargo build` for one example, returning the reason on failure.
+fn cargo_build(example: &Example, options: &BuildOptions) -> Result<(), String> {
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let mut features = options.features.clone();
+    if example.socs.is_empty() {
+        features.push(format!("kendryte-rt/{}", options.soc));
+    }
+
+    let mut command = Command::new(cargo);
+    command
+        .current_dir(&options.root)
+        .args(["build", "-p", &example.name, "--target", TARGET]);
+    if options.release {
+        command.arg("--release");
+    }
+    if !features.is_empty() {
+        command.args(["--features", &features.join(",")]);
+    }
+    match command.status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("cargo build failed ({status})")),
+        Err(err) => Err(format!("cannot run cargo: {err}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = r#"
+            [package]
+            name = "uart-demo"
+            version = "0.1.0"
+
+            [dependencies]
+            kendryte-hal = { path = "../../../kendryte-hal" }
+            kendryte-rt = { path = "../../../kendryte-rt", features = ["k230", "ramfunc"] }
+
+            [[bin]]
+            name = "uart-demo-bin"
+        "#;
+        let example = parse_manifest(manifest).expect("parse");
+        assert_eq!(example.name, "uart-demo");
+        assert_eq!(example.socs, ["k230"]);
+        assert!(example.supports("k230"));
+        assert!(!example.supports("k210"));
+
+        let generic = parse_manifest(
+            "[package]\nname = \"any\"\n[dependencies]\nkendryte-rt = { path = \"rt\" }\n",
+        )
+        .expect("parse");
+        assert!(generic.socs.is_empty());
+        assert!(generic.supports("k510"));
+        assert!(parse_manifest("[dependencies]\nriscv = \"0.14\"\n").is_none());
+    }
+}
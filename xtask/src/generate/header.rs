@@ -0,0 +1,151 @@
+//! Image header fields checked by the BootROM.
+//!
+//! The K230 BootROM checks for the magic [`MAGIC`] at the start of the image
+//! header and for the format version [`VERSION`] before the firmware, but other
+//! Kendryte BootROM variants and test ROMs expect different values.
+//! [`ImageHeader`] carries both fields so images for any of them can be
+//! generated; it defaults to the K230 values.
+//!
+//! The fields can come from a config file of `key = value` lines:
+//!
+//! ```text
+//! # Test ROM image header
+//! magic = "K23T"
+//! version = "0x00000001"
+//! ```
+
+use crate::error::{XtaskError, XtaskResult};
+use crate::generate::config::{MAGIC, VERSION};
+
+/// Length in bytes of the magic.
+pub const MAGIC_LEN: usize = 4;
+
+/// Length in bytes of the firmware format version.
+pub const VERSION_LEN: usize = 4;
+
+/// Magic and firmware format version of an image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImageHeader {
+    /// Magic at the start of the image header.
+    pub magic: [u8; MAGIC_LEN],
+    /// Version bytes prepended to the firmware.
+    pub version: [u8; VERSION_LEN],
+}
+
+impl Default for ImageHeader {
+    fn default() -> Self {
+        Self {
+            magic: MAGIC.as_bytes().try_into().expect("MAGIC is 4 bytes"),
+            version: VERSION.try_into().expect("VERSION is 4 bytes"),
+        }
+    }
+}
+
+impl ImageHeader {
+    /// Parse a config file, starting from the defaults.
+    ///
+    /// Blank lines and lines starting with `#` are ignored. Unknown keys are
+    /// rejected so a typo does not silently produce a K230 image.
+    pub fn from_config(config: &str) -> XtaskResult<Self> {
+        let mut header = Self::default();
+        for (number, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once('=').ok_or_else(|| {
+                XtaskError::InvalidImageHeader(format!(
+                    "line {}: expected `key = value`",
+                    number + 1
+                ))
+            })?;
+            let value = value.trim().trim_matches('"');
+            match key.trim() {
+                "magic" => header.magic = parse_magic(value)?,
+                "version" => header.version = parse_version(value)?,
+                key => {
+                    return Err(XtaskError::InvalidImageHeader(format!(
+                        "line {}: unknown key `{key}`",
+                        number + 1
+                    )));
+                }
+            }
+        }
+        Ok(header)
+    }
+
+    /// Override the fields given, keeping the others.
+    pub fn with_overrides(
+        mut self,
+        magic: Option<&str>,
+        version: Option<&str>,
+    ) -> XtaskResult<Self> {
+        if let Some(magic) = magic {
+            self.magic = parse_magic(magic)?;
+        }
+        if let Some(version) = version {
+            self.version = parse_version(version)?;
+        }
+        Ok(self)
+    }
+}
+
+/// Parse a magic of exactly four printable ASCII characters.
+pub fn parse_magic(s: &str) -> XtaskResult<[u8; MAGIC_LEN]> {
+    if !s.bytes().all(|b| b.is_ascii_graphic()) {
+        return Err(XtaskError::InvalidImageHeader(format!(
+            "magic `{s}` must be printable ASCII"
+        )));
+    }
+    s.as_bytes().try_into().map_err(|_| {
+        XtaskError::InvalidImageHeader(format!("magic `{s}` must be {MAGIC_LEN} characters"))
+    })
+}
+
+/// Parse a version given as `0x`-prefixed hex or as dotted decimal bytes.
+///
+/// Bytes are stored in the order written: `0x00000102` and `0.0.1.2` are the
+/// same version.
+pub fn parse_version(s: &str) -> XtaskResult<[u8; VERSION_LEN]> {
+    let invalid = || {
+        XtaskError::InvalidImageHeader(format!(
+            "version `{s}` must be {VERSION_LEN} bytes, e.g. `0x00000001` or `0.0.0.1`"
+        ))
+    };
+    let bytes = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => hex::decode(hex).map_err(|_| invalid())?,
+        None => s
+            .split('.')
+            .map(|part| part.parse::<u8>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?,
+    };
+    bytes.try_into().map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_config_and_overrides() {
+        let header =
+            ImageHeader::from_config("# test ROM\nmagic = \"K23T\"\n\nversion = \"0x00000102\"\n")
+                .expect("config");
+        assert_eq!(&header.magic, b"K23T");
+        assert_eq!(header.version, [0, 0, 1, 2]);
+
+        let header = header
+            .with_overrides(Some("K510"), Some("1.0.0.0"))
+            .expect("overrides");
+        assert_eq!(&header.magic, b"K510");
+        assert_eq!(header.version, [1, 0, 0, 0]);
+
+        assert_eq!(&ImageHeader::default().magic, b"K230");
+        assert!(ImageHeader::from_config("magik = \"K230\"").is_err());
+        assert!(parse_magic("K23").is_err());
+        assert!(parse_magic("K2 0").is_err());
+        assert!(parse_version("0x000001").is_err());
+        assert!(parse_version("0.0.256.0").is_err());
+    }
+}
@@ -2,9 +2,10 @@
 
 use crate::error::{XtaskError, XtaskResult};
 use crate::generate::config::{
-    ADD_AUTH_DATA, D, E, ID, ID_LEN, INITIAL_AES_IV, INITIAL_AES_KEY, K, MAGIC, N, PRIVATE_KEY,
-    PUBLIC_KEY_X, PUBLIC_KEY_Y, SM4_IV, SM4_KEY, VERSION,
+    ADD_AUTH_DATA, D, E, ID, ID_LEN, INITIAL_AES_IV, INITIAL_AES_KEY, K, N, PRIVATE_KEY,
+    PUBLIC_KEY_X, PUBLIC_KEY_Y, SM4_IV, SM4_KEY,
 };
+use crate::generate::header::ImageHeader;
 use aes_gcm::{AeadInPlace, Aes256Gcm, Key, KeyInit, Nonce, Tag};
 use cbc::cipher::KeyIvInit;
 use cipher::BlockEncryptMut;
@@ -49,15 +50,27 @@ impl FromStr for EncryptionType {
 /// The image is padded to a multiple of 512 bytes.
 /// Returns the generated image as a vector of bytes.
 pub fn gen_image(firmware: &[u8], encryption: EncryptionType) -> XtaskResult<Vec<u8>> {
+    gen_image_with_header(firmware, encryption, &ImageHeader::default())
+}
+
+/// Generate a firmware image with the magic and version of `header`.
+///
+/// See [`gen_image`] for the image layout.
+pub fn gen_image_with_header(
+    firmware: &[u8],
+    encryption: EncryptionType,
+    header: &ImageHeader,
+) -> XtaskResult<Vec<u8>> {
     println!("----- Generating image -----");
     let mut image = vec![0; 0x100000];
-    image.extend(MAGIC.as_bytes());
-    println!("the magic is: {}", MAGIC);
+    image.extend(header.magic);
+    println!("the magic is: {}", String::from_utf8_lossy(&header.magic));
 
+    let version = &header.version;
     match encryption {
-        EncryptionType::None => handle_none_encryption(&mut image, firmware)?,
-        EncryptionType::Sm4 => handle_sm4_encryption(&mut image, firmware)?,
-        EncryptionType::Aes => handle_aes_encryption(&mut image, firmware)?,
+        EncryptionType::None => handle_none_encryption(&mut image, firmware, version)?,
+        EncryptionType::Sm4 => handle_sm4_encryption(&mut image, firmware, version)?,
+        EncryptionType::Aes => handle_aes_encryption(&mut image, firmware, version)?,
     }
 
     if image.len() % 512 != 0 {
@@ -71,9 +84,9 @@ pub fn gen_image(firmware: &[u8], encryption: EncryptionType) -> XtaskResult<Vec
 /// Prepare the firmware data with version information.
 /// This function prepends the version bytes to the firmware data.
 /// Returns a new vector containing the version and firmware.
-fn prepare_firmware_with_version(firmware: &[u8]) -> Vec<u8> {
-    let mut firmware_with_version: Vec<u8> = Vec::with_capacity(VERSION.len() + firmware.len());
-    firmware_with_version.extend(VERSION);
+fn prepare_firmware_with_version(firmware: &[u8], version: &[u8]) -> Vec<u8> {
+    let mut firmware_with_version: Vec<u8> = Vec::with_capacity(version.len() + firmware.len());
+    firmware_with_version.extend(version);
     firmware_with_version.extend(firmware);
     firmware_with_version
 }
@@ -89,9 +102,9 @@ fn add_header_info(image: &mut Vec<u8>, len: i32, encryption: EncryptionType) {
 /// Handle the case of no encryption for the firmware image.
 /// This function adds a SHA-256 hash of the firmware to the image.
 /// The hash is followed by padding and the firmware data itself.
-fn handle_none_encryption(image: &mut Vec<u8>, firmware: &[u8]) -> XtaskResult<()> {
+fn handle_none_encryption(image: &mut Vec<u8>, firmware: &[u8], version: &[u8]) -> XtaskResult<()> {
     println!("----- NO ENCRYPTION + HASH-256 -----");
-    let firmware_with_version = prepare_firmware_with_version(firmware);
+    let firmware_with_version = prepare_firmware_with_version(firmware, version);
 
     add_header_info(
         image,
@@ -113,9 +126,9 @@ fn handle_none_encryption(image: &mut Vec<u8>, firmware: &[u8]) -> XtaskResult<(
 /// Handle the case of SM4 encryption for the firmware image.
 /// This function encrypts the firmware using SM4-CBC and signs it with SM2.
 /// The image includes the signature, public key, and encrypted firmware.
-fn handle_sm4_encryption(image: &mut Vec<u8>, firmware: &[u8]) -> XtaskResult<()> {
+fn handle_sm4_encryption(image: &mut Vec<u8>, firmware: &[u8], version: &[u8]) -> XtaskResult<()> {
     println!("----- SM4-CBC + SM2 -----");
-    let firmware_with_version = prepare_firmware_with_version(firmware);
+    let firmware_with_version = prepare_firmware_with_version(firmware, version);
 
     let ciphertext = encrypt_sm4(&firmware_with_version);
 
@@ -136,9 +149,9 @@ fn handle_sm4_encryption(image: &mut Vec<u8>, firmware: &[u8]) -> XtaskResult<()
 /// Handle the case of AES encryption for the firmware image.
 /// This function encrypts the firmware using AES-GCM and signs the tag with RSA-2048.
 /// The image includes the RSA signature, public key, and encrypted firmware.
-fn handle_aes_encryption(image: &mut Vec<u8>, firmware: &[u8]) -> XtaskResult<()> {
+fn handle_aes_encryption(image: &mut Vec<u8>, firmware: &[u8], version: &[u8]) -> XtaskResult<()> {
     println!("----- AES-GCM + RSA-2048 -----");
-    let firmware_with_version = prepare_firmware_with_version(firmware);
+    let firmware_with_version = prepare_firmware_with_version(firmware, version);
 
     // Perform AES-GCM encryption.
     let (ciphertext, tag) = encrypt_aes(&firmware_with_version)?;
@@ -0,0 +1,204 @@
+//! Firmware metadata trailer.
+//!
+//! kendryte-rt reserves space for the trailer at the end of the loaded image,
+//! in its `.trailer` section before `.bss`. The trailer is filled in there
+//! before image generation and read back at runtime by
+//! `kendryte_hal::firmware::FirmwareInfo`. See that module for the layout.
+
+use crate::error::{XtaskError, XtaskResult};
+use sha2::{Digest, Sha256};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Magic bytes at the start of the trailer.
+pub const TRAILER_MAGIC: &[u8; 4] = b"KFWI";
+/// Trailer format version.
+pub const TRAILER_VERSION: u16 = 1;
+/// Length of the trailer in bytes.
+pub const TRAILER_LEN: usize = 96;
+
+/// Start of the space kendryte-rt reserves for the trailer: the magic and
+/// length with format version 0, the rest zero.
+const RESERVED_HEAD: [u8; 8] = [b'K', b'F', b'W', b'I', 0, 0, TRAILER_LEN as u8, 0];
+
+/// Metadata stored in the trailer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    /// Firmware version as major, minor and patch.
+    pub version: [u16; 3],
+    /// Build time in seconds since the Unix epoch.
+    pub build_time: u64,
+    /// Git commit hash, zero if unknown.
+    pub git_hash: [u8; 20],
+}
+
+impl Metadata {
+    /// Collect metadata for the current build.
+    ///
+    /// The build time honours `SOURCE_DATE_EPOCH` for reproducible builds, and
+    /// the git hash is taken from the working directory if it is a repository.
+    pub fn collect(version: &str) -> XtaskResult<Self> {
+        let build_time = match std::env::var("SOURCE_DATE_EPOCH") {
+            Ok(epoch) => epoch
+                .parse()
+                .map_err(|_| XtaskError::InvalidMetadata(format!("SOURCE_DATE_EPOCH={epoch}")))?,
+            Err(_) => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+        };
+        Ok(Self {
+            version: parse_version(version)?,
+            build_time,
+            git_hash: git_hash().unwrap_or_default(),
+        })
+    }
+}
+
+/// Parse a `MAJOR.MINOR.PATCH` version, ignoring pre-release and build suffixes.
+pub fn parse_version(version: &str) -> XtaskResult<[u16; 3]> {
+    let invalid = || XtaskError::InvalidMetadata(format!("version `{version}`"));
+    let core = version
+        .trim_start_matches('v')
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default();
+    let mut parts = core.split('.').map(|part| part.parse::<u16>());
+    let mut next =
+        || -> XtaskResult<u16> { parts.next().ok_or_else(invalid)?.map_err(|_| invalid()) };
+    let parsed = [next()?, next()?, next()?];
+    if parts.next().is_some() {
+        return Err(invalid());
+    }
+    Ok(parsed)
+}
+
+/// Returns the commit hash of `HEAD`, if git is available.
+fn git_hash() -> Option<[u8; 20]> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let hash = hex::decode(String::from_utf8(output.stdout).ok()?.trim()).ok()?;
+    hash.try_into().ok()
+}
+
+/// Returns true if `firmware` ends with the space reserved for the trailer.
+pub fn has_reserved_trailer(firmware: &[u8]) -> bool {
+    firmware.len() >= TRAILER_LEN && {
+        let reserved = &firmware[firmware.len() - TRAILER_LEN..];
+        reserved[..8] == RESERVED_HEAD && reserved[8..].iter().all(|&b| b == 0)
+    }
+}
+
+/// Fill the trailer space at the end of `firmware` with `metadata`.
+///
+/// The checksums cover everything before the trailer. Fails if `firmware`
+/// does not end with the space kendryte-rt reserves: appending a trailer
+/// instead would place it where `.bss` starts, which is cleared at boot.
+pub fn fill_trailer(firmware: &[u8], metadata: &Metadata) -> XtaskResult<Vec<u8>> {
+    if !has_reserved_trailer(firmware) {
+        return Err(XtaskError::InvalidMetadata(
+            "payload has no reserved trailer space; link it with kendryte-rt".to_string(),
+        ));
+    }
+    let firmware = &firmware[..firmware.len() - TRAILER_LEN];
+    let mut trailer = Vec::with_capacity(TRAILER_LEN);
+    trailer.extend(TRAILER_MAGIC);
+    trailer.extend(TRAILER_VERSION.to_le_bytes());
+    trailer.extend((TRAILER_LEN as u16).to_le_bytes());
+    for part in metadata.version {
+        trailer.extend(part.to_le_bytes());
+    }
+    trailer.extend([0; 2]);
+    trailer.extend(metadata.build_time.to_le_bytes());
+    trailer.extend(metadata.git_hash);
+    trailer.extend(crc32fast::hash(firmware).to_le_bytes());
+
+    let mut hasher = Sha256::new();
+    hasher.update(firmware);
+    hasher.update(&trailer);
+    trailer.extend(hasher.finalize());
+    trailer.resize(TRAILER_LEN, 0);
+
+    println!(
+        "Firmware metadata: version {}.{}.{}, build time {}, git {}",
+        metadata.version[0],
+        metadata.version[1],
+        metadata.version[2],
+        metadata.build_time,
+        hex::encode(metadata.git_hash)
+    );
+
+    let mut output = Vec::with_capacity(firmware.len() + TRAILER_LEN);
+    output.extend(firmware);
+    output.extend(trailer);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("1.2.3").unwrap(), [1, 2, 3]);
+        assert_eq!(parse_version("v0.10.0-rc.1+abc").unwrap(), [0, 10, 0]);
+        assert!(parse_version("1.2").is_err());
+        assert!(parse_version("1.2.3.4").is_err());
+        assert!(parse_version("1.x.3").is_err());
+    }
+
+    #[test]
+    fn test_trailer_layout() {
+        let metadata = Metadata {
+            version: [1, 2, 3],
+            build_time: 0x1122_3344_5566_7788,
+            git_hash: [0xAB; 20],
+        };
+        let firmware = b"123456789";
+        let mut reserved = firmware.to_vec();
+        reserved.extend(RESERVED_HEAD);
+        reserved.resize(firmware.len() + TRAILER_LEN, 0);
+        assert!(has_reserved_trailer(&reserved));
+        let output = fill_trailer(&reserved, &metadata).unwrap();
+        assert_eq!(output.len(), firmware.len() + TRAILER_LEN);
+        assert_eq!(&output[..9], firmware);
+        assert!(!has_reserved_trailer(&output));
+
+        let trailer = &output[9..];
+        assert_eq!(&trailer[0..4], b"KFWI");
+        assert_eq!(trailer[4..8], [1, 0, 96, 0]);
+        assert_eq!(trailer[8..16], [1, 0, 2, 0, 3, 0, 0, 0]);
+        assert_eq!(trailer[16..24], 0x1122_3344_5566_7788u64.to_le_bytes());
+        assert_eq!(trailer[24..44], [0xAB; 20]);
+        // CRC-32 check value of "123456789".
+        assert_eq!(trailer[44..48], 0xCBF4_3926u32.to_le_bytes());
+        let hash = Sha256::new()
+            .chain_update(firmware)
+            .chain_update(&trailer[..48])
+            .finalize();
+        assert_eq!(&trailer[48..80], hash.as_slice());
+        assert_eq!(trailer[80..], [0; 16]);
+    }
+
+    #[test]
+    fn test_trailer_needs_reserved_space() {
+        let metadata = Metadata {
+            version: [1, 0, 0],
+            build_time: 0,
+            git_hash: [0; 20],
+        };
+        assert!(fill_trailer(b"123456789", &metadata).is_err());
+        assert!(fill_trailer(&[0; 2 * TRAILER_LEN], &metadata).is_err());
+
+        // Filling twice fails: the space is no longer reserved.
+        let mut firmware = vec![0x13; 64];
+        firmware.extend(RESERVED_HEAD);
+        firmware.resize(64 + TRAILER_LEN, 0);
+        let output = fill_trailer(&firmware, &metadata).unwrap();
+        assert!(fill_trailer(&output, &metadata).is_err());
+    }
+}
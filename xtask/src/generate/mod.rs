@@ -3,4 +3,7 @@
 //! This module provides functionality for generating image,
 //! including encryption, signing, and proper formatting for the K230 platform.
 pub mod config;
+pub mod header;
 pub mod image;
+pub mod metadata;
+pub mod ota;
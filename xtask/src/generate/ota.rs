@@ -0,0 +1,102 @@
+//! A/B update slot images.
+//!
+//! Slot images are read by `kendryte_hal::ota`; see that module for the
+//! header layout. Unused bytes are `0xFF` so the image matches erased flash.
+
+use crate::error::{XtaskError, XtaskResult};
+
+/// Magic bytes at the start of a slot header.
+pub const HEADER_MAGIC: &[u8; 4] = b"KOTA";
+/// Length of a slot header in bytes.
+pub const HEADER_LEN: usize = 32;
+/// Offset of the image from the start of its slot.
+pub const IMAGE_OFFSET: usize = 4096;
+
+/// Encode the header of a freshly written slot, with all state flags set.
+pub fn slot_header(sequence: u32, image: &[u8]) -> [u8; HEADER_LEN] {
+    let mut header = [0xFF; HEADER_LEN];
+    header[..4].copy_from_slice(HEADER_MAGIC);
+    header[4..8].copy_from_slice(&sequence.to_le_bytes());
+    header[12..16].copy_from_slice(&(image.len() as u32).to_le_bytes());
+    header[16..20].copy_from_slice(&crc32fast::hash(image).to_le_bytes());
+
+    // The state flags at 8..12 change on the device and are not covered.
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&header[..8]);
+    hasher.update(&header[12..28]);
+    header[28..32].copy_from_slice(&hasher.finalize().to_le_bytes());
+    header
+}
+
+/// Generate the contents of one slot: header, padding and `image`.
+pub fn gen_slot_image(image: &[u8], sequence: u32) -> XtaskResult<Vec<u8>> {
+    if u32::try_from(image.len()).is_err() {
+        return Err(XtaskError::SlotOverflow {
+            len: image.len() as u64,
+            max: u32::MAX as u64,
+        });
+    }
+    println!("----- Generating OTA slot image -----");
+    println!(
+        "sequence: {}, image length: 0x{:x}, image crc32: 0x{:08x}",
+        sequence,
+        image.len(),
+        crc32fast::hash(image)
+    );
+    let mut slot = vec![0xFF; IMAGE_OFFSET];
+    slot[..HEADER_LEN].copy_from_slice(&slot_header(sequence, image));
+    slot.extend(image);
+    Ok(slot)
+}
+
+/// Generate a flash region holding slot A with `image` followed by an erased slot B.
+pub fn gen_dual_slot_image(image: &[u8], sequence: u32, slot_size: u64) -> XtaskResult<Vec<u8>> {
+    let max = slot_size.saturating_sub(IMAGE_OFFSET as u64);
+    if image.len() as u64 > max {
+        return Err(XtaskError::SlotOverflow {
+            len: image.len() as u64,
+            max,
+        });
+    }
+    let slot_size =
+        usize::try_from(slot_size).map_err(|_| XtaskError::SectionSizeOverflow(slot_size))?;
+    let mut output = gen_slot_image(image, sequence)?;
+    output.resize(2 * slot_size, 0xFF);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_header() {
+        let header = slot_header(7, b"123456789");
+        assert_eq!(&header[0..4], b"KOTA");
+        assert_eq!(header[4..8], 7u32.to_le_bytes());
+        assert_eq!(header[8..12], [0xFF; 4]);
+        assert_eq!(header[12..16], 9u32.to_le_bytes());
+        assert_eq!(header[16..20], 0xCBF4_3926u32.to_le_bytes());
+        assert_eq!(header[20..28], [0xFF; 8]);
+
+        let mut covered = header[..8].to_vec();
+        covered.extend(&header[12..28]);
+        assert_eq!(header[28..32], crc32fast::hash(&covered).to_le_bytes());
+    }
+
+    #[test]
+    fn test_dual_slot_image() {
+        let image = gen_dual_slot_image(b"firmware", 1, 0x2000).expect("dual slot image");
+        assert_eq!(image.len(), 0x4000);
+        assert_eq!(&image[IMAGE_OFFSET..IMAGE_OFFSET + 8], b"firmware");
+        assert!(image[IMAGE_OFFSET + 8..].iter().all(|&b| b == 0xFF));
+
+        assert!(matches!(
+            gen_dual_slot_image(&[0; 0x1001], 1, 0x2000),
+            Err(XtaskError::SlotOverflow {
+                len: 0x1001,
+                max: 0x1000
+            })
+        ));
+    }
+}
@@ -5,13 +5,20 @@
 
 extern crate core;
 
+use crate::convert::format::OutputFormat;
 use crate::generate::image::EncryptionType;
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
 pub mod convert;
 pub mod error;
+pub mod examples;
 pub mod generate;
+pub mod monitor;
+pub mod pinmux;
+pub mod provision;
+pub mod size;
+pub mod svd;
 
 /// CLI structure for the xtask utility.
 #[derive(Parser, Debug)]
@@ -57,6 +64,30 @@ pub enum Command {
         /// - `aes`: AES-GCM + RSA-2048
         #[arg(long, short = 'e')]
         encryption: Option<EncryptionType>,
+        /// Fill in the metadata trailer with this firmware version (optional).
+        ///
+        /// The trailer also records the build time, the git commit and checksums
+        /// of the payload, and can be read at runtime with
+        /// `kendryte_hal::firmware::FirmwareInfo`. The payload must end with the
+        /// trailer space kendryte-rt reserves.
+        #[arg(long = "fw-version")]
+        fw_version: Option<String>,
+        /// Image header config file setting `magic` and `version` (optional).
+        ///
+        /// See `xtask::generate::header` for the format.
+        #[arg(long = "header-config")]
+        header_config: Option<PathBuf>,
+        /// Image magic, four ASCII characters (optional), defaults to `K230`.
+        ///
+        /// Overrides the magic of `--header-config`.
+        #[arg(long)]
+        magic: Option<String>,
+        /// Image format version (optional), defaults to `0x00000000`.
+        ///
+        /// Accepts `0x`-prefixed hex or dotted decimal bytes, e.g. `0.0.0.1`.
+        /// Overrides the version of `--header-config`.
+        #[arg(long = "image-version")]
+        image_version: Option<String>,
     },
     /// Convert ELF to raw binary data.
     #[command(name = "elf2bin")]
@@ -67,6 +98,21 @@ pub enum Command {
         /// Output binary file path (optional).
         #[arg(long = "output", short = 'o')]
         output: Option<PathBuf>,
+        /// Load address of the first output byte (optional).
+        ///
+        /// Defaults to the lowest `PT_LOAD` physical address. Accepts decimal or
+        /// `0x`-prefixed hexadecimal, e.g. `--base 0x80300000`.
+        #[arg(long = "base", value_parser = parse_address)]
+        base: Option<u64>,
+        /// Output format (optional).
+        ///
+        /// Parameter options:
+        ///
+        /// - `bin`: raw binary (default)
+        /// - `hex`: Intel HEX
+        /// - `uf2`: UF2 blocks
+        #[arg(long, short = 'f')]
+        format: Option<OutputFormat>,
     },
     /// Convert ELF directly into a flashable image.
     #[command(name = "elf2img")]
@@ -77,8 +123,215 @@ pub enum Command {
         /// Output image file path (optional).
         #[arg(long = "output", short = 'o')]
         output: Option<PathBuf>,
+        /// Load address of the first output byte (optional).
+        ///
+        /// Defaults to the lowest `PT_LOAD` physical address. Accepts decimal or
+        /// `0x`-prefixed hexadecimal, e.g. `--base 0x80300000`.
+        #[arg(long = "base", value_parser = parse_address)]
+        base: Option<u64>,
+        /// Output format (optional).
+        ///
+        /// Parameter options:
+        ///
+        /// - `bin`: raw binary (default)
+        /// - `hex`: Intel HEX
+        /// - `uf2`: UF2 blocks
+        #[arg(long, short = 'f')]
+        format: Option<OutputFormat>,
         /// Encryption type (optional).
         #[arg(long, short = 'e')]
         encryption: Option<EncryptionType>,
+        /// Fill in the metadata trailer with this firmware version (optional).
+        ///
+        /// The trailer also records the build time, the git commit and checksums
+        /// of the payload, and can be read at runtime with
+        /// `kendryte_hal::firmware::FirmwareInfo`. The payload must end with the
+        /// trailer space kendryte-rt reserves.
+        #[arg(long = "fw-version")]
+        fw_version: Option<String>,
+        /// Image header config file setting `magic` and `version` (optional).
+        ///
+        /// See `xtask::generate::header` for the format.
+        #[arg(long = "header-config")]
+        header_config: Option<PathBuf>,
+        /// Image magic, four ASCII characters (optional), defaults to `K230`.
+        ///
+        /// Overrides the magic of `--header-config`.
+        #[arg(long)]
+        magic: Option<String>,
+        /// Image format version (optional), defaults to `0x00000000`.
+        ///
+        /// Accepts `0x`-prefixed hex or dotted decimal bytes, e.g. `0.0.0.1`.
+        /// Overrides the version of `--header-config`.
+        #[arg(long = "image-version")]
+        image_version: Option<String>,
+    },
+    /// Generate an A/B update slot image.
+    ///
+    /// The output holds the slot header followed by the input image, ready to be
+    /// written to a slot by `kendryte_hal::ota` or a flashing tool.
+    GenOta {
+        /// Input image file path, usually the output of `gen-image` or `elf2img`.
+        #[arg(long = "input", short = 'i')]
+        input: PathBuf,
+        /// Output file path (optional), defaults to the input with an `ota` extension.
+        #[arg(long = "output", short = 'o')]
+        output: Option<PathBuf>,
+        /// Update sequence number; the bootable slot with the highest one is booted.
+        #[arg(long = "sequence", short = 's', default_value_t = 1)]
+        sequence: u32,
+        /// Slot size (optional).
+        ///
+        /// When given, the output covers both slots: slot A holds the image and
+        /// slot B is left erased. Accepts decimal or `0x`-prefixed hexadecimal.
+        #[arg(long = "slot-size", value_parser = parse_address)]
+        slot_size: Option<u64>,
     },
+    /// Derive the secure boot key hash and build its OTP programming payload.
+    ///
+    /// Programming OTP cannot be undone: a chip with a wrong key hash only
+    /// boots images signed with the wrong key. Without `--yes-i-know` the hash
+    /// is printed but no payload is written. `gen-image` signs with the
+    /// built-in key only, so any other key is refused.
+    ///
+    /// ```text
+    /// cargo xtask provision -e aes -k signing-key.pem --yes-i-know
+    /// Output: signing-key.otp
+    /// ```
+    Provision {
+        /// Signing key file: a PEM RSA key for `aes`, a raw or hex SM2 public key for `sm4`.
+        #[arg(long = "key", short = 'k')]
+        key: PathBuf,
+        /// Encryption type whose signing scheme the key belongs to: `sm4` or `aes`.
+        #[arg(long, short = 'e')]
+        encryption: EncryptionType,
+        /// Output payload file path (optional), defaults to the key with an `otp` extension.
+        #[arg(long = "output", short = 'o')]
+        output: Option<PathBuf>,
+        /// Confirm that the payload is meant to be programmed into OTP.
+        #[arg(long = "yes-i-know")]
+        yes_i_know: bool,
+    },
+    /// Report section sizes and memory region usage of an ELF file.
+    ///
+    /// Exits with an error if a region is over-full or a section lies outside
+    /// every region, so it can gate CI builds.
+    ///
+    /// ```text
+    /// cargo xtask size -i target/riscv64gc-unknown-none-elf/release/uart-demo
+    /// ```
+    Size {
+        /// Input ELF file path.
+        #[arg(long = "input", short = 'i')]
+        input: PathBuf,
+        /// Linker script whose MEMORY regions bound the sections (optional).
+        ///
+        /// Defaults to the K230 regions of `kendryte-rt`.
+        #[arg(long = "linker-script", short = 'T')]
+        linker_script: Option<PathBuf>,
+        /// Print the report as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Generate the pad function tables from the vendor pinmux sheet.
+    ///
+    /// Reads the sheet exported as CSV and writes the `pad_*!` table
+    /// invocations for `kendryte-rt/src/soc/k230`, see `xtask::pinmux`.
+    /// Signals without a pad trait in the HAL are listed but not written.
+    ///
+    /// ```text
+    /// cargo xtask gen-pinmux -i K230_PINOUT.csv --check kendryte-rt/src/soc/k230
+    /// Output: K230_PINOUT.rs
+    /// ```
+    GenPinmux {
+        /// Input CSV file path.
+        #[arg(long = "input", short = 'i')]
+        input: PathBuf,
+        /// Output file path (optional), defaults to the input with an `rs` extension.
+        #[arg(long = "output", short = 'o')]
+        output: Option<PathBuf>,
+        /// Directory of Rust sources to check against the sheet (optional).
+        ///
+        /// Fails if a table entry in the sources is not in the sheet, or the
+        /// other way round.
+        #[arg(long = "check")]
+        check: Option<PathBuf>,
+    },
+    /// Export the HAL register definitions as a CMSIS-SVD file.
+    ///
+    /// Gives debuggers such as probe-rs or GDB with an SVD plugin a register
+    /// view of the peripherals, see `xtask::svd`.
+    ///
+    /// ```text
+    /// cargo xtask export-svd --soc k230
+    /// Output: target/k230.svd
+    /// ```
+    ExportSvd {
+        /// SoC to export: `k230` (default), `k510` or `k210`.
+        #[arg(long, default_value = "k230")]
+        soc: String,
+        /// Output file path (optional), defaults to `target/<soc>.svd`.
+        #[arg(long = "output", short = 'o')]
+        output: Option<PathBuf>,
+    },
+    /// Build every peripheral example and convert each one to an image.
+    ///
+    /// Examples not supporting the selected SoC are skipped. A failing example
+    /// does not stop the others; the command fails at the end if any did.
+    ///
+    /// ```text
+    /// cargo xtask build-examples --soc k230
+    /// Output: target/riscv64gc-unknown-none-elf/release/<example>.img
+    /// ```
+    BuildExamples {
+        /// SoC to build for: `k230` (default), `k510` or `k210`.
+        #[arg(long, default_value = "k230")]
+        soc: String,
+        /// Only build this example; may be repeated.
+        #[arg(long = "example", short = 'p')]
+        examples: Vec<String>,
+        /// Extra features passed to every build, comma separated.
+        #[arg(long = "features", short = 'F', value_delimiter = ',')]
+        features: Vec<String>,
+        /// Build with the debug profile instead of release.
+        #[arg(long)]
+        debug: bool,
+        /// Directory for the images (optional), defaults to next to the ELF files.
+        #[arg(long = "out-dir", short = 'o')]
+        out_dir: Option<PathBuf>,
+        /// Encryption type (optional).
+        #[arg(long, short = 'e')]
+        encryption: Option<EncryptionType>,
+    },
+    /// Monitor a serial port, sending lines typed on standard input to the board.
+    ///
+    /// While running, type `~h` to toggle hex view, `~t` to toggle timestamps
+    /// and `~q` to quit.
+    Monitor {
+        /// Serial port, e.g. `/dev/ttyUSB0` or `COM3`.
+        #[arg(long = "port", short = 'p')]
+        port: String,
+        /// Baud rate.
+        #[arg(long = "baud", short = 'b', default_value_t = 115_200)]
+        baud: u32,
+        /// Start in hex view.
+        #[arg(long)]
+        hex: bool,
+        /// Prefix every line with the time since the monitor started.
+        #[arg(long, short = 't')]
+        timestamps: bool,
+        /// Append everything received to this file (optional).
+        #[arg(long = "log", short = 'l')]
+        log: Option<PathBuf>,
+    },
+}
+
+/// Parse an address given in decimal or `0x`-prefixed hexadecimal.
+fn parse_address(s: &str) -> Result<u64, String> {
+    let s = s.replace('_', "");
+    let result = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    result.map_err(|e| format!("invalid address `{s}`: {e}"))
 }
@@ -1,9 +1,19 @@
 use clap::Parser;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use xtask::convert::elf::{elf_to_bin, elf_to_image};
-use xtask::error::XtaskResult;
-use xtask::generate::image::gen_image;
+use xtask::error::{XtaskError, XtaskResult};
+use xtask::examples::{BuildOptions, build_examples};
+use xtask::generate::header::ImageHeader;
+use xtask::generate::image::gen_image_with_header;
+use xtask::generate::metadata::{Metadata, fill_trailer};
+use xtask::generate::ota::{gen_dual_slot_image, gen_slot_image};
+use xtask::monitor::{MonitorOptions, run_monitor};
+use xtask::pinmux::{bind_all, compare, generate, parse_csv, parse_tables};
+use xtask::provision::{OtpPayload, PublicKey};
+use xtask::size::{SizeReport, k230_linker_regions, parse_memory_regions};
+use xtask::svd::export_svd;
 use xtask::{Cli, Command};
 
 /// Entry point for the xtask utility.
@@ -21,38 +31,247 @@ fn run() -> XtaskResult<()> {
             input,
             output,
             encryption,
+            fw_version,
+            header_config,
+            magic,
+            image_version,
         } => {
             let output_path = resolve_output_path(&input, output, "img");
             let encryption = encryption.unwrap_or_default();
+            let header = resolve_image_header(header_config, magic, image_version)?;
 
-            let data = fs::read(&input)?;
-            let image = gen_image(&data, encryption)?;
+            let mut data = fs::read(&input)?;
+            if let Some(version) = fw_version {
+                data = fill_trailer(&data, &Metadata::collect(&version)?)?;
+            }
+            let image = gen_image_with_header(&data, encryption, &header)?;
             fs::write(&output_path, &image)?;
 
             println!("Success! Image saved to: {}", output_path.display());
         }
-        Command::Elf2Bin { input, output } => {
-            let output_path = resolve_output_path(&input, output, "bin");
-            elf_to_bin(&input, &output_path)?;
+        Command::Elf2Bin {
+            input,
+            output,
+            base,
+            format,
+        } => {
+            let format = format.unwrap_or_default();
+            let output_path = resolve_output_path(&input, output, format.extension("bin"));
+            elf_to_bin(&input, &output_path, base, format)?;
 
             println!("Success! Binary saved to: {}", output_path.display());
         }
         Command::Elf2Img {
             input,
             output,
+            base,
+            format,
             encryption,
+            fw_version,
+            header_config,
+            magic,
+            image_version,
         } => {
-            let output_path = resolve_output_path(&input, output, "img");
+            let format = format.unwrap_or_default();
+            let output_path = resolve_output_path(&input, output, format.extension("img"));
             let encryption = encryption.unwrap_or_default();
-            elf_to_image(&input, &output_path, encryption)?;
+            let header = resolve_image_header(header_config, magic, image_version)?;
+            let metadata = fw_version.as_deref().map(Metadata::collect).transpose()?;
+            elf_to_image(
+                &input,
+                &output_path,
+                base,
+                encryption,
+                &header,
+                format,
+                metadata.as_ref(),
+            )?;
 
             println!("Success! Image saved to: {}", output_path.display());
         }
+        Command::GenOta {
+            input,
+            output,
+            sequence,
+            slot_size,
+        } => {
+            let output_path = resolve_output_path(&input, output, "ota");
+
+            let data = fs::read(&input)?;
+            let slots = match slot_size {
+                Some(slot_size) => gen_dual_slot_image(&data, sequence, slot_size)?,
+                None => gen_slot_image(&data, sequence)?,
+            };
+            fs::write(&output_path, &slots)?;
+
+            println!("Success! Slot image saved to: {}", output_path.display());
+        }
+        Command::Provision {
+            key,
+            encryption,
+            output,
+            yes_i_know,
+        } => {
+            let output_path = resolve_output_path(&key, output, "otp");
+            let key = PublicKey::parse(encryption, &fs::read(&key)?)?;
+            key.check_signer(encryption)?;
+            let payload = OtpPayload::new(&key);
+            print!("{}", payload);
+            if !yes_i_know {
+                return Err(XtaskError::ProvisionNotConfirmed);
+            }
+            fs::write(&output_path, payload.to_bytes())?;
+
+            println!("Success! OTP payload saved to: {}", output_path.display());
+        }
+        Command::Size {
+            input,
+            linker_script,
+            json,
+        } => {
+            let regions = match linker_script {
+                Some(path) => parse_memory_regions(&fs::read_to_string(path)?)?,
+                None => k230_linker_regions(),
+            };
+            let report = SizeReport::from_elf(&fs::read(&input)?, &regions)?;
+            if json {
+                println!("{}", report.to_json());
+            } else {
+                print!("{}", report);
+            }
+            let problems = report.problems();
+            if !problems.is_empty() {
+                return Err(XtaskError::RegionOverflow(problems.join("; ")));
+            }
+        }
+        Command::GenPinmux {
+            input,
+            output,
+            check,
+        } => {
+            let output_path = resolve_output_path(&input, output, "rs");
+            let functions = parse_csv(&fs::read_to_string(&input)?)?;
+            let (bindings, unbound) = bind_all(&functions);
+            let source = input.file_name().unwrap_or_default().to_string_lossy();
+            fs::write(&output_path, generate(&bindings, &source))?;
+            for function in &unbound {
+                println!(
+                    "no pad trait for {} (pad {}, function {})",
+                    function.signal, function.pad, function.select
+                );
+            }
+            println!(
+                "Success! {} table entries saved to: {}",
+                bindings.len(),
+                output_path.display()
+            );
+            if let Some(dir) = check {
+                let mut tables: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+                for source in rust_sources(&dir)? {
+                    for (table, entries) in parse_tables(&fs::read_to_string(source)?) {
+                        tables.entry(table).or_default().extend(entries);
+                    }
+                }
+                let problems = compare(&bindings, &tables);
+                if !problems.is_empty() {
+                    return Err(XtaskError::PinmuxMismatch(problems.join("; ")));
+                }
+            }
+        }
+        Command::ExportSvd { soc, output } => {
+            let root = workspace_root();
+            let output_path =
+                output.unwrap_or_else(|| root.join("target").join(format!("{soc}.svd")));
+            let svd = export_svd(&root, &soc)?;
+            if let Some(dir) = output_path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&output_path, svd)?;
+
+            println!("Success! SVD file saved to: {}", output_path.display());
+        }
+        Command::BuildExamples {
+            soc,
+            examples,
+            features,
+            debug,
+            out_dir,
+            encryption,
+        } => {
+            let root = workspace_root();
+            let summary = build_examples(&BuildOptions {
+                root,
+                soc,
+                only: examples,
+                features,
+                release: !debug,
+                out_dir,
+                encryption: encryption.unwrap_or_default(),
+            })?;
+            print!("{}", summary);
+            if !summary.failed.is_empty() {
+                let names: Vec<_> = summary
+                    .failed
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect();
+                return Err(XtaskError::ExamplesFailed(names.join(", ")));
+            }
+        }
+        Command::Monitor {
+            port,
+            baud,
+            hex,
+            timestamps,
+            log,
+        } => {
+            run_monitor(&MonitorOptions {
+                port,
+                baud,
+                hex,
+                timestamps,
+                log,
+            })?;
+        }
     }
 
     Ok(())
 }
 
+fn resolve_image_header(
+    config: Option<PathBuf>,
+    magic: Option<String>,
+    version: Option<String>,
+) -> XtaskResult<ImageHeader> {
+    let header = match config {
+        Some(path) => ImageHeader::from_config(&fs::read_to_string(path)?)?,
+        None => ImageHeader::default(),
+    };
+    header.with_overrides(magic.as_deref(), version.as_deref())
+}
+
+fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("xtask lives in the workspace")
+        .to_path_buf()
+}
+
+/// Every `.rs` file below `dir`.
+fn rust_sources(dir: &Path) -> XtaskResult<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(rust_sources(&path)?);
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
 fn resolve_output_path(input: &Path, output: Option<PathBuf>, default_extension: &str) -> PathBuf {
     output.unwrap_or_else(|| input.with_extension(default_extension))
 }
@@ -221,6 +440,28 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_elf2bin_hex_format_default_output_path() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = TempDir::new()?;
+        let input_path = write_temp_elf(&dir, "firmware.elf");
+
+        let mut cmd = AssertCommand::cargo_bin("xtask")?;
+        cmd.arg("elf2bin")
+            .arg("--input")
+            .arg(&input_path)
+            .arg("--format")
+            .arg("hex");
+
+        let expected_output = input_path.with_extension("hex");
+        cmd.assert().success();
+
+        let contents = std::fs::read_to_string(&expected_output)?;
+        assert!(contents.starts_with(':'));
+        assert!(contents.ends_with(":00000001FF\n"));
+
+        Ok(())
+    }
+
     #[test]
     fn test_elf2img_default_output_path() -> Result<(), Box<dyn std::error::Error>> {
         let dir = TempDir::new()?;
@@ -270,4 +511,76 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_size_with_linker_script() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = TempDir::new()?;
+        let input_path = write_temp_elf(&dir, "firmware.elf");
+        let script_path = dir.path().join("link.x");
+
+        std::fs::write(
+            &script_path,
+            "MEMORY {\n    RAM : ORIGIN = 0, LENGTH = 4K\n}\n",
+        )?;
+        let mut cmd = AssertCommand::cargo_bin("xtask")?;
+        cmd.arg("size")
+            .arg("--input")
+            .arg(&input_path)
+            .arg("--linker-script")
+            .arg(&script_path)
+            .arg("--json");
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("\"name\":\".text\""))
+            .stdout(predicate::str::contains("\"problems\":[]"));
+
+        std::fs::write(
+            &script_path,
+            "MEMORY {\n    RAM : ORIGIN = 0, LENGTH = 8\n}\n",
+        )?;
+        let mut cmd = AssertCommand::cargo_bin("xtask")?;
+        cmd.arg("size")
+            .arg("--input")
+            .arg(&input_path)
+            .arg("-T")
+            .arg(&script_path);
+        cmd.assert()
+            .failure()
+            .stdout(predicate::str::contains("OVERFLOW"))
+            .stderr(predicate::str::contains("region RAM overflowed"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_provision_requires_confirmation() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = TempDir::new()?;
+        let key_path = dir.path().join("sm2.pub");
+        std::fs::write(&key_path, hex::encode(xtask::generate::config::PUBLIC_KEY))?;
+        let output_path = key_path.with_extension("otp");
+
+        let mut cmd = AssertCommand::cargo_bin("xtask")?;
+        cmd.arg("provision")
+            .arg("-e")
+            .arg("sm4")
+            .arg("-k")
+            .arg(&key_path);
+        cmd.assert()
+            .failure()
+            .stdout(predicate::str::contains("key hash:"))
+            .stderr(predicate::str::contains("--yes-i-know"));
+        assert!(!output_path.exists());
+
+        let mut cmd = AssertCommand::cargo_bin("xtask")?;
+        cmd.arg("provision")
+            .arg("-e")
+            .arg("sm4")
+            .arg("-k")
+            .arg(&key_path)
+            .arg("--yes-i-know");
+        cmd.assert().success();
+        assert_eq!(std::fs::read(&output_path)?.len(), 32);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,276 @@
+//! Serial monitor for boards running Kendryte firmware.
+//!
+//! Prints everything received on a serial port, optionally with timestamps
+//! or as a hex dump, and copies it to a log file. Lines typed on standard
+//! input are sent to the board. Lines starting with `~` are commands:
+//!
+//! - `~h`: toggle between ASCII and hex view
+//! - `~t`: toggle timestamps
+//! - `~q`: quit
+
+use crate::error::XtaskResult;
+use std::fs::File;
+use std::io::{self, BufRead, Read, Write};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Bytes shown per line in hex view.
+const HEX_BYTES_PER_LINE: usize = 16;
+
+/// Options for [`run_monitor`].
+#[derive(Debug, Clone)]
+pub struct MonitorOptions {
+    /// Serial port name, e.g. `/dev/ttyUSB0` or `COM3`.
+    pub port: String,
+    /// Baud rate.
+    pub baud: u32,
+    /// Start in hex view.
+    pub hex: bool,
+    /// Prefix every line with the time since the monitor started.
+    pub timestamps: bool,
+    /// Append everything received to this file.
+    pub log: Option<PathBuf>,
+}
+
+/// How received bytes are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    /// Printable text; other bytes except line breaks and tabs are shown as `.`.
+    Ascii,
+    /// Offset, hex bytes and ASCII column, like `hexdump -C`.
+    Hex,
+}
+
+/// Turns the received byte stream into display lines.
+#[derive(Debug)]
+pub struct Formatter {
+    view: View,
+    timestamps: bool,
+    start: Instant,
+    /// Current partial line: text in ASCII view, raw bytes in hex view.
+    line: Vec<u8>,
+    /// Number of bytes already shown in hex view.
+    offset: usize,
+}
+
+impl Formatter {
+    /// Creates a formatter starting in `view`.
+    pub fn new(view: View, timestamps: bool) -> Self {
+        Self {
+            view,
+            timestamps,
+            start: Instant::now(),
+            line: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    /// Switches between ASCII and hex view, flushing the partial line.
+    pub fn toggle_view(&mut self) -> String {
+        let pending = self.flush();
+        self.view = match self.view {
+            View::Ascii => View::Hex,
+            View::Hex => View::Ascii,
+        };
+        pending
+    }
+
+    /// Turns timestamps on or off.
+    pub fn toggle_timestamps(&mut self) {
+        self.timestamps = !self.timestamps;
+    }
+
+    /// Feeds received bytes and returns the complete lines produced.
+    pub fn push(&mut self, data: &[u8]) -> String {
+        let mut out = String::new();
+        for &byte in data {
+            match self.view {
+                View::Ascii => match byte {
+                    b'\n' => self.emit_ascii(&mut out),
+                    b'\r' => {}
+                    b'\t' | 0x20..=0x7E => self.line.push(byte),
+                    _ => self.line.push(b'.'),
+                },
+                View::Hex => {
+                    self.line.push(byte);
+                    if self.line.len() == HEX_BYTES_PER_LINE {
+                        self.emit_hex(&mut out);
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Returns the partial line, if any, as a complete line.
+    pub fn flush(&mut self) -> String {
+        let mut out = String::new();
+        if !self.line.is_empty() {
+            match self.view {
+                View::Ascii => self.emit_ascii(&mut out),
+                View::Hex => self.emit_hex(&mut out),
+            }
+        }
+        out
+    }
+
+    fn prefix(&self, out: &mut String) {
+        if self.timestamps {
+            let elapsed = self.start.elapsed();
+            out.push_str(&format!(
+                "[{:>5}.{:03}] ",
+                elapsed.as_secs(),
+                elapsed.subsec_millis()
+            ));
+        }
+    }
+
+    fn emit_ascii(&mut self, out: &mut String) {
+        self.prefix(out);
+        out.push_str(&String::from_utf8_lossy(&self.line));
+        out.push('\n');
+        self.line.clear();
+    }
+
+    fn emit_hex(&mut self, out: &mut String) {
+        self.prefix(out);
+        out.push_str(&format!("{:08x} ", self.offset));
+        for i in 0..HEX_BYTES_PER_LINE {
+            if i == HEX_BYTES_PER_LINE / 2 {
+                out.push(' ');
+            }
+            match self.line.get(i) {
+                Some(byte) => out.push_str(&format!(" {byte:02x}")),
+                None => out.push_str("   "),
+            }
+        }
+        out.push_str("  |");
+        for &byte in &self.line {
+            out.push(if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            });
+        }
+        out.push_str("|\n");
+        self.offset += self.line.len();
+        self.line.clear();
+    }
+}
+
+/// Command typed on standard input.
+enum Input {
+    Send(Vec<u8>),
+    ToggleView,
+    ToggleTimestamps,
+    Quit,
+}
+
+/// Opens the port and runs the monitor until `~q` or end of input.
+pub fn run_monitor(options: &MonitorOptions) -> XtaskResult<()> {
+    let mut port = serialport::new(&options.port, options.baud)
+        .timeout(Duration::from_millis(20))
+        .open()?;
+    let mut log = match &options.log {
+        Some(path) => Some(File::options().create(true).append(true).open(path)?),
+        None => None,
+    };
+    let view = if options.hex { View::Hex } else { View::Ascii };
+    let mut formatter = Formatter::new(view, options.timestamps);
+
+    eprintln!(
+        "Monitoring {} at {} baud. Type ~h to toggle hex view, ~t for timestamps, ~q to quit.",
+        options.port, options.baud
+    );
+
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            let input = match line.trim() {
+                "~h" => Input::ToggleView,
+                "~t" => Input::ToggleTimestamps,
+                "~q" => Input::Quit,
+                _ => Input::Send(format!("{line}\r\n").into_bytes()),
+            };
+            if tx.send(input).is_err() {
+                return;
+            }
+        }
+        let _ = tx.send(Input::Quit);
+    });
+
+    let mut stdout = io::stdout();
+    let mut buf = [0; 1024];
+    loop {
+        match rx.try_recv() {
+            Ok(Input::Send(data)) => port.write_all(&data)?,
+            Ok(Input::ToggleView) => stdout.write_all(formatter.toggle_view().as_bytes())?,
+            Ok(Input::ToggleTimestamps) => formatter.toggle_timestamps(),
+            Ok(Input::Quit) | Err(mpsc::TryRecvError::Disconnected) => break,
+            Err(mpsc::TryRecvError::Empty) => {}
+        }
+
+        let len = match port.read(&mut buf) {
+            Ok(len) => len,
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(log) = log.as_mut() {
+            log.write_all(&buf[..len])?;
+        }
+        stdout.write_all(formatter.push(&buf[..len]).as_bytes())?;
+        stdout.flush()?;
+    }
+
+    stdout.write_all(formatter.flush().as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii_lines() {
+        let mut formatter = Formatter::new(View::Ascii, false);
+        assert_eq!(formatter.push(b"hello\r\nwor"), "hello\n");
+        assert_eq!(formatter.push(b"ld\x01\n"), "world.\n");
+        assert_eq!(formatter.flush(), "");
+    }
+
+    #[test]
+    fn test_hex_lines() {
+        let mut formatter = Formatter::new(View::Hex, false);
+        let out = formatter.push(b"0123456789abcdefXY");
+        assert_eq!(
+            out,
+            "00000000  30 31 32 33 34 35 36 37  38 39 61 62 63 64 65 66  |0123456789abcdef|\n"
+        );
+        assert_eq!(
+            formatter.flush(),
+            "00000010  58 59                                             |XY|\n"
+        );
+    }
+
+    #[test]
+    fn test_toggle_view_flushes_partial_line() {
+        let mut formatter = Formatter::new(View::Ascii, false);
+        assert_eq!(formatter.push(b"abc"), "");
+        assert_eq!(formatter.toggle_view(), "abc\n");
+        assert_eq!(
+            formatter.push(b"\x00"),
+            "",
+            "hex view buffers until a full line"
+        );
+    }
+
+    #[test]
+    fn test_timestamps() {
+        let mut formatter = Formatter::new(View::Ascii, true);
+        let out = formatter.push(b"boot\n");
+        assert!(out.starts_with('['), "{out}");
+        assert!(out.ends_with("] boot\n"), "{out}");
+    }
+}
@@ -0,0 +1,379 @@
+//! Pad function tables from the vendor pinmux spreadsheet.
+//!
+//! The K230 pinmux sheet, exported as CSV, has a row per pad and a column per
+//! function select, holding signal names such as `UART0_TXD` or `GPIO38`:
+//!
+//! ```text
+//! Pad,Func0,Func1,Func2,Func3
+//! IO38,GPIO38,UART0_TXD,OSPI_CS,
+//! ```
+//!
+//! Columns without a number in their header, such as a voltage domain, are
+//! ignored. Signals with a pad trait in the HAL are turned into the `pad_*!`
+//! table invocations of `kendryte-rt/src/soc/k230`, grouped by the file that
+//! defines each macro; other signals are listed so they can be added by hand.
+
+use crate::error::{XtaskError, XtaskResult};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// One function of one pad, as listed in the sheet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PadFunction {
+    /// Pad number.
+    pub pad: u32,
+    /// Function select value.
+    pub select: u32,
+    /// Signal name, upper case.
+    pub signal: String,
+}
+
+/// Entry of a `pad_*!` table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Binding {
+    /// Table macro, e.g. `pad_uart_sout`.
+    pub table: &'static str,
+    /// Source file defining the macro, relative to the SoC directory.
+    pub file: &'static str,
+    /// Peripheral instance, or GPIO group.
+    pub instance: u32,
+    /// Pad number.
+    pub pad: u32,
+    /// Macro arguments, e.g. `(38, 1, 0)`.
+    pub args: String,
+}
+
+/// Parse the pinmux sheet.
+pub fn parse_csv(text: &str) -> XtaskResult<Vec<PadFunction>> {
+    let mut rows = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'));
+    let (_, header) = rows
+        .next()
+        .ok_or_else(|| XtaskError::PinmuxParseError("empty sheet".into()))?;
+    let columns: Vec<(usize, u32)> = split_line(header)
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter_map(|(column, name)| number(name).map(|select| (column, select)))
+        .collect();
+    if columns.is_empty() {
+        return Err(XtaskError::PinmuxParseError(
+            "no function select columns in the header".into(),
+        ));
+    }
+
+    let mut functions = Vec::new();
+    let mut seen = BTreeMap::new();
+    for (index, line) in rows {
+        let cells = split_line(line);
+        let line = index + 1;
+        let pad = number(&cells[0]).ok_or_else(|| {
+            XtaskError::PinmuxParseError(format!("line {line}: no pad number in `{}`", cells[0]))
+        })?;
+        for &(column, select) in &columns {
+            let signal = cells.get(column).map_or("", |cell| cell.as_str());
+            let signal = signal.trim().to_ascii_uppercase().replace(' ', "");
+            if signal.is_empty() || signal == "-" || signal == "NC" || signal == "RESERVED" {
+                continue;
+            }
+            if let Some(previous) = seen.insert((pad, select), line) {
+                return Err(XtaskError::PinmuxParseError(format!(
+                    "line {line}: function {select} of pad {pad} already given on line {previous}"
+                )));
+            }
+            functions.push(PadFunction {
+                pad,
+                select,
+                signal,
+            });
+        }
+    }
+    Ok(functions)
+}
+
+/// Split one CSV line, honouring double quotes.
+fn split_line(line: &str) -> Vec<String> {
+    let mut cells = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                cells.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => cells.push(String::new()),
+            c => cells.last_mut().unwrap().push(c),
+        }
+    }
+    cells
+}
+
+/// First decimal number in `s`, e.g. 38 for `IO38`.
+fn number(s: &str) -> Option<u32> {
+    let start = s.find(|c: char| c.is_ascii_digit())?;
+    let digits: String = s[start..]
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    digits.parse().ok()
+}
+
+/// Map a pad function to its table entry, if the HAL has a trait for it.
+pub fn bind(function: &PadFunction) -> Option<Binding> {
+    let PadFunction { pad, select, .. } = *function;
+    let signal = function.signal.as_str();
+    let binding = |table, file, instance, args: String| Binding {
+        table,
+        file,
+        instance,
+        pad,
+        args,
+    };
+
+    if let Some(n) = signal.strip_prefix("GPIO") {
+        let n: u32 = n.parse().ok()?;
+        let args = format!("({pad}, {select}, {}, GpioPort::A, {})", n / 32, n % 32);
+        return Some(binding("pad_gpio", "peripheral/gpio.rs", n / 32, args));
+    }
+    if let Some(n) = signal.strip_prefix("PWM") {
+        let n: u32 = n.parse().ok()?;
+        let args = format!("({pad}, {select}, {n})");
+        return Some(binding("pad_pwm_out", "peripheral/pwm.rs", n, args));
+    }
+    if let Some(rest) = signal.strip_prefix("UART") {
+        let (n, line) = rest.split_once('_')?;
+        let n: u32 = n.parse().ok()?;
+        let table = match line {
+            "TXD" | "TX" => "pad_uart_sout",
+            "RXD" | "RX" => "pad_uart_sin",
+            "RTS" => "pad_uart_rts",
+            "CTS" => "pad_uart_cts",
+            "DE" => "pad_uart_de",
+            "RE" => "pad_uart_re",
+            _ => return None,
+        };
+        let args = format!("({pad}, {select}, {n})");
+        return Some(binding(table, "peripheral/uart.rs", n, args));
+    }
+    // OSPI is SPI0, QSPI0 and QSPI1 are SPI1 and SPI2.
+    let (n, line) = match signal.split_once('_')? {
+        ("OSPI", line) => (0, line),
+        ("QSPI0", line) => (1, line),
+        ("QSPI1", line) => (2, line),
+        _ => return None,
+    };
+    let table = match line {
+        "CLK" => "pad_spi_clk",
+        "D0" => "pad_spi_mosi",
+        "D1" => "pad_spi_miso",
+        "CS" | "CS0" => "pad_spi_cs",
+        _ => return None,
+    };
+    Some(binding(
+        table,
+        "pads.rs",
+        n,
+        format!("({pad}, {select}, {n})"),
+    ))
+}
+
+/// Table entries of every function with a pad trait, sorted by table,
+/// instance and pad, and the functions without one.
+pub fn bind_all(functions: &[PadFunction]) -> (Vec<Binding>, Vec<&PadFunction>) {
+    let mut bound = Vec::new();
+    let mut unbound = Vec::new();
+    for function in functions {
+        match bind(function) {
+            Some(binding) => bound.push(binding),
+            None => unbound.push(function),
+        }
+    }
+    bound.sort_by(|a, b| {
+        (a.file, a.table, a.instance, a.pad).cmp(&(b.file, b.table, b.instance, b.pad))
+    });
+    (bound, unbound)
+}
+
+/// Render the table invocations, one section per source file.
+pub fn generate(bindings: &[Binding], source: &str) -> String {
+    let mut out = format!("// Generated by `cargo xtask gen-pinmux` from {source}.\n");
+    let mut file = "";
+    let mut table = "";
+    for binding in bindings {
+        if binding.table != table {
+            if !table.is_empty() {
+                out.push_str("}\n");
+            }
+            if binding.file != file {
+                file = binding.file;
+                let _ = write!(out, "\n// {file}\n");
+            }
+            table = binding.table;
+            let _ = writeln!(out, "\n{table}! {{");
+        }
+        let _ = writeln!(out, "    {},", binding.args);
+    }
+    if !table.is_empty() {
+        out.push_str("}\n");
+    }
+    out
+}
+
+/// Extract the entries of `pad_*!` table invocations from Rust source.
+pub fn parse_tables(source: &str) -> BTreeMap<String, BTreeSet<String>> {
+    let mut tables: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let source: String = source
+        .lines()
+        .map(|line| line.split("//").next().unwrap())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut rest = source.as_str();
+    while let Some(start) = rest.find("pad_") {
+        rest = &rest[start..];
+        let name_len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let name = &rest[..name_len];
+        let after = rest[name_len..].trim_start();
+        // Only invocations, `name! { ... }`; not `macro_rules! name` or a
+        // `$pad_num` metavariable.
+        let Some(body) = after.strip_prefix('!').map(str::trim_start) else {
+            rest = &rest[name_len..];
+            continue;
+        };
+        let Some(body) = body.strip_prefix('{') else {
+            rest = &rest[name_len..];
+            continue;
+        };
+        let end = body.find('}').unwrap_or(body.len());
+        let entries = tables.entry(name.to_string()).or_default();
+        let mut entry = &body[..end];
+        while let Some(open) = entry.find('(') {
+            let Some(close) = entry[open..].find(')') else {
+                break;
+            };
+            entries.insert(normalize(&entry[open..open + close + 1]));
+            entry = &entry[open + close + 1..];
+        }
+        rest = &body[end..];
+    }
+    tables
+}
+
+/// Canonical spelling of a table entry, `(a, b, c)`.
+fn normalize(entry: &str) -> String {
+    let inner = entry.trim_start_matches('(').trim_end_matches(')');
+    let args: Vec<&str> = inner.split(',').map(str::trim).collect();
+    format!("({})", args.join(", "))
+}
+
+/// Compare generated entries with the tables found in the sources.
+///
+/// Returns one line per entry missing on either side, for the tables the
+/// generator produces.
+pub fn compare(bindings: &[Binding], sources: &BTreeMap<String, BTreeSet<String>>) -> Vec<String> {
+    let mut generated: BTreeMap<&str, BTreeSet<String>> = BTreeMap::new();
+    for binding in bindings {
+        generated
+            .entry(binding.table)
+            .or_default()
+            .insert(normalize(&binding.args));
+    }
+    let empty = BTreeSet::new();
+    let mut problems = Vec::new();
+    for (table, entries) in &generated {
+        let existing = sources.get(*table).unwrap_or(&empty);
+        for entry in entries.difference(existing) {
+            problems.push(format!(
+                "{table}: {entry} is in the sheet but not in the sources"
+            ));
+        }
+        for entry in existing.difference(entries) {
+            problems.push(format!(
+                "{table}: {entry} is in the sources but not in the sheet"
+            ));
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHEET: &str = "\
+Pad,Voltage,Func0,Func1,Func2,Func3
+IO38,3.3V,GPIO38,UART0_TXD,OSPI_CS,
+IO39,3.3V,GPIO39,uart0_rxd,OSPI_D1,\"IIC1_SCL\"
+IO60,1.8V,GPIO60,PWM0,-,
+";
+
+    #[test]
+    fn test_parse_and_bind() {
+        let functions = parse_csv(SHEET).expect("parse");
+        assert_eq!(functions.len(), 9);
+        assert_eq!(
+            functions[1],
+            PadFunction {
+                pad: 38,
+                select: 1,
+                signal: "UART0_TXD".into(),
+            }
+        );
+
+        let (bound, unbound) = bind_all(&functions);
+        assert_eq!(bound.len(), 8);
+        assert_eq!(unbound.len(), 1);
+        assert_eq!(unbound[0].signal, "IIC1_SCL");
+        assert_eq!(bound[0].table, "pad_spi_cs");
+        assert_eq!(bound[0].args, "(38, 2, 0)");
+        let gpio = bound.iter().find(|b| b.table == "pad_gpio" && b.pad == 60);
+        assert_eq!(gpio.unwrap().args, "(60, 0, 1, GpioPort::A, 28)");
+
+        let text = generate(&bound, "k230.csv");
+        assert!(text.contains("// peripheral/uart.rs\n\npad_uart_sin! {\n    (39, 1, 0),\n}\n"));
+        assert!(text.contains("pad_pwm_out! {\n    (60, 1, 0),\n}\n"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_csv("").is_err());
+        assert!(parse_csv("Pad,Voltage\nIO1,3.3V\n").is_err());
+        assert!(parse_csv("Pad,Func1\nNC,UART0_TXD\n").is_err());
+        let duplicate = "Pad,Func1,Function 1\nIO3,UART1_TXD,PWM0\n";
+        assert!(parse_csv(duplicate).is_err());
+    }
+
+    #[test]
+    fn test_compare_with_sources() {
+        let functions = parse_csv(SHEET).expect("parse");
+        let (bound, _) = bind_all(&functions);
+        let source = "
+            macro_rules! pad_uart_sout {
+                ($pad_num:expr, $function_select:expr, $uart_num:expr) => {};
+            }
+            pad_uart_sout! {
+                (38, 1, 0),
+                (40,1,1),
+            }
+            pad_pwm_out! {
+                // pwm_0 outputs
+                (60, 1, 0),
+            }
+        ";
+        let tables = parse_tables(source);
+        assert_eq!(tables["pad_uart_sout"].len(), 2);
+        let problems = compare(&bound, &tables);
+        assert!(problems.contains(
+            &"pad_uart_sout: (40, 1, 1) is in the sources but not in the sheet".to_string()
+        ));
+        assert!(problems.contains(
+            &"pad_uart_sin: (39, 1, 0) is in the sheet but not in the sources".to_string()
+        ));
+        assert!(!problems.iter().any(|p| p.contains("pad_pwm_out")));
+    }
+}
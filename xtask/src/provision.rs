@@ -0,0 +1,279 @@
+//! Secure boot key provisioning.
+//!
+//! With secure boot enabled, the K230 BootROM hashes the public key carried in
+//! an image header and only boots the image if the hash matches the one
+//! programmed into OTP. This module derives that hash from a signing key and
+//! builds the payload to program into the OTP key hash words.
+//!
+//! The hash covers the key exactly as [`gen_image`] places it in the header:
+//!
+//! - SM2: SM3 of the public key `X || Y`, 32 bytes each, big endian.
+//! - RSA-2048: SHA-256 of the modulus (256 bytes, big endian) followed by the
+//!   public exponent (4 bytes, little endian).
+//!
+//! This layout follows the header written by the vendor image tool
+//! (`tools/firmware_gen.py` in kendryte/canmv_k230), which [`gen_image`] ports;
+//! the BootROM hashes the key fields exactly as that tool emits them. The
+//! vendor does not publish the OTP word order, so [`OtpPayload::words`] assumes
+//! the hash bytes are stored in order, four per little endian word.
+//!
+//! OTP bits can only be set, never cleared, so a wrong hash bricks secure boot
+//! on the chip for good. [`gen_image`] always signs with the built-in keys of
+//! [`config`], so [`PublicKey::check_signer`] refuses any other key: a chip
+//! provisioned with it would reject every image this tool produces.
+//!
+//! [`gen_image`]: crate::generate::image::gen_image
+//! [`config`]: crate::generate::config
+
+use crate::error::{XtaskError, XtaskResult};
+use crate::generate::config::{E, N, PUBLIC_KEY};
+use crate::generate::image::EncryptionType;
+use rsa::pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use rsa::traits::PublicKeyParts;
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use sha2::{Digest, Sha256};
+use sm3::Sm3;
+use std::fmt;
+
+/// Length in bytes of the RSA modulus accepted by the BootROM.
+pub const RSA_MODULUS_LEN: usize = 256;
+
+/// Length in bytes of an SM2 public key, `X || Y`.
+pub const SM2_PUBLIC_KEY_LEN: usize = 64;
+
+/// Number of 32-bit OTP words holding the key hash.
+pub const OTP_HASH_WORDS: usize = 8;
+
+/// A public key as laid out in the image header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PublicKey {
+    /// SM2 public key, `X || Y`.
+    Sm2([u8; SM2_PUBLIC_KEY_LEN]),
+    /// RSA-2048 modulus and public exponent.
+    Rsa {
+        /// Modulus, big endian.
+        n: [u8; RSA_MODULUS_LEN],
+        /// Public exponent.
+        e: u32,
+    },
+}
+
+impl PublicKey {
+    /// Parse a key for the signing scheme of `encryption`.
+    ///
+    /// SM2 keys (`sm4`) are 64 or 65 (with a leading `0x04`) bytes, either raw
+    /// or hex encoded. RSA keys (`aes`) are PEM files holding a PKCS#1 or
+    /// PKCS#8 public or private key.
+    pub fn parse(encryption: EncryptionType, data: &[u8]) -> XtaskResult<Self> {
+        match encryption {
+            EncryptionType::None => Err(XtaskError::InvalidKey(
+                "images without encryption are not signed".to_string(),
+            )),
+            EncryptionType::Sm4 => Self::parse_sm2(data),
+            EncryptionType::Aes => Self::parse_rsa(data),
+        }
+    }
+
+    /// The key [`gen_image`] signs images of `encryption` with.
+    ///
+    /// [`gen_image`]: crate::generate::image::gen_image
+    pub fn signer(encryption: EncryptionType) -> XtaskResult<Self> {
+        match encryption {
+            EncryptionType::None => Err(XtaskError::InvalidKey(
+                "images without encryption are not signed".to_string(),
+            )),
+            EncryptionType::Sm4 => Self::parse_sm2(PUBLIC_KEY),
+            EncryptionType::Aes => {
+                let e = u32::from_str_radix(&E[2..], 16).map_err(|_| {
+                    XtaskError::RsaParseError("Failed to parse E for RSA".to_string())
+                })?;
+                let n = N.try_into().map_err(|_| {
+                    XtaskError::RsaParseError("Failed to parse N for RSA".to_string())
+                })?;
+                Ok(Self::Rsa { n, e })
+            }
+        }
+    }
+
+    /// Fail unless this is the key images of `encryption` are signed with.
+    ///
+    /// Image generation has no way to sign with another key, so programming
+    /// the hash of any other key would leave the chip unable to boot.
+    pub fn check_signer(&self, encryption: EncryptionType) -> XtaskResult<()> {
+        if *self != Self::signer(encryption)? {
+            return Err(XtaskError::InvalidKey(
+                "gen-image signs with the built-in key only; a chip provisioned with this key \
+                 would reject every generated image"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn parse_sm2(data: &[u8]) -> XtaskResult<Self> {
+        let text = std::str::from_utf8(data).ok().map(|s| {
+            s.chars()
+                .filter(|c| !c.is_whitespace() && *c != ':')
+                .collect::<String>()
+        });
+        let decoded = text.and_then(|s| hex::decode(s.trim_start_matches("0x")).ok());
+        let bytes = decoded.as_deref().unwrap_or(data);
+        let bytes = match bytes {
+            [0x04, rest @ ..] if rest.len() == SM2_PUBLIC_KEY_LEN => rest,
+            _ => bytes,
+        };
+        let key = bytes.try_into().map_err(|_| {
+            XtaskError::InvalidKey(format!(
+                "SM2 public key must be {SM2_PUBLIC_KEY_LEN} bytes, got {}",
+                bytes.len()
+            ))
+        })?;
+        Ok(Self::Sm2(key))
+    }
+
+    fn parse_rsa(data: &[u8]) -> XtaskResult<Self> {
+        let pem = std::str::from_utf8(data)
+            .map_err(|_| XtaskError::InvalidKey("RSA key must be PEM encoded".to_string()))?;
+        let key = RsaPublicKey::from_public_key_pem(pem)
+            .or_else(|_| RsaPublicKey::from_pkcs1_pem(pem))
+            .or_else(|_| RsaPrivateKey::from_pkcs8_pem(pem).map(|k| k.to_public_key()))
+            .or_else(|_| RsaPrivateKey::from_pkcs1_pem(pem).map(|k| k.to_public_key()))
+            .map_err(|_| XtaskError::InvalidKey("no RSA key found in PEM".to_string()))?;
+
+        let n = key.n().to_bytes_be();
+        let n = n.as_slice().try_into().map_err(|_| {
+            XtaskError::InvalidKey(format!(
+                "RSA modulus must be {} bits, got {}",
+                RSA_MODULUS_LEN * 8,
+                key.n().bits()
+            ))
+        })?;
+        let e = key.e().to_bytes_le();
+        if e.len() > 4 {
+            return Err(XtaskError::InvalidKey(
+                "RSA public exponent must fit in 32 bits".to_string(),
+            ));
+        }
+        let mut e_bytes = [0; 4];
+        e_bytes[..e.len()].copy_from_slice(&e);
+        Ok(Self::Rsa {
+            n,
+            e: u32::from_le_bytes(e_bytes),
+        })
+    }
+
+    /// The key hash the BootROM compares against OTP.
+    pub fn hash(&self) -> [u8; 32] {
+        match self {
+            Self::Sm2(key) => Sm3::digest(key).into(),
+            Self::Rsa { n, e } => {
+                let mut hasher = Sha256::new();
+                hasher.update(n);
+                hasher.update(e.to_le_bytes());
+                hasher.finalize().into()
+            }
+        }
+    }
+}
+
+/// The OTP programming payload for a key hash.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OtpPayload {
+    /// Key hash.
+    pub hash: [u8; 32],
+}
+
+impl OtpPayload {
+    /// Build the payload for `key`.
+    pub fn new(key: &PublicKey) -> Self {
+        Self { hash: key.hash() }
+    }
+
+    /// The hash as the OTP words, each read little endian from the hash bytes.
+    pub fn words(&self) -> [u32; OTP_HASH_WORDS] {
+        core::array::from_fn(|i| {
+            u32::from_le_bytes(self.hash[i * 4..i * 4 + 4].try_into().unwrap())
+        })
+    }
+
+    /// The payload bytes, the OTP words in order.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.words().iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+}
+
+impl fmt::Display for OtpPayload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "key hash: {}", hex::encode(self.hash))?;
+        for (i, word) in self.words().iter().enumerate() {
+            writeln!(f, "  word {i}: 0x{word:08x}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint_dig::BigUint;
+    use rsa::pkcs8::EncodePublicKey;
+    use rsa::pkcs8::LineEnding;
+
+    #[test]
+    fn test_sm2_key_formats() {
+        let raw = PublicKey::parse(EncryptionType::Sm4, PUBLIC_KEY).expect("raw");
+        let mut sec1 = vec![0x04];
+        sec1.extend(PUBLIC_KEY);
+        let hex = format!("04{}\n", hex::encode(PUBLIC_KEY));
+        assert_eq!(
+            PublicKey::parse(EncryptionType::Sm4, &sec1).expect("sec1"),
+            raw
+        );
+        assert_eq!(
+            PublicKey::parse(EncryptionType::Sm4, hex.as_bytes()).expect("hex"),
+            raw
+        );
+        assert_eq!(raw.hash(), <[u8; 32]>::from(Sm3::digest(PUBLIC_KEY)));
+        assert!(PublicKey::parse(EncryptionType::Sm4, &PUBLIC_KEY[1..]).is_err());
+        assert!(PublicKey::parse(EncryptionType::None, PUBLIC_KEY).is_err());
+    }
+
+    #[test]
+    fn test_rsa_key_hash_matches_image_header() {
+        let e = u32::from_str_radix(&E[2..], 16).unwrap();
+        let key = RsaPublicKey::new(BigUint::from_bytes_be(N), BigUint::from(e)).unwrap();
+        let pem = key.to_public_key_pem(LineEnding::LF).unwrap();
+        let key = PublicKey::parse(EncryptionType::Aes, pem.as_bytes()).expect("pem");
+
+        let mut header = N.to_vec();
+        header.extend(e.to_le_bytes());
+        let payload = OtpPayload::new(&key);
+        assert_eq!(payload.hash, <[u8; 32]>::from(Sha256::digest(&header)));
+        assert_eq!(payload.to_bytes(), payload.hash);
+    }
+
+    #[test]
+    fn test_only_signer_key_is_accepted() {
+        let key = PublicKey::parse(EncryptionType::Sm4, PUBLIC_KEY).expect("raw");
+        key.check_signer(EncryptionType::Sm4)
+            .expect("built-in SM2 key");
+        assert!(key.check_signer(EncryptionType::Aes).is_err());
+
+        let mut other = *PUBLIC_KEY.first_chunk::<SM2_PUBLIC_KEY_LEN>().unwrap();
+        other[0] ^= 1;
+        assert!(
+            PublicKey::Sm2(other)
+                .check_signer(EncryptionType::Sm4)
+                .is_err()
+        );
+
+        let e = u32::from_str_radix(&E[2..], 16).unwrap();
+        let key = RsaPublicKey::new(BigUint::from_bytes_be(N), BigUint::from(e)).unwrap();
+        let pem = key.to_public_key_pem(LineEnding::LF).unwrap();
+        let key = PublicKey::parse(EncryptionType::Aes, pem.as_bytes()).expect("pem");
+        key.check_signer(EncryptionType::Aes)
+            .expect("built-in RSA key");
+        assert!(key.check_signer(EncryptionType::None).is_err());
+    }
+}
@@ -0,0 +1,428 @@
+//! ELF size and memory usage reports.
+//!
+//! Sizes every allocated section of a linked firmware, places it in the
+//! memory regions of the linker script and in the K230 memory map, and flags
+//! regions that are over-full or sections that fall outside every region.
+
+use crate::error::{XtaskError, XtaskResult};
+use object::{Object, ObjectSection, SectionFlags};
+use std::fmt;
+
+/// A named address range.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryRegion {
+    /// Region name, e.g. `SPL` or `SRAM`.
+    pub name: String,
+    /// First address of the region.
+    pub origin: u64,
+    /// Size of the region in bytes.
+    pub length: u64,
+}
+
+impl MemoryRegion {
+    fn new(name: &str, origin: u64, length: u64) -> Self {
+        Self {
+            name: name.to_string(),
+            origin,
+            length,
+        }
+    }
+
+    /// Returns true if `size` bytes at `address` lie within the region.
+    fn contains(&self, address: u64, size: u64) -> bool {
+        address >= self.origin
+            && address.saturating_add(size) <= self.origin.saturating_add(self.length)
+    }
+
+    /// Returns true if `address` lies within the region.
+    fn contains_address(&self, address: u64) -> bool {
+        address >= self.origin && address - self.origin < self.length
+    }
+}
+
+/// On-chip memories of the K230.
+pub fn k230_memory_map() -> Vec<MemoryRegion> {
+    vec![
+        MemoryRegion::new("SRAM", 0x8020_0000, 0x20_0000),
+        MemoryRegion::new("DDR", 0x0000_0000, 0x8000_0000),
+    ]
+}
+
+/// Linker script regions used by `kendryte-rt` for the K230.
+pub fn k230_linker_regions() -> Vec<MemoryRegion> {
+    vec![MemoryRegion::new("SPL", 0x8030_0000, 0x10_0000)]
+}
+
+/// Parse the `MEMORY` command of a linker script.
+///
+/// Each region must be on a line of its own, in the form
+/// `NAME [(attributes)] : ORIGIN = value, LENGTH = value`. Values are
+/// decimal, octal or hexadecimal numbers with an optional `K` or `M` suffix.
+pub fn parse_memory_regions(script: &str) -> XtaskResult<Vec<MemoryRegion>> {
+    let script = strip_comments(script);
+    let start = script
+        .find("MEMORY")
+        .ok_or_else(|| XtaskError::LinkerScriptParseError("no MEMORY command".into()))?;
+    let rest = &script[start..];
+    let open = rest
+        .find('{')
+        .ok_or_else(|| XtaskError::LinkerScriptParseError("MEMORY without `{`".into()))?;
+    let close = rest
+        .find('}')
+        .ok_or_else(|| XtaskError::LinkerScriptParseError("MEMORY without `}`".into()))?;
+    rest[open + 1..close]
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(parse_region)
+        .collect()
+}
+
+fn parse_region(line: &str) -> XtaskResult<MemoryRegion> {
+    let error = || XtaskError::LinkerScriptParseError(format!("invalid region `{line}`"));
+    let (head, attributes) = line.split_once(':').ok_or_else(error)?;
+    let name = head.split('(').next().unwrap_or_default().trim();
+    if name.is_empty() {
+        return Err(error());
+    }
+    let mut origin = None;
+    let mut length = None;
+    for attribute in attributes.split(',') {
+        let (key, value) = attribute.split_once('=').ok_or_else(error)?;
+        let value = parse_number(value.trim()).ok_or_else(error)?;
+        match key.trim() {
+            "ORIGIN" | "org" | "o" => origin = Some(value),
+            "LENGTH" | "len" | "l" => length = Some(value),
+            _ => return Err(error()),
+        }
+    }
+    Ok(MemoryRegion::new(
+        name,
+        origin.ok_or_else(error)?,
+        length.ok_or_else(error)?,
+    ))
+}
+
+fn parse_number(s: &str) -> Option<u64> {
+    let (digits, scale) = match s.as_bytes().last()? {
+        b'K' | b'k' => (&s[..s.len() - 1], 1 << 10),
+        b'M' | b'm' => (&s[..s.len() - 1], 1 << 20),
+        _ => (s, 1),
+    };
+    let value = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        u64::from_str_radix(hex, 16).ok()?
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        u64::from_str_radix(&digits[1..], 8).ok()?
+    } else {
+        digits.parse().ok()?
+    };
+    value.checked_mul(scale)
+}
+
+fn strip_comments(script: &str) -> String {
+    let mut out = String::with_capacity(script.len());
+    let mut rest = script;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[..start]);
+        rest = match rest[start..].find("*/") {
+            Some(end) => &rest[start + end + 2..],
+            None => "",
+        };
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Size and placement of one allocated section.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SectionUsage {
+    /// Section name.
+    pub name: String,
+    /// Run-time address.
+    pub address: u64,
+    /// Size in bytes, including zero-initialized sections.
+    pub size: u64,
+    /// Linker script region holding the section, if any.
+    pub region: Option<String>,
+}
+
+/// Bytes used in a memory region.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegionUsage {
+    /// The region.
+    pub region: MemoryRegion,
+    /// Bytes used by sections placed in the region.
+    pub used: u64,
+}
+
+impl RegionUsage {
+    /// Returns true if the sections placed in the region do not fit.
+    pub fn overflows(&self) -> bool {
+        self.used > self.region.length
+    }
+
+    fn percent(&self) -> f64 {
+        match self.region.length {
+            0 => 100.0,
+            length => self.used as f64 * 100.0 / length as f64,
+        }
+    }
+}
+
+/// Memory usage of a linked firmware.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SizeReport {
+    /// Allocated sections, in address order.
+    pub sections: Vec<SectionUsage>,
+    /// Usage of the linker script regions.
+    pub regions: Vec<RegionUsage>,
+    /// Usage of the chip memories.
+    pub memories: Vec<RegionUsage>,
+}
+
+impl SizeReport {
+    /// Build a report from the allocated sections of `elf_data`.
+    pub fn from_elf(elf_data: &[u8], regions: &[MemoryRegion]) -> XtaskResult<Self> {
+        let elf_file =
+            object::File::parse(elf_data).map_err(|e| XtaskError::ElfParseError(e.to_string()))?;
+        let sections = elf_file
+            .sections()
+            .filter(|s| match s.flags() {
+                SectionFlags::Elf { sh_flags } => sh_flags & object::elf::SHF_ALLOC as u64 != 0,
+                _ => false,
+            })
+            .filter(|s| s.size() != 0)
+            .map(|s| {
+                let name = s.name().unwrap_or("<unnamed>").to_string();
+                (name, s.address(), s.size())
+            })
+            .collect::<Vec<_>>();
+        Ok(Self::from_sections(&sections, regions))
+    }
+
+    /// Build a report from `(name, address, size)` section triples.
+    pub fn from_sections(sections: &[(String, u64, u64)], regions: &[MemoryRegion]) -> Self {
+        let mut sections = sections
+            .iter()
+            .map(|(name, address, size)| SectionUsage {
+                name: name.clone(),
+                address: *address,
+                size: *size,
+                region: regions
+                    .iter()
+                    .find(|r| r.contains_address(*address))
+                    .map(|r| r.name.clone()),
+            })
+            .collect::<Vec<_>>();
+        sections.sort_by_key(|s| s.address);
+        let usage = |regions: &[MemoryRegion]| {
+            regions
+                .iter()
+                .map(|region| RegionUsage {
+                    region: region.clone(),
+                    used: sections
+                        .iter()
+                        .filter(|s| region.contains_address(s.address))
+                        .map(|s| s.size)
+                        .sum(),
+                })
+                .collect::<Vec<_>>()
+        };
+        Self {
+            regions: usage(regions),
+            memories: usage(&k230_memory_map()),
+            sections,
+        }
+    }
+
+    /// Describe every problem found: over-full regions and sections outside all regions.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for usage in self.regions.iter().filter(|u| u.overflows()) {
+            problems.push(format!(
+                "region {} overflowed by {} bytes",
+                usage.region.name,
+                usage.used - usage.region.length
+            ));
+        }
+        for section in &self.sections {
+            let usage = self
+                .regions
+                .iter()
+                .find(|u| Some(&u.region.name) == section.region.as_ref());
+            match usage {
+                None => problems.push(format!(
+                    "section {} at 0x{:x} is outside every region",
+                    section.name, section.address
+                )),
+                // Over-full regions are already reported above.
+                Some(u) if !u.overflows() && !u.region.contains(section.address, section.size) => {
+                    problems.push(format!(
+                        "section {} at 0x{:x} runs past the end of region {}",
+                        section.name, section.address, u.region.name
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+        problems
+    }
+
+    /// Render the report as JSON.
+    pub fn to_json(&self) -> String {
+        let sections = self
+            .sections
+            .iter()
+            .map(|s| {
+                let region = match &s.region {
+                    Some(name) => format!("\"{}\"", json_escape(name)),
+                    None => "null".to_string(),
+                };
+                format!(
+                    "{{\"name\":\"{}\",\"address\":{},\"size\":{},\"region\":{}}}",
+                    json_escape(&s.name),
+                    s.address,
+                    s.size,
+                    region
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let regions = |usages: &[RegionUsage]| {
+            usages
+                .iter()
+                .map(|u| {
+                    format!(
+                        "{{\"name\":\"{}\",\"origin\":{},\"length\":{},\"used\":{},\"overflow\":{}}}",
+                        json_escape(&u.region.name),
+                        u.region.origin,
+                        u.region.length,
+                        u.used,
+                        u.overflows()
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let problems = self
+            .problems()
+            .iter()
+            .map(|p| format!("\"{}\"", json_escape(p)))
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            "{{\"sections\":[{}],\"regions\":[{}],\"memories\":[{}],\"problems\":[{}]}}",
+            sections,
+            regions(&self.regions),
+            regions(&self.memories),
+            problems
+        )
+    }
+}
+
+impl fmt::Display for SizeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<24} {:>12} {:>10}  region",
+            "section", "address", "size"
+        )?;
+        for s in &self.sections {
+            writeln!(
+                f,
+                "{:<24} {:>#12x} {:>10}  {}",
+                s.name,
+                s.address,
+                s.size,
+                s.region.as_deref().unwrap_or("-")
+            )?;
+        }
+        for (title, usages) in [("region", &self.regions), ("memory", &self.memories)] {
+            writeln!(f)?;
+            writeln!(
+                f,
+                "{:<24} {:>12} {:>10} {:>10}  use",
+                title, "origin", "used", "length"
+            )?;
+            for u in usages {
+                writeln!(
+                    f,
+                    "{:<24} {:>#12x} {:>10} {:>10} {:>5.1}%{}",
+                    u.region.name,
+                    u.region.origin,
+                    u.used,
+                    u.region.length,
+                    u.percent(),
+                    if u.overflows() { "  OVERFLOW" } else { "" }
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_memory_regions() {
+        let script = "
+            OUTPUT_ARCH(riscv)
+            MEMORY {
+                /* boot loader */
+                SPL (rwx) : ORIGIN = 0x80300000, LENGTH = 1M
+                DDR : org = 0x200000, len = 512K
+            }
+        ";
+        let regions = parse_memory_regions(script).expect("parse");
+        assert_eq!(
+            regions,
+            [
+                MemoryRegion::new("SPL", 0x8030_0000, 0x10_0000),
+                MemoryRegion::new("DDR", 0x20_0000, 0x8_0000),
+            ]
+        );
+        assert!(parse_memory_regions("SECTIONS {}").is_err());
+        assert!(parse_memory_regions("MEMORY { SPL : ORIGIN = 0x0 }").is_err());
+    }
+
+    #[test]
+    fn test_report_usage_and_overflow() {
+        let sections = [
+            (".text".to_string(), 0x8030_0000, 0xC_0000),
+            (".data".to_string(), 0x803C_0000, 0x2_0000),
+            (".bss".to_string(), 0x803E_0000, 0x4_0000),
+            (".stray".to_string(), 0x1000, 0x10),
+        ];
+        let report = SizeReport::from_sections(&sections, &k230_linker_regions());
+        assert_eq!(report.regions[0].used, 0x12_0000);
+        assert!(report.regions[0].overflows());
+        assert_eq!(report.memories[0].used, 0x12_0000);
+        assert_eq!(report.memories[1].used, 0x10);
+        let problems = report.problems();
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert!(problems[0].contains("SPL overflowed by 131072 bytes"));
+        assert!(problems[1].contains(".stray"));
+
+        let json = report.to_json();
+        assert!(json.contains("\"name\":\".text\",\"address\":2150629376,\"size\":786432"));
+        assert!(json.contains("\"overflow\":true"));
+    }
+}
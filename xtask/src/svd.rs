@@ -0,0 +1,745 @@
+//! CMSIS-SVD export of the HAL register definitions.
+//!
+//! There is no public SVD file for the Kendryte chips, so this module builds
+//! one from the sources: the peripheral list and base addresses come from the
+//! `peripheral!` block of `kendryte-rt/src/soc/<soc>/mod.rs`, and each
+//! register block from the `#[derive(Mmio)]` struct in
+//! `kendryte-hal/src/<module>/register.rs`, with the fields of its
+//! `#[bitfield]` register types and the doc comments as descriptions.
+//!
+//! The sources are read as text, relying on the one-item-per-line layout
+//! rustfmt gives them, rather than compiled.
+
+use crate::error::{XtaskError, XtaskResult};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// Register or field access.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    ReadOnly,
+    WriteOnly,
+    ReadWrite,
+}
+
+impl Access {
+    fn as_str(self) -> &'static str {
+        match self {
+            Access::ReadOnly => "read-only",
+            Access::WriteOnly => "write-only",
+            Access::ReadWrite => "read-write",
+        }
+    }
+
+    /// Access of a `#[bit(.., rw)]` style attribute argument.
+    fn from_bitbybit(s: &str) -> Option<Self> {
+        match s.trim() {
+            "r" => Some(Access::ReadOnly),
+            "w" => Some(Access::WriteOnly),
+            "rw" => Some(Access::ReadWrite),
+            _ => None,
+        }
+    }
+}
+
+/// Field of a `#[bitfield]` register type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub description: String,
+    pub lsb: u32,
+    pub msb: u32,
+    pub access: Access,
+}
+
+/// `#[bitfield]` register type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bitfield {
+    pub width: u32,
+    pub fields: Vec<Field>,
+}
+
+/// Field of a `#[derive(Mmio)]` register block, as written in the source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockField {
+    pub name: String,
+    pub description: String,
+    pub ty: String,
+    pub access: Access,
+    /// Marked `#[mmio(Inner)]`, a nested register block.
+    pub inner: bool,
+}
+
+/// Structs of interest in one source file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SourceFile {
+    pub blocks: BTreeMap<String, Vec<BlockField>>,
+    pub bitfields: BTreeMap<String, Bitfield>,
+}
+
+/// Collect the register blocks and bitfield types of a source file.
+pub fn parse_source(source: &str) -> SourceFile {
+    let mut file = SourceFile::default();
+    let mut docs: Vec<String> = Vec::new();
+    let mut attrs: Vec<String> = Vec::new();
+    let mut lines = source.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        if let Some(doc) = line.strip_prefix("///") {
+            docs.push(doc.trim().to_string());
+            continue;
+        }
+        if line.starts_with("#[") {
+            attrs.push(line.to_string());
+            continue;
+        }
+        let struct_name = line
+            .strip_prefix("pub struct ")
+            .and_then(|rest| rest.strip_suffix('{'))
+            .map(str::trim);
+        match struct_name {
+            Some(name) if attrs.iter().any(|a| a.contains("derive(Mmio)")) => {
+                let fields = parse_block_fields(&mut lines);
+                file.blocks.insert(name.to_string(), fields);
+            }
+            Some(name) if attrs.iter().any(|a| a.starts_with("#[bitfield(")) => {
+                let attr = attrs.iter().find(|a| a.starts_with("#[bitfield(")).unwrap();
+                let width = attr["#[bitfield(u".len()..]
+                    .split(|c: char| !c.is_ascii_digit())
+                    .next()
+                    .and_then(|w| w.parse().ok())
+                    .unwrap_or(32);
+                let fields = parse_bitfield_fields(&mut lines);
+                file.bitfields
+                    .insert(name.to_string(), Bitfield { width, fields });
+            }
+            _ => {}
+        }
+        docs.clear();
+        attrs.clear();
+    }
+    file
+}
+
+fn parse_block_fields<'a>(lines: &mut impl Iterator<Item = &'a str>) -> Vec<BlockField> {
+    let mut fields = Vec::new();
+    let mut docs: Vec<&str> = Vec::new();
+    let mut access = Access::ReadWrite;
+    let mut inner = false;
+    for line in lines {
+        if line.starts_with('}') {
+            break;
+        }
+        if let Some(doc) = line.strip_prefix("///") {
+            docs.push(doc.trim());
+            continue;
+        }
+        if let Some(attr) = line.strip_prefix("#[mmio(") {
+            if attr.contains("Inner") {
+                inner = true;
+            } else if attr.contains("Read") {
+                access = Access::ReadOnly;
+            } else if attr.contains("Write") {
+                access = Access::WriteOnly;
+            }
+            continue;
+        }
+        if let Some((name, ty)) = split_field(line) {
+            fields.push(BlockField {
+                name: name.to_string(),
+                description: docs.join(" "),
+                ty: ty.to_string(),
+                access,
+                inner,
+            });
+        }
+        docs.clear();
+        access = Access::ReadWrite;
+        inner = false;
+    }
+    fields
+}
+
+fn parse_bitfield_fields<'a>(lines: &mut impl Iterator<Item = &'a str>) -> Vec<Field> {
+    let mut fields = Vec::new();
+    let mut docs: Vec<&str> = Vec::new();
+    let mut bits = None;
+    for line in lines {
+        if line.starts_with('}') {
+            break;
+        }
+        if let Some(doc) = line.strip_prefix("///") {
+            docs.push(doc.trim());
+            continue;
+        }
+        if let Some(args) = line
+            .strip_prefix("#[bits(")
+            .or_else(|| line.strip_prefix("#[bit("))
+        {
+            let args = args.trim_end_matches(")]");
+            let (range, access) = args.split_once(',').unwrap_or((args, "rw"));
+            let (lsb, msb) = match range.split_once("..=") {
+                Some((lsb, msb)) => (parse_number(lsb), parse_number(msb)),
+                None => (parse_number(range), parse_number(range)),
+            };
+            bits = lsb
+                .zip(msb)
+                .zip(Access::from_bitbybit(access))
+                .map(|((lsb, msb), access)| (lsb, msb, access));
+            continue;
+        }
+        if let (Some((name, _)), Some((lsb, msb, access))) = (split_field(line), bits) {
+            fields.push(Field {
+                name: name.to_string(),
+                description: docs.join(" "),
+                lsb,
+                msb,
+                access,
+            });
+        }
+        if !line.is_empty() {
+            docs.clear();
+            bits = None;
+        }
+    }
+    fields
+}
+
+/// Split `pub name: Type, // comment` into name and type.
+fn split_field(line: &str) -> Option<(&str, &str)> {
+    let line = line.split("//").next().unwrap().split("/*").next().unwrap();
+    let line = line.trim().strip_suffix(',')?;
+    let line = line.strip_prefix("pub ").unwrap_or(line);
+    let (name, ty) = line.split_once(':')?;
+    Some((name.trim(), ty.trim()))
+}
+
+/// Parse a decimal or `0x` hexadecimal literal, with `_` separators.
+fn parse_number(s: &str) -> Option<u32> {
+    let s = s.trim().replace('_', "");
+    match s.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+/// Peripheral instance from the `peripheral!` block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Instance {
+    pub name: String,
+    pub description: String,
+    pub base: u64,
+    /// HAL module defining the register block, e.g. `uart`.
+    pub module: String,
+    pub irq: Option<u32>,
+}
+
+/// Parse the `peripheral!` block of a SoC module.
+pub fn parse_instances(source: &str) -> XtaskResult<Vec<Instance>> {
+    let start = source
+        .find("peripheral! {")
+        .ok_or_else(|| XtaskError::SvdExportError("no `peripheral!` block".into()))?;
+    let mut instances = Vec::new();
+    let mut docs: Vec<&str> = Vec::new();
+    let mut lines = source[start..].lines().skip(1).map(str::trim);
+    while let Some(line) = lines.next() {
+        if line == "}" {
+            break;
+        }
+        if let Some(doc) = line.strip_prefix("///") {
+            docs.push(doc.trim());
+            continue;
+        }
+        let Some(item) = line.strip_prefix("pub struct ") else {
+            docs.clear();
+            continue;
+        };
+        // Items with keys continue up to the `};` line.
+        let mut item = item.to_string();
+        while !item.trim_end().ends_with(';') {
+            match lines.next() {
+                Some(next) => item.push_str(next),
+                None => break,
+            }
+        }
+        let error = || XtaskError::SvdExportError(format!("cannot parse `pub struct {item}`"));
+        let (name, rest) = item.split_once("=>").ok_or_else(error)?;
+        let (address, rest) = rest.split_once(',').ok_or_else(error)?;
+        let block = rest.split([',', '{', ';']).next().ok_or_else(error)?.trim();
+        let module = block.rsplit("::").nth(1).ok_or_else(error)?;
+        let irq = rest
+            .split_once("irq =")
+            .and_then(|(_, irq)| parse_number(irq.split([',', '}']).next().unwrap()));
+        instances.push(Instance {
+            name: name.trim().to_string(),
+            description: docs.join(" "),
+            base: parse_number(address).ok_or_else(error)? as u64,
+            module: module.to_string(),
+            irq,
+        });
+        docs.clear();
+    }
+    Ok(instances)
+}
+
+/// Register of a peripheral, resolved to offsets and sizes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Item {
+    Register {
+        name: String,
+        description: String,
+        offset: u64,
+        /// Width in bits.
+        size: u32,
+        access: Access,
+        /// Element count and stride of a register array.
+        dim: Option<(u64, u64)>,
+        fields: Vec<Field>,
+    },
+    Cluster {
+        name: String,
+        description: String,
+        offset: u64,
+        dim: Option<(u64, u64)>,
+        items: Vec<Item>,
+    },
+}
+
+/// Register blocks and bitfield types of one HAL module.
+#[derive(Debug, Default)]
+pub struct Module {
+    /// Source files by stem, e.g. `register` or `pad`.
+    pub files: BTreeMap<String, SourceFile>,
+}
+
+impl Module {
+    /// Read every source file of the module directory `dir`.
+    pub fn load(dir: &Path) -> XtaskResult<Self> {
+        let mut module = Module::default();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "rs") {
+                let stem = path.file_stem().unwrap().to_string_lossy().into_owned();
+                module
+                    .files
+                    .insert(stem, parse_source(&fs::read_to_string(&path)?));
+            }
+        }
+        Ok(module)
+    }
+
+    fn bitfield(&self, name: &str) -> Option<&Bitfield> {
+        self.files
+            .values()
+            .find_map(|file| file.bitfields.get(name))
+    }
+
+    /// Lay out the register block `name` of `file`, returning its items and
+    /// size in bytes.
+    pub fn layout(&self, file: &str, name: &str) -> XtaskResult<(Vec<Item>, u64)> {
+        let fields = self
+            .files
+            .get(file)
+            .and_then(|f| f.blocks.get(name))
+            .ok_or_else(|| {
+                XtaskError::SvdExportError(format!("no register block {file}::{name}"))
+            })?;
+        let mut items = Vec::new();
+        let mut offset = 0;
+        for field in fields {
+            let (element, count) = split_array(&field.ty)?;
+            let dim = count.map(|count| count as u64);
+            if field.inner {
+                // `pad::RegisterBlock` lives in `pad.rs`.
+                let (inner_file, inner_name) = element.rsplit_once("::").unwrap_or((file, element));
+                let (inner, size) = self.layout(inner_file, inner_name)?;
+                items.push(Item::Cluster {
+                    name: array_name(&field.name, dim),
+                    description: field.description.clone(),
+                    offset,
+                    dim: dim.map(|count| (count, size)),
+                    items: inner,
+                });
+                offset += size * dim.unwrap_or(1);
+                continue;
+            }
+            let (element, access) = unwrap_access(element, field.access);
+            let (size, fields) = match primitive_width(element) {
+                Some(width) => (width, Vec::new()),
+                None => {
+                    let bitfield = self.bitfield(element).ok_or_else(|| {
+                        XtaskError::SvdExportError(format!("unknown register type `{element}`"))
+                    })?;
+                    (bitfield.width, bitfield.fields.clone())
+                }
+            };
+            let bytes = size as u64 / 8;
+            // repr(C) aligns every field to its size.
+            offset = offset.next_multiple_of(bytes);
+            if !field.name.starts_with('_') {
+                items.push(Item::Register {
+                    name: array_name(&field.name, dim),
+                    description: field.description.clone(),
+                    offset,
+                    size,
+                    access,
+                    dim: dim.map(|count| (count, bytes)),
+                    fields,
+                });
+            }
+            offset += bytes * dim.unwrap_or(1);
+        }
+        Ok((items, offset))
+    }
+}
+
+/// Split `[T; N]` into `T` and `N`; other types have no count.
+fn split_array(ty: &str) -> XtaskResult<(&str, Option<u32>)> {
+    let Some(inner) = ty.strip_prefix('[').and_then(|t| t.strip_suffix(']')) else {
+        return Ok((ty, None));
+    };
+    let (element, count) = inner
+        .split_once(';')
+        .ok_or_else(|| XtaskError::SvdExportError(format!("cannot parse type `{ty}`")))?;
+    let count = parse_number(count)
+        .ok_or_else(|| XtaskError::SvdExportError(format!("cannot parse type `{ty}`")))?;
+    Ok((element.trim(), Some(count)))
+}
+
+/// Strip an `RW<T>`, `RO<T>` or `WO<T>` wrapper, returning its access.
+fn unwrap_access(ty: &str, access: Access) -> (&str, Access) {
+    let wrappers = [
+        ("RW<", Access::ReadWrite),
+        ("RO<", Access::ReadOnly),
+        ("WO<", Access::WriteOnly),
+    ];
+    for (prefix, wrapper_access) in wrappers {
+        if let Some(inner) = ty.strip_prefix(prefix).and_then(|t| t.strip_suffix('>')) {
+            return (inner.trim(), wrapper_access);
+        }
+    }
+    (ty, access)
+}
+
+fn primitive_width(ty: &str) -> Option<u32> {
+    match ty {
+        "u8" => Some(8),
+        "u16" => Some(16),
+        "u32" => Some(32),
+        "u64" => Some(64),
+        _ => None,
+    }
+}
+
+fn array_name(name: &str, dim: Option<u64>) -> String {
+    match dim {
+        Some(_) => format!("{name}[%s]"),
+        None => name.to_string(),
+    }
+}
+
+/// Build the SVD file of `soc` from the workspace at `root`.
+pub fn export_svd(root: &Path, soc: &str) -> XtaskResult<String> {
+    let soc_file = root.join("kendryte-rt/src/soc").join(soc).join("mod.rs");
+    if !soc_file.exists() {
+        return Err(XtaskError::UnknownSoc(soc.to_string()));
+    }
+    let instances = parse_instances(&fs::read_to_string(soc_file)?)?;
+    let mut modules = BTreeMap::new();
+    for instance in &instances {
+        if !modules.contains_key(&instance.module) {
+            let dir = root.join("kendryte-hal/src").join(&instance.module);
+            modules.insert(instance.module.clone(), Module::load(&dir)?);
+        }
+    }
+    let mut blocks = BTreeMap::new();
+    for (name, module) in &modules {
+        blocks.insert(name.as_str(), module.layout("register", "RegisterBlock")?);
+    }
+    Ok(render(soc, &instances, &blocks))
+}
+
+/// Render the SVD document.
+pub fn render(
+    soc: &str,
+    instances: &[Instance],
+    blocks: &BTreeMap<&str, (Vec<Item>, u64)>,
+) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    out.push_str(
+        "<device schemaVersion=\"1.3\" xmlns:xs=\"http://www.w3.org/2001/XMLSchema-instance\" \
+         xs:noNamespaceSchemaLocation=\"CMSIS-SVD.xsd\">\n",
+    );
+    let _ = writeln!(out, "  <vendor>Canaan</vendor>");
+    let _ = writeln!(out, "  <name>{}</name>", soc.to_uppercase());
+    let _ = writeln!(out, "  <version>{}</version>", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(
+        out,
+        "  <description>Kendryte {} peripherals, generated from kendryte-hal by `cargo xtask export-svd`</description>",
+        soc.to_uppercase()
+    );
+    out.push_str("  <addressUnitBits>8</addressUnitBits>\n");
+    out.push_str("  <width>64</width>\n");
+    out.push_str("  <size>32</size>\n");
+    out.push_str("  <access>read-write</access>\n");
+    out.push_str("  <peripherals>\n");
+    let mut first_of: BTreeMap<&str, &str> = BTreeMap::new();
+    for instance in instances {
+        let (items, size) = &blocks[instance.module.as_str()];
+        match first_of.get(instance.module.as_str()) {
+            Some(first) => {
+                let _ = writeln!(out, "    <peripheral derivedFrom=\"{first}\">");
+                let _ = writeln!(out, "      <name>{}</name>", instance.name);
+            }
+            None => {
+                first_of.insert(&instance.module, &instance.name);
+                out.push_str("    <peripheral>\n");
+                let _ = writeln!(out, "      <name>{}</name>", instance.name);
+                let _ = writeln!(
+                    out,
+                    "      <groupName>{}</groupName>",
+                    instance.module.to_uppercase()
+                );
+            }
+        }
+        if !instance.description.is_empty() {
+            let _ = writeln!(
+                out,
+                "      <description>{}</description>",
+                escape(&instance.description)
+            );
+        }
+        let _ = writeln!(
+            out,
+            "      <baseAddress>0x{:08X}</baseAddress>",
+            instance.base
+        );
+        if let Some(irq) = instance.irq {
+            out.push_str("      <interrupt>\n");
+            let _ = writeln!(out, "        <name>{}</name>", instance.name);
+            let _ = writeln!(out, "        <value>{irq}</value>");
+            out.push_str("      </interrupt>\n");
+        }
+        if first_of[instance.module.as_str()] == instance.name {
+            out.push_str("      <addressBlock>\n");
+            out.push_str("        <offset>0x0</offset>\n");
+            let _ = writeln!(out, "        <size>0x{size:X}</size>");
+            out.push_str("        <usage>registers</usage>\n");
+            out.push_str("      </addressBlock>\n");
+            out.push_str("      <registers>\n");
+            render_items(&mut out, items, 4);
+            out.push_str("      </registers>\n");
+        }
+        out.push_str("    </peripheral>\n");
+    }
+    out.push_str("  </peripherals>\n");
+    out.push_str("</device>\n");
+    out
+}
+
+fn render_items(out: &mut String, items: &[Item], depth: usize) {
+    let pad = "  ".repeat(depth);
+    for item in items {
+        match item {
+            Item::Register {
+                name,
+                description,
+                offset,
+                size,
+                access,
+                dim,
+                fields,
+            } => {
+                let _ = writeln!(out, "{pad}<register>");
+                render_dim(out, &pad, *dim);
+                let _ = writeln!(out, "{pad}  <name>{name}</name>");
+                render_description(out, &pad, description, name);
+                let _ = writeln!(out, "{pad}  <addressOffset>0x{offset:X}</addressOffset>");
+                let _ = writeln!(out, "{pad}  <size>{size}</size>");
+                let _ = writeln!(out, "{pad}  <access>{}</access>", access.as_str());
+                if !fields.is_empty() {
+                    let _ = writeln!(out, "{pad}  <fields>");
+                    for field in fields {
+                        let _ = writeln!(out, "{pad}    <field>");
+                        let _ = writeln!(out, "{pad}      <name>{}</name>", field.name);
+                        render_description(
+                            out,
+                            &format!("{pad}    "),
+                            &field.description,
+                            &field.name,
+                        );
+                        let _ = writeln!(
+                            out,
+                            "{pad}      <bitRange>[{}:{}]</bitRange>",
+                            field.msb, field.lsb
+                        );
+                        let _ =
+                            writeln!(out, "{pad}      <access>{}</access>", field.access.as_str());
+                        let _ = writeln!(out, "{pad}    </field>");
+                    }
+                    let _ = writeln!(out, "{pad}  </fields>");
+                }
+                let _ = writeln!(out, "{pad}</register>");
+            }
+            Item::Cluster {
+                name,
+                description,
+                offset,
+                dim,
+                items,
+            } => {
+                let _ = writeln!(out, "{pad}<cluster>");
+                render_dim(out, &pad, *dim);
+                let _ = writeln!(out, "{pad}  <name>{name}</name>");
+                render_description(out, &pad, description, name);
+                let _ = writeln!(out, "{pad}  <addressOffset>0x{offset:X}</addressOffset>");
+                render_items(out, items, depth + 1);
+                let _ = writeln!(out, "{pad}</cluster>");
+            }
+        }
+    }
+}
+
+fn render_dim(out: &mut String, pad: &str, dim: Option<(u64, u64)>) {
+    if let Some((count, increment)) = dim {
+        let _ = writeln!(out, "{pad}  <dim>{count}</dim>");
+        let _ = writeln!(out, "{pad}  <dimIncrement>0x{increment:X}</dimIncrement>");
+    }
+}
+
+/// SVD requires a description; the name stands in for undocumented items.
+fn render_description(out: &mut String, pad: &str, description: &str, name: &str) {
+    let description = if description.is_empty() {
+        name
+    } else {
+        description
+    };
+    let _ = writeln!(
+        out,
+        "{pad}  <description>{}</description>",
+        escape(description)
+    );
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('`', "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REGISTER_RS: &str = "
+use derive_mmio::Mmio;
+
+/// Example Register Block.
+#[derive(Mmio)]
+#[repr(C)]
+pub struct RegisterBlock {
+    /// Control register.
+    pub ctrl: Ctrl,
+    /// Status register.
+    #[mmio(PureRead)]
+    pub status: u32,
+    _reserved0: [u8; 0x08],
+    /// Data FIFO.
+    pub data: [RW<u32>; 4],
+    pub wide: u64,
+}
+
+/// Control register.
+#[bitfield(u32)]
+#[derive(Debug, PartialEq, Eq)]
+pub struct Ctrl {
+    /// Enable the block.
+    #[bit(0, rw)]
+    pub enable: bool,
+
+    /// Clock divider.
+    #[bits(4..=11, rw)]
+    pub divider: u8,
+
+    /// Busy flag.
+    #[bit(31, r)]
+    pub busy: bool,
+}
+";
+
+    const SOC_RS: &str = "
+peripheral! {
+    use kendryte_hal::uart;
+    /// Universal Asynchronous Receiver Transmitter 0.
+    pub struct UART0 => 0x9140_0000, uart::RegisterBlock, uart::MmioRegisterBlock<'static> {
+        irq = 16, clock = ClockId::UartSclk(0)
+    };
+    /// Universal Asynchronous Receiver Transmitter 1.
+    pub struct UART1 => 0x9140_1000, uart::RegisterBlock;
+}
+";
+
+    #[test]
+    fn test_layout() {
+        let mut module = Module::default();
+        module
+            .files
+            .insert("register".into(), parse_source(REGISTER_RS));
+        let (items, size) = module.layout("register", "RegisterBlock").expect("layout");
+        assert_eq!(size, 0x28);
+        assert_eq!(items.len(), 4);
+        let Item::Register {
+            name,
+            offset,
+            fields,
+            ..
+        } = &items[0]
+        else {
+            panic!("not a register");
+        };
+        assert_eq!((name.as_str(), *offset), ("ctrl", 0));
+        assert_eq!(fields.len(), 3);
+        assert_eq!((fields[1].lsb, fields[1].msb), (4, 11));
+        assert_eq!(fields[2].access, Access::ReadOnly);
+        let Item::Register {
+            name, offset, dim, ..
+        } = &items[2]
+        else {
+            panic!("not a register");
+        };
+        assert_eq!(
+            (name.as_str(), *offset, *dim),
+            ("data[%s]", 0x10, Some((4, 4)))
+        );
+        let Item::Register { offset, size, .. } = &items[3] else {
+            panic!("not a register");
+        };
+        assert_eq!((*offset, *size), (0x20, 64));
+    }
+
+    #[test]
+    fn test_render() {
+        let instances = parse_instances(SOC_RS).expect("instances");
+        assert_eq!(instances.len(), 2);
+        assert_eq!(instances[0].irq, Some(16));
+        assert_eq!(instances[1].base, 0x9140_1000);
+        assert_eq!(instances[1].module, "uart");
+
+        let mut module = Module::default();
+        module
+            .files
+            .insert("register".into(), parse_source(REGISTER_RS));
+        let blocks =
+            BTreeMap::from([("uart", module.layout("register", "RegisterBlock").unwrap())]);
+        let svd = render("k230", &instances, &blocks);
+        assert!(svd.contains("<name>K230</name>"));
+        assert!(svd.contains("<peripheral derivedFrom=\"UART0\">\n      <name>UART1</name>"));
+        assert!(svd.contains("<baseAddress>0x91400000</baseAddress>"));
+        assert!(svd.contains("<bitRange>[11:4]</bitRange>"));
+        assert!(svd.contains("<name>status</name>\n          <description>Status register.</description>\n          <addressOffset>0x4</addressOffset>\n          <size>32</size>\n          <access>read-only</access>"));
+        assert_eq!(svd.matches("<register>").count(), 4);
+    }
+}